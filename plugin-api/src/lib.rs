@@ -8,9 +8,12 @@
 //! All plugins are external executables that communicate via JSON over stdin/stdout.
 //! Protocol:
 //! - `--plugin-info`: Print plugin metadata as JSON
-//! - stdin/stdout: JSON commands and responses
+//! - stdin/stdout: JSON commands and responses (see [`protocol`])
+
+pub mod protocol;
 
 use std::collections::HashMap;
+use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::time::SystemTime;
 
@@ -48,6 +51,105 @@ impl PluginType {
     }
 }
 
+// ============================================================================
+// PROTOCOL VERSION AND CAPABILITIES
+// ============================================================================
+
+/// Version of the plugin protocol spoken by this crate.
+///
+/// Plugins report the version they were written against as `api_version` in
+/// `--plugin-info`. Bark refuses plugins that need a newer version; plugins
+/// that don't report one are treated as version 1.
+pub const API_VERSION: u32 = 1;
+
+/// Optional provider operations, declared by plugins in `capabilities`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Create and overwrite files (`write_file`)
+    Write,
+    /// Remove files and directories (`delete`)
+    Delete,
+    /// Rename and move within the provider (`rename`)
+    Rename,
+    /// Create directories (`mkdir`)
+    Mkdir,
+    /// Server-side copy within the provider (`copy_file`)
+    CopyFile,
+    /// Set modification time and permissions (`set_attributes`)
+    SetAttributes,
+    /// Report free space (`get_free_space`)
+    FreeSpace,
+    /// Accept a password for an open session (`set_password`)
+    SetPassword,
+    /// Chunked transfers (`read_range` / `write_range`)
+    Streaming,
+    /// Hold edits back and commit them together (`flush`)
+    Flush,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 10] = [
+        Capability::Write,
+        Capability::Delete,
+        Capability::Rename,
+        Capability::Mkdir,
+        Capability::CopyFile,
+        Capability::SetAttributes,
+        Capability::FreeSpace,
+        Capability::SetPassword,
+        Capability::Streaming,
+        Capability::Flush,
+    ];
+
+    /// What a provider plugin is assumed to support when it does not send a
+    /// `capabilities` list: everything the protocol had before negotiation.
+    pub const LEGACY: [Capability; 7] = [
+        Capability::Write,
+        Capability::Delete,
+        Capability::Rename,
+        Capability::Mkdir,
+        Capability::CopyFile,
+        Capability::SetAttributes,
+        Capability::SetPassword,
+    ];
+
+    /// Name used in the `capabilities` array
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Write => "write",
+            Capability::Delete => "delete",
+            Capability::Rename => "rename",
+            Capability::Mkdir => "mkdir",
+            Capability::CopyFile => "copy_file",
+            Capability::SetAttributes => "set_attributes",
+            Capability::FreeSpace => "free_space",
+            Capability::SetPassword => "set_password",
+            Capability::Streaming => "streaming",
+            Capability::Flush => "flush",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == s)
+    }
+
+    /// Short description of the operation for "not supported" messages
+    pub fn description(&self) -> &'static str {
+        match self {
+            Capability::Write => "writing files",
+            Capability::Delete => "deleting files",
+            Capability::Rename => "renaming files",
+            Capability::Mkdir => "creating directories",
+            Capability::CopyFile => "copying on the server",
+            Capability::SetAttributes => "setting file attributes",
+            Capability::FreeSpace => "reporting free space",
+            Capability::SetPassword => "passwords",
+            Capability::Streaming => "streaming transfers",
+            Capability::Flush => "batched edits",
+        }
+    }
+}
+
 // ============================================================================
 // UNIFIED PLUGIN INFO
 // ============================================================================
@@ -73,6 +175,10 @@ pub struct PluginInfo {
     pub extensions: Vec<String>,
     /// For viewer plugins: MIME types handled
     pub mime_types: Vec<String>,
+    /// Protocol version the plugin was written against
+    pub api_version: u32,
+    /// For provider plugins: optional operations the plugin supports
+    pub capabilities: Vec<Capability>,
 }
 
 impl PluginInfo {
@@ -88,6 +194,8 @@ impl PluginInfo {
             schemes,
             extensions: Vec::new(),
             mime_types: Vec::new(),
+            api_version: API_VERSION,
+            capabilities: Capability::LEGACY.to_vec(),
         }
     }
 
@@ -103,6 +211,8 @@ impl PluginInfo {
             schemes: Vec::new(),
             extensions: Vec::new(),
             mime_types: Vec::new(),
+            api_version: API_VERSION,
+            capabilities: Vec::new(),
         }
     }
 
@@ -118,6 +228,8 @@ impl PluginInfo {
             schemes: Vec::new(),
             extensions: Vec::new(),
             mime_types: Vec::new(),
+            api_version: API_VERSION,
+            capabilities: Vec::new(),
         }
     }
 
@@ -140,6 +252,16 @@ impl PluginInfo {
         self.mime_types = mimes;
         self
     }
+
+    pub fn with_capabilities(mut self, capabilities: Vec<Capability>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Check whether the plugin declared an optional operation
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
 }
 
 /// Legacy alias for backwards compatibility
@@ -468,6 +590,56 @@ impl std::fmt::Display for ProviderError {
 
 impl std::error::Error for ProviderError {}
 
+// ============================================================================
+// STREAMING
+// ============================================================================
+
+/// Writable handle returned by `ProviderSession::open_write`.
+///
+/// Data written through `Write` is only guaranteed to be stored once
+/// `finish` returns successfully. Dropping the writer without calling
+/// `finish` may discard the data.
+pub trait SessionWriter: Write {
+    /// Flush remaining data and commit the file
+    fn finish(self: Box<Self>) -> ProviderResult<()>;
+}
+
+/// Fallback writer that collects everything in memory and hands it to
+/// `ProviderSession::write_file` on `finish`.
+pub struct BufferedSessionWriter<'a, S: ProviderSession + ?Sized> {
+    session: &'a mut S,
+    path: String,
+    buffer: Vec<u8>,
+}
+
+impl<'a, S: ProviderSession + ?Sized> BufferedSessionWriter<'a, S> {
+    pub fn new(session: &'a mut S, path: &str) -> Self {
+        Self {
+            session,
+            path: path.to_string(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<S: ProviderSession + ?Sized> Write for BufferedSessionWriter<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: ProviderSession + ?Sized> SessionWriter for BufferedSessionWriter<'_, S> {
+    fn finish(self: Box<Self>) -> ProviderResult<()> {
+        let this = *self;
+        this.session.write_file(&this.path, &this.buffer)
+    }
+}
+
 // ============================================================================
 // PLUGIN TRAITS
 // ============================================================================
@@ -513,6 +685,19 @@ pub trait ProviderSession: Send {
     /// Write file contents
     fn write_file(&mut self, path: &str, data: &[u8]) -> ProviderResult<()>;
 
+    /// Open a file for chunked reading.
+    /// The default reads the whole file with `read_file` and serves it from memory.
+    fn open_read(&mut self, path: &str) -> ProviderResult<Box<dyn Read + Send>> {
+        let data = self.read_file(path)?;
+        Ok(Box::new(std::io::Cursor::new(data)))
+    }
+
+    /// Open a file for chunked writing. Call `SessionWriter::finish` to commit.
+    /// The default buffers in memory and calls `write_file` on finish.
+    fn open_write(&mut self, path: &str) -> ProviderResult<Box<dyn SessionWriter + '_>> {
+        Ok(Box::new(BufferedSessionWriter::new(self, path)))
+    }
+
     /// Delete a file or empty directory
     fn delete(&mut self, path: &str) -> ProviderResult<()>;
 
@@ -541,6 +726,12 @@ pub trait ProviderSession: Send {
         Ok(())
     }
 
+    /// Commit edits the session has held back. Called at the end of every
+    /// operation that changed something; a no-op by default.
+    fn flush(&mut self) -> ProviderResult<()> {
+        Ok(())
+    }
+
     /// Get free space (if supported)
     fn get_free_space(&self, _path: &str) -> Option<u64> {
         None
@@ -0,0 +1,827 @@
+//! JSON wire protocol between Bark and script plugins
+//!
+//! Every request is one JSON object on a single line, tagged with a
+//! `command` field. Provider sessions also send their `session_id` with
+//! each command. The plugin answers with one JSON object.
+//!
+//! Replies are decoded with serde, so field order and whitespace do not
+//! matter and unknown fields are ignored. Known fields must have the
+//! documented type; anything else is reported as a [`ProtocolError`]
+//! naming the plugin and the command it was answering.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::UNIX_EPOCH;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{API_VERSION, Capability, DialogField, DialogFieldType, FileEntry, ProviderConfig};
+
+// ============================================================================
+// REQUESTS
+// ============================================================================
+
+/// A command sent to a plugin on stdin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    // Provider plugins
+    GetDialogFields,
+    ValidateConfig {
+        config: BTreeMap<String, String>,
+    },
+    Connect {
+        config: BTreeMap<String, String>,
+    },
+    Disconnect,
+    ListDirectory {
+        path: String,
+    },
+    ReadFile {
+        path: String,
+    },
+    WriteFile {
+        path: String,
+        /// Base64 encoded file content
+        data: String,
+    },
+    Delete {
+        path: String,
+        #[serde(default, skip_serializing_if = "is_false")]
+        recursive: bool,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    Mkdir {
+        path: String,
+    },
+    CopyFile {
+        from: String,
+        to: String,
+    },
+    SetAttributes {
+        path: String,
+        /// Unix timestamp in seconds
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        modified: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        permissions: Option<u32>,
+    },
+    SetPassword {
+        password: String,
+    },
+    GetFreeSpace {
+        path: String,
+    },
+    /// Read up to `length` bytes at `offset` ("streaming" capability).
+    /// An empty `data` reply marks the end of the file.
+    ReadRange {
+        path: String,
+        offset: u64,
+        length: u64,
+    },
+    /// Write base64 `data` at `offset` ("streaming" capability).
+    /// Offset 0 creates or truncates the file.
+    WriteRange {
+        path: String,
+        offset: u64,
+        data: String,
+    },
+    /// Commit the edits sent since the last `flush` ("flush" capability).
+    /// Bark sends it at the end of every operation that changed something.
+    Flush,
+
+    // Status bar plugins
+    StatusRender {
+        path: String,
+        selected_file: Option<String>,
+        is_dir: bool,
+        file_size: u64,
+        selected_count: usize,
+    },
+
+    // Viewer plugins
+    ViewerCanHandle {
+        path: String,
+    },
+    ViewerRender {
+        path: String,
+        width: usize,
+        height: usize,
+        scroll: usize,
+        config: BTreeMap<String, String>,
+        /// Column to sort a table by, for plugins that send `columns`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sort: Option<ViewerSort>,
+    },
+
+    // Overlay plugins
+    Init {
+        width: u16,
+        height: u16,
+    },
+    Key {
+        key: String,
+        modifiers: Vec<String>,
+    },
+    Tick,
+    Close,
+}
+
+/// Sort order the user picked in a table viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewerSort {
+    pub column: usize,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+impl Command {
+    /// Wire name of the command (the value of the `command` field)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::GetDialogFields => "get_dialog_fields",
+            Command::ValidateConfig { .. } => "validate_config",
+            Command::Connect { .. } => "connect",
+            Command::Disconnect => "disconnect",
+            Command::ListDirectory { .. } => "list_directory",
+            Command::ReadFile { .. } => "read_file",
+            Command::WriteFile { .. } => "write_file",
+            Command::Delete { .. } => "delete",
+            Command::Rename { .. } => "rename",
+            Command::Mkdir { .. } => "mkdir",
+            Command::CopyFile { .. } => "copy_file",
+            Command::SetAttributes { .. } => "set_attributes",
+            Command::SetPassword { .. } => "set_password",
+            Command::GetFreeSpace { .. } => "get_free_space",
+            Command::ReadRange { .. } => "read_range",
+            Command::WriteRange { .. } => "write_range",
+            Command::Flush => "flush",
+            Command::StatusRender { .. } => "status_render",
+            Command::ViewerCanHandle { .. } => "viewer_can_handle",
+            Command::ViewerRender { .. } => "viewer_render",
+            Command::Init { .. } => "init",
+            Command::Key { .. } => "key",
+            Command::Tick => "tick",
+            Command::Close => "close",
+        }
+    }
+}
+
+/// Flatten a provider configuration into the `config` object sent with
+/// `validate_config` and `connect`. The connection name is sent as `name`.
+pub fn config_object(config: &ProviderConfig) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    map.insert("name".to_string(), config.name.clone());
+    for (key, value) in &config.values {
+        map.insert(key.clone(), value.clone());
+    }
+    map
+}
+
+/// Rebuild a provider configuration from the `config` object of a
+/// `validate_config` or `connect` request, for plugins written in Rust
+pub fn provider_config(config: BTreeMap<String, String>) -> ProviderConfig {
+    let mut provider_config = ProviderConfig::new();
+    for (key, value) in config {
+        if key == "name" {
+            provider_config.name = value;
+        } else {
+            provider_config.set(key, value);
+        }
+    }
+    provider_config
+}
+
+/// A full request line: the command plus the session it belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+impl Request {
+    pub fn new(command: Command) -> Self {
+        Self { session_id: None, command }
+    }
+
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Encode as a single newline-terminated JSON line
+    pub fn to_line(&self) -> String {
+        // Serializing plain strings, numbers and maps cannot fail
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+}
+
+// ============================================================================
+// REPLIES
+// ============================================================================
+
+/// Output of `--plugin-info`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginInfoReply {
+    /// Protocol version the plugin was written against (1 when missing)
+    pub api_version: Option<u32>,
+    /// Optional operations supported by a provider plugin
+    pub capabilities: Option<Vec<String>>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// "provider", "viewer", "status" or "overlay"
+    #[serde(rename = "type")]
+    pub plugin_type: Option<String>,
+    pub description: String,
+    /// Icon character (only the first character is used)
+    pub icon: Option<String>,
+    pub schemes: Vec<String>,
+    pub extensions: Vec<String>,
+    /// Viewer wants the real terminal instead of returning lines
+    pub needs_terminal: bool,
+    /// Preferred overlay size
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+}
+
+impl PluginInfoReply {
+    /// Protocol version of the plugin, or why Bark can't talk to it
+    pub fn api_version(&self) -> Result<u32, String> {
+        match self.api_version {
+            None => Ok(1),
+            Some(0) => Err("invalid plugin API version 0".to_string()),
+            Some(v) if v > API_VERSION => Err(format!(
+                "needs plugin API version {}, this Bark supports up to {}",
+                v, API_VERSION
+            )),
+            Some(v) => Ok(v),
+        }
+    }
+
+    /// Declared capabilities. Unknown names are ignored; plugins without a
+    /// `capabilities` list get [`Capability::LEGACY`].
+    pub fn capabilities(&self) -> Vec<Capability> {
+        match &self.capabilities {
+            Some(names) => names.iter().filter_map(|n| Capability::parse(n)).collect(),
+            None => Capability::LEGACY.to_vec(),
+        }
+    }
+}
+
+/// Error fields any reply may carry. A reply with `error` set is a failure,
+/// whatever else it contains.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorReply {
+    pub error: Option<String>,
+    pub error_type: Option<ErrorType>,
+}
+
+/// What kind of failure an error reply reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorType {
+    Auth,
+    NotFound,
+    Permission,
+    /// The target can be read but not changed, e.g. a 7z archive
+    ReadOnly,
+    Connection,
+    PasswordRequired,
+    /// Any type this version of Bark doesn't know
+    #[serde(other)]
+    Other,
+}
+
+impl ErrorReply {
+    /// A failure reply without an error type
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { error: Some(message.into()), error_type: None }
+    }
+
+    pub fn with_type(mut self, error_type: ErrorType) -> Self {
+        self.error_type = Some(error_type);
+        self
+    }
+}
+
+/// Reply to `get_dialog_fields`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DialogFieldsReply {
+    pub fields: Vec<FieldSpec>,
+}
+
+/// One connection dialog field as described by a plugin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldSpec {
+    pub id: String,
+    pub label: Option<String>,
+    /// "text", "password", "number", "checkbox", "select", "textarea" or "file"
+    #[serde(rename = "type")]
+    pub field_type: Option<String>,
+    #[serde(rename = "default")]
+    pub default_value: Option<String>,
+    pub placeholder: Option<String>,
+    pub required: bool,
+    #[serde(rename = "help")]
+    pub help_text: Option<String>,
+    /// Choices for "select" fields
+    pub options: Vec<SelectOption>,
+}
+
+/// A choice of a "select" dialog field
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectOption {
+    pub value: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl FieldSpec {
+    /// Convert to a dialog field. Fields without an id are dropped.
+    pub fn into_dialog_field(self) -> Option<DialogField> {
+        if self.id.is_empty() {
+            return None;
+        }
+        let field_type = match self.field_type.as_deref().unwrap_or("text") {
+            "password" => DialogFieldType::Password,
+            "number" => DialogFieldType::Number,
+            "checkbox" => DialogFieldType::Checkbox,
+            "textarea" => DialogFieldType::TextArea,
+            "file" | "filepath" => DialogFieldType::FilePath,
+            "select" => DialogFieldType::Select {
+                options: self
+                    .options
+                    .into_iter()
+                    .filter(|o| !o.value.is_empty())
+                    .map(|o| {
+                        let label = o.label.unwrap_or_else(|| o.value.clone());
+                        (o.value, label)
+                    })
+                    .collect(),
+            },
+            _ => DialogFieldType::Text,
+        };
+        Some(DialogField {
+            label: self.label.unwrap_or_else(|| self.id.clone()),
+            id: self.id,
+            field_type,
+            default_value: self.default_value,
+            placeholder: self.placeholder,
+            required: self.required,
+            help_text: self.help_text,
+        })
+    }
+}
+
+impl From<&DialogField> for FieldSpec {
+    fn from(field: &DialogField) -> Self {
+        let (field_type, options) = match &field.field_type {
+            DialogFieldType::Text => ("text", Vec::new()),
+            DialogFieldType::Password => ("password", Vec::new()),
+            DialogFieldType::Number => ("number", Vec::new()),
+            DialogFieldType::Checkbox => ("checkbox", Vec::new()),
+            DialogFieldType::TextArea => ("textarea", Vec::new()),
+            DialogFieldType::FilePath => ("file", Vec::new()),
+            DialogFieldType::Select { options } => (
+                "select",
+                options
+                    .iter()
+                    .map(|(value, label)| SelectOption { value: value.clone(), label: Some(label.clone()) })
+                    .collect(),
+            ),
+        };
+        FieldSpec {
+            id: field.id.clone(),
+            label: Some(field.label.clone()),
+            field_type: Some(field_type.to_string()),
+            default_value: field.default_value.clone(),
+            placeholder: field.placeholder.clone(),
+            required: field.required,
+            help_text: field.help_text.clone(),
+            options,
+        }
+    }
+}
+
+/// Reply to `validate_config`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidateReply {
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+/// Reply to `connect`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectReply {
+    pub success: bool,
+    pub session_id: Option<String>,
+    pub display_name: Option<String>,
+    /// Panel header label, e.g. "[ZIP]"
+    pub short_label: Option<String>,
+}
+
+/// Reply to `list_directory`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListReply {
+    pub entries: Vec<EntrySpec>,
+}
+
+/// One directory entry as described by a plugin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntrySpec {
+    pub name: String,
+    #[serde(default)]
+    pub is_dir: bool,
+    #[serde(default)]
+    pub size: u64,
+    /// Unix timestamp in seconds
+    #[serde(default)]
+    pub modified: Option<i64>,
+    #[serde(default)]
+    pub is_hidden: bool,
+    #[serde(default)]
+    pub permissions: u32,
+    #[serde(default)]
+    pub is_symlink: bool,
+    #[serde(default)]
+    pub symlink_target: Option<String>,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub group: String,
+}
+
+impl From<&FileEntry> for EntrySpec {
+    fn from(entry: &FileEntry) -> Self {
+        EntrySpec {
+            name: entry.name.clone(),
+            is_dir: entry.is_dir,
+            size: entry.size,
+            modified: entry
+                .modified
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|age| age.as_secs() as i64),
+            is_hidden: entry.is_hidden,
+            permissions: entry.permissions,
+            is_symlink: entry.is_symlink,
+            symlink_target: entry.symlink_target.as_ref().map(|target| target.to_string_lossy().into_owned()),
+            owner: entry.owner.clone(),
+            group: entry.group.clone(),
+        }
+    }
+}
+
+/// Reply to `read_file`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadReply {
+    /// Base64 encoded file content
+    pub data: String,
+}
+
+/// Reply to `get_free_space`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FreeSpaceReply {
+    /// Free bytes, or null when unknown
+    pub free: Option<u64>,
+}
+
+/// Reply to commands that only report success (write, delete, rename, ...)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuccessReply {
+    pub success: bool,
+}
+
+/// Reply to `status_render`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusReply {
+    pub text: String,
+}
+
+/// Reply to `viewer_can_handle`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CanHandleReply {
+    pub can_handle: bool,
+    pub priority: i32,
+}
+
+/// Reply to `viewer_render`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewerReply {
+    pub lines: Vec<String>,
+    /// Defaults to the number of lines returned
+    #[serde(default)]
+    pub total_lines: Option<usize>,
+    /// Blocks of nested data the user can fold away
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folds: Vec<ViewerFold>,
+    /// Where in the data each line is (e.g. `$.users[2].name`), shown as
+    /// a breadcrumb; may be shorter than `lines`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Lines kept above the scrolling ones, such as table headings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub header: Vec<String>,
+    /// Columns of a table, which the user can step through and sort by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<ViewerColumn>,
+}
+
+/// Where a table column is in the lines of a viewer reply
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewerColumn {
+    /// Character offset of the column
+    pub start: usize,
+    /// Width in characters
+    pub width: usize,
+    /// Shown while the column is selected, e.g. its name and statistics
+    #[serde(default)]
+    pub info: String,
+}
+
+/// Lines `start + 1..=end` of a viewer reply, which fold into line `start`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewerFold {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Reply to the overlay commands `init`, `key` and `tick`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlayReply {
+    pub lines: Vec<String>,
+    pub title: String,
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    /// Plugin wants the overlay closed
+    pub close: bool,
+    /// Plugin wants periodic `tick` commands
+    pub tick: bool,
+}
+
+/// Encode a reply as a single JSON line (without the newline), for plugins
+/// written in Rust
+pub fn encode_reply<T: Serialize>(reply: &T) -> String {
+    // Replies hold plain strings, numbers and lists, which cannot fail
+    serde_json::to_string(reply).unwrap_or_default()
+}
+
+// ============================================================================
+// DECODING
+// ============================================================================
+
+/// A plugin reply that could not be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolError {
+    /// Plugin name (or executable name before the plugin is loaded)
+    pub plugin: String,
+    /// Command the plugin was answering
+    pub command: String,
+    /// What was wrong with the reply
+    pub message: String,
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "plugin '{}' sent a malformed reply to '{}': {}",
+            self.plugin, self.command, self.message
+        )
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// Decode a plugin reply.
+///
+/// Lines before the first one starting with `{` are skipped so stray log
+/// output does not break the plugin. The JSON object itself may span
+/// several lines, but nothing except whitespace may follow it.
+pub fn parse_reply<T: DeserializeOwned>(
+    plugin: &str,
+    command: &str,
+    output: &str,
+) -> Result<T, ProtocolError> {
+    let fail = |message: String| ProtocolError {
+        plugin: plugin.to_string(),
+        command: command.to_string(),
+        message,
+    };
+
+    let mut offset = 0;
+    let mut start = None;
+    for line in output.split_inclusive('\n') {
+        if line.trim_start().starts_with('{') {
+            start = Some(offset);
+            break;
+        }
+        offset += line.len();
+    }
+    let Some(start) = start else {
+        return Err(fail(if output.trim().is_empty() {
+            "empty reply".to_string()
+        } else {
+            "reply is not a JSON object".to_string()
+        }));
+    };
+
+    let mut de = serde_json::Deserializer::from_str(&output[start..]);
+    let value = T::deserialize(&mut de).map_err(|e| fail(e.to_string()))?;
+    de.end().map_err(|e| fail(e.to_string()))?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_encoding() {
+        let line = Request::new(Command::ListDirectory { path: "/a \"b\"".into() })
+            .with_session("s1")
+            .to_line();
+        assert!(line.ends_with('\n'));
+        let back: Request = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(back.session_id.as_deref(), Some("s1"));
+        assert_eq!(back.command, Command::ListDirectory { path: "/a \"b\"".into() });
+
+        let tick = Request::new(Command::Tick).to_line();
+        assert_eq!(tick, "{\"command\":\"tick\"}\n");
+
+        let delete = Request::new(Command::Delete { path: "/x".into(), recursive: false }).to_line();
+        assert!(!delete.contains("recursive"));
+    }
+
+    #[test]
+    fn test_reply_order_nesting_and_escapes() {
+        let output = r#"{"entries":[{"size":3,"name":"a\"b","extra":{"entries":[1]}},{"name":"c","is_dir":true}]}"#;
+        let reply: ListReply = parse_reply("p", "list_directory", output).unwrap();
+        assert_eq!(reply.entries.len(), 2);
+        assert_eq!(reply.entries[0].name, "a\"b");
+        assert_eq!(reply.entries[0].size, 3);
+        assert!(reply.entries[1].is_dir);
+    }
+
+    #[test]
+    fn test_reply_skips_leading_noise() {
+        let output = "debug: starting\n{\n  \"text\": \"ok\"\n}\n";
+        let reply: StatusReply = parse_reply("p", "status_render", output).unwrap();
+        assert_eq!(reply.text, "ok");
+    }
+
+    #[test]
+    fn test_viewer_reply_outline_is_optional() {
+        let plain: ViewerReply = parse_reply("p", "viewer_render", r#"{"lines":["a"]}"#).unwrap();
+        assert!(plain.folds.is_empty() && plain.paths.is_empty());
+        assert_eq!(serde_json::to_string(&plain).unwrap(), r#"{"lines":["a"],"total_lines":null}"#);
+
+        let tree: ViewerReply = parse_reply(
+            "p",
+            "viewer_render",
+            r#"{"lines":["{","  \"a\": 1","}"],"folds":[{"start":0,"end":2}],"paths":["$","$.a","$"]}"#,
+        )
+        .unwrap();
+        assert_eq!(tree.folds, [ViewerFold { start: 0, end: 2 }]);
+        assert_eq!(tree.paths[1], "$.a");
+
+        let table: ViewerReply = parse_reply(
+            "p",
+            "viewer_render",
+            r#"{"lines":["1  x"],"header":["a  b"],"columns":[{"start":0,"width":1},{"start":3,"width":1,"info":"b: text"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(table.header, ["a  b"]);
+        assert_eq!(table.columns[1], ViewerColumn { start: 3, width: 1, info: "b: text".into() });
+
+        let render = |sort| Command::ViewerRender {
+            path: "/t.csv".into(),
+            width: 80,
+            height: 24,
+            scroll: 0,
+            config: BTreeMap::new(),
+            sort,
+        };
+        assert!(!Request::new(render(None)).to_line().contains("sort"));
+        let line = Request::new(render(Some(ViewerSort { column: 2, descending: true }))).to_line();
+        assert!(line.contains(r#""sort":{"column":2,"descending":true}"#), "{line}");
+    }
+
+    #[test]
+    fn test_malformed_reply_names_plugin_and_command() {
+        let err = parse_reply::<ListReply>("FTP", "list_directory", r#"{"entries":[{"size":1}]}"#)
+            .unwrap_err();
+        assert_eq!(err.plugin, "FTP");
+        assert_eq!(err.command, "list_directory");
+        assert!(err.to_string().contains("missing field `name`"), "{err}");
+
+        let err = parse_reply::<StatusReply>("S", "status_render", r#"{"text":1}"#).unwrap_err();
+        assert!(err.to_string().starts_with("plugin 'S' sent a malformed reply to 'status_render'"));
+
+        assert!(parse_reply::<StatusReply>("S", "status_render", "{\"text\":\"a\"} trailing").is_err());
+        assert_eq!(
+            parse_reply::<StatusReply>("S", "status_render", "  \n").unwrap_err().message,
+            "empty reply"
+        );
+    }
+
+    #[test]
+    fn test_api_version_and_capabilities() {
+        let legacy: PluginInfoReply = parse_reply("p", "--plugin-info", r#"{"type":"provider"}"#).unwrap();
+        assert_eq!(legacy.api_version(), Ok(1));
+        assert_eq!(legacy.capabilities(), Capability::LEGACY.to_vec());
+
+        let current: PluginInfoReply = parse_reply(
+            "p",
+            "--plugin-info",
+            r#"{"api_version":1,"capabilities":["write","streaming","teleport"]}"#,
+        )
+        .unwrap();
+        assert_eq!(current.api_version(), Ok(1));
+        assert_eq!(current.capabilities(), vec![Capability::Write, Capability::Streaming]);
+
+        let future = PluginInfoReply { api_version: Some(API_VERSION + 1), ..Default::default() };
+        assert!(future.api_version().unwrap_err().contains("needs plugin API version"));
+    }
+
+    #[test]
+    fn test_field_spec_conversion() {
+        let reply: DialogFieldsReply = parse_reply(
+            "p",
+            "get_dialog_fields",
+            r#"{"fields":[{"id":"mode","type":"select","default":null,
+                "options":[{"value":"a"},{"label":"Bee","value":"b"}]},{"label":"no id"}]}"#,
+        )
+        .unwrap();
+        let fields: Vec<DialogField> = reply
+            .fields
+            .into_iter()
+            .filter_map(FieldSpec::into_dialog_field)
+            .collect();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].label, "mode");
+        assert_eq!(
+            fields[0].field_type,
+            DialogFieldType::Select {
+                options: vec![("a".into(), "a".into()), ("b".into(), "Bee".into())]
+            }
+        );
+    }
+
+    #[test]
+    fn test_plugin_side_round_trip() {
+        let mut config = ProviderConfig::new();
+        config.name = "nas".into();
+        config.set("host", "example.org");
+        let config = provider_config(config_object(&config));
+        assert_eq!((config.name.as_str(), config.get("host")), ("nas", Some("example.org")));
+        assert_eq!(config.get("name"), None);
+
+        let field = DialogField {
+            id: "mode".into(),
+            label: "Mode".into(),
+            field_type: DialogFieldType::Select { options: vec![("a".into(), "A".into())] },
+            default_value: Some("a".into()),
+            placeholder: None,
+            required: true,
+            help_text: None,
+        };
+        let line = encode_reply(&DialogFieldsReply { fields: vec![FieldSpec::from(&field)] });
+        let reply: DialogFieldsReply = parse_reply("p", "get_dialog_fields", &line).unwrap();
+        let decoded = reply.fields.into_iter().next().and_then(FieldSpec::into_dialog_field).unwrap();
+        assert_eq!((decoded.label, decoded.field_type), (field.label, field.field_type));
+
+        let entry = FileEntry::file("a \"b\"".into(), "/a".into(), 3)
+            .with_modified(Some(UNIX_EPOCH + std::time::Duration::from_secs(60)));
+        let line = encode_reply(&ListReply { entries: vec![EntrySpec::from(&entry)] });
+        let reply: ListReply = parse_reply("p", "list_directory", &line).unwrap();
+        assert_eq!((reply.entries[0].name.as_str(), reply.entries[0].modified), ("a \"b\"", Some(60)));
+
+        let line = encode_reply(&ErrorReply::new("gone").with_type(ErrorType::NotFound));
+        assert!(line.contains(r#""error_type":"not_found""#));
+        let reply: ErrorReply = parse_reply("p", "read_file", &line).unwrap();
+        assert_eq!(reply.error_type, Some(ErrorType::NotFound));
+        let reply: ErrorReply = parse_reply("p", "read_file", r#"{"error":"x","error_type":"quota"}"#).unwrap();
+        assert_eq!(reply.error_type, Some(ErrorType::Other));
+    }
+}
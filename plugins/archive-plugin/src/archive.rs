@@ -3,12 +3,16 @@
 //! Supports: zip, tar, tar.gz, tar.bz2, tar.xz, 7z, rar, and single-file xz/gz/bz2
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Cursor, Read};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use bark_plugin_api::FileEntry;
+use bark_plugin_api::protocol::ErrorType;
+
+use crate::rewrite::{self, Edit, WritableFormat};
 
 /// Normalize archive paths: replace backslashes, strip leading "./"
 fn normalize_archive_path(p: &str) -> String {
@@ -109,20 +113,57 @@ struct ArchiveEntry {
 
 /// Compression type for tar archives
 #[derive(Debug, Clone, Copy)]
-enum Compression {
+pub enum Compression {
     Gzip,
     Bzip2,
     Xz,
 }
 
+/// Why an edit was refused or couldn't be written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// 7z, RAR and single-file archives can't be rewritten
+    ReadOnly(String),
+    NotFound(String),
+    Other(String),
+}
+
+impl EditError {
+    /// Error type sent with the reply
+    pub fn error_type(&self) -> Option<ErrorType> {
+        match self {
+            EditError::ReadOnly(_) => Some(ErrorType::ReadOnly),
+            EditError::NotFound(_) => Some(ErrorType::NotFound),
+            EditError::Other(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::ReadOnly(msg) | EditError::NotFound(msg) | EditError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<String> for EditError {
+    fn from(msg: String) -> Self {
+        EditError::Other(msg)
+    }
+}
+
 /// Archive session — holds the loaded archive state
 #[allow(dead_code)]
 pub struct ArchiveSession {
     archive_path: PathBuf,
     archive_type: ArchiveType,
+    /// Entries as they will be once `pending` is written
     entries: Vec<ArchiveEntry>,
     display_name: String,
     password: Option<String>,
+    /// Edits not yet written to the archive, in order
+    pending: Vec<Edit>,
 }
 
 #[allow(dead_code)]
@@ -144,6 +185,7 @@ impl ArchiveSession {
             entries: Vec::new(),
             display_name,
             password,
+            pending: Vec::new(),
         };
 
         session.load_entries()?;
@@ -960,11 +1002,191 @@ impl ArchiveSession {
             .map_err(|e| format!("Failed to decompress BZIP2: {}", e))?;
         Ok(contents)
     }
+
+    // === Editing (ZIP and TAR family) ===
+    //
+    // Edits are checked against the entry list, applied to it and queued.
+    // `flush` writes the queue to the archive in a single rewrite.
+
+    /// Format to rewrite with; 7z, RAR and single-file archives are read-only
+    fn writable_format(&self) -> Result<WritableFormat, EditError> {
+        match self.archive_type {
+            ArchiveType::Zip => Ok(WritableFormat::Zip),
+            ArchiveType::Tar => Ok(WritableFormat::Tar(None)),
+            ArchiveType::TarGz => Ok(WritableFormat::Tar(Some(Compression::Gzip))),
+            ArchiveType::TarBz2 => Ok(WritableFormat::Tar(Some(Compression::Bzip2))),
+            ArchiveType::TarXz => Ok(WritableFormat::Tar(Some(Compression::Xz))),
+            other => Err(EditError::ReadOnly(format!("{} archives are read-only", other.display_name()))),
+        }
+    }
+
+    fn find_entry(&self, path: &str) -> Option<&ArchiveEntry> {
+        self.entries.iter().find(|e| e.path == path)
+    }
+
+    /// Apply `edit` to the entry list and queue it for the next `flush`
+    fn stage(&mut self, edit: Edit) {
+        let mut entries = Vec::with_capacity(self.entries.len() + 1);
+        let mut replaced_mode = None;
+        for entry in self.entries.drain(..) {
+            let targets = edit.targets(&entry.path);
+            if targets.is_empty() && matches!(edit, Edit::Write { .. }) {
+                replaced_mode = Some(entry.permissions);
+            }
+            for target in targets {
+                let mut entry = ArchiveEntry { path: target, ..entry.clone() };
+                if let Some((modified, permissions)) = edit.attributes_for(&entry.path) {
+                    entry.modified = modified.or(entry.modified);
+                    entry.permissions = permissions.unwrap_or(entry.permissions);
+                }
+                entries.push(entry);
+            }
+        }
+
+        let modified = Some(SystemTime::now());
+        match &edit {
+            Edit::Write { path, data } => entries.push(ArchiveEntry {
+                path: path.clone(),
+                is_dir: false,
+                size: data.len() as u64,
+                modified,
+                permissions: replaced_mode.unwrap_or(0o644),
+            }),
+            Edit::Mkdir { path } => entries.push(ArchiveEntry {
+                path: path.clone(),
+                is_dir: true,
+                size: 0,
+                modified,
+                permissions: 0o755,
+            }),
+            _ => {}
+        }
+
+        self.entries = entries;
+        self.pending.push(edit);
+    }
+
+    /// Write the queued edits to the archive in one rewrite, then reload the
+    /// entry list from it. If the rewrite fails the edits are dropped and the
+    /// list shows the archive as it still is.
+    pub fn flush(&mut self) -> Result<(), EditError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let edits = std::mem::take(&mut self.pending);
+        let format = self.writable_format()?;
+        let result = rewrite::rewrite_archive(&self.archive_path, format, &edits);
+        let reloaded = self.load_entries();
+        result?;
+        Ok(reloaded?)
+    }
+
+    /// Add or replace a file
+    pub fn write_file(&mut self, path: &str, data: &[u8]) -> Result<(), EditError> {
+        self.writable_format()?;
+        let path = edit_path(path)?;
+        if self.find_entry(&path).is_some_and(|e| e.is_dir) {
+            return Err(EditError::Other(format!("Is a directory: {}", path)));
+        }
+        self.stage(Edit::Write { path, data: data.to_vec() });
+        Ok(())
+    }
+
+    /// Delete a file, or a directory (only if empty unless `recursive`)
+    pub fn delete(&mut self, path: &str, recursive: bool) -> Result<(), EditError> {
+        self.writable_format()?;
+        let path = edit_path(path)?;
+        let entry = self.find_entry(&path)
+            .ok_or_else(|| EditError::NotFound(format!("File not found: {}", path)))?;
+        let prefix = format!("{}/", path);
+        if entry.is_dir && !recursive && self.entries.iter().any(|e| e.path.starts_with(&prefix)) {
+            return Err(EditError::Other(format!("Directory not empty: {}", path)));
+        }
+        self.stage(Edit::Delete { path });
+        Ok(())
+    }
+
+    /// Create an empty directory. A directory that already exists is left
+    /// as it is.
+    pub fn mkdir(&mut self, path: &str) -> Result<(), EditError> {
+        self.writable_format()?;
+        let path = edit_path(path)?;
+        let prefix = format!("{}/", path);
+        match self.find_entry(&path) {
+            Some(entry) if !entry.is_dir => Err(EditError::Other(format!("Already exists: {}", path))),
+            Some(_) => Ok(()),
+            // Only implied by the entries below it
+            None if self.entries.iter().any(|e| e.path.starts_with(&prefix)) => Ok(()),
+            None => {
+                self.stage(Edit::Mkdir { path });
+                Ok(())
+            }
+        }
+    }
+
+    /// Rename or move a file or directory within the archive
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), EditError> {
+        self.writable_format()?;
+        let (from, to) = self.check_transfer(from, to)?;
+        if to.starts_with(&format!("{}/", from)) {
+            return Err(EditError::Other(format!("Cannot move {} into itself", from)));
+        }
+        self.stage(Edit::Rename { from, to });
+        Ok(())
+    }
+
+    /// Copy a file or directory within the archive
+    pub fn copy_file(&mut self, from: &str, to: &str) -> Result<(), EditError> {
+        self.writable_format()?;
+        let (from, to) = self.check_transfer(from, to)?;
+        if to.starts_with(&format!("{}/", from)) {
+            return Err(EditError::Other(format!("Cannot copy {} into itself", from)));
+        }
+        self.stage(Edit::Copy { from, to });
+        Ok(())
+    }
+
+    /// Update an entry's timestamp and/or permissions
+    pub fn set_attributes(&mut self, path: &str, modified: Option<SystemTime>, permissions: Option<u32>) -> Result<(), EditError> {
+        self.writable_format()?;
+        let path = edit_path(path)?;
+        if self.find_entry(&path).is_none() {
+            return Err(EditError::NotFound(format!("File not found: {}", path)));
+        }
+        self.stage(Edit::SetAttributes { path, modified, permissions });
+        Ok(())
+    }
+
+    /// Normalize both paths of a rename/copy: source must exist, target must not
+    fn check_transfer(&self, from: &str, to: &str) -> Result<(String, String), EditError> {
+        let from = edit_path(from)?;
+        let to = edit_path(to)?;
+        if self.find_entry(&from).is_none() {
+            return Err(EditError::NotFound(format!("File not found: {}", from)));
+        }
+        if self.find_entry(&to).is_some() {
+            return Err(EditError::Other(format!("Already exists: {}", to)));
+        }
+        Ok((from, to))
+    }
+}
+
+/// Normalize a path for editing; the archive root itself can't be edited
+fn edit_path(path: &str) -> Result<String, String> {
+    let path = normalize_archive_path(path.trim_start_matches('/'))
+        .trim_end_matches('/')
+        .to_string();
+    if path.is_empty() {
+        Err("Invalid path".to_string())
+    } else {
+        Ok(path)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::ScratchDir;
 
     #[test]
     fn test_7z_lists_all_entries() {
@@ -991,4 +1213,112 @@ mod tests {
         let data = session.read_file("main.c").expect("Failed to read main.c");
         assert_eq!(data.len(), 4765, "main.c should be 4765 bytes");
     }
+
+    #[test]
+    fn test_zip_edits() {
+        use std::io::Write;
+
+        let dir = ScratchDir::new("archive-zip");
+        let path = dir.join("test.zip");
+        {
+            let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+            let options = zip::write::SimpleFileOptions::default().unix_permissions(0o600);
+            writer.start_file("a.txt", options).unwrap();
+            writer.write_all(b"alpha").unwrap();
+            writer.start_file("dir/b.txt", options).unwrap();
+            writer.write_all(b"beta").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let mut session = ArchiveSession::open(path.clone(), None).unwrap();
+        session.write_file("/a.txt", b"patched").unwrap();
+        session.mkdir("/empty").unwrap();
+        session.rename("/dir", "/moved").unwrap();
+        session.copy_file("/moved/b.txt", "/c.txt").unwrap();
+        session.mkdir("/empty").unwrap();
+        session.mkdir("/moved").unwrap();
+        assert!(session.mkdir("/c.txt").is_err());
+        assert!(session.delete("/moved", false).is_err());
+        assert!(session.find_entry("c.txt").is_some());
+
+        // Nothing is written before the flush, then everything at once
+        let on_disk = ArchiveSession::open(path.clone(), None).unwrap();
+        assert_eq!(on_disk.read_file("a.txt").unwrap(), b"alpha");
+        session.flush().unwrap();
+        assert!(session.pending.is_empty());
+
+        // Reopen from disk to check what was actually written
+        let mut session = ArchiveSession::open(path, None).unwrap();
+        assert_eq!(session.read_file("a.txt").unwrap(), b"patched");
+        assert_eq!(session.read_file("moved/b.txt").unwrap(), b"beta");
+        assert_eq!(session.read_file("c.txt").unwrap(), b"beta");
+        assert!(session.read_file("dir/b.txt").is_err());
+        assert!(session.find_entry("empty").is_some_and(|e| e.is_dir));
+        // Replaced and renamed entries keep their permissions
+        assert_eq!(session.find_entry("a.txt").unwrap().permissions & 0o777, 0o600);
+        assert_eq!(session.find_entry("moved/b.txt").unwrap().permissions & 0o777, 0o600);
+
+        session.delete("/moved", true).unwrap();
+        assert!(session.find_entry("moved/b.txt").is_none());
+    }
+
+    #[test]
+    fn test_tar_gz_edits() {
+        let dir = ScratchDir::new("archive-targz");
+        let path = dir.join("test.tar.gz");
+        {
+            let encoder = flate2::write::GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::fast());
+            let mut builder = tar::Builder::new(encoder);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(0o600);
+            header.set_mtime(1_000_000_000);
+            builder.append_pax_extensions([("SCHILY.xattr.user.tag", &b"blue"[..])]).unwrap();
+            builder.append_data(&mut header, "keep.txt", &b"keep"[..]).unwrap();
+            builder.append_data(&mut header, "gone.txt", &b"gone"[..]).unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+
+        let mut session = ArchiveSession::open(path.clone(), None).unwrap();
+        session.delete("/gone.txt", false).unwrap();
+        session.write_file("/new.txt", b"fresh").unwrap();
+        let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_500_000_000);
+        session.set_attributes("/new.txt", Some(mtime), Some(0o640)).unwrap();
+        session.rename("/keep.txt", "/kept.txt").unwrap();
+        session.flush().unwrap();
+
+        let session = ArchiveSession::open(path.clone(), None).unwrap();
+        assert!(session.find_entry("gone.txt").is_none());
+        assert_eq!(session.read_file("new.txt").unwrap(), b"fresh");
+        let new = session.find_entry("new.txt").unwrap();
+        assert_eq!((new.modified, new.permissions), (Some(mtime), 0o640));
+        let kept = session.find_entry("kept.txt").unwrap();
+        let kept_mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        assert_eq!((kept.modified, kept.permissions), (Some(kept_mtime), 0o600));
+
+        // The extended attribute moved with the entry
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(File::open(&path).unwrap()));
+        let mut entry = archive.entries().unwrap().next().unwrap().unwrap();
+        assert_eq!(entry.path().unwrap().to_string_lossy(), "kept.txt");
+        let xattr = entry.pax_extensions().unwrap().unwrap()
+            .filter_map(|ext| ext.ok())
+            .find(|ext| ext.key() == Ok("SCHILY.xattr.user.tag"))
+            .map(|ext| ext.value_bytes().to_vec());
+        assert_eq!(xattr.as_deref(), Some(&b"blue"[..]));
+    }
+
+    #[test]
+    fn test_7z_is_read_only() {
+        let mut session = ArchiveSession {
+            archive_path: PathBuf::from("test.7z"),
+            archive_type: ArchiveType::SevenZip,
+            entries: Vec::new(),
+            display_name: String::new(),
+            password: None,
+            pending: Vec::new(),
+        };
+        let err = session.mkdir("/dir").unwrap_err();
+        assert_eq!(err, EditError::ReadOnly("7Z archives are read-only".to_string()));
+        assert_eq!(err.error_type(), Some(ErrorType::ReadOnly));
+    }
 }
@@ -2,17 +2,31 @@
 //!
 //! Extension-based provider plugin for browsing archive files
 //! (zip, tar, tar.gz, tar.bz2, tar.xz, 7z, xz, gz, bz2).
+//! ZIP and TAR-family archives are also writable. Edits are queued and
+//! written in one rewrite on `flush`, on `disconnect` and when stdin closes.
 //!
 //! Protocol:
 //! - `--plugin-info`: Print plugin metadata (type=provider, extensions=[...])
 //! - stdin/stdout: JSON commands and responses
 
+use std::collections::BTreeMap;
 use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
+use bark_plugin_api::Capability;
+use bark_plugin_api::protocol::{
+    Command, ConnectReply, DialogFieldsReply, EntrySpec, ErrorReply, ErrorType, ListReply, PluginInfoReply, ReadReply,
+    Request, SuccessReply, ValidateReply, encode_reply,
+};
+
 mod archive;
-use archive::{ArchiveSession, ArchiveType};
+mod rewrite;
+// Bark's test helpers, shared by path
+#[cfg(test)]
+#[path = "../../../src/test_utils.rs"]
+mod test_utils;
+use archive::{ArchiveSession, ArchiveType, EditError};
 
 /// Global session storage
 static SESSION: Mutex<Option<ArchiveSession>> = Mutex::new(None);
@@ -42,54 +56,92 @@ fn main() {
         writeln!(stdout, "{}", response).ok();
         stdout.flush().ok();
     }
+
+    // Bark went away without a disconnect; keep what was edited
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        let _ = session.flush();
+    }
 }
 
 fn print_plugin_info() {
-    let extensions = ArchiveType::all_extensions();
-    let ext_json: Vec<String> = extensions.iter().map(|e| format!("\"{}\"", e)).collect();
-
-    println!(
-        r#"{{"name":"Archive Provider","version":"0.1","type":"provider","extensions":[{}],"description":"Browse archive files (zip, tar, 7z, xz, gz, bz2)","icon":"{}"}}"#,
-        ext_json.join(","),
-        '\u{1F4E6}' // 📦
-    );
+    let capabilities = [
+        Capability::Write,
+        Capability::Delete,
+        Capability::Rename,
+        Capability::Mkdir,
+        Capability::CopyFile,
+        Capability::SetAttributes,
+        Capability::SetPassword,
+        Capability::Flush,
+    ];
+    let info = PluginInfoReply {
+        api_version: Some(1),
+        capabilities: Some(capabilities.iter().map(|c| c.as_str().to_string()).collect()),
+        name: Some("Archive Provider".to_string()),
+        version: Some("0.1".to_string()),
+        plugin_type: Some("provider".to_string()),
+        description: "Browse and edit archive files (zip, tar, 7z, xz, gz, bz2)".to_string(),
+        icon: Some("\u{1F4E6}".to_string()), // 📦
+        extensions: ArchiveType::all_extensions().iter().map(|e| e.to_string()).collect(),
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&info));
 }
 
-fn handle_command(json: &str) -> String {
-    let command = extract_string(json, "command").unwrap_or_default();
-
-    match command.as_str() {
-        "get_dialog_fields" => r#"{"fields":[]}"#.to_string(),
-        "validate_config" => r#"{"valid":true}"#.to_string(),
-        "connect" => handle_connect(json),
-        "disconnect" => handle_disconnect(),
-        "list_directory" => handle_list_directory(json),
-        "read_file" => handle_read_file(json),
-        "set_password" => handle_set_password(json),
-        "write_file" => r#"{"error":"Archives are read-only","error_type":"permission"}"#.to_string(),
-        "delete" => r#"{"error":"Archives are read-only","error_type":"permission"}"#.to_string(),
-        "mkdir" => r#"{"error":"Archives are read-only","error_type":"permission"}"#.to_string(),
-        "rename" => r#"{"error":"Archives are read-only","error_type":"permission"}"#.to_string(),
-        "copy_file" => r#"{"error":"Archives are read-only","error_type":"permission"}"#.to_string(),
-        _ => format!(r#"{{"error":"Unknown command: {}"}}"#, escape_json(&command)),
+fn handle_command(line: &str) -> String {
+    let command = match serde_json::from_str::<Request>(line) {
+        Ok(request) => request.command,
+        Err(e) => return encode_reply(&ErrorReply::new(format!("Invalid request: {}", e))),
+    };
+
+    match command {
+        Command::GetDialogFields => encode_reply(&DialogFieldsReply::default()),
+        Command::ValidateConfig { .. } => encode_reply(&ValidateReply { valid: true, error: None }),
+        Command::Connect { config } => handle_connect(config),
+        Command::Disconnect => handle_disconnect(),
+        Command::ListDirectory { path } => handle_list_directory(&path),
+        Command::ReadFile { path } => handle_read_file(&path),
+        Command::SetPassword { password } => handle_set_password(password),
+        Command::WriteFile { path, data } => match base64_decode(&data) {
+            Ok(data) => edit_session(|session| session.write_file(&path, &data)),
+            Err(e) => encode_reply(&ErrorReply::new(e)),
+        },
+        Command::Delete { path, recursive } => edit_session(|session| session.delete(&path, recursive)),
+        Command::Mkdir { path } => edit_session(|session| session.mkdir(&path)),
+        Command::Rename { from, to } => edit_session(|session| session.rename(&from, &to)),
+        Command::CopyFile { from, to } => edit_session(|session| session.copy_file(&from, &to)),
+        Command::SetAttributes { path, modified, permissions } => {
+            let modified = modified.map(|secs| std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+            edit_session(|session| session.set_attributes(&path, modified, permissions))
+        }
+        Command::Flush => edit_session(ArchiveSession::flush),
+        other => encode_reply(&ErrorReply::new(format!("Unknown command: {}", other.name()))),
     }
 }
 
-fn handle_connect(json: &str) -> String {
-    // Extract the archive path and optional password from config
-    let path = extract_config_value(json, "path").unwrap_or_default();
-    let password = extract_config_value(json, "password");
+fn handle_connect(config: BTreeMap<String, String>) -> String {
+    // The archive path and optional password come from the config
+    let path = config.get("path").cloned().unwrap_or_default();
+    let password = config.get("password").cloned();
 
     if path.is_empty() {
-        return r#"{"success":false,"error":"No archive path provided"}"#.to_string();
+        return encode_reply(&ErrorReply::new("No archive path provided"));
     }
 
     match ArchiveSession::open(PathBuf::from(&path), password.clone()) {
         Ok(session) => {
             let label = session.short_label();
             let mut guard = SESSION.lock().unwrap();
+            if let Some(previous) = guard.as_mut() {
+                let _ = previous.flush();
+            }
             *guard = Some(session);
-            format!(r#"{{"success":true,"session_id":"default","short_label":"{}"}}"#, escape_json(&label))
+            encode_reply(&ConnectReply {
+                success: true,
+                session_id: Some("default".to_string()),
+                display_name: None,
+                short_label: Some(label),
+            })
         }
         Err(e) if e.starts_with("PASSWORD_REQUIRED:") => {
             let msg = &e["PASSWORD_REQUIRED:".len()..];
@@ -98,157 +150,118 @@ fn handle_connect(json: &str) -> String {
             } else {
                 msg
             };
-            format!(r#"{{"success":false,"error":"{}","error_type":"password_required"}}"#, escape_json(error_msg))
+            encode_reply(&ErrorReply::new(error_msg).with_type(ErrorType::PasswordRequired))
         }
-        Err(e) => format!(r#"{{"success":false,"error":"{}"}}"#, escape_json(&e)),
+        Err(e) => encode_reply(&ErrorReply::new(e)),
     }
 }
 
 fn handle_disconnect() -> String {
     let mut guard = SESSION.lock().unwrap();
+    let flushed = guard.as_mut().map_or(Ok(()), ArchiveSession::flush);
     *guard = None;
-    r#"{"success":true}"#.to_string()
+    match flushed {
+        Ok(()) => encode_reply(&SuccessReply { success: true }),
+        Err(e) => edit_error(e),
+    }
 }
 
-fn handle_list_directory(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_else(|| "/".to_string());
-
+fn handle_list_directory(path: &str) -> String {
     let guard = SESSION.lock().unwrap();
     let session = match guard.as_ref() {
         Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
+        None => return encode_reply(&ErrorReply::new("Not connected")),
     };
 
-    let entries = session.list_directory(&path);
-
-    let entries_json: Vec<String> = entries
-        .iter()
-        .map(|e| {
-            let modified_ts = e.modified
-                .and_then(|t| t.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs() as i64)
-                .unwrap_or(0);
-
-            format!(
-                r#"{{"name":"{}","path":"{}","is_dir":{},"size":{},"is_hidden":{},"permissions":{},"is_symlink":false,"modified":{}}}"#,
-                escape_json(&e.name),
-                escape_json(&e.path.to_string_lossy()),
-                e.is_dir,
-                e.size,
-                e.is_hidden,
-                e.permissions,
-                modified_ts
-            )
-        })
-        .collect();
-
-    format!(r#"{{"entries":[{}]}}"#, entries_json.join(","))
+    let entries = session.list_directory(path);
+    encode_reply(&ListReply { entries: entries.iter().map(EntrySpec::from).collect() })
 }
 
-fn handle_read_file(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-
-    let guard = SESSION.lock().unwrap();
-    let session = match guard.as_ref() {
+fn handle_read_file(path: &str) -> String {
+    let mut guard = SESSION.lock().unwrap();
+    let session = match guard.as_mut() {
         Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
+        None => return encode_reply(&ErrorReply::new("Not connected")),
     };
 
-    match session.read_file(&path) {
-        Ok(data) => {
-            let b64 = base64_encode(&data);
-            format!(r#"{{"data":"{}"}}"#, b64)
-        }
+    // Queued edits have to be in the archive to be read back
+    if let Err(e) = session.flush() {
+        return edit_error(e);
+    }
+
+    match session.read_file(path) {
+        Ok(data) => encode_reply(&ReadReply { data: base64_encode(&data) }),
         Err(e) if e.starts_with("PASSWORD_REQUIRED:") => {
             let msg = &e["PASSWORD_REQUIRED:".len()..];
-            format!(r#"{{"error":"{}","error_type":"password_required"}}"#, escape_json(msg))
+            encode_reply(&ErrorReply::new(msg).with_type(ErrorType::PasswordRequired))
         }
-        Err(e) => format!(r#"{{"error":"{}","error_type":"not_found"}}"#, escape_json(&e)),
+        Err(e) => encode_reply(&ErrorReply::new(e).with_type(ErrorType::NotFound)),
     }
 }
 
-fn handle_set_password(json: &str) -> String {
-    let password = extract_string(json, "password").unwrap_or_default();
-
+fn handle_set_password(password: String) -> String {
     let mut guard = SESSION.lock().unwrap();
     match guard.as_mut() {
         Some(session) => {
             session.set_password(if password.is_empty() { None } else { Some(password) });
-            r#"{"success":true}"#.to_string()
+            encode_reply(&SuccessReply { success: true })
         }
-        None => r#"{"error":"Not connected"}"#.to_string(),
+        None => encode_reply(&ErrorReply::new("Not connected")),
     }
 }
 
-// === JSON helpers ===
-
-fn extract_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = &json[start..];
-    let rest = rest.trim_start();
-
-    if rest.starts_with('"') {
-        let rest = &rest[1..];
-        let mut result = String::new();
-        let mut chars = rest.chars().peekable();
-        while let Some(c) = chars.next() {
-            match c {
-                '"' => break,
-                '\\' => {
-                    if let Some(&next) = chars.peek() {
-                        chars.next();
-                        match next {
-                            'n' => result.push('\n'),
-                            'r' => result.push('\r'),
-                            't' => result.push('\t'),
-                            '"' => result.push('"'),
-                            '\\' => result.push('\\'),
-                            _ => {
-                                result.push('\\');
-                                result.push(next);
-                            }
-                        }
-                    }
-                }
-                _ => result.push(c),
-            }
-        }
-        Some(result)
-    } else if rest.starts_with('{') {
-        let mut depth = 0;
-        let mut end = 0;
-        for (i, c) in rest.chars().enumerate() {
-            match c {
-                '{' => depth += 1,
-                '}' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        end = i + 1;
-                        break;
-                    }
-                }
-                _ => {}
-            }
-        }
-        Some(rest[..end].to_string())
-    } else {
-        let end = rest.find(|c: char| c == ',' || c == '}' || c == ']')?;
-        Some(rest[..end].trim().to_string())
+/// Run an edit against the open session and format the response
+fn edit_session(edit: impl FnOnce(&mut ArchiveSession) -> Result<(), EditError>) -> String {
+    let mut guard = SESSION.lock().unwrap();
+    let session = match guard.as_mut() {
+        Some(s) => s,
+        None => return encode_reply(&ErrorReply::new("Not connected")),
+    };
+
+    match edit(session) {
+        Ok(()) => encode_reply(&SuccessReply { success: true }),
+        Err(e) => edit_error(e),
     }
 }
 
-fn extract_config_value(json: &str, key: &str) -> Option<String> {
-    let config_json = extract_string(json, "config")?;
-    extract_string(&config_json, key)
+fn edit_error(e: EditError) -> String {
+    encode_reply(&ErrorReply { error: Some(e.to_string()), error_type: e.error_type() })
 }
 
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
+fn base64_decode(input: &str) -> Result<Vec<u8>, String> {
+    fn value(c: u8) -> Result<u32, String> {
+        match c {
+            b'A'..=b'Z' => Ok((c - b'A') as u32),
+            b'a'..=b'z' => Ok((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Ok((c - b'0' + 52) as u32),
+            b'+' => Ok(62),
+            b'/' => Ok(63),
+            _ => Err(format!("Invalid base64 character: {}", c as char)),
+        }
+    }
+
+    let bytes: Vec<u8> = input.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    let mut result = Vec::with_capacity(bytes.len() / 4 * 3);
+
+    for chunk in bytes.chunks(4) {
+        let data: Vec<u8> = chunk.iter().copied().take_while(|&c| c != b'=').collect();
+        if data.len() < 2 {
+            return Err("Invalid base64 length".to_string());
+        }
+        let mut n = 0u32;
+        for (i, &c) in data.iter().enumerate() {
+            n |= value(c)? << (18 - 6 * i);
+        }
+        result.push((n >> 16) as u8);
+        if data.len() > 2 {
+            result.push((n >> 8) as u8);
+        }
+        if data.len() > 3 {
+            result.push(n as u8);
+        }
+    }
+
+    Ok(result)
 }
 
 fn base64_encode(data: &[u8]) -> String {
@@ -0,0 +1,539 @@
+//! Rewriting ZIP and TAR-family archives
+//!
+//! Archives can't be edited in place, so a batch of `Edit`s streams the
+//! original into a temp file next to it, applying all of them on the way,
+//! and renames the temp file over the original. Entries that aren't touched
+//! keep their timestamps and permissions, TAR entries their PAX headers;
+//! ZIP entries are copied without recompressing.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::archive::Compression;
+
+/// Archive formats that can be rewritten
+#[derive(Debug, Clone, Copy)]
+pub enum WritableFormat {
+    Zip,
+    Tar(Option<Compression>),
+}
+
+/// A change applied while rewriting an archive.
+/// Paths are archive-relative, without leading or trailing slashes.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// Add a file, replacing an existing one with the same path
+    Write { path: String, data: Vec<u8> },
+    /// Remove a file, or a directory with everything below it
+    Delete { path: String },
+    /// Add an empty directory
+    Mkdir { path: String },
+    /// Move a file or directory with everything below it
+    Rename { from: String, to: String },
+    /// Duplicate a file or directory with everything below it
+    Copy { from: String, to: String },
+    /// Change the timestamp and/or permissions of one entry
+    SetAttributes {
+        path: String,
+        modified: Option<SystemTime>,
+        permissions: Option<u32>,
+    },
+}
+
+impl Edit {
+    /// Names an existing entry is written out under (empty = dropped)
+    pub fn targets(&self, name: &str) -> Vec<String> {
+        match self {
+            Edit::Write { path, .. } if path == name => Vec::new(),
+            Edit::Delete { path } if is_under(name, path) => Vec::new(),
+            Edit::Rename { from, to } if is_under(name, from) => {
+                vec![format!("{}{}", to, &name[from.len()..])]
+            }
+            Edit::Copy { from, to } if is_under(name, from) => {
+                vec![name.to_string(), format!("{}{}", to, &name[from.len()..])]
+            }
+            _ => vec![name.to_string()],
+        }
+    }
+
+    /// New timestamp and permissions for an entry, if this edit changes them
+    pub fn attributes_for(&self, name: &str) -> Option<(Option<SystemTime>, Option<u32>)> {
+        match self {
+            Edit::SetAttributes { path, modified, permissions } if path == name => {
+                Some((*modified, permissions.map(|p| p & 0o7777)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Where an entry ends up once a batch of edits is applied, with the
+/// attributes they set on the way
+#[derive(Debug, Clone, PartialEq)]
+struct Planned {
+    name: String,
+    modified: Option<SystemTime>,
+    permissions: Option<u32>,
+}
+
+impl Planned {
+    fn touched(&self) -> bool {
+        self.modified.is_some() || self.permissions.is_some()
+    }
+}
+
+/// Follow the entry `name`, whose mode is `mode`, through `edits`. Returns
+/// every place it is written out to; none if it's dropped. A `Write` that
+/// drops it gets its mode in the matching slot of `replaced`, so the new
+/// file keeps it.
+fn plan(edits: &[Edit], name: &str, mode: Option<u32>, replaced: &mut [Option<u32>]) -> Vec<Planned> {
+    let mut items = vec![Planned { name: name.to_string(), modified: None, permissions: None }];
+    for (edit, replaced) in edits.iter().zip(replaced.iter_mut()) {
+        let mut next = Vec::new();
+        for item in items {
+            let targets = edit.targets(&item.name);
+            if targets.is_empty() && matches!(edit, Edit::Write { .. }) {
+                *replaced = item.permissions.or(mode);
+            }
+            for target in targets {
+                let mut out = Planned { name: target, ..item.clone() };
+                if let Some((modified, permissions)) = edit.attributes_for(&out.name) {
+                    out.modified = modified.or(out.modified);
+                    out.permissions = permissions.or(out.permissions);
+                }
+                next.push(out);
+            }
+        }
+        items = next;
+    }
+    items
+}
+
+/// A file or directory the edits add
+struct NewEntry<'a> {
+    name: String,
+    modified: Option<SystemTime>,
+    mode: u32,
+    /// None for a directory
+    data: Option<&'a [u8]>,
+}
+
+/// Entries added by `Write` and `Mkdir`, where later edits leave them.
+/// `replaced` must already hold the modes of the existing entries the
+/// writes replace.
+fn new_entries<'a>(edits: &'a [Edit], replaced: &mut [Option<u32>]) -> Vec<NewEntry<'a>> {
+    let mut entries = Vec::new();
+    for (k, edit) in edits.iter().enumerate() {
+        let (path, data, mode) = match edit {
+            Edit::Write { path, data } => (path, Some(data.as_slice()), replaced[k].unwrap_or(0o644)),
+            Edit::Mkdir { path } => (path, None, 0o755),
+            _ => continue,
+        };
+        for out in plan(&edits[k + 1..], path, Some(mode), &mut replaced[k + 1..]) {
+            entries.push(NewEntry {
+                mode: out.permissions.unwrap_or(mode),
+                name: out.name,
+                modified: out.modified,
+                data,
+            });
+        }
+    }
+    entries
+}
+
+/// True if `name` is `prefix` itself or lies below it
+fn is_under(name: &str, prefix: &str) -> bool {
+    name.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Entry name as used for matching: no "./", no trailing slash
+fn entry_key(raw: &str) -> String {
+    raw.replace('\\', "/")
+        .trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Apply `edits`, in order, to the archive at `archive_path` in a single
+/// rewrite. The original is only replaced once the new archive is fully
+/// written.
+pub fn rewrite_archive(archive_path: &Path, format: WritableFormat, edits: &[Edit]) -> Result<(), String> {
+    let temp_path = temp_path_for(archive_path);
+
+    let result = match format {
+        WritableFormat::Zip => rewrite_zip(archive_path, &temp_path, edits),
+        WritableFormat::Tar(compression) => rewrite_tar(archive_path, &temp_path, compression, edits),
+    }
+    .and_then(|()| replace_original(archive_path, &temp_path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Hidden temp file in the same directory, so the final rename is atomic
+fn temp_path_for(archive_path: &Path) -> PathBuf {
+    let name = archive_path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    archive_path.with_file_name(format!(".{}.bark-{}.tmp", name, std::process::id()))
+}
+
+/// Give the temp file the original's permissions, flush it to disk and
+/// move it into place
+fn replace_original(archive_path: &Path, temp_path: &Path) -> Result<(), String> {
+    if let Ok(meta) = fs::metadata(archive_path) {
+        let _ = fs::set_permissions(temp_path, meta.permissions());
+    }
+    File::open(temp_path)
+        .and_then(|f| f.sync_all())
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    fs::rename(temp_path, archive_path)
+        .map_err(|e| format!("Failed to replace archive: {}", e))
+}
+
+fn create_temp(temp_path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(temp_path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("Failed to create temp file: {}", e))
+}
+
+fn finish_file(writer: BufWriter<File>) -> Result<(), String> {
+    writer.into_inner()
+        .map(drop)
+        .map_err(|e| format!("Failed to write archive: {}", e.error()))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// === ZIP ===
+
+fn rewrite_zip(archive_path: &Path, temp_path: &Path, edits: &[Edit]) -> Result<(), String> {
+    let zip_err = |e: zip::result::ZipError| format!("Failed to rewrite ZIP: {}", e);
+
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to open ZIP: {}", e))?;
+    let mut writer = zip::ZipWriter::new(create_temp(temp_path)?);
+    let mut replaced = vec![None; edits.len()];
+
+    for i in 0..archive.len() {
+        let (raw_name, mode) = {
+            let file = archive.by_index_raw(i).map_err(zip_err)?;
+            (file.name().to_string(), file.unix_mode())
+        };
+        let name = entry_key(&raw_name);
+        let suffix = if raw_name.ends_with('/') { "/" } else { "" };
+
+        // Entries are copied raw: no decompression, no password needed
+        for out in plan(edits, &name, mode, &mut replaced) {
+            let file = archive.by_index_raw(i).map_err(zip_err)?;
+            let target = format!("{}{}", out.name, suffix);
+            if !out.touched() {
+                if out.name == name {
+                    writer.raw_copy_file(file)
+                } else {
+                    writer.raw_copy_file_rename(file, target)
+                }
+                .map_err(zip_err)?;
+                continue;
+            }
+
+            let time = out.modified.and_then(zip_datetime)
+                .or_else(|| file.last_modified())
+                .unwrap_or_default();
+            if out.name == name {
+                writer.raw_copy_file_touch(file, time, out.permissions).map_err(zip_err)?;
+                continue;
+            }
+
+            // Moved and touched: the raw copy can't do both, so this one
+            // entry is recompressed
+            let mut options = file.options().last_modified_time(time);
+            if let Some(mode) = out.permissions {
+                options = options.unix_permissions(mode);
+            }
+            drop(file);
+            if suffix.is_empty() {
+                let mut data = Vec::new();
+                archive.by_index(i).map_err(zip_err)?
+                    .read_to_end(&mut data)
+                    .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+                writer.start_file(target, options).map_err(zip_err)?;
+                writer.write_all(&data)
+                    .map_err(|e| format!("Failed to write {}: {}", out.name, e))?;
+            } else {
+                writer.add_directory(target, options).map_err(zip_err)?;
+            }
+        }
+    }
+
+    let now = zip_datetime(SystemTime::now()).unwrap_or_default();
+    for entry in new_entries(edits, &mut replaced) {
+        let options = zip::write::SimpleFileOptions::default()
+            .last_modified_time(entry.modified.and_then(zip_datetime).unwrap_or(now))
+            .unix_permissions(entry.mode);
+        match entry.data {
+            Some(data) => {
+                let options = options
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .large_file(data.len() as u64 >= u32::MAX as u64);
+                writer.start_file(entry.name.as_str(), options).map_err(zip_err)?;
+                writer.write_all(data)
+                    .map_err(|e| format!("Failed to write {}: {}", entry.name, e))?;
+            }
+            None => writer.add_directory(format!("{}/", entry.name), options).map_err(zip_err)?,
+        }
+    }
+
+    finish_file(writer.finish().map_err(zip_err)?)
+}
+
+/// Convert a timestamp to a ZIP date (UTC, like the reader assumes).
+/// Returns None outside the 1980–2107 range ZIP can store.
+fn zip_datetime(time: SystemTime) -> Option<zip::DateTime> {
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs() as i64;
+    let days = secs / 86400;
+    let rem = secs % 86400;
+
+    // Civil date from days since the Unix epoch
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    zip::DateTime::from_date_and_time(
+        u16::try_from(year).ok()?,
+        month as u8,
+        day as u8,
+        (rem / 3600) as u8,
+        (rem % 3600 / 60) as u8,
+        (rem % 60) as u8,
+    ).ok()
+}
+
+// === TAR ===
+
+fn rewrite_tar(
+    archive_path: &Path,
+    temp_path: &Path,
+    compression: Option<Compression>,
+    edits: &[Edit],
+) -> Result<(), String> {
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let reader = BufReader::new(file);
+    let out = create_temp(temp_path)?;
+    let compress_err = |e: io::Error| format!("Failed to compress archive: {}", e);
+
+    let out = match compression {
+        None => copy_tar(reader, out, edits)?,
+        Some(Compression::Gzip) => {
+            let encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+            copy_tar(flate2::read::GzDecoder::new(reader), encoder, edits)?
+                .finish()
+                .map_err(compress_err)?
+        }
+        Some(Compression::Bzip2) => {
+            let encoder = bzip2::write::BzEncoder::new(out, bzip2::Compression::default());
+            copy_tar(bzip2::read::BzDecoder::new(reader), encoder, edits)?
+                .finish()
+                .map_err(compress_err)?
+        }
+        Some(Compression::Xz) => {
+            let encoder = xz2::write::XzEncoder::new(out, 6);
+            copy_tar(xz2::read::XzDecoder::new(reader), encoder, edits)?
+                .finish()
+                .map_err(compress_err)?
+        }
+    };
+
+    finish_file(out)
+}
+
+/// Copy every entry from `reader` into a new tar stream on `writer`,
+/// applying `edits`. Returns the writer so the caller can finish compression.
+fn copy_tar<R: Read, W: Write>(reader: R, writer: W, edits: &[Edit]) -> Result<W, String> {
+    let tar_err = |e: io::Error| format!("Failed to rewrite TAR: {}", e);
+
+    let mut archive = tar::Archive::new(reader);
+    let mut builder = tar::Builder::new(writer);
+    let mut replaced = vec![None; edits.len()];
+
+    for entry in archive.entries().map_err(tar_err)? {
+        let mut entry = entry.map_err(tar_err)?;
+        let raw_path = entry.path().map_err(tar_err)?.to_string_lossy().to_string();
+        let name = entry_key(&raw_path);
+
+        let outputs = plan(edits, &name, entry.header().mode().ok(), &mut replaced);
+        if outputs.is_empty() {
+            continue;
+        }
+
+        // Extended headers (xattrs, ACLs, precise times) go with the entry.
+        // Path, link name and size are written again from the header.
+        let pax: Vec<(String, Vec<u8>)> = match entry.pax_extensions().map_err(tar_err)? {
+            Some(extensions) => extensions
+                .filter_map(|ext| ext.ok())
+                .filter_map(|ext| Some((ext.key().ok()?.to_string(), ext.value_bytes().to_vec())))
+                .filter(|(key, _)| !matches!(key.as_str(), "path" | "linkpath" | "size"))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // The header is reused as-is so ownership, mode and mtime survive.
+        // Long names and PAX sizes were resolved by the reader, so set them
+        // back explicitly.
+        let mut base = entry.header().clone();
+        base.set_size(entry.size());
+
+        let mut link_name = entry.link_name().map_err(tar_err)?
+            .map(|l| l.to_string_lossy().to_string());
+        // Hard links refer to archive paths, so follow a moved target
+        if let Some(link) = &link_name
+            && base.entry_type().is_hard_link()
+            && let Some(target) = plan(edits, &entry_key(link), None, &mut vec![None; edits.len()]).into_iter().next()
+        {
+            link_name = Some(target.name);
+        }
+
+        let is_dir = base.entry_type().is_dir();
+
+        // A copy needs the data twice; everything else streams through
+        let buffered = if outputs.len() > 1 {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).map_err(tar_err)?;
+            Some(data)
+        } else {
+            None
+        };
+
+        for out in outputs {
+            let mut header = base.clone();
+            if let Some(modified) = out.modified {
+                header.set_mtime(unix_secs(modified));
+            }
+            if let Some(mode) = out.permissions {
+                header.set_mode(mode);
+            }
+            // A new timestamp replaces the precise one
+            builder.append_pax_extensions(
+                pax.iter()
+                    .filter(|(key, _)| out.modified.is_none() || key != "mtime")
+                    .map(|(key, value)| (key.as_str(), value.as_slice())),
+            )
+            .map_err(tar_err)?;
+
+            let path = if out.name == name {
+                raw_path.clone()
+            } else if is_dir {
+                format!("{}/", out.name)
+            } else {
+                out.name
+            };
+            match (&link_name, &buffered) {
+                (Some(link), _) => builder.append_link(&mut header, &path, link),
+                (None, Some(data)) => builder.append_data(&mut header, &path, data.as_slice()),
+                (None, None) => builder.append_data(&mut header, &path, &mut entry),
+            }
+            .map_err(tar_err)?;
+        }
+    }
+
+    let now = SystemTime::now();
+    for entry in new_entries(edits, &mut replaced) {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(entry.mode);
+        header.set_mtime(unix_secs(entry.modified.unwrap_or(now)));
+        match entry.data {
+            Some(data) => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_size(data.len() as u64);
+                builder.append_data(&mut header, &entry.name, data)
+            }
+            None => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_size(0);
+                builder.append_data(&mut header, format!("{}/", entry.name), io::empty())
+            }
+        }
+        .map_err(tar_err)?;
+    }
+
+    builder.into_inner().map_err(tar_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_under() {
+        assert!(is_under("docs", "docs"));
+        assert!(is_under("docs/a.txt", "docs"));
+        assert!(!is_under("docs2/a.txt", "docs"));
+        assert!(!is_under("doc", "docs"));
+    }
+
+    #[test]
+    fn test_edit_targets() {
+        let rename = Edit::Rename { from: "src".into(), to: "lib".into() };
+        assert_eq!(rename.targets("src/main.rs"), vec!["lib/main.rs"]);
+        assert_eq!(rename.targets("srcs/main.rs"), vec!["srcs/main.rs"]);
+
+        let copy = Edit::Copy { from: "a.txt".into(), to: "b.txt".into() };
+        assert_eq!(copy.targets("a.txt"), vec!["a.txt", "b.txt"]);
+
+        let delete = Edit::Delete { path: "dir".into() };
+        assert!(delete.targets("dir/x").is_empty());
+        assert_eq!(delete.targets("x"), vec!["x"]);
+    }
+
+    #[test]
+    fn test_plan_batch() {
+        let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_500_000_000);
+        let edits = [
+            Edit::Write { path: "a.txt".into(), data: b"new".to_vec() },
+            Edit::SetAttributes { path: "a.txt".into(), modified: Some(mtime), permissions: None },
+            Edit::Rename { from: "dir".into(), to: "moved".into() },
+            Edit::SetAttributes { path: "moved/b.txt".into(), modified: None, permissions: Some(0o600) },
+        ];
+        let mut replaced = vec![None; edits.len()];
+
+        // The old a.txt is replaced; the write inherits its mode
+        assert!(plan(&edits, "a.txt", Some(0o640), &mut replaced).is_empty());
+        let moved = plan(&edits, "dir/b.txt", Some(0o644), &mut replaced);
+        assert_eq!(moved, [Planned { name: "moved/b.txt".into(), modified: None, permissions: Some(0o600) }]);
+
+        let added = new_entries(&edits, &mut replaced);
+        assert_eq!(added.len(), 1);
+        assert_eq!((added[0].name.as_str(), added[0].modified, added[0].mode), ("a.txt", Some(mtime), 0o640));
+        assert_eq!(added[0].data, Some(&b"new"[..]));
+    }
+
+    #[test]
+    fn test_zip_datetime() {
+        // 2024-02-29 12:34:56 UTC
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1709210096);
+        let dt = zip_datetime(time).unwrap();
+        assert_eq!((dt.year(), dt.month(), dt.day()), (2024, 2, 29));
+        assert_eq!((dt.hour(), dt.minute(), dt.second()), (12, 34, 56));
+
+        // Before the DOS epoch
+        assert!(zip_datetime(SystemTime::UNIX_EPOCH).is_none());
+    }
+}
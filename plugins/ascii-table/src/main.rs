@@ -2,13 +2,22 @@
 
 use std::io::{self, BufRead, Write};
 
+use bark_plugin_api::protocol::{Command, OverlayReply, PluginInfoReply, Request, encode_reply};
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
     if args.iter().any(|a| a == "--plugin-info") {
-        println!(
-            r#"{{"name":"ASCII Table","version":"1.0.0","type":"overlay","description":"ASCII character reference","width":62,"height":22}}"#
-        );
+        let info = PluginInfoReply {
+            name: Some("ASCII Table".to_string()),
+            version: Some("1.0.0".to_string()),
+            plugin_type: Some("overlay".to_string()),
+            description: "ASCII character reference".to_string(),
+            width: Some(62),
+            height: Some(22),
+            ..Default::default()
+        };
+        println!("{}", encode_reply(&info));
         return;
     }
 
@@ -28,29 +37,22 @@ fn main() {
             continue;
         }
 
-        let command = extract_str(line, "command").unwrap_or_default();
-
-        match command.as_str() {
-            "init" => {
-                state.width = extract_int(line, "width").unwrap_or(62) as u16;
-                state.height = extract_int(line, "height").unwrap_or(22) as u16;
-                let response = state.render();
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
-            }
-            "key" => {
-                let key = extract_str(line, "key").unwrap_or_default();
-                let response = state.handle_key(&key);
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
+        let reply = match serde_json::from_str::<Request>(line).map(|request| request.command) {
+            Ok(Command::Init { width, height }) => {
+                state.width = width;
+                state.height = height;
+                state.render()
             }
-            "close" => break,
+            Ok(Command::Key { key, .. }) => state.handle_key(&key),
+            Ok(Command::Close) => break,
             _ => {
-                let _ = writeln!(writer, r#"{{"close":true}}"#);
+                let _ = writeln!(writer, "{}", encode_reply(&OverlayReply { close: true, ..Default::default() }));
                 let _ = writer.flush();
                 break;
             }
-        }
+        };
+        let _ = writeln!(writer, "{}", encode_reply(&reply));
+        let _ = writer.flush();
     }
 }
 
@@ -124,10 +126,10 @@ impl AsciiState {
         self.height.saturating_sub(5) as usize
     }
 
-    fn handle_key(&mut self, key: &str) -> String {
+    fn handle_key(&mut self, key: &str) -> OverlayReply {
         let visible = self.visible_rows();
         match key {
-            "Escape" => return r#"{"close":true}"#.to_string(),
+            "Escape" => return OverlayReply { close: true, ..Default::default() },
             "Up" => { if self.scroll > 0 { self.scroll -= 1; } }
             "Down" => { if self.scroll + visible < 128 { self.scroll += 1; } }
             "PageUp" => { self.scroll = self.scroll.saturating_sub(visible); }
@@ -149,7 +151,7 @@ impl AsciiState {
         self.render()
     }
 
-    fn render(&self) -> String {
+    fn render(&self) -> OverlayReply {
         let inner_w = self.width.saturating_sub(2) as usize;
         let visible = self.visible_rows();
         let mut lines: Vec<String> = Vec::new();
@@ -190,55 +192,16 @@ impl AsciiState {
             lines.push(String::new());
         }
 
-        let lines_json: Vec<String> = lines.iter()
-            .map(|l| format!("\"{}\"", escape_json(l)))
-            .collect();
-
-        format!(
-            r#"{{"title":" ASCII Table ","width":{},"height":{},"close":false,"lines":[{}]}}"#,
-            self.width, self.height, lines_json.join(",")
-        )
+        OverlayReply {
+            lines,
+            title: " ASCII Table ".to_string(),
+            width: Some(self.width),
+            height: Some(self.height),
+            ..Default::default()
+        }
     }
 }
 
 fn truncate(s: &str, max: usize) -> String {
     if s.len() <= max { s.to_string() } else { s[..max].to_string() }
 }
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn extract_str(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    if !rest.starts_with('"') { return None; }
-    let rest = &rest[1..];
-    let mut result = String::new();
-    let mut chars = rest.chars().peekable();
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => break,
-            '\\' => { if let Some(&n) = chars.peek() { chars.next(); match n {
-                'n' => result.push('\n'), '"' => result.push('"'),
-                '\\' => result.push('\\'), 't' => result.push('\t'),
-                _ => { result.push('\\'); result.push(n); }
-            }}}
-            _ => result.push(c),
-        }
-    }
-    Some(result)
-}
-
-fn extract_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
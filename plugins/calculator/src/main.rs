@@ -4,13 +4,22 @@
 
 use std::io::{self, BufRead, Write};
 
+use bark_plugin_api::protocol::{Command, OverlayReply, PluginInfoReply, Request, encode_reply};
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
     if args.iter().any(|a| a == "--plugin-info") {
-        println!(
-            r#"{{"name":"Calculator","version":"1.0.0","type":"overlay","description":"Programmer calculator (DEC/HEX)","width":48,"height":20}}"#
-        );
+        let info = PluginInfoReply {
+            name: Some("Calculator".to_string()),
+            version: Some("1.0.0".to_string()),
+            plugin_type: Some("overlay".to_string()),
+            description: "Programmer calculator (DEC/HEX)".to_string(),
+            width: Some(48),
+            height: Some(20),
+            ..Default::default()
+        };
+        println!("{}", encode_reply(&info));
         return;
     }
 
@@ -31,34 +40,24 @@ fn main() {
             continue;
         }
 
-        let command = extract_str(line, "command").unwrap_or_default();
-
-        match command.as_str() {
-            "init" => {
-                let w = extract_int(line, "width").unwrap_or(48) as u16;
-                let h = extract_int(line, "height").unwrap_or(20) as u16;
-                state.width = w;
-                state.height = h;
-                let response = state.render();
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
-            }
-            "key" => {
-                let key = extract_str(line, "key").unwrap_or_default();
-                let mods = extract_str_array(line, "modifiers");
-                let response = state.handle_key(&key, &mods);
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
+        let reply = match serde_json::from_str::<Request>(line).map(|request| request.command) {
+            Ok(Command::Init { width, height }) => {
+                state.width = width;
+                state.height = height;
+                state.render()
             }
-            "close" => {
+            Ok(Command::Key { key, modifiers }) => state.handle_key(&key, &modifiers),
+            Ok(Command::Close) => {
                 break;
             }
             _ => {
-                let _ = writeln!(writer, r#"{{"close":true}}"#);
+                let _ = writeln!(writer, "{}", encode_reply(&OverlayReply { close: true, ..Default::default() }));
                 let _ = writer.flush();
                 break;
             }
-        }
+        };
+        let _ = writeln!(writer, "{}", encode_reply(&reply));
+        let _ = writer.flush();
     }
 }
 
@@ -95,14 +94,14 @@ impl CalcState {
         }
     }
 
-    fn handle_key(&mut self, key: &str, modifiers: &[String]) -> String {
+    fn handle_key(&mut self, key: &str, modifiers: &[String]) -> OverlayReply {
         let ctrl = modifiers.iter().any(|m| m == "ctrl");
 
         self.error = None;
 
         match key {
             "Escape" => {
-                return r#"{"close":true}"#.to_string();
+                return OverlayReply { close: true, ..Default::default() };
             }
             "Tab" => {
                 self.mode = match self.mode {
@@ -115,7 +114,7 @@ impl CalcState {
             }
             "Enter" => {
                 if ctrl {
-                    return r#"{"close":true}"#.to_string();
+                    return OverlayReply { close: true, ..Default::default() };
                 }
                 self.evaluate();
             }
@@ -201,7 +200,7 @@ impl CalcState {
         Ok(format!("{:X} = {}", val as u64, val))
     }
 
-    fn render(&self) -> String {
+    fn render(&self) -> OverlayReply {
         let mode_str = match self.mode {
             CalcMode::Dec => "DEC",
             CalcMode::Hex => "HEX",
@@ -268,18 +267,13 @@ impl CalcState {
         }
         lines.truncate(content_height);
 
-        // Build JSON response
-        let lines_json: Vec<String> = lines.iter()
-            .map(|l| format!("\"{}\"", escape_json(l)))
-            .collect();
-
-        format!(
-            r#"{{"title":"{}","width":{},"height":{},"close":false,"lines":[{}]}}"#,
-            escape_json(&title),
-            self.width,
-            self.height,
-            lines_json.join(",")
-        )
+        OverlayReply {
+            lines,
+            title,
+            width: Some(self.width),
+            height: Some(self.height),
+            ..Default::default()
+        }
     }
 }
 
@@ -558,113 +552,3 @@ fn parse_hex_atom(tokens: &[String], pos: &mut usize) -> Result<i64, String> {
     *pos += 1;
     Ok(val)
 }
-
-// ============================================================================
-// JSON Helpers (minimal, no dependencies)
-// ============================================================================
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn extract_str(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    if !rest.starts_with('"') {
-        return None;
-    }
-    let rest = &rest[1..];
-    let mut result = String::new();
-    let mut chars = rest.chars().peekable();
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => break,
-            '\\' => {
-                if let Some(&next) = chars.peek() {
-                    chars.next();
-                    match next {
-                        'n' => result.push('\n'),
-                        't' => result.push('\t'),
-                        '"' => result.push('"'),
-                        '\\' => result.push('\\'),
-                        _ => { result.push('\\'); result.push(next); }
-                    }
-                }
-            }
-            _ => result.push(c),
-        }
-    }
-    Some(result)
-}
-
-fn extract_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn extract_str_array(json: &str, key: &str) -> Vec<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = match json.find(&pattern) {
-        Some(s) => s + pattern.len(),
-        None => return Vec::new(),
-    };
-    let rest = json[start..].trim_start();
-    if !rest.starts_with('[') {
-        return Vec::new();
-    }
-
-    let mut depth = 0;
-    let mut end = 0;
-    for (i, c) in rest.char_indices() {
-        match c {
-            '[' => depth += 1,
-            ']' => {
-                depth -= 1;
-                if depth == 0 {
-                    end = i;
-                    break;
-                }
-            }
-            _ => {}
-        }
-    }
-
-    if end == 0 {
-        return Vec::new();
-    }
-
-    let inner = &rest[1..end];
-    let mut result = Vec::new();
-    let mut current = String::new();
-    let mut in_string = false;
-    let mut escape = false;
-
-    for c in inner.chars() {
-        if escape {
-            current.push(c);
-            escape = false;
-            continue;
-        }
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => {
-                if in_string {
-                    result.push(current.clone());
-                    current.clear();
-                }
-                in_string = !in_string;
-            }
-            _ if in_string => current.push(c),
-            _ => {}
-        }
-    }
-    result
-}
@@ -0,0 +1,132 @@
+//! CSV/TSV viewer plugin executable
+//!
+//! Guesses the delimiter and quote character, then lays the file out as an
+//! aligned table. The `viewer_render` reply carries the heading, which Bark
+//! keeps above the rows, and the position of every column with its type
+//! guess and statistics, which Bark shows while the column is selected.
+//! Rows are sorted by the column in the request's `sort`.
+//!
+//! Protocol:
+//! - `--plugin-info`: Print plugin metadata as JSON
+//! - stdin/stdout: JSON commands and responses
+
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use bark_plugin_api::protocol::{
+    CanHandleReply, Command, ErrorReply, PluginInfoReply, Request, ViewerColumn, ViewerReply, ViewerSort, encode_reply,
+};
+
+mod csv;
+mod table;
+
+use table::Table;
+
+/// Larger files are left to the built-in viewer
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Extensions handled, and whether they hold tab-separated values
+const EXTENSIONS: [(&str, bool); 3] = [("csv", false), ("tsv", true), ("tab", true)];
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Handle --plugin-info
+    if args.len() > 1 && args[1] == "--plugin-info" {
+        print_plugin_info();
+        return;
+    }
+
+    // Handle JSON commands on stdin
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = handle_command(&line);
+        writeln!(stdout, "{}", response).ok();
+        stdout.flush().ok();
+    }
+}
+
+fn print_plugin_info() {
+    let info = PluginInfoReply {
+        name: Some("CSV Viewer".to_string()),
+        version: Some("1.0.0".to_string()),
+        plugin_type: Some("viewer".to_string()),
+        description: "Aligned, sortable table view of CSV and TSV files with column statistics".to_string(),
+        icon: Some("▦".to_string()),
+        extensions: EXTENSIONS.map(|(ext, _)| ext.to_string()).to_vec(),
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&info));
+}
+
+fn handle_command(line: &str) -> String {
+    match serde_json::from_str::<Request>(line).map(|request| request.command) {
+        Ok(Command::ViewerCanHandle { path }) => {
+            let can_handle = tabs(Path::new(&path)).is_some();
+            encode_reply(&CanHandleReply { can_handle, priority: if can_handle { 5 } else { 0 } })
+        }
+        Ok(Command::ViewerRender { path, scroll, height, sort, .. }) => {
+            match render(Path::new(&path), scroll, height, sort) {
+                Ok(reply) => encode_reply(&reply),
+                Err(e) => encode_reply(&ErrorReply::new(e)),
+            }
+        }
+        Ok(other) => encode_reply(&ErrorReply::new(format!("Unknown command: {}", other.name()))),
+        Err(e) => encode_reply(&ErrorReply::new(format!("Invalid request: {}", e))),
+    }
+}
+
+/// Whether the file at `path` is tab-separated, or None when it's neither
+/// CSV nor TSV
+fn tabs(path: &Path) -> Option<bool> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    EXTENSIONS.iter().find(|(e, _)| *e == ext).map(|&(_, tabs)| tabs)
+}
+
+/// Rows `scroll..scroll + height` of the file's table, sorted as asked
+fn render(path: &Path, scroll: usize, height: usize, sort: Option<ViewerSort>) -> Result<ViewerReply, String> {
+    let tabs = tabs(path).ok_or("Not a CSV or TSV file")?;
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(format!("File too large ({} bytes)", size));
+    }
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    let dialect = csv::detect(&text, tabs);
+    let table = Table::new(csv::parse(&text, dialect, None));
+    let sort = sort.map(|sort| (sort.column, sort.descending));
+    let order = table.order(sort);
+    let (header, rows, placements) = table.layout(&order, sort);
+
+    let columns = placements
+        .iter()
+        .zip(table.infos())
+        .map(|(placement, info)| ViewerColumn {
+            start: placement.start,
+            width: placement.width,
+            info: format!("{} │ {}", info, dialect.describe()),
+        })
+        .collect();
+
+    let total_lines = rows.len();
+    let end = scroll.saturating_add(height).min(total_lines);
+    Ok(ViewerReply {
+        lines: rows[scroll.min(end)..end].to_vec(),
+        total_lines: Some(total_lines),
+        header,
+        columns,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,315 @@
+//! Column statistics, sorting and the aligned layout of a table
+
+use std::cmp::Ordering;
+
+/// Widest a column gets; longer values are cut with an ellipsis
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Between two columns
+const SEPARATOR: &str = " │ ";
+
+/// Longest min/max value shown in a column's info
+const MAX_INFO_VALUE: usize = 30;
+
+/// What the values of a column look like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Empty,
+    Integer,
+    Number,
+    Boolean,
+    Date,
+    Text,
+}
+
+impl Kind {
+    /// Kind of a single non-empty value
+    fn of(value: &str) -> Kind {
+        let value = value.trim();
+        if value.parse::<i64>().is_ok() {
+            Kind::Integer
+        } else if value.bytes().any(|b| b.is_ascii_digit()) && value.parse::<f64>().is_ok_and(f64::is_finite) {
+            Kind::Number
+        } else if matches!(value.to_lowercase().as_str(), "true" | "false" | "yes" | "no") {
+            Kind::Boolean
+        } else if is_date(value) {
+            Kind::Date
+        } else {
+            Kind::Text
+        }
+    }
+
+    /// Kind of a column whose values so far are `self` and which also
+    /// holds a value of `other`
+    fn merge(self, other: Kind) -> Kind {
+        match (self, other) {
+            (Kind::Empty, kind) | (kind, Kind::Empty) => kind,
+            (a, b) if a == b => a,
+            (Kind::Integer | Kind::Number, Kind::Integer | Kind::Number) => Kind::Number,
+            _ => Kind::Text,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Kind::Integer | Kind::Number)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Empty => "empty",
+            Kind::Integer => "integer",
+            Kind::Number => "number",
+            Kind::Boolean => "boolean",
+            Kind::Date => "date",
+            Kind::Text => "text",
+        }
+    }
+}
+
+/// `YYYY-MM-DD`, optionally followed by a time
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, b)| if i == 4 || i == 7 { *b == b'-' } else { b.is_ascii_digit() })
+        && (bytes.len() == 10 || matches!(bytes[10], b'T' | b' '))
+}
+
+/// Values that stand for a missing one
+fn is_null(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "" | "null" | "na" | "n/a" | "none" | "nil")
+}
+
+/// Type guess and statistics of one column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    pub kind: Kind,
+    pub nulls: usize,
+    pub min: Option<String>,
+    pub max: Option<String>,
+}
+
+impl ColumnStats {
+    fn of(rows: &[Vec<String>], column: usize) -> Self {
+        let values = || rows.iter().filter_map(|row| row.get(column)).filter(|value| !is_null(value));
+        let kind = values().fold(Kind::Empty, |kind, value| kind.merge(Kind::of(value)));
+        let nulls = rows.len() - values().count();
+        let min = values().min_by(|a, b| compare(a, b, kind)).map(|v| v.trim().to_string());
+        let max = values().max_by(|a, b| compare(a, b, kind)).map(|v| v.trim().to_string());
+        Self { kind, nulls, min, max }
+    }
+
+    /// One line for the footer: `price: number, 2 empty, min 0.5, max 120`
+    fn info(&self, name: &str) -> String {
+        let short = |value: &str| -> String {
+            if value.chars().count() > MAX_INFO_VALUE {
+                format!("{}…", value.chars().take(MAX_INFO_VALUE - 1).collect::<String>())
+            } else {
+                value.to_string()
+            }
+        };
+        let mut info = format!("{}: {}, {} empty", name, self.kind.name(), self.nulls);
+        if let (Some(min), Some(max)) = (&self.min, &self.max) {
+            info.push_str(&format!(", min {}, max {}", short(min), short(max)));
+        }
+        info
+    }
+}
+
+/// Order of two non-null values of a column of `kind`
+fn compare(a: &str, b: &str, kind: Kind) -> Ordering {
+    if kind.is_numeric() {
+        let number = |v: &str| v.trim().parse::<f64>().unwrap_or(f64::NAN);
+        number(a).total_cmp(&number(b))
+    } else {
+        a.trim().to_lowercase().cmp(&b.trim().to_lowercase())
+    }
+}
+
+/// A parsed file: column names, rows, and the statistics of each column
+pub struct Table {
+    pub names: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub stats: Vec<ColumnStats>,
+}
+
+/// Where a column ended up in the laid out lines
+pub struct Placement {
+    pub start: usize,
+    pub width: usize,
+}
+
+impl Table {
+    /// Table of `records`, of which the first names the columns unless it
+    /// holds a number (headings rarely do)
+    pub fn new(mut records: Vec<Vec<String>>) -> Self {
+        let has_header = records
+            .first()
+            .is_some_and(|first| first.iter().all(|field| is_null(field) || !Kind::of(field).is_numeric()));
+        let mut names = if has_header && records.len() > 1 { records.remove(0) } else { Vec::new() };
+
+        let columns = records.iter().map(Vec::len).chain([names.len()]).max().unwrap_or(0);
+        for i in names.len()..columns {
+            names.push(format!("col{}", i + 1));
+        }
+        for (i, name) in names.iter_mut().enumerate() {
+            if name.trim().is_empty() {
+                *name = format!("col{}", i + 1);
+            }
+        }
+        let stats = (0..columns).map(|column| ColumnStats::of(&records, column)).collect();
+        Self { names, rows: records, stats }
+    }
+
+    /// Indices of the rows in the order asked for. Empty values go last
+    /// either way.
+    pub fn order(&self, sort: Option<(usize, bool)>) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        let Some((column, descending)) = sort.filter(|&(column, _)| column < self.names.len()) else {
+            return order;
+        };
+        let kind = self.stats[column].kind;
+        let value = |row: usize| self.rows[row].get(column).map(String::as_str).filter(|v| !is_null(v));
+        order.sort_by(|&a, &b| match (value(a), value(b)) {
+            (Some(a), Some(b)) if descending => compare(b, a, kind),
+            (Some(a), Some(b)) => compare(a, b, kind),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        order
+    }
+
+    /// Heading lines, row lines in `order` and where each column is. Each
+    /// line starts with the row number; numeric columns are right-aligned.
+    pub fn layout(&self, order: &[usize], sort: Option<(usize, bool)>) -> (Vec<String>, Vec<String>, Vec<Placement>) {
+        let names: Vec<String> = self
+            .names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match sort {
+                Some((column, false)) if column == i => format!("{} ▲", cell(name)),
+                Some((column, true)) if column == i => format!("{} ▼", cell(name)),
+                _ => cell(name),
+            })
+            .collect();
+        let widths: Vec<usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let values = self.rows.iter().filter_map(|row| row.get(i)).map(|v| cell(v).chars().count());
+                values.chain([name.chars().count(), 1]).max().unwrap_or(1).min(MAX_COLUMN_WIDTH)
+            })
+            .collect();
+
+        let gutter = self.rows.len().max(1).to_string().len();
+        let mut placements = Vec::with_capacity(widths.len());
+        let mut start = gutter + SEPARATOR.chars().count();
+        for &width in &widths {
+            placements.push(Placement { start, width });
+            start += width + SEPARATOR.chars().count();
+        }
+
+        let line = |number: &str, cells: &mut dyn Iterator<Item = (usize, String)>| {
+            let mut line = format!("{:>gutter$}", number);
+            for (i, text) in cells {
+                let right = self.stats[i].kind.is_numeric();
+                line.push_str(SEPARATOR);
+                line.push_str(&fit(&text, widths[i], right));
+            }
+            line.trim_end().to_string()
+        };
+        let heading = line("#", &mut names.iter().cloned().enumerate());
+        let rule = {
+            let mut rule = "─".repeat(gutter + 1);
+            for width in &widths {
+                rule.push('┼');
+                rule.push_str(&"─".repeat(width + 2));
+            }
+            rule
+        };
+        let rows = order
+            .iter()
+            .map(|&row| {
+                let values = &self.rows[row];
+                let mut cells = (0..widths.len()).map(|i| (i, values.get(i).map_or(String::new(), |v| cell(v))));
+                line(&(row + 1).to_string(), &mut cells)
+            })
+            .collect();
+        (vec![heading, rule], rows, placements)
+    }
+
+    /// Footer info of each column
+    pub fn infos(&self) -> Vec<String> {
+        self.names.iter().zip(&self.stats).map(|(name, stats)| stats.info(name.trim())).collect()
+    }
+}
+
+/// A value on one line: line breaks as ↵, other control characters as
+/// spaces
+fn cell(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '\n' => '↵',
+            '\r' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+/// `text` padded or cut to `width` characters
+fn fit(text: &str, width: usize, right: bool) -> String {
+    let len = text.chars().count();
+    if len > width {
+        let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+        cut.push('…');
+        cut
+    } else if right {
+        format!("{:>width$}", text)
+    } else {
+        format!("{:<width$}", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(rows: &[&[&str]]) -> Table {
+        Table::new(rows.iter().map(|row| row.iter().map(|f| f.to_string()).collect()).collect())
+    }
+
+    #[test]
+    fn test_stats_and_sort() {
+        let t = table(&[&["name", "price", "when"], &["b", "10", "2024-01-02"], &["a", "", "2023-12-31"], &["c", "2.5"]]);
+        assert_eq!(t.stats[0].kind, Kind::Text);
+        assert_eq!(
+            t.stats[1],
+            ColumnStats { kind: Kind::Number, nulls: 1, min: Some("2.5".into()), max: Some("10".into()) }
+        );
+        assert_eq!((t.stats[2].kind, t.stats[2].nulls), (Kind::Date, 1));
+        assert_eq!(t.infos()[1], "price: number, 1 empty, min 2.5, max 10");
+
+        assert_eq!(t.order(None), [0, 1, 2]);
+        assert_eq!(t.order(Some((0, false))), [1, 0, 2]);
+        // Empty values stay last, and numbers sort as numbers
+        assert_eq!(t.order(Some((1, false))), [2, 0, 1]);
+        assert_eq!(t.order(Some((1, true))), [0, 2, 1]);
+
+        // A first row with numbers is data
+        let t = table(&[&["1", "x"], &["2", "y", "extra"]]);
+        assert_eq!((t.names.as_slice(), t.rows.len()), (&["col1", "col2", "col3"].map(String::from)[..], 2));
+    }
+
+    #[test]
+    fn test_layout() {
+        let t = table(&[&["id", "note"], &["7", "line\nbreak"], &["10", &"x".repeat(50)]]);
+        let (heading, rows, placements) = t.layout(&t.order(Some((0, true))), Some((0, true)));
+        assert_eq!(heading[0], "# │ id ▼ │ note");
+        assert_eq!(heading[1], format!("──┼──────┼{}", "─".repeat(42)));
+        assert_eq!(rows[0], format!("2 │   10 │ {}…", "x".repeat(39)));
+        assert_eq!(rows[1], "1 │    7 │ line↵break");
+        assert_eq!((placements[0].start, placements[0].width), (4, 4));
+        assert_eq!((placements[1].start, placements[1].width), (11, 40));
+        assert_eq!(rows[1].chars().skip(11).take(4).collect::<String>(), "line");
+    }
+}
@@ -1,20 +1,28 @@
 //! Hex Editor launcher plugin for Bark
 //!
-//! This is a viewer plugin that launches an external hex editor (default: jinx)
-//! for any file. It has the lowest priority (1) so all other viewer plugins
-//! take precedence.
+//! This is a viewer plugin that launches an external hex editor for any
+//! file. It has the lowest priority (1) so all other viewer plugins take
+//! precedence.
 //!
 //! The editor command is configurable via `editor.hex_editor` in Bark's
-//! config.toml (default: "jinx"). Bark passes the config to the plugin
-//! in the `viewer_render` JSON command.
+//! config.toml. Bark itself runs that command when this plugin is picked,
+//! and opens its built-in hex editor instead when the setting is empty or
+//! the command can't be started. Bark passes the config to the plugin in
+//! the `viewer_render` JSON command, where an empty setting falls back to
+//! "jinx".
 //!
 //! On Unix, the editor is spawned with /dev/tty so it gets direct terminal
 //! access even though the plugin's own stdin/stdout are piped by Bark.
 //! On Windows, the editor is spawned with CONIN$/CONOUT$ handles (the
 //! Windows equivalent of /dev/tty) for the same reason.
 
+use std::collections::BTreeMap;
 use std::io::{self, BufRead, Write};
-use std::process::Command;
+use std::process::Command as Process;
+
+use bark_plugin_api::protocol::{
+    CanHandleReply, Command, ErrorReply, PluginInfoReply, Request, SuccessReply, encode_reply,
+};
 
 const DEFAULT_EDITOR: &str = "jinx";
 
@@ -46,51 +54,50 @@ fn main() {
 }
 
 fn print_plugin_info() {
-    println!(
-        r#"{{"name":"HexEditor","version":"1.0.0","type":"viewer","description":"Launches an external hex editor (configurable via editor.hex_editor in config.toml, default: jinx)","icon":"H","extensions":["*"],"needs_terminal":true}}"#
-    );
+    let info = PluginInfoReply {
+        name: Some("HexEditor".to_string()),
+        version: Some("1.0.0".to_string()),
+        plugin_type: Some("viewer".to_string()),
+        description: "Opens a hex editor: the one set in editor.hex_editor in config.toml, or the built-in one"
+            .to_string(),
+        icon: Some("H".to_string()),
+        extensions: vec!["*".to_string()],
+        needs_terminal: true,
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&info));
 }
 
-/// Extract the hex editor command from the config object in the JSON,
-/// falling back to DEFAULT_EDITOR.
-fn get_editor(json: &str) -> String {
-    // The config is a nested object: "config":{"editor.hex_editor":"jinx",...}
-    // First, find the config object, then extract the key from within it.
-    if let Some(config_str) = extract_object(json, "config") {
-        if let Some(editor) = extract_string(&config_str, "editor.hex_editor") {
-            if !editor.is_empty() {
-                return editor;
-            }
-        }
+/// The hex editor command from the request's config, falling back to
+/// DEFAULT_EDITOR.
+fn get_editor(config: &BTreeMap<String, String>) -> String {
+    match config.get("editor.hex_editor") {
+        Some(editor) if !editor.is_empty() => editor.clone(),
+        _ => DEFAULT_EDITOR.to_string(),
     }
-    DEFAULT_EDITOR.to_string()
 }
 
-fn handle_command(json: &str) -> String {
-    let command = extract_string(json, "command").unwrap_or_default();
-
-    match command.as_str() {
-        "viewer_can_handle" => handle_can_handle(),
-        "viewer_render" => handle_render(json),
-        _ => format!(r#"{{"error":"Unknown command: {}"}}"#, escape_json(&command)),
+fn handle_command(line: &str) -> String {
+    match serde_json::from_str::<Request>(line).map(|request| request.command) {
+        Ok(Command::ViewerCanHandle { .. }) => handle_can_handle(),
+        Ok(Command::ViewerRender { path, config, .. }) => handle_render(&path, &config),
+        Ok(other) => encode_reply(&ErrorReply::new(format!("Unknown command: {}", other.name()))),
+        Err(e) => encode_reply(&ErrorReply::new(format!("Invalid request: {}", e))),
     }
 }
 
 fn handle_can_handle() -> String {
     // Accept any file, but with lowest priority so other viewers win
-    r#"{"can_handle":true,"priority":1}"#.to_string()
+    encode_reply(&CanHandleReply { can_handle: true, priority: 1 })
 }
 
-fn handle_render(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-    let editor = get_editor(json);
-
-    launch_editor(&editor, &path);
+fn handle_render(path: &str, config: &BTreeMap<String, String>) -> String {
+    let launched = launch_editor(&get_editor(config), path);
 
     // Return without "lines" so Bark's render() returns None and falls
     // through to the built-in viewer, which re-reads the (possibly modified)
     // file and auto-detects text vs binary mode.
-    r#"{"launched":true}"#.to_string()
+    encode_reply(&SuccessReply { success: launched })
 }
 
 #[cfg(unix)]
@@ -110,7 +117,7 @@ fn launch_editor(editor: &str, path: &str) -> bool {
         Err(_) => return false,
     };
 
-    Command::new(editor)
+    Process::new(editor)
         .arg(path)
         .stdin(tty_in)
         .stdout(tty_out)
@@ -139,7 +146,7 @@ fn launch_editor(editor: &str, path: &str) -> bool {
         Err(_) => return false,
     };
 
-    Command::new(editor)
+    Process::new(editor)
         .arg(path)
         .stdin(con_in)
         .stdout(con_out)
@@ -148,87 +155,3 @@ fn launch_editor(editor: &str, path: &str) -> bool {
         .map(|s| s.success())
         .unwrap_or(false)
 }
-
-// === JSON helpers ===
-
-fn extract_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if rest.starts_with('"') {
-        let rest = &rest[1..];
-        let mut result = String::new();
-        let mut chars = rest.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            match c {
-                '"' => break,
-                '\\' => {
-                    if let Some(&next) = chars.peek() {
-                        chars.next();
-                        match next {
-                            'n' => result.push('\n'),
-                            'r' => result.push('\r'),
-                            't' => result.push('\t'),
-                            '"' => result.push('"'),
-                            '\\' => result.push('\\'),
-                            _ => {
-                                result.push('\\');
-                                result.push(next);
-                            }
-                        }
-                    }
-                }
-                _ => result.push(c),
-            }
-        }
-
-        Some(result)
-    } else {
-        let end = rest.find(|c: char| c == ',' || c == '}' || c == ']')?;
-        Some(rest[..end].trim().to_string())
-    }
-}
-
-/// Extract a nested JSON object as a raw string (including braces).
-fn extract_object(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('{') {
-        return None;
-    }
-
-    let mut depth = 0;
-    let mut in_str = false;
-    let mut esc = false;
-    for (i, c) in rest.char_indices() {
-        if esc {
-            esc = false;
-            continue;
-        }
-        match c {
-            '\\' if in_str => esc = true,
-            '"' => in_str = !in_str,
-            '{' if !in_str => depth += 1,
-            '}' if !in_str => {
-                depth -= 1;
-                if depth == 0 {
-                    return Some(rest[..i + c.len_utf8()].to_string());
-                }
-            }
-            _ => {}
-        }
-    }
-    None
-}
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
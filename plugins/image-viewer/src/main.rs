@@ -7,6 +7,10 @@
 
 use std::io::{self, BufRead, Write};
 
+use bark_plugin_api::protocol::{
+    CanHandleReply, Command, ErrorReply, PluginInfoReply, Request, ViewerReply, encode_reply,
+};
+
 mod image_info;
 
 fn main() {
@@ -39,119 +43,53 @@ fn main() {
 }
 
 fn print_plugin_info() {
-    println!(
-        r#"{{"name":"Image Viewer","version":"1.0.0","type":"viewer","description":"Displays image file metadata, EXIF data, and format details","icon":"","extensions":["jpg","jpeg","jpe","jfif","png","bmp","dib","gif","webp","tiff","tif","ico","cur","avif","tga","vda","icb","vst","dds","hdr","rgbe","xyze","exr","ff","farbfeld","pnm","pbm","pgm","ppm","pam","qoi","svg","svgz","heif","heic","jp2","j2k","jxl","raw","cr2","nef","arw","dng","orf","rw2","pef","sr2","raf","pcx","sgi","rgb","rgba","bw"],"magic":""}}"#
-    );
+    let info = PluginInfoReply {
+        name: Some("Image Viewer".to_string()),
+        version: Some("1.0.0".to_string()),
+        plugin_type: Some("viewer".to_string()),
+        description: "Displays image file metadata, EXIF data, and format details".to_string(),
+        icon: None,
+        extensions: [
+            "jpg", "jpeg", "jpe", "jfif", "png", "bmp", "dib", "gif", "webp", "tiff", "tif", "ico", "cur",
+            "avif", "tga", "vda", "icb", "vst", "dds", "hdr", "rgbe", "xyze", "exr", "ff", "farbfeld",
+            "pnm", "pbm", "pgm", "ppm", "pam", "qoi", "svg", "svgz", "heif", "heic", "jp2", "j2k", "jxl",
+            "raw", "cr2", "nef", "arw", "dng", "orf", "rw2", "pef", "sr2", "raf", "pcx", "sgi", "rgb",
+            "rgba", "bw",
+        ]
+        .map(String::from)
+        .to_vec(),
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&info));
 }
 
-fn handle_command(json: &str) -> String {
-    let command = extract_string(json, "command").unwrap_or_default();
-
-    match command.as_str() {
-        "viewer_can_handle" => handle_can_handle(json),
-        "viewer_render" => handle_render(json),
-        _ => format!(r#"{{"error":"Unknown command: {}"}}"#, escape_json(&command)),
+fn handle_command(line: &str) -> String {
+    match serde_json::from_str::<Request>(line).map(|request| request.command) {
+        Ok(Command::ViewerCanHandle { path }) => handle_can_handle(&path),
+        Ok(Command::ViewerRender { path, scroll, height, .. }) => handle_render(&path, scroll, height),
+        Ok(other) => encode_reply(&ErrorReply::new(format!("Unknown command: {}", other.name()))),
+        Err(e) => encode_reply(&ErrorReply::new(format!("Invalid request: {}", e))),
     }
 }
 
-fn handle_can_handle(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-
-    match image_info::can_handle(&path) {
-        true => r#"{"can_handle":true,"priority":10}"#.to_string(),
-        false => r#"{"can_handle":false,"priority":0}"#.to_string(),
+fn handle_can_handle(path: &str) -> String {
+    match image_info::can_handle(path) {
+        true => encode_reply(&CanHandleReply { can_handle: true, priority: 10 }),
+        false => encode_reply(&CanHandleReply { can_handle: false, priority: 0 }),
     }
 }
 
-fn handle_render(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-    let scroll: usize = extract_int(json, "scroll").unwrap_or(0) as usize;
-    let height: usize = extract_int(json, "height").unwrap_or(24) as usize;
-
-    match image_info::parse_image(&path) {
+fn handle_render(path: &str, scroll: usize, height: usize) -> String {
+    match image_info::parse_image(path) {
         Ok(content) => {
             let lines: Vec<&str> = content.lines().collect();
             let total_lines = lines.len();
 
             // Apply scrolling and height limit
-            let visible: Vec<&str> = lines.into_iter().skip(scroll).take(height).collect();
-
-            // Build JSON array of lines
-            let lines_json: Vec<String> = visible
-                .iter()
-                .map(|l| format!("\"{}\"", escape_json(l)))
-                .collect();
+            let visible = lines.into_iter().skip(scroll).take(height).map(String::from).collect();
 
-            format!(
-                r#"{{"lines":[{}],"total_lines":{}}}"#,
-                lines_json.join(","),
-                total_lines
-            )
-        }
-        Err(e) => {
-            format!(r#"{{"error":"{}"}}"#, escape_json(&e))
+            encode_reply(&ViewerReply { lines: visible, total_lines: Some(total_lines), ..Default::default() })
         }
+        Err(e) => encode_reply(&ErrorReply::new(e)),
     }
 }
-
-// === JSON helpers ===
-
-fn extract_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if rest.starts_with('"') {
-        let rest = &rest[1..];
-        let mut result = String::new();
-        let mut chars = rest.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            match c {
-                '"' => break,
-                '\\' => {
-                    if let Some(&next) = chars.peek() {
-                        chars.next();
-                        match next {
-                            'n' => result.push('\n'),
-                            'r' => result.push('\r'),
-                            't' => result.push('\t'),
-                            '"' => result.push('"'),
-                            '\\' => result.push('\\'),
-                            _ => {
-                                result.push('\\');
-                                result.push(next);
-                            }
-                        }
-                    }
-                }
-                _ => result.push(c),
-            }
-        }
-
-        Some(result)
-    } else {
-        // Non-string value
-        let end = rest.find(|c: char| c == ',' || c == '}' || c == ']')?;
-        Some(rest[..end].trim().to_string())
-    }
-}
-
-fn extract_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    let end = rest
-        .find(|c: char| !c.is_ascii_digit() && c != '-')
-        .unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
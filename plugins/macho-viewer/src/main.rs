@@ -7,6 +7,10 @@
 
 use std::io::{self, BufRead, Write};
 
+use bark_plugin_api::protocol::{
+    CanHandleReply, Command, ErrorReply, PluginInfoReply, Request, ViewerReply, encode_reply,
+};
+
 mod macho;
 
 fn main() {
@@ -39,134 +43,60 @@ fn main() {
 }
 
 fn print_plugin_info() {
-    println!(
-        r#"{{"name":"Mach-O Viewer","version":"1.0.0","type":"viewer","description":"Displays Mach-O binary file headers","icon":"🔧","extensions":["dylib","so","a","o","bundle","kext"],"magic":"feedface"}}"#
-    );
+    let info = PluginInfoReply {
+        name: Some("Mach-O Viewer".to_string()),
+        version: Some("1.0.0".to_string()),
+        plugin_type: Some("viewer".to_string()),
+        description: "Displays Mach-O binary file headers".to_string(),
+        icon: Some("🔧".to_string()),
+        extensions: ["dylib", "so", "a", "o", "bundle", "kext"].map(String::from).to_vec(),
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&info));
 }
 
-fn handle_command(json: &str) -> String {
-    let command = extract_string(json, "command").unwrap_or_default();
-
-    match command.as_str() {
-        "viewer_can_handle" => handle_can_handle(json),
-        "viewer_render" => handle_render(json),
-        _ => format!(r#"{{"error":"Unknown command: {}"}}"#, escape_json(&command)),
+fn handle_command(line: &str) -> String {
+    match serde_json::from_str::<Request>(line).map(|request| request.command) {
+        Ok(Command::ViewerCanHandle { path }) => handle_can_handle(&path),
+        Ok(Command::ViewerRender { path, scroll, height, .. }) => handle_render(&path, scroll, height),
+        Ok(other) => encode_reply(&ErrorReply::new(format!("Unknown command: {}", other.name()))),
+        Err(e) => encode_reply(&ErrorReply::new(format!("Invalid request: {}", e))),
     }
 }
 
-fn handle_can_handle(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-
+fn handle_can_handle(path: &str) -> String {
     // Try to read magic bytes
-    let magic = match std::fs::File::open(&path) {
+    let magic = match std::fs::File::open(path) {
         Ok(mut f) => {
             use std::io::Read;
             let mut buf = [0u8; 4];
             if f.read_exact(&mut buf).is_ok() {
                 buf
             } else {
-                return r#"{"can_handle":false,"priority":0}"#.to_string();
+                return encode_reply(&CanHandleReply { can_handle: false, priority: 0 });
             }
         }
-        Err(_) => return r#"{"can_handle":false,"priority":0}"#.to_string(),
+        Err(_) => return encode_reply(&CanHandleReply { can_handle: false, priority: 0 }),
     };
 
     if macho::is_macho(&magic) {
-        r#"{"can_handle":true,"priority":10}"#.to_string()
+        encode_reply(&CanHandleReply { can_handle: true, priority: 10 })
     } else {
-        r#"{"can_handle":false,"priority":0}"#.to_string()
+        encode_reply(&CanHandleReply { can_handle: false, priority: 0 })
     }
 }
 
-fn handle_render(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-    let scroll: usize = extract_int(json, "scroll").unwrap_or(0) as usize;
-    let height: usize = extract_int(json, "height").unwrap_or(24) as usize;
-
-    match macho::parse_macho(&path) {
+fn handle_render(path: &str, scroll: usize, height: usize) -> String {
+    match macho::parse_macho(path) {
         Ok(content) => {
             let lines: Vec<&str> = content.lines().collect();
             let total_lines = lines.len();
 
             // Apply scrolling and height limit
-            let visible: Vec<&str> = lines.into_iter().skip(scroll).take(height).collect();
-
-            // Build JSON array of lines
-            let lines_json: Vec<String> = visible
-                .iter()
-                .map(|l| format!("\"{}\"", escape_json(l)))
-                .collect();
-
-            format!(
-                r#"{{"lines":[{}],"total_lines":{}}}"#,
-                lines_json.join(","),
-                total_lines
-            )
-        }
-        Err(e) => {
-            format!(r#"{{"error":"{}"}}"#, escape_json(&e))
-        }
-    }
-}
+            let visible = lines.into_iter().skip(scroll).take(height).map(String::from).collect();
 
-// === JSON helpers ===
-
-fn extract_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if rest.starts_with('"') {
-        let rest = &rest[1..];
-        let mut result = String::new();
-        let mut chars = rest.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            match c {
-                '"' => break,
-                '\\' => {
-                    if let Some(&next) = chars.peek() {
-                        chars.next();
-                        match next {
-                            'n' => result.push('\n'),
-                            'r' => result.push('\r'),
-                            't' => result.push('\t'),
-                            '"' => result.push('"'),
-                            '\\' => result.push('\\'),
-                            _ => {
-                                result.push('\\');
-                                result.push(next);
-                            }
-                        }
-                    }
-                }
-                _ => result.push(c),
-            }
+            encode_reply(&ViewerReply { lines: visible, total_lines: Some(total_lines), ..Default::default() })
         }
-
-        Some(result)
-    } else {
-        // Non-string value
-        let end = rest.find(|c: char| c == ',' || c == '}' || c == ']')?;
-        Some(rest[..end].trim().to_string())
+        Err(e) => encode_reply(&ErrorReply::new(e)),
     }
 }
-
-fn extract_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    let end = rest
-        .find(|c: char| !c.is_ascii_digit() && c != '-')
-        .unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
@@ -4,13 +4,22 @@
 
 use std::io::{self, BufRead, Write};
 
+use bark_plugin_api::protocol::{Command, OverlayReply, PluginInfoReply, Request, encode_reply};
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
     if args.iter().any(|a| a == "--plugin-info") {
-        println!(
-            r#"{{"name":"Notes","version":"1.0.0","type":"overlay","description":"Persistent scratchpad","width":62,"height":22}}"#
-        );
+        let info = PluginInfoReply {
+            name: Some("Notes".to_string()),
+            version: Some("1.0.0".to_string()),
+            plugin_type: Some("overlay".to_string()),
+            description: "Persistent scratchpad".to_string(),
+            width: Some(62),
+            height: Some(22),
+            ..Default::default()
+        };
+        println!("{}", encode_reply(&info));
         return;
     }
 
@@ -30,31 +39,23 @@ fn main() {
             continue;
         }
 
-        let command = extract_str(line, "command").unwrap_or_default();
-
-        match command.as_str() {
-            "init" => {
-                state.width = extract_int(line, "width").unwrap_or(62) as u16;
-                state.height = extract_int(line, "height").unwrap_or(22) as u16;
+        let reply = match serde_json::from_str::<Request>(line).map(|request| request.command) {
+            Ok(Command::Init { width, height }) => {
+                state.width = width;
+                state.height = height;
                 state.load();
-                let response = state.render();
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
+                state.render()
             }
-            "key" => {
-                let key = extract_str(line, "key").unwrap_or_default();
-                let mods = extract_str_array(line, "modifiers");
-                let response = state.handle_key(&key, &mods);
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
-            }
-            "close" => break,
+            Ok(Command::Key { key, modifiers }) => state.handle_key(&key, &modifiers),
+            Ok(Command::Close) => break,
             _ => {
-                let _ = writeln!(writer, r#"{{"close":true}}"#);
+                let _ = writeln!(writer, "{}", encode_reply(&OverlayReply { close: true, ..Default::default() }));
                 let _ = writer.flush();
                 break;
             }
-        }
+        };
+        let _ = writeln!(writer, "{}", encode_reply(&reply));
+        let _ = writer.flush();
     }
 }
 
@@ -131,7 +132,7 @@ impl NotesState {
         }
     }
 
-    fn handle_key(&mut self, key: &str, modifiers: &[String]) -> String {
+    fn handle_key(&mut self, key: &str, modifiers: &[String]) -> OverlayReply {
         let ctrl = modifiers.iter().any(|m| m == "ctrl");
 
         // Clear status on any key
@@ -140,13 +141,13 @@ impl NotesState {
         match key {
             "Escape" => {
                 self.save();
-                return r#"{"close":true}"#.to_string();
+                return OverlayReply { close: true, ..Default::default() };
             }
             "Enter" if ctrl => {
                 if self.dirty {
                     self.save();
                 }
-                return r#"{"close":true}"#.to_string();
+                return OverlayReply { close: true, ..Default::default() };
             }
             "s" | "S" if ctrl => {
                 self.save();
@@ -253,7 +254,7 @@ impl NotesState {
         self.render()
     }
 
-    fn render(&self) -> String {
+    fn render(&self) -> OverlayReply {
         let inner_w = self.width.saturating_sub(2) as usize;
         let visible = self.visible_rows();
         let mut out: Vec<String> = Vec::new();
@@ -304,14 +305,13 @@ impl NotesState {
 
         let title = if self.dirty { " Notes [*] " } else { " Notes " };
 
-        let lines_json: Vec<String> = out.iter()
-            .map(|l| format!("\"{}\"", escape_json(l)))
-            .collect();
-
-        format!(
-            r#"{{"title":"{}","width":{},"height":{},"close":false,"lines":[{}]}}"#,
-            escape_json(title), self.width, self.height, lines_json.join(",")
-        )
+        OverlayReply {
+            lines: out,
+            title: title.to_string(),
+            width: Some(self.width),
+            height: Some(self.height),
+            ..Default::default()
+        }
     }
 }
 
@@ -319,76 +319,3 @@ fn truncate(s: &str, max: usize) -> String {
     let chars: Vec<char> = s.chars().collect();
     if chars.len() <= max { s.to_string() } else { chars[..max].iter().collect() }
 }
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn extract_str(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    if !rest.starts_with('"') { return None; }
-    let rest = &rest[1..];
-    let mut result = String::new();
-    let mut chars = rest.chars().peekable();
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => break,
-            '\\' => { if let Some(&n) = chars.peek() { chars.next(); match n {
-                'n' => result.push('\n'), '"' => result.push('"'),
-                '\\' => result.push('\\'), 't' => result.push('\t'),
-                _ => { result.push('\\'); result.push(n); }
-            }}}
-            _ => result.push(c),
-        }
-    }
-    Some(result)
-}
-
-fn extract_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn extract_str_array(json: &str, key: &str) -> Vec<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = match json.find(&pattern) {
-        Some(s) => s + pattern.len(),
-        None => return Vec::new(),
-    };
-    let rest = json[start..].trim_start();
-    if !rest.starts_with('[') { return Vec::new(); }
-    let mut depth = 0;
-    let mut end = 0;
-    for (i, c) in rest.char_indices() {
-        match c {
-            '[' => depth += 1,
-            ']' => { depth -= 1; if depth == 0 { end = i; break; } }
-            _ => {}
-        }
-    }
-    if end == 0 { return Vec::new(); }
-    let inner = &rest[1..end];
-    let mut result = Vec::new();
-    let mut current = String::new();
-    let mut in_string = false;
-    let mut escape = false;
-    for c in inner.chars() {
-        if escape { current.push(c); escape = false; continue; }
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => { if in_string { result.push(current.clone()); current.clear(); } in_string = !in_string; }
-            _ if in_string => current.push(c),
-            _ => {}
-        }
-    }
-    result
-}
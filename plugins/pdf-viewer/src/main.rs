@@ -7,6 +7,10 @@
 
 use std::io::{self, BufRead, Write};
 
+use bark_plugin_api::protocol::{
+    CanHandleReply, Command, ErrorReply, PluginInfoReply, Request, ViewerReply, encode_reply,
+};
+
 mod pdf_info;
 
 /// Cached render output for a single file.  Metadata lines are populated on
@@ -53,50 +57,50 @@ fn main() {
 }
 
 fn print_plugin_info() {
-    println!(
-        r#"{{"name":"PDF Viewer","version":"1.0.0","type":"viewer","description":"Displays PDF document metadata, structure, and text content","icon":"","extensions":["pdf"],"magic":"255044462d"}}"#
-    );
+    let info = PluginInfoReply {
+        name: Some("PDF Viewer".to_string()),
+        version: Some("1.0.0".to_string()),
+        plugin_type: Some("viewer".to_string()),
+        description: "Displays PDF document metadata, structure, and text content".to_string(),
+        icon: None,
+        extensions: ["pdf"].map(String::from).to_vec(),
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&info));
 }
 
-fn handle_command(json: &str, cache: &mut Option<RenderCache>) -> String {
-    let command = extract_string(json, "command").unwrap_or_default();
-
-    match command.as_str() {
-        "viewer_can_handle" => handle_can_handle(json),
-        "viewer_render" => handle_render(json, cache),
-        _ => format!(r#"{{"error":"Unknown command: {}"}}"#, escape_json(&command)),
+fn handle_command(line: &str, cache: &mut Option<RenderCache>) -> String {
+    match serde_json::from_str::<Request>(line).map(|request| request.command) {
+        Ok(Command::ViewerCanHandle { path }) => handle_can_handle(&path),
+        Ok(Command::ViewerRender { path, scroll, height, .. }) => handle_render(path, scroll, height, cache),
+        Ok(other) => encode_reply(&ErrorReply::new(format!("Unknown command: {}", other.name()))),
+        Err(e) => encode_reply(&ErrorReply::new(format!("Invalid request: {}", e))),
     }
 }
 
-fn handle_can_handle(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-
+fn handle_can_handle(path: &str) -> String {
     // Try to read magic bytes: %PDF- (25 50 44 46 2d)
-    let magic = match std::fs::File::open(&path) {
+    let magic = match std::fs::File::open(path) {
         Ok(mut f) => {
             use std::io::Read;
             let mut buf = [0u8; 5];
             if f.read_exact(&mut buf).is_ok() {
                 buf
             } else {
-                return r#"{"can_handle":false,"priority":0}"#.to_string();
+                return encode_reply(&CanHandleReply { can_handle: false, priority: 0 });
             }
         }
-        Err(_) => return r#"{"can_handle":false,"priority":0}"#.to_string(),
+        Err(_) => return encode_reply(&CanHandleReply { can_handle: false, priority: 0 }),
     };
 
     if &magic == b"%PDF-" {
-        r#"{"can_handle":true,"priority":10}"#.to_string()
+        encode_reply(&CanHandleReply { can_handle: true, priority: 10 })
     } else {
-        r#"{"can_handle":false,"priority":0}"#.to_string()
+        encode_reply(&CanHandleReply { can_handle: false, priority: 0 })
     }
 }
 
-fn handle_render(json: &str, cache: &mut Option<RenderCache>) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-    let scroll: usize = extract_int(json, "scroll").unwrap_or(0) as usize;
-    let height: usize = extract_int(json, "height").unwrap_or(24) as usize;
-
+fn handle_render(path: String, scroll: usize, height: usize, cache: &mut Option<RenderCache>) -> String {
     // Invalidate cache if viewing a different file.
     let needs_invalidate = match cache {
         Some(c) => c.path != path,
@@ -113,14 +117,14 @@ fn handle_render(json: &str, cache: &mut Option<RenderCache>) -> String {
                 let lines: Vec<String> = metadata.lines().map(|l| l.to_string()).collect();
                 let count = lines.len();
                 *cache = Some(RenderCache {
-                    path: path.clone(),
+                    path,
                     lines,
                     metadata_lines: count,
                     text_extracted: false,
                 });
             }
             Err(e) => {
-                return format!(r#"{{"error":"{}"}}"#, escape_json(&e));
+                return encode_reply(&ErrorReply::new(e));
             }
         }
     }
@@ -139,82 +143,7 @@ fn handle_render(json: &str, cache: &mut Option<RenderCache>) -> String {
     }
 
     let total_lines = c.lines.len();
-    let visible: Vec<&str> = c.lines.iter()
-        .skip(scroll)
-        .take(height)
-        .map(|s| s.as_str())
-        .collect();
-
-    let lines_json: Vec<String> = visible
-        .iter()
-        .map(|l| format!("\"{}\"", escape_json(l)))
-        .collect();
-
-    format!(
-        r#"{{"lines":[{}],"total_lines":{}}}"#,
-        lines_json.join(","),
-        total_lines
-    )
-}
-
-// === JSON helpers ===
-
-fn extract_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if rest.starts_with('"') {
-        let rest = &rest[1..];
-        let mut result = String::new();
-        let mut chars = rest.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            match c {
-                '"' => break,
-                '\\' => {
-                    if let Some(&next) = chars.peek() {
-                        chars.next();
-                        match next {
-                            'n' => result.push('\n'),
-                            'r' => result.push('\r'),
-                            't' => result.push('\t'),
-                            '"' => result.push('"'),
-                            '\\' => result.push('\\'),
-                            _ => {
-                                result.push('\\');
-                                result.push(next);
-                            }
-                        }
-                    }
-                }
-                _ => result.push(c),
-            }
-        }
-
-        Some(result)
-    } else {
-        // Non-string value
-        let end = rest.find(|c: char| c == ',' || c == '}' || c == ']')?;
-        Some(rest[..end].trim().to_string())
-    }
-}
-
-fn extract_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    let end = rest
-        .find(|c: char| !c.is_ascii_digit() && c != '-')
-        .unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
+    let visible = c.lines.iter().skip(scroll).take(height).cloned().collect();
 
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
+    encode_reply(&ViewerReply { lines: visible, total_lines: Some(total_lines), ..Default::default() })
 }
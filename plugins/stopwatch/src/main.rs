@@ -5,13 +5,22 @@
 use std::io::{self, BufRead, Write};
 use std::time::Instant;
 
+use bark_plugin_api::protocol::{Command, OverlayReply, PluginInfoReply, Request, encode_reply};
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
     if args.iter().any(|a| a == "--plugin-info") {
-        println!(
-            r#"{{"name":"Stopwatch","version":"1.0.0","type":"overlay","description":"Timer with lap support","width":42,"height":18}}"#
-        );
+        let info = PluginInfoReply {
+            name: Some("Stopwatch".to_string()),
+            version: Some("1.0.0".to_string()),
+            plugin_type: Some("overlay".to_string()),
+            description: "Timer with lap support".to_string(),
+            width: Some(42),
+            height: Some(18),
+            ..Default::default()
+        };
+        println!("{}", encode_reply(&info));
         return;
     }
 
@@ -31,36 +40,24 @@ fn main() {
             continue;
         }
 
-        let command = extract_str(line, "command").unwrap_or_default();
-
-        match command.as_str() {
-            "init" => {
-                state.width = extract_int(line, "width").unwrap_or(42) as u16;
-                state.height = extract_int(line, "height").unwrap_or(18) as u16;
-                let response = state.render();
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
-            }
-            "key" => {
-                let key = extract_str(line, "key").unwrap_or_default();
-                let mods = extract_str_array(line, "modifiers");
-                let response = state.handle_key(&key, &mods);
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
-            }
-            "tick" => {
-                // Periodic update — just re-render with current time
-                let response = state.render();
-                let _ = writeln!(writer, "{}", response);
-                let _ = writer.flush();
+        let reply = match serde_json::from_str::<Request>(line).map(|request| request.command) {
+            Ok(Command::Init { width, height }) => {
+                state.width = width;
+                state.height = height;
+                state.render()
             }
-            "close" => break,
+            Ok(Command::Key { key, modifiers }) => state.handle_key(&key, &modifiers),
+            // Periodic update — just re-render with current time
+            Ok(Command::Tick) => state.render(),
+            Ok(Command::Close) => break,
             _ => {
-                let _ = writeln!(writer, r#"{{"close":true}}"#);
+                let _ = writeln!(writer, "{}", encode_reply(&OverlayReply { close: true, ..Default::default() }));
                 let _ = writer.flush();
                 break;
             }
-        }
+        };
+        let _ = writeln!(writer, "{}", encode_reply(&reply));
+        let _ = writer.flush();
     }
 }
 
@@ -97,12 +94,12 @@ impl StopwatchState {
         self.accumulated + current
     }
 
-    fn handle_key(&mut self, key: &str, modifiers: &[String]) -> String {
+    fn handle_key(&mut self, key: &str, modifiers: &[String]) -> OverlayReply {
         let ctrl = modifiers.iter().any(|m| m == "ctrl");
 
         match key {
-            "Escape" => return r#"{"close":true}"#.to_string(),
-            "Enter" if ctrl => return r#"{"close":true}"#.to_string(),
+            "Escape" => return OverlayReply { close: true, ..Default::default() },
+            "Enter" if ctrl => return OverlayReply { close: true, ..Default::default() },
 
             // Space or Enter = Start/Stop
             " " | "Enter" => {
@@ -139,7 +136,7 @@ impl StopwatchState {
         self.render()
     }
 
-    fn render(&self) -> String {
+    fn render(&self) -> OverlayReply {
         let inner_w = self.width.saturating_sub(2) as usize;
         let elapsed = self.elapsed();
         let mut lines: Vec<String> = Vec::new();
@@ -192,17 +189,15 @@ impl StopwatchState {
 
         let title = format!(" Stopwatch [{}] ", if self.running { "Running" } else { "Stopped" });
 
-        let lines_json: Vec<String> = lines.iter()
-            .map(|l| format!("\"{}\"", escape_json(l)))
-            .collect();
-
-        // Request ticks only when running (live updates)
-        let tick = self.running;
-
-        format!(
-            r#"{{"title":"{}","width":{},"height":{},"close":false,"tick":{},"lines":[{}]}}"#,
-            escape_json(&title), self.width, self.height, tick, lines_json.join(",")
-        )
+        OverlayReply {
+            lines,
+            title,
+            width: Some(self.width),
+            height: Some(self.height),
+            close: false,
+            // Request ticks only when running (live updates)
+            tick: self.running,
+        }
     }
 }
 
@@ -231,76 +226,3 @@ fn center(s: &str, width: usize) -> String {
 fn truncate(s: &str, max: usize) -> String {
     if s.len() <= max { s.to_string() } else { s[..max].to_string() }
 }
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn extract_str(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    if !rest.starts_with('"') { return None; }
-    let rest = &rest[1..];
-    let mut result = String::new();
-    let mut chars = rest.chars().peekable();
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => break,
-            '\\' => { if let Some(&n) = chars.peek() { chars.next(); match n {
-                'n' => result.push('\n'), '"' => result.push('"'),
-                '\\' => result.push('\\'), 't' => result.push('\t'),
-                _ => { result.push('\\'); result.push(n); }
-            }}}
-            _ => result.push(c),
-        }
-    }
-    Some(result)
-}
-
-fn extract_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn extract_str_array(json: &str, key: &str) -> Vec<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = match json.find(&pattern) {
-        Some(s) => s + pattern.len(),
-        None => return Vec::new(),
-    };
-    let rest = json[start..].trim_start();
-    if !rest.starts_with('[') { return Vec::new(); }
-    let mut depth = 0;
-    let mut end = 0;
-    for (i, c) in rest.char_indices() {
-        match c {
-            '[' => depth += 1,
-            ']' => { depth -= 1; if depth == 0 { end = i; break; } }
-            _ => {}
-        }
-    }
-    if end == 0 { return Vec::new(); }
-    let inner = &rest[1..end];
-    let mut result = Vec::new();
-    let mut current = String::new();
-    let mut in_string = false;
-    let mut escape = false;
-    for c in inner.chars() {
-        if escape { current.push(c); escape = false; continue; }
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => { if in_string { result.push(current.clone()); current.clear(); } in_string = !in_string; }
-            _ if in_string => current.push(c),
-            _ => {}
-        }
-    }
-    result
-}
@@ -0,0 +1,265 @@
+//! Supported formats: reading each into lines, or into a syntax error
+
+use std::path::Path;
+
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::tree::{self, quote, Entry, Node, Outline};
+use crate::{json, xml};
+
+/// Lines of source shown before and after a syntax error
+const ERROR_CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    Xml,
+}
+
+impl Format {
+    /// Format of a file, by its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "json" | "geojson" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "xml" | "xsd" | "xsl" | "xslt" | "svg" => Some(Format::Xml),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+            Format::Xml => "XML",
+        }
+    }
+
+    /// Parse `text` and lay it out as a tree
+    pub fn outline(self, text: &str) -> Result<Outline, SyntaxError> {
+        match self {
+            Format::Json => json::parse(text).map(|root| tree::json(&root)),
+            Format::Yaml => {
+                let documents = YamlLoader::load_from_str(text).map_err(|e| SyntaxError {
+                    line: e.marker().line(),
+                    column: e.marker().col() + 1,
+                    message: e.info().to_string(),
+                })?;
+                let documents: Vec<Node> = documents.iter().map(yaml_node).collect();
+                Ok(tree::yaml(&documents))
+            }
+            Format::Toml => {
+                let document = text.parse::<toml_edit::DocumentMut>().map_err(|e| {
+                    let offset = e.span().map_or(0, |span| span.start);
+                    // The message repeats the position and source line; the
+                    // first line of it is the part worth showing
+                    let message = e.message().lines().next().unwrap_or_default().to_string();
+                    SyntaxError::at(text, offset, &message)
+                })?;
+                Ok(tree::toml(&toml_table(document.as_table())))
+            }
+            Format::Xml => xml::outline(text),
+        }
+    }
+}
+
+/// Where and why a document failed to parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    /// Error at byte `offset` of `text`
+    pub fn at(text: &str, offset: usize, message: &str) -> Self {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.to_string(),
+        }
+    }
+
+    /// The error followed by the source lines around it, with a caret
+    /// under the column
+    pub fn lines(&self, format: Format, text: &str) -> Vec<String> {
+        let mut out = vec![
+            format!("Invalid {}: line {}, column {}: {}", format.name(), self.line, self.column, self.message),
+            String::new(),
+        ];
+        let first = self.line.saturating_sub(ERROR_CONTEXT).max(1);
+        let last = self.line + ERROR_CONTEXT;
+        let width = last.to_string().len();
+        for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)).skip(first - 1) {
+            if number > last {
+                break;
+            }
+            // One cell per character keeps the caret in place
+            let line: String = line.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+            out.push(format!("{:>width$} │ {}", number, line));
+            if number == self.line {
+                out.push(format!("{:>width$} │ {}^", "", " ".repeat(self.column - 1)));
+            }
+        }
+        // An error at the very end may be on a line `lines()` doesn't yield
+        if text.lines().count() < self.line {
+            out.push(format!("{:>width$} │ {}^", self.line, " ".repeat(self.column - 1)));
+        }
+        out
+    }
+}
+
+// ============================================================================
+// YAML
+// ============================================================================
+
+fn yaml_node(yaml: &Yaml) -> Node {
+    match yaml {
+        Yaml::Array(items) => Node::List(items.iter().map(yaml_node).collect()),
+        Yaml::Hash(hash) => Node::Map(
+            hash.iter()
+                .map(|(key, value)| {
+                    let (key, label) = match key {
+                        Yaml::String(key) => (key.clone(), yaml_string(key)),
+                        key => {
+                            let label = yaml_scalar(key);
+                            (label.clone(), label)
+                        }
+                    };
+                    Entry { key, label, value: yaml_node(value) }
+                })
+                .collect(),
+        ),
+        scalar => Node::Scalar(yaml_scalar(scalar)),
+    }
+}
+
+fn yaml_scalar(yaml: &Yaml) -> String {
+    match yaml {
+        Yaml::Real(text) => text.clone(),
+        Yaml::Integer(value) => value.to_string(),
+        Yaml::String(text) => yaml_string(text),
+        Yaml::Boolean(value) => value.to_string(),
+        Yaml::Null => "null".to_string(),
+        // Complex keys and unresolved aliases
+        Yaml::Array(_) | Yaml::Hash(_) => "?".to_string(),
+        Yaml::Alias(_) | Yaml::BadValue => "*".to_string(),
+    }
+}
+
+/// A YAML string, left plain unless it would read as something else
+fn yaml_string(text: &str) -> String {
+    let special = text.is_empty()
+        || text.trim() != text
+        || text.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        || text.contains(": ")
+        || text.contains(" #")
+        || text.ends_with(':')
+        || text.chars().any(char::is_control)
+        || matches!(
+            text.to_lowercase().as_str(),
+            "null" | "~" | "true" | "false" | "yes" | "no" | "on" | "off" | ".inf" | "-.inf" | ".nan"
+        )
+        || text.parse::<f64>().is_ok()
+        || text.starts_with("0x")
+        || text.starts_with("0o");
+    if special { quote(text) } else { text.to_string() }
+}
+
+// ============================================================================
+// TOML
+// ============================================================================
+
+fn toml_table(table: &toml_edit::Table) -> Vec<Entry> {
+    table
+        .iter()
+        .map(|(key, item)| Entry { key: key.to_string(), label: toml_key(key), value: toml_item(item) })
+        .collect()
+}
+
+fn toml_item(item: &toml_edit::Item) -> Node {
+    match item {
+        toml_edit::Item::Table(table) => Node::Map(toml_table(table)),
+        toml_edit::Item::ArrayOfTables(tables) => Node::List(tables.iter().map(|t| Node::Map(toml_table(t))).collect()),
+        toml_edit::Item::Value(value) => toml_value(value),
+        toml_edit::Item::None => Node::Scalar(String::new()),
+    }
+}
+
+fn toml_value(value: &toml_edit::Value) -> Node {
+    match value {
+        toml_edit::Value::Array(array) => Node::List(array.iter().map(toml_value).collect()),
+        toml_edit::Value::InlineTable(table) => Node::Map(
+            table
+                .iter()
+                .map(|(key, value)| Entry { key: key.to_string(), label: toml_key(key), value: toml_value(value) })
+                .collect(),
+        ),
+        // Multi-line strings are shown on one line, with escapes
+        toml_edit::Value::String(text) => Node::Scalar(quote(text.value())),
+        other => {
+            let mut other = other.clone();
+            other.decor_mut().clear();
+            Node::Scalar(other.to_string())
+        }
+    }
+}
+
+/// A TOML key, quoted unless it is a bare key
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare { key.to_string() } else { quote(key) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        let out = Format::Yaml.outline("name: bark\nports:\n  - 80\n  - '443'\nempty: ~\n").unwrap();
+        assert_eq!(out.lines, ["name: bark", "ports:", "  - 80", "  - \"443\"", "empty: null"]);
+        assert_eq!(out.folds, [(1, 3)]);
+
+        let out = Format::Yaml.outline("a: 1\n---\n- x\n").unwrap();
+        assert_eq!(out.lines, ["---", "a: 1", "---", "- x"]);
+        assert_eq!(out.paths[3], "#2 $[0]");
+
+        let toml = "title = \"x\" # comment\n[server]\nport = 8080\ntls = { cert = '''a\nb''' }\n[[user]]\nname = \"a\"\n";
+        let out = Format::Toml.outline(toml).unwrap();
+        assert_eq!(
+            out.lines,
+            ["title = \"x\"", "[server]", "  port = 8080", "  [server.tls]", "    cert = \"a\\nb\"", "[[user]]", "  name = \"a\""]
+        );
+        assert_eq!(out.folds, [(3, 4), (1, 4), (5, 6)]);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = Format::Yaml.outline("a: [1, 2\nb: 3\n").unwrap_err();
+        assert!(error.line >= 2, "{error:?}");
+
+        let error = Format::Toml.outline("a = 1\nb = \n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+
+        let text = "{\n\t\"a\": 1\n\t\"b\": 2\n}";
+        let error = Format::Json.outline(text).unwrap_err();
+        let lines = error.lines(Format::Json, text);
+        assert_eq!(lines[0], "Invalid JSON: line 3, column 2: expected ',' or '}'");
+        assert_eq!(&lines[2..6], ["1 │ {", "2 │  \"a\": 1", "3 │  \"b\": 2", "  │  ^"]);
+    }
+}
@@ -0,0 +1,245 @@
+//! JSON reader that keeps key order and reports where parsing failed
+
+use crate::formats::SyntaxError;
+use crate::tree::{quote, Entry, Node};
+
+/// Deepest nesting read before giving up, so the stack can't run out
+const MAX_DEPTH: usize = 512;
+
+/// Parse a JSON document
+pub fn parse(text: &str) -> Result<Node, SyntaxError> {
+    let mut parser = Parser { text, bytes: text.as_bytes(), pos: 0 };
+    if text.starts_with('\u{FEFF}') {
+        parser.pos = 3;
+    }
+    let root = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("unexpected text after the document"));
+    }
+    Ok(root)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError::at(self.text, self.pos, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.bytes.get(self.pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace and step over `byte` if it comes next
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.bytes.get(self.pos) == Some(&byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Node, SyntaxError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            None => Err(self.error("unexpected end of file")),
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => Ok(Node::Scalar(quote(&self.string()?))),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => {
+                for word in ["true", "false", "null"] {
+                    if self.text[self.pos..].starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(Node::Scalar(word.to_string()));
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Node, SyntaxError> {
+        self.pos += 1;
+        let mut entries = Vec::new();
+        if self.eat(b'}') {
+            return Ok(Node::Map(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.bytes.get(self.pos) != Some(&b'"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.string()?;
+            if !self.eat(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            let value = self.value(depth + 1)?;
+            entries.push(Entry { label: quote(&key), key, value });
+            if self.eat(b'}') {
+                return Ok(Node::Map(entries));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or '}'"));
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Node, SyntaxError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        if self.eat(b']') {
+            return Ok(Node::List(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            if self.eat(b']') {
+                return Ok(Node::List(items));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or ']'"));
+            }
+        }
+    }
+
+    /// Read the string starting at the opening quote
+    fn string(&mut self) -> Result<String, SyntaxError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let Some(c) = rest.chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    self.pos += 1;
+                    out.push(self.escape()?);
+                }
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => {
+                    out.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    /// Read the escape after a backslash
+    fn escape(&mut self) -> Result<char, SyntaxError> {
+        let c = match self.bytes.get(self.pos) {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                let high = self.hex4()?;
+                // Characters outside the BMP come as a surrogate pair
+                if (0xD800..0xDC00).contains(&high) && self.text[self.pos..].starts_with("\\u") {
+                    self.pos += 2;
+                    let low = self.hex4()?;
+                    let code = (0xDC00..0xE000).contains(&low).then(|| 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00));
+                    return Ok(code.and_then(char::from_u32).unwrap_or('\u{FFFD}'));
+                }
+                return Ok(char::from_u32(high).unwrap_or('\u{FFFD}'));
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn hex4(&mut self) -> Result<u32, SyntaxError> {
+        let digits = self.text.get(self.pos..self.pos + 4).filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()));
+        let value = digits.and_then(|d| u32::from_str_radix(d, 16).ok()).ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Node, SyntaxError> {
+        let start = self.pos;
+        let digits = |parser: &mut Self| {
+            let from = parser.pos;
+            while parser.bytes.get(parser.pos).is_some_and(u8::is_ascii_digit) {
+                parser.pos += 1;
+            }
+            parser.pos > from
+        };
+
+        if self.bytes[self.pos] == b'-' {
+            self.pos += 1;
+        }
+        let leading_zero = self.bytes.get(self.pos) == Some(&b'0');
+        if !digits(self) {
+            return Err(self.error("expected a digit"));
+        }
+        if leading_zero && self.pos - start > 1 + usize::from(self.bytes[start] == b'-') {
+            self.pos = start;
+            return Err(self.error("numbers can't have leading zeros"));
+        }
+        if self.bytes.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            if !digits(self) {
+                return Err(self.error("expected a digit after '.'"));
+            }
+        }
+        if matches!(self.bytes.get(self.pos), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.bytes.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+        }
+        Ok(Node::Scalar(self.text[start..self.pos].to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let node = parse("\u{FEFF}{\"b\": [1, -2.5e3, true, null], \"a\": \"x\\u00e9\\ud83d\\ude00\\n\"}").unwrap();
+        let Node::Map(entries) = node else { panic!("not a map") };
+        assert_eq!(entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>(), ["b", "a"]);
+        assert_eq!(entries[0].value, Node::List(["1", "-2.5e3", "true", "null"].map(|s| Node::Scalar(s.into())).to_vec()));
+        assert_eq!(entries[1].value, Node::Scalar("\"xé😀\\n\"".into()));
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let error = |text: &str| {
+            let e = parse(text).unwrap_err();
+            (e.line, e.column, e.message)
+        };
+        assert_eq!(error("{\n  \"a\": 1\n  \"b\": 2\n}"), (3, 3, "expected ',' or '}'".into()));
+        assert_eq!(error("[1, 2,]"), (1, 7, "expected a value".into()));
+        assert_eq!(error("{\"é\": 01}"), (1, 7, "numbers can't have leading zeros".into()));
+        assert_eq!(error("\"abc"), (1, 5, "unterminated string".into()));
+        assert_eq!(error("{} x"), (1, 4, "unexpected text after the document".into()));
+        assert_eq!(error(&"[".repeat(600)).2, "nested too deeply");
+    }
+}
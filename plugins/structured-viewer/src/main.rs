@@ -0,0 +1,122 @@
+//! Structured data viewer plugin executable
+//!
+//! Pretty-prints JSON, YAML, TOML and XML files as a tree. Along with the
+//! lines, the `viewer_render` reply carries the blocks Bark can fold and
+//! the path of every line, which Bark shows as a breadcrumb. A file that
+//! doesn't parse is shown as the error with the source around it.
+//!
+//! Protocol:
+//! - `--plugin-info`: Print plugin metadata as JSON
+//! - stdin/stdout: JSON commands and responses
+
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use bark_plugin_api::protocol::{
+    CanHandleReply, Command, ErrorReply, PluginInfoReply, Request, ViewerFold, ViewerReply, encode_reply,
+};
+
+mod formats;
+mod json;
+mod tree;
+mod xml;
+
+use formats::Format;
+
+/// Larger files are left to the built-in viewer
+const MAX_FILE_SIZE: u64 = 32 * 1024 * 1024;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Handle --plugin-info
+    if args.len() > 1 && args[1] == "--plugin-info" {
+        print_plugin_info();
+        return;
+    }
+
+    // Handle JSON commands on stdin
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = handle_command(&line);
+        writeln!(stdout, "{}", response).ok();
+        stdout.flush().ok();
+    }
+}
+
+fn print_plugin_info() {
+    let info = PluginInfoReply {
+        name: Some("Structured Viewer".to_string()),
+        version: Some("1.0.0".to_string()),
+        plugin_type: Some("viewer".to_string()),
+        description: "Collapsible tree view of JSON, YAML, TOML and XML with syntax checking".to_string(),
+        icon: Some("🌲".to_string()),
+        extensions: ["json", "geojson", "yaml", "yml", "toml", "xml", "xsd", "xsl", "xslt", "svg"]
+            .map(String::from)
+            .to_vec(),
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&info));
+}
+
+fn handle_command(line: &str) -> String {
+    match serde_json::from_str::<Request>(line).map(|request| request.command) {
+        Ok(Command::ViewerCanHandle { path }) => {
+            let can_handle = Format::from_path(Path::new(&path)).is_some();
+            encode_reply(&CanHandleReply { can_handle, priority: if can_handle { 5 } else { 0 } })
+        }
+        Ok(Command::ViewerRender { path, scroll, height, .. }) => match render(Path::new(&path), scroll, height) {
+            Ok(reply) => encode_reply(&reply),
+            Err(e) => encode_reply(&ErrorReply::new(e)),
+        },
+        Ok(other) => encode_reply(&ErrorReply::new(format!("Unknown command: {}", other.name()))),
+        Err(e) => encode_reply(&ErrorReply::new(format!("Invalid request: {}", e))),
+    }
+}
+
+/// Lines `scroll..scroll + height` of the file's tree, or of its syntax
+/// error. Errors are for files that can't be shown at all.
+fn render(path: &Path, scroll: usize, height: usize) -> Result<ViewerReply, String> {
+    let format = Format::from_path(path).ok_or("Not a JSON, YAML, TOML or XML file")?;
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(format!("File too large ({} bytes)", size));
+    }
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let text = String::from_utf8(bytes).map_err(|_| "File is not UTF-8 text".to_string())?;
+
+    let outline = match format.outline(&text) {
+        Ok(outline) => outline,
+        Err(error) => tree::Outline { lines: error.lines(format, &text), ..Default::default() },
+    };
+
+    // Only what falls inside the requested window is sent, renumbered
+    let total_lines = outline.lines.len();
+    let end = scroll.saturating_add(height).min(total_lines);
+    let window = scroll.min(end)..end;
+    let folds = outline
+        .folds
+        .iter()
+        .filter(|&&(start, last)| window.contains(&start) && window.contains(&last))
+        .map(|&(start, last)| ViewerFold { start: start - window.start, end: last - window.start })
+        .collect();
+    let paths = outline.paths.get(window.clone()).map(<[String]>::to_vec).unwrap_or_default();
+    Ok(ViewerReply {
+        lines: outline.lines[window].to_vec(),
+        total_lines: Some(total_lines),
+        folds,
+        paths,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,286 @@
+//! Parsed data and the lines it is laid out into
+//!
+//! JSON, YAML and TOML are all read into a [`Node`] tree and written back
+//! out in their own syntax. Every container becomes a block of lines the
+//! viewer can fold, and every line gets the path of the value it shows.
+
+/// A parsed value. Scalars and keys keep the text they are shown with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Scalar(String),
+    List(Vec<Node>),
+    Map(Vec<Entry>),
+}
+
+/// One key of a map
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// The key itself, for paths
+    pub key: String,
+    /// The key as written in the format (quoted where needed)
+    pub label: String,
+    pub value: Node,
+}
+
+impl Node {
+    fn is_empty_container(&self) -> Option<&'static str> {
+        match self {
+            Node::List(items) if items.is_empty() => Some("[]"),
+            Node::Map(entries) if entries.is_empty() => Some("{}"),
+            _ => None,
+        }
+    }
+}
+
+/// Lines of a view with the blocks that fold and the path of each line
+#[derive(Debug, Default)]
+pub struct Outline {
+    pub lines: Vec<String>,
+    /// First and last line of each block
+    pub folds: Vec<(usize, usize)>,
+    pub paths: Vec<String>,
+}
+
+impl Outline {
+    /// Add a line indented to `depth`; returns its index
+    pub fn push(&mut self, depth: usize, text: &str, path: &str) -> usize {
+        self.lines.push(format!("{}{}", "  ".repeat(depth), text));
+        self.paths.push(path.to_string());
+        self.lines.len() - 1
+    }
+
+    /// Make the lines added since `start` fold into it
+    pub fn fold(&mut self, start: usize) {
+        let end = self.lines.len().saturating_sub(1);
+        if end > start {
+            self.folds.push((start, end));
+        }
+    }
+}
+
+/// Path of `key` in the map at `parent`: `$.name`, or `$["odd key"]`
+pub fn key_path(parent: &str, key: &str) -> String {
+    let plain = key.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if plain { format!("{}.{}", parent, key) } else { format!("{}[{}]", parent, quote(key)) }
+}
+
+/// Path of item `index` of the list at `parent`
+pub fn index_path(parent: &str, index: usize) -> String {
+    format!("{}[{}]", parent, index)
+}
+
+/// `text` as a double-quoted string with JSON escapes, which JSON, YAML
+/// and TOML all read
+pub fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+// ============================================================================
+// JSON
+// ============================================================================
+
+/// Lay out `root` as indented JSON
+pub fn json(root: &Node) -> Outline {
+    let mut out = Outline::default();
+    json_node(&mut out, root, 0, "", "$", "");
+    out
+}
+
+fn json_node(out: &mut Outline, node: &Node, depth: usize, prefix: &str, path: &str, comma: &str) {
+    if let Some(empty) = node.is_empty_container() {
+        out.push(depth, &format!("{}{}{}", prefix, empty, comma), path);
+        return;
+    }
+    match node {
+        Node::Scalar(text) => {
+            out.push(depth, &format!("{}{}{}", prefix, text, comma), path);
+        }
+        Node::List(items) => {
+            let start = out.push(depth, &format!("{}[", prefix), path);
+            for (i, item) in items.iter().enumerate() {
+                let comma = if i + 1 < items.len() { "," } else { "" };
+                json_node(out, item, depth + 1, "", &index_path(path, i), comma);
+            }
+            out.push(depth, &format!("]{}", comma), path);
+            out.fold(start);
+        }
+        Node::Map(entries) => {
+            let start = out.push(depth, &format!("{}{{", prefix), path);
+            for (i, entry) in entries.iter().enumerate() {
+                let comma = if i + 1 < entries.len() { "," } else { "" };
+                let prefix = format!("{}: ", entry.label);
+                json_node(out, &entry.value, depth + 1, &prefix, &key_path(path, &entry.key), comma);
+            }
+            out.push(depth, &format!("}}{}", comma), path);
+            out.fold(start);
+        }
+    }
+}
+
+// ============================================================================
+// YAML
+// ============================================================================
+
+/// Lay out YAML documents in block style. Several documents each start
+/// with `---` and their paths with the document number.
+pub fn yaml(documents: &[Node]) -> Outline {
+    let mut out = Outline::default();
+    for (i, document) in documents.iter().enumerate() {
+        if documents.len() == 1 {
+            yaml_children(&mut out, document, 0, "$");
+            continue;
+        }
+        let root = format!("#{} $", i + 1);
+        let start = out.push(0, "---", &root);
+        yaml_children(&mut out, document, 0, &root);
+        out.fold(start);
+    }
+    out
+}
+
+/// The items or keys of `node`, each on its own line at `depth`
+fn yaml_children(out: &mut Outline, node: &Node, depth: usize, path: &str) {
+    if let Some(empty) = node.is_empty_container() {
+        out.push(depth, empty, path);
+        return;
+    }
+    match node {
+        Node::Scalar(text) => {
+            out.push(depth, text, path);
+        }
+        Node::List(items) => {
+            for (i, item) in items.iter().enumerate() {
+                yaml_entry(out, depth, "-", item, &index_path(path, i));
+            }
+        }
+        Node::Map(entries) => {
+            for entry in entries {
+                let lead = format!("{}:", entry.label);
+                yaml_entry(out, depth, &lead, &entry.value, &key_path(path, &entry.key));
+            }
+        }
+    }
+}
+
+/// `key:` or `-` followed by a scalar on the same line, or by a block
+fn yaml_entry(out: &mut Outline, depth: usize, lead: &str, node: &Node, path: &str) {
+    match (node, node.is_empty_container()) {
+        (_, Some(empty)) => {
+            out.push(depth, &format!("{} {}", lead, empty), path);
+        }
+        (Node::Scalar(text), _) => {
+            out.push(depth, &format!("{} {}", lead, text), path);
+        }
+        _ => {
+            let start = out.push(depth, lead, path);
+            yaml_children(out, node, depth + 1, path);
+            out.fold(start);
+        }
+    }
+}
+
+// ============================================================================
+// TOML
+// ============================================================================
+
+/// Lay out a TOML document: plain keys first, then each table under a
+/// `[header]` with its keys indented below it
+pub fn toml(root: &[Entry]) -> Outline {
+    let mut out = Outline::default();
+    toml_table(&mut out, root, 0, "", "$");
+    out
+}
+
+/// Whether `node` is written as `[[header]]` sections
+fn is_table_array(node: &Node) -> bool {
+    matches!(node, Node::List(items) if !items.is_empty() && items.iter().all(|item| matches!(item, Node::Map(_))))
+}
+
+fn toml_table(out: &mut Outline, entries: &[Entry], depth: usize, header: &str, path: &str) {
+    let is_section = |node: &Node| matches!(node, Node::Map(_)) || is_table_array(node);
+    for entry in entries.iter().filter(|entry| !is_section(&entry.value)) {
+        let line = format!("{} = {}", entry.label, toml_inline(&entry.value));
+        out.push(depth, &line, &key_path(path, &entry.key));
+    }
+
+    for entry in entries.iter().filter(|entry| is_section(&entry.value)) {
+        let header = if header.is_empty() { entry.label.clone() } else { format!("{}.{}", header, entry.label) };
+        let path = key_path(path, &entry.key);
+        match &entry.value {
+            Node::Map(children) => {
+                let start = out.push(depth, &format!("[{}]", header), &path);
+                toml_table(out, children, depth + 1, &header, &path);
+                out.fold(start);
+            }
+            Node::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    let Node::Map(children) = item else { continue };
+                    let path = index_path(&path, i);
+                    let start = out.push(depth, &format!("[[{}]]", header), &path);
+                    toml_table(out, children, depth + 1, &header, &path);
+                    out.fold(start);
+                }
+            }
+            Node::Scalar(_) => {}
+        }
+    }
+}
+
+/// A value written on one line: `[1, 2]`, `{ a = 1 }`
+fn toml_inline(node: &Node) -> String {
+    match node {
+        Node::Scalar(text) => text.clone(),
+        Node::List(items) => format!("[{}]", items.iter().map(toml_inline).collect::<Vec<_>>().join(", ")),
+        Node::Map(entries) if entries.is_empty() => "{}".to_string(),
+        Node::Map(entries) => {
+            let entries: Vec<String> =
+                entries.iter().map(|entry| format!("{} = {}", entry.label, toml_inline(&entry.value))).collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, value: Node) -> Entry {
+        Entry { key: key.to_string(), label: key.to_string(), value }
+    }
+
+    fn scalar(text: &str) -> Node {
+        Node::Scalar(text.to_string())
+    }
+
+    #[test]
+    fn test_layouts() {
+        let root = Node::Map(vec![
+            entry("name", scalar("\"bark\"")),
+            entry("tags", Node::List(vec![scalar("1"), Node::Map(vec![entry("a b", scalar("true"))])])),
+            entry("empty", Node::Map(Vec::new())),
+        ]);
+
+        let out = json(&root);
+        assert_eq!(
+            out.lines,
+            ["{", "  name: \"bark\",", "  tags: [", "    1,", "    {", "      a b: true", "    }", "  ],", "  empty: {}", "}"]
+        );
+        assert_eq!(out.folds, [(4, 6), (2, 7), (0, 9)]);
+        assert_eq!(out.paths[5], "$.tags[1][\"a b\"]");
+
+        let out = yaml(std::slice::from_ref(&root));
+        assert_eq!(out.lines, ["name: \"bark\"", "tags:", "  - 1", "  -", "    a b: true", "empty: {}"]);
+        assert_eq!(out.folds, [(3, 4), (1, 4)]);
+
+        let Node::Map(entries) = &root else { unreachable!() };
+        let out = toml(entries);
+        assert_eq!(out.lines, ["name = \"bark\"", "tags = [1, { a b = true }]", "[empty]"]);
+        assert!(out.folds.is_empty());
+
+        let servers = Node::List(vec![Node::Map(vec![entry("port", scalar("80"))]), Node::Map(Vec::new())]);
+        let out = toml(&[entry("servers", servers)]);
+        assert_eq!(out.lines, ["[[servers]]", "  port = 80", "[[servers]]"]);
+        assert_eq!((out.folds.as_slice(), out.paths[1].as_str()), (&[(0, 1)][..], "$.servers[0].port"));
+    }
+}
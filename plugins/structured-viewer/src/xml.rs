@@ -0,0 +1,185 @@
+//! XML laid out as indented elements
+//!
+//! Elements holding only a short text stay on one line; any other element
+//! with content is a block from its start tag to its end tag. Paths are
+//! XPath-like, e.g. `/config/server[2]/name`.
+
+use roxmltree::{Document, Node, NodeType, ParsingOptions};
+
+use crate::formats::SyntaxError;
+use crate::tree::Outline;
+
+pub fn outline(text: &str) -> Result<Outline, SyntaxError> {
+    let options = ParsingOptions { allow_dtd: true, ..ParsingOptions::default() };
+    let document = Document::parse_with_options(text, options).map_err(|e| {
+        let pos = e.pos();
+        let message = e.to_string();
+        let message = message.strip_suffix(&format!(" at {}", pos)).unwrap_or(&message);
+        SyntaxError { line: pos.row as usize, column: pos.col as usize, message: message.to_string() }
+    })?;
+
+    let mut out = Outline::default();
+    for child in document.root().children() {
+        node(&mut out, child, 0, "");
+    }
+    Ok(out)
+}
+
+fn node(out: &mut Outline, node: Node, depth: usize, parent: &str) {
+    match node.node_type() {
+        NodeType::Element => element(out, node, depth, &element_path(node, parent)),
+        NodeType::Text => {
+            let path = format!("{}/text()", parent);
+            for line in node.text().unwrap_or_default().lines().map(str::trim).filter(|l| !l.is_empty()) {
+                out.push(depth, &escape(line, false), &path);
+            }
+        }
+        NodeType::Comment => {
+            let path = format!("{}/comment()", parent);
+            let text = node.text().unwrap_or_default();
+            match text.lines().count() {
+                0 | 1 => {
+                    out.push(depth, &format!("<!--{}-->", text), &path);
+                }
+                _ => {
+                    let start = out.push(depth, "<!--", &path);
+                    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                        out.push(depth + 1, line, &path);
+                    }
+                    out.push(depth, "-->", &path);
+                    out.fold(start);
+                }
+            }
+        }
+        NodeType::PI => {
+            if let Some(pi) = node.pi() {
+                let text = match pi.value {
+                    Some(value) => format!("<?{} {}?>", pi.target, value.replace('\n', " ")),
+                    None => format!("<?{}?>", pi.target),
+                };
+                out.push(depth, &text, &format!("{}/processing-instruction()", parent));
+            }
+        }
+        NodeType::Root => {}
+    }
+}
+
+fn element(out: &mut Outline, element: Node, depth: usize, path: &str) {
+    let name = qualified_name(element, element.tag_name().namespace(), element.tag_name().name());
+    let mut open = format!("<{}", name);
+    // Namespaces declared here rather than inherited from the parent
+    let inherited: Vec<_> = element.parent_element().map(|p| p.namespaces().collect()).unwrap_or_default();
+    for ns in element.namespaces().filter(|ns| ns.name() != Some("xml") && !inherited.contains(ns)) {
+        match ns.name() {
+            Some(prefix) => open.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape(ns.uri(), true))),
+            None => open.push_str(&format!(" xmlns=\"{}\"", escape(ns.uri(), true))),
+        }
+    }
+    for attr in element.attributes() {
+        let attr_name = qualified_name(element, attr.namespace(), attr.name());
+        open.push_str(&format!(" {}=\"{}\"", attr_name, escape(attr.value(), true)));
+    }
+
+    let children: Vec<Node> = element
+        .children()
+        .filter(|child| !child.is_text() || child.text().is_some_and(|t| !t.trim().is_empty()))
+        .collect();
+    match children.as_slice() {
+        [] => {
+            out.push(depth, &format!("{}/>", open), path);
+        }
+        [text] if text.is_text() && !text.text().unwrap_or_default().trim().contains('\n') => {
+            let text = escape(text.text().unwrap_or_default().trim(), false);
+            out.push(depth, &format!("{}>{}</{}>", open, text, name), path);
+        }
+        children => {
+            let start = out.push(depth, &format!("{}>", open), path);
+            for &child in children {
+                node(out, child, depth + 1, path);
+            }
+            out.push(depth, &format!("</{}>", name), path);
+            out.fold(start);
+        }
+    }
+}
+
+/// `parent/name`, with the position among siblings of the same name when
+/// there are several
+fn element_path(element: Node, parent: &str) -> String {
+    let name = element.tag_name().name();
+    let same_name = |n: &Node| n.is_element() && n.tag_name().name() == name;
+    let siblings = element.parent().map_or(1, |p| p.children().filter(same_name).count());
+    if siblings > 1 {
+        let position = element.prev_siblings().filter(same_name).count();
+        format!("{}/{}[{}]", parent, name, position)
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+/// `prefix:name` for a name in a prefixed namespace
+fn qualified_name(element: Node, namespace: Option<&str>, name: &str) -> String {
+    match namespace.and_then(|uri| element.lookup_prefix(uri)) {
+        Some(prefix) if !prefix.is_empty() => format!("{}:{}", prefix, name),
+        _ => name.to_string(),
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' if !attribute => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '\n' | '\t' | '\r' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline() {
+        let text = r#"<?xml version="1.0"?>
+<!-- settings -->
+<config xmlns:x="urn:x" version="2">
+  <server x:port="80">web &amp; api</server>
+  <server>
+    <name>b</name>
+    mixed text
+  </server>
+  <empty/>
+</config>"#;
+        let out = outline(text).unwrap();
+        assert_eq!(
+            out.lines,
+            [
+                "<!-- settings -->",
+                "<config xmlns:x=\"urn:x\" version=\"2\">",
+                "  <server x:port=\"80\">web &amp; api</server>",
+                "  <server>",
+                "    <name>b</name>",
+                "    mixed text",
+                "  </server>",
+                "  <empty/>",
+                "</config>",
+            ]
+        );
+        assert_eq!(out.folds, [(3, 6), (1, 8)]);
+        assert_eq!(out.paths[4], "/config/server[2]/name");
+        assert_eq!(out.paths[5], "/config/server[2]/text()");
+    }
+
+    #[test]
+    fn test_error_position() {
+        let error = outline("<a>\n  <b></c>\n</a>").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 6));
+        assert!(error.message.contains("expected 'b'"), "{}", error.message);
+    }
+}
@@ -11,7 +11,11 @@ use std::sync::Mutex;
 mod webdav;
 use webdav::{WebdavProviderPlugin, WebdavProviderSession};
 
-use bark_plugin_api::{ProviderConfig, ProviderPlugin, ProviderSession};
+use bark_plugin_api::protocol::{
+    Command, ConnectReply, DialogFieldsReply, EntrySpec, ErrorReply, FieldSpec, ListReply, PluginInfoReply, ReadReply,
+    Request, SuccessReply, ValidateReply, encode_reply, provider_config,
+};
+use bark_plugin_api::{Capability, ProviderConfig, ProviderPlugin, ProviderResult, ProviderSession};
 
 /// Global session storage (simple single-session for now)
 static SESSION: Mutex<Option<WebdavProviderSession>> = Mutex::new(None);
@@ -49,88 +53,67 @@ fn print_plugin_info() {
     let plugin = WebdavProviderPlugin::new();
     let info = plugin.info();
 
-    println!(
-        r#"{{"name":"{}","version":"{}","type":"provider","schemes":{},"description":"{}","icon":"{}"}}"#,
-        escape_json(&info.name),
-        escape_json(&info.version),
-        format!(
-            "[{}]",
-            info.schemes
+    let reply = PluginInfoReply {
+        api_version: Some(1),
+        capabilities: Some(
+            [Capability::Write, Capability::Delete, Capability::Rename, Capability::Mkdir, Capability::CopyFile]
                 .iter()
-                .map(|s| format!("\"{}\"", escape_json(s)))
-                .collect::<Vec<_>>()
-                .join(",")
+                .map(|c| c.as_str().to_string())
+                .collect(),
         ),
-        escape_json(&info.description),
-        info.icon.unwrap_or(' ')
-    );
+        name: Some(info.name.clone()),
+        version: Some(info.version.clone()),
+        plugin_type: Some("provider".to_string()),
+        description: info.description.clone(),
+        icon: info.icon.map(String::from),
+        schemes: info.schemes.clone(),
+        ..Default::default()
+    };
+    println!("{}", encode_reply(&reply));
 }
 
-fn handle_command(json: &str) -> String {
-    let command = extract_string(json, "command").unwrap_or_default();
-
-    match command.as_str() {
-        "get_dialog_fields" => handle_get_dialog_fields(),
-        "validate_config" => handle_validate_config(json),
-        "connect" => handle_connect(json),
-        "disconnect" => handle_disconnect(),
-        "list_directory" => handle_list_directory(json),
-        "read_file" => handle_read_file(json),
-        "write_file" => handle_write_file(json),
-        "delete" => handle_delete(json),
-        "mkdir" => handle_mkdir(json),
-        "rename" => handle_rename(json),
-        "copy_file" => handle_copy_file(json),
-        _ => format!(r#"{{"error":"Unknown command: {}"}}"#, escape_json(&command)),
+fn handle_command(line: &str) -> String {
+    let command = match serde_json::from_str::<Request>(line) {
+        Ok(request) => request.command,
+        Err(e) => return error_reply(format!("Invalid request: {}", e)),
+    };
+
+    match command {
+        Command::GetDialogFields => handle_get_dialog_fields(),
+        Command::ValidateConfig { config } => handle_validate_config(provider_config(config)),
+        Command::Connect { config } => handle_connect(provider_config(config)),
+        Command::Disconnect => handle_disconnect(),
+        Command::ListDirectory { path } => handle_list_directory(&path),
+        Command::ReadFile { path } => handle_read_file(&path),
+        Command::WriteFile { path, data } => handle_write_file(&path, &data),
+        Command::Delete { path, .. } => with_session(|session| session.delete(&path)),
+        Command::Mkdir { path } => with_session(|session| session.mkdir(&path)),
+        Command::Rename { from, to } => with_session(|session| session.rename(&from, &to)),
+        Command::CopyFile { from, to } => with_session(|session| session.copy_file(&from, &to)),
+        other => error_reply(format!("Unknown command: {}", other.name())),
     }
 }
 
+fn error_reply(message: impl Into<String>) -> String {
+    encode_reply(&ErrorReply::new(message))
+}
+
 fn handle_get_dialog_fields() -> String {
     let plugin = WebdavProviderPlugin::new();
-    let fields = plugin.get_dialog_fields();
-
-    let fields_json: Vec<String> = fields
-        .iter()
-        .map(|f| {
-            let field_type = match &f.field_type {
-                bark_plugin_api::DialogFieldType::Text => "text",
-                bark_plugin_api::DialogFieldType::Password => "password",
-                bark_plugin_api::DialogFieldType::Number => "number",
-                bark_plugin_api::DialogFieldType::Checkbox => "checkbox",
-                bark_plugin_api::DialogFieldType::Select { .. } => "select",
-                bark_plugin_api::DialogFieldType::TextArea => "textarea",
-                bark_plugin_api::DialogFieldType::FilePath => "filepath",
-            };
-
-            format!(
-                r#"{{"id":"{}","label":"{}","type":"{}","required":{},"default":{}}}"#,
-                escape_json(&f.id),
-                escape_json(&f.label),
-                field_type,
-                f.required,
-                f.default_value
-                    .as_ref()
-                    .map(|v| format!("\"{}\"", escape_json(v)))
-                    .unwrap_or_else(|| "null".to_string())
-            )
-        })
-        .collect();
-
-    format!(r#"{{"fields":[{}]}}"#, fields_json.join(","))
+    let fields = plugin.get_dialog_fields().iter().map(FieldSpec::from).collect();
+    encode_reply(&DialogFieldsReply { fields })
 }
 
-fn handle_validate_config(json: &str) -> String {
-    let config = parse_config(json);
+fn handle_validate_config(config: ProviderConfig) -> String {
     let plugin = WebdavProviderPlugin::new();
 
     match plugin.validate_config(&config) {
-        Ok(()) => r#"{"valid":true}"#.to_string(),
-        Err(e) => format!(r#"{{"valid":false,"error":"{}"}}"#, escape_json(&e.to_string())),
+        Ok(()) => encode_reply(&ValidateReply { valid: true, error: None }),
+        Err(e) => encode_reply(&ValidateReply { valid: false, error: Some(e.to_string()) }),
     }
 }
 
-fn handle_connect(json: &str) -> String {
-    let config = parse_config(json);
+fn handle_connect(config: ProviderConfig) -> String {
     let plugin = WebdavProviderPlugin::new();
 
     match plugin.connect(&config) {
@@ -147,9 +130,14 @@ fn handle_connect(json: &str) -> String {
             let mut guard = SESSION.lock().unwrap();
             *guard = Some(*webdav_session);
 
-            format!(r#"{{"success":true,"session_id":"default","display_name":"{}"}}"#, escape_json(&display_name))
+            encode_reply(&ConnectReply {
+                success: true,
+                session_id: Some("default".to_string()),
+                display_name: Some(display_name),
+                short_label: None,
+            })
         }
-        Err(e) => format!(r#"{{"success":false,"error":"{}"}}"#, escape_json(&e.to_string())),
+        Err(e) => error_reply(e.to_string()),
     }
 }
 
@@ -159,213 +147,56 @@ fn handle_disconnect() -> String {
         session.disconnect();
     }
     *guard = None;
-    r#"{"success":true}"#.to_string()
-}
-
-fn handle_list_directory(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_else(|| "/".to_string());
-
-    let mut guard = SESSION.lock().unwrap();
-    let session = match guard.as_mut() {
-        Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
-    };
-
-    match session.list_directory(&path) {
-        Ok(entries) => {
-            let entries_json: Vec<String> = entries
-                .iter()
-                .map(|e| {
-                    format!(
-                        r#"{{"name":"{}","path":"{}","is_dir":{},"size":{},"is_hidden":{},"permissions":{},"is_symlink":{}}}"#,
-                        escape_json(&e.name),
-                        escape_json(&e.path.to_string_lossy()),
-                        e.is_dir,
-                        e.size,
-                        e.is_hidden,
-                        e.permissions,
-                        e.is_symlink
-                    )
-                })
-                .collect();
-
-            format!(r#"{{"entries":[{}]}}"#, entries_json.join(","))
-        }
-        Err(e) => format!(r#"{{"error":"{}"}}"#, escape_json(&e.to_string())),
-    }
-}
-
-fn handle_read_file(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-
-    let mut guard = SESSION.lock().unwrap();
-    let session = match guard.as_mut() {
-        Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
-    };
-
-    match session.read_file(&path) {
-        Ok(data) => {
-            let b64 = base64_encode(&data);
-            format!(r#"{{"data":"{}"}}"#, b64)
-        }
-        Err(e) => format!(r#"{{"error":"{}"}}"#, escape_json(&e.to_string())),
-    }
-}
-
-fn handle_write_file(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-    let data_b64 = extract_string(json, "data").unwrap_or_default();
-
-    let data = match base64_decode(&data_b64) {
-        Ok(d) => d,
-        Err(e) => return format!(r#"{{"error":"Invalid base64: {}"}}"#, e),
-    };
-
-    let mut guard = SESSION.lock().unwrap();
-    let session = match guard.as_mut() {
-        Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
-    };
-
-    match session.write_file(&path, &data) {
-        Ok(()) => r#"{"success":true}"#.to_string(),
-        Err(e) => format!(r#"{{"error":"{}"}}"#, escape_json(&e.to_string())),
-    }
+    encode_reply(&SuccessReply { success: true })
 }
 
-fn handle_delete(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-
+/// Run an operation that only reports success on the connected session
+fn with_session(op: impl FnOnce(&mut WebdavProviderSession) -> ProviderResult<()>) -> String {
     let mut guard = SESSION.lock().unwrap();
     let session = match guard.as_mut() {
         Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
+        None => return error_reply("Not connected"),
     };
 
-    match session.delete(&path) {
-        Ok(()) => r#"{"success":true}"#.to_string(),
-        Err(e) => format!(r#"{{"error":"{}"}}"#, escape_json(&e.to_string())),
+    match op(session) {
+        Ok(()) => encode_reply(&SuccessReply { success: true }),
+        Err(e) => error_reply(e.to_string()),
     }
 }
 
-fn handle_mkdir(json: &str) -> String {
-    let path = extract_string(json, "path").unwrap_or_default();
-
+fn handle_list_directory(path: &str) -> String {
     let mut guard = SESSION.lock().unwrap();
     let session = match guard.as_mut() {
         Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
+        None => return error_reply("Not connected"),
     };
 
-    match session.mkdir(&path) {
-        Ok(()) => r#"{"success":true}"#.to_string(),
-        Err(e) => format!(r#"{{"error":"{}"}}"#, escape_json(&e.to_string())),
+    match session.list_directory(path) {
+        Ok(entries) => encode_reply(&ListReply { entries: entries.iter().map(EntrySpec::from).collect() }),
+        Err(e) => error_reply(e.to_string()),
     }
 }
 
-fn handle_rename(json: &str) -> String {
-    let from = extract_string(json, "from").unwrap_or_default();
-    let to = extract_string(json, "to").unwrap_or_default();
-
+fn handle_read_file(path: &str) -> String {
     let mut guard = SESSION.lock().unwrap();
     let session = match guard.as_mut() {
         Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
+        None => return error_reply("Not connected"),
     };
 
-    match session.rename(&from, &to) {
-        Ok(()) => r#"{"success":true}"#.to_string(),
-        Err(e) => format!(r#"{{"error":"{}"}}"#, escape_json(&e.to_string())),
+    match session.read_file(path) {
+        Ok(data) => encode_reply(&ReadReply { data: base64_encode(&data) }),
+        Err(e) => error_reply(e.to_string()),
     }
 }
 
-fn handle_copy_file(json: &str) -> String {
-    let from = extract_string(json, "from").unwrap_or_default();
-    let to = extract_string(json, "to").unwrap_or_default();
-
-    let mut guard = SESSION.lock().unwrap();
-    let session = match guard.as_mut() {
-        Some(s) => s,
-        None => return r#"{"error":"Not connected"}"#.to_string(),
+fn handle_write_file(path: &str, data_b64: &str) -> String {
+    let data = match base64_decode(data_b64) {
+        Ok(d) => d,
+        Err(e) => return error_reply(format!("Invalid base64: {}", e)),
     };
 
-    match session.copy_file(&from, &to) {
-        Ok(()) => r#"{"success":true}"#.to_string(),
-        Err(e) => format!(r#"{{"error":"{}"}}"#, escape_json(&e.to_string())),
-    }
-}
-
-// === JSON helpers (simple, no dependencies) ===
-
-fn extract_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = &json[start..];
-    let rest = rest.trim_start();
-
-    if rest.starts_with('"') {
-        // String value
-        let rest = &rest[1..];
-        let end = rest.find('"')?;
-        Some(unescape_json(&rest[..end]))
-    } else if rest.starts_with('{') {
-        // Object value - find matching brace
-        let mut depth = 0;
-        let mut end = 0;
-        for (i, c) in rest.chars().enumerate() {
-            match c {
-                '{' => depth += 1,
-                '}' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        end = i + 1;
-                        break;
-                    }
-                }
-                _ => {}
-            }
-        }
-        Some(rest[..end].to_string())
-    } else {
-        // Other value (number, bool, null)
-        let end = rest.find(|c: char| c == ',' || c == '}' || c == ']')?;
-        Some(rest[..end].trim().to_string())
-    }
-}
-
-fn parse_config(json: &str) -> ProviderConfig {
-    let mut config = ProviderConfig::new();
-
-    // Extract config object
-    if let Some(config_json) = extract_string(json, "config") {
-        let keys = [
-            "name", "url", "user", "password", "path", "verify_ssl",
-        ];
-        for key in keys {
-            if let Some(value) = extract_string(&config_json, key) {
-                config.set(key, value);
-            }
-        }
-    }
-
-    config
-}
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn unescape_json(s: &str) -> String {
-    s.replace("\\\"", "\"")
-        .replace("\\\\", "\\")
-        .replace("\\n", "\n")
-        .replace("\\r", "\r")
-        .replace("\\t", "\t")
+    with_session(|session| session.write_file(path, &data))
 }
 
 fn base64_encode(data: &[u8]) -> String {
@@ -1,20 +1,37 @@
 //! Background I/O manager using threads and channels.
 
+use std::sync::atomic::Ordering;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread;
-use crate::async_io::{IoRequest, IoResponse};
+use crate::async_io::{read_listing, IoRequest, IoResponse};
 
-/// Manages background I/O operations on a dedicated thread.
+/// Manages background I/O operations.
 ///
-/// Requests are sent via `send()` and responses are polled via `try_recv()`.
-/// The background thread automatically terminates when the IoManager is dropped.
+/// Requests are sent via an [`IoHandle`] and responses are
+/// polled via `try_recv()`. A dispatcher thread hands each request to its
+/// own worker, so a slow listing on one panel never delays the other. The
+/// dispatcher exits once every handle and the manager have been dropped.
 pub struct IoManager {
     tx: Sender<IoRequest>,
     rx: Receiver<IoResponse>,
 }
 
+/// Cloneable sending side of an [`IoManager`], held by each panel
+#[derive(Clone)]
+pub struct IoHandle {
+    tx: Sender<IoRequest>,
+}
+
+impl IoHandle {
+    /// Send a request to the background dispatcher.
+    pub fn send(&self, req: IoRequest) {
+        // Ignore send errors - they only occur if the dispatcher has exited.
+        let _ = self.tx.send(req);
+    }
+}
+
 impl IoManager {
-    /// Create a new IoManager with a background worker thread.
+    /// Create a new IoManager with a background dispatcher thread.
     #[must_use]
     pub fn new() -> Self {
         let (req_tx, req_rx) = channel::<IoRequest>();
@@ -22,7 +39,8 @@ impl IoManager {
 
         thread::spawn(move || {
             while let Ok(request) = req_rx.recv() {
-                handle_request(request, &res_tx);
+                let res_tx = res_tx.clone();
+                thread::spawn(move || handle_request(request, &res_tx));
             }
         });
 
@@ -32,11 +50,10 @@ impl IoManager {
         }
     }
 
-    /// Send a request to the background worker.
-    pub fn send(&self, req: IoRequest) {
-        // Ignore send errors - they only occur if the receiver is dropped,
-        // which means the worker thread has exited.
-        let _ = self.tx.send(req);
+    /// Get a handle panels can use to submit requests.
+    #[must_use]
+    pub fn handle(&self) -> IoHandle {
+        IoHandle { tx: self.tx.clone() }
     }
 
     /// Try to receive a response without blocking.
@@ -55,30 +72,26 @@ impl Default for IoManager {
 
 fn handle_request(req: IoRequest, tx: &Sender<IoResponse>) {
     match req {
-        IoRequest::List(side, path, provider) => {
+        IoRequest::List { side, id, path, provider, cancel } => {
             let path_str = path.to_string_lossy().to_string();
 
-            // Try to acquire lock on the provider
+            // Waiting for the lock can take a while if an abandoned listing
+            // is still running on the same connection
             let result = match provider.lock() {
-                Ok(mut p) => p.list_directory(&path_str),
-                Err(_) => {
-                    // Mutex was poisoned (previous holder panicked)
-                    let _ = tx.send(IoResponse::Error(
-                        side,
-                        "Provider lock poisoned".to_string(),
-                    ));
-                    return;
+                Ok(mut p) => {
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    read_listing(p.as_mut(), &path_str)
                 }
+                // Mutex was poisoned (previous holder panicked)
+                Err(_) => Err("Provider lock poisoned".to_string()),
             };
 
-            match result {
-                Ok(entries) => {
-                    let _ = tx.send(IoResponse::Listed(side, path, entries));
-                }
-                Err(e) => {
-                    let _ = tx.send(IoResponse::Error(side, e.to_string()));
-                }
+            if cancel.load(Ordering::Relaxed) {
+                return;
             }
+            let _ = tx.send(IoResponse::Listed { side, id, result });
         }
     }
 }
@@ -1,37 +1,73 @@
 //! Asynchronous I/O operations for non-blocking directory listing.
 //!
-//! This module provides background I/O operations to prevent UI freezes
-//! when listing remote directories (SCP) or large local directories.
+//! Panels send listing requests through an [`IoHandle`] and the main loop
+//! drains finished listings from [`IoManager`]. This keeps the UI responsive
+//! while a slow remote server or a huge local directory is being read.
 //!
-//! # Status
-//! Currently implemented but not integrated into the main event loop.
-//! Integration is planned for a future release.
-
-// Allow dead code - this module is infrastructure for future async integration
-#![allow(dead_code)]
+//! Every request carries the panel's request id and a cancel flag. A panel
+//! only applies the response matching its latest request, so listings the
+//! user navigated away from (or cancelled with Esc) are dropped.
 
 pub mod manager;
 
+pub use manager::{IoHandle, IoManager};
+
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
 use crate::fs::FileEntry;
 use crate::state::Side;
-use crate::providers::PanelProvider;
+use crate::providers::{Capability, PanelProvider};
+
+/// A provider shared between a panel and the I/O worker threads
+pub type SharedProvider = Arc<Mutex<Box<dyn PanelProvider>>>;
+
+/// A finished directory listing
+#[derive(Debug)]
+pub struct Listing {
+    pub entries: Vec<FileEntry>,
+    /// Free space reported by a remote provider that supports it
+    pub free_space: Option<u64>,
+}
+
+/// List `path` with `provider`. Remote providers that report free space are
+/// asked for it while the connection is held anyway.
+pub fn read_listing(provider: &mut dyn PanelProvider, path: &str) -> Result<Listing, String> {
+    let entries = provider.list_directory(path).map_err(|e| e.to_string())?;
+    let free_space = if !provider.is_local() && provider.supports(Capability::FreeSpace) {
+        provider.get_free_space(path)
+    } else {
+        None
+    };
+    Ok(Listing { entries, free_space })
+}
 
 /// Request for an I/O operation
 pub enum IoRequest {
-    /// List directory contents: (target panel, path, provider)
-    List(Side, PathBuf, Arc<Mutex<Box<dyn PanelProvider + Send>>>),
+    /// List directory contents for a panel
+    List {
+        /// Panel the listing is for
+        side: Side,
+        /// Panel-local request id, echoed back in the response
+        id: u64,
+        /// Directory to list
+        path: PathBuf,
+        /// Provider to list with
+        provider: SharedProvider,
+        /// Set when the panel no longer wants the result
+        cancel: Arc<AtomicBool>,
+    },
 }
 
 impl std::fmt::Debug for IoRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            IoRequest::List(side, path, _) => {
-                f.debug_tuple("List")
-                    .field(side)
-                    .field(path)
-                    .field(&"<provider>")
+            IoRequest::List { side, id, path, .. } => {
+                f.debug_struct("List")
+                    .field("side", side)
+                    .field("id", id)
+                    .field("path", path)
+                    .field("provider", &"<provider>")
                     .finish()
             }
         }
@@ -41,8 +77,10 @@ impl std::fmt::Debug for IoRequest {
 /// Response from an I/O operation
 #[derive(Debug)]
 pub enum IoResponse {
-    /// Directory listing completed successfully
-    Listed(Side, PathBuf, Vec<FileEntry>),
-    /// I/O operation failed with error message
-    Error(Side, String),
-}
\ No newline at end of file
+    /// Directory listing finished (successfully or with an error message)
+    Listed {
+        side: Side,
+        id: u64,
+        result: Result<Listing, String>,
+    },
+}
@@ -19,6 +19,8 @@ pub struct Config {
     pub sorting: SortingConfig,
     /// Editor settings
     pub editor: EditorConfig,
+    /// Built-in viewer settings
+    pub viewer: ViewerConfig,
     /// Confirmation settings
     pub confirmations: ConfirmConfig,
     /// Theme settings
@@ -253,12 +255,22 @@ pub struct GeneralConfig {
     /// Use shell history viewer instead of interactive PTY shell on Ctrl+O
     #[serde(default)]
     pub shell_history_mode: bool,
+    /// F8 on a local panel moves items to the trash; Shift+F8 still deletes
+    #[serde(default)]
+    pub use_trash: bool,
+    /// Background jobs that run at once; the rest wait in the queue
+    #[serde(default = "default_max_jobs")]
+    pub max_jobs: usize,
 }
 
 fn default_remote_transfer_limit_mb() -> u64 {
     512
 }
 
+fn default_max_jobs() -> usize {
+    2
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DisplayConfig {
@@ -303,13 +315,36 @@ pub struct EditorConfig {
     pub command: String,
     /// External viewer command (empty = use built-in)
     pub viewer: String,
-    /// External hex editor command (used by HexEditor plugin)
-    #[serde(default = "default_hex_editor")]
+    /// External hex editor command used by the HexEditor plugin
+    /// (empty = built-in hex editor)
     pub hex_editor: String,
 }
 
-fn default_hex_editor() -> String {
-    "jinx".to_string()
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewerConfig {
+    /// Soft-wrap long lines in the text view
+    pub wrap: bool,
+    /// Show line numbers in the text view
+    pub line_numbers: bool,
+    /// Columns between tab stops (1-16)
+    pub tab_width: usize,
+    /// Highlight source code in the text view
+    pub syntax_highlighting: bool,
+    /// Extension or file name -> grammar name, checked before detection
+    pub syntax_map: HashMap<String, String>,
+}
+
+impl Default for ViewerConfig {
+    fn default() -> Self {
+        Self {
+            wrap: false,
+            line_numbers: true,
+            tab_width: 8,
+            syntax_highlighting: true,
+            syntax_map: HashMap::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -330,6 +365,7 @@ impl Default for Config {
             display: DisplayConfig::default(),
             sorting: SortingConfig::default(),
             editor: EditorConfig::default(),
+            viewer: ViewerConfig::default(),
             confirmations: ConfirmConfig::default(),
             theme: ThemeConfig::default(),
             keybindings: KeyBindings::default(),
@@ -372,6 +408,10 @@ pub fn default_keybinding(action: &str) -> &'static str {
         "move" => "F6",
         "mkdir" => "F7",
         "delete" => "F8",
+        "delete_permanent" => "Shift+F8",
+        "pack" => "Alt+F5",
+        "undo" => "Ctrl+Z",
+        "jobs" => "Alt+J",
 
         // Selection
         "select_toggle" => "Insert",
@@ -402,6 +442,7 @@ pub fn default_keybinding(action: &str) -> &'static str {
         // Search
         "find_files" => "Alt+/",
         "quick_search" => "Alt+S",
+        "compare_dirs" => "Alt+C",
 
         // Command line
         "insert_filename" => "Ctrl+F",
@@ -495,6 +536,8 @@ impl Default for GeneralConfig {
             view_plugin_first: false,
             remote_transfer_limit_mb: 512,
             shell_history_mode: false,
+            use_trash: false,
+            max_jobs: 2,
         }
     }
 }
@@ -598,6 +641,11 @@ pub fn history_file() -> Option<PathBuf> {
     config_dir().map(|p| p.join("history"))
 }
 
+/// Get the undo journal file path
+pub fn undo_file() -> Option<PathBuf> {
+    config_dir().map(|p| p.join("undo.toml"))
+}
+
 /// Load command history from file
 pub fn load_command_history() -> Vec<String> {
     let Some(path) = history_file() else {
@@ -675,6 +723,16 @@ view_plugin_first = false
 # Set to 0 to disable the size guard (no confirmation regardless of size)
 remote_transfer_limit_mb = 512
 
+# Move files to the trash on F8 in local panels instead of deleting them
+# (freedesktop.org trash: ~/.local/share/Trash, or .Trash-$uid on other mounts)
+# Shift+F8 always deletes permanently; the Trash entry in Alt+F1/F2 lists,
+# restores and empties trashed items
+use_trash = false
+
+# Copies and moves sent to the background (B in the progress dialog) that
+# run at once; the others wait in the queue. Alt+J shows the jobs
+max_jobs = 2
+
 [display]
 # Default view mode: "brief" (two columns) or "full" (detailed list)
 view_mode = "brief"
@@ -725,6 +783,33 @@ command = ""
 # Example: "less", "bat"
 viewer = ""
 
+# External hex editor for the HexEditor viewer plugin (leave empty to use
+# the built-in hex editor, also opened with F4 in the viewer)
+# Example: "jinx", "hexedit"
+hex_editor = ""
+
+[viewer]
+# Soft-wrap long lines in the built-in text viewer (toggle with w)
+wrap = false
+
+# Show line numbers in the built-in text viewer (toggle with #)
+line_numbers = true
+
+# Columns between tab stops (1-16)
+tab_width = 8
+
+# Highlight source code in the built-in text viewer. The language is picked
+# from the file extension or name, then from a #! line.
+syntax_highlighting = true
+
+# Map extensions or file names to a grammar: Rust, C, C++, C#, Go, Java,
+# JavaScript, TypeScript, Python, Ruby, Shell, Lua, SQL, TOML, YAML, JSON,
+# XML, Makefile, Dockerfile. Any other name (e.g. "plain") turns it off.
+# [viewer.syntax_map]
+# h = "C++"
+# conf = "Shell"
+# log = "plain"
+
 [confirmations]
 # Show confirmation dialog before deleting files
 delete = true
@@ -782,6 +867,14 @@ preset = "dark"
 # viewer_footer_bg = "cyan"          # Viewer footer background
 # viewer_footer_fg = "black"         # Viewer footer text color
 #
+# ## Viewer syntax highlighting colors
+# syntax_keyword = "#c678dd"
+# syntax_type = "#e5c07b"
+# syntax_string = "#98c379"
+# syntax_number = "#d19a66"
+# syntax_comment = "#7f848e"
+# syntax_constant = "#56b6c2"
+#
 # ## Help viewer colors
 # help_header_bg = "cyan"
 # help_header_fg = "black"
@@ -900,6 +993,10 @@ preset = "dark"
 # move = "F6"                     # Move/rename file(s)
 # mkdir = "F7"                    # Create directory
 # delete = "F8"                   # Delete file(s)
+# delete_permanent = "Shift+F8"   # Delete file(s), bypassing the trash
+# pack = "Alt+F5"                 # Pack file(s) into an archive
+# undo = "Ctrl+Z"                 # Undo the last file operation
+# jobs = "Alt+J"                  # Show background copy/move jobs
 #
 # ## Selection
 # select_toggle = "Insert"        # Toggle file selection
@@ -930,6 +1027,7 @@ preset = "dark"
 # ## Search
 # find_files = "Alt+/"            # Find files dialog
 # quick_search = "Alt+S"          # Quick search (type to jump)
+# compare_dirs = "Alt+C"          # Compare / synchronize the two panels
 #
 # ## Command line
 # insert_filename = "Ctrl+F"      # Insert filename into command
@@ -1096,6 +1194,8 @@ impl Config {
             general["run_executables"] = value(self.general.run_executables);
             general["autosave"] = value(self.general.autosave);
             general["view_plugin_first"] = value(self.general.view_plugin_first);
+            general["use_trash"] = value(self.general.use_trash);
+            general["max_jobs"] = value(self.general.max_jobs as i64);
             general["remote_transfer_limit_mb"] = value(self.general.remote_transfer_limit_mb as i64);
 
             // Update last paths (these are optional)
@@ -1141,6 +1241,14 @@ impl Config {
             editor["hex_editor"] = value(&self.editor.hex_editor);
         }
 
+        // Update [viewer] section
+        if let Some(viewer) = doc.get_mut("viewer").and_then(|v| v.as_table_mut()) {
+            viewer["wrap"] = value(self.viewer.wrap);
+            viewer["line_numbers"] = value(self.viewer.line_numbers);
+            viewer["tab_width"] = value(self.viewer.tab_width as i64);
+            viewer["syntax_highlighting"] = value(self.viewer.syntax_highlighting);
+        }
+
         // Update [confirmations] section
         if let Some(conf) = doc.get_mut("confirmations").and_then(|v| v.as_table_mut()) {
             conf["delete"] = value(self.confirmations.delete);
@@ -0,0 +1,342 @@
+//! Directory comparison and synchronization (Alt+C)
+//!
+//! [`compare_entries`] pairs the two panels' listings by name and classifies
+//! each pair. Only the listed level is compared: a directory present on both
+//! sides counts as equal, one present on a single side is copied whole.
+//! [`plan`] turns the differences into [`SyncItem`]s, each holding the action
+//! the Synchronize dialog proposes and the alternatives the user may pick.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::fs::FileEntry;
+use crate::providers::PanelProvider;
+
+/// Modification times closer than this count as equal. FAT and many FTP
+/// servers only store times to the nearest 2 seconds or worse.
+pub const MTIME_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// How a name differs between the left and right panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    LeftOnly,
+    RightOnly,
+    LeftNewer,
+    RightNewer,
+    /// Same modification time but different size or content, or a file on
+    /// one side and a directory on the other
+    Different,
+    Same,
+}
+
+/// One name found in either panel
+#[derive(Clone, Debug)]
+pub struct CompareItem {
+    pub name: String,
+    pub kind: DiffKind,
+    pub left: Option<FileEntry>,
+    pub right: Option<FileEntry>,
+}
+
+impl CompareItem {
+    /// Whether the left entry gets marked after a compare
+    pub fn marks_left(&self) -> bool {
+        matches!(self.kind, DiffKind::LeftOnly | DiffKind::LeftNewer | DiffKind::Different)
+    }
+
+    /// Whether the right entry gets marked after a compare
+    pub fn marks_right(&self) -> bool {
+        matches!(self.kind, DiffKind::RightOnly | DiffKind::RightNewer | DiffKind::Different)
+    }
+
+    /// A file on one side and a directory on the other
+    fn type_mismatch(&self) -> bool {
+        matches!((&self.left, &self.right), (Some(l), Some(r)) if l.is_dir != r.is_dir)
+    }
+}
+
+/// Decides whether two same-sized files have the same content
+pub type ContentEqual<'a> = &'a mut dyn FnMut(&FileEntry, &FileEntry) -> bool;
+
+/// Compare two listings by name. Files are compared by size and modification
+/// time; with `content_equal`, same-sized files are compared by content
+/// instead and their times only decide which side is newer. Results are
+/// sorted by name.
+pub fn compare_entries(
+    left: &[FileEntry],
+    right: &[FileEntry],
+    mut content_equal: Option<ContentEqual>,
+) -> Vec<CompareItem> {
+    let mut pairs = BTreeMap::new();
+    for entry in left.iter().filter(|e| e.name != "..") {
+        pairs.entry(entry.name.as_str()).or_insert((None, None)).0 = Some(entry);
+    }
+    for entry in right.iter().filter(|e| e.name != "..") {
+        pairs.entry(entry.name.as_str()).or_insert((None, None)).1 = Some(entry);
+    }
+
+    pairs
+        .into_iter()
+        .map(|(name, (l, r))| {
+            let kind = match (l, r) {
+                (Some(_), None) => DiffKind::LeftOnly,
+                (None, Some(_)) => DiffKind::RightOnly,
+                (Some(l), Some(r)) if l.is_dir && r.is_dir => DiffKind::Same,
+                (Some(l), Some(r)) if l.is_dir != r.is_dir => DiffKind::Different,
+                (Some(l), Some(r)) => match content_equal.as_mut() {
+                    Some(equal) => {
+                        if l.size == r.size && equal(l, r) {
+                            DiffKind::Same
+                        } else {
+                            newer_side(l, r)
+                        }
+                    }
+                    None => match newer_side(l, r) {
+                        DiffKind::Different if l.size == r.size => DiffKind::Same,
+                        kind => kind,
+                    },
+                },
+                (None, None) => unreachable!("every name comes from one of the listings"),
+            };
+            CompareItem {
+                name: name.to_string(),
+                kind,
+                left: l.cloned(),
+                right: r.cloned(),
+            }
+        })
+        .collect()
+}
+
+/// LeftNewer or RightNewer, or Different when the times are equal or unknown
+fn newer_side(left: &FileEntry, right: &FileEntry) -> DiffKind {
+    match (left.modified, right.modified) {
+        (Some(l), Some(r)) if l > r + MTIME_TOLERANCE => DiffKind::LeftNewer,
+        (Some(l), Some(r)) if r > l + MTIME_TOLERANCE => DiffKind::RightNewer,
+        _ => DiffKind::Different,
+    }
+}
+
+/// SHA-256 of a file read through a provider, None if it can't be read or
+/// the compare was cancelled
+pub fn provider_digest(provider: &Mutex<Box<dyn PanelProvider>>, path: &Path, cancel: &AtomicBool) -> Option<[u8; 32]> {
+    let mut provider = provider.lock().unwrap_or_else(|e| e.into_inner());
+    let mut reader = provider.open_read(&path.to_string_lossy()).ok()?;
+    super::copy::sha256(&mut reader, cancel).ok()
+}
+
+/// What synchronization does with one name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncAction {
+    Skip,
+    CopyToRight,
+    CopyToLeft,
+    DeleteLeft,
+    DeleteRight,
+}
+
+impl SyncAction {
+    /// Text of the action column in the Synchronize dialog
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncAction::Skip => " skip ",
+            SyncAction::CopyToRight => "copy ►",
+            SyncAction::CopyToLeft => "◄ copy",
+            SyncAction::DeleteLeft => "◄ del ",
+            SyncAction::DeleteRight => " del ►",
+        }
+    }
+}
+
+/// What each panel allows synchronization to do
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SyncPermissions {
+    pub left_write: bool,
+    pub left_delete: bool,
+    pub right_write: bool,
+    pub right_delete: bool,
+}
+
+/// A planned action for one differing name
+#[derive(Clone, Debug)]
+pub struct SyncItem {
+    pub item: CompareItem,
+    pub action: SyncAction,
+    /// Actions the user can switch to, always ending with Skip
+    pub choices: Vec<SyncAction>,
+}
+
+impl SyncItem {
+    /// Switch to the next (or previous) allowed action
+    pub fn cycle(&mut self, forward: bool) {
+        let idx = self.choices.iter().position(|a| *a == self.action).unwrap_or(0);
+        let len = self.choices.len();
+        self.action = self.choices[if forward { (idx + 1) % len } else { (idx + len - 1) % len }];
+    }
+
+    /// Switch to `action` if it is allowed for this item
+    pub fn set(&mut self, action: SyncAction) {
+        if self.choices.contains(&action) {
+            self.action = action;
+        }
+    }
+}
+
+/// Propose an action for every differing name: new and newer entries are
+/// copied to the other side, deletions are only offered. Files that differ
+/// without one being newer are skipped until the user picks a direction.
+pub fn plan(items: Vec<CompareItem>, perms: SyncPermissions) -> Vec<SyncItem> {
+    items
+        .into_iter()
+        .filter(|item| item.kind != DiffKind::Same)
+        .map(|item| {
+            let mut choices = Vec::new();
+            if !item.type_mismatch() {
+                let (copy_right, copy_left) = match item.kind {
+                    DiffKind::LeftOnly => (true, false),
+                    DiffKind::RightOnly => (false, true),
+                    _ => (true, true),
+                };
+                if copy_right && perms.right_write {
+                    choices.push(SyncAction::CopyToRight);
+                }
+                if copy_left && perms.left_write {
+                    choices.push(SyncAction::CopyToLeft);
+                }
+                if item.kind == DiffKind::LeftOnly && perms.left_delete {
+                    choices.push(SyncAction::DeleteLeft);
+                }
+                if item.kind == DiffKind::RightOnly && perms.right_delete {
+                    choices.push(SyncAction::DeleteRight);
+                }
+            }
+            choices.push(SyncAction::Skip);
+
+            let proposed = match item.kind {
+                DiffKind::LeftOnly | DiffKind::LeftNewer => SyncAction::CopyToRight,
+                DiffKind::RightOnly | DiffKind::RightNewer => SyncAction::CopyToLeft,
+                _ => SyncAction::Skip,
+            };
+            let action = if choices.contains(&proposed) { proposed } else { SyncAction::Skip };
+            SyncItem { item, action, choices }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    fn entry(name: &str, size: u64, secs: u64) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            is_dir: false,
+            size,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+            is_hidden: false,
+            permissions: 0o644,
+            is_symlink: false,
+            symlink_target: None,
+            owner: String::new(),
+            group: String::new(),
+        }
+    }
+
+    fn dir(name: &str) -> FileEntry {
+        FileEntry { is_dir: true, size: 0, ..entry(name, 0, 0) }
+    }
+
+    fn kinds(items: &[CompareItem]) -> Vec<(&str, DiffKind)> {
+        items.iter().map(|i| (i.name.as_str(), i.kind)).collect()
+    }
+
+    #[test]
+    fn test_compare_by_size_and_time() {
+        let left = [
+            entry("a.txt", 10, 1000),
+            entry("b.txt", 10, 5000),
+            entry("c.txt", 10, 1000),
+            entry("d.txt", 10, 1001),
+            entry("left.txt", 1, 0),
+            dir("both"),
+            dir("clash"),
+        ];
+        let right = [
+            entry("a.txt", 10, 1000),
+            entry("b.txt", 10, 1000),
+            entry("c.txt", 20, 1000),
+            entry("d.txt", 10, 1000),
+            entry("clash", 1, 0),
+            dir("both"),
+            dir("right"),
+        ];
+        let items = compare_entries(&left, &right, None);
+        assert_eq!(kinds(&items), [
+            ("a.txt", DiffKind::Same),
+            ("b.txt", DiffKind::LeftNewer),
+            ("both", DiffKind::Same),
+            ("c.txt", DiffKind::Different),
+            ("clash", DiffKind::Different),
+            // Within the mtime tolerance
+            ("d.txt", DiffKind::Same),
+            ("left.txt", DiffKind::LeftOnly),
+            ("right", DiffKind::RightOnly),
+        ]);
+        assert!(items[3].marks_left() && items[3].marks_right());
+        assert!(items[1].marks_left() && !items[1].marks_right());
+    }
+
+    #[test]
+    fn test_compare_by_content() {
+        let left = [entry("same.bin", 4, 1000), entry("edited.bin", 4, 9000), entry("grown.bin", 4, 1000)];
+        let right = [entry("same.bin", 4, 9000), entry("edited.bin", 4, 1000), entry("grown.bin", 8, 1000)];
+        let mut hashed = Vec::new();
+        let mut equal = |l: &FileEntry, _: &FileEntry| {
+            hashed.push(l.name.clone());
+            l.name == "same.bin"
+        };
+        let items = compare_entries(&left, &right, Some(&mut equal));
+        assert_eq!(kinds(&items), [
+            ("edited.bin", DiffKind::LeftNewer),
+            ("grown.bin", DiffKind::Different),
+            ("same.bin", DiffKind::Same),
+        ]);
+        // Files of different sizes are never read
+        assert_eq!(hashed, ["edited.bin", "same.bin"]);
+    }
+
+    #[test]
+    fn test_plan_respects_permissions() {
+        let left = [entry("new.txt", 1, 0), entry("old.txt", 1, 0), entry("same.txt", 1, 0), dir("clash")];
+        let right = [entry("old.txt", 1, 9000), entry("same.txt", 1, 0), entry("extra.txt", 1, 0), entry("clash", 1, 0)];
+        let items = compare_entries(&left, &right, None);
+
+        let all = SyncPermissions { left_write: true, left_delete: true, right_write: true, right_delete: true };
+        let plan_all = plan(items.clone(), all);
+        let actions: Vec<_> = plan_all.iter().map(|s| (s.item.name.as_str(), s.action)).collect();
+        assert_eq!(actions, [
+            ("clash", SyncAction::Skip),
+            ("extra.txt", SyncAction::CopyToLeft),
+            ("new.txt", SyncAction::CopyToRight),
+            ("old.txt", SyncAction::CopyToLeft),
+        ]);
+        assert_eq!(plan_all[0].choices, [SyncAction::Skip]);
+        assert_eq!(plan_all[1].choices, [SyncAction::CopyToLeft, SyncAction::DeleteRight, SyncAction::Skip]);
+
+        // A read-only right side (e.g. an archive) can only be copied from
+        let read_only_right = SyncPermissions { right_write: false, right_delete: false, ..all };
+        let mut plan_ro = plan(items, read_only_right);
+        assert_eq!(plan_ro[2].action, SyncAction::Skip);
+        assert_eq!(plan_ro[2].choices, [SyncAction::DeleteLeft, SyncAction::Skip]);
+        plan_ro[2].cycle(true);
+        assert_eq!(plan_ro[2].action, SyncAction::DeleteLeft);
+        plan_ro[2].set(SyncAction::CopyToRight);
+        assert_eq!(plan_ro[2].action, SyncAction::DeleteLeft);
+    }
+}
@@ -0,0 +1,498 @@
+//! Copy engine: copies files and trees as they are, keeping symlinks as
+//! links, hardlinked names sharing one copy, holes in sparse files and
+//! extended attributes. FIFOs, sockets and device nodes are recreated or
+//! left out.
+
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::utils::copy_file_with_progress;
+
+/// What a copy keeps besides the data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Copy symlinks as links, rather than what they point to
+    pub symlinks: bool,
+    /// Names hardlinked to one file stay hardlinked in the copy
+    pub hardlinks: bool,
+    /// Holes in sparse files stay holes
+    pub sparse: bool,
+    /// Copy extended attributes, which also hold ACLs on Linux
+    pub xattrs: bool,
+    /// Recreate FIFOs, sockets and device nodes instead of skipping them
+    pub special: bool,
+    /// Read back each copied file and compare its SHA-256 with the source's
+    pub verify: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self { symlinks: true, hardlinks: true, sparse: true, xattrs: true, special: false, verify: false }
+    }
+}
+
+impl CopyOptions {
+    /// Everything kept. A move across filesystems uses this, since it
+    /// leaves nothing behind.
+    pub const FAITHFUL: CopyOptions =
+        CopyOptions { symlinks: true, hardlinks: true, sparse: true, xattrs: true, special: true, verify: false };
+
+    /// Labels of the options, in the order the copy dialog lists them
+    pub const LABELS: [&str; 6] = [
+        "Copy symlinks as links",
+        "Preserve hardlinks",
+        "Keep sparse files sparse",
+        "Copy extended attributes and ACLs",
+        "Recreate FIFOs and devices (else skip)",
+        "Verify copies (SHA-256)",
+    ];
+
+    /// Option `i`, in the order of `LABELS`
+    pub fn get(&self, i: usize) -> bool {
+        [self.symlinks, self.hardlinks, self.sparse, self.xattrs, self.special, self.verify]
+            .get(i)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Flip option `i`, in the order of `LABELS`
+    pub fn toggle(&mut self, i: usize) {
+        let flags = [
+            &mut self.symlinks,
+            &mut self.hardlinks,
+            &mut self.sparse,
+            &mut self.xattrs,
+            &mut self.special,
+            &mut self.verify,
+        ];
+        if let Some(flag) = flags.into_iter().nth(i) {
+            *flag = !*flag;
+        }
+    }
+}
+
+/// Copies one or more trees with the same options. Hardlinks are kept
+/// across everything one copier copies.
+pub struct Copier {
+    options: CopyOptions,
+    cancel: Arc<AtomicBool>,
+    /// Copies of files with more than one name, by device and inode of the
+    /// original
+    linked: HashMap<(u64, u64), PathBuf>,
+    /// Special files left out
+    pub skipped: Vec<PathBuf>,
+}
+
+impl Copier {
+    pub fn new(options: CopyOptions, cancel: Arc<AtomicBool>) -> Self {
+        Self { options, cancel, linked: HashMap::new(), skipped: Vec::new() }
+    }
+
+    /// Copy `src`, whatever it is, to `dest`. `progress` gets the number of
+    /// bytes done after each chunk.
+    pub fn copy(&mut self, src: &Path, dest: &Path, progress: &dyn Fn(u64)) -> io::Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        let meta = fs::symlink_metadata(src)?;
+        if meta.file_type().is_symlink() {
+            #[cfg(unix)]
+            if self.options.symlinks {
+                return copy_symlink(src, dest, &meta);
+            }
+            let target = fs::metadata(src)?;
+            return self.copy_entry(src, dest, &target, progress);
+        }
+        self.copy_entry(src, dest, &meta, progress)
+    }
+
+    fn copy_entry(&mut self, src: &Path, dest: &Path, meta: &Metadata, progress: &dyn Fn(u64)) -> io::Result<()> {
+        if meta.is_dir() {
+            fs::create_dir_all(dest)?;
+            for entry in fs::read_dir(src)? {
+                let entry = entry?;
+                self.copy(&entry.path(), &dest.join(entry.file_name()), progress)?;
+            }
+            // Last, so creating the children doesn't change the mtime
+            self.copy_attributes(src, dest)
+        } else if meta.is_file() {
+            self.copy_file(src, dest, meta, progress)
+        } else {
+            self.copy_special(src, dest, meta)
+        }
+    }
+
+    fn copy_file(&mut self, src: &Path, dest: &Path, meta: &Metadata, progress: &dyn Fn(u64)) -> io::Result<()> {
+        let key = link_key(meta).filter(|_| self.options.hardlinks);
+        if let Some(first) = key.and_then(|key| self.linked.get(&key)) {
+            remove_existing(dest)?;
+            fs::hard_link(first, dest)?;
+            progress(meta.len());
+            return Ok(());
+        }
+
+        if self.options.sparse && is_sparse(meta) {
+            self.copy_sparse(src, dest, meta.len(), progress)?;
+        } else {
+            copy_file_with_progress(src, dest, &self.cancel, progress)?;
+        }
+        if self.options.verify {
+            self.verify(src, dest)?;
+        }
+        self.copy_attributes(src, dest)?;
+        if let Some(key) = key {
+            self.linked.insert(key, dest.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Copy only the parts of a sparse file that hold data, then extend
+    /// the copy to full length, so the holes stay holes
+    #[cfg(target_os = "linux")]
+    fn copy_sparse(&self, src: &Path, dest: &Path, len: u64, progress: &dyn Fn(u64)) -> io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let mut reader = File::open(src)?;
+        let mut writer = File::create(dest)?;
+        let fd = reader.as_raw_fd();
+        let mut copy = || -> io::Result<()> {
+            let mut pos = 0u64;
+            while pos < len {
+                // SAFETY: lseek on a descriptor we hold open
+                let data = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_DATA) };
+                if data < 0 {
+                    let err = io::Error::last_os_error();
+                    // ENXIO: only a hole is left
+                    if err.raw_os_error() == Some(libc::ENXIO) {
+                        break;
+                    }
+                    return Err(err);
+                }
+                // SAFETY: as above
+                let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+                if hole < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let (data, hole) = (data as u64, (hole as u64).min(len));
+                // Skipped holes count as done
+                progress(data - pos);
+                reader.seek(SeekFrom::Start(data))?;
+                writer.seek(SeekFrom::Start(data))?;
+                self.copy_bytes(&mut reader, &mut writer, hole - data, progress)?;
+                pos = hole;
+            }
+            progress(len.saturating_sub(pos));
+            writer.set_len(len)
+        };
+        let result = copy();
+        if result.is_err() {
+            drop(writer);
+            let _ = fs::remove_file(dest);
+        }
+        result
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn copy_sparse(&self, src: &Path, dest: &Path, _len: u64, progress: &dyn Fn(u64)) -> io::Result<()> {
+        copy_file_with_progress(src, dest, &self.cancel, progress).map(|_| ())
+    }
+
+    /// Copy `count` bytes from the reader's position to the writer's
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn copy_bytes(&self, reader: &mut File, writer: &mut File, count: u64, progress: &dyn Fn(u64)) -> io::Result<()> {
+        let mut buf = [0u8; 64 * 1024];
+        let mut left = count;
+        while left > 0 {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let want = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+            let n = reader.read(&mut buf[..want])?;
+            if n == 0 {
+                // The file shrank while being copied
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+            left -= n as u64;
+            progress(n as u64);
+        }
+        Ok(())
+    }
+
+    /// Read back the copy and compare it with the source. A copy that
+    /// doesn't match is removed.
+    fn verify(&self, src: &Path, dest: &Path) -> io::Result<()> {
+        let expected = sha256(&mut File::open(src)?, &self.cancel)?;
+        if sha256(&mut File::open(dest)?, &self.cancel)? != expected {
+            let _ = fs::remove_file(dest);
+            return Err(io::Error::other(format!(
+                "verification failed: {} does not match the source",
+                dest.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// FIFOs, sockets and devices: recreated with mknod, or skipped
+    fn copy_special(&mut self, src: &Path, dest: &Path, meta: &Metadata) -> io::Result<()> {
+        if !self.options.special {
+            self.skipped.push(src.to_path_buf());
+            return Ok(());
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            remove_existing(dest)?;
+            let path = c_path(dest)?;
+            // SAFETY: mknod with a valid C string; the mode carries the file type
+            let ret = unsafe { libc::mknod(path.as_ptr(), meta.mode() as libc::mode_t, meta.rdev() as libc::dev_t) };
+            if ret != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            self.copy_attributes(src, dest)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (dest, meta);
+            self.skipped.push(src.to_path_buf());
+            Ok(())
+        }
+    }
+
+    /// Permissions, modification time and, if asked, extended attributes
+    fn copy_attributes(&self, src: &Path, dest: &Path) -> io::Result<()> {
+        preserve_attributes(src, dest);
+        if self.options.xattrs {
+            copy_xattrs(src, dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 of everything `reader` has left. Fails with `Interrupted` once
+/// `cancel` is raised.
+pub fn sha256(reader: &mut dyn Read, cancel: &AtomicBool) -> io::Result<[u8; 32]> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(hasher.finalize().into()),
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Preserve file attributes (permissions, modification time) from src to dest.
+/// Best-effort — errors are silently ignored since the file data is already written.
+fn preserve_attributes(src: &Path, dest: &Path) {
+    if let Ok(meta) = fs::metadata(src) {
+        // Preserve modification time
+        if let Ok(mtime) = meta.modified() {
+            let _ = filetime::set_file_mtime(dest, filetime::FileTime::from_system_time(mtime));
+        }
+        // Preserve permissions (Unix only — Windows permissions are handled by std::fs::copy)
+        #[cfg(unix)]
+        {
+            let _ = fs::set_permissions(dest, meta.permissions());
+        }
+    }
+}
+
+/// Recreate a symlink, pointing where the original points
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dest: &Path, meta: &Metadata) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+    remove_existing(dest)?;
+    std::os::unix::fs::symlink(target, dest)?;
+    // A link has no permissions of its own, but it does have an mtime
+    let mtime = filetime::FileTime::from_last_modification_time(meta);
+    let _ = filetime::set_symlink_file_times(dest, mtime, mtime);
+    Ok(())
+}
+
+/// Make way for a link or node at `dest`, which can't be created over an
+/// existing file the way a copy's data can. Directories are left alone.
+fn remove_existing(dest: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(dest) {
+        Ok(meta) if !meta.is_dir() => fs::remove_file(dest),
+        _ => Ok(()),
+    }
+}
+
+/// Device and inode of a file with more than one name
+#[cfg(unix)]
+fn link_key(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (meta.nlink() > 1).then(|| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn link_key(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Whether fewer blocks are allocated than the size needs
+#[cfg(unix)]
+fn is_sparse(meta: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks().saturating_mul(512) < meta.len()
+}
+
+#[cfg(not(unix))]
+fn is_sparse(_meta: &Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn c_path(path: &Path) -> io::Result<std::ffi::CString> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Copy every extended attribute. Attributes the destination filesystem
+/// doesn't support, or that only root may set, are left out.
+#[cfg(target_os = "linux")]
+fn copy_xattrs(src: &Path, dest: &Path) -> io::Result<()> {
+    let skippable = |err: &io::Error| matches!(err.raw_os_error(), Some(libc::ENOTSUP) | Some(libc::EPERM));
+    let (src, dest) = (c_path(src)?, c_path(dest)?);
+
+    // SAFETY: a null buffer of size 0 asks for the size of the list
+    let size = unsafe { libc::listxattr(src.as_ptr(), std::ptr::null_mut(), 0) };
+    if size <= 0 {
+        let err = io::Error::last_os_error();
+        return if size == 0 || skippable(&err) { Ok(()) } else { Err(err) };
+    }
+    let mut names = vec![0u8; size as usize];
+    // SAFETY: the buffer is as long as we say
+    let size = unsafe { libc::listxattr(src.as_ptr(), names.as_mut_ptr().cast(), names.len()) };
+    if size < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    names.truncate(size as usize);
+
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let name = std::ffi::CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // SAFETY: as for the list, first the size and then the value
+        let len = unsafe { libc::getxattr(src.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut value = vec![0u8; len as usize];
+        // SAFETY: the buffer is as long as we say
+        let len = unsafe { libc::getxattr(src.as_ptr(), name.as_ptr(), value.as_mut_ptr().cast(), value.len()) };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: name and value are valid for the given length
+        let ret = unsafe { libc::setxattr(dest.as_ptr(), name.as_ptr(), value.as_ptr().cast(), len as usize, 0) };
+        if ret != 0 {
+            let err = io::Error::last_os_error();
+            if !skippable(&err) {
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_xattrs(_src: &Path, _dest: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+    use std::os::unix::fs::MetadataExt;
+
+    fn copy_tree(options: CopyOptions, src: &Path, dest: &Path) -> Copier {
+        let mut copier = Copier::new(options, Arc::new(AtomicBool::new(false)));
+        copier.copy(src, dest, &|_| {}).unwrap();
+        copier
+    }
+
+    #[test]
+    fn test_links_and_special_files() {
+        let dir = ScratchDir::new("copy-links");
+        let src = dir.join("src");
+        fs::create_dir(&src).unwrap();
+        fs::write(src.join("data"), "data").unwrap();
+        fs::hard_link(src.join("data"), src.join("same")).unwrap();
+        std::os::unix::fs::symlink("data", src.join("link")).unwrap();
+        let fifo = c_path(&src.join("fifo")).unwrap();
+        // SAFETY: valid C string
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+
+        let copier = copy_tree(CopyOptions::default(), &src, &dir.join("a"));
+        let a = dir.join("a");
+        assert_eq!(fs::read_link(a.join("link")).unwrap(), Path::new("data"));
+        assert_eq!(fs::metadata(a.join("data")).unwrap().ino(), fs::metadata(a.join("same")).unwrap().ino());
+        assert!(!a.join("fifo").exists());
+        assert_eq!(copier.skipped, [src.join("fifo")]);
+
+        let mut options = CopyOptions::default();
+        for i in [0, 1, 4] {
+            options.toggle(i);
+        }
+        let copier = copy_tree(options, &src, &dir.join("b"));
+        let b = dir.join("b");
+        assert!(fs::symlink_metadata(b.join("link")).unwrap().is_file());
+        assert_ne!(fs::metadata(b.join("data")).unwrap().ino(), fs::metadata(b.join("same")).unwrap().ino());
+        assert!(copier.skipped.is_empty());
+        use std::os::unix::fs::FileTypeExt;
+        assert!(fs::symlink_metadata(b.join("fifo")).unwrap().file_type().is_fifo());
+    }
+
+    #[test]
+    fn test_sparse_copy() {
+        let dir = ScratchDir::new("copy-sparse");
+        let src = dir.join("image");
+        let len = 8 * 1024 * 1024;
+        let mut file = File::create(&src).unwrap();
+        file.seek(SeekFrom::Start(4 * 1024 * 1024)).unwrap();
+        file.write_all(b"middle").unwrap();
+        file.set_len(len).unwrap();
+        drop(file);
+        if !is_sparse(&fs::metadata(&src).unwrap()) {
+            // The filesystem under the temp dir doesn't do holes
+            return;
+        }
+
+        let done = std::cell::Cell::new(0);
+        let mut copier = Copier::new(CopyOptions::default(), Arc::new(AtomicBool::new(false)));
+        copier.copy(&src, &dir.join("copy"), &|n| done.set(done.get() + n)).unwrap();
+        let copy = fs::metadata(dir.join("copy")).unwrap();
+        assert_eq!((copy.len(), done.get()), (len, len));
+        assert!(is_sparse(&copy));
+        assert_eq!(fs::read(dir.join("copy")).unwrap(), fs::read(&src).unwrap());
+    }
+
+    #[test]
+    fn test_verified_copy() {
+        let dir = ScratchDir::new("copy-verify");
+        fs::write(dir.join("data"), "checked").unwrap();
+        let options = CopyOptions { verify: true, ..CopyOptions::default() };
+        copy_tree(options, &dir.join("data"), &dir.join("copy"));
+        assert_eq!(fs::read(dir.join("copy")).unwrap(), b"checked");
+
+        let cancel = AtomicBool::new(false);
+        let digest = sha256(&mut &b"checked"[..], &cancel).unwrap();
+        assert_eq!(digest, sha256(&mut File::open(dir.join("copy")).unwrap(), &cancel).unwrap());
+        assert_ne!(digest, sha256(&mut &b"checke"[..], &cancel).unwrap());
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(sha256(&mut &b""[..], &cancel).unwrap_err().kind(), io::ErrorKind::Interrupted);
+    }
+}
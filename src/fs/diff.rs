@@ -0,0 +1,360 @@
+//! Recursive tree diff and per-file line diff
+//!
+//! [`diff_trees`] walks two directory trees through their providers, the left
+//! one being the old side, and lists every added, removed or modified entry
+//! under the directories that contain it. [`diff_text`] builds the unified
+//! line diff shown when a modified file is opened from that list.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use similar::{ChangeTag, TextDiff};
+
+use crate::fs::FileEntry;
+use crate::fs::compare::{self, DiffKind};
+use crate::providers::PanelProvider;
+
+/// Files larger than this are not read for a line diff
+pub const MAX_DIFF_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Unchanged lines kept around each change
+const CONTEXT_LINES: usize = 3;
+
+/// How an entry changed from the left tree to the right one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffStatus {
+    /// Only in the right tree
+    Added,
+    /// Only in the left tree
+    Removed,
+    /// Different size, time or content, or a file replaced by a directory
+    Modified,
+}
+
+impl DiffStatus {
+    /// Marker shown in front of the entry
+    pub fn marker(self) -> char {
+        match self {
+            DiffStatus::Added => '+',
+            DiffStatus::Removed => '-',
+            DiffStatus::Modified => '~',
+        }
+    }
+}
+
+/// One row of the tree diff
+#[derive(Clone, Debug)]
+pub struct DiffNode {
+    /// Path relative to the compared roots, '/'-separated
+    pub rel_path: String,
+    pub name: String,
+    /// Nesting level below the roots
+    pub depth: usize,
+    pub is_dir: bool,
+    /// None for a directory on both sides that has changes further down
+    pub status: Option<DiffStatus>,
+    pub left: Option<FileEntry>,
+    pub right: Option<FileEntry>,
+}
+
+impl DiffNode {
+    /// A file modified in place, which can be opened as a line diff
+    pub fn is_diffable(&self) -> bool {
+        self.status == Some(DiffStatus::Modified)
+            && matches!((&self.left, &self.right), (Some(l), Some(r)) if !l.is_dir && !r.is_dir)
+    }
+}
+
+/// Result of walking both trees
+#[derive(Clone, Debug, Default)]
+pub struct TreeDiff {
+    pub left_root: String,
+    pub right_root: String,
+    pub nodes: Vec<DiffNode>,
+    /// Directories that could not be listed, with the reason
+    pub errors: Vec<String>,
+}
+
+impl TreeDiff {
+    /// Number of entries with the given status
+    pub fn count(&self, status: DiffStatus) -> usize {
+        self.nodes.iter().filter(|n| n.status == Some(status)).count()
+    }
+}
+
+/// Diff the trees below `left_root` and `right_root`. Directories present on
+/// both sides are descended into (symlinked ones are not); a directory on one
+/// side only is listed as a single entry. With `by_content`, same-sized files
+/// are compared by SHA-256 instead of by modification time.
+pub fn diff_trees(
+    left: &Mutex<Box<dyn PanelProvider>>,
+    left_root: &str,
+    right: &Mutex<Box<dyn PanelProvider>>,
+    right_root: &str,
+    by_content: bool,
+    cancel: &AtomicBool,
+) -> TreeDiff {
+    let mut diff = TreeDiff {
+        left_root: left_root.to_string(),
+        right_root: right_root.to_string(),
+        ..TreeDiff::default()
+    };
+    let walk = Walk { left, right, by_content, cancel };
+    walk.diff_dir(left_root, right_root, "", 0, &mut diff);
+    diff
+}
+
+struct Walk<'a> {
+    left: &'a Mutex<Box<dyn PanelProvider>>,
+    right: &'a Mutex<Box<dyn PanelProvider>>,
+    by_content: bool,
+    cancel: &'a AtomicBool,
+}
+
+impl Walk<'_> {
+    fn diff_dir(&self, left_dir: &str, right_dir: &str, rel: &str, depth: usize, out: &mut TreeDiff) {
+        if self.cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let (Some(left_entries), Some(right_entries)) = (list(self.left, left_dir, out), list(self.right, right_dir, out))
+        else {
+            return;
+        };
+
+        let mut content_equal = |l: &FileEntry, r: &FileEntry| {
+            let left_hash = compare::provider_digest(self.left, &l.path, self.cancel);
+            left_hash.is_some() && left_hash == compare::provider_digest(self.right, &r.path, self.cancel)
+        };
+        let items = compare::compare_entries(
+            &left_entries,
+            &right_entries,
+            if self.by_content { Some(&mut content_equal) } else { None },
+        );
+
+        for item in items {
+            if self.cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            let rel_path = if rel.is_empty() { item.name.clone() } else { format!("{}/{}", rel, item.name) };
+            let status = match item.kind {
+                DiffKind::Same => None,
+                DiffKind::LeftOnly => Some(DiffStatus::Removed),
+                DiffKind::RightOnly => Some(DiffStatus::Added),
+                DiffKind::LeftNewer | DiffKind::RightNewer | DiffKind::Different => Some(DiffStatus::Modified),
+            };
+            let is_dir = item.left.as_ref().or(item.right.as_ref()).is_some_and(|e| e.is_dir);
+
+            let descend = match (&item.left, &item.right) {
+                (Some(l), Some(r)) if status.is_none() && l.is_dir && r.is_dir => {
+                    (!l.is_symlink && !r.is_symlink).then(|| (l.path.to_string_lossy().to_string(), r.path.to_string_lossy().to_string()))
+                }
+                _ => None,
+            };
+            if status.is_none() && descend.is_none() {
+                continue;
+            }
+
+            let header = out.nodes.len();
+            out.nodes.push(DiffNode {
+                rel_path: rel_path.clone(),
+                name: item.name,
+                depth,
+                is_dir,
+                status,
+                left: item.left,
+                right: item.right,
+            });
+            if let Some((left_sub, right_sub)) = descend {
+                self.diff_dir(&left_sub, &right_sub, &rel_path, depth + 1, out);
+                // Identical directories are left out of the tree
+                if out.nodes.len() == header + 1 {
+                    out.nodes.pop();
+                }
+            }
+        }
+    }
+}
+
+/// List a directory without "." and "..", recording a failure in `out`
+fn list(provider: &Mutex<Box<dyn PanelProvider>>, dir: &str, out: &mut TreeDiff) -> Option<Vec<FileEntry>> {
+    let mut provider = provider.lock().unwrap_or_else(|e| e.into_inner());
+    match provider.list_directory(dir) {
+        Ok(entries) => Some(entries.into_iter().filter(|e| e.name != "." && e.name != "..").collect()),
+        Err(e) => {
+            out.errors.push(format!("{}: {}", dir, e));
+            None
+        }
+    }
+}
+
+/// One row of a line diff
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    /// "@@ -start,len +start,len @@" header opening a group of changes
+    Hunk(String),
+    /// Unchanged line with its 1-based number on each side
+    Context { old: usize, new: usize, text: String },
+    Removed { old: usize, text: String },
+    Added { new: usize, text: String },
+    /// Shown instead of lines when there is nothing to diff line by line
+    Note(String),
+}
+
+/// Unified line diff of one file
+#[derive(Clone, Debug, Default)]
+pub struct FileDiff {
+    pub rel_path: String,
+    pub lines: Vec<DiffLine>,
+    /// Indices of the hunk headers in `lines`
+    pub hunks: Vec<usize>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl FileDiff {
+    /// A diff that only carries an explanation
+    pub fn note(rel_path: &str, message: impl Into<String>) -> Self {
+        FileDiff {
+            rel_path: rel_path.to_string(),
+            lines: vec![DiffLine::Note(message.into())],
+            ..FileDiff::default()
+        }
+    }
+}
+
+/// Diff the old (left) and new (right) contents of a file line by line.
+/// Content with a NUL byte is treated as binary; other invalid UTF-8 is
+/// decoded lossily.
+pub fn diff_text(rel_path: &str, old: &[u8], new: &[u8]) -> FileDiff {
+    if old.contains(&0) || new.contains(&0) {
+        return FileDiff::note(rel_path, "Binary files differ");
+    }
+    let old = String::from_utf8_lossy(old);
+    let new = String::from_utf8_lossy(new);
+    let text_diff = TextDiff::from_lines(old.as_ref(), new.as_ref());
+
+    let mut diff = FileDiff { rel_path: rel_path.to_string(), ..FileDiff::default() };
+    for group in text_diff.grouped_ops(CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+        diff.hunks.push(diff.lines.len());
+        diff.lines.push(DiffLine::Hunk(format!(
+            "@@ -{} +{} @@",
+            hunk_range(old_range.start, old_range.len()),
+            hunk_range(new_range.start, new_range.len()),
+        )));
+
+        for op in &group {
+            for change in text_diff.iter_changes(op) {
+                let text = change.value().trim_end_matches(['\n', '\r']).to_string();
+                let old = change.old_index().map_or(0, |i| i + 1);
+                let new = change.new_index().map_or(0, |i| i + 1);
+                diff.lines.push(match change.tag() {
+                    ChangeTag::Equal => DiffLine::Context { old, new, text },
+                    ChangeTag::Delete => {
+                        diff.removed += 1;
+                        DiffLine::Removed { old, text }
+                    }
+                    ChangeTag::Insert => {
+                        diff.added += 1;
+                        DiffLine::Added { new, text }
+                    }
+                });
+            }
+        }
+    }
+
+    if diff.lines.is_empty() {
+        diff.lines.push(DiffLine::Note("Contents are identical".to_string()));
+    }
+    diff
+}
+
+/// Hunk range in unified diff notation: an empty range names the line
+/// before it, a single line omits the length
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+    use std::path::Path;
+
+    fn write(path: &Path, data: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn test_diff_trees() {
+        let dir = ScratchDir::new("diff-trees");
+        let (old, new) = (dir.join("old"), dir.join("new"));
+        for root in [&old, &new] {
+            write(&root.join("same.txt"), "same\n");
+            write(&root.join("lib/unchanged/a.txt"), "a\n");
+            write(&root.join("lib/deep/keep.txt"), "keep\n");
+        }
+        write(&old.join("edited.txt"), "one\n");
+        write(&new.join("edited.txt"), "one two\n");
+        write(&old.join("lib/deep/gone.txt"), "gone\n");
+        write(&new.join("lib/deep/fresh.txt"), "fresh\n");
+        write(&new.join("docs/index.md"), "# docs\n");
+        write(&old.join("clash"), "file\n");
+        std::fs::create_dir_all(new.join("clash")).unwrap();
+
+        let left: Mutex<Box<dyn PanelProvider>> = Mutex::new(Box::new(crate::providers::LocalProvider::new()));
+        let right: Mutex<Box<dyn PanelProvider>> = Mutex::new(Box::new(crate::providers::LocalProvider::new()));
+        let cancel = AtomicBool::new(false);
+        let diff = diff_trees(&left, &old.to_string_lossy(), &right, &new.to_string_lossy(), true, &cancel);
+
+        let rows: Vec<_> = diff.nodes.iter()
+            .map(|n| (n.rel_path.as_str(), n.depth, n.status.map(DiffStatus::marker)))
+            .collect();
+        assert_eq!(rows, [
+            ("clash", 0, Some('~')),
+            ("docs", 0, Some('+')),
+            ("edited.txt", 0, Some('~')),
+            ("lib", 0, None),
+            ("lib/deep", 1, None),
+            ("lib/deep/fresh.txt", 2, Some('+')),
+            ("lib/deep/gone.txt", 2, Some('-')),
+        ]);
+        assert!(diff.errors.is_empty());
+        assert!(!diff.nodes[0].is_diffable());
+        assert!(diff.nodes[2].is_diffable());
+        assert_eq!(diff.count(DiffStatus::Added), 2);
+
+        let missing = diff_trees(&left, &dir.join("nope").to_string_lossy(), &right, &new.to_string_lossy(), false, &cancel);
+        assert!(missing.nodes.is_empty());
+        assert_eq!(missing.errors.len(), 1);
+
+    }
+
+    #[test]
+    fn test_diff_text() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let diff = diff_text("x.txt", old.as_bytes(), new.as_bytes());
+        assert_eq!((diff.added, diff.removed), (2, 1));
+        // The two changes are more than twice the context apart
+        assert_eq!(diff.hunks.len(), 2);
+        assert_eq!(diff.lines[0], DiffLine::Hunk("@@ -1,5 +1,5 @@".to_string()));
+        assert_eq!(diff.lines[2], DiffLine::Removed { old: 2, text: "b".to_string() });
+        assert_eq!(diff.lines[3], DiffLine::Added { new: 2, text: "B".to_string() });
+        assert_eq!(diff.lines[diff.hunks[1]], DiffLine::Hunk("@@ -8,3 +8,4 @@".to_string()));
+        assert_eq!(diff.lines.last(), Some(&DiffLine::Added { new: 11, text: "k".to_string() }));
+
+        let binary = diff_text("x.bin", b"a\0b", b"a\0c");
+        assert_eq!(binary.lines, [DiffLine::Note("Binary files differ".to_string())]);
+        assert!(diff_text("x.txt", b"same\n", b"same\n").hunks.is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+//! Editable bytes of a file for the hex editor
+//!
+//! [`EditBuffer`] is a piece table: the file's bytes (memory-mapped, so
+//! large files are never read in full) plus an append-only buffer of typed
+//! bytes, stitched together by a list of pieces. Every edit replaces the
+//! piece list, and undo/redo swap whole piece lists. Bytes that come from
+//! the typed buffer are the changed ones.
+//!
+//! When edits only overwrite bytes, [`EditBuffer::save`] writes just those
+//! bytes back into the file; once bytes were inserted or deleted the file is
+//! rewritten through a temporary file next to it.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Most edits that can be undone
+const UNDO_LIMIT: usize = 1000;
+
+/// Bytes of the file as opened
+#[derive(Clone, Debug)]
+enum Source {
+    Mapped(Arc<memmap2::Mmap>),
+    Bytes(Vec<u8>),
+}
+
+impl Source {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Source::Mapped(mmap) => mmap,
+            Source::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+/// A run of bytes from the file (`added` false) or from the typed bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Piece {
+    added: bool,
+    start: usize,
+    len: usize,
+}
+
+/// A file's bytes with the edits made to them so far
+#[derive(Clone, Debug)]
+pub struct EditBuffer {
+    source: Source,
+    added: Vec<u8>,
+    pieces: Vec<Piece>,
+    /// Piece lists to go back to, with the offset each edit was made at
+    undo: Vec<(Vec<Piece>, usize)>,
+    redo: Vec<(Vec<Piece>, usize)>,
+}
+
+impl EditBuffer {
+    /// Open the file at `path` for editing
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // Empty files can't be mapped
+        let source = if file.metadata()?.len() == 0 {
+            Source::Bytes(Vec::new())
+        } else {
+            Source::Mapped(Arc::new(unsafe { memmap2::Mmap::map(&file)? }))
+        };
+        Ok(Self::with_source(source))
+    }
+
+    fn with_source(source: Source) -> Self {
+        let len = source.bytes().len();
+        let pieces = if len > 0 { vec![Piece { added: false, start: 0, len }] } else { Vec::new() };
+        Self { source, added: Vec::new(), pieces, undo: Vec::new(), redo: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pieces.iter().map(|p| p.len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// Whether the bytes differ from the file as opened
+    pub fn is_modified(&self) -> bool {
+        let original = self.source.bytes().len();
+        !(original == 0 && self.is_empty() || self.pieces == [Piece { added: false, start: 0, len: original }])
+    }
+
+    fn piece_bytes(&self, piece: &Piece) -> &[u8] {
+        let bytes = if piece.added { &self.added } else { self.source.bytes() };
+        &bytes[piece.start..piece.start + piece.len]
+    }
+
+    /// Up to `len` bytes from `offset`, each with whether it was changed
+    pub fn read(&self, offset: usize, len: usize) -> Vec<(u8, bool)> {
+        let end = offset.saturating_add(len);
+        let mut out = Vec::with_capacity(len.min(self.len()));
+        let mut pos = 0;
+        for piece in &self.pieces {
+            let (from, to) = (pos.max(offset), (pos + piece.len).min(end));
+            if from < to {
+                let bytes = self.piece_bytes(piece);
+                out.extend(bytes[from - pos..to - pos].iter().map(|&b| (b, piece.added)));
+            }
+            pos += piece.len;
+            if pos >= end {
+                break;
+            }
+        }
+        out
+    }
+
+    /// The byte at `offset`, if there is one
+    pub fn get(&self, offset: usize) -> Option<u8> {
+        self.read(offset, 1).first().map(|&(b, _)| b)
+    }
+
+    /// Replace `remove` bytes at `offset` with `insert`, as one undoable
+    /// edit. Overwriting is removing as many bytes as are inserted.
+    pub fn replace(&mut self, offset: usize, remove: usize, insert: &[u8]) {
+        let len = self.len();
+        let offset = offset.min(len);
+        let end = offset.saturating_add(remove).min(len);
+        if end == offset && insert.is_empty() {
+            return;
+        }
+
+        let start = self.added.len();
+        self.added.extend_from_slice(insert);
+        let mut pieces = slice_pieces(&self.pieces, 0, offset);
+        pieces.push(Piece { added: true, start, len: insert.len() });
+        pieces.extend(slice_pieces(&self.pieces, end, len));
+
+        let previous = std::mem::replace(&mut self.pieces, merge_pieces(pieces));
+        self.undo.push((previous, offset));
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Take back the last edit. Returns the offset it was made at.
+    pub fn undo(&mut self) -> Option<usize> {
+        let (pieces, offset) = self.undo.pop()?;
+        let current = std::mem::replace(&mut self.pieces, pieces);
+        self.redo.push((current, offset));
+        Some(offset)
+    }
+
+    /// Make the last undone edit again. Returns the offset it was made at.
+    pub fn redo(&mut self) -> Option<usize> {
+        let (pieces, offset) = self.redo.pop()?;
+        let current = std::mem::replace(&mut self.pieces, pieces);
+        self.undo.push((current, offset));
+        Some(offset)
+    }
+
+    /// Whether saving only has to overwrite bytes: the length is unchanged
+    /// and every byte from the file is still at its own offset
+    pub fn saves_in_place(&self) -> bool {
+        let mut pos = 0;
+        for piece in &self.pieces {
+            if !piece.added && piece.start != pos {
+                return false;
+            }
+            pos += piece.len;
+        }
+        pos == self.source.bytes().len()
+    }
+
+    /// Write the edits to `path`, the file this buffer was opened from, and
+    /// start over from the saved file. Undo history does not survive a save.
+    pub fn save(&mut self, path: &Path) -> io::Result<()> {
+        if self.saves_in_place() {
+            let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+            let mut pos = 0;
+            for piece in &self.pieces {
+                if piece.added {
+                    file.seek(SeekFrom::Start(pos as u64))?;
+                    file.write_all(self.piece_bytes(piece))?;
+                }
+                pos += piece.len;
+            }
+            file.sync_all()?;
+        } else {
+            self.rewrite(path)?;
+        }
+        // After a rewrite the old map is gone, so the pieces can't be kept
+        *self = Self::open(path).inspect_err(|_| *self = Self::with_source(Source::Bytes(Vec::new())))?;
+        Ok(())
+    }
+
+    /// Write all bytes to a temporary file next to `path` and move it over
+    /// the original, keeping the original's permissions
+    fn rewrite(&mut self, path: &Path) -> io::Result<()> {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let temp = path.with_file_name(format!(".{}.bark-save", name));
+        let written = (|| {
+            let mut out = BufWriter::new(File::create(&temp)?);
+            for piece in &self.pieces {
+                out.write_all(self.piece_bytes(piece))?;
+            }
+            let file = out.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+            std::fs::set_permissions(&temp, std::fs::metadata(path)?.permissions())
+        })();
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+
+        // The map has to go before the file can be replaced on Windows
+        let source = std::mem::replace(&mut self.source, Source::Bytes(Vec::new()));
+        if let Err(e) = std::fs::rename(&temp, path) {
+            self.source = source;
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// The part of `pieces` covering bytes `from..to`
+fn slice_pieces(pieces: &[Piece], from: usize, to: usize) -> Vec<Piece> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    for piece in pieces {
+        let (start, end) = (pos.max(from), (pos + piece.len).min(to));
+        if start < end {
+            out.push(Piece { added: piece.added, start: piece.start + start - pos, len: end - start });
+        }
+        pos += piece.len;
+    }
+    out
+}
+
+/// Drop empty pieces and join pieces that continue each other, so typing
+/// byte after byte keeps the list short
+fn merge_pieces(pieces: Vec<Piece>) -> Vec<Piece> {
+    let mut out: Vec<Piece> = Vec::with_capacity(pieces.len());
+    for piece in pieces.into_iter().filter(|p| p.len > 0) {
+        match out.last_mut() {
+            Some(last) if last.added == piece.added && last.start + last.len == piece.start => last.len += piece.len,
+            _ => out.push(piece),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    fn bytes(buffer: &EditBuffer) -> Vec<u8> {
+        buffer.read(0, buffer.len()).into_iter().map(|(b, _)| b).collect()
+    }
+
+    #[test]
+    fn test_edit_undo_redo() {
+        let mut buffer = EditBuffer::with_source(Source::Bytes(b"hello world".to_vec()));
+        assert!(!buffer.is_modified());
+
+        buffer.replace(0, 1, b"J");
+        buffer.replace(5, 0, b",");
+        buffer.replace(7, 5, b"there");
+        assert_eq!(bytes(&buffer), b"Jello, there");
+        assert_eq!(buffer.read(0, 3), [(b'J', true), (b'e', false), (b'l', false)]);
+        assert!(buffer.is_modified() && !buffer.saves_in_place());
+
+        assert_eq!(buffer.undo(), Some(7));
+        assert_eq!(bytes(&buffer), b"Jello, world");
+        assert_eq!(buffer.undo(), Some(5));
+        assert!(buffer.saves_in_place());
+        assert_eq!(buffer.undo(), Some(0));
+        assert!(!buffer.is_modified());
+        assert_eq!(buffer.undo(), None);
+
+        assert_eq!(buffer.redo(), Some(0));
+        assert_eq!(bytes(&buffer), b"Jello world");
+        // A new edit drops what could be redone
+        buffer.replace(buffer.len(), 0, b"!");
+        assert!(buffer.redo.is_empty());
+        assert_eq!(bytes(&buffer), b"Jello world!");
+        buffer.replace(0, 100, b"");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_typing_keeps_pieces_short() {
+        let mut buffer = EditBuffer::with_source(Source::Bytes(vec![0; 64]));
+        for (i, b) in (10..20u8).enumerate() {
+            buffer.replace(8 + i, 1, &[b]);
+        }
+        assert_eq!(buffer.pieces.len(), 3);
+        assert_eq!(buffer.get(9), Some(11));
+        assert_eq!(buffer.get(64), None);
+    }
+
+    #[test]
+    fn test_save() {
+        let dir = ScratchDir::new("edit");
+        let path = dir.join("data.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        // Overwrites go straight into the file
+        let mut buffer = EditBuffer::open(&path).unwrap();
+        buffer.replace(2, 2, b"ab");
+        buffer.replace(9, 1, b"z");
+        assert!(buffer.saves_in_place());
+        buffer.save(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"01ab45678z");
+        assert!(!buffer.is_modified() && buffer.undo.is_empty());
+
+        // Inserts and deletes rewrite it
+        buffer.replace(0, 2, b"");
+        buffer.replace(buffer.len(), 0, b"\n");
+        buffer.save(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"ab45678z\n");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -199,6 +199,25 @@ fn get_username(uid: u32) -> String {
     }
 }
 
+/// Resolve a user name (or a numeric uid) to a uid
+#[cfg(unix)]
+pub fn lookup_uid(name: &str) -> Option<u32> {
+    if let Ok(uid) = name.parse::<u32>() {
+        return Some(uid);
+    }
+    let c_name = std::ffi::CString::new(name).ok()?;
+
+    // SAFETY: getpwnam is called with a valid NUL-terminated string
+    unsafe {
+        let pw = libc::getpwnam(c_name.as_ptr());
+        if pw.is_null() {
+            None
+        } else {
+            Some((*pw).pw_uid)
+        }
+    }
+}
+
 /// Enumerate all system users, returning sorted unique names
 #[cfg(unix)]
 pub fn enumerate_users() -> Vec<String> {
@@ -0,0 +1,833 @@
+//! Find Files search (Alt+/)
+//!
+//! The dialog edits a [`FindOptions`], which [`FindQuery::new`] validates
+//! and compiles. [`search`] walks a local tree on several threads;
+//! [`search_provider`] walks a remote server or archive through its
+//! provider. Both count progress in a shared [`FindProgress`] and stop once
+//! the cancel flag is set.
+
+use std::collections::VecDeque;
+use std::fs::{File, FileType, Metadata};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use ignore::{WalkBuilder, WalkState};
+use regex::Regex;
+
+use crate::fs::FileEntry;
+use crate::providers::PanelProvider;
+use crate::utils::glob_to_regex;
+
+/// Remote files larger than this are not searched for content
+pub const REMOTE_CONTENT_LIMIT: u64 = 32 * 1024 * 1024;
+
+/// Longest stretch of a file searched at once; longer lines are searched
+/// in overlapping pieces
+const CONTENT_WINDOW: usize = 64 * 1024;
+
+/// What a piece of a long line keeps of the one before it. Matches longer
+/// than this that straddle a cut are missed.
+const CONTENT_OVERLAP: usize = 4 * 1024;
+
+/// Which kind of entry a search returns
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileTypeFilter {
+    #[default]
+    File,
+    Dir,
+    Symlink,
+    Any,
+}
+
+impl FileTypeFilter {
+    /// All filters in the order they are offered in the dialog
+    pub const ALL: [FileTypeFilter; 4] = [
+        FileTypeFilter::File,
+        FileTypeFilter::Dir,
+        FileTypeFilter::Symlink,
+        FileTypeFilter::Any,
+    ];
+
+    /// Name shown in the dialog
+    pub fn label(self) -> &'static str {
+        match self {
+            FileTypeFilter::File => "Files",
+            FileTypeFilter::Dir => "Directories",
+            FileTypeFilter::Symlink => "Symlinks",
+            FileTypeFilter::Any => "Anything",
+        }
+    }
+
+    /// Next (or previous) filter, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        let idx = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        let len = Self::ALL.len();
+        let next = if forward { (idx + 1) % len } else { (idx + len - 1) % len };
+        Self::ALL[next]
+    }
+}
+
+/// Focusable elements of the Find Files dialog, in Tab order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FindField {
+    Pattern,
+    PatternCase,
+    PatternRegex,
+    Content,
+    ContentCase,
+    ContentRegex,
+    Path,
+    MinSize,
+    MaxSize,
+    NewerThan,
+    OlderThan,
+    Owner,
+    Permissions,
+    FileType,
+    Recursive,
+    SkipIgnored,
+    Search,
+    Cancel,
+}
+
+impl FindField {
+    pub const ALL: [FindField; 18] = [
+        FindField::Pattern,
+        FindField::PatternCase,
+        FindField::PatternRegex,
+        FindField::Content,
+        FindField::ContentCase,
+        FindField::ContentRegex,
+        FindField::Path,
+        FindField::MinSize,
+        FindField::MaxSize,
+        FindField::NewerThan,
+        FindField::OlderThan,
+        FindField::Owner,
+        FindField::Permissions,
+        FindField::FileType,
+        FindField::Recursive,
+        FindField::SkipIgnored,
+        FindField::Search,
+        FindField::Cancel,
+    ];
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let idx = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(idx + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    pub fn is_checkbox(self) -> bool {
+        matches!(
+            self,
+            FindField::PatternCase | FindField::PatternRegex | FindField::ContentCase
+                | FindField::ContentRegex | FindField::Recursive | FindField::SkipIgnored
+        )
+    }
+}
+
+/// Everything entered in the Find Files dialog
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FindOptions {
+    /// File name pattern: a glob, or a regex if `pattern_regex` is set
+    pub pattern: String,
+    pub pattern_case_sensitive: bool,
+    pub pattern_regex: bool,
+    /// Text to look for inside files (empty = don't search content)
+    pub content: String,
+    pub content_case_sensitive: bool,
+    pub content_regex: bool,
+    /// Directory to start in
+    pub path: String,
+    /// Size bounds such as "10K" or "2M" (empty = unbounded)
+    pub min_size: String,
+    pub max_size: String,
+    /// Modification time bounds: "YYYY-MM-DD" (UTC) or an age such as "7d"
+    pub newer_than: String,
+    pub older_than: String,
+    /// User name or uid
+    pub owner: String,
+    /// Octal permission bits that must all be set, e.g. "111"
+    pub permissions: String,
+    pub file_type: FileTypeFilter,
+    pub recursive: bool,
+    /// Skip paths matched by .gitignore / .ignore files
+    pub skip_ignored: bool,
+}
+
+impl FindOptions {
+    /// Dialog defaults for a search starting in `path`
+    pub fn new(path: String) -> Self {
+        Self {
+            pattern: "*".to_string(),
+            pattern_case_sensitive: false,
+            pattern_regex: false,
+            content: String::new(),
+            content_case_sensitive: false,
+            content_regex: false,
+            path,
+            min_size: String::new(),
+            max_size: String::new(),
+            newer_than: String::new(),
+            older_than: String::new(),
+            owner: String::new(),
+            permissions: String::new(),
+            file_type: FileTypeFilter::File,
+            recursive: true,
+            skip_ignored: false,
+        }
+    }
+
+    /// The text behind a text field, or None for other fields
+    pub fn text(&self, field: FindField) -> Option<&String> {
+        match field {
+            FindField::Pattern => Some(&self.pattern),
+            FindField::Content => Some(&self.content),
+            FindField::Path => Some(&self.path),
+            FindField::MinSize => Some(&self.min_size),
+            FindField::MaxSize => Some(&self.max_size),
+            FindField::NewerThan => Some(&self.newer_than),
+            FindField::OlderThan => Some(&self.older_than),
+            FindField::Owner => Some(&self.owner),
+            FindField::Permissions => Some(&self.permissions),
+            _ => None,
+        }
+    }
+
+    pub fn text_mut(&mut self, field: FindField) -> Option<&mut String> {
+        match field {
+            FindField::Pattern => Some(&mut self.pattern),
+            FindField::Content => Some(&mut self.content),
+            FindField::Path => Some(&mut self.path),
+            FindField::MinSize => Some(&mut self.min_size),
+            FindField::MaxSize => Some(&mut self.max_size),
+            FindField::NewerThan => Some(&mut self.newer_than),
+            FindField::OlderThan => Some(&mut self.older_than),
+            FindField::Owner => Some(&mut self.owner),
+            FindField::Permissions => Some(&mut self.permissions),
+            _ => None,
+        }
+    }
+
+    /// The value behind a checkbox, or None for other fields
+    pub fn flag(&self, field: FindField) -> Option<bool> {
+        match field {
+            FindField::PatternCase => Some(self.pattern_case_sensitive),
+            FindField::PatternRegex => Some(self.pattern_regex),
+            FindField::ContentCase => Some(self.content_case_sensitive),
+            FindField::ContentRegex => Some(self.content_regex),
+            FindField::Recursive => Some(self.recursive),
+            FindField::SkipIgnored => Some(self.skip_ignored),
+            _ => None,
+        }
+    }
+
+    /// Flip a checkbox (no-op for other fields)
+    pub fn toggle(&mut self, field: FindField) {
+        let flag = match field {
+            FindField::PatternCase => &mut self.pattern_case_sensitive,
+            FindField::PatternRegex => &mut self.pattern_regex,
+            FindField::ContentCase => &mut self.content_case_sensitive,
+            FindField::ContentRegex => &mut self.content_regex,
+            FindField::Recursive => &mut self.recursive,
+            FindField::SkipIgnored => &mut self.skip_ignored,
+            _ => return,
+        };
+        *flag = !*flag;
+    }
+
+    /// Short description for result messages, e.g. "matching '*.rs' containing 'TODO'"
+    pub fn describe(&self) -> String {
+        if self.content.is_empty() {
+            format!("matching '{}'", self.pattern)
+        } else {
+            format!("matching '{}' containing '{}'", self.pattern, self.content)
+        }
+    }
+}
+
+/// Search counters shared with the UI while a search runs
+#[derive(Debug, Default)]
+pub struct FindProgress {
+    /// Entries looked at so far
+    pub scanned: AtomicUsize,
+    /// Entries that matched so far
+    pub found: AtomicUsize,
+    current_dir: Mutex<String>,
+}
+
+impl FindProgress {
+    /// Directory most recently entered by one of the walker threads
+    pub fn current_dir(&self) -> String {
+        self.current_dir.lock().map(|d| d.clone()).unwrap_or_default()
+    }
+
+    fn enter_dir(&self, path: &Path) {
+        if let Ok(mut dir) = self.current_dir.lock() {
+            *dir = path.to_string_lossy().to_string();
+        }
+    }
+}
+
+/// A validated, compiled search
+#[derive(Debug)]
+pub struct FindQuery {
+    name: Regex,
+    content: Option<regex::bytes::Regex>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    newer_than: Option<SystemTime>,
+    older_than: Option<SystemTime>,
+    /// Owner name as entered; remote entries are compared by name
+    owner: Option<String>,
+    /// The owner resolved to a local uid (None if no such local user)
+    owner_uid: Option<u32>,
+    permissions: Option<u32>,
+    file_type: FileTypeFilter,
+    recursive: bool,
+    skip_ignored: bool,
+}
+
+impl FindQuery {
+    /// Compile the dialog input, reporting the first invalid field
+    pub fn new(options: &FindOptions) -> Result<Self, String> {
+        if options.pattern.is_empty() {
+            return Err("Pattern cannot be empty".to_string());
+        }
+        let name_pattern = if options.pattern_regex {
+            with_case(&options.pattern, options.pattern_case_sensitive)
+        } else {
+            glob_to_regex(&options.pattern, options.pattern_case_sensitive)
+        };
+        let name = Regex::new(&name_pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+
+        let content = if options.content.is_empty() {
+            None
+        } else {
+            let text = if options.content_regex {
+                options.content.clone()
+            } else {
+                regex::escape(&options.content)
+            };
+            let re = regex::bytes::Regex::new(&with_case(&text, options.content_case_sensitive))
+                .map_err(|e| format!("Invalid content pattern: {}", e))?;
+            Some(re)
+        };
+
+        let owner = Some(options.owner.trim()).filter(|o| !o.is_empty()).map(str::to_string);
+        let now = SystemTime::now();
+        Ok(Self {
+            name,
+            content,
+            min_size: parse_size(&options.min_size)?,
+            max_size: parse_size(&options.max_size)?,
+            newer_than: parse_time(&options.newer_than, now)?,
+            older_than: parse_time(&options.older_than, now)?,
+            owner_uid: owner.as_deref().and_then(local_uid),
+            owner,
+            permissions: parse_permissions(&options.permissions)?,
+            file_type: options.file_type,
+            recursive: options.recursive,
+            skip_ignored: options.skip_ignored,
+        })
+    }
+
+    /// Check one entry against every filter. Cheap checks run first so the
+    /// content scan only happens for otherwise matching files.
+    fn matches(&self, path: &Path, file_type: FileType, cancel: &AtomicBool) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        if !self.name.is_match(&name.to_string_lossy()) {
+            return false;
+        }
+
+        // Filters look at the link target; a dangling link falls back to the link itself
+        let Ok(meta) = std::fs::metadata(path).or_else(|_| std::fs::symlink_metadata(path)) else {
+            return false;
+        };
+        let type_ok = match self.file_type {
+            FileTypeFilter::File => meta.is_file(),
+            FileTypeFilter::Dir => meta.is_dir(),
+            FileTypeFilter::Symlink => file_type.is_symlink(),
+            FileTypeFilter::Any => true,
+        };
+        if !type_ok || !self.metadata_matches(&meta) {
+            return false;
+        }
+
+        match &self.content {
+            Some(re) => meta.is_file() && file_contains(path, re, cancel),
+            None => true,
+        }
+    }
+
+    fn metadata_matches(&self, meta: &Metadata) -> bool {
+        if !self.size_and_time_match(meta.is_file(), meta.len(), meta.modified().ok()) {
+            return false;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if self.owner.is_some() && self.owner_uid != Some(meta.uid()) {
+                return false;
+            }
+            if self.permissions.is_some_and(|bits| meta.mode() & bits != bits) {
+                return false;
+            }
+        }
+        #[cfg(not(unix))]
+        if self.owner.is_some() || self.permissions.is_some() {
+            return false;
+        }
+        true
+    }
+
+    fn size_and_time_match(&self, is_file: bool, size: u64, modified: Option<SystemTime>) -> bool {
+        // Sizes only make sense for files
+        if self.min_size.is_some() || self.max_size.is_some() {
+            if !is_file {
+                return false;
+            }
+            if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+                return false;
+            }
+        }
+
+        if self.newer_than.is_some() || self.older_than.is_some() {
+            let Some(modified) = modified else {
+                return false;
+            };
+            if self.newer_than.is_some_and(|t| modified < t) || self.older_than.is_some_and(|t| modified > t) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Check an entry listed by a provider. Symlinks count as whatever the
+    /// provider says they point to, like local ones.
+    fn entry_matches(&self, entry: &FileEntry, provider: &mut dyn PanelProvider, cancel: &AtomicBool) -> bool {
+        if !self.name.is_match(&entry.name) {
+            return false;
+        }
+        let type_ok = match self.file_type {
+            FileTypeFilter::File => !entry.is_dir,
+            FileTypeFilter::Dir => entry.is_dir,
+            FileTypeFilter::Symlink => entry.is_symlink,
+            FileTypeFilter::Any => true,
+        };
+        if !type_ok || !self.size_and_time_match(!entry.is_dir, entry.size, entry.modified) {
+            return false;
+        }
+        if self.owner.as_ref().is_some_and(|owner| *owner != entry.owner) {
+            return false;
+        }
+        if self.permissions.is_some_and(|bits| entry.permissions & bits != bits) {
+            return false;
+        }
+
+        match &self.content {
+            Some(re) => {
+                !entry.is_dir
+                    && entry.size <= REMOTE_CONTENT_LIMIT
+                    && provider_file_contains(provider, &entry.path.to_string_lossy(), re, cancel)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Walk `start` and return every entry matching `query`, sorted by path.
+/// When `cancel` is set the walk stops and the matches found so far are
+/// returned.
+pub fn search(start: &Path, query: &FindQuery, progress: &FindProgress, cancel: &AtomicBool) -> Vec<PathBuf> {
+    let mut builder = WalkBuilder::new(start);
+    builder
+        .standard_filters(false)
+        .follow_links(false)
+        .threads(0)
+        .max_depth(if query.recursive { None } else { Some(1) });
+    if query.skip_ignored {
+        builder
+            .git_ignore(true)
+            .git_exclude(true)
+            .git_global(true)
+            .ignore(true)
+            .parents(true)
+            .require_git(false)
+            .filter_entry(|entry| entry.file_name() != ".git");
+    }
+
+    let matches = Mutex::new(Vec::new());
+    builder.build_parallel().run(|| {
+        Box::new(|result| {
+            if cancel.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            // Unreadable directories are skipped, as before
+            let Ok(entry) = result else {
+                return WalkState::Continue;
+            };
+            if entry.depth() == 0 {
+                return WalkState::Continue;
+            }
+            let Some(file_type) = entry.file_type() else {
+                return WalkState::Continue;
+            };
+
+            progress.scanned.fetch_add(1, Ordering::Relaxed);
+            if file_type.is_dir() {
+                progress.enter_dir(entry.path());
+            }
+            if query.matches(entry.path(), file_type, cancel) {
+                progress.found.fetch_add(1, Ordering::Relaxed);
+                if let Ok(mut matches) = matches.lock() {
+                    matches.push(entry.into_path());
+                }
+            }
+            WalkState::Continue
+        })
+    });
+
+    let mut matches = matches.into_inner().unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+/// Walk `start` through a provider and return the matching entries, sorted
+/// by path. Directories are listed breadth-first, locking the provider for
+/// one directory at a time. Symlinked directories are not followed and
+/// .gitignore files are not consulted.
+pub fn search_provider(
+    provider: &Mutex<Box<dyn PanelProvider>>,
+    start: &str,
+    query: &FindQuery,
+    progress: &FindProgress,
+    cancel: &AtomicBool,
+) -> Vec<FileEntry> {
+    let mut matches = Vec::new();
+    let mut pending = VecDeque::from([start.to_string()]);
+
+    while let Some(dir) = pending.pop_front() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        progress.enter_dir(Path::new(&dir));
+
+        let mut provider = provider.lock().unwrap_or_else(|e| e.into_inner());
+        // Unreadable directories are skipped, as for local searches
+        let Ok(entries) = provider.list_directory(&dir) else {
+            continue;
+        };
+        for entry in entries {
+            if entry.name == ".." || entry.name == "." {
+                continue;
+            }
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            progress.scanned.fetch_add(1, Ordering::Relaxed);
+            if query.recursive && entry.is_dir && !entry.is_symlink {
+                pending.push_back(entry.path.to_string_lossy().to_string());
+            }
+            if query.entry_matches(&entry, provider.as_mut(), cancel) {
+                progress.found.fetch_add(1, Ordering::Relaxed);
+                matches.push(entry);
+            }
+        }
+    }
+
+    matches.sort_by(|a, b| a.path.cmp(&b.path));
+    matches
+}
+
+/// Scan a file line by line, so large and non-UTF-8 files are searched too
+fn file_contains(path: &Path, re: &regex::bytes::Regex, cancel: &AtomicBool) -> bool {
+    match File::open(path) {
+        Ok(file) => reader_contains(file, re, cancel),
+        Err(_) => false,
+    }
+}
+
+/// Stream a provider file through the same line scan
+fn provider_file_contains(provider: &mut dyn PanelProvider, path: &str, re: &regex::bytes::Regex, cancel: &AtomicBool) -> bool {
+    match provider.open_read(path) {
+        Ok(reader) => reader_contains(reader.take(REMOTE_CONTENT_LIMIT), re, cancel),
+        Err(_) => false,
+    }
+}
+
+fn reader_contains(reader: impl Read, re: &regex::bytes::Regex, cancel: &AtomicBool) -> bool {
+    let mut reader = BufReader::with_capacity(CONTENT_WINDOW, reader);
+    let mut line = Vec::new();
+    loop {
+        // Up to the end of the line, but no more than a window: a file
+        // without newlines must not end up in memory whole
+        let room = (CONTENT_WINDOW - line.len()) as u64;
+        match (&mut reader).take(room).read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {
+                if re.is_match(&line) {
+                    return true;
+                }
+                if cancel.load(Ordering::Relaxed) {
+                    return false;
+                }
+            }
+        }
+        if line.ends_with(b"\n") {
+            line.clear();
+        } else {
+            // Cut mid-line: keep the tail so a match across the cut is found
+            line.drain(..line.len().saturating_sub(CONTENT_OVERLAP));
+        }
+    }
+}
+
+fn with_case(pattern: &str, case_sensitive: bool) -> String {
+    if case_sensitive {
+        pattern.to_string()
+    } else {
+        format!("(?i){}", pattern)
+    }
+}
+
+/// Parse a size such as "512", "10K", "1.5M" or "2GB" (binary units)
+fn parse_size(input: &str) -> Result<Option<u64>, String> {
+    let s = input.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || format!("Invalid size '{}': use a number with optional K, M, G or T", s);
+
+    let upper = s.to_ascii_uppercase();
+    let upper = upper.strip_suffix("IB").or_else(|| upper.strip_suffix('B')).unwrap_or(&upper);
+    let (number, multiplier) = match upper.chars().last() {
+        Some('K') => (&upper[..upper.len() - 1], 1u64 << 10),
+        Some('M') => (&upper[..upper.len() - 1], 1u64 << 20),
+        Some('G') => (&upper[..upper.len() - 1], 1u64 << 30),
+        Some('T') => (&upper[..upper.len() - 1], 1u64 << 40),
+        _ => (upper, 1),
+    };
+    let value: f64 = number.trim().parse().map_err(|_| invalid())?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(invalid());
+    }
+    Ok(Some((value * multiplier as f64) as u64))
+}
+
+/// Parse "YYYY-MM-DD" (midnight UTC, like the panel's dates) or an age
+/// such as "30m", "12h", "7d" or "2w" counted back from `now`
+fn parse_time(input: &str, now: SystemTime) -> Result<Option<SystemTime>, String> {
+    let s = input.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || format!("Invalid date '{}': use YYYY-MM-DD or an age like 7d", s);
+
+    if let Some(unit) = s.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+        let amount: u64 = s[..s.len() - 1].trim().parse().map_err(|_| invalid())?;
+        let unit_secs = match unit.to_ascii_lowercase() {
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 7 * 86400,
+            _ => return Err(invalid()),
+        };
+        let age = Duration::from_secs(amount.saturating_mul(unit_secs));
+        return Ok(Some(now.checked_sub(age).unwrap_or(SystemTime::UNIX_EPOCH)));
+    }
+
+    let parts: Vec<&str> = s.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: u32 = month.parse().map_err(|_| invalid())?;
+    let day: u32 = day.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return Err(invalid());
+    }
+    let days = days_from_civil(year, month, day);
+    Ok(Some(SystemTime::UNIX_EPOCH + Duration::from_secs(days as u64 * 86400)))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[cfg(unix)]
+fn local_uid(owner: &str) -> Option<u32> {
+    super::entry::lookup_uid(owner)
+}
+
+#[cfg(not(unix))]
+fn local_uid(_owner: &str) -> Option<u32> {
+    None
+}
+
+fn parse_permissions(input: &str) -> Result<Option<u32>, String> {
+    let s = input.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    match u32::from_str_radix(s, 8) {
+        Ok(bits) if bits <= 0o7777 => Ok(Some(bits)),
+        _ => Err(format!("Invalid permissions '{}': use octal bits like 644 or 111", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    fn run(dir: &Path, options: &FindOptions) -> Vec<String> {
+        let query = FindQuery::new(options).unwrap();
+        let progress = FindProgress::default();
+        let cancel = AtomicBool::new(false);
+        search(dir, &query, &progress, &cancel)
+            .iter()
+            .map(|p| p.strip_prefix(dir).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_size_and_time() {
+        assert_eq!(parse_size(""), Ok(None));
+        assert_eq!(parse_size("512"), Ok(Some(512)));
+        assert_eq!(parse_size("10k"), Ok(Some(10 * 1024)));
+        assert_eq!(parse_size("1.5MB"), Ok(Some(3 * 512 * 1024)));
+        assert_eq!(parse_size("2GiB"), Ok(Some(2 << 30)));
+        assert!(parse_size("lots").is_err());
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(30 * 86400);
+        assert_eq!(parse_time("2d", now), Ok(Some(SystemTime::UNIX_EPOCH + Duration::from_secs(28 * 86400))));
+        assert_eq!(parse_time("1970-01-02", now), Ok(Some(SystemTime::UNIX_EPOCH + Duration::from_secs(86400))));
+        assert_eq!(days_from_civil(2024, 3, 1), 19783);
+        assert!(parse_time("yesterday", now).is_err());
+        assert!(parse_time("2024-13-01", now).is_err());
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let dir = ScratchDir::new("find-filters");
+        std::fs::create_dir_all(dir.join("src/deep")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), b"fn main() {}\n// TODO: more\n").unwrap();
+        std::fs::write(dir.join("src/deep/lib.rs"), b"\xff\xfe binary TODO\n").unwrap();
+        std::fs::write(dir.join("src/notes.txt"), vec![b'x'; 4096]).unwrap();
+        std::fs::write(dir.join("target/out.rs"), b"// TODO generated\n").unwrap();
+        std::fs::write(dir.join(".gitignore"), b"target/\n").unwrap();
+
+        let mut options = FindOptions::new(dir.to_string_lossy().to_string());
+        options.pattern = "*.rs".to_string();
+        assert_eq!(run(&dir, &options), ["src/deep/lib.rs", "src/main.rs", "target/out.rs"]);
+
+        options.skip_ignored = true;
+        assert_eq!(run(&dir, &options), ["src/deep/lib.rs", "src/main.rs"]);
+
+        // Content search no longer skips non-UTF-8 files
+        options.content = "todo".to_string();
+        assert_eq!(run(&dir, &options), ["src/deep/lib.rs", "src/main.rs"]);
+        options.content = r"^// TODO:".to_string();
+        options.content_regex = true;
+        assert_eq!(run(&dir, &options), ["src/main.rs"]);
+
+        let mut options = FindOptions::new(String::new());
+        options.pattern = r"^(main|notes)\.".to_string();
+        options.pattern_regex = true;
+        options.min_size = "1K".to_string();
+        assert_eq!(run(&dir, &options), ["src/notes.txt"]);
+
+        options.pattern = "*".to_string();
+        options.pattern_regex = false;
+        options.min_size.clear();
+        options.file_type = FileTypeFilter::Dir;
+        assert_eq!(run(&dir, &options), ["src", "src/deep", "target"]);
+        options.recursive = false;
+        assert_eq!(run(&dir, &options), ["src", "target"]);
+
+    }
+
+    #[test]
+    fn test_search_provider() {
+        let dir = ScratchDir::new("find-provider");
+        std::fs::create_dir_all(dir.join("sub/inner")).unwrap();
+        std::fs::write(dir.join("top.rs"), b"fn main() {}\n").unwrap();
+        std::fs::write(dir.join("sub/a.rs"), b"// TODO\n").unwrap();
+        std::fs::write(dir.join("sub/inner/b.txt"), b"TODO later\n").unwrap();
+        std::fs::write(dir.join(".gitignore"), b"sub/\n").unwrap();
+
+        let provider: Mutex<Box<dyn PanelProvider>> = Mutex::new(Box::new(crate::providers::LocalProvider::new()));
+        let run_provider = |options: &FindOptions| -> Vec<String> {
+            let query = FindQuery::new(options).unwrap();
+            let progress = FindProgress::default();
+            let cancel = AtomicBool::new(false);
+            search_provider(&provider, &dir.to_string_lossy(), &query, &progress, &cancel)
+                .iter()
+                .map(|e| e.path.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect()
+        };
+
+        // Ignore files only apply to local walks
+        let mut options = FindOptions::new(String::new());
+        options.pattern = "*.rs".to_string();
+        options.skip_ignored = true;
+        assert_eq!(run_provider(&options), ["sub/a.rs", "top.rs"]);
+
+        options.pattern = "*".to_string();
+        options.content = "todo".to_string();
+        assert_eq!(run_provider(&options), ["sub/a.rs", "sub/inner/b.txt"]);
+
+        options.content.clear();
+        options.file_type = FileTypeFilter::Dir;
+        assert_eq!(run_provider(&options), ["sub", "sub/inner"]);
+        options.recursive = false;
+        assert_eq!(run_provider(&options), ["sub"]);
+
+    }
+
+    #[test]
+    fn test_content_in_long_lines() {
+        let cancel = AtomicBool::new(false);
+        let re = regex::bytes::Regex::new("needle").unwrap();
+        // No newline anywhere, and the match straddles the first cut
+        let mut data = vec![0u8; CONTENT_WINDOW * 3];
+        data[CONTENT_WINDOW - 3..CONTENT_WINDOW + 3].copy_from_slice(b"needle");
+        assert!(reader_contains(&data[..], &re, &cancel));
+        assert!(!reader_contains(&vec![0u8; CONTENT_WINDOW * 3][..], &re, &cancel));
+        // At the very end, without a trailing newline
+        let mut data = vec![b'x'; CONTENT_WINDOW * 2 + 10];
+        data.extend_from_slice(b"\nlast needle");
+        assert!(reader_contains(&data[..], &re, &cancel));
+    }
+
+    #[test]
+    fn test_cancelled_search_stops() {
+        let dir = ScratchDir::new("find-cancel");
+        std::fs::write(dir.join("a.txt"), b"a").unwrap();
+        let query = FindQuery::new(&FindOptions::new(String::new())).unwrap();
+        let progress = FindProgress::default();
+        let cancel = AtomicBool::new(true);
+        assert!(search(&dir, &query, &progress, &cancel).is_empty());
+        assert!(FindQuery::new(&FindOptions { pattern: String::new(), ..FindOptions::new(String::new()) }).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Follow mode for the file viewer (F), like `tail -f`
+//!
+//! [`FileFollower`] remembers how long the viewed file was and which file
+//! it was. Each poll tells whether data was appended or whether the file
+//! was truncated or replaced by a new one, as happens on log rotation.
+
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How often a followed file is checked for changes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What happened to a followed file since it was last checked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FollowChange {
+    Unchanged,
+    /// Data was added at the end
+    Appended,
+    /// The file shrank, or its name now belongs to another file
+    Replaced,
+}
+
+/// Size and identity of a followed file
+#[derive(Clone, Debug)]
+pub struct FileFollower {
+    len: u64,
+    id: Option<(u64, u64)>,
+    last_poll: Option<Instant>,
+}
+
+impl FileFollower {
+    /// Follow the file at `path`, of which the first `len` bytes are shown
+    pub fn new(path: &Path, len: u64) -> Self {
+        let id = std::fs::metadata(path).ok().and_then(|meta| file_id(&meta));
+        Self { len, id, last_poll: None }
+    }
+
+    /// Check `path` for changes, at most once per [`POLL_INTERVAL`]. A file
+    /// that is missing, as between a rotation and the new file showing up,
+    /// counts as unchanged.
+    pub fn poll(&mut self, path: &Path) -> FollowChange {
+        if self.last_poll.is_some_and(|last| last.elapsed() < POLL_INTERVAL) {
+            return FollowChange::Unchanged;
+        }
+        self.last_poll = Some(Instant::now());
+        let Ok(meta) = std::fs::metadata(path) else {
+            return FollowChange::Unchanged;
+        };
+        self.update(meta.len(), file_id(&meta))
+    }
+
+    fn update(&mut self, len: u64, id: Option<(u64, u64)>) -> FollowChange {
+        let change = if id != self.id || len < self.len {
+            FollowChange::Replaced
+        } else if len > self.len {
+            FollowChange::Appended
+        } else {
+            FollowChange::Unchanged
+        };
+        self.len = len;
+        self.id = id;
+        change
+    }
+}
+
+/// Device and inode, which tell a rotated-in file from the old one
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+/// Without inodes a replaced file is only noticed when it is shorter
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    #[test]
+    fn test_follow_changes() {
+        let dir = ScratchDir::new("follow");
+        let path = dir.join("app.log");
+        std::fs::write(&path, "one\n").unwrap();
+
+        let mut follower = FileFollower::new(&path, 4);
+        assert_eq!(follower.poll(&path), FollowChange::Unchanged);
+
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        follower.last_poll = None;
+        assert_eq!(follower.poll(&path), FollowChange::Appended);
+        std::fs::write(&path, "three\n").unwrap();
+        follower.last_poll = None;
+        assert_eq!(follower.poll(&path), FollowChange::Replaced);
+
+        // Rotation: the old file is renamed away and a new one created
+        #[cfg(unix)]
+        {
+            std::fs::rename(&path, dir.join("app.log.1")).unwrap();
+            follower.last_poll = None;
+            assert_eq!(follower.poll(&path), FollowChange::Unchanged);
+            std::fs::write(&path, "a much longer first line\n").unwrap();
+            follower.last_poll = None;
+            assert_eq!(follower.poll(&path), FollowChange::Replaced);
+        }
+
+        // Checks are rate limited
+        std::fs::write(&path, "a much longer first line\nand more\n").unwrap();
+        assert_eq!(follower.poll(&path), FollowChange::Unchanged);
+    }
+}
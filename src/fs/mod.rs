@@ -1,7 +1,17 @@
 //! Filesystem module
 
+pub mod compare;
+pub mod copy;
+pub mod diff;
 pub mod entry;
+pub mod edit_buffer;
+pub mod find;
+pub mod follow;
 pub mod ops;
+pub mod pack;
+pub mod search;
+pub mod trash;
+pub mod undo;
 pub mod utils;
 
 pub use entry::FileEntry;
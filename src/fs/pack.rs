@@ -0,0 +1,506 @@
+//! Creating archives from local files (Pack command)
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+/// Archive formats the Pack command can produce
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarBz2,
+    SevenZip,
+}
+
+impl PackFormat {
+    /// All formats in the order they are offered in the dialog
+    pub const ALL: [PackFormat; 6] = [
+        PackFormat::Zip,
+        PackFormat::Tar,
+        PackFormat::TarGz,
+        PackFormat::TarXz,
+        PackFormat::TarBz2,
+        PackFormat::SevenZip,
+    ];
+
+    /// Name shown in the dialog
+    pub fn label(self) -> &'static str {
+        match self {
+            PackFormat::Zip => "zip",
+            PackFormat::Tar => "tar",
+            PackFormat::TarGz => "tar.gz",
+            PackFormat::TarXz => "tar.xz",
+            PackFormat::TarBz2 => "tar.bz2",
+            PackFormat::SevenZip => "7z",
+        }
+    }
+
+    /// File extension including the leading dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            PackFormat::Zip => ".zip",
+            PackFormat::Tar => ".tar",
+            PackFormat::TarGz => ".tar.gz",
+            PackFormat::TarXz => ".tar.xz",
+            PackFormat::TarBz2 => ".tar.bz2",
+            PackFormat::SevenZip => ".7z",
+        }
+    }
+
+    /// Whether the compression level setting applies to this format
+    pub fn has_level(self) -> bool {
+        self != PackFormat::Tar
+    }
+
+    /// The next (or previous) format in `ALL`, wrapping around
+    pub fn cycle(self, forward: bool) -> PackFormat {
+        let idx = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        let len = Self::ALL.len();
+        let next = if forward { (idx + 1) % len } else { (idx + len - 1) % len };
+        Self::ALL[next]
+    }
+
+    /// Replace any known archive extension on `name` with this format's extension.
+    pub fn apply_extension(self, name: &str) -> String {
+        format!("{}{}", strip_archive_extension(name), self.extension())
+    }
+}
+
+/// Strip a trailing archive extension (".tar.gz", ".zip", ...) if present
+fn strip_archive_extension(name: &str) -> &str {
+    let lower = name.to_lowercase();
+    // Longest first so ".tar.gz" wins over ".gz"-less ".tar" checks
+    let mut exts: Vec<&str> = PackFormat::ALL.iter().map(|f| f.extension()).collect();
+    exts.extend([".tgz", ".txz", ".tbz2"]);
+    exts.sort_by_key(|e| std::cmp::Reverse(e.len()));
+    for ext in exts {
+        if lower.ends_with(ext) && lower.len() > ext.len() {
+            return &name[..name.len() - ext.len()];
+        }
+    }
+    name
+}
+
+/// Kind of filesystem object being packed
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ItemKind {
+    File,
+    Dir,
+    Symlink(PathBuf),
+}
+
+/// One filesystem object to store in the archive
+#[derive(Clone, Debug)]
+struct PackItem {
+    path: PathBuf,
+    /// Name inside the archive, always '/'-separated
+    name: String,
+    kind: ItemKind,
+    size: u64,
+    modified: Option<SystemTime>,
+    mode: u32,
+}
+
+/// The expanded list of everything a Pack operation will store
+pub struct PackJob {
+    items: Vec<PackItem>,
+    /// Number of regular files (directories and links are not counted)
+    pub files_total: usize,
+    /// Total size of all regular files
+    pub bytes_total: u64,
+}
+
+impl PackJob {
+    /// Walk the sources and collect every entry to pack. Entry names are
+    /// relative to each source's parent directory. Symlinked directories are
+    /// stored as links, not followed.
+    pub fn scan(sources: &[PathBuf]) -> io::Result<PackJob> {
+        let mut job = PackJob { items: Vec::new(), files_total: 0, bytes_total: 0 };
+        for src in sources {
+            let name = src.file_name()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: not a file name", src.display())))?
+                .to_string_lossy()
+                .to_string();
+            job.scan_path(src, name)?;
+        }
+        Ok(job)
+    }
+
+    fn scan_path(&mut self, path: &Path, name: String) -> io::Result<()> {
+        let meta = std::fs::symlink_metadata(path)?;
+        let ft = meta.file_type();
+        let kind = if ft.is_symlink() {
+            ItemKind::Symlink(std::fs::read_link(path)?)
+        } else if ft.is_dir() {
+            ItemKind::Dir
+        } else {
+            ItemKind::File
+        };
+        let size = if kind == ItemKind::File { meta.len() } else { 0 };
+        if kind == ItemKind::File {
+            self.files_total += 1;
+            self.bytes_total += size;
+        }
+        self.items.push(PackItem {
+            path: path.to_path_buf(),
+            name: name.clone(),
+            kind: kind.clone(),
+            size,
+            modified: meta.modified().ok(),
+            mode: file_mode(&meta),
+        });
+
+        if kind == ItemKind::Dir {
+            let mut children: Vec<_> = std::fs::read_dir(path)?.collect::<io::Result<_>>()?;
+            children.sort_by_key(|e| e.file_name());
+            for child in children {
+                let child_name = format!("{}/{}", name, child.file_name().to_string_lossy());
+                self.scan_path(&child.path(), child_name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the archive. `level` is 0-9 and ignored for plain tar.
+    /// `progress` receives the current entry name, files done and bytes done.
+    /// A partially written archive is removed on error or cancellation;
+    /// cancellation is reported as `ErrorKind::Interrupted`.
+    pub fn run(
+        &self,
+        archive_path: &Path,
+        format: PackFormat,
+        level: u32,
+        cancel: &Arc<AtomicBool>,
+        progress: &dyn Fn(&str, usize, u64),
+    ) -> io::Result<()> {
+        // Never overwrite: an existing file is an error before anything is written
+        let file = File::options().write(true).create_new(true).open(archive_path)?;
+        let mut tracker = Tracker { cancel, progress, files_done: 0, bytes_done: 0 };
+        let result = match format {
+            PackFormat::Zip => self.write_zip(file, level, &mut tracker),
+            PackFormat::SevenZip => self.write_7z(file, level, &mut tracker),
+            _ => self.write_tar(file, format, level, &mut tracker),
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let _ = std::fs::remove_file(archive_path);
+                if cancel.load(Ordering::Relaxed) {
+                    Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    fn write_zip(&self, file: File, level: u32, tracker: &mut Tracker) -> io::Result<()> {
+        use zip::write::SimpleFileOptions;
+        use zip::CompressionMethod;
+
+        let mut zip = zip::ZipWriter::new(BufWriter::new(file));
+        let base = if level == 0 {
+            SimpleFileOptions::default().compression_method(CompressionMethod::Stored)
+        } else {
+            SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .compression_level(Some(level as i64))
+        };
+
+        for item in &self.items {
+            tracker.check_cancel()?;
+            let mut options = base.unix_permissions(item.mode);
+            if let Some(dt) = item.modified.and_then(zip_datetime) {
+                options = options.last_modified_time(dt);
+            }
+            match &item.kind {
+                ItemKind::Dir => zip.add_directory(item.name.as_str(), options).map_err(zip_err)?,
+                ItemKind::Symlink(target) => zip
+                    .add_symlink(item.name.as_str(), target.to_string_lossy(), options)
+                    .map_err(zip_err)?,
+                ItemKind::File => {
+                    let options = options.large_file(item.size >= 0xFFFF_FFFF);
+                    zip.start_file(item.name.as_str(), options).map_err(zip_err)?;
+                    tracker.copy_file(item, &mut zip)?;
+                }
+            }
+        }
+
+        let mut out = zip.finish().map_err(zip_err)?;
+        out.flush()?;
+        out.get_ref().sync_all()
+    }
+
+    fn write_tar(&self, file: File, format: PackFormat, level: u32, tracker: &mut Tracker) -> io::Result<()> {
+        let file = BufWriter::new(file);
+        match format {
+            PackFormat::TarGz => {
+                let enc = flate2::write::GzEncoder::new(file, flate2::Compression::new(level.min(9)));
+                finish_tar(self.fill_tar(tar::Builder::new(enc), tracker)?.finish()?)
+            }
+            PackFormat::TarXz => {
+                let enc = xz2::write::XzEncoder::new(file, level.min(9));
+                finish_tar(self.fill_tar(tar::Builder::new(enc), tracker)?.finish()?)
+            }
+            PackFormat::TarBz2 => {
+                let enc = bzip2::write::BzEncoder::new(file, bzip2::Compression::new(level.clamp(1, 9)));
+                finish_tar(self.fill_tar(tar::Builder::new(enc), tracker)?.finish()?)
+            }
+            _ => finish_tar(self.fill_tar(tar::Builder::new(file), tracker)?),
+        }
+    }
+
+    fn fill_tar<W: Write>(&self, mut builder: tar::Builder<W>, tracker: &mut Tracker) -> io::Result<W> {
+        for item in &self.items {
+            tracker.check_cancel()?;
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(item.mode);
+            header.set_mtime(item.modified
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0));
+            match &item.kind {
+                ItemKind::Dir => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_size(0);
+                    builder.append_data(&mut header, format!("{}/", item.name), io::empty())?;
+                }
+                ItemKind::Symlink(target) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_size(0);
+                    builder.append_link(&mut header, &item.name, target)?;
+                }
+                ItemKind::File => {
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_size(item.size);
+                    let reader = tracker.open(item)?;
+                    builder.append_data(&mut header, &item.name, reader)?;
+                    tracker.file_done();
+                }
+            }
+        }
+        builder.into_inner()
+    }
+
+    fn write_7z(&self, file: File, level: u32, tracker: &mut Tracker) -> io::Result<()> {
+        use sevenz_rust::{SevenZArchiveEntry, SevenZWriter};
+
+        let mut sz = SevenZWriter::new(file).map_err(sevenz_err)?;
+        sz.set_content_methods(vec![sevenz_rust::lzma::LZMA2Options::with_preset(level.min(9)).into()]);
+
+        for item in &self.items {
+            tracker.check_cancel()?;
+            // 7z has no portable symlink entry; links are stored as the file they point to
+            let entry = SevenZArchiveEntry::from_path(&item.path, item.name.clone());
+            if entry.is_directory() {
+                sz.push_archive_entry(entry, None::<File>).map_err(sevenz_err)?;
+            } else {
+                let reader = tracker.open(item)?;
+                sz.push_archive_entry(entry, Some(reader)).map_err(sevenz_err)?;
+                tracker.file_done();
+            }
+        }
+
+        sz.finish()?.sync_all()
+    }
+}
+
+/// Shared cancel flag and progress counters while writing an archive
+struct Tracker<'a> {
+    cancel: &'a Arc<AtomicBool>,
+    progress: &'a dyn Fn(&str, usize, u64),
+    files_done: usize,
+    bytes_done: u64,
+}
+
+impl<'a> Tracker<'a> {
+    fn check_cancel(&self) -> io::Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Open a source file wrapped so reads report progress and honour cancel
+    fn open<'r>(&'r mut self, item: &'r PackItem) -> io::Result<ProgressReader<'r, 'a>> {
+        (self.progress)(&item.name, self.files_done, self.bytes_done);
+        Ok(ProgressReader { inner: File::open(&item.path)?, name: &item.name, tracker: self })
+    }
+
+    fn copy_file<W: Write>(&mut self, item: &PackItem, out: &mut W) -> io::Result<()> {
+        let mut reader = self.open(item)?;
+        io::copy(&mut reader, out)?;
+        self.file_done();
+        Ok(())
+    }
+
+    fn file_done(&mut self) {
+        self.files_done += 1;
+    }
+}
+
+struct ProgressReader<'r, 'a> {
+    inner: File,
+    name: &'r str,
+    tracker: &'r mut Tracker<'a>,
+}
+
+impl Read for ProgressReader<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.tracker.cancel.load(Ordering::Relaxed) {
+            // Not Interrupted: io::copy and friends silently retry that kind
+            return Err(io::Error::other("cancelled"));
+        }
+        let n = self.inner.read(buf)?;
+        self.tracker.bytes_done += n as u64;
+        (self.tracker.progress)(self.name, self.tracker.files_done, self.tracker.bytes_done);
+        Ok(n)
+    }
+}
+
+fn finish_tar<W: Write>(mut out: W) -> io::Result<()> {
+    out.flush()
+}
+
+fn zip_err(e: zip::result::ZipError) -> io::Error {
+    match e {
+        zip::result::ZipError::Io(e) => e,
+        other => io::Error::other(other.to_string()),
+    }
+}
+
+fn sevenz_err(e: sevenz_rust::Error) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+#[cfg(unix)]
+fn file_mode(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn file_mode(meta: &std::fs::Metadata) -> u32 {
+    if meta.is_dir() { 0o755 } else if meta.permissions().readonly() { 0o444 } else { 0o644 }
+}
+
+/// Convert a timestamp to a ZIP (MS-DOS) date/time in UTC
+fn zip_datetime(time: SystemTime) -> Option<zip::DateTime> {
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs() as i64;
+    let days = secs / 86400;
+    let rem = secs % 86400;
+
+    // Civil date from days since the Unix epoch
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    zip::DateTime::from_date_and_time(
+        u16::try_from(year).ok()?,
+        month as u8,
+        day as u8,
+        (rem / 3600) as u8,
+        (rem % 3600 / 60) as u8,
+        (rem % 60) as u8,
+    ).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    fn sample_tree(dir: &Path) -> PathBuf {
+        let src = dir.join("docs");
+        std::fs::create_dir_all(src.join("sub")).unwrap();
+        std::fs::write(src.join("a.txt"), b"hello").unwrap();
+        std::fs::write(src.join("sub/b.txt"), b"world!").unwrap();
+        src
+    }
+
+    #[test]
+    fn test_apply_extension() {
+        assert_eq!(PackFormat::Zip.apply_extension("docs"), "docs.zip");
+        assert_eq!(PackFormat::TarGz.apply_extension("docs.zip"), "docs.tar.gz");
+        assert_eq!(PackFormat::SevenZip.apply_extension("docs.TAR.GZ"), "docs.7z");
+        assert_eq!(PackFormat::Tar.apply_extension("v1.2"), "v1.2.tar");
+        assert_eq!(PackFormat::Zip.apply_extension(".zip"), ".zip.zip");
+        assert_eq!(PackFormat::SevenZip.cycle(true), PackFormat::Zip);
+        assert_eq!(PackFormat::Zip.cycle(false), PackFormat::SevenZip);
+    }
+
+    #[test]
+    fn test_scan_counts_files() {
+        let dir = ScratchDir::new("pack-scan");
+        let src = sample_tree(&dir);
+        let job = PackJob::scan(&[src]).unwrap();
+        let names: Vec<&str> = job.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["docs", "docs/a.txt", "docs/sub", "docs/sub/b.txt"]);
+        assert_eq!(job.files_total, 2);
+        assert_eq!(job.bytes_total, 11);
+    }
+
+    #[test]
+    fn test_pack_zip_tar_gz_7z() {
+        let dir = ScratchDir::new("pack-roundtrip");
+        let src = sample_tree(&dir);
+        let job = PackJob::scan(&[src]).unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let zip_path = dir.join("out.zip");
+        job.run(&zip_path, PackFormat::Zip, 6, &cancel, &|_, _, _| {}).unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        let mut content = String::new();
+        archive.by_name("docs/sub/b.txt").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "world!");
+        assert!(archive.by_name("docs/sub/").unwrap().is_dir());
+
+        let tgz_path = dir.join("out.tar.gz");
+        job.run(&tgz_path, PackFormat::TarGz, 6, &cancel, &|_, _, _| {}).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(File::open(&tgz_path).unwrap()));
+        let names: Vec<String> = archive.entries().unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["docs/", "docs/a.txt", "docs/sub/", "docs/sub/b.txt"]);
+
+        let sz_path = dir.join("out.7z");
+        job.run(&sz_path, PackFormat::SevenZip, 6, &cancel, &|_, _, _| {}).unwrap();
+        let mut reader = sevenz_rust::SevenZReader::open(&sz_path, sevenz_rust::Password::empty()).unwrap();
+        let mut names = Vec::new();
+        reader.for_each_entries(|entry, _| {
+            names.push(entry.name().to_string());
+            Ok(true)
+        }).unwrap();
+        assert!(names.contains(&"docs/sub/b.txt".to_string()));
+
+        // Existing archives are never overwritten
+        assert!(job.run(&zip_path, PackFormat::Zip, 6, &cancel, &|_, _, _| {}).is_err());
+        assert!(zip_path.exists());
+
+    }
+
+    #[test]
+    fn test_pack_cancel_removes_partial() {
+        let dir = ScratchDir::new("pack-cancel");
+        let src = sample_tree(&dir);
+        let job = PackJob::scan(&[src]).unwrap();
+        let cancel = Arc::new(AtomicBool::new(true));
+        let path = dir.join("out.tar.xz");
+        let err = job.run(&path, PackFormat::TarXz, 6, &cancel, &|_, _, _| {}).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        assert!(!path.exists());
+    }
+}
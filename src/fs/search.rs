@@ -0,0 +1,183 @@
+//! Search inside a viewed file (/ in the viewer)
+//!
+//! [`SearchQuery`] compiles the search dialog input into a byte regex.
+//! [`search`] runs it over the file in line-aligned chunks, counting
+//! progress in a shared [`SearchProgress`] so a large memory-mapped file can
+//! be searched in the background and cancelled.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::utils::{parse_hex_string, wildcard_to_regex};
+
+/// Searches stop collecting matches after this many
+pub const MAX_MATCHES: usize = 100_000;
+
+/// Memory-mapped files larger than this are searched in the background
+pub const BACKGROUND_SEARCH_SIZE: usize = 8 * 1024 * 1024;
+
+/// Bytes searched between progress updates and cancel checks
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Search counters shared with the UI while a search runs
+#[derive(Debug, Default)]
+pub struct SearchProgress {
+    /// Bytes searched so far
+    pub scanned: AtomicUsize,
+    /// Matches found so far
+    pub found: AtomicUsize,
+}
+
+/// A compiled viewer search
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+    regex: Regex,
+    /// Matches may run across line breaks, so the file can't be split at them
+    spans_lines: bool,
+    /// What is searched for, e.g. "'foo'" or "hex 4D 5A"
+    pub description: String,
+}
+
+impl SearchQuery {
+    /// Compile a text search. Without `regex` the pattern is literal text
+    /// where `*` matches anything on one line. `multiline` lets a regex
+    /// match across lines, with `.` matching line breaks too.
+    pub fn text(pattern: &str, case_sensitive: bool, regex: bool, multiline: bool) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("Search text cannot be empty".to_string());
+        }
+        let source = if regex {
+            pattern.to_string()
+        } else if pattern.contains('*') {
+            wildcard_to_regex(pattern, true)
+        } else {
+            regex::escape(pattern)
+        };
+        let multiline = regex && multiline;
+        let compiled = RegexBuilder::new(&source)
+            .case_insensitive(!case_sensitive)
+            .multi_line(true)
+            .dot_matches_new_line(multiline)
+            .build()
+            .map_err(|e| format!("Invalid pattern: {}", e))?;
+        let kind = if regex { "regex " } else { "" };
+        Ok(Self {
+            regex: compiled,
+            spans_lines: multiline,
+            description: format!("{}'{}'", kind, pattern),
+        })
+    }
+
+    /// Compile a search for the bytes given in hex, e.g. "4D 5A"
+    pub fn hex(input: &str) -> Result<Self, String> {
+        let bytes = parse_hex_string(input)
+            .filter(|b| !b.is_empty())
+            .ok_or_else(|| "Invalid hex bytes".to_string())?;
+        let source: String = std::iter::once("(?-u)".to_string())
+            .chain(bytes.iter().map(|b| format!("\\x{:02X}", b)))
+            .collect();
+        let regex = Regex::new(&source).map_err(|e| format!("Invalid hex bytes: {}", e))?;
+        let description = bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+        Ok(Self {
+            regex,
+            spans_lines: bytes.contains(&b'\n'),
+            description: format!("hex {}", description),
+        })
+    }
+}
+
+/// Find the matches of `query` in `bytes` as (byte offset, length) pairs,
+/// at most [`MAX_MATCHES`]. Stops early, keeping what it found, once
+/// `cancel` is set.
+pub fn search(bytes: &[u8], query: &SearchQuery, progress: &SearchProgress, cancel: &AtomicBool) -> Vec<(usize, usize)> {
+    search_chunked(bytes, query, CHUNK_SIZE, progress, cancel)
+}
+
+fn search_chunked(
+    bytes: &[u8],
+    query: &SearchQuery,
+    chunk_size: usize,
+    progress: &SearchProgress,
+    cancel: &AtomicBool,
+) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() && !cancel.load(Ordering::Relaxed) {
+        // Chunks end at a line break so single-line matches are never cut
+        let end = if query.spans_lines {
+            bytes.len()
+        } else {
+            let end = (start + chunk_size).min(bytes.len());
+            bytes[end..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |p| end + p + 1)
+        };
+        let chunk = &bytes[start..end];
+        for m in query.regex.find_iter(chunk) {
+            if m.is_empty() || (!query.spans_lines && m.as_bytes().contains(&b'\n')) {
+                continue;
+            }
+            matches.push((start + m.start(), m.len()));
+            if matches.len() >= MAX_MATCHES {
+                break;
+            }
+        }
+        progress.scanned.store(end, Ordering::Relaxed);
+        progress.found.store(matches.len(), Ordering::Relaxed);
+        if matches.len() >= MAX_MATCHES {
+            break;
+        }
+        start = end;
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(bytes: &[u8], query: &SearchQuery, chunk_size: usize) -> Vec<(usize, usize)> {
+        search_chunked(bytes, query, chunk_size, &SearchProgress::default(), &AtomicBool::new(false))
+    }
+
+    #[test]
+    fn test_text_queries() {
+        let text = b"Error: disk\nerror: net\nwarning: error later\n";
+        let plain = SearchQuery::text("error", false, false, false).unwrap();
+        assert_eq!(find(text, &plain, CHUNK_SIZE), [(0, 5), (12, 5), (32, 5)]);
+        let case = SearchQuery::text("error", true, false, false).unwrap();
+        assert_eq!(find(text, &case, CHUNK_SIZE).len(), 2);
+        let wildcard = SearchQuery::text("err*:", true, false, false).unwrap();
+        assert_eq!(find(text, &wildcard, CHUNK_SIZE), [(12, 6)]);
+
+        let anchored = SearchQuery::text(r"^\w+:", true, true, false).unwrap();
+        assert_eq!(find(text, &anchored, CHUNK_SIZE), [(0, 6), (12, 6), (23, 8)]);
+        assert!(SearchQuery::text("(", false, true, false).is_err());
+        assert!(SearchQuery::text("(", false, false, false).is_ok());
+    }
+
+    #[test]
+    fn test_multiline_and_chunks() {
+        let text = b"begin\nmiddle\nend\nbegin\nend\n";
+        let spanning = SearchQuery::text("begin.*?end", true, true, false).unwrap();
+        assert!(find(text, &spanning, CHUNK_SIZE).is_empty());
+        let spanning = SearchQuery::text("begin.*?end", true, true, true).unwrap();
+        assert_eq!(find(text, &spanning, CHUNK_SIZE), [(0, 16), (17, 9)]);
+
+        // Tiny chunks give the same matches as one big one
+        let words = SearchQuery::text(r"\b[a-z]+\b", true, true, false).unwrap();
+        assert_eq!(find(text, &words, 3), find(text, &words, CHUNK_SIZE));
+        assert_eq!(find(text, &words, 3).len(), 5);
+    }
+
+    #[test]
+    fn test_hex_query() {
+        let bytes = b"MZ\x90\x00\r\nPE\x00\x00";
+        let query = SearchQuery::hex("4d 5a").unwrap();
+        assert_eq!(find(bytes, &query, CHUNK_SIZE), [(0, 2)]);
+        let crlf = SearchQuery::hex("0D0A").unwrap();
+        assert_eq!(find(bytes, &crlf, 1), [(4, 2)]);
+        assert_eq!(SearchQuery::hex("90 00").unwrap().description, "hex 90 00");
+        assert!(SearchQuery::hex("4").is_err());
+        assert!(SearchQuery::hex("").is_err());
+    }
+}
@@ -0,0 +1,527 @@
+//! Moving files to the trash, following the freedesktop.org Trash specification
+//!
+//! Items on the same filesystem as the home directory go to the home trash,
+//! `$XDG_DATA_HOME/Trash` (`~/.local/share/Trash` by default). Items on other
+//! filesystems go to a trash at the top of their mount: `$topdir/.Trash/$uid`
+//! when the administrator set up a shared `.Trash` (sticky, not a symlink),
+//! otherwise `$topdir/.Trash-$uid`. A trashed item lives in `files/<name>`,
+//! with `info/<name>.trashinfo` recording where it came from and when.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An item in one of the trash directories
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    /// Name under `files/`, unique within its trash directory
+    pub name: String,
+    /// Where the item was before it was trashed
+    pub original: PathBuf,
+    /// `YYYY-MM-DDThh:mm:ss` in local time, as recorded
+    pub deleted: String,
+    pub is_dir: bool,
+    /// Size of a file; zero for directories
+    pub size: u64,
+    /// Trash directory holding the item
+    pub trash: PathBuf,
+}
+
+impl TrashedItem {
+    /// The trashed file or directory itself
+    pub fn file_path(&self) -> PathBuf {
+        self.trash.join("files").join(&self.name)
+    }
+
+    fn info_path(&self) -> PathBuf {
+        self.trash.join("info").join(format!("{}.trashinfo", self.name))
+    }
+}
+
+/// A trash directory, and the directory its relative `Path=` entries are
+/// relative to (None for the home trash, whose entries are absolute)
+struct TrashDir {
+    path: PathBuf,
+    topdir: Option<PathBuf>,
+}
+
+/// Move `path` to the trash of its filesystem. Returns where it ended up.
+pub fn trash(path: &Path) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let meta = fs::symlink_metadata(&path)?;
+    let home = home_trash().ok_or_else(|| io::Error::other("Cannot locate the home trash: HOME is not set"))?;
+
+    let home_device = nearest_existing(&home).and_then(|dir| fs::metadata(dir).ok()).map(|m| device(&m));
+    let trash = if home_device == Some(device(&meta)) {
+        TrashDir { path: home, topdir: None }
+    } else {
+        let topdir = mount_top(&path, device(&meta));
+        TrashDir { path: mount_trash(&topdir)?, topdir: Some(topdir) }
+    };
+    move_into(&path, &trash)
+}
+
+/// Everything in the trash directories this user can see, most recently
+/// trashed first
+pub fn list() -> Vec<TrashedItem> {
+    let mut items: Vec<TrashedItem> = trash_dirs().iter().flat_map(items_in).collect();
+    items.sort_by(|a, b| b.deleted.cmp(&a.deleted).then_with(|| a.name.cmp(&b.name)));
+    items
+}
+
+/// Move an item back to where it was, recreating missing parent directories.
+/// Refuses to overwrite anything there now.
+pub fn restore(item: &TrashedItem) -> io::Result<()> {
+    if fs::symlink_metadata(&item.original).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", item.original.display()),
+        ));
+    }
+    if let Some(parent) = item.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(item.file_path(), &item.original)?;
+    fs::remove_file(item.info_path())
+}
+
+/// Delete an item from the trash for good
+pub fn purge(item: &TrashedItem) -> io::Result<()> {
+    let file = item.file_path();
+    match fs::symlink_metadata(&file) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(&file)?,
+        Ok(_) => fs::remove_file(&file)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    fs::remove_file(item.info_path())
+}
+
+/// Delete everything in the trash. Returns how many items went, and an
+/// error for each that couldn't be deleted.
+pub fn empty() -> (usize, Vec<String>) {
+    let mut count = 0;
+    let mut errors = Vec::new();
+    for item in list() {
+        match purge(&item) {
+            Ok(()) => count += 1,
+            Err(e) => errors.push(format!("{}: {}", item.original.display(), e)),
+        }
+    }
+    (count, errors)
+}
+
+/// `$XDG_DATA_HOME/Trash`, or `~/.local/share/Trash`
+fn home_trash() -> Option<PathBuf> {
+    match std::env::var_os("XDG_DATA_HOME").filter(|dir| Path::new(dir).is_absolute()) {
+        Some(data) => Some(PathBuf::from(data).join("Trash")),
+        None => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/Trash")),
+    }
+}
+
+/// The trash to use for items under the mount at `topdir`: the user's
+/// directory in a shared `.Trash` if there is a valid one, else
+/// `.Trash-$uid`, created if needed
+fn mount_trash(topdir: &Path) -> io::Result<PathBuf> {
+    let uid = uid();
+    let shared = topdir.join(".Trash");
+    if is_valid_shared_trash(&shared) {
+        let dir = shared.join(uid.to_string());
+        if create_private_dir(&dir).is_ok() {
+            return Ok(dir);
+        }
+    }
+    let dir = topdir.join(format!(".Trash-{}", uid));
+    create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// A shared `.Trash` must be a real directory with the sticky bit set, so
+/// users can't remove each other's trash
+fn is_valid_shared_trash(dir: &Path) -> bool {
+    let Ok(meta) = fs::symlink_metadata(dir) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        meta.is_dir() && meta.permissions().mode() & 0o1000 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        meta.is_dir()
+    }
+}
+
+/// Create a directory readable only by this user, if it doesn't exist yet
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    match builder.create(dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Home trash and the trash directories of other mounts that exist
+fn trash_dirs() -> Vec<TrashDir> {
+    let mut dirs: Vec<TrashDir> = home_trash().map(|path| TrashDir { path, topdir: None }).into_iter().collect();
+    let uid = uid();
+    for topdir in mount_points() {
+        let candidates = [topdir.join(".Trash").join(uid.to_string()), topdir.join(format!(".Trash-{}", uid))];
+        for path in candidates {
+            // A filesystem mounted twice shows the same trash twice
+            if path.is_dir() && !dirs.iter().any(|dir| dir.path == path) {
+                dirs.push(TrashDir { path, topdir: Some(topdir.clone()) });
+            }
+        }
+    }
+    dirs
+}
+
+/// Mount points from `/proc/self/mounts`, where it exists
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|field| PathBuf::from(unescape_mount(field)))
+        .collect()
+}
+
+/// Mount fields escape spaces and a few other characters as `\ooo`
+fn unescape_mount(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let code = rest.get(i + 1..i + 4).and_then(|octal| u8::from_str_radix(octal, 8).ok());
+        match code {
+            Some(byte) => {
+                out.push(byte as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Items in one trash directory. Info files whose item is gone are skipped.
+fn items_in(trash: &TrashDir) -> Vec<TrashedItem> {
+    let Ok(entries) = fs::read_dir(trash.path.join("info")) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name();
+            let name = file_name.to_str()?.strip_suffix(".trashinfo")?.to_string();
+            let (path, deleted) = parse_info(&fs::read_to_string(entry.path()).ok()?)?;
+            let meta = fs::symlink_metadata(trash.path.join("files").join(&name)).ok()?;
+            let original = match &trash.topdir {
+                Some(topdir) if path.is_relative() => topdir.join(path),
+                _ => path,
+            };
+            Some(TrashedItem {
+                name,
+                original,
+                deleted,
+                is_dir: meta.is_dir(),
+                size: if meta.is_dir() { 0 } else { meta.len() },
+                trash: trash.path.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Move `path` into `trash`: reserve a name by creating its info file, then
+/// rename the item into `files/`
+fn move_into(path: &Path, trash: &TrashDir) -> io::Result<PathBuf> {
+    let files = trash.path.join("files");
+    let info = trash.path.join("info");
+    fs::create_dir_all(&trash.path)?;
+    create_private_dir(&files)?;
+    create_private_dir(&info)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Cannot trash a root directory"))?;
+    let recorded = match &trash.topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(path),
+        None => path,
+    };
+    let contents = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(recorded),
+        format_local_time(SystemTime::now()),
+    );
+
+    for n in 1.. {
+        let name = numbered_name(&file_name.to_string_lossy(), n);
+        let info_path = info.join(format!("{}.trashinfo", name));
+        let mut info_file = match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        // A leftover item without its info file also takes the name
+        let dest = files.join(&name);
+        if fs::symlink_metadata(&dest).is_ok() {
+            drop(info_file);
+            fs::remove_file(&info_path)?;
+            continue;
+        }
+        let moved = info_file.write_all(contents.as_bytes()).and_then(|_| fs::rename(path, &dest));
+        if let Err(e) = moved {
+            drop(info_file);
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+        return Ok(dest);
+    }
+    unreachable!()
+}
+
+/// `name` for the first item of that name, then `stem.2.ext`, `stem.3.ext`...
+fn numbered_name(name: &str, n: usize) -> String {
+    if n == 1 {
+        return name.to_string();
+    }
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}.{}.{}", stem, n, ext),
+        _ => format!("{}.{}", name, n),
+    }
+}
+
+/// `Path` and `DeletionDate` of a `.trashinfo` file
+fn parse_info(text: &str) -> Option<(PathBuf, String)> {
+    let lines = text.lines().map(str::trim).skip_while(|line| *line != "[Trash Info]").skip(1);
+    let mut path = None;
+    let mut deleted = String::new();
+    for line in lines.take_while(|line| !line.starts_with('[')) {
+        if let Some(value) = line.strip_prefix("Path=") {
+            path = Some(decode_path(value));
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deleted = value.to_string();
+        }
+    }
+    Some((path?, deleted))
+}
+
+/// Percent-encode a path as the spec asks, leaving `/` alone
+fn encode_path(path: &Path) -> String {
+    let mut out = String::new();
+    for &byte in path_bytes(path).iter() {
+        if byte.is_ascii_alphanumeric() || b"/-_.!~*'()".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn decode_path(text: &str) -> PathBuf {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    path_from_bytes(out)
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// `path` or its closest ancestor that exists
+fn nearest_existing(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|dir| dir.exists())
+}
+
+/// Top directory of the mount holding `path`, whose device is `dev`
+fn mount_top(path: &Path, dev: u64) -> PathBuf {
+    let mut top = path.parent().unwrap_or(path);
+    while let Some(parent) = top.parent() {
+        match fs::metadata(parent) {
+            Ok(meta) if device(&meta) == dev => top = parent,
+            _ => break,
+        }
+    }
+    top.to_path_buf()
+}
+
+#[cfg(unix)]
+fn device(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.dev()
+}
+
+#[cfg(not(unix))]
+fn device(_meta: &fs::Metadata) -> u64 {
+    0
+}
+
+#[cfg(unix)]
+fn uid() -> u32 {
+    unsafe { libc::getuid() }
+}
+
+#[cfg(not(unix))]
+fn uid() -> u32 {
+    0
+}
+
+/// `YYYY-MM-DDThh:mm:ss` in local time
+fn format_local_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    let local = secs + utc_offset(secs);
+    let (days, time_of_day) = (local.div_euclid(86400), local.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
+
+/// Seconds the local time zone is ahead of UTC at `secs`
+#[cfg(unix)]
+fn utc_offset(secs: i64) -> i64 {
+    unsafe {
+        let epoch = secs as libc::time_t;
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&epoch, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+#[cfg(not(unix))]
+fn utc_offset(_secs: i64) -> i64 {
+    0
+}
+
+/// Proleptic Gregorian date of a day counted from 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    #[test]
+    fn test_trash_list_restore_purge() {
+        let dir = ScratchDir::new("trash");
+        let work = dir.join("work");
+        fs::create_dir_all(work.join("sub dir")).unwrap();
+        fs::write(work.join("a.txt"), "one").unwrap();
+        fs::write(work.join("sub dir/x"), "x").unwrap();
+        let trash = TrashDir { path: dir.join("Trash"), topdir: Some(dir.to_path_buf()) };
+
+        let first = move_into(&work.join("a.txt"), &trash).unwrap();
+        fs::write(work.join("a.txt"), "two").unwrap();
+        let second = move_into(&work.join("a.txt"), &trash).unwrap();
+        move_into(&work.join("sub dir"), &trash).unwrap();
+        assert_eq!(first.file_name().unwrap(), "a.txt");
+        assert_eq!(second.file_name().unwrap(), "a.2.txt");
+        assert!(!work.join("a.txt").exists() && !work.join("sub dir").exists());
+
+        // Paths in a mount's trash are relative to the top of the mount
+        let info = fs::read_to_string(trash.path.join("info/sub dir.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=work/sub%20dir\nDeletionDate="), "{}", info);
+
+        let mut items = items_in(&trash);
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["a.2.txt", "a.txt", "sub dir"]);
+        assert_eq!((items[0].original.clone(), items[0].size, items[2].is_dir), (work.join("a.txt"), 3, true));
+
+        restore(&items[2]).unwrap();
+        assert!(work.join("sub dir/x").exists());
+        restore(&items[1]).unwrap();
+        assert_eq!(fs::read_to_string(work.join("a.txt")).unwrap(), "one");
+        // The other a.txt can't go back while this one is there
+        assert_eq!(restore(&items[0]).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        purge(&items[0]).unwrap();
+        assert!(items_in(&trash).is_empty());
+        assert_eq!(fs::read_dir(trash.path.join("files")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_info_format() {
+        let path = Path::new("/home/me/50% off/ünï.txt");
+        let encoded = encode_path(path);
+        assert_eq!(encoded, "/home/me/50%25%20off/%C3%BCn%C3%AF.txt");
+        assert_eq!(decode_path(&encoded), path);
+
+        let info = "[Trash Info]\nPath=%2Ftmp%2Fa b\nDeletionDate=2024-02-29T23:59:01\n";
+        assert_eq!(parse_info(info), Some((PathBuf::from("/tmp/a b"), "2024-02-29T23:59:01".to_string())));
+        assert_eq!(parse_info("[Other]\nPath=/x\n"), None);
+
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(numbered_name(".bashrc", 2), ".bashrc.2");
+        assert_eq!(unescape_mount("/mnt/my\\040disk"), "/mnt/my disk");
+    }
+}
@@ -0,0 +1,549 @@
+//! Undo journal: completed file operations, each with what it takes to
+//! reverse it
+//!
+//! Every entry is one user operation (a move, a new directory, a chmod...)
+//! made of steps, one per path touched, or one per tree for a recursive
+//! chmod or chown. A step remembers how its path looked right after the
+//! operation, so undoing it later can tell whether something else has
+//! changed it since. Each change is appended to the journal file as it
+//! happens, so it survives restarts; the file is rewritten now and then to
+//! drop what's no longer needed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Oldest entries are dropped beyond this
+const MAX_ENTRIES: usize = 100;
+
+/// The journal file is rewritten once it holds this many records
+const MAX_RECORDS: usize = 2 * MAX_ENTRIES;
+
+/// One change to a path, holding what the path was before
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Change {
+    /// Moved or renamed `from` to `to`
+    Move { from: PathBuf, to: PathBuf },
+    /// Created the directory
+    Mkdir { path: PathBuf },
+    /// Moved `original` to the trash, where it is now `trashed`
+    Trash { original: PathBuf, trashed: PathBuf },
+    /// Changed the permission bits, which were `mode`
+    Chmod { path: PathBuf, mode: u32 },
+    /// Changed the owner and group, which were `uid` and `gid`
+    Chown { path: PathBuf, uid: u32, gid: u32 },
+    /// Changed the permission bits of `root` and everything below it. Each
+    /// path had `dirs` or `files` by its kind, except those in `others`,
+    /// which are keyed relative to `root`.
+    ChmodTree {
+        root: PathBuf,
+        dirs: u32,
+        files: u32,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        others: BTreeMap<PathBuf, u32>,
+    },
+    /// Changed the owner and group of `root` and everything below it. Each
+    /// path had `uid` and `gid`, except those in `others`.
+    ChownTree {
+        root: PathBuf,
+        uid: u32,
+        gid: u32,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        others: BTreeMap<PathBuf, (u32, u32)>,
+    },
+}
+
+impl Change {
+    /// A `ChmodTree` for `root` from the old `(path, is_dir, mode)` of each
+    /// path in it; None if there are none
+    pub fn chmod_tree(root: PathBuf, old: Vec<(PathBuf, bool, u32)>) -> Option<Change> {
+        let usual = |is_dir: bool| most_common(old.iter().filter(|o| o.1 == is_dir).map(|o| o.2));
+        let (dirs, files) = match (usual(true), usual(false)) {
+            (None, None) => return None,
+            (dirs, files) => (dirs.or(files).unwrap_or(0), files.or(dirs).unwrap_or(0)),
+        };
+        let others = old
+            .into_iter()
+            .filter(|(_, is_dir, mode)| *mode != if *is_dir { dirs } else { files })
+            .map(|(path, _, mode)| (relative(&root, &path), mode))
+            .collect();
+        Some(Change::ChmodTree { root, dirs, files, others })
+    }
+
+    /// A `ChownTree` for `root` from the old `(path, uid, gid)` of each path
+    /// in it; None if there are none
+    pub fn chown_tree(root: PathBuf, old: Vec<(PathBuf, u32, u32)>) -> Option<Change> {
+        let (uid, gid) = most_common(old.iter().map(|o| (o.1, o.2)))?;
+        let others = old
+            .into_iter()
+            .filter(|o| (o.1, o.2) != (uid, gid))
+            .map(|(path, u, g)| (relative(&root, &path), (u, g)))
+            .collect();
+        Some(Change::ChownTree { root, uid, gid, others })
+    }
+
+    /// The path undoing this change acts on
+    fn target(&self) -> &Path {
+        match self {
+            Change::Move { to, .. } => to,
+            Change::Mkdir { path } | Change::Chmod { path, .. } | Change::Chown { path, .. } => path,
+            Change::Trash { trashed, .. } => trashed,
+            Change::ChmodTree { root, .. } | Change::ChownTree { root, .. } => root,
+        }
+    }
+
+    /// Put things back the way they were before this change
+    fn revert(&self) -> io::Result<()> {
+        match self {
+            Change::Move { from, to } => {
+                if fs::symlink_metadata(from).is_ok() {
+                    return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", from.display())));
+                }
+                if let Some(parent) = from.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                super::utils::move_path(to, from)
+            }
+            Change::Mkdir { path } => fs::remove_dir(path),
+            Change::Trash { trashed, .. } => {
+                let item = super::trash::list()
+                    .into_iter()
+                    .find(|item| item.file_path() == *trashed)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no longer in the trash"))?;
+                super::trash::restore(&item)
+            }
+            #[cfg(unix)]
+            Change::Chmod { path, mode } => {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(path, fs::Permissions::from_mode(*mode))
+            }
+            #[cfg(unix)]
+            Change::Chown { path, uid, gid } => std::os::unix::fs::chown(path, Some(*uid), Some(*gid)),
+            #[cfg(unix)]
+            Change::ChmodTree { root, dirs, files, others } => walk_tree(root, &mut |path| {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = match others.get(&relative(root, path)) {
+                    Some(mode) => *mode,
+                    None if path.is_dir() => *dirs,
+                    None => *files,
+                };
+                fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            }),
+            #[cfg(unix)]
+            Change::ChownTree { root, uid, gid, others } => walk_tree(root, &mut |path| {
+                let (uid, gid) = others.get(&relative(root, path)).copied().unwrap_or((*uid, *gid));
+                std::os::unix::fs::chown(path, Some(uid), Some(gid))
+            }),
+            #[cfg(not(unix))]
+            Change::Chmod { .. } | Change::Chown { .. } | Change::ChmodTree { .. } | Change::ChownTree { .. } => {
+                Err(io::Error::from(io::ErrorKind::Unsupported))
+            }
+        }
+    }
+}
+
+/// The value seen most often
+fn most_common<T: std::hash::Hash + Eq + Copy>(values: impl Iterator<Item = T>) -> Option<T> {
+    let mut counts = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_insert(0usize) += 1;
+    }
+    counts.into_iter().max_by_key(|(_, count)| *count).map(|(value, _)| value)
+}
+
+/// `path` relative to `root`, which it is in
+fn relative(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+/// Call `f` on `root` and then on everything below it, the way a recursive
+/// chmod or chown went through it. Carries on past failures and returns the
+/// first.
+#[cfg(unix)]
+fn walk_tree(root: &Path, f: &mut dyn FnMut(&Path) -> io::Result<()>) -> io::Result<()> {
+    fn walk(path: &Path, f: &mut dyn FnMut(&Path) -> io::Result<()>, first: &mut Option<io::Error>) {
+        if let Err(e) = f(path) {
+            first.get_or_insert_with(|| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+            return;
+        }
+        if !path.is_dir() {
+            return;
+        }
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    walk(&entry.path(), f, first);
+                }
+            }
+            Err(e) => {
+                first.get_or_insert_with(|| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+            }
+        }
+    }
+
+    let mut first = None;
+    walk(root, f, &mut first);
+    first.map_or(Ok(()), Err)
+}
+
+/// What identifies the state of a path: changing its contents, size,
+/// permissions or owner changes this
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    size: u64,
+    modified_secs: i64,
+    modified_nanos: u32,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+}
+
+impl Stamp {
+    /// Stamp of the path now, or None if it doesn't exist
+    fn of(path: &Path) -> Option<Stamp> {
+        let meta = fs::symlink_metadata(path).ok()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Some(Stamp {
+                size: meta.size(),
+                modified_secs: meta.mtime(),
+                modified_nanos: meta.mtime_nsec() as u32,
+                mode: meta.mode(),
+                uid: meta.uid(),
+                gid: meta.gid(),
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let modified = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+            Some(Stamp {
+                size: meta.len(),
+                modified_secs: modified.as_secs() as i64,
+                modified_nanos: modified.subsec_nanos(),
+                mode: meta.permissions().readonly() as u32,
+                uid: 0,
+                gid: 0,
+            })
+        }
+    }
+}
+
+/// A change with the state its path was left in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    #[serde(flatten)]
+    pub change: Change,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<Stamp>,
+}
+
+/// One operation in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// What was done, for the user: "Move 3 item(s) to /tmp"
+    pub description: String,
+    pub steps: Vec<Step>,
+}
+
+impl Entry {
+    /// Paths that have changed since the operation, or are gone
+    pub fn changed(&self) -> Vec<PathBuf> {
+        self.steps
+            .iter()
+            .filter(|step| Stamp::of(step.change.target()) != step.stamp)
+            .map(|step| step.change.target().to_path_buf())
+            .collect()
+    }
+
+    /// Revert the steps, last first, calling `progress` with each path and
+    /// how many steps came before it. Returns how many were reverted, an
+    /// error for each that couldn't be, and an entry holding those steps so
+    /// they can be tried again, along with any left when `cancel` was raised.
+    pub fn undo(&self, cancel: &AtomicBool, progress: &dyn Fn(&Path, usize)) -> (usize, Vec<String>, Option<Entry>) {
+        let mut count = 0;
+        let mut errors = Vec::new();
+        let mut failed = Vec::new();
+        for (done, step) in self.steps.iter().rev().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                failed.push(step.clone());
+                continue;
+            }
+            progress(step.change.target(), done);
+            match step.change.revert() {
+                Ok(()) => count += 1,
+                Err(e) => {
+                    errors.push(format!("{}: {}", step.change.target().display(), e));
+                    failed.push(step.clone());
+                }
+            }
+        }
+        let rest = (!failed.is_empty()).then(|| {
+            failed.reverse();
+            Entry { description: self.description.clone(), steps: failed }
+        });
+        (count, errors, rest)
+    }
+}
+
+/// One record in the journal file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Record {
+    /// An operation was done
+    Done(Entry),
+    /// The most recent operation was undone
+    Undone,
+}
+
+/// The journal file: records, in the order they happened
+#[derive(Default, Serialize, Deserialize)]
+struct Saved {
+    #[serde(default)]
+    record: Vec<Record>,
+}
+
+/// Completed operations, oldest first, kept in a file
+pub struct Journal {
+    path: Option<PathBuf>,
+    entries: Vec<Entry>,
+    /// Records in the file since it was last rewritten
+    records: usize,
+}
+
+impl Journal {
+    /// The journal kept at `path`; empty if it can't be read. Without a
+    /// path the journal only lasts the session.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let saved = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| toml_edit::de::from_str::<Saved>(&content).ok())
+            .unwrap_or_default();
+        let records = saved.record.len();
+        let mut entries = Vec::new();
+        for record in saved.record {
+            Self::apply(&mut entries, record);
+        }
+        Self { path, entries, records }
+    }
+
+    /// Record an operation made of `changes`, all already done. Nothing is
+    /// recorded for an empty list.
+    pub fn record(&mut self, description: String, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+        let steps = changes
+            .into_iter()
+            .map(|change| {
+                let stamp = Stamp::of(change.target());
+                Step { change, stamp }
+            })
+            .collect();
+        self.push(Entry { description, steps });
+    }
+
+    /// Put an entry back on top, such as the steps an undo couldn't revert
+    pub fn push(&mut self, entry: Entry) {
+        self.append(Record::Done(entry.clone()));
+        Self::apply(&mut self.entries, Record::Done(entry));
+    }
+
+    /// The most recent operation
+    pub fn last(&self) -> Option<&Entry> {
+        self.entries.last()
+    }
+
+    /// Forget the most recent operation, once it's been undone
+    pub fn pop(&mut self) -> Option<Entry> {
+        let entry = self.entries.pop();
+        if entry.is_some() {
+            self.append(Record::Undone);
+        }
+        entry
+    }
+
+    /// Replay one record. The file is replayed through here too, so what's
+    /// dropped beyond `MAX_ENTRIES` is the same either way.
+    fn apply(entries: &mut Vec<Entry>, record: Record) {
+        match record {
+            Record::Done(entry) => {
+                entries.push(entry);
+                if entries.len() > MAX_ENTRIES {
+                    entries.remove(0);
+                }
+            }
+            Record::Undone => {
+                entries.pop();
+            }
+        }
+    }
+
+    /// Add a record to the end of the file, or rewrite the file once it has
+    /// grown long. Called before `record` is applied to the entries.
+    fn append(&mut self, record: Record) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if self.records >= MAX_RECORDS {
+            let mut entries = self.entries.clone();
+            Self::apply(&mut entries, record.clone());
+            if self.rewrite(entries) {
+                return;
+            }
+        }
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let saved = Saved { record: vec![record] };
+        let Ok(content) = toml_edit::ser::to_string_pretty(&saved) else {
+            return;
+        };
+        let appended = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(format!("\n{}", content).as_bytes()));
+        if appended.is_ok() {
+            self.records += 1;
+        }
+    }
+
+    /// Replace the file with just `entries`, through a temporary file so a
+    /// crash midway leaves the old one. Returns false if that failed.
+    fn rewrite(&mut self, entries: Vec<Entry>) -> bool {
+        let Some(path) = &self.path else {
+            return false;
+        };
+        let count = entries.len();
+        let saved = Saved { record: entries.into_iter().map(Record::Done).collect() };
+        let Ok(content) = toml_edit::ser::to_string_pretty(&saved) else {
+            return false;
+        };
+        let temp = path.with_extension("toml.tmp");
+        let written = fs::File::create(&temp).and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        });
+        if written.and_then(|()| fs::rename(&temp, path)).is_ok() {
+            self.records = count;
+            true
+        } else {
+            let _ = fs::remove_file(&temp);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let dir = ScratchDir::new("undo-roundtrip");
+        let file = dir.join("undo.toml");
+        let mut journal = Journal::load(Some(file.clone()));
+        journal.record("Create directory new".into(), vec![Change::Mkdir { path: dir.join("new") }]);
+        journal.record(
+            "Move 2 item(s)".into(),
+            vec![
+                Change::Move { from: dir.join("a"), to: dir.join("b") },
+                Change::Chown { path: dir.join("c"), uid: 1000, gid: 100 },
+            ],
+        );
+        journal.record("Nothing".into(), Vec::new());
+        journal.record("Create directory other".into(), vec![Change::Mkdir { path: dir.join("other") }]);
+        journal.pop();
+
+        let loaded = Journal::load(Some(file.clone()));
+        assert_eq!(loaded.entries, journal.entries);
+        assert_eq!(loaded.last().map(|e| e.description.as_str()), Some("Move 2 item(s)"));
+
+        // Records pile up until the file is rewritten with just the entries
+        for i in 0..MAX_RECORDS {
+            journal.record(format!("Create directory {}", i), vec![Change::Mkdir { path: dir.join("x") }]);
+            journal.pop();
+        }
+        assert!(journal.records < MAX_RECORDS);
+        let loaded = Journal::load(Some(file));
+        assert_eq!(loaded.entries, journal.entries);
+        assert_eq!(loaded.records, journal.records);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_undo_chmod_tree() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = ScratchDir::new("undo-tree");
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        for name in ["a", "b", "sub/c"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+        let set = |path: &Path, mode: u32| fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+        set(&root.join("b"), 0o600);
+        let paths = [root.clone(), root.join("sub"), root.join("a"), root.join("b"), root.join("sub/c")];
+        let old: Vec<_> = paths.iter().map(|p| (p.clone(), p.is_dir(), mode(p))).collect();
+        let before: Vec<_> = paths.iter().map(|p| mode(p)).collect();
+
+        // One step for the whole tree, listing only the file that differed
+        let change = Change::chmod_tree(root.clone(), old).unwrap();
+        let Change::ChmodTree { others, .. } = &change else {
+            panic!("not a tree: {:?}", change);
+        };
+        assert_eq!(others.keys().collect::<Vec<_>>(), vec![Path::new("b")]);
+
+        for path in &paths {
+            set(path, 0o700);
+        }
+        let mut journal = Journal::load(None);
+        journal.record("Change permissions".into(), vec![change]);
+        let entry = journal.pop().unwrap();
+        assert_eq!(entry.undo(&AtomicBool::new(false), &|_, _| {}), (1, Vec::new(), None));
+        assert_eq!(paths.iter().map(|p| mode(p)).collect::<Vec<_>>(), before);
+    }
+
+    #[test]
+    fn test_undo_move_and_mkdir() {
+        let dir = ScratchDir::new("undo-move");
+        let (from, to) = (dir.join("from.txt"), dir.join("sub/to.txt"));
+        fs::create_dir(dir.join("sub")).unwrap();
+        fs::write(&to, "moved").unwrap();
+
+        let mut journal = Journal::load(None);
+        journal.record("Create directory sub".into(), vec![Change::Mkdir { path: dir.join("sub") }]);
+        journal.record("Move from.txt".into(), vec![Change::Move { from: from.clone(), to: to.clone() }]);
+        assert!(journal.last().unwrap().changed().is_empty());
+
+        // Touching the moved file is noticed
+        fs::write(&to, "edited").unwrap();
+        assert_eq!(journal.last().unwrap().changed(), vec![to.clone()]);
+
+        let entry = journal.pop().unwrap();
+        assert_eq!(entry.undo(&AtomicBool::new(false), &|_, _| {}), (1, Vec::new(), None));
+        assert_eq!(fs::read_to_string(&from).unwrap(), "edited");
+        // Steps that fail are handed back to retry
+        fs::write(&to, "again").unwrap();
+        let (count, errors, rest) = entry.undo(&AtomicBool::new(false), &|_, _| {});
+        assert_eq!((count, errors.len()), (0, 1));
+        assert_eq!(rest, Some(entry.clone()));
+        fs::remove_file(&to).unwrap();
+        // The directory is empty again, so it can go too
+        // Steps not reached before a cancel are handed back too
+        let entry = journal.pop().unwrap();
+        assert_eq!(entry.undo(&AtomicBool::new(true), &|_, _| {}), (0, Vec::new(), Some(entry.clone())));
+        assert_eq!(entry.undo(&AtomicBool::new(false), &|_, _| {}).0, 1);
+        assert!(!dir.join("sub").exists());
+        assert!(journal.last().is_none());
+    }
+}
@@ -2,62 +2,16 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-/// Preserve file attributes (permissions, modification time) from src to dest.
-/// Best-effort — errors are silently ignored since the file data is already written.
-fn preserve_attributes(src: &Path, dest: &Path) {
-    if let Ok(meta) = std::fs::metadata(src) {
-        // Preserve modification time
-        if let Ok(mtime) = meta.modified() {
-            let _ = filetime::set_file_mtime(dest, filetime::FileTime::from_system_time(mtime));
-        }
-        // Preserve permissions (Unix only — Windows permissions are handled by std::fs::copy)
-        #[cfg(unix)]
-        {
-            let _ = std::fs::set_permissions(dest, meta.permissions());
-        }
-    }
-}
+use super::copy::{Copier, CopyOptions};
 
-/// Copy a file or directory recursively, preserving attributes
-#[allow(dead_code)]
-pub fn copy_path(src: &PathBuf, dest: &PathBuf) -> std::io::Result<()> {
-    if src.is_dir() {
-        copy_dir_recursive(src, dest)
-    } else {
-        std::fs::copy(src, dest)?;
-        preserve_attributes(src, dest);
-        Ok(())
-    }
-}
-
-/// Copy a directory recursively, preserving attributes
-#[allow(dead_code)]
-fn copy_dir_recursive(src: &PathBuf, dest: &PathBuf) -> std::io::Result<()> {
-    std::fs::create_dir_all(dest)?;
-
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dest_path = dest.join(entry.file_name());
-
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dest_path)?;
-        } else {
-            std::fs::copy(&src_path, &dest_path)?;
-            preserve_attributes(&src_path, &dest_path);
-        }
-    }
-
-    // Preserve directory attributes after copying contents
-    // (done last so mtime isn't changed by creating children)
-    preserve_attributes(src, dest);
-
-    Ok(())
+/// Copy a file or directory recursively, keeping everything the copy
+/// engine can
+pub fn copy_path(src: &Path, dest: &Path) -> std::io::Result<()> {
+    Copier::new(CopyOptions::FAITHFUL, Arc::new(AtomicBool::new(false))).copy(src, dest, &|_| {})
 }
 
 /// Move a file or directory
-#[allow(dead_code)]
-pub fn move_path(src: &PathBuf, dest: &PathBuf) -> std::io::Result<()> {
+pub fn move_path(src: &Path, dest: &Path) -> std::io::Result<()> {
     // Try rename first (fast, works on same filesystem)
     match std::fs::rename(src, dest) {
         Ok(()) => Ok(()),
@@ -118,58 +72,12 @@ pub fn copy_file_with_progress(
     Ok(total)
 }
 
-/// Copy a file or directory recursively with progress callback.
-/// The callback receives the number of bytes just written in each chunk.
-pub fn copy_path_with_progress(
-    src: &Path,
-    dest: &Path,
-    cancel: &Arc<AtomicBool>,
-    progress: &dyn Fn(u64),
-) -> std::io::Result<()> {
-    if src.is_dir() {
-        copy_dir_recursive_progress(src, dest, cancel, progress)
-    } else {
-        copy_file_with_progress(src, dest, cancel, progress)?;
-        preserve_attributes(src, dest);
-        Ok(())
-    }
-}
-
-fn copy_dir_recursive_progress(
-    src: &Path,
-    dest: &Path,
-    cancel: &Arc<AtomicBool>,
-    progress: &dyn Fn(u64),
-) -> std::io::Result<()> {
-    std::fs::create_dir_all(dest)?;
-
-    for entry in std::fs::read_dir(src)? {
-        if cancel.load(Ordering::Relaxed) {
-            return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "cancelled"));
-        }
-        let entry = entry?;
-        let src_path = entry.path();
-        let dest_path = dest.join(entry.file_name());
-
-        if src_path.is_dir() {
-            copy_dir_recursive_progress(&src_path, &dest_path, cancel, progress)?;
-        } else {
-            copy_file_with_progress(&src_path, &dest_path, cancel, progress)?;
-            preserve_attributes(&src_path, &dest_path);
-        }
-    }
-
-    // Preserve directory attributes after contents (so mtime isn't changed by creating children)
-    preserve_attributes(src, dest);
-
-    Ok(())
-}
-
-/// Move a file or directory with progress callback.
+/// Move a file or directory with progress callback. Across filesystems
+/// `copier` copies it before the original is deleted.
 pub fn move_path_with_progress(
     src: &Path,
     dest: &Path,
-    cancel: &Arc<AtomicBool>,
+    copier: &mut Copier,
     progress: &dyn Fn(u64),
 ) -> std::io::Result<()> {
     // Try rename first (fast, same filesystem)
@@ -189,7 +97,7 @@ pub fn move_path_with_progress(
         }
         Err(_) => {
             // Cross-filesystem: copy with progress, then delete
-            copy_path_with_progress(src, dest, cancel, progress)?;
+            copier.copy(src, dest, progress)?;
             if src.is_dir() {
                 std::fs::remove_dir_all(src)?;
             } else {
@@ -206,7 +114,11 @@ pub fn calculate_total_bytes(sources: &[PathBuf]) -> u64 {
 }
 
 fn path_size(path: &Path) -> u64 {
-    if path.is_dir() {
+    // Links count as themselves, so a link to a parent can't loop
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if meta.is_dir() {
         std::fs::read_dir(path)
             .map(|entries| {
                 entries
@@ -216,6 +128,6 @@ fn path_size(path: &Path) -> u64 {
             })
             .unwrap_or(0)
     } else {
-        path.metadata().map(|m| m.len()).unwrap_or(0)
+        meta.len()
     }
 }
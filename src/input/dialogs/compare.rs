@@ -0,0 +1,132 @@
+//! Compare directories, Synchronize and tree diff handlers
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::fs::compare::SyncAction;
+use crate::state::app::App;
+use crate::state::mode::Mode;
+
+/// Handle input in the Compare Directories dialog
+pub fn handle_compare_dirs_mode(app: &mut App, key: KeyEvent) {
+    let Mode::CompareDirs { by_content, focus } = &mut app.mode else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+        }
+        KeyCode::Tab | KeyCode::Down | KeyCode::Right => {
+            *focus = (*focus + 1) % 5;
+        }
+        KeyCode::BackTab | KeyCode::Up | KeyCode::Left => {
+            *focus = (*focus + 4) % 5;
+        }
+        KeyCode::Char(' ') if *focus == 0 => {
+            *by_content = !*by_content;
+        }
+        KeyCode::Enter => {
+            let by_content = *by_content;
+            match *focus {
+                // Enter on the checkbox runs a plain compare
+                0 | 1 => app.start_compare(by_content, false),
+                2 => app.start_compare(by_content, true),
+                3 => app.start_tree_diff(by_content),
+                _ => app.mode = Mode::Normal,
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Handle input in the Synchronize dialog
+pub fn handle_synchronize_mode(app: &mut App, key: KeyEvent) {
+    let Mode::Synchronize { items, cursor, scroll } = &mut app.mode else {
+        return;
+    };
+
+    // Same layout as the command history: 6 rows of margin, 4 of chrome
+    let visible_height = (app.ui.terminal_height.saturating_sub(10) as usize).max(1);
+    let last = items.len().saturating_sub(1);
+
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+            return;
+        }
+        KeyCode::Enter => {
+            let items = std::mem::take(items);
+            app.start_synchronize(items);
+            return;
+        }
+        KeyCode::Up => *cursor = cursor.saturating_sub(1),
+        KeyCode::Down => *cursor = (*cursor + 1).min(last),
+        KeyCode::PageUp => *cursor = cursor.saturating_sub(visible_height),
+        KeyCode::PageDown => *cursor = (*cursor + visible_height).min(last),
+        KeyCode::Home => *cursor = 0,
+        KeyCode::End => *cursor = last,
+        KeyCode::Char(' ') => {
+            if let Some(item) = items.get_mut(*cursor) {
+                item.cycle(true);
+            }
+        }
+        KeyCode::Right => {
+            if let Some(item) = items.get_mut(*cursor) {
+                item.set(SyncAction::CopyToRight);
+            }
+        }
+        KeyCode::Left => {
+            if let Some(item) = items.get_mut(*cursor) {
+                item.set(SyncAction::CopyToLeft);
+            }
+        }
+        KeyCode::Delete => {
+            if let Some(item) = items.get_mut(*cursor) {
+                // Only one side has the entry, so at most one of these applies
+                item.set(SyncAction::DeleteLeft);
+                item.set(SyncAction::DeleteRight);
+            }
+        }
+        _ => {}
+    }
+
+    if *cursor < *scroll {
+        *scroll = *cursor;
+    } else if *cursor >= *scroll + visible_height {
+        *scroll = *cursor + 1 - visible_height;
+    }
+}
+
+/// Handle input in the full-screen tree diff
+pub fn handle_diff_tree_mode(app: &mut App, key: KeyEvent, visible_height: usize) {
+    let Mode::DiffTree { tree, cursor, scroll } = &mut app.mode else {
+        return;
+    };
+
+    let visible_height = visible_height.max(1);
+    let last = tree.nodes.len().saturating_sub(1);
+
+    match key.code {
+        KeyCode::Esc | KeyCode::F(10) | KeyCode::Char('q') => {
+            app.mode = Mode::Normal;
+            return;
+        }
+        KeyCode::Enter | KeyCode::F(3) => {
+            app.open_file_diff();
+            return;
+        }
+        KeyCode::Up | KeyCode::Char('k') => *cursor = cursor.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => *cursor = (*cursor + 1).min(last),
+        KeyCode::PageUp => *cursor = cursor.saturating_sub(visible_height),
+        KeyCode::PageDown => *cursor = (*cursor + visible_height).min(last),
+        KeyCode::Home | KeyCode::Char('g') => *cursor = 0,
+        KeyCode::End | KeyCode::Char('G') => *cursor = last,
+        _ => {}
+    }
+
+    if *cursor < *scroll {
+        *scroll = *cursor;
+    } else if *cursor >= *scroll + visible_height {
+        *scroll = *cursor + 1 - visible_height;
+    }
+}
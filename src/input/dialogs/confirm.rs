@@ -2,25 +2,38 @@
 
 use std::path::PathBuf;
 use crossterm::event::{KeyCode, KeyEvent};
+use crate::fs::copy::CopyOptions;
 use crate::input::TextField;
 use crate::state::app::App;
-use crate::state::mode::Mode;
+use crate::state::mode::{FileOperation, Mode};
 
 pub fn handle_confirming_mode(app: &mut App, key: KeyEvent) {
-    let Mode::Confirming { operation, sources, dest_input, cursor_pos, focus, apply_all } = &mut app.mode else {
+    let Mode::Confirming { operation, sources, dest_input, cursor_pos, focus, apply_all, options } = &mut app.mode else {
         return;
     };
 
-    let is_delete = matches!(operation, crate::state::mode::FileOperation::Delete);
-    // When deleting a single directory, checkbox is a focusable element:
-    //   focus 1 = checkbox, 2 = Delete, 3 = Cancel
-    // Otherwise for delete: focus 1 = Delete, 2 = Cancel
-    // For copy/move: focus 0 = input, 1 = OK, 2 = Cancel
-    let show_checkbox = is_delete && sources.len() == 1 && sources[0].is_dir();
-    let max_focus = if show_checkbox { 3 } else { 2 };
+    let is_delete = operation.is_delete();
+    let is_copy = matches!(operation, FileOperation::Copy);
+    // Checkboxes are focusable elements between the input and the buttons:
+    //   copy: focus 0 = input, 1-5 = copy options, 6 = OK, 7 = Cancel
+    //   move: focus 0 = input, 1 = OK, 2 = Cancel
+    //   delete of a single directory: focus 1 = checkbox, 2 = Delete, 3 = Cancel
+    //   other delete or trash: focus 1 = Delete, 2 = Cancel
+    let show_checkbox = matches!(operation, FileOperation::Delete)
+        && sources.len() == 1
+        && sources[0].is_dir();
+    let checkboxes = if is_copy {
+        CopyOptions::LABELS.len()
+    } else if show_checkbox {
+        1
+    } else {
+        0
+    };
     let min_focus = if is_delete { 1 } else { 0 };
-    let delete_button = if show_checkbox { 2 } else { 1 };
-    let cancel_button = if show_checkbox { 3 } else { 2 };
+    let delete_button = checkboxes + 1;
+    let cancel_button = checkboxes + 2;
+    let max_focus = cancel_button;
+    let on_checkbox = (1..=checkboxes).contains(focus);
 
     match key.code {
         KeyCode::Esc => {
@@ -29,8 +42,12 @@ pub fn handle_confirming_mode(app: &mut App, key: KeyEvent) {
         }
 
         // Space toggles checkbox when it's focused
-        KeyCode::Char(' ') if show_checkbox && *focus == 1 => {
-            *apply_all = !*apply_all;
+        KeyCode::Char(' ') if on_checkbox => {
+            if is_copy {
+                options.toggle(*focus - 1);
+            } else {
+                *apply_all = !*apply_all;
+            }
         }
 
         KeyCode::Tab => {
@@ -55,8 +72,12 @@ pub fn handle_confirming_mode(app: &mut App, key: KeyEvent) {
             app.ui.input_selected = false;
 
             // Enter on checkbox toggles it
-            if show_checkbox && *focus == 1 {
-                *apply_all = !*apply_all;
+            if on_checkbox {
+                if is_copy {
+                    options.toggle(*focus - 1);
+                } else {
+                    *apply_all = !*apply_all;
+                }
                 return;
             }
 
@@ -66,11 +87,15 @@ pub fn handle_confirming_mode(app: &mut App, key: KeyEvent) {
                 let sources = sources.clone();
                 let dest = PathBuf::from(dest_input.as_str());
                 let apply = *apply_all;
+                let options = *options;
                 app.mode = Mode::Normal;
+                if is_copy {
+                    app.copy_options = options;
+                }
 
                 // For delete of a single directory without apply_all,
                 // enumerate contents and confirm each item individually
-                if matches!(operation, crate::state::mode::FileOperation::Delete)
+                if matches!(operation, FileOperation::Delete)
                     && !apply
                     && sources.len() == 1
                     && sources[0].is_dir()
@@ -262,154 +287,96 @@ pub fn handle_delete_iterative_mode(app: &mut App, key: KeyEvent) {
 
 pub fn handle_overwrite_confirm_mode(app: &mut App, key: KeyEvent) {
     let Mode::OverwriteConfirm {
-        operation, all_sources, dest, conflicts, current_conflict,
-        skip_set, overwrite_all, focus,
+        conflicts, current_conflict, skip_set, overwrite_all, resumable, resume, focus, ..
     } = &mut app.mode else {
         return;
     };
 
-    let button_count = 5; // Yes, All, Skip, SkipAll, Cancel
+    let button_count = 6; // Yes, All, Resume, Skip, SkipAll, Cancel
+    // Resume is only offered for a shorter copy a remote job can carry on from
+    let can_resume = conflicts.get(*current_conflict).is_some_and(|c| resumable.contains_key(c));
 
-    match key.code {
-        KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('C') => {
-            app.mode = Mode::Normal;
-        }
+    // Hotkeys pick the same buttons as Enter
+    let choice = match key.code {
+        KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('C') => 5,
+        KeyCode::Char('y') | KeyCode::Char('Y') => 0,
+        KeyCode::Char('a') | KeyCode::Char('A') => 1,
+        KeyCode::Char('r') | KeyCode::Char('R') => 2,
+        KeyCode::Char('s') | KeyCode::Char('S') => 3,
+        KeyCode::Char('n') | KeyCode::Char('N') => 4,
+        KeyCode::Enter => *focus,
 
-        KeyCode::Char('y') | KeyCode::Char('Y') => {
-            // Overwrite this one — advance to next conflict
-            *current_conflict += 1;
-            if *current_conflict >= conflicts.len() || *overwrite_all {
-                let op = operation.clone();
-                let srcs: Vec<PathBuf> = all_sources.iter()
-                    .filter(|s| !skip_set.contains(*s))
-                    .cloned().collect();
-                let d = dest.clone();
-                app.mode = Mode::Normal;
-                app.execute_file_operation(op, srcs, d);
+        KeyCode::Tab | KeyCode::Right | KeyCode::Down => {
+            *focus = (*focus + 1) % button_count;
+            if *focus == 2 && !can_resume {
+                *focus += 1;
             }
+            return;
         }
 
-        KeyCode::Char('a') | KeyCode::Char('A') => {
-            // Overwrite all remaining
-            let op = operation.clone();
-            let srcs: Vec<PathBuf> = all_sources.iter()
-                .filter(|s| !skip_set.contains(*s))
-                .cloned().collect();
-            let d = dest.clone();
-            app.mode = Mode::Normal;
-            app.execute_file_operation(op, srcs, d);
+        KeyCode::BackTab | KeyCode::Left | KeyCode::Up => {
+            *focus = if *focus == 0 { button_count - 1 } else { *focus - 1 };
+            if *focus == 2 && !can_resume {
+                *focus -= 1;
+            }
+            return;
         }
 
-        KeyCode::Char('s') | KeyCode::Char('S') => {
-            // Skip this one
+        _ => return,
+    };
+
+    let settled = match choice {
+        // Yes: overwrite this one and go on to the next conflict
+        0 => {
+            *current_conflict += 1;
+            *current_conflict >= conflicts.len() || *overwrite_all
+        }
+        // All: overwrite all remaining
+        1 => true,
+        // Resume: carry on after what the copy already has
+        2 if can_resume => {
+            let conflict_src = conflicts[*current_conflict].clone();
+            let copied = resumable[&conflict_src];
+            resume.insert(conflict_src, copied);
+            *current_conflict += 1;
+            *current_conflict >= conflicts.len()
+        }
+        // Skip this one
+        3 => {
             if let Some(conflict_src) = conflicts.get(*current_conflict).cloned() {
                 skip_set.insert(conflict_src);
             }
             *current_conflict += 1;
-            if *current_conflict >= conflicts.len() {
-                let op = operation.clone();
-                let srcs: Vec<PathBuf> = all_sources.iter()
-                    .filter(|s| !skip_set.contains(*s))
-                    .cloned().collect();
-                let d = dest.clone();
-                app.mode = Mode::Normal;
-                if !srcs.is_empty() {
-                    app.execute_file_operation(op, srcs, d);
-                }
-            }
+            *current_conflict >= conflicts.len()
         }
-
-        KeyCode::Char('n') | KeyCode::Char('N') => {
-            // Skip All remaining
-            for i in *current_conflict..conflicts.len() {
-                if let Some(c) = conflicts.get(i).cloned() {
-                    skip_set.insert(c);
-                }
-            }
-            let op = operation.clone();
-            let srcs: Vec<PathBuf> = all_sources.iter()
-                .filter(|s| !skip_set.contains(*s))
-                .cloned().collect();
-            let d = dest.clone();
-            app.mode = Mode::Normal;
-            if !srcs.is_empty() {
-                app.execute_file_operation(op, srcs, d);
-            }
+        // Skip All remaining
+        4 => {
+            skip_set.extend(conflicts.iter().skip(*current_conflict).cloned());
+            true
         }
-
-        KeyCode::Tab | KeyCode::Right | KeyCode::Down => {
-            *focus = (*focus + 1) % button_count;
-        }
-
-        KeyCode::BackTab | KeyCode::Left | KeyCode::Up => {
-            *focus = if *focus == 0 { button_count - 1 } else { *focus - 1 };
+        // Cancel
+        5 => {
+            app.mode = Mode::Normal;
+            return;
         }
-
-        KeyCode::Enter => {
-            // Dispatch based on focused button
-            match *focus {
-                0 => { // Yes
-                    *current_conflict += 1;
-                    if *current_conflict >= conflicts.len() || *overwrite_all {
-                        let op = operation.clone();
-                        let srcs: Vec<PathBuf> = all_sources.iter()
-                            .filter(|s| !skip_set.contains(*s))
-                            .cloned().collect();
-                        let d = dest.clone();
-                        app.mode = Mode::Normal;
-                        app.execute_file_operation(op, srcs, d);
-                    }
-                }
-                1 => { // All
-                    let op = operation.clone();
-                    let srcs: Vec<PathBuf> = all_sources.iter()
-                        .filter(|s| !skip_set.contains(*s))
-                        .cloned().collect();
-                    let d = dest.clone();
-                    app.mode = Mode::Normal;
-                    app.execute_file_operation(op, srcs, d);
-                }
-                2 => { // Skip
-                    if let Some(conflict_src) = conflicts.get(*current_conflict).cloned() {
-                        skip_set.insert(conflict_src);
-                    }
-                    *current_conflict += 1;
-                    if *current_conflict >= conflicts.len() {
-                        let op = operation.clone();
-                        let srcs: Vec<PathBuf> = all_sources.iter()
-                            .filter(|s| !skip_set.contains(*s))
-                            .cloned().collect();
-                        let d = dest.clone();
-                        app.mode = Mode::Normal;
-                        if !srcs.is_empty() {
-                            app.execute_file_operation(op, srcs, d);
-                        }
-                    }
-                }
-                3 => { // Skip All
-                    for i in *current_conflict..conflicts.len() {
-                        if let Some(c) = conflicts.get(i).cloned() {
-                            skip_set.insert(c);
-                        }
-                    }
-                    let op = operation.clone();
-                    let srcs: Vec<PathBuf> = all_sources.iter()
-                        .filter(|s| !skip_set.contains(*s))
-                        .cloned().collect();
-                    let d = dest.clone();
-                    app.mode = Mode::Normal;
-                    if !srcs.is_empty() {
-                        app.execute_file_operation(op, srcs, d);
-                    }
-                }
-                4 => { // Cancel
-                    app.mode = Mode::Normal;
-                }
-                _ => {}
-            }
+        _ => false,
+    };
+    if !settled {
+        // The next conflict may have nothing to resume
+        if *focus == 2 && !conflicts.get(*current_conflict).is_some_and(|c| resumable.contains_key(c)) {
+            *focus = 0;
         }
+        return;
+    }
 
-        _ => {}
+    let Mode::OverwriteConfirm { operation, all_sources, dest, skip_set, resume, .. } =
+        std::mem::replace(&mut app.mode, Mode::Normal)
+    else {
+        return;
+    };
+    let sources: Vec<PathBuf> = all_sources.into_iter().filter(|s| !skip_set.contains(s)).collect();
+    if !sources.is_empty() {
+        app.execute_file_operation(operation, sources, dest, resume);
     }
 }
 
@@ -469,7 +436,8 @@ pub fn handle_simple_confirm_mode(app: &mut App, key: KeyEvent) {
 
     match key.code {
         KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
-            app.mode = Mode::Normal;
+            let action = action.clone();
+            app.cancel_simple_confirm_action(action);
         }
 
         KeyCode::Char('y') | KeyCode::Char('Y') => {
@@ -484,7 +452,8 @@ pub fn handle_simple_confirm_mode(app: &mut App, key: KeyEvent) {
                 app.mode = Mode::Normal;
                 app.execute_simple_confirm_action(action);
             } else {
-                app.mode = Mode::Normal;
+                let action = action.clone();
+                app.cancel_simple_confirm_action(action);
             }
         }
 
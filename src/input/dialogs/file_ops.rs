@@ -1,7 +1,7 @@
-//! File operation dialog handlers (mkdir, find, select)
+//! File operation dialog handlers (mkdir, pack, find, select)
 
-use std::path::PathBuf;
 use crossterm::event::{KeyCode, KeyEvent};
+use crate::fs::find::FindField;
 use crate::input::TextField;
 use crate::state::app::App;
 use crate::state::mode::Mode;
@@ -98,35 +98,9 @@ pub fn handle_mkdir_mode(app: &mut App, key: KeyEvent) {
     }
 }
 
-/// Check if focus is on a text field for find files dialog
-fn is_find_text_field(focus: usize) -> bool {
-    matches!(focus, 0 | 2 | 4)
-}
-
-/// Get field length for find files dialog
-fn find_field_len(focus: usize, pattern: &str, content: &str, path: &str) -> usize {
-    match focus {
-        0 => pattern.len(),
-        2 => content.len(),
-        4 => path.len(),
-        _ => 0,
-    }
-}
-
-pub fn handle_find_files_mode(app: &mut App, key: KeyEvent) {
-    let Mode::FindFiles {
-        pattern_input,
-        pattern_cursor,
-        pattern_case_sensitive,
-        content_input,
-        content_cursor,
-        content_case_sensitive,
-        path_input,
-        path_cursor,
-        recursive,
-        focus,
-    } = &mut app.mode
-    else {
+/// Handle key input in the pack dialog
+pub fn handle_pack_mode(app: &mut App, key: KeyEvent) {
+    let Mode::Packing { sources, name_input, cursor_pos, format, level, focus } = &mut app.mode else {
         return;
     };
 
@@ -137,196 +111,158 @@ pub fn handle_find_files_mode(app: &mut App, key: KeyEvent) {
         }
 
         KeyCode::Tab => {
-            *focus = (*focus + 1) % 8;
-            let len = find_field_len(*focus, pattern_input, content_input, path_input);
-            app.ui.input_selected = is_find_text_field(*focus) && len > 0;
+            *focus = (*focus + 1) % 5;
+            app.ui.input_selected = false;
         }
 
         KeyCode::BackTab => {
-            *focus = if *focus == 0 { 7 } else { *focus - 1 };
-            let len = find_field_len(*focus, pattern_input, content_input, path_input);
-            app.ui.input_selected = is_find_text_field(*focus) && len > 0;
+            *focus = if *focus == 0 { 4 } else { *focus - 1 };
+            app.ui.input_selected = false;
         }
 
-        KeyCode::Char(' ') if *focus == 1 => {
-            *pattern_case_sensitive = !*pattern_case_sensitive;
-        }
-        KeyCode::Char(' ') if *focus == 3 => {
-            *content_case_sensitive = !*content_case_sensitive;
+        KeyCode::Up if *focus > 0 && *focus < 3 => {
+            *focus -= 1;
         }
-        KeyCode::Char(' ') if *focus == 5 => {
-            *recursive = !*recursive;
+        KeyCode::Down if *focus < 3 => {
+            *focus += 1;
         }
 
         KeyCode::Enter => {
             app.ui.input_selected = false;
-            match *focus {
-                0..=6 => {
-                    let pattern = pattern_input.clone();
-                    let pat_case = *pattern_case_sensitive;
-                    let content = content_input.clone();
-                    let cont_case = *content_case_sensitive;
-                    let path = PathBuf::from(path_input.clone());
-                    let rec = *recursive;
-                    app.mode = Mode::Normal;
-                    app.execute_find_files(&pattern, pat_case, &content, cont_case, &path, rec);
-                }
-                7 => {
-                    app.mode = Mode::Normal;
-                }
-                _ => {}
+            if *focus == 4 {
+                app.mode = Mode::Normal;
+                return;
             }
+            let sources = std::mem::take(sources);
+            let name = name_input.clone();
+            let (format, level) = (*format, *level);
+            app.mode = Mode::Normal;
+            app.start_pack(sources, &name, format, level);
+        }
+
+        // Format and level are cycled with Left/Right
+        KeyCode::Left | KeyCode::Right if *focus == 1 => {
+            *format = format.cycle(key.code == KeyCode::Right);
+            *name_input = format.apply_extension(name_input);
+            *cursor_pos = name_input.len();
+        }
+        KeyCode::Left if *focus == 2 => {
+            *level = level.saturating_sub(1);
+        }
+        KeyCode::Right if *focus == 2 => {
+            *level = (*level + 1).min(9);
+        }
+        KeyCode::Char(c @ '0'..='9') if *focus == 2 => {
+            *level = c as u32 - '0' as u32;
+        }
+
+        KeyCode::Left if *focus == 4 => {
+            *focus = 3;
+        }
+        KeyCode::Right if *focus == 3 => {
+            *focus = 4;
         }
 
-        // Pattern input (focus == 0)
         KeyCode::Backspace if *focus == 0 => {
-            if app.ui.input_selected && !pattern_input.is_empty() {
-                pattern_input.clear();
-                *pattern_cursor = 0;
-            } else {
-                TextField::backspace(pattern_input, pattern_cursor);
-            }
-            app.ui.input_selected = false;
+            TextField::backspace(name_input, cursor_pos);
         }
         KeyCode::Delete if *focus == 0 => {
-            if app.ui.input_selected && !pattern_input.is_empty() {
-                pattern_input.clear();
-                *pattern_cursor = 0;
-            } else {
-                TextField::delete(pattern_input, *pattern_cursor);
-            }
-            app.ui.input_selected = false;
+            TextField::delete(name_input, *cursor_pos);
         }
         KeyCode::Left if *focus == 0 => {
-            app.ui.input_selected = false;
-            TextField::left(pattern_cursor);
+            TextField::left(cursor_pos);
         }
         KeyCode::Right if *focus == 0 => {
-            app.ui.input_selected = false;
-            TextField::right(pattern_input, pattern_cursor);
+            TextField::right(name_input, cursor_pos);
         }
         KeyCode::Home if *focus == 0 => {
-            app.ui.input_selected = false;
-            TextField::home(pattern_cursor);
+            TextField::home(cursor_pos);
         }
         KeyCode::End if *focus == 0 => {
-            app.ui.input_selected = false;
-            TextField::end(pattern_input, pattern_cursor);
+            TextField::end(name_input, cursor_pos);
         }
         KeyCode::Char(c) if *focus == 0 => {
-            if app.ui.input_selected && !pattern_input.is_empty() {
-                pattern_input.clear();
-                *pattern_cursor = 0;
-            }
-            app.ui.input_selected = false;
-            TextField::insert_char(pattern_input, pattern_cursor, c);
+            TextField::insert_char(name_input, cursor_pos, c);
         }
 
-        // Content input (focus == 2)
-        KeyCode::Backspace if *focus == 2 => {
-            if app.ui.input_selected && !content_input.is_empty() {
-                content_input.clear();
-                *content_cursor = 0;
-            } else {
-                TextField::backspace(content_input, content_cursor);
-            }
+        _ => {}
+    }
+}
+
+pub fn handle_find_files_mode(app: &mut App, key: KeyEvent) {
+    let Mode::FindFiles { options, focus, cursor } = &mut app.mode else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Esc => {
             app.ui.input_selected = false;
+            app.mode = Mode::Normal;
         }
-        KeyCode::Delete if *focus == 2 => {
-            if app.ui.input_selected && !content_input.is_empty() {
-                content_input.clear();
-                *content_cursor = 0;
+
+        KeyCode::Tab | KeyCode::Down | KeyCode::BackTab | KeyCode::Up => {
+            *focus = if matches!(key.code, KeyCode::Tab | KeyCode::Down) {
+                focus.next()
             } else {
-                TextField::delete(content_input, *content_cursor);
-            }
-            app.ui.input_selected = false;
+                focus.prev()
+            };
+            // Select the content of text fields so typing replaces it
+            *cursor = options.text(*focus).map_or(0, |t| t.len());
+            app.ui.input_selected = *cursor > 0;
         }
-        KeyCode::Left if *focus == 2 => {
-            app.ui.input_selected = false;
-            TextField::left(content_cursor);
-        }
-        KeyCode::Right if *focus == 2 => {
-            app.ui.input_selected = false;
-            TextField::right(content_input, content_cursor);
+
+        KeyCode::Char(' ') if focus.is_checkbox() => {
+            options.toggle(*focus);
         }
-        KeyCode::Home if *focus == 2 => {
-            app.ui.input_selected = false;
-            TextField::home(content_cursor);
+        KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if *focus == FindField::FileType => {
+            options.file_type = options.file_type.cycle(key.code != KeyCode::Left);
         }
-        KeyCode::End if *focus == 2 => {
-            app.ui.input_selected = false;
-            TextField::end(content_input, content_cursor);
+        KeyCode::Left if *focus == FindField::Cancel => {
+            *focus = FindField::Search;
         }
-        KeyCode::Char(c) if *focus == 2 => {
-            if app.ui.input_selected && !content_input.is_empty() {
-                content_input.clear();
-                *content_cursor = 0;
-            }
-            app.ui.input_selected = false;
-            TextField::insert_char(content_input, content_cursor, c);
+        KeyCode::Right if *focus == FindField::Search => {
+            *focus = FindField::Cancel;
         }
 
-        // Path input (focus == 4)
-        KeyCode::Backspace if *focus == 4 => {
-            if app.ui.input_selected && !path_input.is_empty() {
-                path_input.clear();
-                *path_cursor = 0;
-            } else {
-                TextField::backspace(path_input, path_cursor);
-            }
+        KeyCode::Enter => {
             app.ui.input_selected = false;
-        }
-        KeyCode::Delete if *focus == 4 => {
-            if app.ui.input_selected && !path_input.is_empty() {
-                path_input.clear();
-                *path_cursor = 0;
-            } else {
-                TextField::delete(path_input, *path_cursor);
+            if *focus == FindField::Cancel {
+                app.mode = Mode::Normal;
+                return;
             }
-            app.ui.input_selected = false;
-        }
-        KeyCode::Left if *focus == 4 => {
-            app.ui.input_selected = false;
-            TextField::left(path_cursor);
-        }
-        KeyCode::Right if *focus == 4 => {
-            app.ui.input_selected = false;
-            TextField::right(path_input, path_cursor);
-        }
-        KeyCode::Home if *focus == 4 => {
-            app.ui.input_selected = false;
-            TextField::home(path_cursor);
-        }
-        KeyCode::End if *focus == 4 => {
-            app.ui.input_selected = false;
-            TextField::end(path_input, path_cursor);
-        }
-        KeyCode::Char(c) if *focus == 4 => {
-            if app.ui.input_selected && !path_input.is_empty() {
-                path_input.clear();
-                *path_cursor = 0;
+            let options = options.clone();
+            app.mode = Mode::Normal;
+            app.start_find_files(options);
+        }
+
+        _ => {
+            let Some(text) = options.text_mut(*focus) else {
+                return;
+            };
+            // A selected field is replaced by typing and cleared by Backspace/Delete
+            let replace = app.ui.input_selected && !text.is_empty();
+            match key.code {
+                KeyCode::Backspace | KeyCode::Delete if replace => {
+                    text.clear();
+                    *cursor = 0;
+                }
+                KeyCode::Backspace => TextField::backspace(text, cursor),
+                KeyCode::Delete => TextField::delete(text, *cursor),
+                KeyCode::Left => TextField::left(cursor),
+                KeyCode::Right => TextField::right(text, cursor),
+                KeyCode::Home => TextField::home(cursor),
+                KeyCode::End => TextField::end(text, cursor),
+                KeyCode::Char(c) => {
+                    if replace {
+                        text.clear();
+                        *cursor = 0;
+                    }
+                    TextField::insert_char(text, cursor, c);
+                }
+                _ => return,
             }
             app.ui.input_selected = false;
-            TextField::insert_char(path_input, path_cursor, c);
         }
-
-        KeyCode::Left if *focus >= 6 => {
-            *focus -= 1;
-        }
-        KeyCode::Right if *focus == 6 => {
-            *focus = 7;
-        }
-        KeyCode::Up if *focus >= 6 => {
-            *focus = 5;
-            let len = find_field_len(*focus, pattern_input, content_input, path_input);
-            app.ui.input_selected = is_find_text_field(*focus) && len > 0;
-        }
-        KeyCode::Down if *focus == 5 => {
-            *focus = 6;
-            app.ui.input_selected = false;
-        }
-
-        _ => {}
     }
 }
 
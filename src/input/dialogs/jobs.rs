@@ -0,0 +1,38 @@
+//! Background jobs panel handler
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::state::app::App;
+use crate::state::mode::Mode;
+
+pub fn handle_jobs_mode(app: &mut App, key: KeyEvent) {
+    let Mode::Jobs { cursor } = app.mode else {
+        return;
+    };
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+
+    let cursor = match key.code {
+        KeyCode::Esc | KeyCode::F(10) => {
+            app.mode = Mode::Normal;
+            return;
+        }
+        KeyCode::Char(' ') | KeyCode::Char('p') | KeyCode::Char('P') => {
+            app.jobs.toggle_pause(cursor);
+            cursor
+        }
+        KeyCode::Delete | KeyCode::F(8) | KeyCode::Char('c') | KeyCode::Char('C') => {
+            app.jobs.cancel(cursor);
+            cursor
+        }
+        // Reordering: the cursor follows the job
+        KeyCode::Up if shift => app.jobs.move_job(cursor, true),
+        KeyCode::Down if shift => app.jobs.move_job(cursor, false),
+        KeyCode::Char('K') => app.jobs.move_job(cursor, true),
+        KeyCode::Char('J') => app.jobs.move_job(cursor, false),
+        KeyCode::Up | KeyCode::Char('k') => cursor.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => cursor + 1,
+        KeyCode::Home => 0,
+        KeyCode::End => usize::MAX,
+        _ => cursor,
+    };
+    app.mode = Mode::Jobs { cursor: cursor.min(app.jobs.jobs().len().saturating_sub(1)) };
+}
@@ -3,25 +3,31 @@
 //! Split into focused submodules for maintainability.
 
 mod archive_password;
+mod compare;
 mod confirm;
 mod file_ops;
+mod jobs;
 mod overlay;
 mod plugin;
 mod scp;
 mod shell;
 mod source;
+mod trash;
 mod user_menu;
 mod permissions;
 mod owner;
 mod viewer_search;
 
 pub use archive_password::handle_archive_password_prompt_mode;
+pub use compare::{handle_compare_dirs_mode, handle_diff_tree_mode, handle_synchronize_mode};
 pub use confirm::{handle_confirming_mode, handle_delete_iterative_mode, handle_file_op_error_mode, handle_overwrite_confirm_mode, handle_simple_confirm_mode};
-pub use file_ops::{handle_find_files_mode, handle_mkdir_mode, handle_select_files_mode};
+pub use file_ops::{handle_find_files_mode, handle_mkdir_mode, handle_pack_mode, handle_select_files_mode};
+pub use jobs::handle_jobs_mode;
 pub use plugin::handle_plugin_connect_mode;
-pub use scp::{handle_scp_connect_mode, handle_scp_password_prompt_mode};
+pub use scp::{handle_host_key_confirm_mode, handle_scp_connect_mode, handle_scp_password_prompt_mode};
 pub use shell::{handle_command_history_mode, handle_shell_mode, handle_shell_history_view};
 pub use source::handle_source_selector_mode;
+pub use trash::handle_trash_browser_mode;
 pub use user_menu::{handle_user_menu_mode, handle_user_menu_edit_mode};
 pub use overlay::{handle_overlay_mode, handle_overlay_selector_mode};
 pub use viewer_search::handle_viewer_search_mode;
@@ -23,6 +23,43 @@ fn scp_field_len(focus: usize, name: &str, user: &str, host: &str, port: &str, p
     }
 }
 
+pub fn handle_host_key_confirm_mode(app: &mut App, key: KeyEvent) {
+    let Mode::HostKeyConfirm { focus, display_name, .. } = &mut app.mode else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Esc => {
+            let msg = format!("Connection to {} cancelled: host key not trusted", display_name);
+            app.mode = Mode::Normal;
+            app.add_shell_output(msg);
+        }
+
+        KeyCode::Tab | KeyCode::Right | KeyCode::Down => {
+            *focus = (*focus + 1) % 3;
+        }
+
+        KeyCode::BackTab | KeyCode::Left | KeyCode::Up => {
+            *focus = (*focus + 2) % 3;
+        }
+
+        KeyCode::Char('o') | KeyCode::Char('O') => app.confirm_host_key(false),
+        KeyCode::Char('s') | KeyCode::Char('S') => app.confirm_host_key(true),
+
+        KeyCode::Enter => match *focus {
+            0 => app.confirm_host_key(false),
+            1 => app.confirm_host_key(true),
+            _ => {
+                let msg = format!("Connection to {} cancelled: host key not trusted", display_name);
+                app.mode = Mode::Normal;
+                app.add_shell_output(msg);
+            }
+        },
+
+        _ => {}
+    }
+}
+
 pub fn handle_scp_password_prompt_mode(app: &mut App, key: KeyEvent) {
     let Mode::ScpPasswordPrompt {
         password_input,
@@ -51,6 +51,10 @@ pub fn handle_source_selector_mode(app: &mut App, key: KeyEvent) {
                 let name = connection_name.clone();
                 let target = *target_panel;
                 let provider_type = info.provider_type;
+                // ~/.ssh/config hosts aren't ours to edit
+                if provider_type == ProviderType::Scp && !app.config.connections.iter().any(|c| c.name == name) {
+                    return;
+                }
                 app.mode = Mode::Normal;
                 match provider_type {
                     ProviderType::Scp => app.edit_scp_connection(target, &name),
@@ -74,6 +78,7 @@ pub fn handle_source_selector_mode(app: &mut App, key: KeyEvent) {
                     let name = connection_name.clone();
                     let display_name = info.name.clone();
                     let action = match info.provider_type {
+                        ProviderType::Scp if !app.config.connections.iter().any(|c| c.name == name) => return,
                         ProviderType::Scp => crate::state::mode::SimpleConfirmAction::DeleteConnection { name },
                         ProviderType::Plugin => {
                             // Find the scheme from saved connections
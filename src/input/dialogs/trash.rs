@@ -0,0 +1,47 @@
+//! Trash browser handler
+
+use crossterm::event::{KeyCode, KeyEvent};
+use crate::state::app::App;
+use crate::state::mode::Mode;
+
+pub fn handle_trash_browser_mode(app: &mut App, key: KeyEvent) {
+    let Mode::TrashBrowser { items, cursor, scroll } = &mut app.mode else {
+        return;
+    };
+
+    // Same layout as the command history: 6 rows of margin, 4 of chrome
+    let visible_height = (app.ui.terminal_height.saturating_sub(10) as usize).max(1);
+    let last = items.len().saturating_sub(1);
+
+    match key.code {
+        KeyCode::Esc | KeyCode::F(10) => {
+            app.mode = Mode::Normal;
+            return;
+        }
+        KeyCode::Enter | KeyCode::Char('r') | KeyCode::Char('R') => {
+            app.restore_trashed();
+            return;
+        }
+        KeyCode::F(8) | KeyCode::Delete => {
+            app.confirm_purge_trashed();
+            return;
+        }
+        KeyCode::Char('e') | KeyCode::Char('E') => {
+            app.confirm_empty_trash();
+            return;
+        }
+        KeyCode::Up | KeyCode::Char('k') => *cursor = cursor.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => *cursor = (*cursor + 1).min(last),
+        KeyCode::PageUp => *cursor = cursor.saturating_sub(visible_height),
+        KeyCode::PageDown => *cursor = (*cursor + visible_height).min(last),
+        KeyCode::Home => *cursor = 0,
+        KeyCode::End => *cursor = last,
+        _ => {}
+    }
+
+    if *cursor < *scroll {
+        *scroll = *cursor;
+    } else if *cursor >= *scroll + visible_height {
+        *scroll = *cursor + 1 - visible_height;
+    }
+}
@@ -7,14 +7,14 @@ use crate::state::mode::Mode;
 
 /// Check if focus is on a text field for viewer search dialog
 fn is_search_text_field(focus: usize) -> bool {
-    matches!(focus, 0 | 2)
+    matches!(focus, 0 | 4)
 }
 
 /// Get field length for viewer search dialog
 fn search_field_len(focus: usize, text: &str, hex: &str) -> usize {
     match focus {
         0 => text.len(),
-        2 => hex.len(),
+        4 => hex.len(),
         _ => 0,
     }
 }
@@ -24,15 +24,21 @@ pub fn handle_viewer_search_mode(app: &mut App, key: KeyEvent) {
         text_input,
         text_cursor,
         case_sensitive,
+        regex,
+        multiline,
         hex_input,
         hex_cursor,
         focus,
+        error,
         ..
     } = &mut app.mode
     else {
         return;
     };
 
+    // Any key dismisses the last error
+    *error = None;
+
     match key.code {
         KeyCode::Esc => {
             app.ui.input_selected = false;
@@ -40,13 +46,13 @@ pub fn handle_viewer_search_mode(app: &mut App, key: KeyEvent) {
         }
 
         KeyCode::Tab => {
-            *focus = (*focus + 1) % 5;
+            *focus = (*focus + 1) % 8;
             let len = search_field_len(*focus, text_input, hex_input);
             app.ui.input_selected = is_search_text_field(*focus) && len > 0;
         }
 
         KeyCode::BackTab => {
-            *focus = if *focus == 0 { 4 } else { *focus - 1 };
+            *focus = if *focus == 0 { 7 } else { *focus - 1 };
             let len = search_field_len(*focus, text_input, hex_input);
             app.ui.input_selected = is_search_text_field(*focus) && len > 0;
         }
@@ -54,14 +60,23 @@ pub fn handle_viewer_search_mode(app: &mut App, key: KeyEvent) {
         KeyCode::Char(' ') if *focus == 1 => {
             *case_sensitive = !*case_sensitive;
         }
+        KeyCode::Char(' ') if *focus == 2 => {
+            *regex = !*regex;
+        }
+        KeyCode::Char(' ') if *focus == 3 => {
+            *multiline = !*multiline;
+        }
 
         KeyCode::Enter => {
             app.ui.input_selected = false;
             match *focus {
-                0..=3 => {
-                    app.execute_viewer_search();
+                0..=5 => {
+                    app.execute_viewer_search(false);
                 }
-                4 => {
+                6 => {
+                    app.execute_viewer_search(true);
+                }
+                7 => {
                     app.cancel_viewer_search();
                 }
                 _ => {}
@@ -112,8 +127,8 @@ pub fn handle_viewer_search_mode(app: &mut App, key: KeyEvent) {
             TextField::insert_char(text_input, text_cursor, c);
         }
 
-        // Hex input (focus == 2)
-        KeyCode::Backspace if *focus == 2 => {
+        // Hex input (focus == 4)
+        KeyCode::Backspace if *focus == 4 => {
             if app.ui.input_selected && !hex_input.is_empty() {
                 hex_input.clear();
                 *hex_cursor = 0;
@@ -122,7 +137,7 @@ pub fn handle_viewer_search_mode(app: &mut App, key: KeyEvent) {
             }
             app.ui.input_selected = false;
         }
-        KeyCode::Delete if *focus == 2 => {
+        KeyCode::Delete if *focus == 4 => {
             if app.ui.input_selected && !hex_input.is_empty() {
                 hex_input.clear();
                 *hex_cursor = 0;
@@ -131,23 +146,23 @@ pub fn handle_viewer_search_mode(app: &mut App, key: KeyEvent) {
             }
             app.ui.input_selected = false;
         }
-        KeyCode::Left if *focus == 2 => {
+        KeyCode::Left if *focus == 4 => {
             app.ui.input_selected = false;
             TextField::left(hex_cursor);
         }
-        KeyCode::Right if *focus == 2 => {
+        KeyCode::Right if *focus == 4 => {
             app.ui.input_selected = false;
             TextField::right(hex_input, hex_cursor);
         }
-        KeyCode::Home if *focus == 2 => {
+        KeyCode::Home if *focus == 4 => {
             app.ui.input_selected = false;
             TextField::home(hex_cursor);
         }
-        KeyCode::End if *focus == 2 => {
+        KeyCode::End if *focus == 4 => {
             app.ui.input_selected = false;
             TextField::end(hex_input, hex_cursor);
         }
-        KeyCode::Char(c) if *focus == 2 => {
+        KeyCode::Char(c) if *focus == 4 => {
             if app.ui.input_selected && !hex_input.is_empty() {
                 hex_input.clear();
                 *hex_cursor = 0;
@@ -156,19 +171,20 @@ pub fn handle_viewer_search_mode(app: &mut App, key: KeyEvent) {
             TextField::insert_char_if(hex_input, hex_cursor, c, |c| c.is_ascii_hexdigit() || c == ' ')
         }
 
-        KeyCode::Left if *focus >= 3 => {
+        // The checkboxes share a row, and so do the buttons
+        KeyCode::Left if matches!(*focus, 2 | 3 | 6 | 7) => {
             *focus -= 1;
         }
-        KeyCode::Right if *focus == 3 => {
-            *focus = 4;
+        KeyCode::Right if matches!(*focus, 1 | 2 | 5 | 6) => {
+            *focus += 1;
         }
-        KeyCode::Up if *focus >= 3 => {
-            *focus = 2;
+        KeyCode::Up if *focus >= 5 => {
+            *focus = 4;
             let len = search_field_len(*focus, text_input, hex_input);
             app.ui.input_selected = is_search_text_field(*focus) && len > 0;
         }
-        KeyCode::Down if *focus == 2 => {
-            *focus = 3;
+        KeyCode::Down if *focus == 4 => {
+            *focus = 5;
             app.ui.input_selected = false;
         }
 
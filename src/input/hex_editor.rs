@@ -0,0 +1,132 @@
+//! Hex editor key handling
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::input::TextField;
+use crate::state::app::App;
+use crate::state::hex_editor::{HexConfirm, HexEditor};
+use crate::state::mode::Mode;
+use crate::utils::calculate_hex_bytes_per_line;
+
+pub fn handle_hex_editor_mode(app: &mut App, key: KeyEvent, visible_height: usize) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let bytes_per_line = calculate_hex_bytes_per_line(app.ui.terminal_width as usize);
+    let Mode::HexEditing { editor } = &mut app.mode else {
+        return;
+    };
+    editor.message = None;
+
+    // Save or discard question
+    if let Some((question, focus)) = &mut editor.confirm {
+        let yes = match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => true,
+            KeyCode::Enter => *focus == 0,
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => false,
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Left | KeyCode::Right => {
+                *focus = 1 - *focus;
+                return;
+            }
+            _ => return,
+        };
+        let question = *question;
+        editor.confirm = None;
+        match (yes, question) {
+            (true, HexConfirm::Save) => app.save_hex_editor(),
+            (true, HexConfirm::Discard) => app.close_hex_editor(),
+            (false, _) => {}
+        }
+        return;
+    }
+
+    // Go-to-offset prompt
+    if let Some((input, cursor)) = &mut editor.goto {
+        match key.code {
+            KeyCode::Esc => editor.goto = None,
+            KeyCode::Enter => {
+                let input = std::mem::take(input);
+                editor.goto = None;
+                if let Err(e) = editor.goto_offset(&input) {
+                    editor.message = Some(e);
+                }
+            }
+            KeyCode::Backspace => TextField::backspace(input, cursor),
+            KeyCode::Delete => TextField::delete(input, *cursor),
+            KeyCode::Left => TextField::left(cursor),
+            KeyCode::Right => TextField::right(input, cursor),
+            KeyCode::Home => TextField::home(cursor),
+            KeyCode::End => TextField::end(input, cursor),
+            KeyCode::Char(c) => TextField::insert_char_if(input, cursor, c, |c| c.is_ascii_hexdigit() || c == 'x' || c == 'X'),
+            _ => {}
+        }
+        editor.scroll_to_cursor(bytes_per_line, visible_height);
+        return;
+    }
+
+    let page = (bytes_per_line * visible_height.max(1)) as isize;
+    let line_start = editor.cursor - editor.cursor % bytes_per_line;
+    match key.code {
+        // Leave, asking first if there are unsaved changes
+        KeyCode::Esc | KeyCode::F(10) => {
+            if editor.buffer.is_modified() {
+                editor.confirm = Some((HexConfirm::Discard, 1));
+            } else {
+                app.close_hex_editor();
+                return;
+            }
+        }
+
+        // Save (F2 or Ctrl+S)
+        KeyCode::F(2) => ask_save(editor),
+        KeyCode::Char('s') if ctrl => ask_save(editor),
+
+        KeyCode::Char('z') if ctrl => editor.undo(),
+        KeyCode::Char('y') if ctrl => editor.redo(),
+        KeyCode::Char('g') if ctrl => editor.goto = Some((String::new(), 0)),
+
+        // Switch between typing hex digits and characters
+        KeyCode::Tab => {
+            editor.ascii = !editor.ascii;
+            editor.low_nibble = false;
+        }
+        // Switch between overwriting and inserting
+        KeyCode::Insert => editor.insert = !editor.insert,
+
+        KeyCode::Left => editor.move_by(-1),
+        KeyCode::Right => editor.move_by(1),
+        KeyCode::Up => editor.move_by(-(bytes_per_line as isize)),
+        KeyCode::Down => editor.move_by(bytes_per_line as isize),
+        KeyCode::PageUp => editor.move_by(-page),
+        KeyCode::PageDown => editor.move_by(page),
+        KeyCode::Home if ctrl => editor.move_to(0),
+        KeyCode::End if ctrl => editor.move_to(usize::MAX),
+        KeyCode::Home => editor.move_to(line_start),
+        KeyCode::End => editor.move_to(line_start + bytes_per_line - 1),
+
+        KeyCode::Delete => editor.delete(),
+        KeyCode::Backspace => editor.backspace(),
+
+        KeyCode::Char(c) if !ctrl && editor.ascii => {
+            let mut utf8 = [0; 4];
+            for &byte in c.encode_utf8(&mut utf8).as_bytes() {
+                editor.type_byte(byte);
+            }
+        }
+        KeyCode::Char(c) if !ctrl => {
+            if let Some(digit) = c.to_digit(16) {
+                editor.type_hex(digit as u8);
+            }
+        }
+
+        _ => {}
+    }
+    editor.scroll_to_cursor(bytes_per_line, visible_height);
+}
+
+/// Ask before writing the changes to the file
+fn ask_save(editor: &mut HexEditor) {
+    if editor.buffer.is_modified() {
+        editor.confirm = Some((HexConfirm::Save, 0));
+    } else {
+        editor.message = Some("No changes to save".to_string());
+    }
+}
@@ -4,6 +4,7 @@
 
 mod normal;
 mod viewing;
+mod hex_editor;
 mod dialogs;
 mod text_field;
 
@@ -29,12 +30,30 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
             let height = app.ui.viewer_height;
             viewing::handle_plugin_viewing_mode(app, key, height);
         }
+        Mode::ViewingDiff { .. } => {
+            let height = app.ui.viewer_height;
+            viewing::handle_diff_viewing_mode(app, key, height);
+        }
         Mode::ViewerPluginMenu { .. } => {
             viewing::handle_viewer_plugin_menu(app, key);
         }
         Mode::ViewerSearch { .. } => {
             dialogs::handle_viewer_search_mode(app, key);
         }
+        Mode::ViewerSearching { .. } => {
+            // Escape stops the search; matches found so far are still shown
+            if key.code == KeyCode::Esc {
+                app.cancel_file_operation();
+            }
+        }
+        Mode::ViewerMatches { .. } => {
+            let height = app.ui.viewer_height;
+            viewing::handle_viewer_matches_mode(app, key, height);
+        }
+        Mode::HexEditing { .. } => {
+            let height = app.ui.viewer_height;
+            hex_editor::handle_hex_editor_mode(app, key, height);
+        }
         Mode::Help { .. } => {
             let height = app.ui.viewer_height;
             viewing::handle_help_mode(app, key, height);
@@ -52,10 +71,20 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
         Mode::DeleteIterative { .. } => dialogs::handle_delete_iterative_mode(app, key),
         Mode::SimpleConfirm { .. } => dialogs::handle_simple_confirm_mode(app, key),
         Mode::ScpPasswordPrompt { .. } => dialogs::handle_scp_password_prompt_mode(app, key),
+        Mode::HostKeyConfirm { .. } => dialogs::handle_host_key_confirm_mode(app, key),
         Mode::SourceSelector { .. } => dialogs::handle_source_selector_mode(app, key),
         Mode::MakingDir { .. } => dialogs::handle_mkdir_mode(app, key),
+        Mode::Packing { .. } => dialogs::handle_pack_mode(app, key),
         Mode::CommandHistory { .. } => dialogs::handle_command_history_mode(app, key),
         Mode::FindFiles { .. } => dialogs::handle_find_files_mode(app, key),
+        Mode::CompareDirs { .. } => dialogs::handle_compare_dirs_mode(app, key),
+        Mode::Synchronize { .. } => dialogs::handle_synchronize_mode(app, key),
+        Mode::TrashBrowser { .. } => dialogs::handle_trash_browser_mode(app, key),
+        Mode::Jobs { .. } => dialogs::handle_jobs_mode(app, key),
+        Mode::DiffTree { .. } => {
+            let height = app.ui.viewer_height;
+            dialogs::handle_diff_tree_mode(app, key, height);
+        }
         Mode::SelectFiles { .. } => dialogs::handle_select_files_mode(app, key),
         Mode::ScpConnect { .. } => dialogs::handle_scp_connect_mode(app, key),
         Mode::PluginConnect { .. } => dialogs::handle_plugin_connect_mode(app, key),
@@ -71,7 +100,16 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
         Mode::Overlay { .. } => dialogs::handle_overlay_mode(app, key),
         Mode::OverlaySelector { .. } => dialogs::handle_overlay_selector_mode(app, key),
         Mode::FileOpProgress { .. } => {
-            // During file operations, Escape cancels
+            // During file operations, Escape cancels and B sends the
+            // operation to the job queue
+            match key.code {
+                KeyCode::Esc => app.cancel_file_operation(),
+                KeyCode::Char('b') | KeyCode::Char('B') => app.send_to_background(),
+                _ => {}
+            }
+        }
+        Mode::Searching { .. } => {
+            // Escape stops the search; matches found so far are still shown
             if key.code == KeyCode::Esc {
                 app.cancel_file_operation();
             }
@@ -84,7 +122,8 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
             // F10 / quit exits the app (kill child + quit)
             if app.key_matches("quit", &key) || app.key_matches("quit_alt", &key) {
                 app.cancel_background_task();
-                app.should_quit = true;
+                // Jobs in the queue are asked about first
+                app.request_quit();
             }
         }
     }
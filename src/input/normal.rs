@@ -109,11 +109,11 @@ pub fn handle_normal_mode(app: &mut App, key: KeyEvent) {
     // Check configurable keybindings first
     // Application
     if app.key_matches("quit", &key) {
-        app.should_quit = true;
+        app.request_quit();
         return;
     }
     if app.key_matches("quit_alt", &key) {
-        app.should_quit = true;
+        app.request_quit();
         return;
     }
     if app.key_matches("shell_toggle", &key) {
@@ -140,6 +140,11 @@ pub fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         return;
     }
 
+    // ESC - abandon a directory listing that is still loading
+    if matches!(key.code, KeyCode::Esc) && app.active_panel_mut().cancel_loading() {
+        return;
+    }
+
     // ESC - exit archive if inside one
     if matches!(key.code, KeyCode::Esc) && app.active_panel().is_in_archive() {
         app.active_panel_mut().exit_archive();
@@ -185,21 +190,35 @@ pub fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         app.copy_selected();
         return;
     }
-    // Move is disabled inside archives (read-only)
-    if app.key_matches("move", &key) && !app.active_panel().is_in_archive() {
+    // Inside archives, the archive plugin refuses these for read-only formats
+    if app.key_matches("move", &key) {
         app.move_selected();
         return;
     }
-    // Mkdir is disabled inside archives (read-only)
-    if app.key_matches("mkdir", &key) && !app.active_panel().is_in_archive() {
+    if app.key_matches("mkdir", &key) {
         app.show_mkdir_dialog();
         return;
     }
-    // Delete is disabled inside archives (read-only)
-    if app.key_matches("delete", &key) && !app.active_panel().is_in_archive() {
+    if app.key_matches("pack", &key) {
+        app.show_pack_dialog();
+        return;
+    }
+    if app.key_matches("delete", &key) {
         app.delete_selected();
         return;
     }
+    if app.key_matches("delete_permanent", &key) {
+        app.delete_selected_permanently();
+        return;
+    }
+    if app.key_matches("undo", &key) {
+        app.undo_last_operation();
+        return;
+    }
+    if app.key_matches("jobs", &key) {
+        app.show_jobs();
+        return;
+    }
 
     // Sorting (Ctrl+F-keys)
     if app.key_matches("sort_name_f", &key) {
@@ -270,6 +289,12 @@ pub fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         return;
     }
 
+    // Compare / synchronize directories
+    if app.key_matches("compare_dirs", &key) {
+        app.show_compare_dialog();
+        return;
+    }
+
     // Quick search
     if app.key_matches("quick_search", &key) {
         app.quick_search = Some(String::new());
@@ -537,7 +562,7 @@ pub fn handle_normal_mode(app: &mut App, key: KeyEvent) {
 
         // 'q' to quit only when edit_mode_always is false
         KeyCode::Char('q') if key.modifiers.is_empty() && !app.config.general.edit_mode_always => {
-            app.should_quit = true;
+            app.request_quit();
         }
 
         // In edit_mode_always, regular characters go to command line
@@ -3,147 +3,223 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::state::app::App;
-use crate::state::mode::{BinaryViewMode, Mode};
+use crate::state::mode::{BinaryViewMode, Mode, ViewContent};
+use crate::state::viewer_outline::ViewerOutline;
 use crate::ui::FileViewer;
 
+/// A scroll movement in the built-in viewer
+enum Motion {
+    Up(usize),
+    Down(usize),
+    Top,
+    Bottom,
+}
+
 pub fn handle_viewing_mode(app: &mut App, key: KeyEvent, visible_height: usize) {
     let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
     let term_width = app.ui.terminal_width as usize;
+    app.ui.viewer_notice = None;
+    let layout = &mut app.ui.viewer_layout;
 
-    let Mode::Viewing { content, scroll, binary_mode, .. } = &mut app.mode else {
+    let Mode::Viewing { content, scroll, binary_mode, wrap_row, search_matches, current_match, .. } = &mut app.mode else {
         return;
     };
 
-    let line_count = FileViewer::line_count(content, term_width, *binary_mode);
-    let max_scroll = line_count.saturating_sub(visible_height);
     let half_page = visible_height / 2;
 
     // Handle 'g' prefix commands (gg, ge)
+    let mut motion = None;
     if app.ui.viewer_pending_g {
         app.ui.viewer_pending_g = false;
         match key.code {
             // gg - go to top (helix/vim)
-            KeyCode::Char('g') => {
-                *scroll = 0;
-                return;
-            }
+            KeyCode::Char('g') => motion = Some(Motion::Top),
             // ge - go to end (helix)
-            KeyCode::Char('e') => {
-                *scroll = max_scroll;
-                return;
-            }
+            KeyCode::Char('e') => motion = Some(Motion::Bottom),
             _ => {
                 // Invalid g-command, fall through to normal handling
             }
         }
     }
 
-    match key.code {
-        // Exit viewer
-        KeyCode::Esc | KeyCode::F(3) | KeyCode::Char('q') | KeyCode::F(10) => {
-            app.ui.viewer_pending_g = false;
-            app.mode = Mode::Normal;
-            app.refresh_panels();
-        }
+    if motion.is_none() {
+        match key.code {
+            // Exit viewer
+            KeyCode::Esc | KeyCode::F(3) | KeyCode::Char('q') | KeyCode::F(10) => {
+                app.ui.viewer_pending_g = false;
+                app.mode = Mode::Normal;
+                app.refresh_panels();
+                return;
+            }
 
-        // Show plugin menu (F2)
-        KeyCode::F(2) => {
-            app.show_viewer_plugin_menu();
-        }
+            // Show plugin menu (F2)
+            KeyCode::F(2) => {
+                app.show_viewer_plugin_menu();
+                return;
+            }
 
-        // Open search dialog (/)
-        KeyCode::Char('/') => {
-            app.show_viewer_search();
-        }
+            // Edit the bytes in the hex editor (F4)
+            KeyCode::F(4) => {
+                app.ui.viewer_pending_g = false;
+                if let Mode::Viewing { path, .. } = &app.mode {
+                    let path = path.clone();
+                    app.open_hex_editor(&path);
+                }
+                return;
+            }
 
-        // Next search match (n)
-        KeyCode::Char('n') if !ctrl => {
-            app.viewer_next_match();
-        }
+            // Open search dialog (/)
+            KeyCode::Char('/') => {
+                app.show_viewer_search();
+                return;
+            }
 
-        // Previous search match (N)
-        KeyCode::Char('N') => {
-            app.viewer_prev_match();
-        }
+            // Next search match (n)
+            KeyCode::Char('n') if !ctrl => {
+                app.viewer_next_match();
+                return;
+            }
 
-        // 'g' prefix - start g-command
-        KeyCode::Char('g') => {
-            app.ui.viewer_pending_g = true;
-        }
+            // Previous search match (N)
+            KeyCode::Char('N') => {
+                app.viewer_prev_match();
+                return;
+            }
 
-        // G - go to end (vim)
-        KeyCode::Char('G') => {
-            *scroll = max_scroll;
-        }
+            // List all search matches (L)
+            KeyCode::Char('L') => {
+                app.show_viewer_matches();
+                return;
+            }
 
-        // Toggle view mode (hex/text for text files, hex/cp437 for binary)
-        KeyCode::Tab => {
-            *binary_mode = match *binary_mode {
-                BinaryViewMode::Hex => BinaryViewMode::Cp437,
-                BinaryViewMode::Cp437 => BinaryViewMode::Hex,
-            };
-            // Recalculate max_scroll for new mode and clamp scroll
-            let new_line_count = FileViewer::line_count(content, term_width, *binary_mode);
-            let new_max_scroll = new_line_count.saturating_sub(visible_height);
-            if *scroll > new_max_scroll {
-                *scroll = new_max_scroll;
+            // Cycle the encoding text is decoded with (e forward, E back)
+            KeyCode::Char('e') => {
+                app.viewer_cycle_encoding(true);
+                return;
+            }
+            KeyCode::Char('E') => {
+                app.viewer_cycle_encoding(false);
+                return;
             }
-        }
 
-        // Scroll up - k or Up arrow
-        KeyCode::Up | KeyCode::Char('k') => {
-            *scroll = scroll.saturating_sub(1);
-        }
+            // Follow the file as it grows, like tail -f (F)
+            KeyCode::Char('F') => {
+                app.viewer_toggle_follow();
+                return;
+            }
 
-        // Scroll down - j or Down arrow
-        KeyCode::Down | KeyCode::Char('j') => {
-            if *scroll < max_scroll {
-                *scroll += 1;
+            // 'g' prefix - start g-command
+            KeyCode::Char('g') => {
+                app.ui.viewer_pending_g = true;
             }
-        }
 
-        // Scroll left (not typically used in viewer, but for consistency)
-        KeyCode::Left | KeyCode::Char('h') => {
-            *scroll = scroll.saturating_sub(1);
-        }
+            // G - go to end (vim)
+            KeyCode::Char('G') => motion = Some(Motion::Bottom),
+
+            // Toggle view mode (hex/text for text files, hex/cp437 for binary)
+            KeyCode::Tab => {
+                *binary_mode = match *binary_mode {
+                    BinaryViewMode::Hex => BinaryViewMode::Cp437,
+                    BinaryViewMode::Cp437 => BinaryViewMode::Hex,
+                };
+                // Matches in decoded text don't line up with the raw bytes
+                if matches!(content, ViewContent::Decoded { .. }) {
+                    search_matches.clear();
+                    *current_match = None;
+                }
+                *wrap_row = 0;
+                // Clamp scroll to the new mode's line count
+                motion = Some(Motion::Up(0));
+            }
 
-        // Scroll right (not typically used in viewer, but for consistency)
-        KeyCode::Right | KeyCode::Char('l') => {
-            if *scroll < max_scroll {
-                *scroll += 1;
+            // Toggle soft wrap; the top line stays where it is
+            KeyCode::Char('w') => {
+                layout.wrap = !layout.wrap;
+                *wrap_row = 0;
+                motion = Some(Motion::Up(0));
             }
-        }
 
-        // Half page up - Ctrl+u (vim)
-        KeyCode::Char('u') if ctrl => {
-            *scroll = scroll.saturating_sub(half_page);
-        }
+            // Toggle the line-number gutter, which changes the wrap width
+            KeyCode::Char('#') => {
+                layout.line_numbers = !layout.line_numbers;
+                *wrap_row = 0;
+                motion = Some(Motion::Up(0));
+            }
 
-        // Half page down - Ctrl+d (vim)
-        KeyCode::Char('d') if ctrl => {
-            *scroll = (*scroll + half_page).min(max_scroll);
-        }
+            // Scroll up - k or Up arrow (Left/h kept for consistency)
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Left | KeyCode::Char('h') => motion = Some(Motion::Up(1)),
 
-        // Page up - PageUp or Ctrl+b (vim)
-        KeyCode::PageUp | KeyCode::Char('b') if key.code == KeyCode::PageUp || ctrl => {
-            *scroll = scroll.saturating_sub(visible_height);
-        }
+            // Scroll down - j or Down arrow (Right/l kept for consistency)
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Right | KeyCode::Char('l') => motion = Some(Motion::Down(1)),
 
-        // Page down - PageDown or Ctrl+f (vim)
-        KeyCode::PageDown | KeyCode::Char('f') if key.code == KeyCode::PageDown || ctrl => {
-            *scroll = (*scroll + visible_height).min(max_scroll);
-        }
+            // Half page up - Ctrl+u (vim)
+            KeyCode::Char('u') if ctrl => motion = Some(Motion::Up(half_page)),
 
-        // Home - go to start
-        KeyCode::Home => {
-            *scroll = 0;
-        }
+            // Half page down - Ctrl+d (vim)
+            KeyCode::Char('d') if ctrl => motion = Some(Motion::Down(half_page)),
 
-        // End - go to end
-        KeyCode::End => {
-            *scroll = max_scroll;
+            // Page up - PageUp or Ctrl+b (vim)
+            KeyCode::PageUp | KeyCode::Char('b') if key.code == KeyCode::PageUp || ctrl => {
+                motion = Some(Motion::Up(visible_height));
+            }
+
+            // Page down - PageDown or Ctrl+f (vim)
+            KeyCode::PageDown | KeyCode::Char('f') if key.code == KeyCode::PageDown || ctrl => {
+                motion = Some(Motion::Down(visible_height));
+            }
+
+            // Home - go to start
+            KeyCode::Home => motion = Some(Motion::Top),
+
+            // End - go to end
+            KeyCode::End => motion = Some(Motion::Bottom),
+
+            _ => {}
         }
+    }
 
+    let Some(motion) = motion else {
+        return;
+    };
+
+    // Wrapped text scrolls by screen row, measuring only the lines it
+    // passes; everything else scrolls by whole lines
+    let layout = *layout;
+    if layout.wrap && content.text_lines(*binary_mode).is_some() {
+        let max = FileViewer::max_position(content, layout, term_width, visible_height);
+        let position = match motion {
+            Motion::Up(n) => FileViewer::step_rows(content, layout, term_width, (*scroll, *wrap_row), -(n as isize)),
+            Motion::Down(n) => FileViewer::step_rows(content, layout, term_width, (*scroll, *wrap_row), n as isize),
+            Motion::Top => (0, 0),
+            Motion::Bottom => max,
+        };
+        (*scroll, *wrap_row) = position.min(max);
+    } else {
+        let line_count = FileViewer::line_count(content, term_width, *binary_mode);
+        let max_scroll = line_count.saturating_sub(visible_height);
+        *wrap_row = 0;
+        *scroll = match motion {
+            Motion::Up(n) => scroll.saturating_sub(n),
+            Motion::Down(n) => (*scroll + n).min(max_scroll),
+            Motion::Top => 0,
+            Motion::Bottom => max_scroll,
+        }
+        .min(max_scroll);
+    }
+}
+
+/// Keys for the list of viewer search matches
+pub fn handle_viewer_matches_mode(app: &mut App, key: KeyEvent, visible_height: usize) {
+    let page = visible_height.max(1) as isize;
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('L') => app.close_viewer_matches(false),
+        KeyCode::Enter => app.close_viewer_matches(true),
+        KeyCode::Up | KeyCode::Char('k') => app.viewer_matches_move(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.viewer_matches_move(1),
+        KeyCode::PageUp => app.viewer_matches_move(-page),
+        KeyCode::PageDown => app.viewer_matches_move(page),
+        KeyCode::Home | KeyCode::Char('g') => app.viewer_matches_move(isize::MIN),
+        KeyCode::End | KeyCode::Char('G') => app.viewer_matches_move(isize::MAX),
         _ => {}
     }
 }
@@ -157,13 +233,42 @@ pub fn handle_plugin_viewing_mode(app: &mut App, key: KeyEvent, visible_height:
         return;
     }
 
-    let Mode::ViewingPlugin { scroll, total_lines, status_message, .. } = &mut app.mode else {
+    // Sorting a table has the plugin render it again
+    if key.code == KeyCode::Char('s') && matches!(app.mode, Mode::ViewingPlugin { table: Some(_), .. }) {
+        app.sort_plugin_viewer();
+        return;
+    }
+
+    let term_width = app.ui.terminal_width as usize;
+    let Mode::ViewingPlugin { scroll, total_lines, status_message, outline, table, .. } = &mut app.mode else {
         return;
     };
 
     // Clear any status message on the next keypress.
     *status_message = None;
 
+    if let Some(outline) = outline
+        && handle_outline_key(outline, &mut app.ui.viewer_pending_g, key, visible_height)
+    {
+        *scroll = outline.scroll_to_cursor(*scroll, visible_height);
+        return;
+    }
+
+    // Left/Right step through the columns of a table
+    if let Some(table) = table {
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') => {
+                table.select(-1, term_width);
+                return;
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                table.select(1, term_width);
+                return;
+            }
+            _ => {}
+        }
+    }
+
     let max_scroll = total_lines.saturating_sub(visible_height);
     let half_page = visible_height / 2;
 
@@ -261,6 +366,43 @@ pub fn handle_plugin_viewing_mode(app: &mut App, key: KeyEvent, visible_height:
     }
 }
 
+/// Keys of a plugin viewer showing foldable data, which move a cursor
+/// instead of scrolling. Returns false for keys left to the plain viewer.
+fn handle_outline_key(outline: &mut ViewerOutline, pending_g: &mut bool, key: KeyEvent, visible_height: usize) -> bool {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let page = visible_height.max(1) as isize;
+
+    if std::mem::take(pending_g) {
+        match key.code {
+            KeyCode::Char('g') => outline.cursor = 0,
+            KeyCode::Char('e') => outline.move_cursor(isize::MAX),
+            _ => {}
+        }
+        return true;
+    }
+
+    match key.code {
+        KeyCode::Char('g') => *pending_g = true,
+        KeyCode::Up | KeyCode::Char('k') => outline.move_cursor(-1),
+        KeyCode::Down | KeyCode::Char('j') => outline.move_cursor(1),
+        KeyCode::Char('u') if ctrl => outline.move_cursor(-page / 2),
+        KeyCode::Char('d') if ctrl => outline.move_cursor(page / 2),
+        KeyCode::PageUp => outline.move_cursor(-page),
+        KeyCode::Char('b') if ctrl => outline.move_cursor(-page),
+        KeyCode::PageDown => outline.move_cursor(page),
+        KeyCode::Char('f') if ctrl => outline.move_cursor(page),
+        KeyCode::Home => outline.cursor = 0,
+        KeyCode::End | KeyCode::Char('G') => outline.move_cursor(isize::MAX),
+        KeyCode::Enter | KeyCode::Char(' ') => outline.toggle(),
+        KeyCode::Left | KeyCode::Char('h') => outline.collapse(),
+        KeyCode::Right | KeyCode::Char('l') => outline.expand(),
+        KeyCode::Char('-') => outline.set_all(true),
+        KeyCode::Char('+') | KeyCode::Char('*') => outline.set_all(false),
+        _ => return false,
+    }
+    true
+}
+
 pub fn handle_viewer_plugin_menu(app: &mut App, key: KeyEvent) {
     let Mode::ViewerPluginMenu { plugins, selected, .. } = &mut app.mode else {
         return;
@@ -309,6 +451,43 @@ pub fn handle_viewer_plugin_menu(app: &mut App, key: KeyEvent) {
     }
 }
 
+/// Handle input in the line diff opened from the tree diff
+pub fn handle_diff_viewing_mode(app: &mut App, key: KeyEvent, visible_height: usize) {
+    let Mode::ViewingDiff { diff, scroll, tree, tree_cursor, tree_scroll } = &mut app.mode else {
+        return;
+    };
+
+    let max_scroll = diff.lines.len().saturating_sub(visible_height);
+
+    match key.code {
+        // Back to the tree, where the file was opened
+        KeyCode::Esc | KeyCode::F(3) | KeyCode::Char('q') => {
+            app.mode = Mode::DiffTree { tree: tree.clone(), cursor: *tree_cursor, scroll: *tree_scroll };
+        }
+        KeyCode::F(10) => {
+            app.mode = Mode::Normal;
+        }
+        KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => *scroll = (*scroll + 1).min(max_scroll),
+        KeyCode::PageUp => *scroll = scroll.saturating_sub(visible_height),
+        KeyCode::PageDown | KeyCode::Char(' ') => *scroll = (*scroll + visible_height).min(max_scroll),
+        KeyCode::Home | KeyCode::Char('g') => *scroll = 0,
+        KeyCode::End | KeyCode::Char('G') => *scroll = max_scroll,
+        // Next / previous hunk
+        KeyCode::Char('n') => {
+            if let Some(&hunk) = diff.hunks.iter().find(|&&h| h > *scroll) {
+                *scroll = hunk.min(max_scroll);
+            }
+        }
+        KeyCode::Char('N') => {
+            if let Some(&hunk) = diff.hunks.iter().rev().find(|&&h| h < *scroll) {
+                *scroll = hunk;
+            }
+        }
+        _ => {}
+    }
+}
+
 pub fn handle_help_mode(app: &mut App, key: KeyEvent, visible_height: usize) {
     let Mode::Help { scroll } = &mut app.mode else {
         return;
@@ -413,16 +592,42 @@ FUNCTION KEYS
   F3           View file contents (built-in viewer)
   F4           Edit file with external editor
   F5           Copy selected files to other panel
+               (Space toggles the copy options: symlinks,
+               hardlinks, sparse files, xattrs, FIFOs/devices,
+               SHA-256 verification)
   F6           Move selected files to other panel
   F7           Create new directory
-  F8           Delete selected files
+  F8           Delete selected files (to the trash when
+               general.use_trash is on)
+  Shift+F8     Delete selected files permanently
+  Ctrl+Z       Undo the last move, rename, mkdir, trashing,
+               chmod or chown (asks if things changed since)
+  Alt+F5       Pack selected files into an archive
+  B            (in a copy/move progress dialog) Send the
+               operation to the background job queue
+  Alt+J        Background jobs: progress, speed and ETA;
+               Space pause/resume, Del cancel, Shift+↑/↓
+               reorder. Errors are skipped and kept per job
   F10          Quit
   Alt+F1/Ctrl+F1  Source selector for left panel (drives/connections)
   Alt+F2/Ctrl+F2  Source selector for right panel (drives/connections)
-  Alt+/        Find files (search with * and ? patterns)
-               Results appear in TEMP panel (other panel)
+  Alt+/        Find files by name (glob or regex), content, size,
+               date, owner, permissions and type; runs in the
+               background, Esc stops it. Results appear in the
+               TEMP panel (other panel); remote and archive
+               results stay in the searched panel
+  Alt+C        Compare the two panels: marks new, newer and
+               different files (optionally by content hash);
+               Synchronize... plans copies/deletions to review
+               (←/→ direction, Space cycle, Del delete, Enter run);
+               Tree Diff lists added (+), removed (-) and
+               modified (~) files in all subdirectories. Enter
+               opens a line diff (n/N next/prev change, Esc back)
   Alt+M        Toggle view mode (Brief/Full)
   Ctrl+D       Add current directory to favorites
+  Trash        Source selector entry: restore (Enter), delete
+               (F8) or empty (E) trashed items
+  Esc          Cancel a directory listing that is still loading
 
 TEMP PANEL (for search results, etc.)
 =====================================
@@ -561,11 +766,35 @@ FILE VIEWER (F3)
   PageDown     Page down
   TAB          Toggle HEX/CP437 mode (built-in viewer)
                Switch to built-in viewer (plugin viewer)
+  w            Toggle soft wrap of long lines (text view)
+  #            Toggle line numbers (text view)
+  e / E        Next / previous text encoding (UTF-8, UTF-16,
+               Latin-1, Windows-1251, KOI8-R, Shift-JIS, GBK)
   F2           Select viewer plugin
-  /            Search text
+  F4           Edit the file in the built-in hex editor
+  /            Search text, regex or hex bytes
   n / N        Next / previous match
+  L            List all matches with line numbers and context
+  F            Follow a growing local file (tail -f); stays at
+               the end unless scrolled up, reloads when the file
+               is truncated or rotated
   q, Esc, F3   Exit viewer
 
+HEX EDITOR (F4 in the viewer)
+=============================
+  0-9, A-F     Type the high, then the low nibble (hex column)
+  Any key      Type the character (ASCII column)
+  Tab          Switch between the hex and ASCII columns
+  Insert       Toggle insert / overwrite
+  Delete       Delete the byte under the cursor
+  Backspace    Delete the byte before the cursor (insert mode)
+  Ctrl+Z/Y     Undo / redo
+  Ctrl+G       Go to offset (decimal, or hex with 0x)
+  Ctrl+Home/End  Go to start / end of file
+  F2, Ctrl+S   Save (overwrites in place when the size is unchanged)
+  Esc, F10     Back to the viewer
+  Changed bytes are shown in red until saved.
+
 VIEWER PLUGINS
 ==============
   Press F2 in the viewer to select from available plugins.
@@ -574,6 +803,17 @@ VIEWER PLUGINS
   TAB in a plugin viewer switches to the built-in viewer.
   F2 opens the plugin menu from either viewer.
   Ctrl+S saves plugin viewer output to a .bark_plugin.txt file.
+  Plugins showing nested data (JSON, YAML, TOML, XML) add a
+  cursor with the path of its line in the footer:
+    Enter, Space   Fold / unfold the block under the cursor
+    Left, h        Fold, or go to the enclosing block
+    Right, l       Unfold
+    -  /  +        Fold / unfold everything
+  Plugins showing tables (CSV, TSV) keep the heading in view:
+    Left, Right    Select a column, scrolling sideways to it;
+                   the footer shows its type and statistics
+    s              Sort by the selected column (ascending,
+                   descending, then file order)
 
 OTHER
 =====
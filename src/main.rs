@@ -32,13 +32,17 @@ mod ui;
 mod utils;
 mod persistent_shell;
 mod win_console;
+#[cfg(test)]
+mod test_utils;
 
 use state::app::App;
+use state::hex_editor::HexConfirm;
 use state::mode::Mode;
 use state::Side;
-use ui::{ArchivePasswordPromptDialog, CommandHistoryDialog, ConfirmDialog, DeleteIterativeDialog, SimpleConfirmDialog, SourceSelector, FileViewer, FindFilesDialog, HelpViewer, MkdirDialog, OverlayDialog, OverlaySelectorDialog, OverwriteConfirmDialog, PanelWidget, PluginViewer, ScpConnectDialog, ScpPasswordPromptDialog, SelectFilesDialog, ShellArea, ShellHistoryViewer, SpinnerDialog, StatusBar, ViewerPluginMenu, ViewerSearchDialog, UserMenuDialog, UserMenuEditDialog, FileOpProgressDialog, FileOpErrorDialog};
-use ui::dialog::{archive_password_prompt_cursor_position, dialog_cursor_position, mkdir_cursor_position, find_files_pattern_cursor_position, find_files_content_cursor_position, find_files_path_cursor_position, viewer_search_text_cursor_position, viewer_search_hex_cursor_position, select_files_cursor_position, scp_connect_cursor_position, scp_password_prompt_cursor_position, user_menu_edit_cursor_position, PluginConnectDialog, plugin_connect_cursor_position};
+use ui::{ArchivePasswordPromptDialog, CommandHistoryDialog, CompareDirsDialog, SynchronizeDialog, TrashBrowserDialog, JobsDialog, DiffTreeViewer, DiffViewer, ConfirmDialog, DeleteIterativeDialog, SimpleConfirmDialog, SourceSelector, FileViewer, FindFilesDialog, HelpViewer, HexEditorView, MkdirDialog, OverlayDialog, PackDialog, OverlaySelectorDialog, OverwriteConfirmDialog, PanelWidget, PluginViewer, ScpConnectDialog, ScpPasswordPromptDialog, HostKeyConfirmDialog, SelectFilesDialog, ShellArea, ShellHistoryViewer, SpinnerDialog, StatusBar, ViewerMatchList, ViewerPluginMenu, ViewerSearchDialog, UserMenuDialog, UserMenuEditDialog, FileOpProgressDialog, FileOpErrorDialog};
+use ui::dialog::{archive_password_prompt_cursor_position, dialog_cursor_position, mkdir_cursor_position, pack_cursor_position, find_files_cursor_position, viewer_search_text_cursor_position, viewer_search_hex_cursor_position, select_files_cursor_position, scp_connect_cursor_position, scp_password_prompt_cursor_position, user_menu_edit_cursor_position, PluginConnectDialog, plugin_connect_cursor_position};
 use input::get_help_text;
+use ui::panel::format_size_short;
 
 /// Set up panic hook to restore terminal on panic
 fn setup_panic_hook() {
@@ -102,10 +106,14 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
             }
 
             match &app.mode {
-                Mode::Viewing { content, scroll, path, binary_mode, search_matches, current_match } => {
+                Mode::Viewing { content, scroll, path, binary_mode, search_matches, current_match, wrap_row } => {
                     // Full-screen file viewer with search highlighting
                     let viewer = FileViewer::new(content, *scroll, path, &app.theme, *binary_mode)
-                        .with_search(search_matches, *current_match);
+                        .with_search(search_matches, *current_match)
+                        .with_layout(app.ui.viewer_layout, *wrap_row)
+                        .with_syntax(app.viewer_syntax(path, content))
+                        .with_follow(app.ui.viewer_follow.is_some())
+                        .with_notice(app.ui.viewer_notice.as_deref());
                     app.ui.viewer_height = FileViewer::content_height(size);
                     frame.render_widget(viewer, size);
 
@@ -125,15 +133,21 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                         buf.set_string(status_x, status_y, &status, status_style);
                     }
                 }
-                Mode::ViewingPlugin { plugin_name, path, scroll, lines, total_lines, status_message } => {
+                Mode::ViewingPlugin { plugin_name, path, scroll, lines, total_lines, status_message, outline, table } => {
                     // Full-screen plugin viewer
-                    let viewer = PluginViewer::new(plugin_name, path, lines, *scroll, *total_lines, status_message.as_deref(), &app.theme);
-                    app.ui.viewer_height = PluginViewer::content_height(size);
+                    let viewer = PluginViewer::new(plugin_name, path, lines, *scroll, *total_lines, status_message.as_deref(), &app.theme)
+                        .with_outline(outline.as_ref())
+                        .with_table(table.as_ref());
+                    // A table heading stays put above the rows that scroll
+                    let heading = table.as_ref().map_or(0, |table| table.header.len());
+                    app.ui.viewer_height = PluginViewer::content_height(size).saturating_sub(heading);
                     frame.render_widget(viewer, size);
                 }
                 Mode::ViewerPluginMenu { path, content, binary_mode, original_scroll, plugins, selected } => {
                     // Show the built-in viewer underneath
-                    let viewer = FileViewer::new(content, *original_scroll, path, &app.theme, *binary_mode);
+                    let viewer = FileViewer::new(content, *original_scroll, path, &app.theme, *binary_mode)
+                        .with_layout(app.ui.viewer_layout, 0)
+                        .with_syntax(app.viewer_syntax(path, content));
                     app.ui.viewer_height = FileViewer::content_height(size);
                     frame.render_widget(viewer, size);
 
@@ -143,11 +157,13 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                 }
                 Mode::ViewerSearch {
                     content, scroll, path, binary_mode,
-                    text_input, text_cursor, case_sensitive,
-                    hex_input, hex_cursor, focus, ..
+                    text_input, text_cursor, case_sensitive, regex, multiline,
+                    hex_input, hex_cursor, focus, error, ..
                 } => {
                     // Show the built-in viewer underneath
-                    let viewer = FileViewer::new(content, *scroll, path, &app.theme, *binary_mode);
+                    let viewer = FileViewer::new(content, *scroll, path, &app.theme, *binary_mode)
+                        .with_layout(app.ui.viewer_layout, 0)
+                        .with_syntax(app.viewer_syntax(path, content));
                     app.ui.viewer_height = FileViewer::content_height(size);
                     frame.render_widget(viewer, size);
 
@@ -155,25 +171,97 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                     let dialog = ViewerSearchDialog::new(
                         text_input,
                         *case_sensitive,
+                        *regex,
+                        *multiline,
                         hex_input,
                         *focus,
                         app.ui.input_selected,
                         &app.theme,
-                        0,    // match_count - not available until search is executed
-                        None, // current_match - not available until search is executed
+                        error.as_deref(),
                     );
                     frame.render_widget(dialog, size);
 
                     // Position cursor in the focused input field
-                    // Focus: 0=text, 1=case_sensitive, 2=hex, 3=search, 4=cancel
+                    // Focus: 0=text, 1=case, 2=regex, 3=multiline, 4=hex, 5=search, 6=list all, 7=cancel
                     if *focus == 0 {
                         let (cx, cy) = viewer_search_text_cursor_position(size, text_input, *text_cursor);
                         frame.set_cursor_position((cx, cy));
-                    } else if *focus == 2 {
+                    } else if *focus == 4 {
                         let (cx, cy) = viewer_search_hex_cursor_position(size, hex_input, *hex_cursor);
                         frame.set_cursor_position((cx, cy));
                     }
                 }
+                Mode::ViewerSearching { content, scroll, path, binary_mode, description, progress, total, frame: spinner_frame, started, .. } => {
+                    // Show the built-in viewer underneath
+                    let viewer = FileViewer::new(content, *scroll, path, &app.theme, *binary_mode)
+                        .with_layout(app.ui.viewer_layout, 0)
+                        .with_syntax(app.viewer_syntax(path, content));
+                    app.ui.viewer_height = FileViewer::content_height(size);
+                    frame.render_widget(viewer, size);
+
+                    let elapsed = started.elapsed();
+                    let scanned = progress.scanned.load(std::sync::atomic::Ordering::Relaxed);
+                    let percent = (scanned as u128 * 100 / (*total).max(1) as u128) as u64;
+                    let message = format!(
+                        "Searching for {}\n{} of {} ({}%)  Matches: {}\nElapsed: {}:{:02}",
+                        description,
+                        format_size_short(scanned as u64),
+                        format_size_short(*total as u64),
+                        percent,
+                        progress.found.load(std::sync::atomic::Ordering::Relaxed),
+                        elapsed.as_secs() / 60,
+                        elapsed.as_secs() % 60,
+                    );
+                    let spinner = SpinnerDialog::new(*spinner_frame, "Search", &message)
+                        .border_style(Style::default().fg(app.theme.panel_border_active))
+                        .content_style(Style::default().fg(app.theme.cursor_fg).bg(app.theme.cursor_bg));
+                    frame.render_widget(spinner, size);
+                }
+                Mode::ViewerMatches { content, path, binary_mode, search_matches, cursor, list_scroll, scroll, wrap_row, .. } => {
+                    // Viewer preview on top, match list below
+                    let list_height = (size.height * 2 / 5).max(6).min(size.height);
+                    let chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Min(0), Constraint::Length(list_height)])
+                        .split(size);
+
+                    let viewer = FileViewer::new(content, *scroll, path, &app.theme, *binary_mode)
+                        .with_search(search_matches, Some(*cursor))
+                        .with_layout(app.ui.viewer_layout, *wrap_row)
+                        .with_syntax(app.viewer_syntax(path, content));
+                    frame.render_widget(viewer, chunks[0]);
+
+                    let list = ViewerMatchList::new(content, *binary_mode, search_matches, *cursor, *list_scroll, &app.theme);
+                    app.ui.viewer_height = ViewerMatchList::content_height(chunks[1]);
+                    frame.render_widget(list, chunks[1]);
+                }
+                Mode::HexEditing { editor } => {
+                    let view = HexEditorView::new(editor, &app.theme);
+                    app.ui.viewer_height = HexEditorView::content_height(size);
+                    frame.render_widget(view, size);
+
+                    if let Some(position) = HexEditorView::prompt_cursor(editor, size) {
+                        frame.set_cursor_position(position);
+                    }
+                    if let Some((question, focus)) = editor.confirm {
+                        let message = match question {
+                            HexConfirm::Save if editor.buffer.saves_in_place() => "Save changes to the file?",
+                            HexConfirm::Save => "Save changes? The file will be rewritten.",
+                            HexConfirm::Discard => "Discard unsaved changes?",
+                        };
+                        frame.render_widget(SimpleConfirmDialog::new(message, focus, &app.theme), size);
+                    }
+                }
+                Mode::DiffTree { tree, cursor, scroll } => {
+                    let viewer = DiffTreeViewer::new(tree, *cursor, *scroll, &app.theme);
+                    app.ui.viewer_height = DiffTreeViewer::content_height(size);
+                    frame.render_widget(viewer, size);
+                }
+                Mode::ViewingDiff { diff, scroll, .. } => {
+                    let viewer = DiffViewer::new(diff, *scroll, &app.theme);
+                    app.ui.viewer_height = DiffViewer::content_height(size);
+                    frame.render_widget(viewer, size);
+                }
                 Mode::ShellHistoryView { scroll } => {
                     let viewer = ShellHistoryViewer::new(&app.cmd.output, *scroll);
                     app.ui.viewer_height = ShellHistoryViewer::content_height(size);
@@ -284,10 +372,12 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                     } else {
                         None
                     };
+                    let jobs_summary = app.jobs.summary();
                     let status_bar = StatusBar::new(active_panel, &app.theme)
                         .with_git(git_for_status)
                         .with_python_env(python_env_for_status)
-                        .with_plugin_status(plugin_status_ref);
+                        .with_plugin_status(plugin_status_ref)
+                        .with_jobs(jobs_summary.as_deref());
                     frame.render_widget(status_bar, main_chunks[1]);
 
                     // Draw shell area (history + command line at bottom)
@@ -310,13 +400,13 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                     }
 
                     // Render confirmation dialog if in confirming mode (overlay)
-                    if let Mode::Confirming { operation, sources, dest_input, cursor_pos, focus, apply_all } = &app.mode {
-                        let dialog = ConfirmDialog::new(operation, sources, dest_input, *cursor_pos, *focus, app.ui.input_selected, *apply_all, &app.theme);
+                    if let Mode::Confirming { operation, sources, dest_input, cursor_pos, focus, apply_all, options } = &app.mode {
+                        let dialog = ConfirmDialog::new(operation, sources, dest_input, *cursor_pos, *focus, app.ui.input_selected, *apply_all, *options, &app.theme);
                         frame.render_widget(dialog, size);
 
                         // Position cursor in dialog input field (only when input is focused)
                         if *focus == 0 {
-                            let (cx, cy) = dialog_cursor_position(size, dest_input, *cursor_pos);
+                            let (cx, cy) = dialog_cursor_position(size, operation, dest_input, *cursor_pos);
                             frame.set_cursor_position((cx, cy));
                         }
                     }
@@ -355,6 +445,17 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                         }
                     }
 
+                    // Render pack dialog (overlay)
+                    if let Mode::Packing { sources, name_input, cursor_pos, format, level, focus } = &app.mode {
+                        let dialog = PackDialog::new(sources.len(), name_input, *format, *level, *focus, app.ui.input_selected, &app.theme);
+                        frame.render_widget(dialog, size);
+
+                        if *focus == 0 {
+                            let (cx, cy) = pack_cursor_position(size, name_input, *cursor_pos);
+                            frame.set_cursor_position((cx, cy));
+                        }
+                    }
+
                     // Render command history dialog if in history mode (overlay)
                     if let Mode::CommandHistory { selected, scroll } = &app.mode {
                         let dialog = CommandHistoryDialog::new(
@@ -367,46 +468,30 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                     }
 
                     // Render find files dialog if in find files mode (overlay)
-                    if let Mode::FindFiles {
-                        pattern_input,
-                        pattern_cursor,
-                        pattern_case_sensitive,
-                        content_input,
-                        content_cursor,
-                        content_case_sensitive,
-                        path_input,
-                        path_cursor,
-                        recursive,
-                        focus,
-                    } = &app.mode
-                    {
-                        let dialog = FindFilesDialog::new(
-                            pattern_input,
-                            *pattern_case_sensitive,
-                            content_input,
-                            *content_case_sensitive,
-                            path_input,
-                            *recursive,
-                            *focus,
-                            app.ui.input_selected,
-                            &app.theme,
-                        );
+                    if let Mode::FindFiles { options, focus, cursor } = &app.mode {
+                        let dialog = FindFilesDialog::new(options, *focus, app.ui.input_selected, &app.theme);
                         frame.render_widget(dialog, size);
 
                         // Position cursor in the focused input field
-                        // Focus: 0=pattern, 1=pattern_case, 2=content, 3=content_case, 4=path, 5=recursive, 6=search, 7=cancel
-                        if *focus == 0 {
-                            let (cx, cy) = find_files_pattern_cursor_position(size, pattern_input, *pattern_cursor);
-                            frame.set_cursor_position((cx, cy));
-                        } else if *focus == 2 {
-                            let (cx, cy) = find_files_content_cursor_position(size, content_input, *content_cursor);
-                            frame.set_cursor_position((cx, cy));
-                        } else if *focus == 4 {
-                            let (cx, cy) = find_files_path_cursor_position(size, path_input, *path_cursor);
-                            frame.set_cursor_position((cx, cy));
+                        if let Some(pos) = find_files_cursor_position(size, options, *focus, *cursor) {
+                            frame.set_cursor_position(pos);
                         }
                     }
 
+                    // Render compare / synchronize dialogs (overlay)
+                    if let Mode::CompareDirs { by_content, focus } = &app.mode {
+                        frame.render_widget(CompareDirsDialog::new(*by_content, *focus, &app.theme), size);
+                    }
+                    if let Mode::Synchronize { items, cursor, scroll } = &app.mode {
+                        frame.render_widget(SynchronizeDialog::new(items, *cursor, *scroll, &app.theme), size);
+                    }
+                    if let Mode::TrashBrowser { items, cursor, scroll } = &app.mode {
+                        frame.render_widget(TrashBrowserDialog::new(items, *cursor, *scroll, &app.theme), size);
+                    }
+                    if let Mode::Jobs { cursor } = &app.mode {
+                        frame.render_widget(JobsDialog::new(app.jobs.jobs(), *cursor, &app.theme), size);
+                    }
+
                     // Render select files dialog if in select files mode (overlay)
                     if let Mode::SelectFiles {
                         pattern_input,
@@ -491,6 +576,12 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                         }
                     }
 
+                    // Render SSH host key confirmation (overlay)
+                    if let Mode::HostKeyConfirm { key_host, key_type, fingerprint, focus, .. } = &app.mode {
+                        let dialog = HostKeyConfirmDialog::new(key_host, key_type, fingerprint, *focus, &app.theme);
+                        frame.render_widget(dialog, size);
+                    }
+
                     // Render SCP password prompt dialog if in password prompt mode (overlay)
                     if let Mode::ScpPasswordPrompt {
                         display_name,
@@ -625,12 +716,16 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                     }
 
                     // Render overwrite confirmation dialog (overlay)
-                    if let Mode::OverwriteConfirm { conflicts, current_conflict, focus, .. } = &app.mode {
-                        let filename = conflicts.get(*current_conflict)
+                    if let Mode::OverwriteConfirm { conflicts, current_conflict, resumable, focus, .. } = &app.mode {
+                        let conflict = conflicts.get(*current_conflict);
+                        let filename = conflict
                             .and_then(|p| p.file_name())
                             .map(|n| n.to_string_lossy().to_string())
                             .unwrap_or_default();
-                        let dialog = OverwriteConfirmDialog::new(&filename, *current_conflict, conflicts.len(), *focus, &app.theme);
+                        let copied = conflict.and_then(|p| resumable.get(p)).copied();
+                        let dialog = OverwriteConfirmDialog::new(
+                            &filename, *current_conflict, conflicts.len(), copied, *focus, &app.theme,
+                        );
                         frame.render_widget(dialog, size);
                     }
 
@@ -639,7 +734,7 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                         let dialog = FileOpProgressDialog::new(
                             spinner_frame % 10, title, current_file,
                             *bytes_done, *bytes_total, *files_done, *files_total, &app.theme,
-                        );
+                        ).with_background_hint(app.job_control.is_some());
                         frame.render_widget(dialog, size);
                     }
 
@@ -714,6 +809,32 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                         frame.render_widget(dialog, size);
                     }
 
+                    // Render search progress (overlay)
+                    if let Mode::Searching { description, progress, frame: spinner_frame, started } = &app.mode {
+                        let elapsed = started.elapsed();
+                        // Keep the end of the directory and a fixed width so the dialog doesn't jump around
+                        let dir = progress.current_dir();
+                        let dir_len = dir.chars().count();
+                        let dir: String = if dir_len > 50 {
+                            std::iter::once('…').chain(dir.chars().skip(dir_len - 49)).collect()
+                        } else {
+                            dir
+                        };
+                        let message = format!(
+                            "Searching for files {}\nFound: {}  Scanned: {}\n{:<50}\nElapsed: {}:{:02}",
+                            description,
+                            progress.found.load(std::sync::atomic::Ordering::Relaxed),
+                            progress.scanned.load(std::sync::atomic::Ordering::Relaxed),
+                            dir,
+                            elapsed.as_secs() / 60,
+                            elapsed.as_secs() % 60,
+                        );
+                        let spinner = SpinnerDialog::new(*spinner_frame, "Find Files", &message)
+                            .border_style(Style::default().fg(app.theme.panel_border_active))
+                            .content_style(Style::default().fg(app.theme.cursor_fg).bg(app.theme.cursor_bg));
+                        frame.render_widget(spinner, size);
+                    }
+
                     // Render spinner dialog for background tasks (overlay)
                     if let Mode::BackgroundTask { title, message, frame: spinner_frame, started } = &app.mode {
                         let elapsed = started.elapsed();
@@ -1015,7 +1136,7 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                             Side::Left => &mut app.left_panel,
                             Side::Right => &mut app.right_panel,
                         };
-                        if let Err(e) = panel.write_file(&remote_path, &contents) {
+                        if let Err(e) = panel.write_file(&remote_path, &contents).and_then(|()| panel.flush()) {
                             panel.error = Some(format!("Failed to upload file: {}", e));
                         }
                     }
@@ -1052,21 +1173,29 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
             let path = path.clone();
             app.mode = Mode::Normal;
 
+            // No command, or one that isn't installed, means the built-in
+            // hex editor
             let editor = app.config.editor.hex_editor.clone();
+            let mut launched = false;
             if !editor.is_empty() {
                 // Leave TUI so the editor gets a clean terminal
                 restore_terminal()?;
 
-                let _ = std::process::Command::new(&editor)
+                launched = std::process::Command::new(&editor)
                     .arg(&path)
-                    .status();
+                    .status()
+                    .is_ok();
 
                 // Re-enter TUI
                 *terminal = setup_terminal()?;
             }
 
-            // Open the built-in viewer on the (possibly modified) file
-            app.view_file(&path);
+            if launched {
+                // Open the built-in viewer on the (possibly modified) file
+                app.view_file(&path);
+            } else {
+                app.open_hex_editor(&path);
+            }
             app.active_panel_mut().refresh();
 
             needs_redraw = true;
@@ -1322,8 +1451,8 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
         }
 
         // Poll for background task completion and tick spinner
-        let has_animation = matches!(app.mode, Mode::BackgroundTask { .. } | Mode::FileOpProgress { .. } | Mode::FileOpErrorDialog { .. });
-        if matches!(app.mode, Mode::BackgroundTask { .. }) {
+        let has_animation = matches!(app.mode, Mode::BackgroundTask { .. } | Mode::Searching { .. } | Mode::ViewerSearching { .. } | Mode::FileOpProgress { .. } | Mode::FileOpErrorDialog { .. });
+        if matches!(app.mode, Mode::BackgroundTask { .. } | Mode::Searching { .. } | Mode::ViewerSearching { .. }) {
             app.poll_background_task();
             app.tick_spinner();
             needs_redraw = true;
@@ -1336,14 +1465,42 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
             needs_redraw = true;
         }
 
+        // Background jobs: progress for the jobs panel and status bar, and
+        // reports of the jobs that finished
+        let has_jobs = !app.jobs.is_empty();
+        if has_jobs {
+            app.poll_jobs();
+            needs_redraw = true;
+        }
+
+        // Show what was appended to a file followed in the viewer
+        let is_following = app.ui.viewer_follow.is_some();
+        if is_following && app.poll_viewer_follow() {
+            needs_redraw = true;
+        }
+
+        // Apply finished directory listings. While one is pending, keep
+        // ticking so the panel's loading indicator animates.
+        if app.poll_listings() {
+            needs_redraw = true;
+        }
+        let is_loading = app.left_panel.is_loading() || app.right_panel.is_loading();
+        if is_loading {
+            needs_redraw = true;
+        }
+
         // Use shorter poll timeout for animations (spinner).
         // When the clock is visible, cap at 30s so the minute display stays fresh.
         // Otherwise block until an event arrives.
         let has_clock = app.config.display.show_date || app.config.display.show_time;
-        let poll_timeout = if has_animation {
+        let poll_timeout = if has_animation || is_loading {
             Duration::from_millis(50)
         } else if has_overlay_tick {
             Duration::from_millis(100)
+        } else if has_jobs {
+            Duration::from_millis(250)
+        } else if is_following {
+            fs::follow::POLL_INTERVAL
         } else if has_clock {
             Duration::from_secs(30)
         } else {
@@ -102,6 +102,8 @@ pub struct ViewerContext {
     pub scroll: usize,
     /// Configuration values from Bark (flattened key-value pairs)
     pub config: std::collections::HashMap<String, String>,
+    /// Table column to sort by, and whether descending
+    pub sort: Option<(usize, bool)>,
 }
 
 /// Result from a status bar plugin
@@ -126,6 +128,25 @@ pub struct ViewerRenderResult {
     pub lines: Vec<String>,
     /// Total number of lines (for scrolling)
     pub total_lines: usize,
+    /// Foldable blocks as (first line, last line folded into it)
+    pub folds: Vec<(usize, usize)>,
+    /// Breadcrumb of each line, for plugins showing nested data
+    pub paths: Vec<String>,
+    /// Lines kept above the scrolling ones
+    pub header: Vec<String>,
+    /// Columns, for plugins showing a table
+    pub columns: Vec<TableColumn>,
+}
+
+/// A column of a table shown by a viewer plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableColumn {
+    /// Character offset in each line
+    pub start: usize,
+    /// Width in characters
+    pub width: usize,
+    /// Shown while the column is selected
+    pub info: String,
 }
 
 /// Trait for status bar plugins
@@ -9,6 +9,7 @@ use crate::plugins::overlay_script::ScriptOverlayPlugin;
 use crate::plugins::provider_script::ScriptProviderPlugin;
 use crate::plugins::script::ScriptPlugin;
 
+use bark_plugin_api::protocol::{parse_reply, PluginInfoReply};
 use bark_plugin_api::{ProviderPlugin, ProviderPluginInfo};
 
 /// Manages all loaded plugins
@@ -60,11 +61,19 @@ impl PluginManager {
             }
 
             // Query plugin type via --plugin-info
-            let plugin_type = match query_plugin_type(&path) {
-                Some(t) => t,
-                None => continue,
+            let Some(reply) = query_plugin_info(&path) else {
+                continue;
+            };
+            let Some(plugin_type) = reply.plugin_type.clone() else {
+                continue;
             };
 
+            // Refuse plugins written for a protocol we don't speak
+            if let Err(e) = reply.api_version() {
+                errors.push(format!("{}: {}", path.display(), e));
+                continue;
+            }
+
             match plugin_type.as_str() {
                 "provider" => {
                     match ScriptProviderPlugin::load(&path) {
@@ -283,8 +292,8 @@ fn is_executable(path: &Path) -> bool {
     }
 }
 
-/// Query a plugin executable for its type via --plugin-info
-fn query_plugin_type(path: &Path) -> Option<String> {
+/// Query a plugin executable for its type and version via --plugin-info
+fn query_plugin_info(path: &Path) -> Option<PluginInfoReply> {
     let output = Command::new(path)
         .arg("--plugin-info")
         .stdin(Stdio::null())
@@ -298,22 +307,6 @@ fn query_plugin_type(path: &Path) -> Option<String> {
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        let line = line.trim();
-        if line.starts_with('{') {
-            // Extract "type" field
-            let pattern = "\"type\":";
-            if let Some(start) = line.find(pattern) {
-                let rest = line[start + pattern.len()..].trim_start();
-                if rest.starts_with('"') {
-                    let rest = &rest[1..];
-                    if let Some(end) = rest.find('"') {
-                        return Some(rest[..end].to_string());
-                    }
-                }
-            }
-        }
-    }
-
-    None
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    parse_reply(&file_name, "--plugin-info", &stdout).ok()
 }
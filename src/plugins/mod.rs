@@ -14,7 +14,7 @@ mod script;
 
 #[allow(unused_imports)]
 pub use api::{OverlayPluginInfo, OverlayRenderResult};
-pub use api::{StatusContext, ViewerContext};
+pub use api::{StatusContext, TableColumn, ViewerContext};
 pub use manager::PluginManager;
 
 // Re-export types from the plugin API crate for external use
@@ -5,9 +5,11 @@
 
 use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command as Process, Stdio};
 use std::sync::Mutex;
 
+use bark_plugin_api::protocol::{parse_reply, Command, OverlayReply, PluginInfoReply, Request};
+
 use crate::plugins::api::{OverlayPluginInfo, OverlayRenderResult};
 
 /// A script-based overlay plugin (loaded from executable)
@@ -19,7 +21,7 @@ pub struct ScriptOverlayPlugin {
 impl ScriptOverlayPlugin {
     /// Load an overlay plugin from an executable path
     pub fn load(path: &Path) -> Result<Self, String> {
-        let output = Command::new(path)
+        let output = Process::new(path)
             .arg("--plugin-info")
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
@@ -41,49 +43,29 @@ impl ScriptOverlayPlugin {
     }
 
     fn parse_info(output: &str, path: &Path) -> Result<OverlayPluginInfo, String> {
-        let mut name = "Unknown Overlay".to_string();
-        let mut description = String::new();
-        let mut width: u16 = 46;
-        let mut height: u16 = 18;
-
-        for line in output.lines() {
-            let line = line.trim();
-            if line.starts_with('{') {
-                if let Some(n) = extract_json_string(line, "name") {
-                    name = n;
-                }
-                if let Some(d) = extract_json_string(line, "description") {
-                    description = d;
-                }
-                if let Some(w) = extract_json_int(line, "width") {
-                    width = w as u16;
-                }
-                if let Some(h) = extract_json_int(line, "height") {
-                    height = h as u16;
-                }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let reply: PluginInfoReply = parse_reply(&file_name, "--plugin-info", output)
+            .map_err(|e| e.to_string())?;
 
-                // Verify plugin type
-                if let Some(t) = extract_json_string(line, "type")
-                    && t != "overlay"
-                {
-                    return Err(format!("Not an overlay plugin (type: {})", t));
-                }
-                break;
-            }
+        // Verify plugin type
+        if let Some(t) = &reply.plugin_type
+            && t != "overlay"
+        {
+            return Err(format!("Not an overlay plugin (type: {})", t));
         }
 
         Ok(OverlayPluginInfo {
-            name,
-            description,
-            width,
-            height,
+            name: reply.name.unwrap_or_else(|| "Unknown Overlay".to_string()),
+            description: reply.description,
+            width: reply.width.unwrap_or(46),
+            height: reply.height.unwrap_or(18),
             source: path.to_path_buf(),
         })
     }
 
     /// Start a new overlay session (spawns child process)
     pub fn start_session(&self) -> Result<ScriptOverlaySession, String> {
-        let mut child = Command::new(&self.executable)
+        let mut child = Process::new(&self.executable)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
@@ -96,6 +78,7 @@ impl ScriptOverlayPlugin {
             .ok_or_else(|| "Failed to open plugin stdout".to_string())?;
 
         Ok(ScriptOverlaySession {
+            plugin_name: self.info.name.clone(),
             child: Some(child),
             stdin: Mutex::new(child_stdin),
             stdout: Mutex::new(std::io::BufReader::new(child_stdout)),
@@ -105,6 +88,8 @@ impl ScriptOverlayPlugin {
 
 /// An active overlay session (persistent child process)
 pub struct ScriptOverlaySession {
+    /// Plugin name, used in protocol error messages
+    plugin_name: String,
     child: Option<Child>,
     stdin: Mutex<std::process::ChildStdin>,
     stdout: Mutex<std::io::BufReader<std::process::ChildStdout>>,
@@ -115,7 +100,7 @@ unsafe impl Send for ScriptOverlaySession {}
 
 impl Drop for ScriptOverlaySession {
     fn drop(&mut self) {
-        let _ = self.send_raw("{\"command\":\"close\"}\n");
+        let _ = self.send_raw(&Command::Close);
         if let Some(mut child) = self.child.take() {
             let _ = child.kill();
             let _ = child.wait();
@@ -124,7 +109,8 @@ impl Drop for ScriptOverlaySession {
 }
 
 impl ScriptOverlaySession {
-    fn send_raw(&self, request: &str) -> Result<String, String> {
+    fn send_raw(&self, command: &Command) -> Result<String, String> {
+        let request = Request::new(command.clone()).to_line();
         {
             let mut stdin = self.stdin.lock().unwrap();
             stdin.write_all(request.as_bytes())
@@ -148,45 +134,43 @@ impl ScriptOverlaySession {
         Ok(response)
     }
 
-    fn parse_render_result(response: &str) -> Result<OverlayRenderResult, String> {
-        let title = extract_json_string(response, "title").unwrap_or_default();
-        let width = extract_json_int(response, "width").unwrap_or(46) as u16;
-        let height = extract_json_int(response, "height").unwrap_or(18) as u16;
-        let close = extract_json_bool(response, "close").unwrap_or(false);
-        let tick = extract_json_bool(response, "tick").unwrap_or(false);
-        let lines = extract_json_string_array(response, "lines").unwrap_or_default();
+    /// Send a command and decode the render result it answers with
+    fn render(&self, command: Command) -> Result<OverlayRenderResult, String> {
+        let response = self.send_raw(&command)?;
+        let reply: OverlayReply = parse_reply(&self.plugin_name, command.name(), &response)
+            .map_err(|e| e.to_string())?;
 
-        Ok(OverlayRenderResult { lines, title, width, height, close, tick })
+        Ok(OverlayRenderResult {
+            lines: reply.lines,
+            title: reply.title,
+            width: reply.width.unwrap_or(46),
+            height: reply.height.unwrap_or(18),
+            close: reply.close,
+            tick: reply.tick,
+        })
     }
 
     /// Send init command and get initial render
     pub fn init(&self, width: u16, height: u16) -> Result<OverlayRenderResult, String> {
-        let request = format!("{{\"command\":\"init\",\"width\":{},\"height\":{}}}\n", width, height);
-        let response = self.send_raw(&request)?;
-        Self::parse_render_result(&response)
+        self.render(Command::Init { width, height })
     }
 
     /// Send a key event and get updated render
     pub fn send_key(&self, key: &str, modifiers: &[&str]) -> Result<OverlayRenderResult, String> {
-        let mods_json: Vec<String> = modifiers.iter().map(|m| format!("\"{}\"", m)).collect();
-        let request = format!(
-            "{{\"command\":\"key\",\"key\":\"{}\",\"modifiers\":[{}]}}\n",
-            escape_json(key),
-            mods_json.join(",")
-        );
-        let response = self.send_raw(&request)?;
-        Self::parse_render_result(&response)
+        self.render(Command::Key {
+            key: key.to_string(),
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+        })
     }
 
     /// Send a tick command (for live-updating overlays like stopwatch)
     pub fn tick(&self) -> Result<OverlayRenderResult, String> {
-        let response = self.send_raw("{\"command\":\"tick\"}\n")?;
-        Self::parse_render_result(&response)
+        self.render(Command::Tick)
     }
 
     /// Close the session
     pub fn close(&mut self) {
-        let _ = self.send_raw("{\"command\":\"close\"}\n");
+        let _ = self.send_raw(&Command::Close);
         if let Some(mut child) = self.child.take() {
             let _ = child.kill();
             let _ = child.wait();
@@ -203,147 +187,3 @@ impl ScriptOverlaySession {
         }
     }
 }
-
-// ============================================================================
-// JSON Helpers (same minimal approach as provider_script.rs)
-// ============================================================================
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn extract_json_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('"') {
-        return None;
-    }
-
-    let rest = &rest[1..];
-    let mut result = String::new();
-    let mut chars = rest.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => break,
-            '\\' => {
-                if let Some(&next) = chars.peek() {
-                    chars.next();
-                    match next {
-                        'n' => result.push('\n'),
-                        'r' => result.push('\r'),
-                        't' => result.push('\t'),
-                        '"' => result.push('"'),
-                        '\\' => result.push('\\'),
-                        _ => {
-                            result.push('\\');
-                            result.push(next);
-                        }
-                    }
-                }
-            }
-            _ => result.push(c),
-        }
-    }
-
-    Some(result)
-}
-
-fn extract_json_bool(json: &str, key: &str) -> Option<bool> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if rest.starts_with("true") {
-        Some(true)
-    } else if rest.starts_with("false") {
-        Some(false)
-    } else {
-        None
-    }
-}
-
-fn extract_json_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn extract_json_string_array(json: &str, key: &str) -> Option<Vec<String>> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('[') {
-        return None;
-    }
-
-    let mut depth = 0;
-    let mut end = 0;
-    for (i, c) in rest.char_indices() {
-        match c {
-            '[' => depth += 1,
-            ']' => {
-                depth -= 1;
-                if depth == 0 {
-                    end = i + c.len_utf8();
-                    break;
-                }
-            }
-            _ => {}
-        }
-    }
-
-    if end == 0 {
-        return None;
-    }
-
-    let array_str = &rest['['.len_utf8()..end - ']'.len_utf8()];
-    let mut result = Vec::new();
-    let mut current = String::new();
-    let mut in_string = false;
-    let mut escape = false;
-
-    for c in array_str.chars() {
-        if escape {
-            match c {
-                'n' => current.push('\n'),
-                'r' => current.push('\r'),
-                't' => current.push('\t'),
-                '"' => current.push('"'),
-                '\\' => current.push('\\'),
-                _ => {
-                    current.push('\\');
-                    current.push(c);
-                }
-            }
-            escape = false;
-            continue;
-        }
-
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => {
-                if in_string {
-                    result.push(current.clone());
-                    current.clear();
-                }
-                in_string = !in_string;
-            }
-            ',' if !in_string => {}
-            _ if in_string => current.push(c),
-            _ => {}
-        }
-    }
-
-    Some(result)
-}
@@ -2,17 +2,18 @@
 //!
 //! Bridges the plugin API's ProviderSession to the app's PanelProvider trait.
 
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
 // Re-export common types from the plugin API crate
 pub use bark_plugin_api::{
-    DialogField, DialogFieldType, FileEntry as PluginFileEntry, ProviderConfig,
+    Capability, DialogField, DialogFieldType, FileEntry as PluginFileEntry, ProviderConfig,
     ProviderError as ProviderPluginError, ProviderPlugin, ProviderPluginInfo,
-    ProviderResult as ProviderPluginResult, ProviderSession,
+    ProviderResult as ProviderPluginResult, ProviderSession, SessionWriter,
 };
 
 use crate::fs::FileEntry;
-use crate::providers::{PanelProvider, ProviderError, ProviderInfo, ProviderResult, ProviderType};
+use crate::providers::{PanelProvider, ProviderError, ProviderInfo, ProviderResult, ProviderType, ProviderWriter};
 
 /// Wrapper to adapt ProviderSession to PanelProvider trait
 #[allow(dead_code)]
@@ -20,6 +21,7 @@ pub struct PluginProviderAdapter {
     session: Box<dyn ProviderSession>,
     provider_info: ProviderInfo,
     short_label_value: Option<String>,
+    capabilities: Vec<Capability>,
 }
 
 #[allow(dead_code)]
@@ -35,6 +37,7 @@ impl PluginProviderAdapter {
                 icon: plugin_info.icon,
             },
             short_label_value,
+            capabilities: plugin_info.capabilities.clone(),
             session,
         }
     }
@@ -81,6 +84,27 @@ fn convert_error(e: ProviderPluginError) -> ProviderError {
     }
 }
 
+/// Wraps a plugin session writer so it can be used as a ProviderWriter
+struct SessionWriterAdapter<'a> {
+    inner: Box<dyn SessionWriter + 'a>,
+}
+
+impl Write for SessionWriterAdapter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl ProviderWriter for SessionWriterAdapter<'_> {
+    fn finish(self: Box<Self>) -> ProviderResult<()> {
+        self.inner.finish().map_err(convert_error)
+    }
+}
+
 impl PanelProvider for PluginProviderAdapter {
     fn info(&self) -> &ProviderInfo {
         &self.provider_info
@@ -114,6 +138,15 @@ impl PanelProvider for PluginProviderAdapter {
         self.session.write_file(path, data).map_err(convert_error)
     }
 
+    fn open_read(&mut self, path: &str) -> ProviderResult<Box<dyn Read + Send>> {
+        self.session.open_read(path).map_err(convert_error)
+    }
+
+    fn open_write(&mut self, path: &str) -> ProviderResult<Box<dyn ProviderWriter + '_>> {
+        let inner = self.session.open_write(path).map_err(convert_error)?;
+        Ok(Box::new(SessionWriterAdapter { inner }))
+    }
+
     fn delete(&mut self, path: &str) -> ProviderResult<()> {
         self.session.delete(path).map_err(convert_error)
     }
@@ -143,6 +176,10 @@ impl PanelProvider for PluginProviderAdapter {
         self.session.set_attributes(path, modified, permissions).map_err(convert_error)
     }
 
+    fn flush(&mut self) -> ProviderResult<()> {
+        self.session.flush().map_err(convert_error)
+    }
+
     fn get_free_space(&self, path: &str) -> Option<u64> {
         self.session.get_free_space(path)
     }
@@ -182,4 +219,8 @@ impl PanelProvider for PluginProviderAdapter {
     fn set_password(&mut self, password: &str) -> crate::providers::ProviderResult<()> {
         self.session.set_password(password).map_err(convert_error)
     }
+
+    fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
 }
@@ -38,14 +38,23 @@
 //!
 //! ### delete / mkdir / rename / copy_file
 //! Similar pattern with appropriate parameters
+//!
+//! The typed messages live in `bark_plugin_api::protocol`. A reply that does
+//! not match its type is reported with the plugin name and command.
 
-use std::io::Write;
+use std::io::{BufRead, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::sync::Mutex;
-use std::time::SystemTime;
-
-use bark_plugin_api::FileEntry;
+use std::process::{Command as Process, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use bark_plugin_api::protocol::{
+    config_object, parse_reply, Command, ConnectReply, DialogFieldsReply, ErrorReply, ErrorType,
+    FieldSpec, FreeSpaceReply, ListReply, PluginInfoReply, ReadReply, Request, SuccessReply,
+    ValidateReply,
+};
+use bark_plugin_api::{BufferedSessionWriter, Capability, FileEntry};
+use serde::de::DeserializeOwned;
 use crate::plugins::provider_api::*;
 
 /// A script-based provider plugin
@@ -60,7 +69,7 @@ impl ScriptProviderPlugin {
     /// Load a provider plugin from an executable
     pub fn load(path: &Path) -> Result<Self, String> {
         // Query the plugin for its info
-        let output = Command::new(path)
+        let output = Process::new(path)
             .arg("--plugin-info")
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
@@ -77,7 +86,7 @@ impl ScriptProviderPlugin {
 
         // Pre-fetch dialog fields (only for scheme-based plugins that show connection dialogs)
         let dialog_fields = if !info.schemes.is_empty() {
-            Self::fetch_dialog_fields(path)?
+            Self::fetch_dialog_fields(path, &info.name)?
         } else {
             Vec::new()
         };
@@ -90,158 +99,81 @@ impl ScriptProviderPlugin {
     }
 
     fn parse_info(output: &str, path: &Path) -> Result<ProviderPluginInfo, String> {
-        let mut name = "Unknown Provider".to_string();
-        let mut version = "0.0".to_string();
-        let mut description = String::new();
-        let mut schemes = Vec::new();
-        let mut extensions = Vec::new();
-        let mut icon = None;
-
-        for line in output.lines() {
-            let line = line.trim();
-            if line.starts_with('{') {
-                if let Some(n) = extract_json_string(line, "name") {
-                    name = n;
-                }
-                if let Some(v) = extract_json_string(line, "version") {
-                    version = v;
-                }
-                if let Some(d) = extract_json_string(line, "description") {
-                    description = d;
-                }
-                if let Some(s) = extract_json_string_array(line, "schemes") {
-                    schemes = s;
-                }
-                if let Some(e) = extract_json_string_array(line, "extensions") {
-                    extensions = e;
-                }
-                if let Some(i) = extract_json_string(line, "icon") {
-                    icon = i.chars().next();
-                }
-
-                // Check plugin type
-                if let Some(t) = extract_json_string(line, "type")
-                    && t != "provider"
-                {
-                    return Err(format!("Not a provider plugin (type: {})", t));
-                }
-                break;
-            }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let reply: PluginInfoReply = parse_reply(&file_name, "--plugin-info", output)
+            .map_err(|e| e.to_string())?;
+
+        if let Some(t) = &reply.plugin_type
+            && t != "provider"
+        {
+            return Err(format!("Not a provider plugin (type: {})", t));
         }
 
         // Provider plugins must specify at least one scheme OR one extension
-        if schemes.is_empty() && extensions.is_empty() {
+        if reply.schemes.is_empty() && reply.extensions.is_empty() {
             return Err("Provider plugin must specify at least one URI scheme or file extension".to_string());
         }
 
-        let mut info = ProviderPluginInfo::provider(name, version, schemes)
-            .with_description(description)
-            .with_extensions(extensions);
+        let api_version = reply.api_version()?;
+        let capabilities = reply.capabilities();
+        let name = reply.name.unwrap_or_else(|| "Unknown Provider".to_string());
+        let version = reply.version.unwrap_or_else(|| "0.0".to_string());
+        let mut info = ProviderPluginInfo::provider(name, version, reply.schemes)
+            .with_description(reply.description)
+            .with_extensions(reply.extensions)
+            .with_capabilities(capabilities);
+        info.api_version = api_version;
         info.source = path.to_path_buf();
-        if let Some(i) = icon {
+        if let Some(i) = reply.icon.and_then(|i| i.chars().next()) {
             info = info.with_icon(i);
         }
         Ok(info)
     }
 
-    fn fetch_dialog_fields(path: &Path) -> Result<Vec<DialogField>, String> {
-        let request = r#"{"command":"get_dialog_fields"}"#;
-
-        let mut child = Command::new(path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn plugin: {}", e))?;
-
-        if let Some(mut stdin) = child.stdin.take() {
-            let _ = writeln!(stdin, "{}", request);
-        }
-
-        let output = child.wait_with_output()
-            .map_err(|e| format!("Failed to read plugin output: {}", e))?;
+    fn fetch_dialog_fields(path: &Path, plugin_name: &str) -> Result<Vec<DialogField>, String> {
+        let command = Command::GetDialogFields;
+        let output = run_once(path, &command)?;
 
         if !output.status.success() {
             return Ok(Vec::new()); // Return empty if command not supported
         }
 
         let response = String::from_utf8_lossy(&output.stdout);
-        Self::parse_dialog_fields(&response)
-    }
-
-    fn parse_dialog_fields(response: &str) -> Result<Vec<DialogField>, String> {
-        let mut fields = Vec::new();
-
-        // Find fields array
-        let fields_json = extract_json_array(response, "fields")
-            .unwrap_or_default();
-
-        for field_json in fields_json {
-            let id = extract_json_string(&field_json, "id").unwrap_or_default();
-            let label = extract_json_string(&field_json, "label").unwrap_or_else(|| id.clone());
-            let field_type_str = extract_json_string(&field_json, "type").unwrap_or_else(|| "text".to_string());
-            let default_value = extract_json_string(&field_json, "default");
-            let placeholder = extract_json_string(&field_json, "placeholder");
-            let required = extract_json_bool(&field_json, "required").unwrap_or(false);
-            let help_text = extract_json_string(&field_json, "help");
-
-            let field_type = match field_type_str.as_str() {
-                "text" => DialogFieldType::Text,
-                "password" => DialogFieldType::Password,
-                "number" => DialogFieldType::Number,
-                "checkbox" => DialogFieldType::Checkbox,
-                "textarea" => DialogFieldType::TextArea,
-                "file" | "filepath" => DialogFieldType::FilePath,
-                "select" => {
-                    let options = extract_json_select_options(&field_json);
-                    DialogFieldType::Select { options }
-                }
-                _ => DialogFieldType::Text,
-            };
-
-            if !id.is_empty() {
-                fields.push(DialogField {
-                    id,
-                    label,
-                    field_type,
-                    default_value,
-                    placeholder,
-                    required,
-                    help_text,
-                });
-            }
-        }
-
-        Ok(fields)
+        let reply: DialogFieldsReply = parse_reply(plugin_name, command.name(), &response)
+            .map_err(|e| e.to_string())?;
+        Ok(reply.fields.into_iter().filter_map(FieldSpec::into_dialog_field).collect())
     }
 
-    fn execute_command(&self, command: &str, args: &str) -> Result<String, String> {
-        let request = if args.is_empty() {
-            format!("{{\"command\":\"{}\"}}", command)
-        } else {
-            format!("{{\"command\":\"{}\",{}}}", command, args)
-        };
+    /// Run a one-shot command in a fresh plugin process and decode the reply
+    fn execute_command<T: DeserializeOwned>(&self, command: Command) -> Result<T, String> {
+        let output = run_once(&self.executable, &command)?;
 
-        let mut child = Command::new(&self.executable)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn plugin: {}", e))?;
-
-        if let Some(mut stdin) = child.stdin.take() {
-            let _ = writeln!(stdin, "{}", request);
+        if !output.status.success() {
+            return Err(format!("Plugin command '{}' failed", command.name()));
         }
 
-        let output = child.wait_with_output()
-            .map_err(|e| format!("Failed to read plugin output: {}", e))?;
+        let response = String::from_utf8_lossy(&output.stdout);
+        parse_reply(&self.info.name, command.name(), &response).map_err(|e| e.to_string())
+    }
+}
+
+/// Spawn the plugin, write a single request and wait for it to exit
+fn run_once(executable: &Path, command: &Command) -> Result<Output, String> {
+    let request = Request::new(command.clone()).to_line();
 
-        if !output.status.success() {
-            return Err("Plugin command failed".to_string());
-        }
+    let mut child = Process::new(executable)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn plugin: {}", e))?;
 
-        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(request.as_bytes());
     }
+
+    child.wait_with_output()
+        .map_err(|e| format!("Failed to read plugin output: {}", e))
 }
 
 impl ProviderPlugin for ScriptProviderPlugin {
@@ -254,24 +186,21 @@ impl ProviderPlugin for ScriptProviderPlugin {
     }
 
     fn validate_config(&self, config: &ProviderConfig) -> ProviderPluginResult<()> {
-        let config_json = config_to_json(config);
-        let args = format!("\"config\":{}", config_json);
-
-        let response = self.execute_command("validate_config", &args)
+        let reply: ValidateReply = self
+            .execute_command(Command::ValidateConfig { config: config_object(config) })
             .map_err(ProviderPluginError::PluginError)?;
 
-        if extract_json_bool(&response, "valid").unwrap_or(false) {
+        if reply.valid {
             Ok(())
         } else {
-            let error = extract_json_string(&response, "error")
-                .unwrap_or_else(|| "Validation failed".to_string());
+            let error = reply.error.unwrap_or_else(|| "Validation failed".to_string());
             Err(ProviderPluginError::ConfigError(error))
         }
     }
 
     fn connect(&self, config: &ProviderConfig) -> ProviderPluginResult<Box<dyn ProviderSession>> {
         // Spawn a persistent child process for this session
-        let mut child = Command::new(&self.executable)
+        let mut child = Process::new(&self.executable)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
@@ -285,40 +214,111 @@ impl ProviderPlugin for ScriptProviderPlugin {
 
         let mut session = ScriptProviderSession {
             child: Some(child),
-            stdin: Mutex::new(child_stdin),
-            stdout: Mutex::new(std::io::BufReader::new(child_stdout)),
-            session_id: uuid_v4(),
+            channel: Arc::new(PluginChannel {
+                plugin_name: self.info.name.clone(),
+                session_id: Mutex::new(uuid_v4()),
+                pipe: Mutex::new((child_stdin, std::io::BufReader::new(child_stdout))),
+            }),
+            capabilities: self.info.capabilities.clone(),
             display_name: config.name.clone(),
             short_label: None,
             home_path: config.get("path").unwrap_or("/").to_string(),
             connected: Mutex::new(true),
         };
 
-        // Send the connect command to the persistent process
-        let config_json = config_to_json(config);
-        let args = format!("\"config\":{}", config_json);
-        let response = session.execute_command("connect", &args)
-            .map_err(|e| ProviderPluginError::Connection(format!("Connect failed: {}", e)))?;
+        // Send the connect command to the persistent process. A password
+        // request is passed through untouched so the caller can prompt.
+        let reply: ConnectReply = session.channel
+            .call(Command::Connect { config: config_object(config) })
+            .map_err(|e| match e {
+                ProviderPluginError::PasswordRequired(_) => e,
+                e => ProviderPluginError::Connection(format!("Connect failed: {}", e)),
+            })?;
 
-        if extract_json_bool(&response, "success").unwrap_or(false) {
-            if let Some(sid) = extract_json_string(&response, "session_id") {
-                session.session_id = sid;
-            }
-            if let Some(name) = extract_json_string(&response, "display_name") {
-                if !name.is_empty() && session.display_name.is_empty() {
-                    session.display_name = name;
-                }
-            }
-            session.short_label = extract_json_string(&response, "short_label");
-            Ok(Box::new(session))
+        if !reply.success {
+            return Err(ProviderPluginError::Connection("Connection failed".to_string()));
+        }
+        if let Some(sid) = reply.session_id {
+            *session.channel.session_id.lock().unwrap() = sid;
+        }
+        if let Some(name) = reply.display_name
+            && !name.is_empty()
+            && session.display_name.is_empty()
+        {
+            session.display_name = name;
+        }
+        session.short_label = reply.short_label;
+        Ok(Box::new(session))
+    }
+}
+
+/// Bytes requested per `read_range` / sent per `write_range`
+const STREAM_CHUNK: usize = 1024 * 1024;
+
+/// Request/reply pipe to a running plugin process. Shared with streaming
+/// readers, which outlive the borrow of the session that opened them.
+struct PluginChannel {
+    /// Plugin name, used in protocol error messages
+    plugin_name: String,
+    session_id: Mutex<String>,
+    pipe: Mutex<(std::process::ChildStdin, std::io::BufReader<std::process::ChildStdout>)>,
+}
+
+impl PluginChannel {
+    /// Send a command and return the raw reply line, turning an `error`
+    /// reply into the matching provider error.
+    fn send(&self, command: Command) -> Result<String, ProviderPluginError> {
+        let name = command.name();
+        let session_id = self.session_id.lock().unwrap().clone();
+        let request = Request::new(command).with_session(session_id).to_line();
+
+        let response = {
+            let mut pipe = self.pipe.lock().unwrap();
+            let (stdin, stdout) = &mut *pipe;
+            stdin.write_all(request.as_bytes())
+                .map_err(|e| ProviderPluginError::PluginError(format!("Failed to write to plugin: {}", e)))?;
+            stdin.flush()
+                .map_err(|e| ProviderPluginError::PluginError(format!("Failed to flush plugin stdin: {}", e)))?;
+
+            // Read one line of response from stdout
+            let mut line = String::new();
+            stdout.read_line(&mut line)
+                .map_err(|e| ProviderPluginError::PluginError(format!("Failed to read from plugin: {}", e)))?;
+            line
+        };
+
+        let reply: ErrorReply = parse_reply(&self.plugin_name, name, &response)
+            .map_err(|e| ProviderPluginError::PluginError(e.to_string()))?;
+
+        if let Some(error) = reply.error {
+            return Err(match reply.error_type {
+                Some(ErrorType::Auth) => ProviderPluginError::Auth(error),
+                Some(ErrorType::NotFound) => ProviderPluginError::NotFound(error),
+                Some(ErrorType::Permission | ErrorType::ReadOnly) => ProviderPluginError::PermissionDenied(error),
+                Some(ErrorType::Connection) => ProviderPluginError::Connection(error),
+                Some(ErrorType::PasswordRequired) => ProviderPluginError::PasswordRequired(error),
+                Some(ErrorType::Other) | None => ProviderPluginError::Other(error),
+            });
+        }
+
+        Ok(response)
+    }
+
+    /// Send a command and decode its reply
+    fn call<T: DeserializeOwned>(&self, command: Command) -> ProviderPluginResult<T> {
+        let name = command.name();
+        let response = self.send(command)?;
+        parse_reply(&self.plugin_name, name, &response)
+            .map_err(|e| ProviderPluginError::PluginError(e.to_string()))
+    }
+
+    /// Send a command that answers with `{"success": bool}`
+    fn call_success(&self, command: Command, failure: &str) -> ProviderPluginResult<()> {
+        let reply: SuccessReply = self.call(command)?;
+        if reply.success {
+            Ok(())
         } else {
-            let error = extract_json_string(&response, "error")
-                .unwrap_or_else(|| "Connection failed".to_string());
-            let error_type = extract_json_string(&response, "error_type");
-            match error_type.as_deref() {
-                Some("password_required") => Err(ProviderPluginError::PasswordRequired(error)),
-                _ => Err(ProviderPluginError::Connection(error)),
-            }
+            Err(ProviderPluginError::Other(failure.to_string()))
         }
     }
 }
@@ -328,22 +328,19 @@ impl ProviderPlugin for ScriptProviderPlugin {
 /// sending commands via stdin and reading responses from stdout.
 pub struct ScriptProviderSession {
     child: Option<std::process::Child>,
-    stdin: Mutex<std::process::ChildStdin>,
-    stdout: Mutex<std::io::BufReader<std::process::ChildStdout>>,
-    session_id: String,
+    channel: Arc<PluginChannel>,
+    /// Optional operations declared by the plugin
+    capabilities: Vec<Capability>,
     display_name: String,
     short_label: Option<String>,
     home_path: String,
     connected: Mutex<bool>,
 }
 
-// Safety: the child process handles are accessed through Mutex locks
-unsafe impl Send for ScriptProviderSession {}
-
 impl Drop for ScriptProviderSession {
     fn drop(&mut self) {
         // Try to send disconnect, then kill the child
-        let _ = self.execute_command("disconnect", "");
+        let _ = self.channel.send(Command::Disconnect);
         if let Some(mut child) = self.child.take() {
             let _ = child.kill();
             let _ = child.wait();
@@ -352,58 +349,99 @@ impl Drop for ScriptProviderSession {
 }
 
 impl ScriptProviderSession {
-    fn execute_command(&self, command: &str, args: &str) -> Result<String, ProviderPluginError> {
-        let session_arg = format!("\"session_id\":\"{}\"", escape_json(&self.session_id));
-        let full_args = if args.is_empty() {
-            session_arg
+    fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    fn require(&self, capability: Capability) -> ProviderPluginResult<()> {
+        if self.supports(capability) {
+            Ok(())
         } else {
-            format!("{},{}", session_arg, args)
-        };
+            Err(ProviderPluginError::Other(format!(
+                "{} does not support {}",
+                self.channel.plugin_name,
+                capability.description()
+            )))
+        }
+    }
+}
 
-        let request = format!("{{\"command\":\"{}\",{}}}\n", command, full_args);
+/// Reads a file in `read_range` chunks
+struct ChunkedReader {
+    channel: Arc<PluginChannel>,
+    path: String,
+    offset: u64,
+    chunk: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
 
-        // Write request to stdin
-        {
-            let mut stdin = self.stdin.lock().unwrap();
-            use std::io::Write;
-            stdin.write_all(request.as_bytes())
-                .map_err(|e| ProviderPluginError::PluginError(format!("Failed to write to plugin: {}", e)))?;
-            stdin.flush()
-                .map_err(|e| ProviderPluginError::PluginError(format!("Failed to flush plugin stdin: {}", e)))?;
-        }
+impl Read for ChunkedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos == self.chunk.len() && !self.eof {
+            let reply: ReadReply = self.channel
+                .call(Command::ReadRange {
+                    path: self.path.clone(),
+                    offset: self.offset,
+                    length: STREAM_CHUNK as u64,
+                })
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+            self.chunk = base64_decode(&reply.data).map_err(std::io::Error::other)?;
+            self.pos = 0;
+            self.offset += self.chunk.len() as u64;
+            self.eof = self.chunk.is_empty();
+        }
+
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
 
-        // Read one line of response from stdout
-        let response = {
-            let mut stdout = self.stdout.lock().unwrap();
-            let mut line = String::new();
-            use std::io::BufRead;
-            stdout.read_line(&mut line)
-                .map_err(|e| ProviderPluginError::PluginError(format!("Failed to read from plugin: {}", e)))?;
-            line
-        };
+/// Writes a file in `write_range` chunks
+struct ChunkedWriter {
+    channel: Arc<PluginChannel>,
+    path: String,
+    offset: u64,
+    buffer: Vec<u8>,
+}
 
-        let response = response.trim().to_string();
+impl ChunkedWriter {
+    fn send_chunk(&mut self) -> ProviderPluginResult<()> {
+        let command = Command::WriteRange {
+            path: self.path.clone(),
+            offset: self.offset,
+            data: base64_encode(&self.buffer),
+        };
+        self.channel.call_success(command, "Write failed")?;
+        self.offset += self.buffer.len() as u64;
+        self.buffer.clear();
+        Ok(())
+    }
+}
 
-        if response.is_empty() {
-            return Err(ProviderPluginError::PluginError("Empty response from plugin".to_string()));
+impl Write for ChunkedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= STREAM_CHUNK {
+            self.send_chunk().map_err(|e| std::io::Error::other(e.to_string()))?;
         }
+        Ok(buf.len())
+    }
 
-        // Check for error in response
-        if let Some(error) = extract_json_string(&response, "error") {
-            if let Some(error_type) = extract_json_string(&response, "error_type") {
-                return Err(match error_type.as_str() {
-                    "auth" => ProviderPluginError::Auth(error),
-                    "not_found" => ProviderPluginError::NotFound(error),
-                    "permission" => ProviderPluginError::PermissionDenied(error),
-                    "connection" => ProviderPluginError::Connection(error),
-                    "password_required" => ProviderPluginError::PasswordRequired(error),
-                    _ => ProviderPluginError::Other(error),
-                });
-            }
-            return Err(ProviderPluginError::Other(error));
-        }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
 
-        Ok(response)
+impl SessionWriter for ChunkedWriter {
+    fn finish(mut self: Box<Self>) -> ProviderPluginResult<()> {
+        // An empty file still needs one write to be created
+        if !self.buffer.is_empty() || self.offset == 0 {
+            self.send_chunk()?;
+        }
+        Ok(())
     }
 }
 
@@ -426,134 +464,109 @@ impl ProviderSession for ScriptProviderSession {
     }
 
     fn list_directory(&mut self, path: &str) -> ProviderPluginResult<Vec<FileEntry>> {
-        let args = format!("\"path\":\"{}\"", escape_json(path));
-        let response = self.execute_command("list_directory", &args)?;
-
-        let entries_json = extract_json_array(&response, "entries")
-            .unwrap_or_default();
+        let reply: ListReply = self.channel.call(Command::ListDirectory { path: path.to_string() })?;
 
         let mut entries = Vec::new();
         let base_path = if path == "/" { "" } else { path };
 
-        for entry_json in entries_json {
-            let name = extract_json_string(&entry_json, "name").unwrap_or_default();
-            if name.is_empty() || name == "." {
+        for entry in reply.entries {
+            if entry.name.is_empty() || entry.name == "." {
                 continue;
             }
 
-            let is_dir = extract_json_bool(&entry_json, "is_dir").unwrap_or(false);
-            let size = extract_json_int(&entry_json, "size").unwrap_or(0) as u64;
-            let is_hidden = name.starts_with('.') ||
-                extract_json_bool(&entry_json, "is_hidden").unwrap_or(false);
-            let is_symlink = extract_json_bool(&entry_json, "is_symlink").unwrap_or(false);
-
-            let modified = extract_json_int(&entry_json, "modified")
-                .and_then(|ts| SystemTime::UNIX_EPOCH.checked_add(
-                    std::time::Duration::from_secs(ts as u64)
-                ));
+            let modified = entry.modified
+                .and_then(|ts| u64::try_from(ts).ok())
+                .and_then(|ts| SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(ts)));
 
-            let full_path = if base_path.is_empty() {
-                format!("/{}", name)
-            } else {
-                format!("{}/{}", base_path, name)
-            };
+            let full_path = format!("{}/{}", base_path, entry.name);
 
             entries.push(FileEntry {
-                name,
+                is_hidden: entry.name.starts_with('.') || entry.is_hidden,
+                name: entry.name,
                 path: PathBuf::from(&full_path),
-                is_dir,
-                size,
+                is_dir: entry.is_dir,
+                size: entry.size,
                 modified,
-                is_hidden,
-                permissions: extract_json_int(&entry_json, "permissions").unwrap_or(0) as u32,
-                is_symlink,
-                symlink_target: extract_json_string(&entry_json, "symlink_target")
-                    .map(PathBuf::from),
-                owner: extract_json_string(&entry_json, "owner").unwrap_or_default(),
-                group: extract_json_string(&entry_json, "group").unwrap_or_default(),
+                permissions: entry.permissions,
+                is_symlink: entry.is_symlink,
+                symlink_target: entry.symlink_target.map(PathBuf::from),
+                owner: entry.owner,
+                group: entry.group,
             });
         }
 
         Ok(entries)
     }
 
+    fn open_read(&mut self, path: &str) -> ProviderPluginResult<Box<dyn Read + Send>> {
+        if !self.supports(Capability::Streaming) {
+            let data = self.read_file(path)?;
+            return Ok(Box::new(std::io::Cursor::new(data)));
+        }
+        Ok(Box::new(ChunkedReader {
+            channel: self.channel.clone(),
+            path: path.to_string(),
+            offset: 0,
+            chunk: Vec::new(),
+            pos: 0,
+            eof: false,
+        }))
+    }
+
+    fn open_write(&mut self, path: &str) -> ProviderPluginResult<Box<dyn SessionWriter + '_>> {
+        self.require(Capability::Write)?;
+        if !self.supports(Capability::Streaming) {
+            return Ok(Box::new(BufferedSessionWriter::new(self, path)));
+        }
+        Ok(Box::new(ChunkedWriter {
+            channel: self.channel.clone(),
+            path: path.to_string(),
+            offset: 0,
+            buffer: Vec::new(),
+        }))
+    }
+
     fn read_file(&mut self, path: &str) -> ProviderPluginResult<Vec<u8>> {
-        let args = format!("\"path\":\"{}\"", escape_json(path));
-        let response = self.execute_command("read_file", &args)?;
+        let reply: ReadReply = self.channel.call(Command::ReadFile { path: path.to_string() })?;
 
         // Data is base64 encoded
-        let data_b64 = extract_json_string(&response, "data")
-            .ok_or_else(|| ProviderPluginError::Other("No data in response".to_string()))?;
-
-        base64_decode(&data_b64)
+        base64_decode(&reply.data)
             .map_err(|e| ProviderPluginError::Other(format!("Failed to decode data: {}", e)))
     }
 
     fn write_file(&mut self, path: &str, data: &[u8]) -> ProviderPluginResult<()> {
-        let data_b64 = base64_encode(data);
-        let args = format!("\"path\":\"{}\",\"data\":\"{}\"", escape_json(path), data_b64);
-        let response = self.execute_command("write_file", &args)?;
-
-        if extract_json_bool(&response, "success").unwrap_or(false) {
-            Ok(())
-        } else {
-            Err(ProviderPluginError::Other("Write failed".to_string()))
-        }
+        self.require(Capability::Write)?;
+        let command = Command::WriteFile { path: path.to_string(), data: base64_encode(data) };
+        self.channel.call_success(command, "Write failed")
     }
 
     fn delete(&mut self, path: &str) -> ProviderPluginResult<()> {
-        let args = format!("\"path\":\"{}\"", escape_json(path));
-        let response = self.execute_command("delete", &args)?;
-
-        if extract_json_bool(&response, "success").unwrap_or(false) {
-            Ok(())
-        } else {
-            Err(ProviderPluginError::Other("Delete failed".to_string()))
-        }
+        self.require(Capability::Delete)?;
+        let command = Command::Delete { path: path.to_string(), recursive: false };
+        self.channel.call_success(command, "Delete failed")
     }
 
     fn delete_recursive(&mut self, path: &str) -> ProviderPluginResult<()> {
-        let args = format!("\"path\":\"{}\",\"recursive\":true", escape_json(path));
-        let response = self.execute_command("delete", &args)?;
-
-        if extract_json_bool(&response, "success").unwrap_or(false) {
-            Ok(())
-        } else {
-            Err(ProviderPluginError::Other("Delete failed".to_string()))
-        }
+        self.require(Capability::Delete)?;
+        let command = Command::Delete { path: path.to_string(), recursive: true };
+        self.channel.call_success(command, "Delete failed")
     }
 
     fn rename(&mut self, from: &str, to: &str) -> ProviderPluginResult<()> {
-        let args = format!("\"from\":\"{}\",\"to\":\"{}\"", escape_json(from), escape_json(to));
-        let response = self.execute_command("rename", &args)?;
-
-        if extract_json_bool(&response, "success").unwrap_or(false) {
-            Ok(())
-        } else {
-            Err(ProviderPluginError::Other("Rename failed".to_string()))
-        }
+        self.require(Capability::Rename)?;
+        let command = Command::Rename { from: from.to_string(), to: to.to_string() };
+        self.channel.call_success(command, "Rename failed")
     }
 
     fn mkdir(&mut self, path: &str) -> ProviderPluginResult<()> {
-        let args = format!("\"path\":\"{}\"", escape_json(path));
-        let response = self.execute_command("mkdir", &args)?;
-
-        if extract_json_bool(&response, "success").unwrap_or(false) {
-            Ok(())
-        } else {
-            Err(ProviderPluginError::Other("Mkdir failed".to_string()))
-        }
+        self.require(Capability::Mkdir)?;
+        self.channel.call_success(Command::Mkdir { path: path.to_string() }, "Mkdir failed")
     }
 
     fn copy_file(&mut self, from: &str, to: &str) -> ProviderPluginResult<()> {
-        let args = format!("\"from\":\"{}\",\"to\":\"{}\"", escape_json(from), escape_json(to));
-        let response = self.execute_command("copy", &args)?;
-
-        if extract_json_bool(&response, "success").unwrap_or(false) {
-            Ok(())
-        } else {
-            Err(ProviderPluginError::Other("Copy failed".to_string()))
-        }
+        self.require(Capability::CopyFile)?;
+        let command = Command::CopyFile { from: from.to_string(), to: to.to_string() };
+        self.channel.call_success(command, "Copy failed")
     }
 
     fn set_attributes(
@@ -562,283 +575,47 @@ impl ProviderSession for ScriptProviderSession {
         modified: Option<SystemTime>,
         permissions: u32,
     ) -> ProviderPluginResult<()> {
-        use std::time::UNIX_EPOCH;
-
-        let mtime_arg = modified
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map(|d| format!(",\"modified\":{}", d.as_secs()))
-            .unwrap_or_default();
-        let perm_arg = if permissions != 0 {
-            format!(",\"permissions\":{}", permissions)
-        } else {
-            String::new()
-        };
+        let modified = modified
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        let permissions = (permissions != 0).then_some(permissions);
 
-        // Only send if there's something to set
-        if mtime_arg.is_empty() && perm_arg.is_empty() {
+        // Only send if there's something to set and the plugin can set it
+        if !self.supports(Capability::SetAttributes) || (modified.is_none() && permissions.is_none()) {
             return Ok(());
         }
 
-        let args = format!("\"path\":\"{}\"{}{}",
-            escape_json(path), mtime_arg, perm_arg);
-
         // Best-effort: plugins may not support this command, so ignore errors
-        let _ = self.execute_command("set_attributes", &args);
+        let _ = self.channel.send(Command::SetAttributes { path: path.to_string(), modified, permissions });
         Ok(())
     }
 
-    fn home_path(&self) -> String {
-        self.home_path.clone()
-    }
-
-    fn set_password(&mut self, password: &str) -> ProviderPluginResult<()> {
-        let args = format!("\"password\":\"{}\"", escape_json(password));
-        self.execute_command("set_password", &args)?;
-        Ok(())
-    }
-}
-
-// ============================================================================
-// JSON Helpers (minimal, no external crates)
-// ============================================================================
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn extract_json_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('"') {
-        return None;
-    }
-
-    let rest = &rest[1..];
-    let mut result = String::new();
-    let mut chars = rest.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => break,
-            '\\' => {
-                if let Some(&next) = chars.peek() {
-                    chars.next();
-                    match next {
-                        'n' => result.push('\n'),
-                        'r' => result.push('\r'),
-                        't' => result.push('\t'),
-                        '"' => result.push('"'),
-                        '\\' => result.push('\\'),
-                        _ => {
-                            result.push('\\');
-                            result.push(next);
-                        }
-                    }
-                }
-            }
-            _ => result.push(c),
-        }
-    }
-
-    Some(result)
-}
-
-fn extract_json_bool(json: &str, key: &str) -> Option<bool> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if rest.starts_with("true") {
-        Some(true)
-    } else if rest.starts_with("false") {
-        Some(false)
-    } else {
-        None
-    }
-}
-
-fn extract_json_int(json: &str, key: &str) -> Option<i64> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn extract_json_string_array(json: &str, key: &str) -> Option<Vec<String>> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('[') {
-        return None;
-    }
-
-    let mut depth = 0;
-    let mut end = 0;
-    for (i, c) in rest.char_indices() {
-        match c {
-            '[' => depth += 1,
-            ']' => {
-                depth -= 1;
-                if depth == 0 {
-                    end = i + c.len_utf8();
-                    break;
-                }
-            }
-            _ => {}
-        }
-    }
-
-    if end == 0 {
-        return None;
-    }
-
-    let array_str = &rest['['.len_utf8()..end - ']'.len_utf8()];
-    let mut result = Vec::new();
-    let mut current = String::new();
-    let mut in_string = false;
-    let mut escape = false;
-
-    for c in array_str.chars() {
-        if escape {
-            current.push(c);
-            escape = false;
-            continue;
-        }
-
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => {
-                if in_string {
-                    result.push(current.clone());
-                    current.clear();
-                }
-                in_string = !in_string;
-            }
-            ',' if !in_string => {}
-            _ if in_string => current.push(c),
-            _ => {}
+    fn flush(&mut self) -> ProviderPluginResult<()> {
+        if !self.supports(Capability::Flush) {
+            return Ok(());
         }
+        self.channel.call_success(Command::Flush, "Saving changes failed")
     }
 
-    Some(result)
-}
-
-fn extract_json_array(json: &str, key: &str) -> Option<Vec<String>> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('[') {
-        return None;
-    }
-
-    let mut depth = 0;
-    let mut end = 0;
-    for (i, c) in rest.char_indices() {
-        match c {
-            '[' | '{' => depth += 1,
-            ']' | '}' => {
-                depth -= 1;
-                if depth == 0 {
-                    end = i + c.len_utf8();
-                    break;
-                }
-            }
-            _ => {}
+    fn get_free_space(&self, path: &str) -> Option<u64> {
+        if !self.supports(Capability::FreeSpace) {
+            return None;
         }
+        let reply: FreeSpaceReply = self.channel
+            .call(Command::GetFreeSpace { path: path.to_string() })
+            .ok()?;
+        reply.free
     }
 
-    if end == 0 {
-        return None;
-    }
-
-    // Parse array of objects
-    let array_content = &rest['['.len_utf8()..end - ']'.len_utf8()];
-    let mut objects = Vec::new();
-    let mut current = String::new();
-    let mut depth = 0;
-    let mut in_string = false;
-    let mut escape = false;
-
-    for c in array_content.chars() {
-        if escape {
-            current.push(c);
-            escape = false;
-            continue;
-        }
-
-        match c {
-            '\\' => {
-                escape = true;
-                current.push(c);
-            }
-            '"' => {
-                in_string = !in_string;
-                current.push(c);
-            }
-            '{' if !in_string => {
-                depth += 1;
-                current.push(c);
-            }
-            '}' if !in_string => {
-                depth -= 1;
-                current.push(c);
-                if depth == 0 {
-                    objects.push(current.trim().to_string());
-                    current.clear();
-                }
-            }
-            ',' if depth == 0 && !in_string => {
-                // Skip commas between objects
-            }
-            _ => {
-                if depth > 0 || !c.is_whitespace() {
-                    current.push(c);
-                }
-            }
-        }
-    }
-
-    if !current.trim().is_empty() && current.contains('{') {
-        objects.push(current.trim().to_string());
-    }
-
-    Some(objects)
-}
-
-fn extract_json_select_options(json: &str) -> Vec<(String, String)> {
-    let options_json = extract_json_array(json, "options").unwrap_or_default();
-    let mut options = Vec::new();
-
-    for opt_json in options_json {
-        let value = extract_json_string(&opt_json, "value").unwrap_or_default();
-        let label = extract_json_string(&opt_json, "label").unwrap_or_else(|| value.clone());
-        if !value.is_empty() {
-            options.push((value, label));
-        }
+    fn home_path(&self) -> String {
+        self.home_path.clone()
     }
 
-    options
-}
-
-fn config_to_json(config: &ProviderConfig) -> String {
-    let mut parts = Vec::new();
-    parts.push(format!("\"name\":\"{}\"", escape_json(&config.name)));
-
-    for (key, value) in &config.values {
-        parts.push(format!("\"{}\":\"{}\"", escape_json(key), escape_json(value)));
+    fn set_password(&mut self, password: &str) -> ProviderPluginResult<()> {
+        self.require(Capability::SetPassword)?;
+        self.channel.send(Command::SetPassword { password: password.to_string() })?;
+        Ok(())
     }
-
-    format!("{{{}}}", parts.join(","))
 }
 
 fn uuid_v4() -> String {
@@ -911,3 +688,87 @@ fn base64_decode(s: &str) -> Result<Vec<u8>, String> {
 
     Ok(result)
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A shell plugin that answers with reordered fields, nested objects and
+    /// escaped quotes, and a malformed `read_file` reply
+    const FAKE_PLUGIN: &str = r#"#!/bin/sh
+if [ "$1" = "--plugin-info" ]; then
+  echo '{"extensions":["zz"],"type":"provider","name":"Fake"}'
+  exit 0
+fi
+while read line; do
+  case "$line" in
+    *'"connect"'*) echo '{"short_label":"[Z]","success":true}' ;;
+    *'"list_directory"'*) echo '{"entries":[{"size":2,"meta":{"name":"no"},"name":"x \"y\""}]}' ;;
+    *'"read_file"'*) echo '{"data":5}' ;;
+    *) echo '{"success":true}' ;;
+  esac
+done
+"#;
+
+    /// A read-only streaming plugin
+    const STREAMING_PLUGIN: &str = r#"#!/bin/sh
+if [ "$1" = "--plugin-info" ]; then
+  echo '{"type":"provider","name":"Stream","schemes":["st"],"api_version":1,"capabilities":["streaming","free_space","teleport"]}'
+  exit 0
+fi
+while read line; do
+  case "$line" in
+    *'"read_range"'*'"offset":0,'*) echo '{"data":"aGk="}' ;;
+    *'"read_range"'*) echo '{"data":""}' ;;
+    *'"get_free_space"'*) echo '{"free":42}' ;;
+    *'"get_dialog_fields"'*) echo '{"fields":[]}' ;;
+    *) echo '{"success":true}' ;;
+  esac
+done
+"#;
+
+    fn fake_plugin(name: &str, script: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bark-plugin-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_session_decodes_replies() {
+        let plugin = ScriptProviderPlugin::load(&fake_plugin("fake-provider", FAKE_PLUGIN)).unwrap();
+        assert_eq!(plugin.info().name, "Fake");
+        assert_eq!(plugin.info().api_version, 1);
+        assert!(plugin.info().supports(Capability::Write));
+
+        let mut session = plugin.connect(&ProviderConfig::new()).unwrap();
+        assert_eq!(session.short_label().as_deref(), Some("[Z]"));
+
+        let entries = session.list_directory("/dir").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "x \"y\"");
+        assert_eq!(entries[0].path, PathBuf::from("/dir/x \"y\""));
+        assert_eq!(entries[0].size, 2);
+
+        let err = session.read_file("/dir/x").unwrap_err().to_string();
+        assert!(err.contains("plugin 'Fake'") && err.contains("'read_file'"), "{err}");
+    }
+
+    #[test]
+    fn test_session_honors_capabilities() {
+        let plugin = ScriptProviderPlugin::load(&fake_plugin("stream-provider", STREAMING_PLUGIN)).unwrap();
+        assert_eq!(plugin.info().capabilities, vec![Capability::Streaming, Capability::FreeSpace]);
+
+        let mut session = plugin.connect(&ProviderConfig::new()).unwrap();
+        let err = session.mkdir("/new").unwrap_err().to_string();
+        assert!(err.contains("does not support creating directories"), "{err}");
+        assert_eq!(session.get_free_space("/"), Some(42));
+
+        let mut data = String::new();
+        session.open_read("/f").unwrap().read_to_string(&mut data).unwrap();
+        assert_eq!(data, "hi");
+    }
+}
@@ -2,7 +2,12 @@
 
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command as Process, Stdio};
+
+use bark_plugin_api::protocol::{
+    parse_reply, CanHandleReply, Command, PluginInfoReply, Request, StatusReply, ViewerReply, ViewerSort,
+};
+use serde::de::DeserializeOwned;
 
 use crate::plugins::api::*;
 
@@ -18,7 +23,7 @@ impl ScriptPlugin {
     /// Load a script plugin from an executable
     pub fn load(path: &Path) -> Result<Self, String> {
         // Query the plugin for its info
-        let output = Command::new(path)
+        let output = Process::new(path)
             .arg("--plugin-info")
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
@@ -41,53 +46,32 @@ impl ScriptPlugin {
     }
 
     fn parse_info(output: &str, path: &Path) -> Result<PluginInfo, String> {
-        // Parse JSON output: {"name": "...", "version": "...", "type": "status|viewer"}
-        let mut name = "Unknown".to_string();
-        let mut version = "0.0".to_string();
-        let mut plugin_type = PluginType::StatusBar;
-
-        for line in output.lines() {
-            let line = line.trim();
-            if line.starts_with('{') {
-                // Simple JSON parsing without external crates
-                if let Some(n) = extract_json_string(line, "name") {
-                    name = n;
-                }
-                if let Some(v) = extract_json_string(line, "version") {
-                    version = v;
-                }
-                if let Some(t) = extract_json_string(line, "type") {
-                    plugin_type = match t.as_str() {
-                        "status" | "statusbar" | "status_bar" => PluginType::StatusBar,
-                        "viewer" | "view" => PluginType::Viewer,
-                        _ => PluginType::StatusBar,
-                    };
-                }
-                break;
-            }
-        }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let reply: PluginInfoReply = parse_reply(&file_name, "--plugin-info", output)
+            .map_err(|e| e.to_string())?;
 
-        let needs_terminal = extract_json_bool(output.trim(), "needs_terminal").unwrap_or(false);
+        let plugin_type = match reply.plugin_type.as_deref() {
+            Some("viewer" | "view") => PluginType::Viewer,
+            _ => PluginType::StatusBar,
+        };
 
         Ok(PluginInfo {
-            name,
-            version,
+            name: reply.name.unwrap_or_else(|| "Unknown".to_string()),
+            version: reply.version.unwrap_or_else(|| "0.0".to_string()),
             plugin_type,
             source: PluginSource::Script(path.to_path_buf()),
-            needs_terminal,
+            needs_terminal: reply.needs_terminal,
         })
     }
 
-    fn execute_command(&self, command: &str, args: &str) -> Option<String> {
-        // Build JSON request
-        let request = if args.is_empty() {
-            format!("{{\"command\":\"{}\"}}", command)
-        } else {
-            format!("{{\"command\":\"{}\",{}}}", command, args)
-        };
+    /// Run a command in a fresh plugin process and decode the reply.
+    /// Status and viewer plugins fail quietly, so errors only yield `None`.
+    fn execute_command<T: DeserializeOwned>(&self, command: Command) -> Option<T> {
+        let name = command.name();
+        let request = Request::new(command).to_line();
 
         // Execute plugin with request on stdin
-        let mut child = Command::new(&self.executable)
+        let mut child = Process::new(&self.executable)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
@@ -96,7 +80,7 @@ impl ScriptPlugin {
 
         // Write request
         if let Some(mut stdin) = child.stdin.take() {
-            let _ = writeln!(stdin, "{}", request);
+            let _ = stdin.write_all(request.as_bytes());
         }
 
         // Read response
@@ -105,7 +89,8 @@ impl ScriptPlugin {
             return None;
         }
 
-        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        let response = String::from_utf8_lossy(&output.stdout);
+        parse_reply(&self.info.name, name, &response).ok()
     }
 
     pub fn info(&self) -> &PluginInfo {
@@ -119,22 +104,14 @@ impl StatusBarPlugin for ScriptPlugin {
     }
 
     fn render(&self, context: &StatusContext) -> Option<StatusResult> {
-        let args = format!(
-            "\"path\":\"{}\",\"selected_file\":{},\"is_dir\":{},\"file_size\":{},\"selected_count\":{}",
-            escape_json(&context.path.to_string_lossy()),
-            context.selected_file.as_ref()
-                .map(|s| format!("\"{}\"", escape_json(s)))
-                .unwrap_or_else(|| "null".to_string()),
-            context.is_dir,
-            context.file_size,
-            context.selected_count
-        );
-
-        let response = self.execute_command("status_render", &args)?;
-
-        // Parse response: {"text": "..."}
-        let text = extract_json_string(&response, "text")?;
-        Some(StatusResult { text })
+        let reply: StatusReply = self.execute_command(Command::StatusRender {
+            path: context.path.to_string_lossy().into_owned(),
+            selected_file: context.selected_file.clone(),
+            is_dir: context.is_dir,
+            file_size: context.file_size,
+            selected_count: context.selected_count,
+        })?;
+        Some(StatusResult { text: reply.text })
     }
 }
 
@@ -144,187 +121,39 @@ impl ViewerPlugin for ScriptPlugin {
     }
 
     fn can_handle(&self, path: &Path) -> ViewerCanHandleResult {
-        let args = format!("\"path\":\"{}\"", escape_json(&path.to_string_lossy()));
+        let reply: CanHandleReply = self
+            .execute_command(Command::ViewerCanHandle { path: path.to_string_lossy().into_owned() })
+            .unwrap_or_default();
 
-        let response = match self.execute_command("viewer_can_handle", &args) {
-            Some(r) => r,
-            None => return ViewerCanHandleResult { can_handle: false, priority: 0 },
-        };
-
-        // Parse response: {"can_handle": true, "priority": 10}
-        let can_handle = extract_json_bool(&response, "can_handle").unwrap_or(false);
-        let priority = extract_json_int(&response, "priority").unwrap_or(0);
-
-        ViewerCanHandleResult { can_handle, priority }
+        ViewerCanHandleResult { can_handle: reply.can_handle, priority: reply.priority }
     }
 
     fn render(&self, context: &ViewerContext) -> Option<ViewerRenderResult> {
-        // Build config JSON object
-        let config_entries: Vec<String> = context.config.iter()
-            .map(|(k, v)| format!("\"{}\":\"{}\"", escape_json(k), escape_json(v)))
+        let reply: ViewerReply = self.execute_command(Command::ViewerRender {
+            path: context.path.to_string_lossy().into_owned(),
+            width: context.width,
+            height: context.height,
+            scroll: context.scroll,
+            config: context.config.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            sort: context.sort.map(|(column, descending)| ViewerSort { column, descending }),
+        })?;
+
+        let total_lines = reply.total_lines.unwrap_or(reply.lines.len());
+        let folds = reply.folds.iter().map(|fold| (fold.start, fold.end)).collect();
+        let columns = reply
+            .columns
+            .into_iter()
+            .map(|column| TableColumn { start: column.start, width: column.width, info: column.info })
             .collect();
-        let config_json = format!("{{{}}}", config_entries.join(","));
-
-        let args = format!(
-            "\"path\":\"{}\",\"width\":{},\"height\":{},\"scroll\":{},\"config\":{}",
-            escape_json(&context.path.to_string_lossy()),
-            context.width,
-            context.height,
-            context.scroll,
-            config_json
-        );
-
-        let response = self.execute_command("viewer_render", &args)?;
-
-        // Parse response: {"lines": ["line1", "line2", ...], "total_lines": 100}
-        let lines = extract_json_string_array(&response, "lines")?;
-        let total_lines = extract_json_int(&response, "total_lines")
-            .map(|i| i as usize)
-            .unwrap_or(lines.len());
-
-        Some(ViewerRenderResult { lines, total_lines })
-    }
-}
-
-// Simple JSON parsing helpers (no external crates)
-
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn extract_json_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('"') {
-        return None;
-    }
-
-    let rest = &rest[1..];
-    let mut result = String::new();
-    let mut chars = rest.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => break,
-            '\\' => {
-                if let Some(&next) = chars.peek() {
-                    chars.next();
-                    match next {
-                        'n' => result.push('\n'),
-                        'r' => result.push('\r'),
-                        't' => result.push('\t'),
-                        '"' => result.push('"'),
-                        '\\' => result.push('\\'),
-                        _ => {
-                            result.push('\\');
-                            result.push(next);
-                        }
-                    }
-                }
-            }
-            _ => result.push(c),
-        }
-    }
-
-    Some(result)
-}
-
-fn extract_json_bool(json: &str, key: &str) -> Option<bool> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if rest.starts_with("true") {
-        Some(true)
-    } else if rest.starts_with("false") {
-        Some(false)
-    } else {
-        None
-    }
-}
-
-fn extract_json_int(json: &str, key: &str) -> Option<i32> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
-    rest[..end].parse().ok()
-}
-
-fn extract_json_string_array(json: &str, key: &str) -> Option<Vec<String>> {
-    let pattern = format!("\"{}\":", key);
-    let start = json.find(&pattern)? + pattern.len();
-    let rest = json[start..].trim_start();
-
-    if !rest.starts_with('[') {
-        return None;
-    }
-
-    // Find matching ] — must skip brackets inside JSON strings
-    let mut depth = 0;
-    let mut end = 0;
-    let mut in_str = false;
-    let mut esc = false;
-    for (i, c) in rest.char_indices() {
-        if esc {
-            esc = false;
-            continue;
-        }
-        match c {
-            '\\' if in_str => esc = true,
-            '"' => in_str = !in_str,
-            '[' if !in_str => depth += 1,
-            ']' if !in_str => {
-                depth -= 1;
-                if depth == 0 {
-                    end = i + c.len_utf8();
-                    break;
-                }
-            }
-            _ => {}
-        }
-    }
-
-    if end == 0 {
-        return None;
-    }
-
-    let array_str = &rest['['.len_utf8()..end - ']'.len_utf8()];
-    let mut result = Vec::new();
-    let mut current = String::new();
-    let mut in_string = false;
-    let mut escape = false;
-
-    for c in array_str.chars() {
-        if escape {
-            current.push(c);
-            escape = false;
-            continue;
-        }
-
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => {
-                if in_string {
-                    result.push(current.clone());
-                    current.clear();
-                }
-                in_string = !in_string;
-            }
-            ',' if !in_string => {}
-            _ if in_string => current.push(c),
-            _ => {}
-        }
+        Some(ViewerRenderResult {
+            lines: reply.lines,
+            total_lines,
+            folds,
+            paths: reply.paths,
+            header: reply.header,
+            columns,
+        })
     }
-
-    Some(result)
 }
 
 impl Drop for ScriptPlugin {
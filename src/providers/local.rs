@@ -1,10 +1,11 @@
 //! Local filesystem provider
 
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
 use crate::fs::FileEntry;
-use super::{PanelProvider, ProviderError, ProviderInfo, ProviderResult, ProviderType};
+use super::{PanelProvider, ProviderError, ProviderInfo, ProviderResult, ProviderType, ProviderWriter};
 
 /// Provider for local filesystem operations
 #[derive(Debug)]
@@ -62,6 +63,14 @@ impl PanelProvider for LocalProvider {
         fs::write(path, data).map_err(ProviderError::from)
     }
 
+    fn open_read(&mut self, path: &str) -> ProviderResult<Box<dyn Read + Send>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn open_write(&mut self, path: &str) -> ProviderResult<Box<dyn ProviderWriter + '_>> {
+        Ok(Box::new(fs::File::create(path)?))
+    }
+
     fn delete(&mut self, path: &str) -> ProviderResult<()> {
         let path = Path::new(path);
         if path.is_dir() {
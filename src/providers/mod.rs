@@ -9,11 +9,14 @@
 
 mod local;
 mod scp;
+pub mod ssh_config;
 
 pub use local::LocalProvider;
-pub use scp::{ScpAuth, ScpProvider, ScpConnectionInfo};
+pub use scp::{HostKeyTrust, ScpAuth, ScpProvider, ScpConnectionInfo};
 
 use crate::fs::FileEntry;
+pub use bark_plugin_api::Capability;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
 use thiserror::Error;
@@ -36,12 +39,45 @@ pub enum ProviderError {
     PasswordRequired(String),
     #[error("Not supported: {0}")]
     NotSupported(String),
+    #[error("Unknown host key for {host}: {key_type} {fingerprint}")]
+    HostKeyUnknown {
+        host: String,
+        port: u16,
+        key_type: String,
+        fingerprint: String,
+    },
+    #[error(
+        "Host key for {host} does not match known_hosts ({key_type} {fingerprint}). \
+         The server may have been reinstalled, or the connection is being intercepted. \
+         Remove the old entry from known_hosts if the change is expected."
+    )]
+    HostKeyMismatch {
+        /// As written in known_hosts: "host" or "[host]:port"
+        host: String,
+        key_type: String,
+        fingerprint: String,
+    },
+    #[error("Host key for {host} is marked @revoked in known_hosts ({key_type} {fingerprint}); refusing to connect")]
+    HostKeyRevoked {
+        /// As written in known_hosts: "host" or "[host]:port"
+        host: String,
+        key_type: String,
+        fingerprint: String,
+    },
     #[error("{0}")]
     Other(String),
 }
 
 pub type ProviderResult<T> = Result<T, ProviderError>;
 
+impl ProviderError {
+    /// Whether the error may come from a dropped connection rather than
+    /// from the file at hand
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(self, ProviderError::Connection(_) | ProviderError::Io(_))
+    }
+}
+
 /// Information about a provider for display in the source selector
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -91,6 +127,10 @@ pub trait PanelProvider: Send {
     /// Disconnect from the provider (no-op for local)
     fn disconnect(&mut self);
 
+    /// Forget a connection that failed, without saying goodbye over it, so
+    /// the next call connects again. Default: nothing to forget.
+    fn reset_connection(&mut self) {}
+
     /// List directory contents
     fn list_directory(&mut self, path: &str) -> ProviderResult<Vec<FileEntry>>;
 
@@ -100,6 +140,38 @@ pub trait PanelProvider: Send {
     /// Write file contents
     fn write_file(&mut self, path: &str, data: &[u8]) -> ProviderResult<()>;
 
+    /// Open a file for chunked reading.
+    /// Default falls back to `read_file` and serves the contents from memory.
+    fn open_read(&mut self, path: &str) -> ProviderResult<Box<dyn Read + Send>> {
+        let data = self.read_file(path)?;
+        Ok(Box::new(std::io::Cursor::new(data)))
+    }
+
+    /// Open a file for chunked writing. The data is committed by `ProviderWriter::finish`.
+    /// Default buffers in memory and falls back to `write_file` on finish.
+    fn open_write(&mut self, path: &str) -> ProviderResult<Box<dyn ProviderWriter + '_>> {
+        Ok(Box::new(BufferedWriter::new(self, path)))
+    }
+
+    /// Open a file for chunked reading from `offset` on, to resume an
+    /// interrupted transfer. Default reads and drops the bytes before it.
+    fn open_read_at(&mut self, path: &str, offset: u64) -> ProviderResult<Box<dyn Read + Send>> {
+        let mut reader = self.open_read(path)?;
+        let skipped = std::io::copy(&mut (&mut reader).take(offset), &mut std::io::sink())?;
+        if skipped < offset {
+            return Err(ProviderError::Other(format!("{} is shorter than {} bytes", path, offset)));
+        }
+        Ok(reader)
+    }
+
+    /// Open a file for chunked writing, keeping its first `offset` bytes so
+    /// an interrupted transfer can carry on. Returns the offset writing
+    /// starts from, 0 if the file started over. Default always starts over.
+    #[allow(unused_variables)]
+    fn open_write_at(&mut self, path: &str, offset: u64) -> ProviderResult<(Box<dyn ProviderWriter + '_>, u64)> {
+        Ok((self.open_write(path)?, 0))
+    }
+
     /// Delete a file or empty directory
     fn delete(&mut self, path: &str) -> ProviderResult<()>;
 
@@ -128,6 +200,12 @@ pub trait PanelProvider: Send {
         Ok(())
     }
 
+    /// Commit edits the provider has held back. Called at the end of every
+    /// operation that changed something; a no-op by default.
+    fn flush(&mut self) -> ProviderResult<()> {
+        Ok(())
+    }
+
     /// Get free space at path (if available)
     fn get_free_space(&self, path: &str) -> Option<u64>;
 
@@ -167,6 +245,64 @@ pub trait PanelProvider: Send {
     fn set_password(&mut self, password: &str) -> ProviderResult<()> {
         Ok(())
     }
+
+    /// Whether an optional operation is available, so the UI can refuse it
+    /// up front instead of failing halfway through. Default: everything.
+    #[allow(unused_variables)]
+    fn supports(&self, capability: Capability) -> bool {
+        true
+    }
+}
+
+/// Writable handle returned by `PanelProvider::open_write`
+///
+/// Writes are only guaranteed to land once `finish` succeeds; dropping the
+/// handle early may leave a partial or missing file.
+pub trait ProviderWriter: Write {
+    /// Flush any pending data and commit the file
+    fn finish(self: Box<Self>) -> ProviderResult<()>;
+}
+
+impl ProviderWriter for std::fs::File {
+    fn finish(mut self: Box<Self>) -> ProviderResult<()> {
+        self.flush()?;
+        Ok(())
+    }
+}
+
+/// In-memory writer used by the default `open_write`
+struct BufferedWriter<'a, P: PanelProvider + ?Sized> {
+    provider: &'a mut P,
+    path: String,
+    buffer: Vec<u8>,
+}
+
+impl<'a, P: PanelProvider + ?Sized> BufferedWriter<'a, P> {
+    fn new(provider: &'a mut P, path: &str) -> Self {
+        Self {
+            provider,
+            path: path.to_string(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<P: PanelProvider + ?Sized> Write for BufferedWriter<'_, P> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<P: PanelProvider + ?Sized> ProviderWriter for BufferedWriter<'_, P> {
+    fn finish(self: Box<Self>) -> ProviderResult<()> {
+        let this = *self;
+        this.provider.write_file(&this.path, &this.buffer)
+    }
 }
 
 /// Source entry for the panel source selector
@@ -193,6 +329,8 @@ pub enum PanelSource {
         /// Name used in config (for edit/delete)
         connection_name: String,
     },
+    /// The freedesktop.org trash, opened in the trash browser
+    Trash,
     /// Option to create a new connection (built-in)
     NewConnection {
         provider_type: ProviderType,
@@ -221,6 +359,7 @@ impl PanelSource {
                 }
             }
             PanelSource::QuickAccess { name, .. } => name.clone(),
+            PanelSource::Trash => "Trash".to_string(),
             PanelSource::Provider { info, .. } => info.name.clone(),
             PanelSource::NewConnection { provider_type } => {
                 match provider_type {
@@ -242,6 +381,7 @@ impl PanelSource {
         match self {
             PanelSource::Drive { .. } => '💾',
             PanelSource::QuickAccess { .. } => '📁',
+            PanelSource::Trash => '🗑',
             PanelSource::Provider { info, .. } => info.icon.unwrap_or('🌐'),
             PanelSource::NewConnection { .. } => '+',
             PanelSource::NewPluginConnection { icon, .. } => icon.unwrap_or('+'),
@@ -265,6 +405,7 @@ pub fn get_panel_sources(
     plugin_connections: &[crate::config::SavedPluginConnection],
     favorites: &[crate::config::FavoritePath],
     provider_plugins: &[ProviderPluginSummary],
+    ssh_config_hosts: &[String],
 ) -> Vec<PanelSource> {
     let mut sources = Vec::new();
 
@@ -306,6 +447,9 @@ pub fn get_panel_sources(
         });
     }
 
+    #[cfg(not(windows))]
+    sources.push(PanelSource::Trash);
+
     // Add saved SCP connections
     for conn in saved_connections {
         let uri = format!(
@@ -338,6 +482,24 @@ pub fn get_panel_sources(
         });
     }
 
+    // Add host aliases from ~/.ssh/config that aren't already saved connections
+    for alias in ssh_config_hosts {
+        if saved_connections.iter().any(|c| &c.host == alias || &c.name == alias) {
+            continue;
+        }
+
+        sources.push(PanelSource::Provider {
+            info: ProviderInfo {
+                name: format!("SSH - {}", alias),
+                description: format!("Host {} from ~/.ssh/config", alias),
+                provider_type: ProviderType::Scp,
+                icon: Some('🔒'),
+            },
+            connection_string: format!("scp://{}", alias),
+            connection_name: alias.clone(),
+        });
+    }
+
     // Add saved plugin connections (FTP, WebDAV, S3, etc. — driven by loaded plugins)
     for conn in plugin_connections {
         // Only show if the plugin for this scheme is loaded
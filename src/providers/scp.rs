@@ -2,12 +2,14 @@
 //!
 //! Uses SSH2 protocol for secure file transfer.
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::net::TcpStream;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 
 use crate::fs::FileEntry;
-use super::{PanelProvider, ProviderError, ProviderInfo, ProviderResult, ProviderType};
+use super::{Capability, PanelProvider, ProviderError, ProviderInfo, ProviderResult, ProviderType, ProviderWriter};
+use super::ssh_config::{SshConfig, parse_proxy_jump};
 
 /// Connection information for SCP
 #[derive(Debug, Clone)]
@@ -22,6 +24,31 @@ pub struct ScpConnectionInfo {
     pub initial_path: Option<String>,
     /// Authentication method
     pub auth: ScpAuth,
+    /// What to do with a host key that isn't in known_hosts
+    pub host_key_trust: HostKeyTrust,
+    /// Host keys trusted earlier in this session ("host:port" -> fingerprint)
+    pub session_host_keys: HashMap<String, String>,
+    /// Real address to connect to (ssh_config HostName); `host` may be an alias
+    pub host_name: Option<String>,
+    /// Key files tried after the SSH agent (ssh_config IdentityFile)
+    pub identity_files: Vec<PathBuf>,
+    /// Jump hosts to tunnel through (ssh_config ProxyJump)
+    pub proxy_jump: Option<String>,
+}
+
+/// User decision about a host key that is not yet in known_hosts.
+///
+/// The fingerprint is the one shown to the user; if the server presents a
+/// different key on reconnect, it is treated as unknown again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum HostKeyTrust {
+    /// Only accept keys already listed in known_hosts
+    #[default]
+    Verify,
+    /// Accept this fingerprint for the current connection only
+    AcceptOnce(String),
+    /// Accept this fingerprint and append it to known_hosts
+    AcceptAndSave(String),
 }
 
 /// Authentication method for SCP
@@ -45,37 +72,34 @@ pub enum ScpAuth {
 impl ScpConnectionInfo {
     /// Create a new connection info with password auth
     pub fn with_password(user: String, host: String, password: String) -> Self {
-        Self {
-            user,
-            host,
-            port: 22,
-            initial_path: None,
-            auth: ScpAuth::Password(password),
-        }
+        Self::with_auth(user, host, ScpAuth::Password(password))
     }
 
     /// Create a new connection info with key auth
     pub fn with_key(user: String, host: String, key_path: PathBuf) -> Self {
-        Self {
-            user,
-            host,
-            port: 22,
-            initial_path: None,
-            auth: ScpAuth::Key {
-                private_key: key_path,
-                passphrase: None,
-            },
-        }
+        Self::with_auth(user, host, ScpAuth::Key {
+            private_key: key_path,
+            passphrase: None,
+        })
     }
 
     /// Create a new connection info with SSH agent auth
     pub fn with_agent(user: String, host: String) -> Self {
+        Self::with_auth(user, host, ScpAuth::Agent)
+    }
+
+    fn with_auth(user: String, host: String, auth: ScpAuth) -> Self {
         Self {
             user,
             host,
             port: 22,
             initial_path: None,
-            auth: ScpAuth::Agent,
+            auth,
+            host_key_trust: HostKeyTrust::Verify,
+            session_host_keys: HashMap::new(),
+            host_name: None,
+            identity_files: Vec::new(),
+            proxy_jump: None,
         }
     }
 
@@ -91,6 +115,33 @@ impl ScpConnectionInfo {
         self
     }
 
+    /// Set how an unknown host key should be handled
+    pub fn trust_host_key(mut self, trust: HostKeyTrust) -> Self {
+        self.host_key_trust = trust;
+        self
+    }
+
+    /// Apply ssh_config settings for `host`. A user or port given
+    /// explicitly by the caller (`Some`) overrides the config.
+    pub fn apply_ssh_config(mut self, config: &SshConfig, user: Option<&str>, port: Option<u16>) -> Self {
+        let resolved = config.resolve(&self.host);
+        if let Some(user) = user.or(resolved.user.as_deref()) {
+            self.user = user.to_string();
+        }
+        if let Some(port) = port.or(resolved.port) {
+            self.port = port;
+        }
+        self.host_name = resolved.host_name;
+        self.identity_files = resolved.identity_files;
+        self.proxy_jump = resolved.proxy_jump;
+        self
+    }
+
+    /// Address to actually connect to (HostName if the host is an alias)
+    pub fn connect_host(&self) -> &str {
+        self.host_name.as_deref().unwrap_or(&self.host)
+    }
+
     /// Get display name for this connection
     pub fn display_name(&self) -> String {
         if self.port != 22 {
@@ -114,8 +165,15 @@ impl ScpConnectionInfo {
         }
     }
 
-    /// Parse from URI format (scp://user@host:port/path)
+    /// Parse from URI format (scp://[user@]host[:port]/path).
+    /// The host may be an ~/.ssh/config alias; its settings are applied,
+    /// with user and port from the URI taking precedence.
     pub fn from_uri(uri: &str) -> Option<Self> {
+        Self::from_uri_with_config(uri, &SshConfig::load())
+    }
+
+    /// Like `from_uri`, using an already loaded ssh_config
+    pub fn from_uri_with_config(uri: &str, config: &SshConfig) -> Option<Self> {
         let uri = uri.strip_prefix("scp://")?;
 
         // Split user@host:port/path
@@ -125,25 +183,35 @@ impl ScpConnectionInfo {
             (uri, None)
         };
 
-        let (user, host_port) = user_host.split_once('@')?;
+        let (user, host_port) = match user_host.split_once('@') {
+            Some((u, hp)) => (Some(u), hp),
+            None => (None, user_host),
+        };
 
         let (host, port) = if let Some((h, p)) = host_port.split_once(':') {
-            (h.to_string(), p.parse().ok()?)
+            (h.to_string(), Some(p.parse().ok()?))
         } else {
-            (host_port.to_string(), 22)
+            (host_port.to_string(), None)
         };
+        if host.is_empty() {
+            return None;
+        }
 
-        Some(Self {
-            user: user.to_string(),
-            host,
-            port,
-            initial_path: path.map(|s| s.to_string()),
-            // Auth will need to be set separately
-            auth: ScpAuth::Agent,
-        })
+        // Auth will need to be set separately
+        let mut info = Self::with_agent(local_username(), host)
+            .apply_ssh_config(config, user, port);
+        info.initial_path = path.map(|s| s.to_string());
+        Some(info)
     }
 }
 
+/// Local login name, used when neither the URI nor ssh_config names a user
+fn local_username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "root".to_string())
+}
+
 /// SCP/SFTP provider using ssh2
 pub struct ScpProvider {
     info: ProviderInfo,
@@ -179,6 +247,135 @@ impl ScpProvider {
         })
     }
 
+    /// Run the SSH handshake over `transport` and verify the host key
+    fn start_session(&self, transport: Transport, host: &str, port: u16) -> ProviderResult<ssh2::Session> {
+        let mut session = ssh2::Session::new()
+            .map_err(|e| ProviderError::Connection(format!("Failed to create session: {}", e)))?;
+
+        // Ask for a key of a type known_hosts already has for this host,
+        // so a server with several host keys isn't taken for a new one
+        let known_hosts = known_hosts_path().map(|path| read_known_hosts(&path)).unwrap_or_default();
+        prefer_known_host_key_types(&session, &known_hosts.lines, host, port);
+
+        transport.attach(&mut session);
+        session.handshake()
+            .map_err(|e| ProviderError::Connection(format!("SSH handshake with {} failed: {}", host, e)))?;
+
+        // Refuse to send credentials to a host we can't vouch for
+        self.verify_host_key(&session, &known_hosts, host, port)?;
+        Ok(session)
+    }
+
+    /// Connect through each ProxyJump hop in turn and return a stream that
+    /// is forwarded to `host:port` by the last hop.
+    fn open_jump_tunnel(&self, proxy_jump: &str, host: &str, port: u16) -> ProviderResult<Transport> {
+        let config = SshConfig::load();
+
+        // (user, address, port, identity files) for each hop
+        let hops: Vec<(String, String, u16, Vec<PathBuf>)> = parse_proxy_jump(proxy_jump)
+            .into_iter()
+            .map(|hop| {
+                let resolved = config.resolve(&hop.host);
+                let user = hop.user.or(resolved.user).unwrap_or_else(local_username);
+                let address = resolved.host_name.unwrap_or(hop.host);
+                let hop_port = hop.port.or(resolved.port).unwrap_or(22);
+                (user, address, hop_port, resolved.identity_files)
+            })
+            .collect();
+
+        let mut transport: Option<Transport> = None;
+        for (i, (user, address, hop_port, identity_files)) in hops.iter().enumerate() {
+            let stream = match transport.take() {
+                Some(stream) => stream,
+                None => Transport::Tcp(tcp_connect(address, *hop_port)?),
+            };
+            let session = self.start_session(stream, address, *hop_port)?;
+            authenticate(&session, user, &ScpAuth::Agent, identity_files).map_err(|e| match e {
+                ProviderError::Auth(msg) => ProviderError::Auth(format!("Jump host {}: {}", address, msg)),
+                other => other,
+            })?;
+
+            let (next_host, next_port) = match hops.get(i + 1) {
+                Some((_, next_address, next_port, _)) => (next_address.as_str(), *next_port),
+                None => (host, port),
+            };
+            let channel = session.channel_direct_tcpip(next_host, next_port, None)
+                .map_err(|e| ProviderError::Connection(format!(
+                    "Jump host {} could not reach {}:{}: {}", address, next_host, next_port, e
+                )))?;
+            transport = Some(spawn_tunnel(session, channel)?);
+        }
+
+        transport.ok_or_else(|| ProviderError::Connection(format!("Invalid ProxyJump '{}'", proxy_jump)))
+    }
+
+    /// Check the server's host key against the entries of ~/.ssh/known_hosts
+    /// with the same key type; keys of other types say nothing about it.
+    ///
+    /// A known, matching key passes. A mismatch, or a key listed as
+    /// @revoked, is always rejected. An unknown key is only accepted if the user already approved this exact
+    /// fingerprint (this session or via `HostKeyTrust`); otherwise
+    /// `HostKeyUnknown` is returned so the UI can ask. Jump hosts are
+    /// checked the same way as the target.
+    fn verify_host_key(
+        &self,
+        session: &ssh2::Session,
+        known_hosts: &KnownHostsFile,
+        host: &str,
+        port: u16,
+    ) -> ProviderResult<()> {
+        let (key, key_type) = session.host_key()
+            .ok_or_else(|| ProviderError::Connection("Server did not provide a host key".to_string()))?;
+        let key_type_name = host_key_type_name(key_type);
+        let fingerprint = session.host_key_hash(ssh2::HashType::Sha256)
+            .map(|hash| format!("SHA256:{}", base64_encode(hash, false)))
+            .unwrap_or_default();
+
+        if known_hosts.is_revoked(key) {
+            return Err(ProviderError::HostKeyRevoked {
+                host: known_hosts_entry_name(host, port),
+                key_type: key_type_name.to_string(),
+                fingerprint,
+            });
+        }
+
+        let known_hosts_file = known_hosts_path();
+        let known = known_hosts_of_type(session, &known_hosts.lines, key_type_name)
+            .map_err(|e| ProviderError::Connection(format!("Failed to init known_hosts: {}", e)))?;
+
+        match known.check_port(host, port, key) {
+            ssh2::CheckResult::Match => Ok(()),
+            ssh2::CheckResult::Mismatch => Err(ProviderError::HostKeyMismatch {
+                host: known_hosts_entry_name(host, port),
+                key_type: key_type_name.to_string(),
+                fingerprint,
+            }),
+            ssh2::CheckResult::NotFound | ssh2::CheckResult::Failure => {
+                if self.connection.session_host_keys.get(&format!("{}:{}", host, port)) == Some(&fingerprint) {
+                    return Ok(());
+                }
+                match &self.connection.host_key_trust {
+                    HostKeyTrust::AcceptOnce(fp) if *fp == fingerprint => Ok(()),
+                    HostKeyTrust::AcceptAndSave(fp) if *fp == fingerprint => {
+                        let path = known_hosts_file.ok_or_else(|| {
+                            ProviderError::Other("Cannot locate home directory for known_hosts".to_string())
+                        })?;
+                        append_known_host(&path, host, port, key_type_name, key)
+                            .map_err(|e| ProviderError::Other(
+                                format!("Failed to update {}: {}", path.display(), e)
+                            ))
+                    }
+                    _ => Err(ProviderError::HostKeyUnknown {
+                        host: host.to_string(),
+                        port,
+                        key_type: key_type_name.to_string(),
+                        fingerprint,
+                    }),
+                }
+            }
+        }
+    }
+
     /// Convert ssh2 error to provider error
     fn map_ssh_error(e: ssh2::Error) -> ProviderError {
         match e.code() {
@@ -225,60 +422,20 @@ impl PanelProvider for ScpProvider {
     }
 
     fn connect(&mut self) -> ProviderResult<()> {
-        // Connect TCP
-        let addr = format!("{}:{}", self.connection.host, self.connection.port);
-        let tcp = TcpStream::connect(&addr)
-            .map_err(|e| ProviderError::Connection(format!("Failed to connect to {}: {}", addr, e)))?;
+        let host = self.connection.connect_host().to_string();
+        let port = self.connection.port;
 
-        // Create SSH session
-        let mut session = ssh2::Session::new()
-            .map_err(|e| ProviderError::Connection(format!("Failed to create session: {}", e)))?;
-
-        session.set_tcp_stream(tcp);
-        session.handshake()
-            .map_err(|e| ProviderError::Connection(format!("SSH handshake failed: {}", e)))?;
+        // Reach the target directly or through the ProxyJump chain
+        let transport = match self.connection.proxy_jump.clone() {
+            Some(proxy_jump) => self.open_jump_tunnel(&proxy_jump, &host, port)?,
+            None => Transport::Tcp(tcp_connect(&host, port)?),
+        };
+        let session = self.start_session(transport, &host, port)?;
 
         // Enable keepalive to prevent connection timeout (every 10 seconds)
         session.set_keepalive(true, 10);
 
-        // Authenticate
-        match &self.connection.auth {
-            ScpAuth::Password(password) => {
-                session.userauth_password(&self.connection.user, password)
-                    .map_err(|e| ProviderError::Auth(format!("Password auth failed: {}", e)))?;
-            }
-            ScpAuth::Key { private_key, passphrase } => {
-                session.userauth_pubkey_file(
-                    &self.connection.user,
-                    None,
-                    private_key,
-                    passphrase.as_deref(),
-                ).map_err(|e| ProviderError::Auth(format!("Key auth failed: {}", e)))?;
-            }
-            ScpAuth::Agent => {
-                let mut agent = session.agent()
-                    .map_err(|e| ProviderError::Auth(format!("Failed to connect to SSH agent: {}", e)))?;
-                agent.connect()
-                    .map_err(|e| ProviderError::Auth(format!("Failed to connect to SSH agent: {}", e)))?;
-                agent.list_identities()
-                    .map_err(|e| ProviderError::Auth(format!("Failed to list agent identities: {}", e)))?;
-
-                let mut authenticated = false;
-                for identity in agent.identities().unwrap_or_default() {
-                    if agent.userauth(&self.connection.user, &identity).is_ok() {
-                        authenticated = true;
-                        break;
-                    }
-                }
-                if !authenticated {
-                    return Err(ProviderError::Auth("No valid identity found in SSH agent".to_string()));
-                }
-            }
-        }
-
-        if !session.authenticated() {
-            return Err(ProviderError::Auth("Authentication failed".to_string()));
-        }
+        authenticate(&session, &self.connection.user, &self.connection.auth, &self.connection.identity_files)?;
 
         // Open SFTP session
         let sftp = session.sftp()
@@ -297,6 +454,11 @@ impl PanelProvider for ScpProvider {
         }
     }
 
+    fn reset_connection(&mut self) {
+        self.sftp = None;
+        self.session = None;
+    }
+
     fn list_directory(&mut self, path: &str) -> ProviderResult<Vec<FileEntry>> {
         // Handle empty path as root
         let path = if path.is_empty() { "/" } else { path };
@@ -389,6 +551,49 @@ impl PanelProvider for ScpProvider {
         Ok(())
     }
 
+    fn open_read(&mut self, path: &str) -> ProviderResult<Box<dyn Read + Send>> {
+        let sftp = self.sftp()?;
+        let file = sftp.open(std::path::Path::new(path))
+            .map_err(Self::map_ssh_error)?;
+        Ok(Box::new(file))
+    }
+
+    fn open_write(&mut self, path: &str) -> ProviderResult<Box<dyn ProviderWriter + '_>> {
+        let sftp = self.sftp()?;
+        let file = sftp.create(std::path::Path::new(path))
+            .map_err(Self::map_ssh_error)?;
+        Ok(Box::new(file))
+    }
+
+    fn open_read_at(&mut self, path: &str, offset: u64) -> ProviderResult<Box<dyn Read + Send>> {
+        let sftp = self.sftp()?;
+        let mut file = sftp.open(std::path::Path::new(path))
+            .map_err(Self::map_ssh_error)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(Box::new(file))
+    }
+
+    fn open_write_at(&mut self, path: &str, offset: u64) -> ProviderResult<(Box<dyn ProviderWriter + '_>, u64)> {
+        let sftp = self.sftp()?;
+        let path = std::path::Path::new(path);
+        // Unlike `create`, opening without TRUNCATE keeps what's there
+        let mut file = sftp.open_mode(path, ssh2::OpenFlags::WRITE | ssh2::OpenFlags::CREATE, 0o644, ssh2::OpenType::File)
+            .map_err(Self::map_ssh_error)?;
+        let size = file.stat().map_err(Self::map_ssh_error)?.size.unwrap_or(0);
+        if size < offset {
+            let file = sftp.create(path).map_err(Self::map_ssh_error)?;
+            return Ok((Box::new(file), 0));
+        }
+        // Drop whatever lies past the offset, like a local resume does, so
+        // a longer stale file doesn't keep its old tail
+        if size > offset {
+            let stat = ssh2::FileStat { size: Some(offset), uid: None, gid: None, perm: None, atime: None, mtime: None };
+            file.setstat(stat).map_err(Self::map_ssh_error)?;
+        }
+        file.seek(SeekFrom::Start(offset))?;
+        Ok((Box::new(file), offset))
+    }
+
     fn delete(&mut self, path: &str) -> ProviderResult<()> {
         let sftp = self.sftp()?;
         let path_obj = std::path::Path::new(path);
@@ -489,6 +694,10 @@ impl PanelProvider for ScpProvider {
         None
     }
 
+    fn supports(&self, capability: Capability) -> bool {
+        !matches!(capability, Capability::FreeSpace | Capability::SetPassword)
+    }
+
     fn is_local(&self) -> bool {
         false
     }
@@ -537,8 +746,457 @@ impl PanelProvider for ScpProvider {
     }
 }
 
+/// Stream an SSH session runs over: a direct TCP connection, or the local
+/// end of a tunnel forwarded by a jump host
+enum Transport {
+    Tcp(TcpStream),
+    Tunnel(TunnelSocket),
+}
+
+#[cfg(unix)]
+type TunnelSocket = std::os::unix::net::UnixStream;
+#[cfg(not(unix))]
+type TunnelSocket = TcpStream;
+
+impl Transport {
+    fn attach(self, session: &mut ssh2::Session) {
+        match self {
+            Transport::Tcp(stream) => session.set_tcp_stream(stream),
+            Transport::Tunnel(stream) => session.set_tcp_stream(stream),
+        }
+    }
+}
+
+fn tcp_connect(host: &str, port: u16) -> ProviderResult<TcpStream> {
+    let addr = format!("{}:{}", host, port);
+    TcpStream::connect(&addr)
+        .map_err(|e| ProviderError::Connection(format!("Failed to connect to {}: {}", addr, e)))
+}
+
+/// Authenticate `session` as `user`. Agent auth falls back to the given
+/// identity files (unencrypted keys only) when the agent has no usable key.
+fn authenticate(session: &ssh2::Session, user: &str, auth: &ScpAuth, identity_files: &[PathBuf]) -> ProviderResult<()> {
+    match auth {
+        ScpAuth::Password(password) => {
+            session.userauth_password(user, password)
+                .map_err(|e| ProviderError::Auth(format!("Password auth failed: {}", e)))?;
+        }
+        ScpAuth::Key { private_key, passphrase } => {
+            session.userauth_pubkey_file(
+                user,
+                None,
+                private_key,
+                passphrase.as_deref(),
+            ).map_err(|e| ProviderError::Auth(format!("Key auth failed: {}", e)))?;
+        }
+        ScpAuth::Agent => {
+            if let Err(agent_err) = authenticate_with_agent(session, user) {
+                let identity_ok = identity_files.iter()
+                    .filter(|key| key.exists())
+                    .any(|key| session.userauth_pubkey_file(user, None, key, None).is_ok());
+                if !identity_ok {
+                    return Err(agent_err);
+                }
+            }
+        }
+    }
+
+    if !session.authenticated() {
+        return Err(ProviderError::Auth("Authentication failed".to_string()));
+    }
+    Ok(())
+}
+
+fn authenticate_with_agent(session: &ssh2::Session, user: &str) -> ProviderResult<()> {
+    let mut agent = session.agent()
+        .map_err(|e| ProviderError::Auth(format!("Failed to connect to SSH agent: {}", e)))?;
+    agent.connect()
+        .map_err(|e| ProviderError::Auth(format!("Failed to connect to SSH agent: {}", e)))?;
+    agent.list_identities()
+        .map_err(|e| ProviderError::Auth(format!("Failed to list agent identities: {}", e)))?;
+
+    for identity in agent.identities().unwrap_or_default() {
+        if agent.userauth(user, &identity).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(ProviderError::Auth("No valid identity found in SSH agent".to_string()))
+}
+
+/// Forward a jump host channel to a local socket pair on a background
+/// thread. The returned end is handed to the next session; the thread ends
+/// when either side closes.
+fn spawn_tunnel(session: ssh2::Session, channel: ssh2::Channel) -> ProviderResult<Transport> {
+    let (outer, inner) = tunnel_socket_pair()
+        .map_err(|e| ProviderError::Connection(format!("Failed to create tunnel: {}", e)))?;
+    std::thread::spawn(move || pump_tunnel(session, channel, inner));
+    Ok(Transport::Tunnel(outer))
+}
+
+#[cfg(unix)]
+fn tunnel_socket_pair() -> std::io::Result<(TunnelSocket, TunnelSocket)> {
+    std::os::unix::net::UnixStream::pair()
+}
+
+#[cfg(not(unix))]
+fn tunnel_socket_pair() -> std::io::Result<(TunnelSocket, TunnelSocket)> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let outer = TcpStream::connect(listener.local_addr()?)?;
+    let (inner, peer) = listener.accept()?;
+    // Make sure nobody else grabbed the port before we did
+    if peer != outer.local_addr()? {
+        return Err(std::io::Error::other("unexpected tunnel peer"));
+    }
+    Ok((outer, inner))
+}
+
+fn pump_tunnel(session: ssh2::Session, mut channel: ssh2::Channel, mut socket: TunnelSocket) {
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    session.set_blocking(false);
+    if socket.set_nonblocking(true).is_err() {
+        return;
+    }
+
+    let mut buf = vec![0u8; 32 * 1024];
+    loop {
+        let mut busy = false;
+
+        match socket.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                if write_all_nonblocking(&mut channel, &buf[..n]).is_err() {
+                    break;
+                }
+                busy = true;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        match channel.read(&mut buf) {
+            Ok(0) => {
+                if channel.eof() {
+                    break;
+                }
+            }
+            Ok(n) => {
+                if write_all_nonblocking(&mut socket, &buf[..n]).is_err() {
+                    break;
+                }
+                busy = true;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        if !busy {
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    }
+
+    let _ = channel.close();
+    let _ = session.disconnect(None, "Tunnel closed", None);
+}
+
+/// `write_all` for a non-blocking writer: retry on WouldBlock
+fn write_all_nonblocking(writer: &mut dyn Write, mut data: &[u8]) -> std::io::Result<()> {
+    while !data.is_empty() {
+        match writer.write(data) {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(n) => data = &data[n..],
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Path to the user's OpenSSH known_hosts file
+fn known_hosts_path() -> Option<PathBuf> {
+    #[cfg(unix)]
+    let home = std::env::var_os("HOME");
+    #[cfg(not(unix))]
+    let home = std::env::var_os("USERPROFILE");
+    home.map(|h| PathBuf::from(h).join(".ssh").join("known_hosts"))
+}
+
+/// Key types as written in known_hosts, each with the host key methods
+/// that negotiate it, most preferred first
+const HOST_KEY_METHODS: &[(&str, &[&str])] = &[
+    ("ssh-ed25519", &["ssh-ed25519"]),
+    ("ecdsa-sha2-nistp256", &["ecdsa-sha2-nistp256"]),
+    ("ecdsa-sha2-nistp384", &["ecdsa-sha2-nistp384"]),
+    ("ecdsa-sha2-nistp521", &["ecdsa-sha2-nistp521"]),
+    ("ssh-rsa", &["rsa-sha2-512", "rsa-sha2-256", "ssh-rsa"]),
+    ("ssh-dss", &["ssh-dss"]),
+];
+
+/// What a known_hosts file says about host keys
+#[derive(Default)]
+struct KnownHostsFile {
+    /// Host key lines, each with its key type
+    lines: Vec<(String, String)>,
+    /// Base64 keys of the @revoked lines, refused for every host
+    revoked: Vec<String>,
+}
+
+impl KnownHostsFile {
+    /// Whether the raw host `key` is revoked
+    fn is_revoked(&self, key: &[u8]) -> bool {
+        let key = base64_encode(key, true);
+        self.revoked.contains(&key)
+    }
+}
+
+/// Read the host key lines of a known_hosts file. Comments and
+/// @cert-authority lines are left out; an unreadable file has no lines, so
+/// its hosts come up as unknown.
+fn read_known_hosts(path: &Path) -> KnownHostsFile {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return KnownHostsFile::default();
+    };
+    let mut file = KnownHostsFile::default();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        match line.starts_with('@').then(|| fields.next()).flatten() {
+            // "@revoked hosts key-type key"
+            Some("@revoked") => {
+                if let Some(key) = fields.nth(2) {
+                    file.revoked.push(key.to_string());
+                }
+            }
+            Some(_) => {}
+            None => {
+                if let Some(key_type) = fields.nth(1) {
+                    file.lines.push((key_type.to_string(), line.to_string()));
+                }
+            }
+        }
+    }
+    file
+}
+
+/// libssh2's view of the `lines` holding a `key_type` key. Lines it can't
+/// parse are skipped.
+fn known_hosts_of_type(
+    session: &ssh2::Session,
+    lines: &[(String, String)],
+    key_type: &str,
+) -> Result<ssh2::KnownHosts, ssh2::Error> {
+    let mut known = session.known_hosts()?;
+    for (_, line) in lines.iter().filter(|(line_type, _)| line_type == key_type) {
+        let _ = known.read_str(line, ssh2::KnownHostFileKind::OpenSSH);
+    }
+    Ok(known)
+}
+
+/// Key types known_hosts has for `host`
+fn known_host_types(session: &ssh2::Session, lines: &[(String, String)], host: &str, port: u16) -> Vec<&'static str> {
+    // Any listed key differs from this one, so a listed host is a mismatch
+    const PROBE: &[u8] = &[0];
+    HOST_KEY_METHODS
+        .iter()
+        .map(|(key_type, _)| *key_type)
+        .filter(|key_type| {
+            known_hosts_of_type(session, lines, key_type)
+                .is_ok_and(|known| matches!(known.check_port(host, port, PROBE), ssh2::CheckResult::Mismatch))
+        })
+        .collect()
+}
+
+/// Put the host key methods for the key types known for `host` ahead of
+/// the others. Must be called before the handshake.
+fn prefer_known_host_key_types(session: &ssh2::Session, lines: &[(String, String)], host: &str, port: u16) {
+    let known = known_host_types(session, lines, host, port);
+    if known.is_empty() {
+        return;
+    }
+    let Ok(supported) = session.supported_algs(ssh2::MethodType::HostKey) else {
+        return;
+    };
+    let mut prefs: Vec<&str> = HOST_KEY_METHODS
+        .iter()
+        .filter(|(key_type, _)| known.contains(key_type))
+        .flat_map(|(_, methods)| methods.iter().copied())
+        .filter(|method| supported.contains(method))
+        .collect();
+    for method in supported {
+        if !prefs.contains(&method) {
+            prefs.push(method);
+        }
+    }
+    let _ = session.method_pref(ssh2::MethodType::HostKey, &prefs.join(","));
+}
+
+/// OpenSSH name for a host key algorithm
+fn host_key_type_name(key_type: ssh2::HostKeyType) -> &'static str {
+    match key_type {
+        ssh2::HostKeyType::Rsa => "ssh-rsa",
+        ssh2::HostKeyType::Dss => "ssh-dss",
+        ssh2::HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        ssh2::HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        ssh2::HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        ssh2::HostKeyType::Ed25519 => "ssh-ed25519",
+        ssh2::HostKeyType::Unknown => "unknown",
+    }
+}
+
+/// Format the host part of a known_hosts line ("host" or "[host]:port")
+fn known_hosts_entry_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// Append a host key line to known_hosts.
+/// Appending (rather than rewriting the file through libssh2) keeps any
+/// comments or entries libssh2 doesn't understand intact.
+fn append_known_host(path: &Path, host: &str, port: u16, key_type: &str, key: &[u8]) -> std::io::Result<()> {
+    use std::fs::OpenOptions;
+
+    if let Some(dir) = path.parent()
+        && !dir.exists() {
+            std::fs::create_dir_all(dir)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+            }
+        }
+
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+
+    // Make sure we start on a fresh line even if the file lacks a trailing newline
+    let needs_newline = std::fs::read(path)
+        .map(|data| !data.is_empty() && !data.ends_with(b"\n"))
+        .unwrap_or(false);
+    let line = format!(
+        "{}{} {} {}\n",
+        if needs_newline { "\n" } else { "" },
+        known_hosts_entry_name(host, port),
+        key_type,
+        base64_encode(key, true),
+    );
+    file.write_all(line.as_bytes())
+}
+
+/// Standard base64 encoding (optionally without '=' padding, as used in
+/// OpenSSH fingerprints)
+fn base64_encode(data: &[u8], pad: bool) -> String {
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        out.push(CHARS[(n >> 18) as usize & 63] as char);
+        out.push(CHARS[(n >> 12) as usize & 63] as char);
+        if chunk.len() > 1 {
+            out.push(CHARS[(n >> 6) as usize & 63] as char);
+        } else if pad {
+            out.push('=');
+        }
+        if chunk.len() > 2 {
+            out.push(CHARS[n as usize & 63] as char);
+        } else if pad {
+            out.push('=');
+        }
+    }
+    out
+}
+
+impl ProviderWriter for ssh2::File {
+    fn finish(mut self: Box<Self>) -> ProviderResult<()> {
+        self.flush()?;
+        // Closing the handle makes the server report any deferred write error
+        self.close().map_err(ScpProvider::map_ssh_error)
+    }
+}
+
 impl Drop for ScpProvider {
     fn drop(&mut self) {
         self.disconnect();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    #[test]
+    fn test_base64_padding() {
+        assert_eq!(base64_encode(b"abc", true), "YWJj");
+        assert_eq!(base64_encode(b"ab", true), "YWI=");
+        assert_eq!(base64_encode(b"a", true), "YQ==");
+        assert_eq!(base64_encode(b"a", false), "YQ");
+    }
+
+    #[test]
+    fn test_known_hosts_by_key_type() {
+        let dir = ScratchDir::new("known-hosts");
+        let path = dir.join("known_hosts");
+        std::fs::write(&path, "\
+# comment
+example.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl
+broken.example.com ssh-rsa not*base64
+@revoked other.example.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl
+[port.example.com]:2222 ecdsa-sha2-nistp256 AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBEmKSENjQEezOmxkZMy7opKgwFB9nkt5YRrYMjNuG5N87uRgg6CLrbo5wAdT/y6v0mKV0U2w0WZ2YB/++Tpockg=
+").unwrap();
+        let file = read_known_hosts(&path);
+        let lines = file.lines;
+        assert_eq!(lines.len(), 3);
+
+        // The line libssh2 can't parse doesn't hide the others
+        let session = ssh2::Session::new().unwrap();
+        assert_eq!(known_host_types(&session, &lines, "example.com", 22), ["ssh-ed25519"]);
+        assert_eq!(known_host_types(&session, &lines, "port.example.com", 2222), ["ecdsa-sha2-nistp256"]);
+        assert!(known_host_types(&session, &lines, "other.example.com", 22).is_empty());
+
+        // A key of another type than the one listed is unknown, not a mismatch
+        let ecdsa = known_hosts_of_type(&session, &lines, "ecdsa-sha2-nistp256").unwrap();
+        assert!(matches!(ecdsa.check_port("example.com", 22, b"key"), ssh2::CheckResult::NotFound));
+        let ed25519 = known_hosts_of_type(&session, &lines, "ssh-ed25519").unwrap();
+        assert!(matches!(ed25519.check_port("example.com", 22, b"key"), ssh2::CheckResult::Mismatch));
+
+        assert!(read_known_hosts(&dir.join("missing")).lines.is_empty());
+    }
+
+    #[test]
+    fn test_known_hosts_revoked() {
+        let dir = ScratchDir::new("known-hosts-revoked");
+        let path = dir.join("known_hosts");
+        let (revoked, trusted) = (b"revoked key blob".as_slice(), b"trusted key blob".as_slice());
+        std::fs::write(&path, format!(
+            "@revoked * ssh-ed25519 {}\n@cert-authority *.example.com ssh-ed25519 {}\nexample.com ssh-ed25519 {}\n",
+            base64_encode(revoked, true), base64_encode(trusted, true), base64_encode(revoked, true),
+        )).unwrap();
+
+        // Refused even though a plain line lists the same key
+        let file = read_known_hosts(&path);
+        assert!(file.is_revoked(revoked));
+        assert!(!file.is_revoked(trusted));
+        assert_eq!(file.lines.len(), 1);
+    }
+
+    #[test]
+    fn test_known_hosts_entry_name() {
+        assert_eq!(known_hosts_entry_name("example.com", 22), "example.com");
+        assert_eq!(known_hosts_entry_name("example.com", 2222), "[example.com]:2222");
+    }
+}
@@ -0,0 +1,429 @@
+//! Minimal OpenSSH client config (`~/.ssh/config`) reader
+//!
+//! Supports the subset Bark needs to connect to an alias: `Host` patterns
+//! (with `*`, `?` and `!negation`), `HostName`, `User`, `Port`,
+//! `IdentityFile`, `ProxyJump` and `Include`. `Match` blocks are skipped.
+//! As in OpenSSH, the first value found for an option wins, except
+//! `IdentityFile`, which accumulates.
+
+use std::path::{Path, PathBuf};
+
+/// Maximum nesting depth for `Include` directives
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// One `Host` block with its options (keys lowercased)
+#[derive(Debug, Clone, Default)]
+struct HostBlock {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+/// Parsed ssh_config file
+#[derive(Debug, Clone, Default)]
+pub struct SshConfig {
+    blocks: Vec<HostBlock>,
+}
+
+/// Settings that apply to a given host alias
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SshHostConfig {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_files: Vec<PathBuf>,
+    pub proxy_jump: Option<String>,
+}
+
+/// A single hop parsed from a `ProxyJump` value (`[user@]host[:port]`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpSpec {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl SshConfig {
+    /// Load `~/.ssh/config`. A missing or unreadable file yields an empty config.
+    pub fn load() -> Self {
+        match Self::default_path() {
+            Some(path) => Self::load_from(&path),
+            None => Self::default(),
+        }
+    }
+
+    /// Load a config file (and its includes) from the given path
+    pub fn load_from(path: &Path) -> Self {
+        let mut config = Self::default();
+        if let Ok(text) = std::fs::read_to_string(path) {
+            config.parse_into(&text, 0);
+        }
+        config
+    }
+
+    /// Parse config text. `Include` paths are resolved against ~/.ssh.
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        config.parse_into(text, 0);
+        config
+    }
+
+    fn default_path() -> Option<PathBuf> {
+        ssh_dir().map(|dir| dir.join("config"))
+    }
+
+    fn parse_into(&mut self, text: &str, depth: usize) {
+        // Options before the first Host line apply to every host
+        let mut current = HostBlock {
+            patterns: vec!["*".to_string()],
+            options: Vec::new(),
+        };
+        let mut in_match = false;
+
+        for raw_line in text.lines() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = split_option(line) else {
+                continue;
+            };
+            let key = key.to_ascii_lowercase();
+
+            match key.as_str() {
+                "host" => {
+                    self.push_block(std::mem::take(&mut current));
+                    current.patterns = split_words(&value);
+                    in_match = false;
+                }
+                "match" => {
+                    self.push_block(std::mem::take(&mut current));
+                    in_match = true;
+                }
+                "include" if depth < MAX_INCLUDE_DEPTH => {
+                    // Included blocks are spliced in place; the current
+                    // block's remaining options continue after them
+                    let before = HostBlock {
+                        patterns: current.patterns.clone(),
+                        options: std::mem::take(&mut current.options),
+                    };
+                    self.push_block(before);
+                    for pattern in split_words(&value) {
+                        for file in expand_include(&pattern) {
+                            if let Ok(text) = std::fs::read_to_string(&file) {
+                                self.parse_into(&text, depth + 1);
+                            }
+                        }
+                    }
+                }
+                _ if in_match => {}
+                _ => current.options.push((key, unquote(&value))),
+            }
+        }
+        self.push_block(current);
+    }
+
+    fn push_block(&mut self, block: HostBlock) {
+        if !block.patterns.is_empty() && !block.options.is_empty() {
+            self.blocks.push(block);
+        }
+    }
+
+    /// Collect the settings that apply to `alias`
+    pub fn resolve(&self, alias: &str) -> SshHostConfig {
+        let mut result = SshHostConfig::default();
+
+        for block in &self.blocks {
+            if !host_matches(&block.patterns, alias) {
+                continue;
+            }
+            for (key, value) in &block.options {
+                match key.as_str() {
+                    "hostname" if result.host_name.is_none() => {
+                        result.host_name = Some(value.replace("%h", alias));
+                    }
+                    "user" if result.user.is_none() => {
+                        result.user = Some(value.clone());
+                    }
+                    "port" if result.port.is_none() => {
+                        result.port = value.parse().ok();
+                    }
+                    "identityfile" => {
+                        result.identity_files.push(PathBuf::from(expand_tilde(value)));
+                    }
+                    "proxyjump" if result.proxy_jump.is_none() => {
+                        result.proxy_jump = Some(value.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // %h in IdentityFile refers to the real host name
+        let host = result.host_name.clone().unwrap_or_else(|| alias.to_string());
+        for file in &mut result.identity_files {
+            let s = file.to_string_lossy();
+            if s.contains("%h") {
+                *file = PathBuf::from(s.replace("%h", &host));
+            }
+        }
+        if result.proxy_jump.as_deref().is_some_and(|j| j.eq_ignore_ascii_case("none")) {
+            result.proxy_jump = None;
+        }
+
+        result
+    }
+
+    /// Concrete host aliases (no wildcards or negations), in file order
+    pub fn host_aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for block in &self.blocks {
+            for pattern in &block.patterns {
+                let concrete = !pattern.contains(['*', '?']) && !pattern.starts_with('!');
+                if concrete && !aliases.contains(pattern) {
+                    aliases.push(pattern.clone());
+                }
+            }
+        }
+        aliases
+    }
+}
+
+/// Parse a ProxyJump value into hops (comma-separated `[user@]host[:port]`)
+pub fn parse_proxy_jump(value: &str) -> Vec<JumpSpec> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(|hop| {
+            let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+            let (user, host_port) = match hop.rsplit_once('@') {
+                Some((u, hp)) => (Some(u.to_string()), hp),
+                None => (None, hop),
+            };
+            let (host, port) = match host_port.rsplit_once(':') {
+                Some((h, p)) if p.parse::<u16>().is_ok() => (h.to_string(), p.parse().ok()),
+                _ => (host_port.to_string(), None),
+            };
+            JumpSpec { user, host, port }
+        })
+        .collect()
+}
+
+/// The user's ~/.ssh directory
+fn ssh_dir() -> Option<PathBuf> {
+    #[cfg(unix)]
+    let home = std::env::var_os("HOME");
+    #[cfg(not(unix))]
+    let home = std::env::var_os("USERPROFILE");
+    home.map(|h| PathBuf::from(h).join(".ssh"))
+}
+
+/// Split "Key value", "Key=value" or "Key = value"
+fn split_option(line: &str) -> Option<(&str, String)> {
+    let idx = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let key = &line[..idx];
+    let rest = line[idx..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    if key.is_empty() || rest.is_empty() {
+        return None;
+    }
+    Some((key, rest.to_string()))
+}
+
+/// Split a value into whitespace-separated words, honoring double quotes
+fn split_words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn expand_tilde(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(dir) = ssh_dir().and_then(|d| d.parent().map(Path::to_path_buf)) {
+            return dir.join(rest).to_string_lossy().into_owned();
+        }
+    path.to_string()
+}
+
+/// Resolve an Include pattern to files. Relative paths are relative to
+/// ~/.ssh; wildcards are supported in the final path component.
+fn expand_include(pattern: &str) -> Vec<PathBuf> {
+    let expanded = expand_tilde(pattern);
+    let path = Path::new(&expanded);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match ssh_dir() {
+            Some(dir) => dir.join(path),
+            None => return Vec::new(),
+        }
+    };
+
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| wildcard_match(&name, &e.file_name().to_string_lossy()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Check a host against a Host line's patterns. Any matching negated
+/// pattern rejects the block outright.
+fn host_matches(patterns: &[String], host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, host) {
+                return false;
+            }
+        } else if wildcard_match(pattern, host) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Glob match supporting `*` and `?` (case-insensitive, like OpenSSH hosts)
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+# Work servers
+Host web
+    HostName web.internal.example.com
+    User deploy
+    Port 2200
+    IdentityFile /keys/web_%h
+
+Host db-* !db-legacy
+    User postgres
+    ProxyJump bastion
+
+Host bastion
+    HostName=bastion.example.com
+    User "ops"
+
+Match host foo
+    User ignored
+
+Host *
+    User fallback
+    IdentityFile /keys/default
+"#;
+
+    #[test]
+    fn test_resolve_alias() {
+        let config = SshConfig::parse(SAMPLE);
+        let web = config.resolve("web");
+        assert_eq!(web.host_name.as_deref(), Some("web.internal.example.com"));
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(web.port, Some(2200));
+        assert_eq!(web.identity_files, vec![
+            PathBuf::from("/keys/web_web.internal.example.com"),
+            PathBuf::from("/keys/default"),
+        ]);
+    }
+
+    #[test]
+    fn test_patterns_and_negation() {
+        let config = SshConfig::parse(SAMPLE);
+        let db = config.resolve("db-main");
+        assert_eq!(db.user.as_deref(), Some("postgres"));
+        assert_eq!(db.proxy_jump.as_deref(), Some("bastion"));
+
+        let legacy = config.resolve("db-legacy");
+        assert_eq!(legacy.user.as_deref(), Some("fallback"));
+        assert_eq!(legacy.proxy_jump, None);
+
+        let bastion = config.resolve("bastion");
+        assert_eq!(bastion.host_name.as_deref(), Some("bastion.example.com"));
+        assert_eq!(bastion.user.as_deref(), Some("ops"));
+    }
+
+    #[test]
+    fn test_host_aliases() {
+        let config = SshConfig::parse(SAMPLE);
+        assert_eq!(config.host_aliases(), vec!["web".to_string(), "bastion".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_proxy_jump() {
+        let hops = parse_proxy_jump("alice@jump1:2222, jump2");
+        assert_eq!(hops, vec![
+            JumpSpec { user: Some("alice".into()), host: "jump1".into(), port: Some(2222) },
+            JumpSpec { user: None, host: "jump2".into(), port: None },
+        ]);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.example.com", "a.example.com"));
+        assert!(wildcard_match("web?", "WEB1"));
+        assert!(!wildcard_match("web?", "web12"));
+        assert!(wildcard_match("*", ""));
+    }
+}
@@ -1,18 +1,32 @@
 //! Application state
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use crate::async_io::{IoManager, IoResponse};
 use crate::config::Config;
 use crate::git::{self, GitStatus};
 use crate::persistent_shell::{PersistentShell, ShellMessage};
 use crate::plugins::{PluginManager, StatusContext, ViewerContext};
-use crate::providers::{PanelSource, ProviderType, ScpAuth, ScpConnectionInfo, get_panel_sources};
-use crate::ui::Theme;
+use crate::providers::{Capability, HostKeyTrust, PanelSource, ProviderType, ScpAuth, ScpConnectionInfo, get_panel_sources};
+use crate::ui::{FileViewer, Theme};
 use crate::errors::AppError;
-use crate::utils::{glob_to_regex, parse_hex_string, wildcard_to_regex};
+use crate::utils::glob_to_regex;
+use crate::fs::compare::{self, CompareItem, DiffKind, SyncAction, SyncItem, SyncPermissions};
+use crate::fs::diff::{self, DiffStatus, FileDiff, TreeDiff};
+use crate::fs::edit_buffer::EditBuffer;
+use crate::fs::find::{FindField, FindOptions, FindProgress, FindQuery};
+use crate::fs::follow::{FileFollower, FollowChange};
+use crate::fs::pack::PackFormat;
+use crate::fs::search::{self, SearchProgress, SearchQuery};
 use crate::fs::utils::delete_path;
-use crate::ui::viewer_utils::compute_line_offsets;
+use crate::ui::encoding::{self, TextEncoding};
+use crate::ui::syntax::{self, Grammar};
+use crate::ui::viewer_utils::{TextLayout, compute_line_offsets};
 
+use super::hex_editor::HexEditor;
+use super::viewer_outline::ViewerOutline;
+use super::viewer_table::ViewerTable;
 use super::mode::{Mode, FileOperation, SimpleConfirmAction, ViewContent, BinaryViewMode};
 use super::panel::{Panel, ViewMode, SortField, SortDirection, SortConfig};
 use super::{Side, UiState, CommandState};
@@ -61,12 +75,25 @@ pub struct App {
     pub quick_search: Option<String>,
     /// Computed directory sizes (F3 on a directory computes and caches size)
     pub dir_sizes: std::collections::HashMap<PathBuf, u64>,
+    /// SSH host keys trusted for this session only ("host:port" -> fingerprint)
+    pub session_host_keys: std::collections::HashMap<String, String>,
+    /// Completed local file operations that can be undone
+    pub journal: crate::fs::undo::Journal,
+    /// Options of the last local copy, offered again by the next F5
+    pub copy_options: crate::fs::copy::CopyOptions,
 
     // === Background tasks ===
     /// Currently running background task (if any)
     pub background_task: Option<super::background::BackgroundTask>,
     /// Cancel token for file operations (shared with background thread)
     pub cancel_token: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    /// Set while the running task is a copy or move, which can be
+    /// sent to the job queue
+    pub job_control: Option<super::jobs::JobControl>,
+    /// Copies and moves running in the background
+    pub jobs: super::jobs::JobQueue,
+    /// Background directory listings for both panels
+    pub io: IoManager,
     /// Child process handle for cancelling a running command (Windows)
     #[cfg(windows)]
     pub command_child: Option<std::sync::Arc<std::sync::Mutex<Option<std::process::Child>>>>,
@@ -184,6 +211,12 @@ impl App {
             right_panel.refresh();
         }
 
+        // The first listing above is synchronous; everything after it
+        // goes through the background I/O manager
+        let io = IoManager::new();
+        left_panel.attach_io(Side::Left, io.handle());
+        right_panel.attach_io(Side::Right, io.handle());
+
         let left_git = git::get_git_status(&left_path);
         let right_git = git::get_git_status(&right_path);
 
@@ -200,7 +233,16 @@ impl App {
         if let Some(config_dir) = crate::config::config_dir() {
             let config_plugins = config_dir.join("plugins");
             if config_plugins.exists() {
-                let _ = plugins.load_from_directory(&config_plugins);
+                // Tell the user why a plugin is missing instead of silently
+                // skipping it (e.g. it needs a newer plugin API)
+                let errors = plugins.load_from_directory(&config_plugins);
+                if let Some(first) = errors.first() {
+                    left_panel.error = Some(if errors.len() == 1 {
+                        format!("Plugin not loaded: {}", first)
+                    } else {
+                        format!("{} plugins not loaded; first: {}", errors.len(), first)
+                    });
+                }
             }
         }
 
@@ -213,6 +255,11 @@ impl App {
             ui: UiState::from_config(
                 config.display.shell_height.max(1),
                 config.display.panel_ratio,
+                TextLayout {
+                    wrap: config.viewer.wrap,
+                    line_numbers: config.viewer.line_numbers,
+                    tab_width: config.viewer.tab_width.clamp(1, 16),
+                },
             ),
             cmd: CommandState::with_history(crate::config::load_command_history()),
             left_git_status: left_git,
@@ -220,13 +267,19 @@ impl App {
             left_git_path: Some(left_path),
             right_git_path: Some(right_path),
             python_env,
+            jobs: super::jobs::JobQueue::new(config.general.max_jobs),
             config,
             theme,
             plugins,
             quick_search: None,
             dir_sizes: std::collections::HashMap::new(),
+            session_host_keys: std::collections::HashMap::new(),
+            journal: crate::fs::undo::Journal::load(crate::config::undo_file()),
+            copy_options: crate::fs::copy::CopyOptions::default(),
             background_task: None,
             cancel_token: None,
+            job_control: None,
+            io,
             #[cfg(windows)]
             command_child: None,
             shell: None,
@@ -491,6 +544,8 @@ impl App {
             "highlights",
             "help",
             "touch",
+            "undo",
+            "jobs",
             "shell",
             "quit",
             "exit",
@@ -631,6 +686,14 @@ impl App {
         }
     }
 
+    /// Get a reference to a panel by side
+    pub fn panel(&self, side: Side) -> &Panel {
+        match side {
+            Side::Left => &self.left_panel,
+            Side::Right => &self.right_panel,
+        }
+    }
+
     /// Get a mutable reference to a panel by side
     pub fn panel_mut(&mut self, side: Side) -> &mut Panel {
         match side {
@@ -904,7 +967,7 @@ impl App {
 
             // Quit
             "q" | "quit" | "exit" => {
-                self.should_quit = true;
+                self.request_quit();
                 Some(String::new())
             }
 
@@ -995,6 +1058,17 @@ impl App {
                 }
             }
 
+            // Undo the last file operation
+            "undo" => {
+                self.undo_last_operation();
+                Some(String::new())
+            }
+
+            "jobs" => {
+                self.show_jobs();
+                Some(String::new())
+            }
+
             // Touch: create file or update timestamp (built-in fallback when no external touch exists)
             "touch" => {
                 if args.is_empty() {
@@ -1370,6 +1444,26 @@ impl App {
                 format!("view_plugin_first = {}", new_val)
             }
 
+            "use_trash" | "trash" => {
+                let new_val = match value {
+                    Some("true") | Some("1") | Some("on") | Some("yes") => true,
+                    Some("false") | Some("0") | Some("off") | Some("no") => false,
+                    None => !self.config.general.use_trash, // Toggle
+                    _ => return format!("Invalid value for {}: use true/false", option),
+                };
+                self.config.general.use_trash = new_val;
+                format!("use_trash = {}", new_val)
+            }
+
+            "max_jobs" | "jobs" => {
+                let Some(new_val) = value.and_then(|v| v.parse::<usize>().ok()).filter(|&n| n > 0) else {
+                    return format!("Invalid value for {}: use a number of jobs (1 or more)", option);
+                };
+                self.config.general.max_jobs = new_val;
+                self.jobs.set_max_running(new_val);
+                format!("max_jobs = {}", new_val)
+            }
+
             _ => format!("Unknown option: {}. Type 'help' for available options.", option),
         }
     }
@@ -1402,7 +1496,7 @@ impl App {
 
     /// Help text for built-in commands
     fn builtin_help(&self) -> String {
-        "Built-in: config-save, config-reload, config-edit, config-upgrade, config-reset, show-hidden, show-settings, set <opt>=<val>, theme <name>, themes, touch <file>, q".to_string()
+        "Built-in: config-save, config-reload, config-edit, config-upgrade, config-reset, show-hidden, show-settings, set <opt>=<val>, theme <name>, themes, touch <file>, undo, jobs, q".to_string()
     }
 
     // ========================================================================
@@ -1480,6 +1574,8 @@ impl App {
         self.left_panel.sort_config.dirs_first = self.config.sorting.dirs_first;
         self.left_panel.sort_config.uppercase_first = self.config.sorting.uppercase_first;
         self.right_panel.sort_config = self.left_panel.sort_config;
+
+        self.jobs.set_max_running(self.config.general.max_jobs);
         self.left_panel.resort();
         self.right_panel.resort();
 
@@ -1529,6 +1625,10 @@ impl App {
             self.active_panel_mut().error = Some("No files to copy".to_string());
             return;
         }
+        if let Err(e) = self.inactive_panel().require(Capability::Write) {
+            self.active_panel_mut().error = Some(e);
+            return;
+        }
 
         let dest_str = dest_path.to_string_lossy().to_string();
         let cursor_pos = dest_str.len();
@@ -1541,6 +1641,7 @@ impl App {
             cursor_pos,
             focus: 0, // Start with input field focused
             apply_all: false,
+            options: self.copy_options,
         };
     }
 
@@ -1566,6 +1667,12 @@ impl App {
             self.active_panel_mut().error = Some("No files to move".to_string());
             return;
         }
+        let supported = self.inactive_panel().require(Capability::Write)
+            .and_then(|_| self.active_panel().require(Capability::Delete));
+        if let Err(e) = supported {
+            self.active_panel_mut().error = Some(e);
+            return;
+        }
 
         let dest_str = dest_path.to_string_lossy().to_string();
         let cursor_pos = dest_str.len();
@@ -1578,11 +1685,23 @@ impl App {
             cursor_pos,
             focus: 0, // Start with input field focused
             apply_all: false,
+            options: self.copy_options,
         };
     }
 
-    /// Show delete confirmation dialog
+    /// Show delete confirmation dialog; local files go to the trash when
+    /// `use_trash` is on
     pub fn delete_selected(&mut self) {
+        let to_trash = self.config.general.use_trash && self.active_panel().is_local();
+        self.confirm_delete(if to_trash { FileOperation::Trash } else { FileOperation::Delete });
+    }
+
+    /// Show delete confirmation dialog, bypassing the trash
+    pub fn delete_selected_permanently(&mut self) {
+        self.confirm_delete(FileOperation::Delete);
+    }
+
+    fn confirm_delete(&mut self, operation: FileOperation) {
         let src_panel = match self.active_panel {
             Side::Left => &self.left_panel,
             Side::Right => &self.right_panel,
@@ -1603,17 +1722,22 @@ impl App {
             self.active_panel_mut().error = Some("No files to delete".to_string());
             return;
         }
+        if let Err(e) = self.active_panel().require(Capability::Delete) {
+            self.active_panel_mut().error = Some(e);
+            return;
+        }
 
         // When deleting a single directory, checkbox is shown — Delete button is focus 2
-        let show_checkbox = sources.len() == 1 && sources[0].is_dir();
+        let show_checkbox = matches!(operation, FileOperation::Delete) && sources.len() == 1 && sources[0].is_dir();
         let initial_focus = if show_checkbox { 2 } else { 1 };
         self.mode = Mode::Confirming {
-            operation: FileOperation::Delete,
+            operation,
             sources,
             dest_input: String::new(), // Not used for delete
             cursor_pos: 0,
             focus: initial_focus,
             apply_all: false,
+            options: self.copy_options,
         };
     }
 
@@ -1683,6 +1807,65 @@ impl App {
         }
     }
 
+    /// Open the trash browser with the cursor on row `cursor`
+    pub fn show_trash_browser(&mut self, cursor: usize) {
+        let items = crate::fs::trash::list();
+        let cursor = cursor.min(items.len().saturating_sub(1));
+        self.mode = Mode::TrashBrowser { items, cursor, scroll: 0 };
+    }
+
+    /// Put the highlighted trash item back where it came from
+    pub fn restore_trashed(&mut self) {
+        let Mode::TrashBrowser { items, cursor, .. } = &self.mode else {
+            return;
+        };
+        let Some(item) = items.get(*cursor).cloned() else {
+            return;
+        };
+        let cursor = *cursor;
+        match crate::fs::trash::restore(&item) {
+            Ok(()) => {
+                self.add_shell_output(format!("Restored {}", item.original.display()));
+                self.left_panel.refresh();
+                self.right_panel.refresh();
+                self.refresh_git_status();
+            }
+            Err(e) => self.active_panel_mut().error = Some(format!("Failed to restore: {}", e)),
+        }
+        self.show_trash_browser(cursor);
+    }
+
+    /// Ask before deleting the highlighted trash item for good
+    pub fn confirm_purge_trashed(&mut self) {
+        let Mode::TrashBrowser { items, cursor, .. } = &self.mode else {
+            return;
+        };
+        let Some(item) = items.get(*cursor).cloned() else {
+            return;
+        };
+        let name = item.original.file_name().unwrap_or_default().to_string_lossy().to_string();
+        self.mode = Mode::SimpleConfirm {
+            message: format!("Permanently delete \"{}\" from the trash?", name),
+            action: SimpleConfirmAction::PurgeTrashed { item, cursor: *cursor },
+            focus: 1,
+        };
+    }
+
+    /// Ask before emptying the trash
+    pub fn confirm_empty_trash(&mut self) {
+        let Mode::TrashBrowser { items, .. } = &self.mode else {
+            return;
+        };
+        if items.is_empty() {
+            return;
+        }
+        self.mode = Mode::SimpleConfirm {
+            message: format!("Permanently delete all {} item(s) in the trash?", items.len()),
+            action: SimpleConfirmAction::EmptyTrash,
+            focus: 1,
+        };
+    }
+
     /// Apply file attributes (modification time, permissions) from a provider entry
     /// to a locally-written file. Best-effort — errors are silently ignored since
     /// the file data was already written successfully.
@@ -1700,23 +1883,16 @@ impl App {
         }
     }
 
-    /// Execute the confirmed file operation
-    pub fn execute_file_operation(&mut self, operation: FileOperation, sources: Vec<PathBuf>, dest: PathBuf) {
-        // Block move and delete from archives (read-only)
-        if self.active_panel().is_in_archive() {
-            match &operation {
-                FileOperation::Move => {
-                    self.active_panel_mut().error = Some("Cannot move from archive (read-only)".into());
-                    return;
-                }
-                FileOperation::Delete => {
-                    self.active_panel_mut().error = Some("Cannot delete from archive (read-only)".into());
-                    return;
-                }
-                FileOperation::Copy => {} // Copy from archive is fine
-            }
-        }
-
+    /// Execute the confirmed file operation. A remote copy or move carries
+    /// on after the shorter copies in `resume`, keyed by source, instead of
+    /// overwriting them.
+    pub fn execute_file_operation(
+        &mut self,
+        operation: FileOperation,
+        sources: Vec<PathBuf>,
+        dest: PathBuf,
+        resume: HashMap<PathBuf, u64>,
+    ) {
         // Resolve relative destination paths against the active panel's directory
         let dest = if dest.is_relative() {
             let base = self.active_panel().path.clone();
@@ -1734,16 +1910,23 @@ impl App {
         };
 
         // For local-to-local copy/move, run in background with progress
-        if !src_is_remote && !dest_is_remote && !matches!(operation, FileOperation::Delete) {
+        if !src_is_remote && !dest_is_remote && !operation.is_delete() {
             let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let paused = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
             self.cancel_token = Some(cancel.clone());
             let title = match &operation {
                 FileOperation::Copy => "Copying",
                 FileOperation::Move => "Moving",
-                FileOperation::Delete => unreachable!(),
-            }.to_string();
+                FileOperation::Delete | FileOperation::Trash => unreachable!(),
+            };
+            self.job_control = Some(super::jobs::JobControl {
+                description: Self::job_description(&operation, &sources, &dest),
+                cancel: cancel.clone(),
+                paused: paused.clone(),
+            });
+            let title = title.to_string();
             let task = super::background::BackgroundTask::file_operation(
-                operation, sources, dest, cancel,
+                operation, sources, dest, self.copy_options, cancel, paused,
             );
             self.background_task = Some(task);
             self.mode = Mode::FileOpProgress {
@@ -1759,11 +1942,20 @@ impl App {
         }
 
         // Handle delete operations synchronously (they're fast, just metadata ops)
-        if matches!(operation, FileOperation::Delete) {
+        if operation.is_delete() {
+            let to_trash = matches!(operation, FileOperation::Trash);
             let mut count = 0;
             let mut errors = Vec::new();
+            let mut trashed = Vec::new();
             for src_path in &sources {
-                let result = if src_is_remote {
+                let result = if to_trash {
+                    crate::fs::trash::trash(src_path)
+                        .map(|path| trashed.push(crate::fs::undo::Change::Trash {
+                            original: std::path::absolute(src_path).unwrap_or_else(|_| src_path.clone()),
+                            trashed: path,
+                        }))
+                        .map_err(|e| e.to_string())
+                } else if src_is_remote {
                     let path_str = src_path.to_string_lossy().to_string();
                     let is_dir = self.active_panel().entries.iter()
                         .find(|e| e.path == *src_path)
@@ -1778,6 +1970,12 @@ impl App {
                     Err(e) => errors.push(format!("{}: {}", src_path.display(), e)),
                 }
             }
+            if src_is_remote
+                && let Err(e) = self.active_panel_mut().flush()
+            {
+                errors.push(e.to_string());
+            }
+            self.journal.record(format!("Move {} item(s) to the trash", trashed.len()), trashed);
             self.active_panel_mut().selected.clear();
             self.left_panel.refresh();
             self.right_panel.refresh();
@@ -1787,6 +1985,8 @@ impl App {
                     "Deleted {}, {} errors: {}",
                     count, errors.len(), errors.first().unwrap_or(&String::new())
                 ));
+            } else if to_trash {
+                self.add_shell_output(format!("Moved {} file(s) to the trash", count));
             } else {
                 self.add_shell_output(format!("Deleted {} file(s)", count));
             }
@@ -1794,12 +1994,18 @@ impl App {
         }
 
         // For remote copy/move, check size guard then dispatch to background thread
-        self.execute_remote_with_size_guard(operation, sources, dest, false);
+        self.execute_remote_with_size_guard(operation, sources, dest, resume, false);
     }
 
     /// Dispatch a remote copy/move to a background thread.
     /// Called from execute_file_operation (after size guard) or from LargeRemoteTransfer confirm.
-    fn dispatch_remote_file_operation(&mut self, operation: FileOperation, sources: Vec<PathBuf>, dest: PathBuf) {
+    fn dispatch_remote_file_operation(
+        &mut self,
+        operation: FileOperation,
+        sources: Vec<PathBuf>,
+        dest: PathBuf,
+        resume: HashMap<PathBuf, u64>,
+    ) {
         use super::background::SourceMeta;
 
         let src_is_remote = self.active_panel().is_remote();
@@ -1810,6 +2016,7 @@ impl App {
 
         // Pre-collect metadata from panel entries (or local fs for local sources)
         let source_metas: Vec<SourceMeta> = sources.iter().map(|src_path| {
+            let resume_from = resume.get(src_path).copied().unwrap_or(0);
             if src_is_remote {
                 // Get metadata from panel entries
                 let entry = self.active_panel().entries.iter().find(|e| e.path == *src_path);
@@ -1819,6 +2026,7 @@ impl App {
                     modified: entry.and_then(|e| e.modified),
                     permissions: entry.map(|e| e.permissions).unwrap_or(0),
                     size: entry.map(|e| e.size).unwrap_or(0),
+                    resume_from,
                 }
             } else {
                 // Local source: read metadata from filesystem
@@ -1833,39 +2041,38 @@ impl App {
                 let permissions = 0u32;
                 let size = meta.as_ref().map(|m| m.len()).unwrap_or(0);
                 let is_dir = meta.as_ref().map(|m| m.is_dir()).unwrap_or(false);
-                SourceMeta { path: src_path.clone(), is_dir, modified, permissions, size }
+                SourceMeta { path: src_path.clone(), is_dir, modified, permissions, size, resume_from }
             }
         }).collect();
 
-        // Take providers from panels as needed
-        let taken_src = if src_is_remote {
-            Some(self.active_panel_mut().take_provider())
-        } else {
-            None
-        };
-        let taken_dest = if dest_is_remote {
-            let panel = match self.active_panel {
-                Side::Left => &mut self.right_panel,
-                Side::Right => &mut self.left_panel,
-            };
-            Some(panel.take_provider())
-        } else {
-            None
-        };
+        // The worker shares the panels' providers, so they stay usable if
+        // the operation goes to the background
+        let src_provider = src_is_remote.then(|| self.active_panel().shared_provider());
+        let dest_provider = dest_is_remote.then(|| match self.active_panel {
+            Side::Left => self.right_panel.shared_provider(),
+            Side::Right => self.left_panel.shared_provider(),
+        });
 
         let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let paused = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
         self.cancel_token = Some(cancel.clone());
+        self.job_control = Some(super::jobs::JobControl {
+            description: Self::job_description(&operation, &sources, &dest),
+            cancel: cancel.clone(),
+            paused: paused.clone(),
+        });
 
         let title = match &operation {
             FileOperation::Copy => "Copying",
             FileOperation::Move => "Moving",
-            FileOperation::Delete => unreachable!(),
+            FileOperation::Delete | FileOperation::Trash => unreachable!(),
         }.to_string();
 
+        let verify = matches!(operation, FileOperation::Copy) && self.copy_options.verify;
         let task = super::background::BackgroundTask::remote_file_operation(
             operation, source_metas, dest,
-            taken_src, taken_dest,
-            self.active_panel, cancel,
+            src_provider, dest_provider, self.active_panel,
+            verify, cancel, paused,
         );
         self.background_task = Some(task);
         self.mode = Mode::FileOpProgress {
@@ -1882,7 +2089,14 @@ impl App {
     /// Execute a remote file operation, optionally checking the size guard first.
     /// Called from execute_file_operation for remote copy/move.
     /// If `bypass_size_guard` is true, skip the size check (used after user confirms large transfer).
-    pub fn execute_remote_with_size_guard(&mut self, operation: FileOperation, sources: Vec<PathBuf>, dest: PathBuf, bypass_size_guard: bool) {
+    pub fn execute_remote_with_size_guard(
+        &mut self,
+        operation: FileOperation,
+        sources: Vec<PathBuf>,
+        dest: PathBuf,
+        resume: HashMap<PathBuf, u64>,
+        bypass_size_guard: bool,
+    ) {
         if !bypass_size_guard {
             let limit_mb = self.config.general.remote_transfer_limit_mb;
             if limit_mb > 0 {
@@ -1898,6 +2112,7 @@ impl App {
                         FileOperation::Copy => "copy",
                         FileOperation::Move => "move",
                         FileOperation::Delete => "delete",
+                        FileOperation::Trash => "trash",
                     };
                     self.mode = Mode::SimpleConfirm {
                         message: format!(
@@ -1908,6 +2123,7 @@ impl App {
                             operation,
                             sources,
                             dest,
+                            resume,
                         },
                         focus: 0,
                     };
@@ -1915,7 +2131,7 @@ impl App {
                 }
             }
         }
-        self.dispatch_remote_file_operation(operation, sources, dest);
+        self.dispatch_remote_file_operation(operation, sources, dest, resume);
     }
 
     /// Start a file operation with overwrite conflict checking.
@@ -1931,24 +2147,31 @@ impl App {
         };
 
         // Check for overwrite conflicts on copy/move (local and remote)
-        if !matches!(operation, FileOperation::Delete) {
+        if !operation.is_delete() {
             let dest_is_remote = match self.active_panel {
                 Side::Left => self.right_panel.is_remote(),
                 Side::Right => self.left_panel.is_remote(),
             };
 
+            let dest_panel = match self.active_panel {
+                Side::Left => &self.right_panel,
+                Side::Right => &self.left_panel,
+            };
             let conflicts = if dest_is_remote {
                 // Remote destination: check against the destination panel's loaded entries
-                let dest_panel = match self.active_panel {
-                    Side::Left => &self.right_panel,
-                    Side::Right => &self.left_panel,
-                };
                 Self::find_remote_overwrite_conflicts(&sources, &dest_panel.entries)
             } else {
                 Self::find_overwrite_conflicts(&sources, &dest)
             };
 
             if !conflicts.is_empty() {
+                // Only remote jobs write files in a way that can be resumed
+                let resumable = if dest_is_remote || self.active_panel().is_remote() {
+                    let rename = sources.len() == 1 && !dest_is_remote && !dest.is_dir();
+                    self.find_resumable_conflicts(&conflicts, &dest, rename, dest_is_remote.then_some(dest_panel))
+                } else {
+                    HashMap::new()
+                };
                 self.mode = Mode::OverwriteConfirm {
                     operation,
                     all_sources: sources,
@@ -1957,6 +2180,8 @@ impl App {
                     current_conflict: 0,
                     skip_set: std::collections::HashSet::new(),
                     overwrite_all: false,
+                    resumable,
+                    resume: HashMap::new(),
                     focus: 0,
                 };
                 return;
@@ -1964,7 +2189,31 @@ impl App {
         }
 
         // No conflicts — proceed directly
-        self.execute_file_operation(operation, sources, dest);
+        self.execute_file_operation(operation, sources, dest, HashMap::new());
+    }
+
+    /// Conflicts whose destination is a shorter copy of the source file,
+    /// most likely left by an interrupted job, with the size of that copy.
+    /// `dest_panel` lists a remote destination; a local one is looked up.
+    fn find_resumable_conflicts(
+        &self,
+        conflicts: &[PathBuf],
+        dest: &Path,
+        rename: bool,
+        dest_panel: Option<&Panel>,
+    ) -> HashMap<PathBuf, u64> {
+        conflicts.iter().filter_map(|src| {
+            let source = self.active_panel().entries.iter().find(|e| e.path == *src && !e.is_dir)?;
+            let name = src.file_name()?;
+            let dest_size = match dest_panel {
+                Some(panel) => panel.entries.iter().find(|e| !e.is_dir && *e.name == *name)?.size,
+                None => {
+                    let meta = std::fs::metadata(if rename { dest.to_path_buf() } else { dest.join(name) }).ok()?;
+                    meta.is_file().then_some(meta.len())?
+                }
+            };
+            (dest_size > 0 && dest_size < source.size).then(|| (src.clone(), dest_size))
+        }).collect()
     }
 
     /// Find destination files that already exist (overwrite conflicts) — local filesystem.
@@ -2001,6 +2250,10 @@ impl App {
 
     /// Show mkdir dialog (F7)
     pub fn show_mkdir_dialog(&mut self) {
+        if let Err(e) = self.active_panel().require(Capability::Mkdir) {
+            self.active_panel_mut().error = Some(e);
+            return;
+        }
         self.mode = Mode::MakingDir {
             name_input: String::new(),
             cursor_pos: 0,
@@ -2008,6 +2261,47 @@ impl App {
         };
     }
 
+    /// Show the pack dialog (Alt+F5) for the selection or the entry under the cursor
+    pub fn show_pack_dialog(&mut self) {
+        if !self.active_panel().is_local() {
+            self.active_panel_mut().error = Some("Pack works on local files only".to_string());
+            return;
+        }
+
+        let entries = self.active_panel().get_selected_entries();
+        if entries.is_empty() {
+            return;
+        }
+        let sources: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+
+        // Single file: "report.txt" -> "report"; otherwise name after the current directory
+        let base = if let [entry] = entries.as_slice() {
+            if entry.is_dir {
+                entry.name.clone()
+            } else {
+                Path::new(&entry.name).file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| entry.name.clone())
+            }
+        } else {
+            self.active_panel().path.file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "archive".to_string())
+        };
+
+        let format = PackFormat::Zip;
+        let name_input = format.apply_extension(&base);
+        self.ui.input_selected = false;
+        self.mode = Mode::Packing {
+            sources,
+            cursor_pos: name_input.len(),
+            name_input,
+            format,
+            level: 6,
+            focus: 0,
+        };
+    }
+
     /// Show the command history panel
     pub fn show_command_history(&mut self) {
         // Start with the last (most recent) command selected, or 0 if empty
@@ -2078,18 +2372,24 @@ impl App {
     /// Apply permission changes to files (Unix only)
     #[cfg(not(windows))]
     pub fn apply_permissions(&mut self, paths: &[std::path::PathBuf], mode: u32, recursive: bool) {
-        use std::os::unix::fs::PermissionsExt;
+        use crate::fs::undo::Change;
 
         let mut errors = Vec::new();
+        let mut changes = Vec::new();
         for path in paths {
-            if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)) {
-                errors.push(format!("{}: {}", path.display(), e));
+            let mut old = Vec::new();
+            if !Self::chmod_path(path, mode, &mut old, &mut errors) {
                 continue;
             }
             if recursive && path.is_dir() {
-                Self::apply_permissions_recursive(path, mode, &mut errors);
+                // A whole tree is journaled as one step
+                Self::apply_permissions_recursive(path, mode, &mut old, &mut errors);
+                changes.extend(Change::chmod_tree(path.clone(), old));
+            } else {
+                changes.extend(old.into_iter().map(|(path, _, mode)| Change::Chmod { path, mode }));
             }
         }
+        self.journal.record(format!("Change permissions of {} item(s) to {:o}", paths.len(), mode), changes);
 
         if !errors.is_empty() {
             for err in &errors {
@@ -2102,10 +2402,39 @@ impl App {
         self.refresh_panels();
     }
 
+    /// Set the permission bits of one path, noting its old `(path, is_dir,
+    /// mode)` in `old`. Returns false on failure.
     #[cfg(not(windows))]
-    fn apply_permissions_recursive(dir: &std::path::Path, mode: u32, errors: &mut Vec<String>) {
+    fn chmod_path(
+        path: &std::path::Path,
+        mode: u32,
+        old: &mut Vec<(PathBuf, bool, u32)>,
+        errors: &mut Vec<String>,
+    ) -> bool {
         use std::os::unix::fs::PermissionsExt;
 
+        let before = std::fs::metadata(path).map(|m| (m.is_dir(), m.permissions().mode() & 0o7777));
+        match std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)) {
+            Ok(()) => {
+                if let Ok((is_dir, before)) = before {
+                    old.push((path.to_path_buf(), is_dir, before));
+                }
+                true
+            }
+            Err(e) => {
+                errors.push(format!("{}: {}", path.display(), e));
+                false
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn apply_permissions_recursive(
+        dir: &std::path::Path,
+        mode: u32,
+        old: &mut Vec<(PathBuf, bool, u32)>,
+        errors: &mut Vec<String>,
+    ) {
         let entries = match std::fs::read_dir(dir) {
             Ok(e) => e,
             Err(e) => {
@@ -2123,12 +2452,11 @@ impl App {
                 }
             };
             let path = entry.path();
-            if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)) {
-                errors.push(format!("{}: {}", path.display(), e));
+            if !Self::chmod_path(&path, mode, old, errors) {
                 continue;
             }
             if path.is_dir() {
-                Self::apply_permissions_recursive(&path, mode, errors);
+                Self::apply_permissions_recursive(&path, mode, old, errors);
             }
         }
     }
@@ -2211,13 +2539,22 @@ impl App {
             return;
         };
 
+        use crate::fs::undo::Change;
+
         let mut errors = Vec::new();
+        let mut changes = Vec::new();
         for path in paths {
-            Self::chown_path(path, uid, gid, &mut errors);
+            let mut old = Vec::new();
+            Self::chown_path(path, uid, gid, &mut old, &mut errors);
             if recursive && path.is_dir() {
-                Self::chown_recursive(path, uid, gid, &mut errors);
+                // A whole tree is journaled as one step
+                Self::chown_recursive(path, uid, gid, &mut old, &mut errors);
+                changes.extend(Change::chown_tree(path.clone(), old));
+            } else {
+                changes.extend(old.into_iter().map(|(path, uid, gid)| Change::Chown { path, uid, gid }));
             }
         }
+        self.journal.record(format!("Change owner of {} item(s) to {}:{}", paths.len(), user, group), changes);
 
         if !errors.is_empty() {
             for err in &errors {
@@ -2231,8 +2568,15 @@ impl App {
     }
 
     #[cfg(not(windows))]
-    fn chown_path(path: &std::path::Path, uid: u32, gid: u32, errors: &mut Vec<String>) {
+    fn chown_path(
+        path: &std::path::Path,
+        uid: u32,
+        gid: u32,
+        old: &mut Vec<(PathBuf, u32, u32)>,
+        errors: &mut Vec<String>,
+    ) {
         use std::ffi::CString;
+        use std::os::unix::fs::MetadataExt;
 
         let c_path = match CString::new(path.to_string_lossy().as_bytes()) {
             Ok(p) => p,
@@ -2242,16 +2586,25 @@ impl App {
             }
         };
 
+        let before = std::fs::metadata(path).map(|m| (m.uid(), m.gid()));
         // SAFETY: chown is safe with a valid C string path and valid uid/gid
         let ret = unsafe { libc::chown(c_path.as_ptr(), uid, gid) };
         if ret != 0 {
             let err = std::io::Error::last_os_error();
             errors.push(format!("{}: {}", path.display(), err));
+        } else if let Ok((old_uid, old_gid)) = before {
+            old.push((path.to_path_buf(), old_uid, old_gid));
         }
     }
 
     #[cfg(not(windows))]
-    fn chown_recursive(dir: &std::path::Path, uid: u32, gid: u32, errors: &mut Vec<String>) {
+    fn chown_recursive(
+        dir: &std::path::Path,
+        uid: u32,
+        gid: u32,
+        old: &mut Vec<(PathBuf, u32, u32)>,
+        errors: &mut Vec<String>,
+    ) {
         let entries = match std::fs::read_dir(dir) {
             Ok(e) => e,
             Err(e) => {
@@ -2269,9 +2622,9 @@ impl App {
                 }
             };
             let path = entry.path();
-            Self::chown_path(&path, uid, gid, errors);
+            Self::chown_path(&path, uid, gid, old, errors);
             if path.is_dir() {
-                Self::chown_recursive(&path, uid, gid, errors);
+                Self::chown_recursive(&path, uid, gid, old, errors);
             }
         }
     }
@@ -2384,189 +2737,501 @@ impl App {
     }
 
     pub fn show_find_files_dialog(&mut self) {
-        let current_path = self.active_panel().path.to_string_lossy().to_string();
-        let path_len = current_path.len();
+        let current_path = self.active_panel().browse_path().to_string_lossy().to_string();
         self.ui.input_selected = true; // Select "*" so typing replaces it
         self.mode = Mode::FindFiles {
-            pattern_input: "*".to_string(),
-            pattern_cursor: 1,
-            pattern_case_sensitive: false,
-            content_input: String::new(),
-            content_cursor: 0,
-            content_case_sensitive: false,
-            path_input: current_path,
-            path_cursor: path_len,
-            recursive: true,
-            focus: 0, // Start with pattern input focused
+            options: FindOptions::new(current_path),
+            focus: FindField::Pattern,
+            cursor: 1,
         };
     }
 
-    /// Execute find files search
-    pub fn execute_find_files(
-        &mut self,
-        pattern: &str,
-        pattern_case_sensitive: bool,
-        content: &str,
-        content_case_sensitive: bool,
-        start_path: &Path,
-        recursive: bool,
-    ) {
-        if pattern.is_empty() {
-            self.active_panel_mut().error = Some("Pattern cannot be empty".to_string());
-            return;
-        }
-
-        // Convert glob pattern (* and ?) to regex
-        let regex_pattern = glob_to_regex(pattern, pattern_case_sensitive);
-        let regex = match regex::Regex::new(&regex_pattern) {
-            Ok(r) => r,
+    /// Start a Find Files search in the background
+    pub fn start_find_files(&mut self, options: FindOptions) {
+        let query = match FindQuery::new(&options) {
+            Ok(q) => q,
             Err(e) => {
-                self.active_panel_mut().error = Some(format!("Invalid pattern: {}", e));
+                self.active_panel_mut().error = Some(e);
                 return;
             }
         };
 
-        // Collect matching files
-        let mut matches: Vec<PathBuf> = Vec::new();
-        let start = if start_path.is_dir() {
-            start_path.to_path_buf()
+        use super::background::FindSource;
+
+        let panel = self.active_panel();
+        let source = if panel.is_local() {
+            let start = PathBuf::from(&options.path);
+            FindSource::Local(if start.is_dir() { start } else { panel.browse_path().to_path_buf() })
         } else {
-            self.active_panel().path.clone()
+            // Remote paths can't be checked up front; an unreadable start
+            // directory simply finds nothing
+            if panel.provider_busy() {
+                self.active_panel_mut().error = Some("Connection is busy with a directory listing".to_string());
+                return;
+            }
+            let start = options.path.trim();
+            let start = if start.is_empty() {
+                panel.browse_path().to_string_lossy().to_string()
+            } else {
+                start.to_string()
+            };
+            FindSource::Provider { provider: panel.shared_provider(), start }
+        };
+
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let progress = std::sync::Arc::new(FindProgress::default());
+        let description = options.describe();
+        self.cancel_token = Some(cancel.clone());
+        self.background_task = Some(super::background::BackgroundTask::find_files(
+            source, self.active_panel, query, description.clone(), progress.clone(), cancel,
+        ));
+        self.mode = Mode::Searching {
+            description,
+            progress,
+            frame: 0,
+            started: std::time::Instant::now(),
         };
+    }
 
-        self.find_files_recursive(&start, &regex, content, content_case_sensitive, recursive, &mut matches, 0);
+    /// Show the matches of a finished (or cancelled) search in a temp panel.
+    /// Local matches go to the other panel when it is local too; remote
+    /// matches stay in the searched panel, whose provider can open them.
+    fn show_find_results(&mut self, matches: Vec<crate::fs::FileEntry>, description: &str, cancelled: bool, side: Side, remote: bool) {
+        let other = match side {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        };
+        let target = if !remote && self.panel(other).is_local() { other } else { side };
 
         if matches.is_empty() {
-            if content.is_empty() {
-                self.active_panel_mut().error = Some(format!("No files matching '{}' found", pattern));
+            self.panel_mut(side).error = Some(if cancelled {
+                "Search cancelled".to_string()
             } else {
-                self.active_panel_mut().error = Some(format!("No files matching '{}' containing '{}' found", pattern, content));
-            }
-        } else {
-            // Show summary in shell
-            let count = matches.len();
-            if content.is_empty() {
-                self.add_shell_output(format!("Found {} file(s) matching '{}':", count, pattern));
-            } else {
-                self.add_shell_output(format!("Found {} file(s) matching '{}' containing '{}':", count, pattern, content));
-            }
+                format!("No files {} found", description)
+            });
+            return;
+        }
 
-            // Output each file path to shell for easy copy-paste
-            for path in &matches {
-                self.add_shell_output(path.to_string_lossy().to_string());
-            }
+        // Show summary in shell
+        let suffix = if cancelled { " before the search was cancelled" } else { "" };
+        self.add_shell_output(format!("Found {} file(s) {}{}:", matches.len(), description, suffix));
 
-            // Enter temp mode with the found files in the OTHER panel
-            self.inactive_panel_mut().enter_temp_mode(matches);
+        // Output each file path to shell for easy copy-paste
+        for entry in &matches {
+            self.add_shell_output(entry.path.to_string_lossy().to_string());
+        }
+
+        self.panel_mut(target).enter_temp_entries(matches);
 
-            // Switch to the other panel so user sees the results
+        // Switch to the results so the user sees them
+        if self.active_panel != target {
             self.toggle_panel();
         }
     }
 
-    /// Recursively find files matching the pattern and optionally containing text
-    #[allow(clippy::too_many_arguments)]
-    fn find_files_recursive(
-        &self,
-        dir: &Path,
-        regex: &regex::Regex,
-        content_search: &str,
-        content_case_sensitive: bool,
-        recursive: bool,
-        matches: &mut Vec<PathBuf>,
-        depth: usize,
-    ) {
-        // Limit recursion depth to prevent stack overflow
-        if depth > 100 {
+    /// Show the Compare Directories dialog for the two panels
+    pub fn show_compare_dialog(&mut self) {
+        if self.left_panel.is_temp_mode() || self.right_panel.is_temp_mode() {
+            self.active_panel_mut().error = Some("Leave the TEMP panel before comparing".to_string());
             return;
         }
-
-        let entries = match std::fs::read_dir(dir) {
-            Ok(e) => e,
-            Err(_) => return,
-        };
-
-        for entry in entries.flatten() {
-            let path = entry.path();
-            let file_name = entry.file_name().to_string_lossy().to_string();
-
-            // Check if file name matches pattern
-            if regex.is_match(&file_name) && path.is_file() {
-                // If content search is specified, check file content
-                if content_search.is_empty()
-                    || Self::file_contains_text(&path, content_search, content_case_sensitive)
-                {
-                    matches.push(path.clone());
-                }
-            }
-
-            // Recurse into directories
-            if recursive && path.is_dir() && file_name != "." && file_name != ".." {
-                self.find_files_recursive(&path, regex, content_search, content_case_sensitive, recursive, matches, depth + 1);
-            }
+        if self.left_panel.is_loading() || self.right_panel.is_loading() {
+            self.active_panel_mut().error = Some("Wait for the directory listing to finish".to_string());
+            return;
         }
+        self.mode = Mode::CompareDirs { by_content: false, focus: 1 };
     }
 
-    /// Check if a file contains the given text
-    fn file_contains_text(path: &Path, search_text: &str, case_sensitive: bool) -> bool {
-        // Read file content - skip binary files and large files
-        let metadata = match std::fs::metadata(path) {
-            Ok(m) => m,
-            Err(_) => return false,
+    /// Compare the two panels. A quick compare works on the loaded listings;
+    /// a content compare reads files through the providers in the background.
+    pub fn start_compare(&mut self, by_content: bool, synchronize: bool) {
+        self.mode = Mode::Normal;
+        if !by_content {
+            let items = compare::compare_entries(&self.left_panel.entries, &self.right_panel.entries, None);
+            self.finish_compare(items, synchronize);
+            return;
+        }
+        if self.left_panel.provider_busy() || self.right_panel.provider_busy() {
+            self.active_panel_mut().error = Some("A directory listing is still running; try again when it finishes".to_string());
+            return;
+        }
+
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        self.cancel_token = Some(cancel.clone());
+        self.background_task = Some(super::background::BackgroundTask::compare_dirs(
+            self.left_panel.entries.clone(),
+            self.right_panel.entries.clone(),
+            self.left_panel.shared_provider(),
+            self.right_panel.shared_provider(),
+            synchronize,
+            cancel,
+        ));
+        self.mode = Mode::BackgroundTask {
+            title: "Compare".to_string(),
+            message: "Comparing file contents...".to_string(),
+            frame: 0,
+            started: std::time::Instant::now(),
         };
+    }
 
-        // Skip files larger than 10MB
-        if metadata.len() > 10 * 1024 * 1024 {
-            return false;
+    /// Mark the differences in both panels, or open the Synchronize dialog
+    fn finish_compare(&mut self, items: Vec<CompareItem>, synchronize: bool) {
+        if synchronize {
+            let perms = SyncPermissions {
+                left_write: self.left_panel.supports(Capability::Write),
+                left_delete: self.left_panel.supports(Capability::Delete),
+                right_write: self.right_panel.supports(Capability::Write),
+                right_delete: self.right_panel.supports(Capability::Delete),
+            };
+            let items = compare::plan(items, perms);
+            if items.is_empty() {
+                self.add_shell_output("Directories are identical, nothing to synchronize".to_string());
+            } else {
+                self.mode = Mode::Synchronize { items, cursor: 0, scroll: 0 };
+            }
+            return;
         }
 
-        let content = match std::fs::read_to_string(path) {
-            Ok(c) => c,
-            Err(_) => return false, // Skip binary files or unreadable files
-        };
+        self.left_panel.selected = items.iter()
+            .filter(|i| i.marks_left())
+            .filter_map(|i| i.left.as_ref().map(|e| e.path.clone()))
+            .collect();
+        self.right_panel.selected = items.iter()
+            .filter(|i| i.marks_right())
+            .filter_map(|i| i.right.as_ref().map(|e| e.path.clone()))
+            .collect();
 
-        if case_sensitive {
-            content.contains(search_text)
+        let count = |kind: DiffKind| items.iter().filter(|i| i.kind == kind).count();
+        let heading = format!(
+            "Compared {} with {}",
+            self.left_panel.path.display(),
+            self.right_panel.path.display(),
+        );
+        if count(DiffKind::Same) == items.len() {
+            self.add_shell_output(format!("{}: no differences", heading));
         } else {
-            content.to_lowercase().contains(&search_text.to_lowercase())
+            self.add_shell_output(format!(
+                "{}: {} only left, {} only right, {} newer left, {} newer right, {} different, {} identical",
+                heading,
+                count(DiffKind::LeftOnly),
+                count(DiffKind::RightOnly),
+                count(DiffKind::LeftNewer),
+                count(DiffKind::RightNewer),
+                count(DiffKind::Different),
+                count(DiffKind::Same),
+            ));
         }
     }
 
-    /// Create the directory with the given name
-    pub fn create_directory(&mut self, name: &str) {
-        if name.is_empty() {
-            self.active_panel_mut().error = Some("Directory name cannot be empty".to_string());
+    /// Diff the trees below both panels' directories in the background
+    pub fn start_tree_diff(&mut self, by_content: bool) {
+        self.mode = Mode::Normal;
+        if self.left_panel.provider_busy() || self.right_panel.provider_busy() {
+            self.active_panel_mut().error = Some("A directory listing is still running; try again when it finishes".to_string());
             return;
         }
 
-        let current_dir = self.active_panel().path.clone();
-        let is_remote = self.active_panel().is_remote();
-
-        let result = if is_remote {
-            // Create directory on remote
-            let new_dir = if current_dir.to_string_lossy() == "/" {
-                format!("/{}", name)
-            } else {
-                format!("{}/{}", current_dir.to_string_lossy().trim_end_matches('/'), name)
-            };
-            self.active_panel_mut().mkdir(&new_dir)
-        } else {
-            // Create directory locally
-            let new_dir_path = current_dir.join(name);
-            std::fs::create_dir(&new_dir_path).map_err(AppError::from)
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        self.cancel_token = Some(cancel.clone());
+        self.background_task = Some(super::background::BackgroundTask::diff_trees(
+            self.left_panel.shared_provider(),
+            self.left_panel.path.to_string_lossy().to_string(),
+            self.right_panel.shared_provider(),
+            self.right_panel.path.to_string_lossy().to_string(),
+            by_content,
+            cancel,
+        ));
+        self.mode = Mode::BackgroundTask {
+            title: "Tree Diff".to_string(),
+            message: "Comparing directory trees...".to_string(),
+            frame: 0,
+            started: std::time::Instant::now(),
         };
+    }
 
-        match result {
-            Ok(()) => {
-                self.add_shell_output(format!("Created directory: {}", name));
-                // Refresh the current panel and position cursor on the new directory
-                self.active_panel_mut().refresh();
-                self.active_panel_mut().jump_to_prefix(name);
-                self.refresh_git_status();
-            }
-            Err(e) => {
-                self.active_panel_mut().error = Some(format!("Failed to create directory: {}", e));
+    /// Open the finished tree diff, or report that there is nothing in it
+    fn show_tree_diff(&mut self, tree: TreeDiff) {
+        for err in &tree.errors {
+            self.add_shell_output(format!("  Error: {}", err));
+        }
+        if tree.nodes.is_empty() {
+            if tree.errors.is_empty() {
+                self.add_shell_output(format!("No differences between {} and {}", tree.left_root, tree.right_root));
+            } else {
+                self.active_panel_mut().error = Some("Tree diff failed; see the shell output".to_string());
             }
+            return;
+        }
+        self.mode = Mode::DiffTree { tree: std::sync::Arc::new(tree), cursor: 0, scroll: 0 };
+    }
+
+    /// Open the line diff of the file under the tree diff cursor. Added and
+    /// removed files are shown against an empty file.
+    pub fn open_file_diff(&mut self) {
+        let Mode::DiffTree { tree, cursor, scroll } = &self.mode else {
+            return;
+        };
+        let (tree, cursor, scroll) = (tree.clone(), *cursor, *scroll);
+        let Some(node) = tree.nodes.get(cursor) else {
+            return;
+        };
+        fn file(entry: &Option<crate::fs::FileEntry>) -> Option<&crate::fs::FileEntry> {
+            entry.as_ref().filter(|e| !e.is_dir)
+        }
+        let (left, right) = match node.status {
+            Some(DiffStatus::Modified) if node.is_diffable() => (file(&node.left), file(&node.right)),
+            Some(DiffStatus::Removed) if file(&node.left).is_some() => (file(&node.left), None),
+            Some(DiffStatus::Added) if file(&node.right).is_some() => (None, file(&node.right)),
+            // Directories, and a file replaced by a directory
+            _ => return,
+        };
+
+        let diff = if left.into_iter().chain(right).any(|e| e.size > diff::MAX_DIFF_SIZE) {
+            FileDiff::note(&node.rel_path, "File is larger than 16 MiB; not diffed")
+        } else {
+            let read = |panel: &mut Panel, entry: Option<&crate::fs::FileEntry>| match entry {
+                Some(e) => panel.read_file(&e.path.to_string_lossy()).map_err(|err| format!("Cannot read '{}': {}", e.path.display(), err)),
+                None => Ok(Vec::new()),
+            };
+            match (read(&mut self.left_panel, left), read(&mut self.right_panel, right)) {
+                (Ok(old), Ok(new)) => diff::diff_text(&node.rel_path, &old, &new),
+                (Err(e), _) | (_, Err(e)) => FileDiff::note(&node.rel_path, e),
+            }
+        };
+        self.mode = Mode::ViewingDiff { diff, scroll: 0, tree, tree_cursor: cursor, tree_scroll: scroll };
+    }
+
+    /// Run the confirmed synchronization plan in the background
+    pub fn start_synchronize(&mut self, items: Vec<SyncItem>) {
+        self.mode = Mode::Normal;
+        let items: Vec<SyncItem> = items.into_iter().filter(|s| s.action != SyncAction::Skip).collect();
+        if items.is_empty() {
+            self.add_shell_output("Nothing to synchronize".to_string());
+            return;
+        }
+
+        // Taking a provider waits for any listing still running on it
+        let left_remote = self.left_panel.is_remote();
+        let right_remote = self.right_panel.is_remote();
+        if (left_remote && self.left_panel.provider_busy()) || (right_remote && self.right_panel.provider_busy()) {
+            self.active_panel_mut().error = Some("A directory listing is still running; try again when it finishes".to_string());
+            return;
+        }
+
+        let left_dir = self.left_panel.path.clone();
+        let right_dir = self.right_panel.path.clone();
+        // Marks from the compare would be stale after synchronizing
+        self.left_panel.selected.clear();
+        self.right_panel.selected.clear();
+        let left_provider = left_remote.then(|| self.left_panel.take_provider());
+        let right_provider = right_remote.then(|| self.right_panel.take_provider());
+
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        self.cancel_token = Some(cancel.clone());
+        self.background_task = Some(super::background::BackgroundTask::synchronize(
+            items, left_dir, right_dir, left_provider, right_provider, cancel,
+        ));
+        self.mode = Mode::FileOpProgress {
+            title: "Synchronizing".to_string(),
+            bytes_done: 0,
+            bytes_total: 0,
+            current_file: String::new(),
+            files_done: 0,
+            files_total: 0,
+            frame: 0,
+        };
+    }
+
+    /// Start packing `sources` into a new archive in the active panel's directory
+    pub fn start_pack(&mut self, sources: Vec<PathBuf>, name: &str, format: PackFormat, level: u32) {
+        let name = name.trim();
+        if name.is_empty() {
+            self.active_panel_mut().error = Some("Archive name cannot be empty".to_string());
+            return;
+        }
+
+        let lower = name.to_lowercase();
+        let name = if lower.ends_with(format.extension()) {
+            name.to_string()
+        } else {
+            format!("{}{}", name, format.extension())
+        };
+        let archive_path = self.active_panel().path.join(&name);
+        if archive_path.exists() {
+            self.active_panel_mut().error = Some(format!("{} already exists", name));
+            return;
+        }
+
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        self.cancel_token = Some(cancel.clone());
+        self.background_task = Some(super::background::BackgroundTask::pack(
+            sources, archive_path, format, level, cancel,
+        ));
+        self.mode = Mode::FileOpProgress {
+            title: "Packing".to_string(),
+            bytes_done: 0,
+            bytes_total: 0,
+            current_file: String::new(),
+            files_done: 0,
+            files_total: 0,
+            frame: 0,
+        };
+    }
+
+    /// Create the directory with the given name
+    pub fn create_directory(&mut self, name: &str) {
+        if name.is_empty() {
+            self.active_panel_mut().error = Some("Directory name cannot be empty".to_string());
+            return;
+        }
+
+        let current_dir = self.active_panel().path.clone();
+        let is_remote = self.active_panel().is_remote();
+
+        let result = if is_remote {
+            // Create directory on remote
+            let new_dir = if current_dir.to_string_lossy() == "/" {
+                format!("/{}", name)
+            } else {
+                format!("{}/{}", current_dir.to_string_lossy().trim_end_matches('/'), name)
+            };
+            self.active_panel_mut().mkdir(&new_dir)
+                .and_then(|()| self.active_panel_mut().flush())
+        } else {
+            // Create directory locally
+            let new_dir_path = current_dir.join(name);
+            std::fs::create_dir(&new_dir_path).map_err(AppError::from).map(|()| {
+                self.journal.record(
+                    format!("Create directory {}", name),
+                    vec![crate::fs::undo::Change::Mkdir { path: new_dir_path }],
+                );
+            })
+        };
+
+        match result {
+            Ok(()) => {
+                self.add_shell_output(format!("Created directory: {}", name));
+                // Refresh the current panel and position cursor on the new directory
+                self.active_panel_mut().refresh_and_focus(name);
+                self.refresh_git_status();
+            }
+            Err(e) => {
+                self.active_panel_mut().error = Some(format!("Failed to create directory: {}", e));
+            }
+        }
+    }
+
+    // ========================================================================
+    // UNDO
+    // ========================================================================
+
+    /// Journal the items a finished local move moved
+    fn record_moves(&mut self, moved: &[(PathBuf, PathBuf)]) {
+        let description = match moved {
+            [] => return,
+            [(from, to)] if from.parent() == to.parent() => format!(
+                "Rename {} to {}",
+                from.file_name().unwrap_or_default().to_string_lossy(),
+                to.file_name().unwrap_or_default().to_string_lossy(),
+            ),
+            [(from, to)] => format!("Move {} to {}", from.display(), to.display()),
+            [(_, to), ..] => format!(
+                "Move {} item(s) to {}",
+                moved.len(),
+                to.parent().unwrap_or(to).display(),
+            ),
+        };
+        let changes = moved
+            .iter()
+            .map(|(from, to)| crate::fs::undo::Change::Move { from: from.clone(), to: to.clone() })
+            .collect();
+        self.journal.record(description, changes);
+    }
+
+    /// Undo the most recent journaled operation. The paths it touched are
+    /// checked in the background first, since there can be many.
+    pub fn undo_last_operation(&mut self) {
+        let Some(entry) = self.journal.last().cloned() else {
+            self.add_shell_output("Nothing to undo".to_string());
+            return;
+        };
+        let message = format!("Checking \"{}\"...", entry.description);
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        self.cancel_token = Some(cancel.clone());
+        self.background_task = Some(super::background::BackgroundTask::check_undo(entry, cancel));
+        self.mode = Mode::BackgroundTask {
+            title: "Undo".to_string(),
+            message,
+            frame: 0,
+            started: std::time::Instant::now(),
+        };
+    }
+
+    /// Undo `entry` once its paths are checked, asking first if any have
+    /// changed since
+    fn finish_undo_check(&mut self, entry: crate::fs::undo::Entry, changed: Vec<PathBuf>) {
+        if self.journal.last() != Some(&entry) {
+            self.add_shell_output("The undo journal changed while checking; undo again".to_string());
+            return;
+        }
+        if let Some(first) = changed.first() {
+            self.mode = Mode::SimpleConfirm {
+                message: format!(
+                    "{} path(s) changed since \"{}\" (first: {}). Undo anyway?",
+                    changed.len(), entry.description, first.display(),
+                ),
+                action: SimpleConfirmAction::Undo,
+                focus: 1,
+            };
+            return;
+        }
+        self.perform_undo();
+    }
+
+    /// Replay the inverse of the most recent journaled operation in the
+    /// background; a move back across filesystems or a whole tree can take
+    /// a while
+    fn perform_undo(&mut self) {
+        let Some(entry) = self.journal.pop() else {
+            return;
+        };
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        self.cancel_token = Some(cancel.clone());
+        let files_total = entry.steps.len();
+        self.background_task = Some(super::background::BackgroundTask::undo(entry, cancel));
+        self.mode = Mode::FileOpProgress {
+            title: "Undoing".to_string(),
+            bytes_done: 0,
+            bytes_total: 0,
+            current_file: String::new(),
+            files_done: 0,
+            files_total,
+            frame: 0,
+        };
+    }
+
+    /// Report a finished undo
+    fn finish_undo(&mut self, description: String, count: usize, errors: Vec<String>, rest: Option<crate::fs::undo::Entry>) {
+        // What couldn't be reverted stays in the journal to retry
+        let cancelled = rest.is_some() && errors.is_empty();
+        if let Some(rest) = rest {
+            self.journal.push(rest);
+        }
+        self.left_panel.refresh();
+        self.right_panel.refresh();
+        self.refresh_git_status();
+        if let Some(first) = errors.first() {
+            self.active_panel_mut().error = Some(format!(
+                "Undo \"{}\": reverted {}, {} errors: {}",
+                description, count, errors.len(), first
+            ));
+            for err in &errors {
+                self.add_shell_output(format!("  Error: {}", err));
+            }
+        } else if cancelled {
+            self.add_shell_output(format!("Undo of \"{}\" cancelled after {} step(s)", description, count));
+        } else {
+            self.add_shell_output(format!("Undone: {}", description));
         }
     }
 
@@ -2592,6 +3257,7 @@ impl App {
             &self.config.plugin_connections,
             &self.config.favorites,
             &plugin_summaries,
+            &crate::providers::ssh_config::SshConfig::load().host_aliases(),
         );
 
         if sources.is_empty() {
@@ -2756,6 +3422,9 @@ impl App {
                 let scheme = scheme.clone();
                 self.show_plugin_connect_dialog(target, &scheme);
             }
+            PanelSource::Trash => {
+                self.show_trash_browser(0);
+            }
         }
     }
 
@@ -2836,10 +3505,40 @@ impl App {
                     self.add_shell_output("Favorite removed".to_string());
                 }
             }
-            SimpleConfirmAction::LargeRemoteTransfer { operation, sources, dest } => {
+            SimpleConfirmAction::LargeRemoteTransfer { operation, sources, dest, resume } => {
                 // User confirmed the large transfer — bypass size guard
-                self.dispatch_remote_file_operation(operation, sources, dest);
+                self.dispatch_remote_file_operation(operation, sources, dest, resume);
             }
+            SimpleConfirmAction::PurgeTrashed { item, cursor } => {
+                match crate::fs::trash::purge(&item) {
+                    Ok(()) => self.add_shell_output(format!("Deleted {} from the trash", item.original.display())),
+                    Err(e) => self.active_panel_mut().error = Some(format!("Failed to delete: {}", e)),
+                }
+                self.show_trash_browser(cursor);
+            }
+            SimpleConfirmAction::EmptyTrash => {
+                let (count, errors) = crate::fs::trash::empty();
+                if let Some(first) = errors.first() {
+                    self.active_panel_mut().error = Some(format!(
+                        "Deleted {}, {} errors: {}", count, errors.len(), first
+                    ));
+                } else {
+                    self.add_shell_output(format!("Emptied the trash ({} item(s))", count));
+                }
+                self.show_trash_browser(0);
+            }
+            SimpleConfirmAction::Undo => self.perform_undo(),
+            SimpleConfirmAction::Quit => self.should_quit = true,
+        }
+    }
+
+    /// Go back to where a simple confirmation was asked from, after "No"
+    pub fn cancel_simple_confirm_action(&mut self, action: SimpleConfirmAction) {
+        self.mode = Mode::Normal;
+        match action {
+            SimpleConfirmAction::PurgeTrashed { cursor, .. } => self.show_trash_browser(cursor),
+            SimpleConfirmAction::EmptyTrash => self.show_trash_browser(0),
+            _ => {}
         }
     }
 
@@ -2928,13 +3627,17 @@ impl App {
             return;
         }
 
+        // Blank user/port fall back to ~/.ssh/config for this host
+        let ssh_config = crate::providers::ssh_config::SshConfig::load();
+        let host = host_input.trim().to_string();
+        let host_config = ssh_config.resolve(&host);
         let user = if user_input.trim().is_empty() {
-            "root".to_string()
+            host_config.user.unwrap_or_else(|| "root".to_string())
         } else {
             user_input.trim().to_string()
         };
-        let host = host_input.trim().to_string();
-        let port: u16 = port_input.parse().unwrap_or(22);
+        // The dialog pre-fills 22, so only a non-default port overrides the config
+        let port = port_input.trim().parse().ok().filter(|&port| port != 22);
         let initial_path = if path_input.trim().is_empty() {
             format!("/home/{}", user)
         } else {
@@ -2945,11 +3648,13 @@ impl App {
 
         // Create connection info with appropriate auth
         let conn_info = if password.is_empty() {
-            ScpConnectionInfo::with_agent(user.clone(), host.clone()).port(port)
+            ScpConnectionInfo::with_agent(user.clone(), host.clone())
         } else {
-            ScpConnectionInfo::with_password(user.clone(), host.clone(), password).port(port)
+            ScpConnectionInfo::with_password(user.clone(), host.clone(), password)
         };
+        let conn_info = conn_info.apply_ssh_config(&ssh_config, Some(&user), port);
 
+        let conn_info = self.with_session_host_keys(conn_info);
         let display_name = format!("{}@{}", user, host);
 
         // Spawn background connection task
@@ -2987,6 +3692,7 @@ impl App {
             return;
         };
 
+        let conn_info = self.with_session_host_keys(conn_info);
         let initial_path = conn_info.initial_path.clone()
             .unwrap_or_else(|| format!("/home/{}", conn_info.user));
         let display_name = conn_info.display_name();
@@ -3043,6 +3749,7 @@ impl App {
 
         // Set password auth
         conn_info.auth = ScpAuth::Password(password);
+        let conn_info = self.with_session_host_keys(conn_info);
 
         // Spawn background connection task
         let connection_key = Some(conn_str.clone());
@@ -3064,6 +3771,61 @@ impl App {
         };
     }
 
+    /// Pass along host keys the user trusted earlier in this session
+    fn with_session_host_keys(&self, mut conn_info: ScpConnectionInfo) -> ScpConnectionInfo {
+        conn_info.session_host_keys = self.session_host_keys.clone();
+        conn_info
+    }
+
+    /// Retry an SCP connection after the user accepted an unknown host key.
+    /// With `save` the key is also appended to known_hosts.
+    pub fn confirm_host_key(&mut self, save: bool) {
+        use super::background::BackgroundTask;
+
+        let Mode::HostKeyConfirm {
+            target_panel,
+            conn_info,
+            initial_path,
+            display_name,
+            connection_string,
+            connection_key,
+            key_host,
+            fingerprint,
+            ..
+        } = &self.mode else {
+            return;
+        };
+
+        let trust = if save {
+            HostKeyTrust::AcceptAndSave(fingerprint.clone())
+        } else {
+            // Remember for the rest of the session so a follow-up password
+            // prompt or reconnect doesn't ask again
+            self.session_host_keys.insert(key_host.clone(), fingerprint.clone());
+            HostKeyTrust::AcceptOnce(fingerprint.clone())
+        };
+        let conn_info = self.with_session_host_keys(*conn_info.clone()).trust_host_key(trust);
+        let target = *target_panel;
+        let display = display_name.clone();
+
+        let task = BackgroundTask::connect_scp(
+            conn_info,
+            target,
+            initial_path.clone(),
+            display.clone(),
+            connection_string.clone(),
+            connection_key.clone(),
+        );
+
+        self.background_task = Some(task);
+        self.mode = Mode::BackgroundTask {
+            title: "Connecting".to_string(),
+            message: format!("Connecting to {}...", display),
+            frame: 0,
+            started: std::time::Instant::now(),
+        };
+    }
+
     /// Connect to an encrypted archive with the password from the dialog
     pub fn connect_archive_with_password(&mut self) {
         use super::background::BackgroundTask;
@@ -3460,6 +4222,19 @@ impl App {
         } else {
             self.view_file_remote(path);
         }
+        self.ui.viewer_notice = match &self.mode {
+            Mode::Viewing { content, .. } if content.encoding().is_none() => encoding::undecoded_notice(content.raw_bytes()),
+            _ => None,
+        };
+    }
+
+    /// Grammar to highlight a viewed file with, unless highlighting is off
+    pub fn viewer_syntax(&self, path: &Path, content: &ViewContent) -> Option<&'static Grammar> {
+        if !self.config.viewer.syntax_highlighting {
+            return None;
+        }
+        let first_line = content.line_text(0)?;
+        syntax::detect(path, &first_line, &self.config.viewer.syntax_map)
     }
 
     /// View a local file using memory mapping (efficient for large files)
@@ -3502,6 +4277,7 @@ impl App {
                 binary_mode: BinaryViewMode::Cp437,
                 search_matches: Vec::new(),
                 current_match: None,
+                wrap_row: 0,
             };
             return;
         }
@@ -3516,34 +4292,21 @@ impl App {
             }
         };
 
-        // Check if content is valid UTF-8
-        let bytes: &[u8] = &mmap;
-        let is_text = std::str::from_utf8(bytes).is_ok();
-
-        // Compute line offsets for text files (scan once, enables O(1) line lookup)
-        let line_offsets = if is_text {
-            compute_line_offsets(bytes)
-        } else {
-            Vec::new()
-        };
-
-        let binary_mode = if is_text {
+        let content = ViewContent::from_mmap(mmap);
+        let binary_mode = if content.encoding().is_some() {
             BinaryViewMode::Cp437  // Text view
         } else {
             BinaryViewMode::Hex    // Binary view
         };
 
         self.mode = Mode::Viewing {
-            content: ViewContent::MappedFile {
-                mmap,
-                is_text,
-                line_offsets,
-            },
+            content,
             scroll: 0,
             path: path.to_path_buf(),
             binary_mode,
             search_matches: Vec::new(),
             current_match: None,
+            wrap_row: 0,
         };
     }
 
@@ -3554,31 +4317,35 @@ impl App {
 
         match bytes_result {
             Ok(bytes) => {
-                // Try to interpret as UTF-8 text first
-                match String::from_utf8(bytes.clone()) {
-                    Ok(text) => {
-                        let line_offsets = compute_line_offsets(text.as_bytes());
-                        self.mode = Mode::Viewing {
-                            content: ViewContent::Text(text, line_offsets),
-                            scroll: 0,
-                            path: path.to_path_buf(),
-                            binary_mode: BinaryViewMode::Cp437,  // Default to text view
-                            search_matches: Vec::new(),
-                            current_match: None,
-                        };
-                    }
-                    Err(_) => {
-                        // Not valid UTF-8, show as binary
-                        self.mode = Mode::Viewing {
-                            content: ViewContent::Binary(bytes),
-                            scroll: 0,
-                            path: path.to_path_buf(),
-                            binary_mode: BinaryViewMode::Hex,
-                            search_matches: Vec::new(),
-                            current_match: None,
-                        };
+                // UTF-8 is shown as is, other text is decoded and anything
+                // else opens in hex
+                let content = match encoding::detect(&bytes) {
+                    Some(TextEncoding::Utf8) => match String::from_utf8(bytes) {
+                        Ok(text) => {
+                            let line_offsets = compute_line_offsets(text.as_bytes());
+                            ViewContent::Text(text, line_offsets)
+                        }
+                        Err(e) => ViewContent::Binary(e.into_bytes()),
+                    },
+                    Some(encoding) if bytes.len() <= encoding::AUTO_DECODE_LIMIT => {
+                        ViewContent::Binary(bytes).reencode(encoding)
                     }
-                }
+                    _ => ViewContent::Binary(bytes),
+                };
+                let binary_mode = if content.encoding().is_some() {
+                    BinaryViewMode::Cp437  // Text view
+                } else {
+                    BinaryViewMode::Hex
+                };
+                self.mode = Mode::Viewing {
+                    content,
+                    scroll: 0,
+                    path: path.to_path_buf(),
+                    binary_mode,
+                    search_matches: Vec::new(),
+                    current_match: None,
+                    wrap_row: 0,
+                };
             }
             Err(e) => {
                 // Check if this is a password_required error from an archive provider
@@ -3586,7 +4353,8 @@ impl App {
                     &e,
                     crate::errors::AppError::Provider(crate::providers::ProviderError::PasswordRequired(_))
                 );
-                if is_password_required {
+                // Only prompt if the provider can actually take a password
+                if is_password_required && self.active_panel().supports(Capability::SetPassword) {
                     let target = self.active_panel;
                     if let Some((archive_path, archive_name)) = self.active_panel().archive_source() {
                         self.mode = Mode::ArchivePasswordPrompt {
@@ -3644,10 +4412,13 @@ impl App {
                 height: 1_000_000,
                 scroll: 0,
                 config: self.plugin_config(),
+                sort: None,
             };
 
             if let Some(result) = plugin.render(&context) {
                 let total = result.total_lines;
+                let outline = ViewerOutline::new(result.lines.len(), result.folds, result.paths);
+                let table = ViewerTable::new(result.header, result.columns, None);
                 self.mode = Mode::ViewingPlugin {
                     plugin_name: plugin.info().name.clone(),
                     path: path.to_path_buf(),
@@ -3655,6 +4426,8 @@ impl App {
                     lines: result.lines,
                     total_lines: total,
                     status_message: None,
+                    outline,
+                    table,
                 };
                 return;
             }
@@ -3664,39 +4437,43 @@ impl App {
         self.view_file(path);
     }
 
-    /// Re-render plugin viewer content (full reload from plugin).
-    /// Only needed for resize or plugin switch — not for scrolling.
-    #[allow(dead_code)]
-    pub fn refresh_plugin_viewer(&mut self) {
-        if let Mode::ViewingPlugin { plugin_name, path, scroll, .. } = &self.mode {
-            let plugin_name = plugin_name.clone();
-            let path = path.clone();
-            let scroll = *scroll;
-
-            // Find the plugin again and re-render (all lines)
-            if let Some(plugin) = self.plugins.find_viewer(&path)
-                && plugin.info().name == plugin_name {
-                    let context = ViewerContext {
-                        path: path.clone(),
-                        width: self.ui.terminal_width as usize,
-                        height: 1_000_000,
-                        scroll: 0,
-                        config: self.plugin_config(),
-                    };
+    /// Sort the table in the plugin viewer by the selected column (then
+    /// descending, then unsorted), which the plugin renders anew. The
+    /// selected column and horizontal position stay.
+    pub fn sort_plugin_viewer(&mut self) {
+        let Mode::ViewingPlugin { plugin_name, path, table: Some(table), .. } = &self.mode else {
+            return;
+        };
+        let Some(plugin) = self.plugins.find_viewer_by_name(plugin_name) else {
+            return;
+        };
+        let sort = table.next_sort();
+        let context = ViewerContext {
+            path: path.clone(),
+            width: self.ui.terminal_width as usize,
+            height: 1_000_000,
+            scroll: 0,
+            config: self.plugin_config(),
+            sort,
+        };
+        let Some(result) = plugin.render(&context) else {
+            if let Mode::ViewingPlugin { status_message, .. } = &mut self.mode {
+                *status_message = Some("Sorting failed".to_string());
+            }
+            return;
+        };
 
-                    if let Some(result) = plugin.render(&context) {
-                        let total = result.total_lines;
-                        self.mode = Mode::ViewingPlugin {
-                            plugin_name,
-                            path,
-                            scroll,
-                            lines: result.lines,
-                            total_lines: total,
-                            status_message: None,
-                        };
-                    }
-                }
-        }
+        let Mode::ViewingPlugin { lines, total_lines, table, .. } = &mut self.mode else {
+            return;
+        };
+        let (column, hscroll) = table.as_ref().map_or((0, 0), |t| (t.column, t.hscroll));
+        *table = ViewerTable::new(result.header, result.columns, sort).map(|mut t| {
+            t.column = column.min(t.columns.len().saturating_sub(1));
+            t.hscroll = hscroll;
+            t
+        });
+        *lines = result.lines;
+        *total_lines = result.total_lines;
     }
 
     /// Get status context for plugins
@@ -3809,6 +4586,7 @@ impl App {
                 binary_mode,
                 search_matches: Vec::new(),
                 current_match: None,
+                wrap_row: 0,
             };
         } else if let Some((plugin_name, can_handle)) = plugins.get(index - 1) {
             if *can_handle {
@@ -3830,10 +4608,13 @@ impl App {
                     height: 1_000_000,
                     scroll: 0,
                     config: self.plugin_config(),
+                    sort: None,
                 };
 
                 if let Some(plugin) = self.plugins.find_viewer_by_name(plugin_name)
                     && let Some(result) = plugin.render(&context) {
+                        let outline = ViewerOutline::new(result.lines.len(), result.folds, result.paths);
+                        let table = ViewerTable::new(result.header, result.columns, None);
                         self.mode = Mode::ViewingPlugin {
                             plugin_name: plugin_name.clone(),
                             path,
@@ -3841,6 +4622,8 @@ impl App {
                             lines: result.lines,
                             total_lines: result.total_lines,
                             status_message: None,
+                            outline,
+                            table,
                         };
                         return;
                     }
@@ -3853,6 +4636,7 @@ impl App {
                 binary_mode,
                 search_matches: Vec::new(),
                 current_match: None,
+                wrap_row: 0,
             };
         }
     }
@@ -3870,12 +4654,167 @@ impl App {
             binary_mode: *binary_mode,
             search_matches: Vec::new(),
             current_match: None,
+            wrap_row: 0,
+        };
+    }
+
+    /// Read the viewed file in the next or previous encoding (e / E)
+    pub fn viewer_cycle_encoding(&mut self, forward: bool) {
+        let Mode::Viewing { content, scroll, binary_mode, search_matches, current_match, wrap_row, .. } = &mut self.mode else {
+            return;
+        };
+
+        let encoding = match content.encoding() {
+            Some(current) if forward => current.next(),
+            Some(current) => current.prev(),
+            None if forward => TextEncoding::ALL[0],
+            None => TextEncoding::ALL[TextEncoding::ALL.len() - 1],
+        };
+        let previous = std::mem::replace(content, ViewContent::Binary(Vec::new()));
+        *content = previous.reencode(encoding);
+        *binary_mode = BinaryViewMode::Cp437;
+
+        // Lines and byte offsets both move when the text is decoded anew
+        search_matches.clear();
+        *current_match = None;
+        let line_count = content.text_lines(BinaryViewMode::Cp437).map_or(0, |(_, offsets)| offsets.len());
+        *scroll = (*scroll).min(line_count.saturating_sub(1));
+        *wrap_row = 0;
+    }
+
+    /// Start or stop following the viewed file as it grows (F). Starting
+    /// jumps to the end; only files on a local panel can be followed.
+    pub fn viewer_toggle_follow(&mut self) {
+        if self.ui.viewer_follow.take().is_some() {
+            return;
+        }
+        let Mode::Viewing { path, content, .. } = &self.mode else {
+            return;
+        };
+        if !self.active_panel().is_local() {
+            return;
+        }
+        self.ui.viewer_follow = Some(FileFollower::new(path, content.raw_bytes().len() as u64));
+
+        let term_width = self.ui.terminal_width as usize;
+        let layout = self.ui.viewer_layout;
+        let visible_height = self.ui.viewer_height;
+        if let Mode::Viewing { content, scroll, binary_mode, wrap_row, .. } = &mut self.mode {
+            (*scroll, *wrap_row) = FileViewer::end_position(content, *binary_mode, layout, term_width, visible_height);
+        }
+    }
+
+    /// Take in data appended to a followed file, or the new file after a
+    /// truncation or rotation. The viewer stays at the end unless it was
+    /// scrolled up. Returns true if the content changed.
+    pub fn poll_viewer_follow(&mut self) -> bool {
+        if self.ui.viewer_follow.is_none() {
+            return false;
+        }
+        match &self.mode {
+            Mode::Viewing { .. } => {}
+            // Following pauses under the viewer's dialogs and stops with it
+            Mode::ViewerSearch { .. } | Mode::ViewerSearching { .. } | Mode::ViewerMatches { .. } | Mode::ViewerPluginMenu { .. } => {
+                return false;
+            }
+            _ => {
+                self.ui.viewer_follow = None;
+                return false;
+            }
+        }
+
+        let term_width = self.ui.terminal_width as usize;
+        let layout = self.ui.viewer_layout;
+        let visible_height = self.ui.viewer_height;
+        let (Some(follower), Mode::Viewing { content, scroll, path, binary_mode, search_matches, current_match, wrap_row }) =
+            (&mut self.ui.viewer_follow, &mut self.mode)
+        else {
+            return false;
+        };
+        let change = follower.poll(path);
+        if change == FollowChange::Unchanged {
+            return false;
+        }
+
+        // An empty file can't be mapped; a file that can't be read right
+        // now is tried again on the next change
+        let mapped = match std::fs::File::open(&path) {
+            Ok(file) if file.metadata().is_ok_and(|meta| meta.len() == 0) => None,
+            Ok(file) => match unsafe { memmap2::Mmap::map(&file) } {
+                Ok(mmap) => Some(std::sync::Arc::new(mmap)),
+                Err(_) => return false,
+            },
+            Err(_) => return false,
+        };
+
+        let at_end = (*scroll, *wrap_row) >= FileViewer::end_position(content, *binary_mode, layout, term_width, visible_height);
+        let previous = std::mem::replace(content, ViewContent::Binary(Vec::new()));
+        let was_text = previous.encoding().is_some();
+        *content = match (change, mapped) {
+            (_, None) => ViewContent::Text(String::new(), vec![0]),
+            (FollowChange::Appended, Some(mmap)) => previous.append_mapped(mmap),
+            (_, Some(mmap)) => ViewContent::from_mmap(mmap),
+        };
+        if change == FollowChange::Replaced {
+            search_matches.clear();
+            *current_match = None;
+        }
+        if content.encoding().is_some() != was_text {
+            *binary_mode = if content.encoding().is_some() { BinaryViewMode::Cp437 } else { BinaryViewMode::Hex };
+        }
+
+        let end = FileViewer::end_position(content, *binary_mode, layout, term_width, visible_height);
+        if at_end || (*scroll, *wrap_row) > end {
+            (*scroll, *wrap_row) = end;
+        }
+        true
+    }
+
+    /// Open a local file in the built-in hex editor (F4 in the viewer)
+    pub fn open_hex_editor(&mut self, path: &Path) {
+        if !self.active_panel().is_local() {
+            return;
+        }
+        match EditBuffer::open(path) {
+            Ok(buffer) => {
+                self.mode = Mode::HexEditing {
+                    editor: HexEditor::new(path.to_path_buf(), buffer),
+                };
+            }
+            Err(e) => {
+                self.active_panel_mut().error = Some(format!("Cannot edit '{}': {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Write the hex editor's changes to its file
+    pub fn save_hex_editor(&mut self) {
+        let Mode::HexEditing { editor } = &mut self.mode else {
+            return;
         };
+        let in_place = editor.buffer.saves_in_place();
+        editor.message = Some(match editor.buffer.save(&editor.path) {
+            Ok(()) if in_place => "Saved".to_string(),
+            Ok(()) => format!("Saved, {} bytes written", editor.buffer.len()),
+            Err(e) => format!("Save failed: {}", e),
+        });
+        editor.move_to(editor.cursor);
+    }
+
+    /// Leave the hex editor for the viewer, showing the file as saved
+    pub fn close_hex_editor(&mut self) {
+        let Mode::HexEditing { editor } = std::mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+        let path = editor.path;
+        // Release the file's map before it is viewed again
+        drop(editor.buffer);
+        self.view_file(&path);
     }
 
     /// Show the viewer search dialog (/)
     pub fn show_viewer_search(&mut self) {
-        let Mode::Viewing { content, scroll, path, binary_mode, search_matches, current_match } = &self.mode else {
+        let Mode::Viewing { content, scroll, path, binary_mode, search_matches, current_match, .. } = &self.mode else {
             return;
         };
 
@@ -3889,125 +4828,196 @@ impl App {
             text_input: String::new(),
             text_cursor: 0,
             case_sensitive: false,
+            regex: false,
+            multiline: false,
             hex_input: String::new(),
             hex_cursor: 0,
             focus: 0,
+            error: None,
         };
     }
 
-    /// Execute viewer search
-    pub fn execute_viewer_search(&mut self) {
+    /// Run the search set up in the viewer search dialog. Large mapped
+    /// files are searched in the background; with `list` the matches open
+    /// in the match list once found.
+    pub fn execute_viewer_search(&mut self, list: bool) {
         let Mode::ViewerSearch {
-            content, path, binary_mode,
-            text_input, case_sensitive, hex_input, ..
-        } = &self.mode else {
+            content, text_input, case_sensitive, regex, multiline, hex_input, error, ..
+        } = &mut self.mode else {
             return;
         };
 
-        let content = content.clone();
-        let path = path.clone();
-        let binary_mode = *binary_mode;
-        let text_input = text_input.clone();
-        let case_sensitive = *case_sensitive;
-        let hex_input = hex_input.clone();
-
-        // Get the bytes to search in
-        let bytes: &[u8] = match &content {
-            ViewContent::Text(text, _) => text.as_bytes(),
-            ViewContent::Binary(data) => data,
-            ViewContent::MappedFile { mmap, .. } => mmap,
-        };
-
-        let mut matches: Vec<(usize, usize)> = Vec::new();
-
-        // Try hex search first if hex input is provided
-        if !hex_input.is_empty() {
-            if let Some(hex_bytes) = parse_hex_string(&hex_input)
-                && !hex_bytes.is_empty() {
-                    // Search for hex pattern
-                    let mut pos = 0;
-                    while pos + hex_bytes.len() <= bytes.len() {
-                        if bytes[pos..].starts_with(&hex_bytes) {
-                            matches.push((pos, hex_bytes.len()));
-                            pos += 1; // Allow overlapping matches
-                        } else {
-                            pos += 1;
-                        }
-                    }
-                }
-        }
-        // Otherwise do text search
-        else if !text_input.is_empty() {
-            // Convert wildcard pattern to search
-            let search_pattern = text_input.trim();
-            if !search_pattern.is_empty() {
-                // Check if pattern contains wildcards
-                if search_pattern.contains('*') {
-                    // Build regex from wildcard pattern
-                    let regex_str = wildcard_to_regex(search_pattern, case_sensitive);
-                    if let Ok(regex) = regex::Regex::new(&regex_str) {
-                        for m in regex.find_iter(&String::from_utf8_lossy(bytes)) {
-                            matches.push((m.start(), m.len()));
-                        }
-                    }
-                } else {
-                    // Simple substring search
-                    // IMPORTANT: We must search in the original bytes to get correct offsets.
-                    // Converting to lowercase can change byte lengths for Unicode characters.
-                    let search_bytes = search_pattern.as_bytes();
-                    let search_len = search_bytes.len();
-
-                    if case_sensitive {
-                        // Case-sensitive: direct byte comparison
-                        let mut pos = 0;
-                        while pos + search_len <= bytes.len() {
-                            if bytes[pos..].starts_with(search_bytes) {
-                                matches.push((pos, search_len));
-                                pos += 1;
-                            } else {
-                                pos += 1;
-                            }
-                        }
-                    } else {
-                        // Case-insensitive: compare using ASCII case folding on original bytes
-                        let search_lower: Vec<u8> = search_bytes.iter()
-                            .map(|b| b.to_ascii_lowercase())
-                            .collect();
-
-                        let mut pos = 0;
-                        while pos + search_len <= bytes.len() {
-                            let matches_here = bytes[pos..pos + search_len]
-                                .iter()
-                                .zip(search_lower.iter())
-                                .all(|(a, b)| a.to_ascii_lowercase() == *b);
-
-                            if matches_here {
-                                matches.push((pos, search_len));
-                            }
-                            pos += 1;
-                        }
-                    }
-                }
+        // Hex input wins when both are filled in
+        let query = if !hex_input.trim().is_empty() {
+            SearchQuery::hex(hex_input)
+        } else {
+            SearchQuery::text(text_input.trim(), *case_sensitive, *regex, *multiline)
+        };
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => {
+                *error = Some(e);
+                return;
             }
+        };
+
+        let mmap = match content {
+            ViewContent::MappedFile { mmap, .. } if mmap.len() > search::BACKGROUND_SEARCH_SIZE => Some(mmap.clone()),
+            _ => None,
+        };
+        let Mode::ViewerSearch {
+            content, scroll, path, binary_mode, prev_matches, prev_current, ..
+        } = std::mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+
+        if let Some(mmap) = mmap {
+            let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let progress = std::sync::Arc::new(SearchProgress::default());
+            self.cancel_token = Some(cancel.clone());
+            self.background_task = Some(super::background::BackgroundTask::viewer_search(
+                mmap.clone(), query.clone(), progress.clone(), cancel,
+            ));
+            self.mode = Mode::ViewerSearching {
+                content,
+                scroll,
+                path,
+                binary_mode,
+                prev_matches,
+                prev_current,
+                description: query.description,
+                progress,
+                total: mmap.len(),
+                list,
+                frame: 0,
+                started: std::time::Instant::now(),
+            };
+            return;
         }
 
-        // Calculate scroll position to show first match
+        let cancel = std::sync::atomic::AtomicBool::new(false);
+        let matches = search::search(content.search_bytes(binary_mode), &query, &SearchProgress::default(), &cancel);
+        self.mode = Mode::Viewing {
+            content,
+            scroll,
+            path,
+            binary_mode,
+            search_matches: prev_matches,
+            current_match: prev_current,
+            wrap_row: 0,
+        };
+        self.show_viewer_search_results(matches, list);
+    }
+
+    /// Put the matches of a finished (or cancelled) background search into
+    /// the viewer it was started from
+    fn finish_viewer_search(&mut self, matches: Vec<(usize, usize)>) {
+        let Mode::ViewerSearching {
+            content, scroll, path, binary_mode, prev_matches, prev_current, list, ..
+        } = std::mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+        self.mode = Mode::Viewing {
+            content,
+            scroll,
+            path,
+            binary_mode,
+            search_matches: prev_matches,
+            current_match: prev_current,
+            wrap_row: 0,
+        };
+        self.show_viewer_search_results(matches, list);
+    }
+
+    /// Replace the viewer's matches and scroll to the first one. Finding
+    /// nothing keeps the viewer where it was.
+    fn show_viewer_search_results(&mut self, matches: Vec<(usize, usize)>, list: bool) {
         let term_width = self.ui.terminal_width as usize;
-        let (scroll, current_match) = if matches.is_empty() {
-            (0, None)
-        } else {
-            let first_match_offset = matches[0].0;
-            let scroll = content.byte_offset_to_line(first_match_offset, binary_mode, term_width);
-            (scroll, Some(0))
+        let layout = self.ui.viewer_layout;
+        let Mode::Viewing { content, scroll, binary_mode, search_matches, current_match, wrap_row, .. } = &mut self.mode else {
+            return;
+        };
+
+        *search_matches = matches;
+        *current_match = None;
+        if let Some(&(offset, _)) = search_matches.first() {
+            *current_match = Some(0);
+            (*scroll, *wrap_row) = content.byte_offset_to_position(offset, *binary_mode, term_width, layout);
+        }
+        if list {
+            self.show_viewer_matches();
+        }
+    }
+
+    /// Open the list of all search matches (L)
+    pub fn show_viewer_matches(&mut self) {
+        let Mode::Viewing { search_matches, .. } = &self.mode else {
+            return;
+        };
+        if search_matches.is_empty() {
+            return;
+        }
+        let Mode::Viewing {
+            content, scroll, path, binary_mode, search_matches, current_match, wrap_row,
+        } = std::mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+        self.mode = Mode::ViewerMatches {
+            content,
+            path,
+            binary_mode,
+            search_matches,
+            cursor: current_match.unwrap_or(0),
+            list_scroll: 0,
+            scroll,
+            wrap_row,
+            return_to: (scroll, wrap_row, current_match),
         };
+        self.viewer_matches_move(0);
+    }
 
+    /// Move the match list cursor by `delta` and scroll the preview to it
+    pub fn viewer_matches_move(&mut self, delta: isize) {
+        let term_width = self.ui.terminal_width as usize;
+        let layout = self.ui.viewer_layout;
+        let visible = self.ui.viewer_height.max(1);
+        let Mode::ViewerMatches { content, binary_mode, search_matches, cursor, list_scroll, scroll, wrap_row, .. } = &mut self.mode else {
+            return;
+        };
+
+        let last = search_matches.len().saturating_sub(1);
+        *cursor = cursor.saturating_add_signed(delta).min(last);
+        if *cursor < *list_scroll {
+            *list_scroll = *cursor;
+        } else if *cursor >= *list_scroll + visible {
+            *list_scroll = *cursor + 1 - visible;
+        }
+        if let Some(&(offset, _)) = search_matches.get(*cursor) {
+            (*scroll, *wrap_row) = content.byte_offset_to_position(offset, *binary_mode, term_width, layout);
+        }
+    }
+
+    /// Leave the match list, at the highlighted match or back where the
+    /// viewer was
+    pub fn close_viewer_matches(&mut self, go_to_match: bool) {
+        let Mode::ViewerMatches {
+            content, path, binary_mode, search_matches, cursor, scroll, wrap_row, return_to, ..
+        } = std::mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+        let (scroll, wrap_row, current_match) = if go_to_match {
+            (scroll, wrap_row, Some(cursor))
+        } else {
+            return_to
+        };
         self.mode = Mode::Viewing {
             content,
             scroll,
             path,
             binary_mode,
-            search_matches: matches,
+            search_matches,
             current_match,
+            wrap_row,
         };
     }
 
@@ -4026,14 +5036,16 @@ impl App {
             binary_mode: *binary_mode,
             search_matches: prev_matches.clone(),
             current_match: *prev_current,
+            wrap_row: 0,
         };
     }
 
     /// Navigate to next search match
     pub fn viewer_next_match(&mut self) {
         let term_width = self.ui.terminal_width as usize;
+        let layout = self.ui.viewer_layout;
 
-        let Mode::Viewing { search_matches, current_match, scroll, content, binary_mode, .. } = &mut self.mode else {
+        let Mode::Viewing { search_matches, current_match, scroll, wrap_row, content, binary_mode, .. } = &mut self.mode else {
             return;
         };
 
@@ -4048,14 +5060,15 @@ impl App {
 
         *current_match = Some(new_idx);
         let match_offset = search_matches[new_idx].0;
-        *scroll = content.byte_offset_to_line(match_offset, *binary_mode, term_width);
+        (*scroll, *wrap_row) = content.byte_offset_to_position(match_offset, *binary_mode, term_width, layout);
     }
 
     /// Navigate to previous search match
     pub fn viewer_prev_match(&mut self) {
         let term_width = self.ui.terminal_width as usize;
+        let layout = self.ui.viewer_layout;
 
-        let Mode::Viewing { search_matches, current_match, scroll, content, binary_mode, .. } = &mut self.mode else {
+        let Mode::Viewing { search_matches, current_match, scroll, wrap_row, content, binary_mode, .. } = &mut self.mode else {
             return;
         };
 
@@ -4076,7 +5089,7 @@ impl App {
 
         *current_match = Some(new_idx);
         let match_offset = search_matches[new_idx].0;
-        *scroll = content.byte_offset_to_line(match_offset, *binary_mode, term_width);
+        (*scroll, *wrap_row) = content.byte_offset_to_position(match_offset, *binary_mode, term_width, layout);
     }
 
     /// Convert byte offset to line number for scrolling
@@ -4243,12 +5256,32 @@ impl App {
                 }
             }
         }
+        // Tasks that check a cancel flag stop instead of running on unseen
+        if let Some(token) = self.cancel_token.take() {
+            token.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
         self.background_task = None;
+        self.job_control = None;
         self.mode = Mode::Normal;
         self.add_shell_output("Cancelled".to_string());
     }
 
     /// Check if a background task has completed and handle the result
+    /// Hand finished directory listings to their panels.
+    /// Returns true if any arrived.
+    pub fn poll_listings(&mut self) -> bool {
+        let mut any = false;
+        while let Some(response) = self.io.try_recv() {
+            any = true;
+            match response {
+                IoResponse::Listed { side, id, result } => {
+                    self.panel_mut(side).apply_io_listing(id, result);
+                }
+            }
+        }
+        any
+    }
+
     pub fn poll_background_task(&mut self) {
         use super::background::TaskResult;
 
@@ -4286,6 +5319,23 @@ impl App {
                         self.mode = Mode::Normal;
                     }
                 }
+                TaskResult::ScpHostKeyUnknown {
+                    target, conn_info, initial_path, display_name,
+                    connection_string, connection_key, key_host, key_type, fingerprint,
+                } => {
+                    self.mode = Mode::HostKeyConfirm {
+                        target_panel: target,
+                        conn_info,
+                        initial_path,
+                        display_name,
+                        connection_string,
+                        connection_key,
+                        key_host,
+                        key_type,
+                        fingerprint,
+                        focus: 0,
+                    };
+                }
                 TaskResult::PluginConnected { target, provider, initial_path, display_name, is_extension_mode, source_path, source_name, connection_key } => {
                     let panel = match target {
                         Side::Left => &mut self.left_panel,
@@ -4338,29 +5388,42 @@ impl App {
                 }
                 TaskResult::FileOpCompleted(result) => {
                     self.cancel_token = None;
+                    self.job_control = None;
                     // Clear selection after copy (keep for move so user sees what wasn't moved)
                     if result.op_name != "Moved" {
                         self.active_panel_mut().selected.clear();
                     }
-                    // Refresh both panels and git status
-                    self.left_panel.refresh();
-                    self.right_panel.refresh();
-                    self.refresh_git_status();
-
-                    if !result.errors.is_empty() {
-                        self.active_panel_mut().error = Some(format!(
-                            "{} {}, {} errors",
-                            result.op_name,
-                            result.count,
-                            result.errors.len(),
-                        ));
-                        for err in &result.errors {
-                            self.add_shell_output(format!("  Error: {}", err));
-                        }
-                    } else {
-                        self.add_shell_output(format!("{} {} file(s)", result.op_name, result.count));
-                    }
+                    self.finish_file_operation(&result);
+                    self.mode = Mode::Normal;
+                }
+                TaskResult::CompareCompleted { items, synchronize } => {
+                    self.cancel_token = None;
+                    self.mode = Mode::Normal;
+                    self.finish_compare(items, synchronize);
+                }
+                TaskResult::ViewerSearchCompleted(matches) => {
+                    self.cancel_token = None;
+                    self.finish_viewer_search(matches);
+                }
+                TaskResult::UndoCompleted { description, count, errors, rest } => {
+                    self.cancel_token = None;
+                    self.mode = Mode::Normal;
+                    self.finish_undo(description, count, errors, rest);
+                }
+                TaskResult::UndoChecked { entry, changed } => {
+                    self.cancel_token = None;
+                    self.mode = Mode::Normal;
+                    self.finish_undo_check(entry, changed);
+                }
+                TaskResult::DiffTreeCompleted(tree) => {
+                    self.cancel_token = None;
+                    self.mode = Mode::Normal;
+                    self.show_tree_diff(tree);
+                }
+                TaskResult::FindCompleted { matches, description, cancelled, side, remote } => {
+                    self.cancel_token = None;
                     self.mode = Mode::Normal;
+                    self.show_find_results(matches, &description, cancelled, side, remote);
                 }
                 TaskResult::CommandCompleted { stdout, stderr, command, cwd } => {
                     #[cfg(windows)]
@@ -4544,9 +5607,112 @@ impl App {
         // The thread will finish and send FileOpCompleted; poll_background_task handles cleanup
     }
 
+    /// Record and report a finished copy or move, whether it ran in
+    /// the foreground or as a job
+    fn finish_file_operation(&mut self, result: &super::background::FileOpResult) {
+        self.record_moves(&result.moved);
+        // Refresh both panels and git status
+        self.left_panel.refresh();
+        self.right_panel.refresh();
+        self.refresh_git_status();
+
+        if !result.errors.is_empty() {
+            self.active_panel_mut().error = Some(format!(
+                "{} {}, {} errors",
+                result.op_name,
+                result.count,
+                result.errors.len(),
+            ));
+            for err in &result.errors {
+                self.add_shell_output(format!("  Error: {}", err));
+            }
+        } else {
+            self.add_shell_output(format!("{} {} file(s)", result.op_name, result.count));
+        }
+        if !result.skipped.is_empty() {
+            self.add_shell_output(format!(
+                "Skipped {} special file(s) (FIFOs, sockets, devices):",
+                result.skipped.len(),
+            ));
+            for path in &result.skipped {
+                self.add_shell_output(format!("  {}", path.display()));
+            }
+        }
+    }
+
+    /// What a copy or move does, for the job list: "Copy 3 item(s) to /mnt/nas"
+    fn job_description(operation: &FileOperation, sources: &[PathBuf], dest: &Path) -> String {
+        let verb = match operation {
+            FileOperation::Copy => "Copy",
+            FileOperation::Move => "Move",
+            FileOperation::Delete | FileOperation::Trash => unreachable!(),
+        };
+        let what = match sources {
+            [single] => single.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            _ => format!("{} item(s)", sources.len()),
+        };
+        format!("{} {} to {}", verb, what, dest.display())
+    }
+
+    /// Hand the running copy or move to the job queue and go back to the
+    /// panels
+    pub fn send_to_background(&mut self) {
+        let Mode::FileOpProgress { bytes_done, bytes_total, current_file, files_done, files_total, .. } = &self.mode else {
+            return;
+        };
+        let progress = super::background::FileOpProgress {
+            bytes_done: *bytes_done,
+            bytes_total: *bytes_total,
+            current_file: current_file.clone(),
+            files_done: *files_done,
+            files_total: *files_total,
+        };
+        let Some(control) = self.job_control.take() else {
+            self.add_shell_output("Only copies and moves can run in the background".to_string());
+            return;
+        };
+        let Some(task) = self.background_task.take() else {
+            return;
+        };
+        self.cancel_token = None;
+        self.add_shell_output(format!("{}: running in the background (:jobs to follow it)", control.description));
+        self.jobs.push(task, control, progress);
+        self.mode = Mode::Normal;
+    }
+
+    /// Take in job progress and report the jobs that have finished
+    pub fn poll_jobs(&mut self) {
+        for (description, result) in self.jobs.poll() {
+            self.add_shell_output(format!("{}: done", description));
+            self.finish_file_operation(&result);
+        }
+    }
+
+    /// Quit, asking first if background jobs would be cut short
+    pub fn request_quit(&mut self) {
+        if self.jobs.is_empty() {
+            self.should_quit = true;
+            return;
+        }
+        self.mode = Mode::SimpleConfirm {
+            message: format!("{} background job(s) haven't finished. Quit anyway?", self.jobs.jobs().len()),
+            action: SimpleConfirmAction::Quit,
+            focus: 1,
+        };
+    }
+
+    /// Open the jobs panel
+    pub fn show_jobs(&mut self) {
+        if self.jobs.is_empty() {
+            self.add_shell_output("No background jobs".to_string());
+            return;
+        }
+        self.mode = Mode::Jobs { cursor: 0 };
+    }
+
     /// Advance the spinner animation frame
     pub fn tick_spinner(&mut self) {
-        if let Mode::BackgroundTask { frame, .. } = &mut self.mode {
+        if let Mode::BackgroundTask { frame, .. } | Mode::Searching { frame, .. } | Mode::ViewerSearching { frame, .. } = &mut self.mode {
             *frame = frame.wrapping_add(1);
         }
         if let Mode::FileOpProgress { frame, .. } = &mut self.mode {
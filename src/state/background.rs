@@ -1,19 +1,36 @@
 //! Background task handling for async operations
 
-use std::path::PathBuf;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread::{self, JoinHandle};
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
+use crate::async_io::SharedProvider;
+use crate::fs::FileEntry;
 use crate::plugins::provider_api::PluginProviderAdapter;
 use bark_plugin_api::{ProviderConfig, ProviderPlugin};
-use crate::providers::{PanelProvider, ScpConnectionInfo, ScpProvider};
+use crate::providers::{Capability, PanelProvider, ProviderError, ProviderWriter, ScpConnectionInfo, ScpProvider};
 use crate::state::mode::FileOperation;
-use crate::fs::utils::{copy_path_with_progress, move_path_with_progress, calculate_total_bytes};
+use crate::fs::compare::{self, CompareItem, SyncAction, SyncItem};
+use crate::fs::diff::{self, TreeDiff};
+use crate::fs::find::{self, FindProgress, FindQuery};
+use crate::fs::pack::{PackFormat, PackJob};
+use crate::fs::search::{self, SearchProgress, SearchQuery};
+use crate::fs::copy::{self, Copier, CopyOptions};
+use crate::fs::utils::{move_path_with_progress, calculate_total_bytes};
 use super::Side;
 
+/// Chunk size used when streaming file data between providers
+const STREAM_CHUNK_SIZE: usize = 256 * 1024;
+
+/// How often a paused worker checks whether it may go on
+const PAUSE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
 /// Progress update for file operations
 #[derive(Clone, Debug)]
 pub struct FileOpProgress {
@@ -44,6 +61,11 @@ pub struct FileOpResult {
     pub count: usize,
     pub errors: Vec<String>,
     pub op_name: String,
+    /// Source and destination of each item a local move moved, for the
+    /// undo journal
+    pub moved: Vec<(PathBuf, PathBuf)>,
+    /// Special files a local copy left out
+    pub skipped: Vec<PathBuf>,
 }
 
 /// Result of a background task
@@ -66,6 +88,19 @@ pub enum TaskResult {
         connection_string: Option<String>,
         display_name: String,
     },
+    /// SCP server presented a host key that isn't in known_hosts
+    ScpHostKeyUnknown {
+        target: Side,
+        conn_info: Box<ScpConnectionInfo>,
+        initial_path: String,
+        display_name: String,
+        connection_string: Option<String>,
+        connection_key: Option<String>,
+        /// "host:port" that presented the key (the target or a jump host)
+        key_host: String,
+        key_type: String,
+        fingerprint: String,
+    },
     /// Plugin provider connection succeeded
     PluginConnected {
         target: Side,
@@ -104,7 +139,43 @@ pub enum TaskResult {
     },
     /// File operation completed
     FileOpCompleted(FileOpResult),
-    /// Remote file operation completed (providers need to be restored to panels)
+    /// Find Files search finished or was cancelled
+    FindCompleted {
+        matches: Vec<FileEntry>,
+        /// What was searched for, e.g. "matching '*.rs'"
+        description: String,
+        cancelled: bool,
+        /// Panel the search started from
+        side: Side,
+        /// Matches came from that panel's remote or archive provider
+        remote: bool,
+    },
+    /// Content comparison of the two panels finished
+    CompareCompleted {
+        items: Vec<CompareItem>,
+        /// Open the Synchronize dialog instead of marking the differences
+        synchronize: bool,
+    },
+    /// Recursive diff of the two panels' trees finished
+    DiffTreeCompleted(TreeDiff),
+    /// The paths of an undo journal entry were checked
+    UndoChecked {
+        entry: crate::fs::undo::Entry,
+        /// Paths that changed since the operation
+        changed: Vec<PathBuf>,
+    },
+    /// An undo finished or was cancelled
+    UndoCompleted {
+        /// What was undone, from the journal entry
+        description: String,
+        count: usize,
+        errors: Vec<String>,
+        /// Steps that failed or weren't reached, to go back in the journal
+        rest: Option<crate::fs::undo::Entry>,
+    },
+    /// Viewer search of a large file finished or was cancelled
+    ViewerSearchCompleted(Vec<(usize, usize)>),
+    /// Synchronization completed (providers need to be restored to panels)
     RemoteFileOpCompleted {
         result: FileOpResult,
         /// Provider(s) to return to panels
@@ -115,6 +186,14 @@ pub enum TaskResult {
     },
 }
 
+/// Where a Find Files search runs
+pub enum FindSource {
+    /// A local directory, walked directly
+    Local(PathBuf),
+    /// A directory of a remote or archive panel, listed through the panel's provider
+    Provider { provider: SharedProvider, start: String },
+}
+
 /// Metadata for a source file (pre-collected from panel entries before provider is taken)
 #[allow(dead_code)]
 pub struct SourceMeta {
@@ -123,6 +202,9 @@ pub struct SourceMeta {
     pub modified: Option<std::time::SystemTime>,
     pub permissions: u32,
     pub size: u64,
+    /// Bytes of a shorter copy at the destination, left by an earlier job,
+    /// to carry on after instead of overwriting it; 0 to overwrite
+    pub resume_from: u64,
 }
 
 /// A background task with its communication channel
@@ -152,7 +234,7 @@ impl BackgroundTask {
         let (tx, rx) = channel::<TaskResult>();
 
         let handle = thread::spawn(move || {
-            let mut provider = ScpProvider::new(conn_info);
+            let mut provider = ScpProvider::new(conn_info.clone());
 
             match provider.connect() {
                 Ok(()) => {
@@ -164,11 +246,26 @@ impl BackgroundTask {
                         connection_key,
                     });
                 }
+                Err(ProviderError::HostKeyUnknown { host, port, key_type, fingerprint }) => {
+                    let _ = tx.send(TaskResult::ScpHostKeyUnknown {
+                        target,
+                        conn_info: Box::new(conn_info),
+                        initial_path,
+                        display_name,
+                        connection_string,
+                        connection_key,
+                        key_host: format!("{}:{}", host, port),
+                        key_type,
+                        fingerprint,
+                    });
+                }
                 Err(e) => {
+                    // A rejected host key is not something a password can fix
+                    let host_key_rejected = matches!(&e, ProviderError::HostKeyMismatch { .. } | ProviderError::HostKeyRevoked { .. });
                     let _ = tx.send(TaskResult::ScpFailed {
                         target,
                         error: e.to_string(),
-                        prompt_password: connection_string.is_some(),
+                        prompt_password: connection_string.is_some() && !host_key_rejected,
                         connection_string,
                         display_name,
                     });
@@ -441,12 +538,16 @@ impl BackgroundTask {
         }
     }
 
-    /// Spawn a background file operation (local-to-local copy or move)
+    /// Spawn a background file operation (local-to-local copy or move).
+    /// A copy keeps what `options` asks for; a move keeps everything. While
+    /// `paused` is raised the worker waits before its next chunk.
     pub fn file_operation(
         operation: FileOperation,
         sources: Vec<PathBuf>,
         dest: PathBuf,
+        options: CopyOptions,
         cancel: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
     ) -> Self {
         let (tx, rx) = channel::<TaskResult>();
         let (progress_tx, progress_rx) = channel::<FileOpProgress>();
@@ -459,6 +560,12 @@ impl BackgroundTask {
         let handle = thread::spawn(move || {
             let mut count = 0usize;
             let mut errors = Vec::new();
+            let mut moved = Vec::new();
+            let options = match operation {
+                FileOperation::Move => CopyOptions::FAITHFUL,
+                _ => options,
+            };
+            let mut copier = Copier::new(options, cancel.clone());
             let bytes_done = Arc::new(AtomicU64::new(0));
             let mut skip_all_errors = false;
             // Single file to a non-directory destination = rename
@@ -466,6 +573,7 @@ impl BackgroundTask {
 
             let mut i = 0;
             while i < sources.len() {
+                wait_while_paused(&paused, &cancel);
                 if cancel.load(Ordering::Relaxed) {
                     break;
                 }
@@ -491,6 +599,7 @@ impl BackgroundTask {
                 let bd = bytes_done.clone();
                 let ptx = progress_tx.clone();
                 let cn = current_name.clone();
+                let (pause, cancelled) = (paused.clone(), cancel.clone());
                 let progress_cb = move |chunk: u64| {
                     bd.fetch_add(chunk, Ordering::Relaxed);
                     let _ = ptx.send(FileOpProgress {
@@ -500,20 +609,24 @@ impl BackgroundTask {
                         files_done: i,
                         files_total,
                     });
+                    wait_while_paused(&pause, &cancelled);
                 };
 
                 let result = match &operation {
                     FileOperation::Copy => {
-                        copy_path_with_progress(src_path, &dest_file, &cancel, &progress_cb)
+                        copier.copy(src_path, &dest_file, &progress_cb)
                     }
                     FileOperation::Move => {
-                        move_path_with_progress(src_path, &dest_file, &cancel, &progress_cb)
+                        move_path_with_progress(src_path, &dest_file, &mut copier, &progress_cb)
                     }
-                    FileOperation::Delete => unreachable!(),
+                    FileOperation::Delete | FileOperation::Trash => unreachable!(),
                 };
 
                 match result {
                     Ok(()) => {
+                        if matches!(operation, FileOperation::Move) {
+                            moved.push((src_path.clone(), dest_file));
+                        }
                         count += 1;
                         i += 1;
                     }
@@ -556,12 +669,15 @@ impl BackgroundTask {
                 FileOperation::Copy => "Copied",
                 FileOperation::Move => "Moved",
                 FileOperation::Delete => "Deleted",
+                FileOperation::Trash => "Trashed",
             }.to_string();
 
             let _ = tx.send(TaskResult::FileOpCompleted(FileOpResult {
                 count,
                 errors,
                 op_name,
+                moved,
+                skipped: copier.skipped,
             }));
         });
 
@@ -574,18 +690,455 @@ impl BackgroundTask {
         }
     }
 
+    /// Spawn a background Pack operation that writes `sources` into a new
+    /// archive. The sources are scanned on the worker thread, so the totals
+    /// arrive with the first progress update.
+    pub fn pack(
+        sources: Vec<PathBuf>,
+        archive_path: PathBuf,
+        format: PackFormat,
+        level: u32,
+        cancel: Arc<AtomicBool>,
+    ) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+        let (progress_tx, progress_rx) = channel::<FileOpProgress>();
+
+        let handle = thread::spawn(move || {
+            let archive_name = archive_path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let result = PackJob::scan(&sources).and_then(|job| {
+                let files_total = job.files_total;
+                let bytes_total = job.bytes_total;
+                let _ = progress_tx.send(FileOpProgress {
+                    bytes_done: 0,
+                    bytes_total,
+                    current_file: String::new(),
+                    files_done: 0,
+                    files_total,
+                });
+                job.run(&archive_path, format, level, &cancel, &|name, files_done, bytes_done| {
+                    let _ = progress_tx.send(FileOpProgress {
+                        bytes_done,
+                        bytes_total,
+                        current_file: name.to_string(),
+                        files_done,
+                        files_total,
+                    });
+                })?;
+                Ok(files_total)
+            });
+
+            let (count, errors) = match result {
+                Ok(count) => (count, Vec::new()),
+                Err(e) => (0, vec![format!("{}: {}", archive_name, e)]),
+            };
+            let _ = tx.send(TaskResult::FileOpCompleted(FileOpResult {
+                count,
+                errors,
+                op_name: "Packed".to_string(),
+                moved: Vec::new(),
+                skipped: Vec::new(),
+            }));
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: Some(progress_rx),
+            error_rx: None,
+            error_response_tx: None,
+            _handle: handle,
+        }
+    }
+
+    /// Spawn a Find Files search. Progress is read from `progress` while it
+    /// runs; setting `cancel` ends it early with the matches found so far.
+    pub fn find_files(
+        source: FindSource,
+        side: Side,
+        query: FindQuery,
+        description: String,
+        progress: Arc<FindProgress>,
+        cancel: Arc<AtomicBool>,
+    ) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+
+        let handle = thread::spawn(move || {
+            let (matches, remote) = match source {
+                FindSource::Local(start) => {
+                    let paths = find::search(&start, &query, &progress, &cancel);
+                    (paths.iter().filter_map(|p| FileEntry::from_path(p).ok()).collect(), false)
+                }
+                FindSource::Provider { provider, start } => {
+                    (find::search_provider(&provider, &start, &query, &progress, &cancel), true)
+                }
+            };
+            let _ = tx.send(TaskResult::FindCompleted {
+                matches,
+                description,
+                cancelled: cancel.load(Ordering::Relaxed),
+                side,
+                remote,
+            });
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: None,
+            error_rx: None,
+            error_response_tx: None,
+            _handle: handle,
+        }
+    }
+
+    /// Spawn a content comparison of two listings. Same-sized files are
+    /// hashed through each panel's provider, one provider lock at a time.
+    pub fn compare_dirs(
+        left_entries: Vec<FileEntry>,
+        right_entries: Vec<FileEntry>,
+        left: SharedProvider,
+        right: SharedProvider,
+        synchronize: bool,
+        cancel: Arc<AtomicBool>,
+    ) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+
+        let handle = thread::spawn(move || {
+            let mut content_equal = |l: &FileEntry, r: &FileEntry| {
+                let left_hash = compare::provider_digest(&left, &l.path, &cancel);
+                left_hash.is_some() && left_hash == compare::provider_digest(&right, &r.path, &cancel)
+            };
+            let items = compare::compare_entries(&left_entries, &right_entries, Some(&mut content_equal));
+            if !cancel.load(Ordering::Relaxed) {
+                let _ = tx.send(TaskResult::CompareCompleted { items, synchronize });
+            }
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: None,
+            error_rx: None,
+            error_response_tx: None,
+            _handle: handle,
+        }
+    }
+
+    /// Spawn a check of which paths of an undo journal entry have changed
+    /// since the operation
+    pub fn check_undo(entry: crate::fs::undo::Entry, cancel: Arc<AtomicBool>) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+
+        let handle = thread::spawn(move || {
+            let changed = entry.changed();
+            if !cancel.load(Ordering::Relaxed) {
+                let _ = tx.send(TaskResult::UndoChecked { entry, changed });
+            }
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: None,
+            error_rx: None,
+            error_response_tx: None,
+            _handle: handle,
+        }
+    }
+
+    /// Spawn an undo of a journal entry, one step at a time. Setting
+    /// `cancel` stops it before the next step.
+    pub fn undo(entry: crate::fs::undo::Entry, cancel: Arc<AtomicBool>) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+        let (progress_tx, progress_rx) = channel::<FileOpProgress>();
+
+        let handle = thread::spawn(move || {
+            let files_total = entry.steps.len();
+            let (count, errors, rest) = entry.undo(&cancel, &|path, files_done| {
+                let _ = progress_tx.send(FileOpProgress {
+                    bytes_done: 0,
+                    bytes_total: 0,
+                    current_file: path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().to_string(),
+                    files_done,
+                    files_total,
+                });
+            });
+            let _ = tx.send(TaskResult::UndoCompleted { description: entry.description, count, errors, rest });
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: Some(progress_rx),
+            error_rx: None,
+            error_response_tx: None,
+            _handle: handle,
+        }
+    }
+
+    /// Spawn a viewer search over a memory-mapped file. Setting `cancel`
+    /// ends it early with the matches found so far.
+    pub fn viewer_search(
+        mmap: Arc<memmap2::Mmap>,
+        query: SearchQuery,
+        progress: Arc<SearchProgress>,
+        cancel: Arc<AtomicBool>,
+    ) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+
+        let handle = thread::spawn(move || {
+            let matches = search::search(&mmap, &query, &progress, &cancel);
+            let _ = tx.send(TaskResult::ViewerSearchCompleted(matches));
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: None,
+            error_rx: None,
+            error_response_tx: None,
+            _handle: handle,
+        }
+    }
+
+    /// Spawn a recursive diff of the trees below `left_root` and `right_root`
+    pub fn diff_trees(
+        left: SharedProvider,
+        left_root: String,
+        right: SharedProvider,
+        right_root: String,
+        by_content: bool,
+        cancel: Arc<AtomicBool>,
+    ) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+
+        let handle = thread::spawn(move || {
+            let diff = diff::diff_trees(&left, &left_root, &right, &right_root, by_content, &cancel);
+            if !cancel.load(Ordering::Relaxed) {
+                let _ = tx.send(TaskResult::DiffTreeCompleted(diff));
+            }
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: None,
+            error_rx: None,
+            error_response_tx: None,
+            _handle: handle,
+        }
+    }
+
+    /// Spawn a synchronization of the two panels. Copies and deletions run
+    /// in plan order with the same progress and error prompts as F5.
+    ///
+    /// Remote providers are taken out of their panels by the caller and come
+    /// back through `TaskResult::RemoteFileOpCompleted` with the left panel
+    /// as the active side.
+    pub fn synchronize(
+        items: Vec<SyncItem>,
+        left_dir: PathBuf,
+        right_dir: PathBuf,
+        left_provider: Option<Box<dyn PanelProvider>>,
+        right_provider: Option<Box<dyn PanelProvider>>,
+        cancel: Arc<AtomicBool>,
+    ) -> Self {
+        let (tx, rx) = channel::<TaskResult>();
+        let (progress_tx, progress_rx) = channel::<FileOpProgress>();
+        let (error_tx, error_rx) = channel::<FileOpError>();
+        let (error_response_tx, error_response_rx) = channel::<FileOpErrorResponse>();
+
+        let files_total = items.len();
+        let left_is_remote = left_provider.is_some();
+        let right_is_remote = right_provider.is_some();
+        // Local directories are sized up front; remote ones report 0
+        let bytes_total: u64 = items.iter().map(|s| {
+            let (entry, remote) = match s.action {
+                SyncAction::CopyToRight => (s.item.left.as_ref(), left_is_remote),
+                SyncAction::CopyToLeft => (s.item.right.as_ref(), right_is_remote),
+                _ => return 0,
+            };
+            match entry {
+                Some(e) if e.is_dir && !remote => calculate_total_bytes(std::slice::from_ref(&e.path)),
+                Some(e) => e.size,
+                None => 0,
+            }
+        }).sum();
+
+        let handle = thread::spawn(move || {
+            // Shared the same way a job shares the panels' providers, so the
+            // copy helpers work on both
+            let share = |p: Box<dyn PanelProvider>| -> SharedProvider { Arc::new(Mutex::new(p)) };
+            let to_right = CopySides { src: left_provider.map(share), dest: right_provider.map(share), src_side: Side::Left };
+            let to_left = CopySides { src: to_right.dest.clone(), dest: to_right.src.clone(), src_side: Side::Right };
+            let not_paused = AtomicBool::new(false);
+            let mut count = 0usize;
+            let mut errors: Vec<String> = Vec::new();
+            let bytes_done = Arc::new(AtomicU64::new(0));
+            let mut skip_all_errors = false;
+
+            let mut i = 0;
+            while i < items.len() {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let step = &items[i];
+                let name = step.item.name.clone();
+                let _ = progress_tx.send(FileOpProgress {
+                    bytes_done: bytes_done.load(Ordering::Relaxed),
+                    bytes_total,
+                    current_file: name.clone(),
+                    files_done: i,
+                    files_total,
+                });
+
+                let item_start = bytes_done.load(Ordering::Relaxed);
+                let bd = bytes_done.clone();
+                let ptx = progress_tx.clone();
+                let cn = name.clone();
+                let progress_cb = move |chunk: u64| {
+                    bd.fetch_add(chunk, Ordering::Relaxed);
+                    let _ = ptx.send(FileOpProgress {
+                        bytes_done: bd.load(Ordering::Relaxed),
+                        bytes_total,
+                        current_file: cn.clone(),
+                        files_done: i,
+                        files_total,
+                    });
+                };
+
+                let (result, path): (Result<(), String>, PathBuf) = match (step.action, &step.item.left, &step.item.right) {
+                    (SyncAction::CopyToRight, Some(src), _) => (Self::remote_copy_one(
+                        &src.path, &right_dir.join(&name), src.modified, src.permissions, src.is_dir,
+                        &to_right, false, None, &cancel, &not_paused, &progress_cb,
+                    ), src.path.clone()),
+                    (SyncAction::CopyToLeft, _, Some(src)) => (Self::remote_copy_one(
+                        &src.path, &left_dir.join(&name), src.modified, src.permissions, src.is_dir,
+                        &to_left, false, None, &cancel, &not_paused, &progress_cb,
+                    ), src.path.clone()),
+                    (SyncAction::DeleteLeft, Some(target), _) => {
+                        (Self::sync_delete(target, to_right.src()), target.path.clone())
+                    }
+                    (SyncAction::DeleteRight, _, Some(target)) => {
+                        (Self::sync_delete(target, to_right.dest()), target.path.clone())
+                    }
+                    _ => (Ok(()), PathBuf::from(&name)),
+                };
+
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                // A retried item is counted again from scratch
+                if result.is_err() {
+                    bytes_done.store(item_start, Ordering::Relaxed);
+                }
+
+                match result {
+                    Ok(()) => {
+                        count += 1;
+                        i += 1;
+                    }
+                    Err(e) => {
+                        let err_msg = format!("{}: {}", path.display(), e);
+                        if skip_all_errors {
+                            errors.push(err_msg);
+                            i += 1;
+                        } else {
+                            let _ = error_tx.send(FileOpError {
+                                file_path: path.display().to_string(),
+                                error_message: e,
+                            });
+                            match error_response_rx.recv() {
+                                Ok(FileOpErrorResponse::Retry) => {
+                                    // Don't increment i — retry same item
+                                }
+                                Ok(FileOpErrorResponse::Skip) => {
+                                    errors.push(err_msg);
+                                    i += 1;
+                                }
+                                Ok(FileOpErrorResponse::SkipAll) => {
+                                    skip_all_errors = true;
+                                    errors.push(err_msg);
+                                    i += 1;
+                                }
+                                Ok(FileOpErrorResponse::Abort) | Err(_) => {
+                                    errors.push(err_msg);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Self::flush_providers(&to_right, &mut errors);
+
+            drop(to_left);
+            let unshare = |p: SharedProvider| {
+                Arc::try_unwrap(p).ok().map(|m| m.into_inner().unwrap_or_else(|e| e.into_inner()))
+            };
+            let left_provider = to_right.src.and_then(unshare);
+            let right_provider = to_right.dest.and_then(unshare);
+            let _ = tx.send(TaskResult::RemoteFileOpCompleted {
+                result: FileOpResult { count, errors, op_name: "Synchronized".to_string(), moved: Vec::new(), skipped: Vec::new() },
+                src_provider: left_provider,
+                dest_provider: right_provider,
+                active_side: Side::Left,
+            });
+        });
+
+        BackgroundTask {
+            receiver: rx,
+            progress_rx: Some(progress_rx),
+            error_rx: Some(error_rx),
+            error_response_tx: Some(error_response_tx),
+            _handle: handle,
+        }
+    }
+
+    /// Helper: have the providers commit the edits they held back during an
+    /// operation. Failures are added to `errors`.
+    fn flush_providers(sides: &CopySides, errors: &mut Vec<String>) {
+        // One at a time, so only one lock is held
+        for provider in [&sides.src, &sides.dest].into_iter().flatten() {
+            if let Err(e) = CopySides::lock(provider).flush() {
+                errors.push(e.to_string());
+            }
+        }
+    }
+
+    /// Helper: delete one synchronization target, through its provider if remote
+    fn sync_delete(target: &FileEntry, provider: Option<ProviderGuard<'_>>) -> Result<(), String> {
+        match provider {
+            Some(mut prov) => {
+                let path_str = target.path.to_string_lossy();
+                if target.is_dir {
+                    prov.delete_recursive(&path_str).map_err(|e| e.to_string())
+                } else {
+                    prov.delete(&path_str).map_err(|e| e.to_string())
+                }
+            }
+            None => crate::fs::utils::delete_path(&target.path).map_err(|e| e.to_string()),
+        }
+    }
+
     /// Spawn a background remote file operation (copy or move involving at least one remote provider).
     ///
-    /// The caller must take providers out of the panels before calling this.
-    /// They will be returned via `TaskResult::RemoteFileOpCompleted`.
+    /// The panels' providers are locked per call, and a destination for as
+    /// long as one file is written, so the panels can list in between while
+    /// the operation runs as a job. `src_side` is the panel copied from. With
+    /// `verify`, every copied file is read back and checked against its
+    /// source. A retry resumes a file where the failed attempt stopped. While
+    /// `paused` is raised the worker lets go of both providers and waits
+    /// before its next chunk.
+    #[allow(clippy::too_many_arguments)]
     pub fn remote_file_operation(
         operation: FileOperation,
         source_metas: Vec<SourceMeta>,
         dest: PathBuf,
-        mut src_provider: Option<Box<dyn PanelProvider>>,
-        mut dest_provider: Option<Box<dyn PanelProvider>>,
-        active_side: Side,
+        src_provider: Option<SharedProvider>,
+        dest_provider: Option<SharedProvider>,
+        src_side: Side,
+        verify: bool,
         cancel: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
     ) -> Self {
         let (tx, rx) = channel::<TaskResult>();
         let (progress_tx, progress_rx) = channel::<FileOpProgress>();
@@ -593,8 +1146,16 @@ impl BackgroundTask {
         let (error_response_tx, error_response_rx) = channel::<FileOpErrorResponse>();
 
         let files_total = source_metas.len();
-        let bytes_total: u64 = source_metas.iter().map(|m| m.size).sum();
         let src_is_remote = src_provider.is_some();
+        // Local directories can be sized up front; remote ones only report
+        // their entry size, so the bar may finish early for remote trees.
+        let bytes_total: u64 = source_metas.iter().map(|m| {
+            if m.is_dir && !src_is_remote {
+                calculate_total_bytes(std::slice::from_ref(&m.path))
+            } else {
+                m.size
+            }
+        }).sum();
         let dest_is_remote = dest_provider.is_some();
         // For local destinations, check the actual filesystem. For remote
         // destinations the path doesn't exist locally, so fall back to never
@@ -604,24 +1165,34 @@ impl BackgroundTask {
             && !dest.is_dir();
 
         let handle = thread::spawn(move || {
+            let sides = CopySides { src: src_provider, dest: dest_provider, src_side };
             let mut count = 0usize;
             let mut errors: Vec<String> = Vec::new();
             let bytes_done = Arc::new(AtomicU64::new(0));
             let mut skip_all_errors = false;
+            let dest_of = |meta: &SourceMeta| if is_rename {
+                dest.clone()
+            } else {
+                dest.join(meta.path.file_name().unwrap_or_default())
+            };
+            // Files a failed attempt left half written, with how much of
+            // them is there, so a retry carries on from that point. Copies
+            // chosen to be resumed start out there.
+            let mut partials: HashMap<PathBuf, u64> = source_metas.iter()
+                .filter(|meta| meta.resume_from > 0)
+                .map(|meta| (dest_of(meta), meta.resume_from))
+                .collect();
 
             let mut i = 0;
             while i < source_metas.len() {
+                wait_while_paused(&paused, &cancel);
                 if cancel.load(Ordering::Relaxed) {
                     break;
                 }
 
                 let meta = &source_metas[i];
                 let file_name = meta.path.file_name().unwrap_or_default().to_string_lossy().to_string();
-                let dest_file = if is_rename {
-                    dest.clone()
-                } else {
-                    dest.join(&file_name)
-                };
+                let dest_file = dest_of(meta);
 
                 // Send progress
                 let _ = progress_tx.send(FileOpProgress {
@@ -632,30 +1203,45 @@ impl BackgroundTask {
                     files_total,
                 });
 
+                let item_start = bytes_done.load(Ordering::Relaxed);
+                let bd = bytes_done.clone();
+                let ptx = progress_tx.clone();
+                let cn = file_name.clone();
+                let progress_cb = move |chunk: u64| {
+                    bd.fetch_add(chunk, Ordering::Relaxed);
+                    let _ = ptx.send(FileOpProgress {
+                        bytes_done: bd.load(Ordering::Relaxed),
+                        bytes_total,
+                        current_file: cn.clone(),
+                        files_done: i,
+                        files_total,
+                    });
+                };
+
                 let result: Result<(), String> = match &operation {
                     FileOperation::Copy => {
                         Self::remote_copy_one(
-                            &meta.path, &dest_file, meta.modified, meta.permissions,
-                            meta.is_dir, src_is_remote, dest_is_remote,
-                            &mut src_provider, &mut dest_provider,
+                            &meta.path, &dest_file, meta.modified, meta.permissions, meta.is_dir,
+                            &sides, verify, Some(&mut partials), &cancel, &paused, &progress_cb,
                         )
                     }
                     FileOperation::Move => {
                         // Move = copy + delete source
                         let copy_result = Self::remote_copy_one(
-                            &meta.path, &dest_file, meta.modified, meta.permissions,
-                            meta.is_dir, src_is_remote, dest_is_remote,
-                            &mut src_provider, &mut dest_provider,
+                            &meta.path, &dest_file, meta.modified, meta.permissions, meta.is_dir,
+                            &sides, false, Some(&mut partials), &cancel, &paused, &progress_cb,
                         );
                         if copy_result.is_ok() {
                             // Delete source
-                            if src_is_remote {
-                                if let Some(ref mut prov) = src_provider {
-                                    let path_str = meta.path.to_string_lossy().to_string();
-                                    prov.delete(&path_str).map_err(|e| e.to_string())
+                            if let Some(mut prov) = sides.src() {
+                                let path_str = meta.path.to_string_lossy().to_string();
+                                if meta.is_dir {
+                                    prov.delete_recursive(&path_str).map_err(|e| e.to_string())
                                 } else {
-                                    Ok(())
+                                    prov.delete(&path_str).map_err(|e| e.to_string())
                                 }
+                            } else if meta.is_dir {
+                                std::fs::remove_dir_all(&meta.path).map_err(|e| e.to_string())
                             } else {
                                 std::fs::remove_file(&meta.path).map_err(|e| e.to_string())
                             }
@@ -663,11 +1249,17 @@ impl BackgroundTask {
                             copy_result
                         }
                     }
-                    FileOperation::Delete => unreachable!(),
+                    FileOperation::Delete | FileOperation::Trash => unreachable!(),
                 };
 
-                // Update bytes done (approximate: add entire file size after completion)
-                bytes_done.fetch_add(meta.size, Ordering::Relaxed);
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                // A retried item is counted again from scratch
+                if result.is_err() {
+                    bytes_done.store(item_start, Ordering::Relaxed);
+                }
 
                 match result {
                     Ok(()) => {
@@ -707,18 +1299,26 @@ impl BackgroundTask {
                 }
             }
 
+            // Whatever is still half written was skipped or abandoned
+            for path in partials.into_keys() {
+                Self::remove_dest(&path, &sides);
+            }
+            Self::flush_providers(&sides, &mut errors);
+
             let op_name = match operation {
                 FileOperation::Copy => "Copied",
                 FileOperation::Move => "Moved",
                 FileOperation::Delete => "Deleted",
+                FileOperation::Trash => "Trashed",
             }.to_string();
 
-            let _ = tx.send(TaskResult::RemoteFileOpCompleted {
-                result: FileOpResult { count, errors, op_name },
-                src_provider,
-                dest_provider,
-                active_side,
-            });
+            let _ = tx.send(TaskResult::FileOpCompleted(FileOpResult {
+                count,
+                errors,
+                op_name,
+                moved: Vec::new(),
+                skipped: Vec::new(),
+            }));
         });
 
         BackgroundTask {
@@ -731,33 +1331,42 @@ impl BackgroundTask {
     }
 
     /// Helper: copy one file or directory between providers.
-    /// Directories are copied recursively.
+    /// Directories are copied recursively; file data is streamed through
+    /// `open_read_at`/`open_write_at` and each chunk is reported to `progress`.
+    /// With `partials`, a file found there is resumed where the last attempt
+    /// stopped, and one that fails halfway is kept there for the next. With
+    /// `verify`, both sides are read back and their SHA-256 compared, and a
+    /// resume only carries on from a copy that matches the source so far.
+    ///
+    /// The providers are locked per call, and a file's destination for as
+    /// long as it is written. While `paused` is raised the copy lets go of
+    /// both and waits, then resumes the file where it stopped.
+    #[allow(clippy::too_many_arguments)]
     fn remote_copy_one(
         src_path: &PathBuf,
         dest_file: &PathBuf,
         modified: Option<std::time::SystemTime>,
         permissions: u32,
         is_dir: bool,
-        src_is_remote: bool,
-        dest_is_remote: bool,
-        src_provider: &mut Option<Box<dyn PanelProvider>>,
-        dest_provider: &mut Option<Box<dyn PanelProvider>>,
+        sides: &CopySides,
+        verify: bool,
+        mut partials: Option<&mut HashMap<PathBuf, u64>>,
+        cancel: &AtomicBool,
+        paused: &AtomicBool,
+        progress: &dyn Fn(u64),
     ) -> Result<(), String> {
         let path_str = src_path.to_string_lossy().to_string();
+        wait_while_paused(paused, cancel);
 
         // Directory entries: create on destination, then recurse into contents
         if is_dir {
-            if dest_is_remote {
-                let dest_str = dest_file.to_string_lossy().to_string();
-                let prov = dest_provider.as_mut().unwrap();
-                prov.mkdir(&dest_str).map_err(|e| e.to_string())?;
-            } else {
-                std::fs::create_dir_all(dest_file).map_err(|e| e.to_string())?;
+            match sides.dest() {
+                Some(mut prov) => prov.mkdir(&dest_file.to_string_lossy()).map_err(|e| e.to_string())?,
+                None => std::fs::create_dir_all(dest_file).map_err(|e| e.to_string())?,
             }
 
             // List source directory contents and copy each entry recursively
-            let entries = if src_is_remote {
-                let prov = src_provider.as_mut().unwrap();
+            let entries = if let Some(mut prov) = sides.src() {
                 prov.list_directory(&path_str).map_err(|e| e.to_string())?
             } else {
                 // Local source: enumerate directory
@@ -796,57 +1405,291 @@ impl BackgroundTask {
                 let child_src = entry.path.clone();
                 let child_dest = dest_file.join(&entry.name);
                 Self::remote_copy_one(
-                    &child_src, &child_dest, entry.modified, entry.permissions,
-                    entry.is_dir, src_is_remote, dest_is_remote,
-                    src_provider, dest_provider,
+                    &child_src, &child_dest, entry.modified, entry.permissions, entry.is_dir,
+                    sides, verify, partials.as_deref_mut(), cancel, paused, progress,
                 )?;
             }
 
             return Ok(());
         }
 
-        match (src_is_remote, dest_is_remote) {
-            (true, false) => {
-                // Remote to local: download
-                let prov = src_provider.as_mut().unwrap();
-                let data = prov.read_file(&path_str).map_err(|e| e.to_string())?;
-                if let Some(parent) = dest_file.parent() {
-                    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-                }
-                std::fs::write(dest_file, data).map_err(|e| e.to_string())?;
-                apply_local_attributes(dest_file, modified, permissions);
-                Ok(())
+        let dest_str = dest_file.to_string_lossy().to_string();
+
+        if sides.dest.is_none()
+            && let Some(parent) = dest_file.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
             }
-            (false, true) => {
-                // Local to remote: upload
-                let data = std::fs::read(src_path).map_err(|e| e.to_string())?;
-                let dest_str = dest_file.to_string_lossy().to_string();
-                let prov = dest_provider.as_mut().unwrap();
-                prov.write_file(&dest_str, &data).map_err(|e| e.to_string())?;
-                let _ = prov.set_attributes(&dest_str, modified, permissions);
-                Ok(())
+
+        let open_reader = |src: Option<&mut Box<dyn PanelProvider>>, offset: u64| -> Result<Box<dyn Read + Send>, String> {
+            match src {
+                Some(prov) => prov.open_read_at(&path_str, offset).map_err(|e| {
+                    if e.is_connection_lost() {
+                        prov.reset_connection();
+                    }
+                    e.to_string()
+                }),
+                None => {
+                    let mut file = std::fs::File::open(src_path).map_err(|e| e.to_string())?;
+                    file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+                    Ok(Box::new(file))
+                }
             }
-            (true, true) => {
-                // Remote to remote: download then upload
-                let data = {
-                    let prov = src_provider.as_mut().unwrap();
-                    prov.read_file(&path_str).map_err(|e| e.to_string())?
+        };
+
+        // Pick up where a failed attempt left off
+        let mut offset = partials.as_mut().and_then(|p| p.remove(dest_file)).unwrap_or(0);
+        if verify && offset > 0 && !Self::same_prefix(src_path, dest_file, offset, sides, cancel)? {
+            offset = 0;
+        }
+        // Bytes of this file already reported, so a resume doesn't count them twice
+        let mut reported = 0u64;
+        loop {
+            // Both sides are needed to open the file, locked in a fixed order
+            let (mut src_guard, mut dest_guard) = sides.lock_both();
+            let mut reader = match open_reader(src_guard.as_deref_mut(), offset) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    if let Some(partials) = partials.filter(|_| offset > 0) {
+                        partials.insert(dest_file.clone(), offset);
+                    }
+                    return Err(e);
+                }
+            };
+
+            // Bytes the destination holds so far; None until it is open
+            let written: Cell<Option<u64>> = Cell::new(None);
+            let lost_connection = Cell::new(false);
+            let copied = (|| {
+                let opened = match dest_guard.as_deref_mut() {
+                    Some(prov) => prov.open_write_at(&dest_str, offset),
+                    None => open_local_write_at(dest_file, offset)
+                        .map(|(file, from)| (Box::new(file) as Box<dyn ProviderWriter>, from))
+                        .map_err(ProviderError::from),
+                };
+                let (mut writer, from) = opened.map_err(|e| {
+                    lost_connection.set(e.is_connection_lost());
+                    e.to_string()
+                })?;
+
+                // What's already there counts as done
+                written.set(Some(from));
+                // A partial that can't be added to is started over
+                if from != offset {
+                    reader = open_reader(src_guard.as_deref_mut(), from)?;
+                }
+                // The reader doesn't need its provider, so the source is free again
+                drop(src_guard);
+                progress(from.saturating_sub(reported));
+                reported = reported.max(from);
+                let counted = |n: u64| {
+                    written.set(written.get().map(|done| done + n));
+                    progress(n);
                 };
-                let dest_str = dest_file.to_string_lossy().to_string();
-                let prov = dest_provider.as_mut().unwrap();
-                prov.write_file(&dest_str, &data).map_err(|e| e.to_string())?;
-                let _ = prov.set_attributes(&dest_str, modified, permissions);
-                Ok(())
+                // A read or write that fails halfway most likely lost the
+                // connection
+                let finished = stream_copy(&mut reader, &mut writer, cancel, paused, &counted)
+                    .inspect_err(|_| lost_connection.set(true))?;
+                if !finished {
+                    // Dropped unfinished; what it wrote stays for the resume
+                    return Ok(false);
+                }
+                writer.finish().map(|()| true).map_err(|e| {
+                    lost_connection.set(true);
+                    e.to_string()
+                })
+            })();
+            drop(dest_guard);
+
+            match copied {
+                Ok(true) => break,
+                Ok(false) => {
+                    offset = written.get().unwrap_or(offset);
+                    reported = reported.max(offset);
+                    wait_while_paused(paused, cancel);
+                    if cancel.load(Ordering::Relaxed) {
+                        Self::remove_dest(dest_file, sides);
+                        return Err("Cancelled".to_string());
+                    }
+                }
+                Err(e) => {
+                    let cancelled = cancel.load(Ordering::Relaxed);
+                    if lost_connection.get() && !cancelled {
+                        // A retry has to connect again to resume
+                        if let Some(mut prov) = sides.src() {
+                            prov.reset_connection();
+                        }
+                        if let Some(mut prov) = sides.dest() {
+                            prov.reset_connection();
+                        }
+                    }
+                    match written.get() {
+                        // Nothing was opened: a partial from before is still there
+                        None => {
+                            if let Some(partials) = partials.filter(|_| offset > 0) {
+                                partials.insert(dest_file.clone(), offset);
+                            }
+                        }
+                        // Keep what made it across for a retry
+                        Some(done) if done > 0 && !cancelled && partials.is_some() => {
+                            partials.unwrap().insert(dest_file.clone(), done);
+                        }
+                        // Don't leave a truncated file behind
+                        Some(_) => Self::remove_dest(dest_file, sides),
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        if verify {
+            let source = match sides.src() {
+                Some(mut prov) => prov.open_read(&path_str).map_err(std::io::Error::other),
+                None => std::fs::File::open(src_path).map(|file| Box::new(file) as Box<dyn Read + Send>),
+            };
+            let copied = match sides.dest() {
+                Some(mut prov) => prov.open_read(&dest_str).map_err(std::io::Error::other),
+                None => std::fs::File::open(dest_file).map(|file| Box::new(file) as Box<dyn Read + Send>),
+            };
+            // Hashed without the locks held
+            let source = source.and_then(|mut reader| copy::sha256(&mut reader, cancel));
+            let copied = copied.and_then(|mut reader| copy::sha256(&mut reader, cancel));
+            let matches = source.map_err(|e| e.to_string())? == copied.map_err(|e| e.to_string())?;
+            if !matches {
+                // A retry copies it again from scratch
+                Self::remove_dest(dest_file, sides);
+                return Err(format!("verification failed: {} does not match the source", dest_str));
+            }
+        }
+
+        match sides.dest() {
+            Some(mut prov) => {
+                if prov.supports(Capability::SetAttributes) {
+                    let _ = prov.set_attributes(&dest_str, modified, permissions);
+                }
+            }
+            None => apply_local_attributes(dest_file, modified, permissions),
+        }
+        Ok(())
+    }
+
+    /// Whether the first `len` bytes of the source and the destination match
+    fn same_prefix(src_path: &Path, dest_file: &Path, len: u64, sides: &CopySides, cancel: &AtomicBool) -> Result<bool, String> {
+        let open = |provider: Option<ProviderGuard<'_>>, path: &Path| match provider {
+            Some(mut prov) => prov.open_read(&path.to_string_lossy()).map_err(std::io::Error::other),
+            None => std::fs::File::open(path).map(|file| Box::new(file) as Box<dyn Read + Send>),
+        };
+        let source = open(sides.src(), src_path);
+        let copied = open(sides.dest(), dest_file);
+        // Hashed without the locks held
+        let hash = |reader: std::io::Result<Box<dyn Read + Send>>| {
+            reader.and_then(|reader| copy::sha256(&mut reader.take(len), cancel)).map_err(|e| e.to_string())
+        };
+        Ok(hash(source)? == hash(copied)?)
+    }
+
+    /// Remove a file the copy left at `dest`, on whichever side it is
+    fn remove_dest(dest: &Path, sides: &CopySides) {
+        match sides.dest() {
+            Some(mut prov) => {
+                let _ = prov.delete(&dest.to_string_lossy());
             }
-            (false, false) => {
-                // Should not happen for remote ops, but handle gracefully
-                std::fs::copy(src_path, dest_file).map_err(|e| e.to_string())?;
-                Ok(())
+            None => {
+                let _ = std::fs::remove_file(dest);
             }
         }
     }
 }
 
+/// A locked panel provider
+type ProviderGuard<'a> = MutexGuard<'a, Box<dyn PanelProvider>>;
+
+/// The providers a copy goes between, None for a local side. Each is locked
+/// only for the calls that need it; when both are needed at once the left
+/// one is locked first, so two jobs copying in opposite directions can't
+/// deadlock.
+struct CopySides {
+    src: Option<SharedProvider>,
+    dest: Option<SharedProvider>,
+    /// Which panel the source is
+    src_side: Side,
+}
+
+impl CopySides {
+    fn lock(provider: &SharedProvider) -> ProviderGuard<'_> {
+        provider.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn src(&self) -> Option<ProviderGuard<'_>> {
+        self.src.as_ref().map(Self::lock)
+    }
+
+    fn dest(&self) -> Option<ProviderGuard<'_>> {
+        self.dest.as_ref().map(Self::lock)
+    }
+
+    /// Lock both, left before right
+    fn lock_both(&self) -> (Option<ProviderGuard<'_>>, Option<ProviderGuard<'_>>) {
+        if self.src_side == Side::Left {
+            let src = self.src();
+            (src, self.dest())
+        } else {
+            let dest = self.dest();
+            (self.src(), dest)
+        }
+    }
+}
+
+/// Sleep while `paused` is raised, unless `cancel` is
+fn wait_while_paused(paused: &AtomicBool, cancel: &AtomicBool) {
+    while paused.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+        thread::sleep(PAUSE_POLL_INTERVAL);
+    }
+}
+
+/// Open a local file for writing, keeping its first `offset` bytes. Returns
+/// the offset writing starts from, 0 if the file is shorter and starts over.
+fn open_local_write_at(path: &Path, offset: u64) -> std::io::Result<(std::fs::File, u64)> {
+    if offset > 0 {
+        let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+        if file.metadata()?.len() >= offset {
+            file.set_len(offset)?;
+            file.seek(SeekFrom::Start(offset))?;
+            return Ok((file, offset));
+        }
+    }
+    Ok((std::fs::File::create(path)?, 0))
+}
+
+/// Pump data from reader to writer in fixed-size chunks, reporting each chunk.
+/// Returns "Cancelled" as the error if the cancel flag is raised mid-transfer,
+/// and Ok(false) if `paused` is, with what was written so far left in place.
+fn stream_copy(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    cancel: &AtomicBool,
+    paused: &AtomicBool,
+    progress: &dyn Fn(u64),
+) -> Result<bool, String> {
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        if paused.load(Ordering::Relaxed) {
+            writer.flush().map_err(|e| e.to_string())?;
+            return Ok(false);
+        }
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(true),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+        writer.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+        progress(n as u64);
+    }
+}
+
 /// Apply file attributes (modification time, permissions) to a local file.
 fn apply_local_attributes(dest: &std::path::Path, modified: Option<std::time::SystemTime>, _permissions: u32) {
     if let Some(mtime) = modified {
@@ -860,3 +1703,256 @@ fn apply_local_attributes(dest: &std::path::Path, modified: Option<std::time::Sy
         let _ = std::fs::set_permissions(dest, std::fs::Permissions::from_mode(_permissions));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+    use crate::providers::{ProviderInfo, ProviderResult, ProviderType};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Local files behind a link that drops once, after a number of bytes
+    /// have been written. Until the connection is reset every call fails.
+    struct FlakyProvider {
+        info: ProviderInfo,
+        lost: Arc<AtomicBool>,
+        drop_after: Option<usize>,
+        /// Offset of each `open_write_at`
+        opened_at: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl FlakyProvider {
+        fn check(&self) -> ProviderResult<()> {
+            if self.lost.load(Ordering::Relaxed) {
+                return Err(ProviderError::Connection("connection lost".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    struct DroppingWriter {
+        file: std::fs::File,
+        budget: Option<usize>,
+        lost: Arc<AtomicBool>,
+    }
+
+    impl Write for DroppingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            match self.budget {
+                Some(0) => {
+                    self.lost.store(true, Ordering::Relaxed);
+                    Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection lost"))
+                }
+                Some(budget) => {
+                    let n = self.file.write(&buf[..buf.len().min(budget)])?;
+                    self.budget = Some(budget - n);
+                    Ok(n)
+                }
+                None => self.file.write(buf),
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.file.flush()
+        }
+    }
+
+    impl ProviderWriter for DroppingWriter {
+        fn finish(mut self: Box<Self>) -> ProviderResult<()> {
+            self.flush()?;
+            Ok(())
+        }
+    }
+
+    impl PanelProvider for FlakyProvider {
+        fn info(&self) -> &ProviderInfo {
+            &self.info
+        }
+        fn is_connected(&self) -> bool {
+            !self.lost.load(Ordering::Relaxed)
+        }
+        fn connect(&mut self) -> ProviderResult<()> {
+            Ok(())
+        }
+        fn disconnect(&mut self) {}
+        fn reset_connection(&mut self) {
+            // The next call connects again
+            self.lost.store(false, Ordering::Relaxed);
+        }
+        fn list_directory(&mut self, _path: &str) -> ProviderResult<Vec<FileEntry>> {
+            Err(ProviderError::NotSupported("list".to_string()))
+        }
+        fn read_file(&mut self, path: &str) -> ProviderResult<Vec<u8>> {
+            self.check()?;
+            Ok(std::fs::read(path)?)
+        }
+        fn write_file(&mut self, path: &str, data: &[u8]) -> ProviderResult<()> {
+            self.check()?;
+            Ok(std::fs::write(path, data)?)
+        }
+        fn open_write_at(&mut self, path: &str, offset: u64) -> ProviderResult<(Box<dyn ProviderWriter + '_>, u64)> {
+            self.check()?;
+            self.opened_at.lock().unwrap().push(offset);
+            let (file, from) = open_local_write_at(Path::new(path), offset)?;
+            let writer = DroppingWriter { file, budget: self.drop_after.take(), lost: self.lost.clone() };
+            Ok((Box::new(writer), from))
+        }
+        fn delete(&mut self, path: &str) -> ProviderResult<()> {
+            self.check()?;
+            Ok(std::fs::remove_file(path)?)
+        }
+        fn delete_recursive(&mut self, path: &str) -> ProviderResult<()> {
+            self.delete(path)
+        }
+        fn rename(&mut self, _from: &str, _to: &str) -> ProviderResult<()> {
+            Err(ProviderError::NotSupported("rename".to_string()))
+        }
+        fn mkdir(&mut self, _path: &str) -> ProviderResult<()> {
+            Err(ProviderError::NotSupported("mkdir".to_string()))
+        }
+        fn copy_file(&mut self, _from: &str, _to: &str) -> ProviderResult<()> {
+            Err(ProviderError::NotSupported("copy".to_string()))
+        }
+        fn get_free_space(&self, _path: &str) -> Option<u64> {
+            None
+        }
+        fn is_local(&self) -> bool {
+            false
+        }
+        fn home_path(&self) -> String {
+            "/".to_string()
+        }
+        fn normalize_path(&self, path: &str) -> String {
+            path.to_string()
+        }
+        fn parent_path(&self, _path: &str) -> Option<String> {
+            None
+        }
+        fn join_path(&self, base: &str, name: &str) -> String {
+            format!("{}/{}", base, name)
+        }
+        fn to_local_path(&self, _path: &str) -> Option<PathBuf> {
+            None
+        }
+        fn from_local_path(&self, _path: &Path) -> Option<String> {
+            None
+        }
+    }
+
+    fn flaky(drop_after: Option<usize>, opened_at: &Arc<Mutex<Vec<u64>>>) -> SharedProvider {
+        Arc::new(Mutex::new(Box::new(FlakyProvider {
+            info: ProviderInfo {
+                name: "flaky".to_string(),
+                description: String::new(),
+                provider_type: ProviderType::Scp,
+                icon: None,
+            },
+            lost: Arc::new(AtomicBool::new(false)),
+            drop_after,
+            opened_at: opened_at.clone(),
+        })))
+    }
+
+    #[test]
+    fn test_resume_after_dropped_connection() {
+        let dir = ScratchDir::new("background-resume");
+        let (src, dest) = (dir.join("src.bin"), dir.join("dest.bin"));
+        let data: Vec<u8> = (0..STREAM_CHUNK_SIZE * 3).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&src, &data).unwrap();
+
+        let opened_at = Arc::new(Mutex::new(Vec::new()));
+        let sides = CopySides { src: None, dest: Some(flaky(Some(STREAM_CHUNK_SIZE + 100), &opened_at)), src_side: Side::Left };
+        let mut partials = HashMap::new();
+        let (cancel, paused) = (AtomicBool::new(false), AtomicBool::new(false));
+        let copy = |partials: &mut HashMap<PathBuf, u64>| {
+            BackgroundTask::remote_copy_one(
+                &src, &dest, None, 0, false, &sides, true, Some(partials), &cancel, &paused, &|_| {},
+            )
+        };
+
+        // Only whole chunks count; the bytes of the one cut short are dropped
+        assert!(copy(&mut partials).is_err());
+        assert_eq!(partials.get(&dest), Some(&(STREAM_CHUNK_SIZE as u64)));
+
+        // The retry reconnects and carries on from there
+        copy(&mut partials).unwrap();
+        assert!(partials.is_empty());
+        assert_eq!(*opened_at.lock().unwrap(), [0, STREAM_CHUNK_SIZE as u64]);
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+
+        // A later job carries on after a shorter copy left behind, unless
+        // it doesn't match the source so far
+        let chunks = |n: usize| (STREAM_CHUNK_SIZE * n) as u64;
+        std::fs::write(&dest, &data[..STREAM_CHUNK_SIZE * 2]).unwrap();
+        copy(&mut HashMap::from([(dest.clone(), chunks(2))])).unwrap();
+        let mut stale = data[..STREAM_CHUNK_SIZE].to_vec();
+        stale[0] ^= 1;
+        std::fs::write(&dest, &stale).unwrap();
+        copy(&mut HashMap::from([(dest.clone(), chunks(1))])).unwrap();
+        assert_eq!(*opened_at.lock().unwrap(), [0, chunks(1), chunks(2), 0]);
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+    }
+
+    #[test]
+    fn test_paused_remote_job() {
+        let dir = ScratchDir::new("background-pause");
+        std::fs::create_dir_all(dir.join("out")).unwrap();
+        let src = dir.join("src.bin");
+        std::fs::write(&src, b"payload").unwrap();
+
+        let provider = flaky(None, &Arc::new(Mutex::new(Vec::new())));
+        let paused = Arc::new(AtomicBool::new(true));
+        let meta = SourceMeta { path: src, is_dir: false, modified: None, permissions: 0, size: 7, resume_from: 0 };
+        let task = BackgroundTask::remote_file_operation(
+            FileOperation::Copy, vec![meta], dir.join("out"),
+            None, Some(provider.clone()), Side::Left,
+            false, Arc::new(AtomicBool::new(false)), paused.clone(),
+        );
+
+        // A held job waits without keeping the panel's provider locked
+        thread::sleep(Duration::from_millis(50));
+        assert!(task.try_recv().is_none());
+        assert!(provider.try_lock().is_ok());
+        assert!(!dir.join("out/src.bin").exists());
+
+        paused.store(false, Ordering::Relaxed);
+        let result = (0..500).find_map(|_| {
+            thread::sleep(Duration::from_millis(10));
+            task.try_recv()
+        });
+        assert!(matches!(result, Some(TaskResult::FileOpCompleted(FileOpResult { count: 1, .. }))));
+        assert_eq!(std::fs::read(dir.join("out/src.bin")).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn test_pause_mid_file_releases_provider() {
+        let dir = ScratchDir::new("background-midpause");
+        let (src, dest) = (dir.join("src.bin"), dir.join("dest.bin"));
+        let data: Vec<u8> = (0..STREAM_CHUNK_SIZE * 3).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&src, &data).unwrap();
+
+        let opened_at = Arc::new(Mutex::new(Vec::new()));
+        let provider = flaky(None, &opened_at);
+        let sides = CopySides { src: None, dest: Some(provider.clone()), src_side: Side::Left };
+        let (cancel, paused, held) = (AtomicBool::new(false), AtomicBool::new(false), AtomicBool::new(false));
+        thread::scope(|scope| {
+            // Held after the first chunk
+            let copy = scope.spawn(|| BackgroundTask::remote_copy_one(
+                &src, &dest, None, 0, false, &sides, false, None, &cancel, &paused,
+                &|n| if n > 0 && !held.swap(true, Ordering::Relaxed) {
+                    paused.store(true, Ordering::Relaxed);
+                },
+            ));
+            thread::sleep(Duration::from_millis(100));
+            let unlocked = provider.try_lock().is_ok();
+            let waiting = !copy.is_finished();
+            paused.store(false, Ordering::Relaxed);
+            copy.join().unwrap().unwrap();
+            assert!(unlocked && waiting);
+        });
+        // The file was reopened where it stopped
+        assert_eq!(*opened_at.lock().unwrap(), [0, STREAM_CHUNK_SIZE as u64]);
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+    }
+}
@@ -0,0 +1,209 @@
+//! State of the built-in hex editor (F4 in the viewer)
+
+use std::path::PathBuf;
+
+use crate::fs::edit_buffer::EditBuffer;
+
+/// Question asked over the editor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexConfirm {
+    /// Write the changes to the file
+    Save,
+    /// Leave without saving
+    Discard,
+}
+
+/// A file open in the hex editor
+#[derive(Clone, Debug)]
+pub struct HexEditor {
+    pub path: PathBuf,
+    pub buffer: EditBuffer,
+    /// Byte under the cursor; the length of the buffer to append
+    pub cursor: usize,
+    /// The high nibble of the byte was typed, the low one is next
+    pub low_nibble: bool,
+    /// Typing goes into the ASCII column instead of the hex one
+    pub ascii: bool,
+    /// Typing inserts bytes instead of overwriting them
+    pub insert: bool,
+    /// First row shown
+    pub scroll: usize,
+    /// Go-to-offset prompt (Ctrl+G): input and cursor
+    pub goto: Option<(String, usize)>,
+    /// Pending question and its focused button (0 = Yes)
+    pub confirm: Option<(HexConfirm, usize)>,
+    /// Footer message shown until the next key
+    pub message: Option<String>,
+}
+
+impl HexEditor {
+    pub fn new(path: PathBuf, buffer: EditBuffer) -> Self {
+        Self {
+            path,
+            buffer,
+            cursor: 0,
+            low_nibble: false,
+            ascii: false,
+            insert: false,
+            scroll: 0,
+            goto: None,
+            confirm: None,
+            message: None,
+        }
+    }
+
+    /// Put the cursor on the byte at `offset`, or after the last byte
+    pub fn move_to(&mut self, offset: usize) {
+        self.cursor = offset.min(self.buffer.len());
+        self.low_nibble = false;
+    }
+
+    /// Move the cursor by `delta` bytes
+    pub fn move_by(&mut self, delta: isize) {
+        self.move_to(self.cursor.saturating_add_signed(delta));
+    }
+
+    /// Type a hex digit into the hex column: the high nibble, then the low
+    /// one, after which the cursor moves on
+    pub fn type_hex(&mut self, digit: u8) {
+        let current = self.buffer.get(self.cursor);
+        if self.low_nibble {
+            let byte = (current.unwrap_or(0) & 0xF0) | digit;
+            self.buffer.replace(self.cursor, 1, &[byte]);
+            self.move_to(self.cursor + 1);
+            return;
+        }
+        match current {
+            Some(byte) if !self.insert => self.buffer.replace(self.cursor, 1, &[(digit << 4) | (byte & 0x0F)]),
+            _ => self.buffer.replace(self.cursor, 0, &[digit << 4]),
+        }
+        self.low_nibble = true;
+    }
+
+    /// Type a byte into the ASCII column
+    pub fn type_byte(&mut self, byte: u8) {
+        let remove = if self.insert { 0 } else { 1 };
+        self.buffer.replace(self.cursor, remove, &[byte]);
+        self.move_to(self.cursor + 1);
+    }
+
+    /// Delete the byte under the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.buffer.len() {
+            self.buffer.replace(self.cursor, 1, &[]);
+        }
+        self.move_to(self.cursor);
+    }
+
+    /// Delete the byte before the cursor when inserting; just move back
+    /// when overwriting
+    pub fn backspace(&mut self) {
+        if self.insert && self.cursor > 0 {
+            self.buffer.replace(self.cursor - 1, 1, &[]);
+        }
+        self.move_by(-1);
+    }
+
+    pub fn undo(&mut self) {
+        match self.buffer.undo() {
+            Some(offset) => self.move_to(offset),
+            None => self.message = Some("Nothing to undo".to_string()),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        match self.buffer.redo() {
+            Some(offset) => self.move_to(offset),
+            None => self.message = Some("Nothing to redo".to_string()),
+        }
+    }
+
+    /// Jump to an offset typed as decimal, or as hex with a 0x prefix
+    pub fn goto_offset(&mut self, input: &str) -> Result<(), String> {
+        let input = input.trim();
+        let offset = match input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+            Some(hex) => usize::from_str_radix(hex, 16),
+            None => input.parse(),
+        }
+        .map_err(|_| format!("Invalid offset: {}", input))?;
+        if offset > self.buffer.len() {
+            return Err(format!("Offset {} is past the end ({} bytes)", offset, self.buffer.len()));
+        }
+        self.move_to(offset);
+        Ok(())
+    }
+
+    /// Scroll so the cursor's row is among the `height` rows shown
+    pub fn scroll_to_cursor(&mut self, bytes_per_line: usize, height: usize) {
+        let row = self.cursor / bytes_per_line.max(1);
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + height.max(1) {
+            self.scroll = row + 1 - height.max(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(bytes: &[u8]) -> HexEditor {
+        let dir = std::env::temp_dir().join(format!("bark-hexedit-{}-{}", bytes.len(), std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.bin");
+        std::fs::write(&path, bytes).unwrap();
+        let buffer = EditBuffer::open(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        HexEditor::new(path, buffer)
+    }
+
+    fn bytes(editor: &HexEditor) -> Vec<u8> {
+        editor.buffer.read(0, editor.buffer.len()).into_iter().map(|(b, _)| b).collect()
+    }
+
+    #[test]
+    fn test_typing() {
+        let mut editor = editor(&[0x11, 0x22, 0x33]);
+        editor.type_hex(0xA);
+        assert_eq!(bytes(&editor), [0xA1, 0x22, 0x33]);
+        editor.type_hex(0xB);
+        assert_eq!((bytes(&editor)[0], editor.cursor), (0xAB, 1));
+
+        editor.insert = true;
+        editor.type_hex(0xC);
+        editor.type_hex(0xD);
+        assert_eq!(bytes(&editor), [0xAB, 0xCD, 0x22, 0x33]);
+        editor.backspace();
+        assert_eq!((bytes(&editor), editor.cursor), (vec![0xAB, 0x22, 0x33], 1));
+
+        editor.insert = false;
+        editor.ascii = true;
+        editor.move_to(3);
+        editor.type_byte(b'!');
+        assert_eq!(bytes(&editor), [0xAB, 0x22, 0x33, b'!']);
+        editor.move_to(0);
+        editor.delete();
+        assert_eq!(bytes(&editor), [0x22, 0x33, b'!']);
+
+        editor.undo();
+        assert_eq!((bytes(&editor), editor.cursor), (vec![0xAB, 0x22, 0x33, b'!'], 0));
+    }
+
+    #[test]
+    fn test_goto_and_scroll() {
+        let mut editor = editor(&[0; 300]);
+        assert!(editor.goto_offset("0x100").is_ok());
+        assert_eq!(editor.cursor, 256);
+        assert!(editor.goto_offset("300").is_ok());
+        assert!(editor.goto_offset("301").is_err());
+        assert!(editor.goto_offset("0xZZ").is_err());
+
+        editor.move_to(100);
+        editor.scroll_to_cursor(16, 4);
+        assert_eq!(editor.scroll, 3);
+        editor.move_to(0);
+        editor.scroll_to_cursor(16, 4);
+        assert_eq!(editor.scroll, 0);
+    }
+}
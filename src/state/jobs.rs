@@ -0,0 +1,334 @@
+//! Job queue: copies and moves, local or remote, sent to the background
+//! from their progress dialog, so browsing can go on while they run
+//!
+//! Each job's worker thread watches a pause flag and sleeps at the next
+//! chunk while it is raised. The queue raises the same flag to hold back
+//! jobs beyond the number allowed to run at once, so the order of the list
+//! decides which of them run. A job doesn't stop on errors: each is
+//! skipped and kept on the job for the user to look at.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use super::background::{BackgroundTask, FileOpErrorResponse, FileOpProgress, FileOpResult, TaskResult};
+
+/// How often a job's speed is measured
+const SPEED_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What the queue needs, besides the task, to take over a running copy or
+/// move
+pub struct JobControl {
+    /// What the job does: "Copy 3 item(s) to /mnt/nas"
+    pub description: String,
+    pub cancel: Arc<AtomicBool>,
+    pub paused: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    /// Waiting for a free slot
+    Queued,
+    /// Held by the user
+    Paused,
+    /// Cancelled; the worker stops at the next chunk
+    Cancelling,
+}
+
+impl JobState {
+    pub fn label(self) -> &'static str {
+        match self {
+            JobState::Running => "Running",
+            JobState::Queued => "Queued",
+            JobState::Paused => "Paused",
+            JobState::Cancelling => "Cancelling",
+        }
+    }
+}
+
+/// A copy or move running, or waiting to run, in the background
+pub struct Job {
+    pub description: String,
+    pub state: JobState,
+    pub progress: FileOpProgress,
+    /// Errors so far, each of them skipped
+    pub errors: Vec<String>,
+    /// Bytes per second, smoothed
+    pub speed: f64,
+    /// When the speed was last measured, and the bytes done then
+    sample: (Instant, u64),
+    task: BackgroundTask,
+    control: JobControl,
+}
+
+impl Job {
+    /// Time left at the current speed, while running
+    pub fn eta(&self) -> Option<Duration> {
+        if self.state != JobState::Running || self.speed < 1.0 {
+            return None;
+        }
+        let left = self.progress.bytes_total.saturating_sub(self.progress.bytes_done);
+        Some(Duration::from_secs_f64(left as f64 / self.speed))
+    }
+
+    /// Take in what the worker sent: progress, and errors, which are
+    /// answered with Skip
+    fn drain(&mut self, now: Instant) {
+        if let Some(rx) = &self.task.progress_rx {
+            while let Ok(progress) = rx.try_recv() {
+                self.progress = progress;
+            }
+        }
+        if let Some(rx) = &self.task.error_rx {
+            while let Ok(error) = rx.try_recv() {
+                self.errors.push(format!("{}: {}", error.file_path, error.error_message));
+                if let Some(tx) = &self.task.error_response_tx {
+                    let _ = tx.send(FileOpErrorResponse::Skip);
+                }
+            }
+        }
+
+        let (then, bytes) = self.sample;
+        let elapsed = now.saturating_duration_since(then);
+        if self.state == JobState::Running && elapsed >= SPEED_INTERVAL {
+            let current = self.progress.bytes_done.saturating_sub(bytes) as f64 / elapsed.as_secs_f64();
+            self.speed = if self.speed == 0.0 { current } else { self.speed * 0.7 + current * 0.3 };
+            self.sample = (now, self.progress.bytes_done);
+        }
+    }
+}
+
+/// Background jobs in the order they get to run
+pub struct JobQueue {
+    jobs: Vec<Job>,
+    /// Most jobs running at once
+    max_running: usize,
+}
+
+impl JobQueue {
+    pub fn new(max_running: usize) -> Self {
+        Self { jobs: Vec::new(), max_running: max_running.max(1) }
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Change how many jobs run at once, e.g. after the config was edited
+    pub fn set_max_running(&mut self, max_running: usize) {
+        self.max_running = max_running.max(1);
+        self.schedule();
+    }
+
+    /// Short line for the status bar: "2 jobs 45%"
+    pub fn summary(&self) -> Option<String> {
+        if self.jobs.is_empty() {
+            return None;
+        }
+        let (done, total) = self.jobs.iter().fold((0u64, 0u64), |(done, total), job| {
+            (done + job.progress.bytes_done, total + job.progress.bytes_total)
+        });
+        let noun = if self.jobs.len() == 1 { "job" } else { "jobs" };
+        Some(match (done * 100).checked_div(total) {
+            Some(percent) => format!("{} {} {}%", self.jobs.len(), noun, percent.min(100)),
+            None => format!("{} {}", self.jobs.len(), noun),
+        })
+    }
+
+    /// Take over a file operation whose progress so far is `progress`. It
+    /// goes to the end of the queue.
+    pub fn push(&mut self, task: BackgroundTask, control: JobControl, progress: FileOpProgress) {
+        self.jobs.push(Job {
+            description: control.description.clone(),
+            state: JobState::Queued,
+            sample: (Instant::now(), progress.bytes_done),
+            progress,
+            errors: Vec::new(),
+            speed: 0.0,
+            task,
+            control,
+        });
+        self.schedule();
+    }
+
+    /// Pause job `index`, or let it go on if it is paused
+    pub fn toggle_pause(&mut self, index: usize) {
+        let Some(job) = self.jobs.get_mut(index) else {
+            return;
+        };
+        job.state = match job.state {
+            JobState::Running | JobState::Queued => JobState::Paused,
+            JobState::Paused => JobState::Queued,
+            JobState::Cancelling => JobState::Cancelling,
+        };
+        self.schedule();
+    }
+
+    /// Stop job `index`. It leaves the list once its worker has stopped.
+    pub fn cancel(&mut self, index: usize) {
+        let Some(job) = self.jobs.get_mut(index) else {
+            return;
+        };
+        job.state = JobState::Cancelling;
+        job.control.cancel.store(true, Ordering::Relaxed);
+        // A held worker has to wake up to see it's cancelled
+        job.control.paused.store(false, Ordering::Relaxed);
+        self.schedule();
+    }
+
+    /// Move job `index` one place up or down the list. Returns where it is
+    /// now.
+    pub fn move_job(&mut self, index: usize, up: bool) -> usize {
+        let other = if up { index.checked_sub(1) } else { Some(index + 1) };
+        match other.filter(|&other| index < self.jobs.len() && other < self.jobs.len()) {
+            Some(other) => {
+                self.jobs.swap(index, other);
+                self.schedule();
+                other
+            }
+            None => index,
+        }
+    }
+
+    /// Take in what the workers sent. Returns the description and result of
+    /// each job that has finished; those leave the list.
+    pub fn poll(&mut self) -> Vec<(String, FileOpResult)> {
+        let now = Instant::now();
+        let mut finished = Vec::new();
+        let mut i = 0;
+        while i < self.jobs.len() {
+            self.jobs[i].drain(now);
+            match self.jobs[i].task.try_recv() {
+                Some(result) => {
+                    let job = self.jobs.remove(i);
+                    // Only file operations are queued
+                    if let TaskResult::FileOpCompleted(result) = result {
+                        finished.push((job.description, result));
+                    }
+                }
+                None => i += 1,
+            }
+        }
+        if !finished.is_empty() {
+            self.schedule();
+        }
+        finished
+    }
+
+    /// Let the first jobs that aren't paused run, up to the limit, and hold
+    /// back the others
+    fn schedule(&mut self) {
+        let now = Instant::now();
+        let mut slots = self.max_running;
+        for job in &mut self.jobs {
+            match job.state {
+                JobState::Cancelling => {}
+                JobState::Paused => job.control.paused.store(true, Ordering::Relaxed),
+                JobState::Running | JobState::Queued => {
+                    let run = slots > 0;
+                    if run {
+                        slots -= 1;
+                        if job.state != JobState::Running {
+                            // Time spent waiting doesn't count towards the speed
+                            job.sample = (now, job.progress.bytes_done);
+                            job.speed = 0.0;
+                        }
+                    }
+                    job.state = if run { JobState::Running } else { JobState::Queued };
+                    job.control.paused.store(!run, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+    use crate::fs::copy::CopyOptions;
+    use crate::state::mode::FileOperation;
+    use std::fs;
+    use std::path::Path;
+
+    /// A copy of `src` into `dest`, held until the queue lets it run
+    fn push_copy(queue: &mut JobQueue, src: &Path, dest: &Path) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(true));
+        let task = BackgroundTask::file_operation(
+            FileOperation::Copy,
+            vec![src.to_path_buf()],
+            dest.to_path_buf(),
+            CopyOptions::default(),
+            cancel.clone(),
+            paused.clone(),
+        );
+        let description = src.file_name().unwrap().to_string_lossy().to_string();
+        let progress =
+            FileOpProgress { bytes_done: 0, bytes_total: 0, current_file: String::new(), files_done: 0, files_total: 1 };
+        queue.push(task, JobControl { description, cancel, paused }, progress);
+    }
+
+    /// Poll until a job finishes
+    fn next_finished(queue: &mut JobQueue) -> Vec<(String, FileOpResult)> {
+        for _ in 0..500 {
+            let finished = queue.poll();
+            if !finished.is_empty() {
+                return finished;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("no job finished");
+    }
+
+    #[test]
+    fn test_queue_order_pause_and_cancel() {
+        let dir = ScratchDir::new("jobs-queue");
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let out = dir.join("out");
+        fs::create_dir(&out).unwrap();
+
+        let mut queue = JobQueue::new(1);
+        push_copy(&mut queue, &dir.join("a"), &out);
+        push_copy(&mut queue, &dir.join("b"), &out);
+        push_copy(&mut queue, &dir.join("c"), &out);
+        let states = |queue: &JobQueue| queue.jobs().iter().map(|job| job.state).collect::<Vec<_>>();
+        assert_eq!(states(&queue), [JobState::Running, JobState::Queued, JobState::Queued]);
+
+        // A paused job keeps its place but gives up its slot
+        queue.toggle_pause(2);
+        assert_eq!(queue.move_job(2, true), 1);
+        assert_eq!(queue.move_job(1, true), 0);
+        assert_eq!(queue.move_job(0, true), 0);
+        assert_eq!(queue.jobs()[0].description, "c");
+        assert_eq!(states(&queue), [JobState::Paused, JobState::Running, JobState::Queued]);
+
+        // Moving a queued job up holds back the one running
+        assert_eq!(queue.move_job(2, true), 1);
+        assert_eq!(queue.jobs()[1].description, "b");
+        assert_eq!(states(&queue), [JobState::Paused, JobState::Running, JobState::Queued]);
+
+        let mut done = Vec::new();
+        while done.len() < 2 {
+            done.extend(next_finished(&mut queue).into_iter().map(|(name, result)| (name, result.count)));
+        }
+        done.sort();
+        assert_eq!(done, [("a".to_string(), 1), ("b".to_string(), 1)]);
+        assert_eq!(fs::read_to_string(out.join("b")).unwrap(), "b");
+        assert_eq!(states(&queue), [JobState::Paused]);
+        assert!(!out.join("c").exists());
+
+        queue.cancel(0);
+        let finished = next_finished(&mut queue);
+        assert_eq!((finished[0].0.as_str(), finished[0].1.count), ("c", 0));
+        assert!(queue.is_empty());
+        assert!(!out.join("c").exists());
+    }
+}
@@ -4,6 +4,10 @@ pub mod app;
 pub mod ui_state;
 pub mod command_state;
 pub mod background;
+pub mod jobs;
+pub mod hex_editor;
+pub mod viewer_outline;
+pub mod viewer_table;
 
 pub use ui_state::UiState;
 pub use command_state::CommandState;
@@ -1,8 +1,20 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use crate::fs::compare::SyncItem;
+use crate::fs::copy::CopyOptions;
+use crate::fs::diff::{FileDiff, TreeDiff};
+use crate::fs::find::{FindField, FindOptions, FindProgress};
+use crate::fs::search::SearchProgress;
+use crate::fs::trash::TrashedItem;
+use crate::fs::pack::PackFormat;
 use crate::plugins::provider_api::DialogField;
-use crate::providers::PanelSource;
+use crate::providers::{PanelSource, ScpConnectionInfo};
 use super::Side;
+use super::viewer_outline::ViewerOutline;
+use super::viewer_table::ViewerTable;
+use super::hex_editor::HexEditor;
+use crate::ui::encoding::TextEncoding;
+use crate::ui::viewer_utils::{TextLayout, compute_line_offsets, display_width, extend_line_offsets, wrapped_rows};
 use crate::utils::calculate_hex_bytes_per_line;
 
 /// Type of file operation for confirmation dialog
@@ -11,6 +23,15 @@ pub enum FileOperation {
     Copy,
     Move,
     Delete,
+    /// Move to the trash instead of deleting
+    Trash,
+}
+
+impl FileOperation {
+    /// Whether the operation removes its sources rather than copying them
+    pub fn is_delete(&self) -> bool {
+        matches!(self, FileOperation::Delete | FileOperation::Trash)
+    }
 }
 
 /// Action to perform on simple confirmation
@@ -28,7 +49,18 @@ pub enum SimpleConfirmAction {
         operation: FileOperation,
         sources: Vec<PathBuf>,
         dest: PathBuf,
-    },
+        /// Sources whose shorter copy is carried on from, with its size
+        resume: HashMap<PathBuf, u64>,
+    },
+    /// Delete an item from the trash for good, then go back to the trash
+    /// browser at `cursor`
+    PurgeTrashed { item: TrashedItem, cursor: usize },
+    /// Delete everything in the trash
+    EmptyTrash,
+    /// Undo the last journaled operation although its paths have changed
+    Undo,
+    /// Quit although background jobs haven't finished
+    Quit,
 }
 
 /// Content type for the file viewer
@@ -49,9 +81,149 @@ pub enum ViewContent {
         /// Only populated for text files
         line_offsets: Vec<usize>,
     },
+    /// Text in another encoding, decoded to UTF-8 for the text view.
+    /// The hex view shows the original bytes in `source`.
+    Decoded {
+        source: Box<ViewContent>,
+        encoding: TextEncoding,
+        text: String,
+        line_offsets: Vec<usize>,
+        /// Raw bytes through the last complete line, which appending to
+        /// the file never decodes again, and the length of their text
+        decoded: (usize, usize),
+    },
 }
 
 impl ViewContent {
+    /// Content of a memory-mapped local file: UTF-8 is shown straight from
+    /// the map, other text is decoded and anything else opens in hex
+    pub fn from_mmap(mmap: std::sync::Arc<memmap2::Mmap>) -> ViewContent {
+        let encoding = crate::ui::encoding::detect(&mmap);
+        let is_text = encoding == Some(TextEncoding::Utf8);
+
+        // Scan once for line starts, enabling O(1) line lookup
+        let line_offsets = if is_text { compute_line_offsets(&mmap) } else { Vec::new() };
+
+        let decode = encoding.filter(|_| !is_text && mmap.len() <= crate::ui::encoding::AUTO_DECODE_LIMIT);
+        let content = ViewContent::MappedFile { mmap, is_text, line_offsets };
+        match decode {
+            Some(encoding) => content.reencode(encoding),
+            None => content,
+        }
+    }
+
+    /// The content after its file grew to what `mmap` now maps. Mapped
+    /// text only has the new bytes scanned for lines and decoded text only
+    /// has them decoded, along with the line they finish; anything else is
+    /// taken in from scratch.
+    pub fn append_mapped(self, mmap: std::sync::Arc<memmap2::Mmap>) -> ViewContent {
+        match self {
+            ViewContent::MappedFile { mmap: old, is_text, mut line_offsets } if mmap.len() >= old.len() => {
+                if is_text {
+                    extend_line_offsets(&mut line_offsets, &mmap, old.len());
+                }
+                ViewContent::MappedFile { mmap, is_text, line_offsets }
+            }
+            ViewContent::Decoded { source, encoding, mut text, mut line_offsets, mut decoded }
+                if matches!(*source, ViewContent::MappedFile { .. }) && mmap.len() >= source.raw_bytes().len() =>
+            {
+                let source = source.append_mapped(mmap);
+                decode_tail(encoding, source.raw_bytes(), &mut text, &mut line_offsets, &mut decoded);
+                ViewContent::Decoded { source: Box::new(source), encoding, text, line_offsets, decoded }
+            }
+            ViewContent::Decoded { encoding, .. } => ViewContent::from_mmap(mmap).reencode(encoding),
+            _ => ViewContent::from_mmap(mmap),
+        }
+    }
+
+    /// The bytes as read from the file, before any decoding
+    pub fn raw_bytes(&self) -> &[u8] {
+        match self {
+            ViewContent::Text(text, _) => text.as_bytes(),
+            ViewContent::Binary(data) => data,
+            ViewContent::MappedFile { mmap, .. } => mmap,
+            ViewContent::Decoded { source, .. } => source.raw_bytes(),
+        }
+    }
+
+    /// The bytes shown in `binary_mode`, which searches run over: decoded
+    /// text in the text view, raw bytes otherwise
+    pub fn search_bytes(&self, binary_mode: BinaryViewMode) -> &[u8] {
+        self.text_lines(binary_mode).map_or_else(|| self.raw_bytes(), |(bytes, _)| bytes)
+    }
+
+    /// Encoding the text view decodes with; None for binary content
+    pub fn encoding(&self) -> Option<TextEncoding> {
+        match self {
+            ViewContent::Text(..) | ViewContent::MappedFile { is_text: true, .. } => Some(TextEncoding::Utf8),
+            ViewContent::Binary(_) | ViewContent::MappedFile { is_text: false, .. } => None,
+            ViewContent::Decoded { encoding, .. } => Some(*encoding),
+        }
+    }
+
+    /// The same bytes read as `encoding`. UTF-8 content goes back to being
+    /// shown as is; anything else is decoded.
+    pub fn reencode(self, encoding: TextEncoding) -> ViewContent {
+        let source = match self {
+            ViewContent::Decoded { source, .. } => *source,
+            content => content,
+        };
+        if source.encoding() == Some(encoding) {
+            return source;
+        }
+        let (mut text, mut line_offsets, mut decoded) = (String::new(), vec![0], (0, 0));
+        decode_tail(encoding, source.raw_bytes(), &mut text, &mut line_offsets, &mut decoded);
+        ViewContent::Decoded { source: Box::new(source), encoding, text, line_offsets, decoded }
+    }
+
+    /// Bytes and line offsets when the content is shown as text
+    pub fn text_lines(&self, binary_mode: BinaryViewMode) -> Option<(&[u8], &[usize])> {
+        match (self, binary_mode) {
+            (ViewContent::Text(text, line_offsets), BinaryViewMode::Cp437) => Some((text.as_bytes(), line_offsets)),
+            (ViewContent::MappedFile { mmap, is_text: true, line_offsets }, BinaryViewMode::Cp437) => Some((mmap, line_offsets)),
+            (ViewContent::Decoded { text, line_offsets, .. }, BinaryViewMode::Cp437) => Some((text.as_bytes(), line_offsets)),
+            _ => None,
+        }
+    }
+
+    /// Line `index` of text content, without its line ending
+    pub fn line_text(&self, index: usize) -> Option<std::borrow::Cow<'_, str>> {
+        let (bytes, line_offsets) = self.text_lines(BinaryViewMode::Cp437)?;
+        let start = *line_offsets.get(index)?;
+        let end = line_offsets.get(index + 1).copied().unwrap_or(bytes.len());
+        let line = &bytes[start..end];
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        Some(String::from_utf8_lossy(line))
+    }
+
+    /// Screen rows text line `index` takes with `layout` in a viewer
+    /// `term_width` wide: 1 unless wrapping
+    pub fn line_rows(&self, index: usize, layout: TextLayout, term_width: usize) -> usize {
+        if !layout.wrap {
+            return 1;
+        }
+        let Some((_, line_offsets)) = self.text_lines(BinaryViewMode::Cp437) else {
+            return 1;
+        };
+        let width = layout.text_width(term_width, line_offsets.len());
+        let columns = self.line_text(index).map_or(0, |line| display_width(&line, layout.tab_width, Some(width)));
+        wrapped_rows(columns, width)
+    }
+
+    /// Scroll position (line, wrapped row within it) that shows `offset` at
+    /// the top. Only wrapped text has rows; everything else scrolls by line.
+    pub fn byte_offset_to_position(&self, offset: usize, binary_mode: BinaryViewMode, term_width: usize, layout: TextLayout) -> (usize, usize) {
+        let line = self.byte_offset_to_line(offset, binary_mode, term_width);
+        let Some((bytes, line_offsets)) = self.text_lines(binary_mode).filter(|_| layout.wrap) else {
+            return (line, 0);
+        };
+        let start = line_offsets.get(line).copied().unwrap_or(0).min(bytes.len());
+        let before = String::from_utf8_lossy(&bytes[start..offset.clamp(start, bytes.len())]);
+        let width = layout.text_width(term_width, line_offsets.len());
+        (line, display_width(&before, layout.tab_width, Some(width)) / width)
+    }
+
     pub fn byte_offset_to_line(&self, offset: usize, binary_mode: BinaryViewMode, term_width: usize) -> usize {
         match self {
             ViewContent::Text(_text, line_offsets) => {
@@ -100,10 +272,43 @@ impl ViewContent {
                     }
                 }
             }
+            ViewContent::Decoded { source, line_offsets, .. } => {
+                match binary_mode {
+                    BinaryViewMode::Cp437 => {
+                        match line_offsets.binary_search(&offset) {
+                            Ok(line) => line,
+                            Err(line) => line.saturating_sub(1),
+                        }
+                    }
+                    BinaryViewMode::Hex => source.byte_offset_to_line(offset, binary_mode, term_width),
+                }
+            }
         }
     }
 }
 
+/// Decode what `raw` has past its last complete line as of `decoded` onto
+/// `text`, taking off the unfinished line decoded before, and bring
+/// `line_offsets` and `decoded` up to date
+fn decode_tail(
+    encoding: TextEncoding,
+    raw: &[u8],
+    text: &mut String,
+    line_offsets: &mut Vec<usize>,
+    decoded: &mut (usize, usize),
+) {
+    let (done, done_text) = *decoded;
+    text.truncate(done_text);
+    line_offsets.retain(|&offset| offset == 0 || offset < done_text);
+
+    let tail = &raw[done..];
+    let split = encoding.line_end(tail);
+    text.push_str(&encoding.decode_part(&tail[..split], done == 0));
+    *decoded = (done + split, text.len());
+    text.push_str(&encoding.decode_part(&tail[split..], done + split == 0));
+    extend_line_offsets(line_offsets, text.as_bytes(), done_text);
+}
+
 /// Binary view mode
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BinaryViewMode {
@@ -129,6 +334,8 @@ pub enum Mode {
         search_matches: Vec<(usize, usize)>,
         /// Current match index (None if no matches or search not active)
         current_match: Option<usize>,
+        /// Wrapped row of the top line shown first (0 unless wrapping)
+        wrap_row: usize,
     },
     /// Viewing a file via plugin
     ViewingPlugin {
@@ -144,6 +351,10 @@ pub enum Mode {
         total_lines: usize,
         /// Transient status message (e.g. "Saved to …"), cleared on next key
         status_message: Option<String>,
+        /// Folding and breadcrumbs, when the plugin sent them
+        outline: Option<ViewerOutline>,
+        /// Heading and columns, when the plugin shows a table
+        table: Option<ViewerTable>,
     },
     /// Plugin selection menu in viewer (F2)
     ViewerPluginMenu {
@@ -200,10 +411,13 @@ pub enum Mode {
         sources: Vec<PathBuf>,
         dest_input: String,
         cursor_pos: usize,
-        /// Focused element: 0 = input field (copy/move only), 1 = OK/Delete, 2 = Cancel
+        /// Focused element: 0 = input field (copy/move only), then the
+        /// checkboxes (copy options, or "apply for all"), then OK/Delete and Cancel
         focus: usize,
         /// "Apply for all" checkbox state (delete only, for directories)
         apply_all: bool,
+        /// What a local copy keeps (copy only)
+        options: CopyOptions,
     },
     /// Iterative delete confirmation (per-item in a directory)
     DeleteIterative {
@@ -257,6 +471,29 @@ pub enum Mode {
         /// Error message if connection failed
         error: Option<String>,
     },
+    /// Confirmation for an SSH host key that isn't in known_hosts
+    HostKeyConfirm {
+        /// Target panel to connect
+        target_panel: Side,
+        /// Connection to retry once the key is trusted
+        conn_info: Box<ScpConnectionInfo>,
+        /// Initial remote path
+        initial_path: String,
+        /// Display name for the connection
+        display_name: String,
+        /// Connection URI for the password-retry flow (saved connections)
+        connection_string: Option<String>,
+        /// Connection key for panel cache matching
+        connection_key: Option<String>,
+        /// "host:port" that presented the key (the target or a jump host)
+        key_host: String,
+        /// Host key algorithm (e.g., "ssh-ed25519")
+        key_type: String,
+        /// SHA256 fingerprint shown to the user
+        fingerprint: String,
+        /// Focused button: 0 = Trust once, 1 = Trust and save, 2 = Cancel
+        focus: usize,
+    },
     /// Creating a new directory (F7)
     MakingDir {
         /// Name for the new directory
@@ -266,6 +503,21 @@ pub enum Mode {
         /// Focused element: 0 = input field, 1 = OK, 2 = Cancel
         focus: usize,
     },
+    /// Packing the selection into a new archive (Alt+F5)
+    Packing {
+        /// Local files and directories to pack
+        sources: Vec<PathBuf>,
+        /// Archive file name (relative to the active panel)
+        name_input: String,
+        /// Cursor position in the name input
+        cursor_pos: usize,
+        /// Archive format
+        format: PackFormat,
+        /// Compression level 0-9
+        level: u32,
+        /// Focused element: 0 = name, 1 = format, 2 = level, 3 = OK, 4 = Cancel
+        focus: usize,
+    },
     /// Command history panel (Alt+H)
     CommandHistory {
         /// Currently selected command index (0 = oldest, len-1 = newest)
@@ -275,27 +527,67 @@ pub enum Mode {
     },
     /// Find files dialog (Alt+/)
     FindFiles {
-        /// File pattern to search for (supports * and ?)
-        pattern_input: String,
-        /// Cursor position in pattern input
-        pattern_cursor: usize,
-        /// Case sensitive filename matching
-        pattern_case_sensitive: bool,
-        /// Text to search for inside files (empty = don't search content)
-        content_input: String,
-        /// Cursor position in content input
-        content_cursor: usize,
-        /// Case sensitive content matching
-        content_case_sensitive: bool,
-        /// Starting path for search
-        path_input: String,
-        /// Cursor position in path input
-        path_cursor: usize,
-        /// Whether to search recursively
-        recursive: bool,
-        /// Focused element: 0 = pattern, 1 = pattern_case, 2 = content, 3 = content_case, 4 = path, 5 = recursive, 6 = Search, 7 = Cancel
+        /// Search criteria being edited
+        options: FindOptions,
+        /// Focused dialog element
+        focus: FindField,
+        /// Cursor position in the focused text field
+        cursor: usize,
+    },
+    /// Find Files search running in the background
+    Searching {
+        /// What is being searched for, e.g. "matching '*.rs'"
+        description: String,
+        /// Live counters updated by the search threads
+        progress: std::sync::Arc<FindProgress>,
+        /// Spinner animation frame
+        frame: usize,
+        /// When the search started (for elapsed time display)
+        started: std::time::Instant,
+    },
+    /// Compare directories dialog (Alt+C)
+    CompareDirs {
+        /// Compare same-sized files by content instead of modification time
+        by_content: bool,
+        /// 0 = content checkbox, 1 = Compare, 2 = Synchronize, 3 = Tree Diff, 4 = Cancel
         focus: usize,
     },
+    /// Full-screen recursive diff of the two panels' trees
+    DiffTree {
+        tree: std::sync::Arc<TreeDiff>,
+        /// Highlighted row
+        cursor: usize,
+        scroll: usize,
+    },
+    /// Full-screen line diff of one modified file, opened from the tree diff
+    ViewingDiff {
+        diff: FileDiff,
+        scroll: usize,
+        /// Tree diff state to return to
+        tree: std::sync::Arc<TreeDiff>,
+        tree_cursor: usize,
+        tree_scroll: usize,
+    },
+    /// Synchronize dialog: the planned action for every differing name
+    Synchronize {
+        items: Vec<SyncItem>,
+        /// Highlighted row
+        cursor: usize,
+        scroll: usize,
+    },
+    /// Trash browser, opened from the Trash entry of the source selector
+    TrashBrowser {
+        /// Trashed items, most recent first
+        items: Vec<TrashedItem>,
+        /// Highlighted row
+        cursor: usize,
+        scroll: usize,
+    },
+    /// Background jobs panel (:jobs); the jobs themselves live in `App::jobs`
+    Jobs {
+        /// Highlighted job
+        cursor: usize,
+    },
     /// Viewer search dialog ( / )
     ViewerSearch {
         /// Original viewing state to return to
@@ -306,18 +598,68 @@ pub enum Mode {
         /// Previous search matches (to restore if cancelled)
         prev_matches: Vec<(usize, usize)>,
         prev_current: Option<usize>,
-        /// Text search input (supports * wildcard)
+        /// Text search input (supports * wildcard, or a regex)
         text_input: String,
         /// Cursor position in text input
         text_cursor: usize,
         /// Case sensitive text search
         case_sensitive: bool,
+        /// Text input is a regular expression
+        regex: bool,
+        /// Regex matches may span lines
+        multiline: bool,
         /// Hex search input (e.g., "4D 5A" or "4D5A")
         hex_input: String,
         /// Cursor position in hex input
         hex_cursor: usize,
-        /// Focused element: 0 = text, 1 = case_sensitive, 2 = hex, 3 = Search, 4 = Cancel
+        /// Focused element: 0 = text, 1 = case_sensitive, 2 = regex, 3 = multiline,
+        /// 4 = hex, 5 = Search, 6 = List All, 7 = Cancel
         focus: usize,
+        /// Why the last search could not start (e.g. an invalid regex)
+        error: Option<String>,
+    },
+    /// Viewer search running in the background over a large file
+    ViewerSearching {
+        /// Viewing state to return to
+        content: ViewContent,
+        scroll: usize,
+        path: std::path::PathBuf,
+        binary_mode: BinaryViewMode,
+        /// Matches to keep if nothing is found
+        prev_matches: Vec<(usize, usize)>,
+        prev_current: Option<usize>,
+        /// What is searched for, e.g. "'foo'"
+        description: String,
+        /// Live counters updated by the search thread
+        progress: std::sync::Arc<SearchProgress>,
+        /// Bytes being searched
+        total: usize,
+        /// Open the match list when the search is done
+        list: bool,
+        /// Spinner animation frame
+        frame: usize,
+        /// When the search started (for elapsed time display)
+        started: std::time::Instant,
+    },
+    /// Editing a file's bytes in the built-in hex editor (F4 in the viewer)
+    HexEditing {
+        editor: HexEditor,
+    },
+    /// List of all viewer search matches below a preview of the file
+    ViewerMatches {
+        content: ViewContent,
+        path: std::path::PathBuf,
+        binary_mode: BinaryViewMode,
+        search_matches: Vec<(usize, usize)>,
+        /// Highlighted match
+        cursor: usize,
+        /// First match shown in the list
+        list_scroll: usize,
+        /// Preview position, following the highlighted match
+        scroll: usize,
+        wrap_row: usize,
+        /// Viewer position and current match to go back to on Esc
+        return_to: (usize, usize, Option<usize>),
     },
     /// Select files by pattern dialog (Ctrl+A / Alt+A)
     SelectFiles {
@@ -433,7 +775,11 @@ pub enum Mode {
         skip_set: HashSet<PathBuf>,
         /// Whether user chose "Overwrite All"
         overwrite_all: bool,
-        /// Focused button: 0=Yes, 1=All, 2=Skip, 3=SkipAll, 4=Cancel
+        /// Conflicts a remote job can resume, with the size already copied
+        resumable: HashMap<PathBuf, u64>,
+        /// Conflicts the user chose to resume rather than overwrite
+        resume: HashMap<PathBuf, u64>,
+        /// Focused button: 0=Yes, 1=All, 2=Resume, 3=Skip, 4=SkipAll, 5=Cancel
         focus: usize,
     },
     /// Password prompt for encrypted archive
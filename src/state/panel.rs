@@ -2,10 +2,15 @@
 
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
+use crate::async_io::{read_listing, IoHandle, IoRequest, Listing, SharedProvider};
 use crate::fs::FileEntry;
-use crate::providers::{LocalProvider, PanelProvider};
-use crate::errors::AppResult;
+use crate::providers::{Capability, LocalProvider, PanelProvider};
+use crate::errors::{AppError, AppResult};
+use super::Side;
 
 /// How files are displayed in a panel
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
@@ -67,11 +72,55 @@ pub struct SavedPanelState {
 
 /// Cached remote provider (preserved when switching to local)
 pub struct CachedRemote {
-    provider: Box<dyn PanelProvider>,
+    provider: SharedProvider,
+    meta: ProviderMeta,
     path: PathBuf,
     connection_key: String,
 }
 
+/// Provider facts cached when the provider is installed, so rendering
+/// never has to wait for a listing that holds the provider lock
+#[derive(Clone, Debug)]
+struct ProviderMeta {
+    local: bool,
+    name: String,
+    short_label: Option<String>,
+    capabilities: Vec<Capability>,
+}
+
+impl ProviderMeta {
+    fn of(provider: &dyn PanelProvider) -> Self {
+        Self {
+            local: provider.is_local(),
+            name: provider.info().name.clone(),
+            short_label: provider.short_label(),
+            capabilities: Capability::ALL.into_iter().filter(|c| provider.supports(*c)).collect(),
+        }
+    }
+}
+
+/// What to do with the cursor once a listing arrives
+#[derive(Clone, Debug)]
+enum ListingIntent {
+    /// Re-read the current directory, keeping the cursor where it is
+    /// unless an entry to focus is given
+    Refresh { focus: Option<String> },
+    /// Enter a new directory, optionally placing the cursor on an entry
+    Enter { focus: Option<String> },
+    /// Re-read and put the cursor and scroll back to saved positions
+    Restore { cursor: usize, scroll_offset: usize },
+}
+
+/// A listing request that has been sent to the I/O worker
+#[derive(Debug)]
+struct PendingListing {
+    id: u64,
+    path: PathBuf,
+    intent: ListingIntent,
+    cancel: Arc<AtomicBool>,
+    started: Instant,
+}
+
 /// Info needed to return to a previous provider (e.g., when exiting an archive)
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -119,14 +168,24 @@ pub struct Panel {
     pub temp_mode: bool,
     /// Saved state to restore when exiting temp mode
     pub saved_state: Option<SavedPanelState>,
-    /// Filesystem provider (local or remote)
-    provider: Box<dyn PanelProvider>,
+    /// Filesystem provider (local or remote), shared with I/O workers
+    provider: SharedProvider,
+    /// Cached facts about `provider`
+    provider_meta: ProviderMeta,
     /// Info about the parent provider (set when entering an archive)
     parent_provider: Option<ParentProviderInfo>,
     /// Cached remote provider (preserved when switching to local)
     cached_remote: Option<CachedRemote>,
     /// Connection key for the current remote provider (used for cache matching)
     connection_key: Option<String>,
+    /// Background I/O channel; without it listings run on the calling thread
+    io: Option<(Side, IoHandle)>,
+    /// Listing in flight, if any
+    loading: Option<PendingListing>,
+    /// Id of the last listing request sent
+    listing_seq: u64,
+    /// Free space reported by a remote provider with the last listing
+    pub remote_free_space: Option<u64>,
 }
 
 impl std::fmt::Debug for Panel {
@@ -136,6 +195,7 @@ impl std::fmt::Debug for Panel {
             .field("entries", &self.entries.len())
             .field("cursor", &self.cursor)
             .field("is_remote", &self.is_remote())
+            .field("loading", &self.loading.is_some())
             .finish()
     }
 }
@@ -144,6 +204,7 @@ impl std::fmt::Debug for Panel {
 impl Panel {
     /// Create a new panel for the given directory
     pub fn new(path: PathBuf) -> Self {
+        let local: Box<dyn PanelProvider> = Box::new(LocalProvider::new());
         let mut panel = Self {
             path: path.clone(),
             entries: Vec::new(),
@@ -159,10 +220,15 @@ impl Panel {
             show_dir_prefix: false,
             temp_mode: false,
             saved_state: None,
-            provider: Box::new(LocalProvider::new()),
+            provider_meta: ProviderMeta::of(local.as_ref()),
+            provider: Arc::new(Mutex::new(local)),
             parent_provider: None,
             cached_remote: None,
             connection_key: None,
+            io: None,
+            loading: None,
+            listing_seq: 0,
+            remote_free_space: None,
         };
         panel.refresh();
         panel
@@ -181,35 +247,88 @@ impl Panel {
         })
     }
 
+    /// Route directory listings through the background I/O manager.
+    /// Responses must be fed back with `apply_io_listing`.
+    pub fn attach_io(&mut self, side: Side, io: IoHandle) {
+        self.io = Some((side, io));
+    }
+
+    /// Lock the provider, even if a previous holder panicked
+    fn lock_provider(&self) -> MutexGuard<'_, Box<dyn PanelProvider>> {
+        self.provider.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Lock the provider without waiting for a listing that is still running
+    fn try_provider(&self) -> AppResult<MutexGuard<'_, Box<dyn PanelProvider>>> {
+        match self.provider.try_lock() {
+            Ok(guard) => Ok(guard),
+            Err(std::sync::TryLockError::Poisoned(e)) => Ok(e.into_inner()),
+            Err(std::sync::TryLockError::WouldBlock) => Err(AppError::Operation(
+                "Connection is busy with a directory listing".to_string(),
+            )),
+        }
+    }
+
+    /// True while an I/O worker still holds (or is queued to use) the provider,
+    /// including listings that were cancelled but have not returned yet
+    pub fn provider_busy(&self) -> bool {
+        Arc::strong_count(&self.provider) > 1
+    }
+
+    /// Another handle to the provider, for background work that lists
+    /// through it. `provider_busy()` stays true while the handle is alive.
+    pub fn shared_provider(&self) -> SharedProvider {
+        self.provider.clone()
+    }
+
+    /// Replace the provider, disconnecting the old one unless a worker is
+    /// still using it (it is then dropped when the worker finishes)
+    fn install_provider(&mut self, provider: Box<dyn PanelProvider>) {
+        self.cancel_loading_quietly();
+        self.provider_meta = ProviderMeta::of(provider.as_ref());
+        let old = std::mem::replace(&mut self.provider, Arc::new(Mutex::new(provider)));
+        Self::retire_provider(old);
+        // Entries from the old provider are meaningless under the new one
+        self.entries.clear();
+        self.sorted_indices.clear();
+    }
+
+    fn retire_provider(provider: SharedProvider) {
+        if let Ok(mut p) = provider.try_lock() {
+            p.disconnect();
+        }
+    }
+
     /// Temporarily extract the provider, replacing it with a dummy LocalProvider.
     /// Use `restore_provider()` to put the real provider back.
+    /// Blocks while a listing is using the provider; check `provider_busy()` first.
     pub fn take_provider(&mut self) -> Box<dyn PanelProvider> {
-        std::mem::replace(&mut self.provider, Box::new(LocalProvider::new()))
+        self.cancel_loading_quietly();
+        std::mem::replace(&mut *self.lock_provider(), Box::new(LocalProvider::new()))
     }
 
     /// Restore a previously taken provider.
     pub fn restore_provider(&mut self, provider: Box<dyn PanelProvider>) {
-        self.provider = provider;
+        *self.lock_provider() = provider;
     }
 
     /// Check if this panel is browsing a remote filesystem
     pub fn is_remote(&self) -> bool {
-        !self.provider.is_local()
+        !self.provider_meta.local
     }
 
     /// Check if this panel is browsing a local filesystem
     pub fn is_local(&self) -> bool {
-        self.provider.is_local()
+        self.provider_meta.local
     }
 
     /// Set a provider for this panel (local or remote)
     pub fn set_provider(&mut self, provider: Box<dyn PanelProvider>, initial_path: &str, connection_key: Option<String>) {
         // Drop cached remote since we're connecting to something new
-        if let Some(mut cached) = self.cached_remote.take() {
-            cached.provider.disconnect();
+        if let Some(cached) = self.cached_remote.take() {
+            Self::retire_provider(cached.provider);
         }
-        self.provider.disconnect();
-        self.provider = provider;
+        self.install_provider(provider);
         self.connection_key = connection_key;
         self.path = PathBuf::from(initial_path);
         self.refresh();
@@ -218,22 +337,28 @@ impl Panel {
     /// Switch back to local filesystem
     /// If the current provider is remote, cache it for potential restoration later.
     pub fn set_local_provider(&mut self, path: PathBuf) {
-        if !self.provider.is_local() {
+        if !self.provider_meta.local {
             // Cache the remote provider instead of disconnecting
-            let old_provider = std::mem::replace(&mut self.provider, Box::new(LocalProvider::new()));
+            self.cancel_loading_quietly();
+            let local: Box<dyn PanelProvider> = Box::new(LocalProvider::new());
+            let old_meta = std::mem::replace(&mut self.provider_meta, ProviderMeta::of(local.as_ref()));
+            let old_provider = std::mem::replace(&mut self.provider, Arc::new(Mutex::new(local)));
             let old_path = std::mem::replace(&mut self.path, path);
             let key = self.connection_key.take().unwrap_or_default();
             // Drop any previously cached remote
-            if let Some(mut prev) = self.cached_remote.take() {
-                prev.provider.disconnect();
+            if let Some(prev) = self.cached_remote.take() {
+                Self::retire_provider(prev.provider);
             }
             self.cached_remote = Some(CachedRemote {
                 provider: old_provider,
+                meta: old_meta,
                 path: old_path,
                 connection_key: key,
             });
+            self.entries.clear();
+            self.sorted_indices.clear();
         } else {
-            self.provider = Box::new(LocalProvider::new());
+            self.install_provider(Box::new(LocalProvider::new()));
             self.path = path;
         }
         self.refresh();
@@ -242,14 +367,20 @@ impl Panel {
     /// Try to restore a cached remote connection by key.
     /// Returns true if the cache matched and was restored.
     pub fn restore_cached_remote(&mut self, key: &str) -> bool {
-        let matches = self.cached_remote.as_ref()
-            .is_some_and(|c| c.connection_key == key && c.provider.is_connected());
+        let matches = self.cached_remote.as_ref().is_some_and(|c| {
+            c.connection_key == key
+                && c.provider.try_lock().is_ok_and(|p| p.is_connected())
+        });
         if matches {
             let cached = self.cached_remote.take().unwrap();
-            self.provider.disconnect();
-            self.provider = cached.provider;
+            self.cancel_loading_quietly();
+            let old = std::mem::replace(&mut self.provider, cached.provider);
+            Self::retire_provider(old);
+            self.provider_meta = cached.meta;
             self.connection_key = Some(cached.connection_key);
             self.path = cached.path;
+            self.entries.clear();
+            self.sorted_indices.clear();
             self.cursor = 0;
             self.scroll_offset = 0;
             self.refresh();
@@ -261,46 +392,66 @@ impl Panel {
 
     /// Get provider info for display
     pub fn provider_name(&self) -> String {
-        self.provider.info().name.clone()
+        self.provider_meta.name.clone()
     }
 
     /// Get short label for panel header (e.g., "[ZIP]" for archives)
     pub fn provider_short_label(&self) -> Option<String> {
-        self.provider.short_label()
+        self.provider_meta.short_label.clone()
+    }
+
+    /// Check whether the provider supports an optional operation
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.provider_meta.capabilities.contains(&capability)
+    }
+
+    /// Error message for an operation the provider doesn't support
+    pub fn require(&self, capability: Capability) -> Result<(), String> {
+        if self.supports(capability) {
+            Ok(())
+        } else {
+            Err(format!("{} does not support {}", self.provider_meta.name, capability.description()))
+        }
     }
 
     /// Write a file via the provider
     pub fn write_file(&mut self, path: &str, data: &[u8]) -> AppResult<()> {
-        Ok(self.provider.write_file(path, data)?)
+        Ok(self.try_provider()?.write_file(path, data)?)
     }
 
     /// Set file attributes (modification time, permissions) via the provider
     pub fn set_attributes(&mut self, path: &str, modified: Option<std::time::SystemTime>, permissions: u32) -> AppResult<()> {
-        Ok(self.provider.set_attributes(path, modified, permissions)?)
+        Ok(self.try_provider()?.set_attributes(path, modified, permissions)?)
     }
 
     /// Delete a file/directory via the provider
     pub fn delete_path(&mut self, path: &str, recursive: bool) -> AppResult<()> {
+        let mut provider = self.try_provider()?;
         if recursive {
-            Ok(self.provider.delete_recursive(path)?)
+            Ok(provider.delete_recursive(path)?)
         } else {
-            Ok(self.provider.delete(path)?)
+            Ok(provider.delete(path)?)
         }
     }
 
     /// Rename/move a file or directory via the provider
     pub fn rename_path(&mut self, from: &str, to: &str) -> AppResult<()> {
-        Ok(self.provider.rename(from, to)?)
+        Ok(self.try_provider()?.rename(from, to)?)
     }
 
     /// Create a directory via the provider
     pub fn mkdir(&mut self, path: &str) -> AppResult<()> {
-        Ok(self.provider.mkdir(path)?)
+        Ok(self.try_provider()?.mkdir(path)?)
+    }
+
+    /// Have the provider commit the edits it held back during an operation
+    pub fn flush(&mut self) -> AppResult<()> {
+        Ok(self.try_provider()?.flush()?)
     }
 
     /// Read a file via the provider
     pub fn read_file(&mut self, path: &str) -> AppResult<Vec<u8>> {
-        Ok(self.provider.read_file(path)?)
+        Ok(self.try_provider()?.read_file(path)?)
     }
 
     /// Refresh directory contents
@@ -310,34 +461,165 @@ impl Panel {
             return;
         }
 
-        // Use provider for all filesystem operations
-        let path_str = self.path.to_string_lossy().to_string();
-        let result = self.provider.list_directory(&path_str);
+        // A refresh during a pending listing re-sends that listing, so a
+        // directory change in flight is not lost
+        match self.loading.take() {
+            Some(pending) => {
+                pending.cancel.store(true, Ordering::Relaxed);
+                self.request_listing(pending.path, pending.intent);
+            }
+            None => {
+                self.request_listing(self.path.clone(), ListingIntent::Refresh { focus: None });
+            }
+        }
+    }
+
+    /// Refresh and put the cursor on `name` once the listing arrives
+    pub fn refresh_and_focus(&mut self, name: &str) {
+        if self.temp_mode {
+            return;
+        }
+        self.request_listing(self.path.clone(), ListingIntent::Refresh { focus: Some(name.to_string()) });
+    }
+
+    /// List `path`, either in the background (when attached to the I/O
+    /// manager) or right here. Returns false if a synchronous listing failed.
+    fn request_listing(&mut self, path: PathBuf, intent: ListingIntent) -> bool {
+        self.cancel_loading_quietly();
+        // Clear now rather than on arrival, so errors reported by the caller
+        // after refreshing are not wiped when the listing comes in
+        if let ListingIntent::Refresh { .. } = intent {
+            self.error = None;
+        }
+
+        let Some((side, io)) = &self.io else {
+            let result = read_listing(self.lock_provider().as_mut(), &path.to_string_lossy());
+            return self.apply_listing(path, intent, result);
+        };
+
+        self.listing_seq += 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        io.send(IoRequest::List {
+            side: *side,
+            id: self.listing_seq,
+            path: path.clone(),
+            provider: self.provider.clone(),
+            cancel: cancel.clone(),
+        });
+        self.loading = Some(PendingListing {
+            id: self.listing_seq,
+            path,
+            intent,
+            cancel,
+            started: Instant::now(),
+        });
+        true
+    }
+
+    /// Apply a listing produced by the I/O manager. Responses to requests
+    /// that were superseded or cancelled are ignored.
+    pub fn apply_io_listing(&mut self, id: u64, result: Result<Listing, String>) {
+        let Some(pending) = self.loading.take_if(|p| p.id == id) else {
+            return;
+        };
+        self.apply_listing(pending.path, pending.intent, result);
+    }
 
-        match result {
-            Ok(entries) => {
-                // Filter hidden files if show_hidden is false
-                self.entries = if self.show_hidden {
-                    entries
+    /// Install listing results. Returns true on success.
+    fn apply_listing(&mut self, path: PathBuf, intent: ListingIntent, result: Result<Listing, String>) -> bool {
+        let entries = match result {
+            Ok(listing) => {
+                self.remote_free_space = listing.free_space;
+                listing.entries
+            }
+            Err(e) => {
+                if let ListingIntent::Enter { .. } = intent {
+                    // Stay where we are
+                    self.error = Some(format!("Cannot enter '{}': {}", path.to_string_lossy(), e));
                 } else {
-                    entries.into_iter()
-                        .filter(|e| e.name == ".." || !e.name.starts_with('.'))
-                        .collect()
-                };
-                self.error = None;
+                    self.error = Some(e);
+                    self.entries.clear();
+                    self.sorted_indices.clear();
+                    self.cursor = 0;
+                }
+                return false;
+            }
+        };
+
+        self.path = path;
+        // Filter hidden files if show_hidden is false
+        self.entries = if self.show_hidden {
+            entries
+        } else {
+            entries.into_iter()
+                .filter(|e| e.name == ".." || !e.name.starts_with('.'))
+                .collect()
+        };
+
+        match intent {
+            ListingIntent::Refresh { focus } => {
                 self.resort();
                 // Keep cursor in bounds
                 if self.cursor >= self.sorted_indices.len() {
                     self.cursor = self.sorted_indices.len().saturating_sub(1);
                 }
+                if let Some(name) = focus {
+                    self.focus_name(&name);
+                }
             }
-            Err(e) => {
-                self.error = Some(e.to_string());
-                self.entries.clear();
-                self.sorted_indices.clear();
+            ListingIntent::Enter { focus } => {
+                self.error = None;
+                self.selected.clear();  // Clear selection when changing directory
+                self.resort();
                 self.cursor = 0;
+                self.scroll_offset = 0;
+                if let Some(name) = focus {
+                    self.focus_name(&name);
+                }
+            }
+            ListingIntent::Restore { cursor, scroll_offset } => {
+                self.error = None;
+                self.resort();
+                self.cursor = cursor.min(self.sorted_indices.len().saturating_sub(1));
+                self.scroll_offset = scroll_offset;
+                self.adjust_scroll();
             }
         }
+        true
+    }
+
+    /// Put the cursor on the entry with exactly this name, if present
+    fn focus_name(&mut self, name: &str) {
+        if let Some(i) = self.sorted_indices.iter().position(|&idx| self.entries[idx].name == name) {
+            self.cursor = i;
+            self.adjust_scroll();
+        }
+    }
+
+    /// Whether a background listing is in flight
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    /// How long the current listing has been running
+    pub fn loading_elapsed(&self) -> Option<std::time::Duration> {
+        self.loading.as_ref().map(|p| p.started.elapsed())
+    }
+
+    /// Abandon the listing in flight (Esc). Returns true if there was one.
+    pub fn cancel_loading(&mut self) -> bool {
+        let Some(pending) = self.loading.take() else {
+            return false;
+        };
+        pending.cancel.store(true, Ordering::Relaxed);
+        self.error = Some(format!("Listing of '{}' cancelled", pending.path.to_string_lossy()));
+        true
+    }
+
+    fn cancel_loading_quietly(&mut self) {
+        if let Some(pending) = self.loading.take() {
+            pending.cancel.store(true, Ordering::Relaxed);
+        }
     }
 
     /// Toggle show hidden files
@@ -346,14 +628,25 @@ impl Panel {
         self.refresh();
     }
 
-    /// Enter temp mode with the given file paths
+    /// Enter temp mode with the given local file paths
     pub fn enter_temp_mode(&mut self, paths: Vec<PathBuf>) {
+        let entries = paths.iter().filter_map(|path| FileEntry::from_path(path).ok()).collect();
+        self.enter_temp_entries(entries);
+    }
+
+    /// Enter temp mode with entries listed by this panel's provider. The
+    /// provider stays attached, so remote entries can still be viewed and
+    /// copied.
+    pub fn enter_temp_entries(&mut self, entries: Vec<FileEntry>) {
         if self.temp_mode {
             // Already in temp mode, just add files
-            self.add_to_temp(paths);
+            self.add_to_temp(entries);
             return;
         }
 
+        // A listing arriving now would overwrite the virtual entries
+        self.cancel_loading_quietly();
+
         // Save current state
         self.saved_state = Some(SavedPanelState {
             path: self.path.clone(),
@@ -372,18 +665,15 @@ impl Panel {
         self.error = None;
 
         // Add files to temp panel
-        self.add_to_temp(paths);
+        self.add_to_temp(entries);
     }
 
     /// Add files to temp panel
-    fn add_to_temp(&mut self, paths: Vec<PathBuf>) {
-        for path in paths {
-            // Create FileEntry for each path using the existing from_path method
-            if let Ok(entry) = FileEntry::from_path(&path) {
-                // Check if entry already exists
-                if !self.entries.iter().any(|e| e.path == entry.path) {
-                    self.entries.push(entry);
-                }
+    fn add_to_temp(&mut self, entries: Vec<FileEntry>) {
+        for entry in entries {
+            // Check if entry already exists
+            if !self.entries.iter().any(|e| e.path == entry.path) {
+                self.entries.push(entry);
             }
         }
 
@@ -419,6 +709,14 @@ impl Panel {
         self.temp_mode
     }
 
+    /// Directory the panel is browsing; in temp mode, the one it returns to
+    pub fn browse_path(&self) -> &Path {
+        match &self.saved_state {
+            Some(saved) if self.temp_mode => &saved.path,
+            _ => &self.path,
+        }
+    }
+
     /// Remove the current entry from temp panel (doesn't delete from disk)
     /// Returns true if an entry was removed
     pub fn remove_current_from_temp(&mut self) -> bool {
@@ -829,14 +1127,13 @@ impl Panel {
         self.parent_provider = Some(ParentProviderInfo {
             path: parent_path,
             entry_name: source_name.to_string(),
-            was_local: self.provider.is_local(),
+            was_local: self.provider_meta.local,
             cursor: self.cursor,
             scroll_offset: self.scroll_offset,
         });
 
         // Switch to extension provider
-        self.provider.disconnect();
-        self.provider = provider;
+        self.install_provider(provider);
         self.path = PathBuf::from("/");
         self.cursor = 0;
         self.scroll_offset = 0;
@@ -847,12 +1144,11 @@ impl Panel {
     /// Reconnect an extension-mode provider (e.g., after entering password for encrypted archive)
     /// Replaces the current provider but preserves parent_provider info so ESC still works.
     pub fn set_provider_password(&mut self, password: &str) -> crate::providers::ProviderResult<()> {
-        self.provider.set_password(password)
+        self.lock_provider().set_password(password)
     }
 
     pub fn reconnect_extension_provider(&mut self, provider: Box<dyn PanelProvider>) {
-        self.provider.disconnect();
-        self.provider = provider;
+        self.install_provider(provider);
         self.path = PathBuf::from("/");
         self.cursor = 0;
         self.scroll_offset = 0;
@@ -863,15 +1159,22 @@ impl Panel {
     /// Go to parent directory
     /// Returns true if successful, false if already at root
     pub fn go_parent(&mut self) -> bool {
+        // Navigate relative to a directory change still in flight, so
+        // repeated Backspace keeps climbing
+        let current = match &self.loading {
+            Some(PendingListing { path, intent: ListingIntent::Enter { .. }, .. }) => path.clone(),
+            _ => self.path.clone(),
+        };
+
         // Check if we're at the root of an archive - if so, exit the archive
-        let path_str = self.path.to_string_lossy();
+        let path_str = current.to_string_lossy();
         let at_archive_root = path_str == "/" || path_str.is_empty();
 
         if at_archive_root && self.parent_provider.is_some() {
             return self.exit_archive();
         }
 
-        let Some(parent) = self.path.parent() else {
+        let Some(parent) = current.parent() else {
             // If we're truly at root and in an archive, exit it
             if self.parent_provider.is_some() {
                 return self.exit_archive();
@@ -882,7 +1185,7 @@ impl Panel {
         // For archive providers, also check if parent would be empty/root
         let parent_str = parent.to_string_lossy();
         if (parent_str.is_empty() || parent_str == "/") && self.parent_provider.is_some() {
-            if self.path.to_string_lossy() == "/" {
+            if path_str == "/" {
                 // Already at root, exit archive
                 return self.exit_archive();
             }
@@ -891,7 +1194,7 @@ impl Panel {
         }
 
         // Remember current directory name to position cursor on it after going up
-        let current_name = self.path.file_name()
+        let current_name = current.file_name()
             .map(|s| s.to_string_lossy().into_owned());
 
         let parent_path = if self.parent_provider.is_some()
@@ -902,21 +1205,8 @@ impl Panel {
             parent.to_path_buf()
         };
 
-        if self.change_directory(parent_path) {
-            // Try to position cursor on the directory we just left
-            if let Some(name) = current_name {
-                for (i, &idx) in self.sorted_indices.iter().enumerate() {
-                    if self.entries[idx].name == name {
-                        self.cursor = i;
-                        self.adjust_scroll();
-                        break;
-                    }
-                }
-            }
-            true
-        } else {
-            false
-        }
+        // Position the cursor on the directory we just left
+        self.request_listing(parent_path, ListingIntent::Enter { focus: current_name })
     }
 
     /// Exit from an archive back to the parent provider
@@ -926,54 +1216,24 @@ impl Panel {
         };
 
         // Switch back to local provider
-        self.provider.disconnect();
-        self.provider = Box::new(LocalProvider::new());
-        self.path = parent_info.path;
+        self.install_provider(Box::new(LocalProvider::new()));
+        self.path = parent_info.path.clone();
         self.selected.clear();
-        self.refresh();
 
         // Restore cursor and scroll position to exactly where we were
-        self.cursor = parent_info.cursor.min(self.sorted_indices.len().saturating_sub(1));
-        self.scroll_offset = parent_info.scroll_offset;
-        self.adjust_scroll();
+        self.request_listing(parent_info.path, ListingIntent::Restore {
+            cursor: parent_info.cursor,
+            scroll_offset: parent_info.scroll_offset,
+        });
 
         true
     }
 
     /// Change to a new directory
-    /// Returns true if successful
+    /// Returns false if the directory could not be read. With background
+    /// I/O the listing arrives later and failures are reported then.
     pub fn change_directory(&mut self, new_path: PathBuf) -> bool {
-        // Use provider for all filesystem operations
-        let path_str = new_path.to_string_lossy().to_string();
-        let result = self.provider.list_directory(&path_str);
-
-        match result {
-            Ok(entries) => {
-                self.path = new_path;
-                // Filter hidden files if show_hidden is false
-                self.entries = if self.show_hidden {
-                    entries
-                } else {
-                    entries.into_iter()
-                        .filter(|e| e.name == ".." || !e.name.starts_with('.'))
-                        .collect()
-                };
-                self.error = None;
-                self.selected.clear();  // Clear selection when changing directory
-                self.resort();
-                self.cursor = 0;
-                self.scroll_offset = 0;
-                true
-            }
-            Err(e) => {
-                self.error = Some(format!(
-                    "Cannot enter '{}': {}",
-                    new_path.to_string_lossy(),
-                    e
-                ));
-                false
-            }
-        }
+        self.request_listing(new_path, ListingIntent::Enter { focus: None })
     }
 
     /// Clear any error message
@@ -1042,3 +1302,66 @@ impl Panel {
             .sum()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::async_io::{IoManager, IoResponse};
+    use crate::test_utils::ScratchDir;
+    use std::time::Duration;
+
+    /// Scratch directory with subdirectories a and b, and a file in b
+    fn sample_dir(name: &str) -> ScratchDir {
+        let dir = ScratchDir::new(&format!("panel-{}", name));
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        std::fs::write(dir.join("b/file.txt"), b"x").unwrap();
+        dir
+    }
+
+    /// Feed every response that arrives within a short window to the panel
+    fn drain(io: &IoManager, panel: &mut Panel) {
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            match io.try_recv() {
+                Some(IoResponse::Listed { id, result, .. }) => panel.apply_io_listing(id, result),
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_stale_listing_is_ignored() {
+        let dir = sample_dir("stale");
+        let io = IoManager::new();
+        let mut panel = Panel::new(dir.to_path_buf());
+        panel.attach_io(Side::Left, io.handle());
+
+        // The second navigation supersedes the first
+        assert!(panel.change_directory(dir.join("a")));
+        assert!(panel.change_directory(dir.join("b")));
+        assert!(panel.is_loading());
+        assert_eq!(panel.path, *dir);
+
+        drain(&io, &mut panel);
+        assert!(!panel.is_loading());
+        assert_eq!(panel.path, dir.join("b"));
+        assert!(panel.entries.iter().any(|e| e.name == "file.txt"));
+    }
+
+    #[test]
+    fn test_cancelled_listing_keeps_directory() {
+        let dir = sample_dir("cancel");
+        let io = IoManager::new();
+        let mut panel = Panel::new(dir.to_path_buf());
+        panel.attach_io(Side::Right, io.handle());
+
+        panel.change_directory(dir.join("b"));
+        assert!(panel.cancel_loading());
+        assert!(!panel.cancel_loading());
+
+        drain(&io, &mut panel);
+        assert_eq!(panel.path, *dir);
+        assert!(panel.entries.iter().any(|e| e.name == "a"));
+    }
+}
@@ -1,5 +1,8 @@
 //! UI-related state that changes during rendering.
 
+use crate::fs::follow::FileFollower;
+use crate::ui::viewer_utils::TextLayout;
+
 /// State related to terminal dimensions and UI layout.
 ///
 /// These values are updated during rendering to reflect the current
@@ -10,6 +13,12 @@ pub struct UiState {
     pub viewer_height: usize,
     /// Pending 'g' key for gg/ge commands in viewer
     pub viewer_pending_g: bool,
+    /// Wrap, line-number and tab settings of the text viewer (toggled with w and #)
+    pub viewer_layout: TextLayout,
+    /// Viewed file being followed as it grows (F), if any
+    pub viewer_follow: Option<FileFollower>,
+    /// Notice in the viewer's footer, cleared by the next key
+    pub viewer_notice: Option<String>,
     /// Terminal height (updated during rendering)
     pub terminal_height: u16,
     /// Terminal width (updated during rendering)
@@ -32,6 +41,9 @@ impl Default for UiState {
         Self {
             viewer_height: 0,
             viewer_pending_g: false,
+            viewer_layout: TextLayout::default(),
+            viewer_follow: None,
+            viewer_notice: None,
             terminal_height: 24,
             terminal_width: 80,
             shell_height: 1,
@@ -46,10 +58,11 @@ impl Default for UiState {
 #[allow(dead_code)]
 impl UiState {
     /// Create a new UiState with values from config
-    pub fn from_config(shell_height: u16, left_panel_percent: u16) -> Self {
+    pub fn from_config(shell_height: u16, left_panel_percent: u16, viewer_layout: TextLayout) -> Self {
         Self {
             shell_height,
             left_panel_percent,
+            viewer_layout,
             ..Default::default()
         }
     }
@@ -0,0 +1,182 @@
+//! Folding and breadcrumbs for plugin viewers that show nested data
+//!
+//! A viewer plugin may send, along with its lines, the blocks that can be
+//! folded and a path for each line. [`ViewerOutline`] keeps which blocks
+//! are folded, the lines left showing, and a cursor on one of them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Fold state and cursor of a plugin viewer
+#[derive(Clone, Debug)]
+pub struct ViewerOutline {
+    /// Last line of each foldable block, by its first line
+    folds: BTreeMap<usize, usize>,
+    paths: Vec<String>,
+    collapsed: BTreeSet<usize>,
+    line_count: usize,
+    /// Lines shown, in order
+    rows: Vec<usize>,
+    /// Row the cursor is on
+    pub cursor: usize,
+}
+
+impl ViewerOutline {
+    /// Outline of `line_count` lines, or None when the plugin sent neither
+    /// folds nor paths. Folds that don't fit the lines are dropped.
+    pub fn new(line_count: usize, folds: Vec<(usize, usize)>, paths: Vec<String>) -> Option<Self> {
+        if folds.is_empty() && paths.is_empty() {
+            return None;
+        }
+        let folds = folds.into_iter().filter(|&(start, end)| start < end && end < line_count).collect();
+        Some(Self {
+            folds,
+            paths,
+            collapsed: BTreeSet::new(),
+            line_count,
+            rows: (0..line_count).collect(),
+            cursor: 0,
+        })
+    }
+
+    /// Lines shown, in order
+    pub fn rows(&self) -> &[usize] {
+        &self.rows
+    }
+
+    /// Line under the cursor
+    pub fn line(&self) -> usize {
+        self.rows.get(self.cursor).copied().unwrap_or(0)
+    }
+
+    /// Breadcrumb of the line under the cursor
+    pub fn path(&self) -> Option<&str> {
+        self.paths.get(self.line()).map(String::as_str).filter(|path| !path.is_empty())
+    }
+
+    /// Whether `line` starts a folded block (Some(true)), an open one
+    /// (Some(false)), or none
+    pub fn fold_state(&self, line: usize) -> Option<bool> {
+        self.folds.contains_key(&line).then(|| self.collapsed.contains(&line))
+    }
+
+    /// Number of lines folded into `line`
+    pub fn folded_lines(&self, line: usize) -> usize {
+        self.folds.get(&line).map_or(0, |end| end - line)
+    }
+
+    /// Fold or unfold the block under the cursor
+    pub fn toggle(&mut self) {
+        let line = self.line();
+        if !self.folds.contains_key(&line) {
+            return;
+        }
+        if !self.collapsed.remove(&line) {
+            self.collapsed.insert(line);
+        }
+        self.rebuild(line);
+    }
+
+    /// Unfold the block under the cursor
+    pub fn expand(&mut self) {
+        let line = self.line();
+        if self.collapsed.remove(&line) {
+            self.rebuild(line);
+        }
+    }
+
+    /// Fold the block under the cursor; on a line that is folded already
+    /// or starts no block, go to the block it is in
+    pub fn collapse(&mut self) {
+        let line = self.line();
+        if self.fold_state(line) == Some(false) {
+            self.collapsed.insert(line);
+            self.rebuild(line);
+        } else if let Some((&start, _)) = self.folds.range(..line).rev().find(|&(_, &end)| end >= line) {
+            self.rebuild(start);
+        }
+    }
+
+    /// Fold every block, or unfold every block
+    pub fn set_all(&mut self, collapsed: bool) {
+        let line = self.line();
+        self.collapsed = if collapsed { self.folds.keys().copied().collect() } else { BTreeSet::new() };
+        self.rebuild(line);
+    }
+
+    /// Recompute the rows shown and put the cursor on `line`, or on the
+    /// folded line it is hidden in
+    fn rebuild(&mut self, line: usize) {
+        self.rows.clear();
+        let mut next = 0;
+        while next < self.line_count {
+            self.rows.push(next);
+            next = match self.folds.get(&next) {
+                Some(&end) if self.collapsed.contains(&next) => end + 1,
+                _ => next + 1,
+            };
+        }
+        self.cursor = self.rows.partition_point(|&row| row <= line).saturating_sub(1);
+    }
+
+    /// Move the cursor by `delta` rows
+    pub fn move_cursor(&mut self, delta: isize) {
+        self.cursor = self.cursor.saturating_add_signed(delta).min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Scroll offset that keeps the cursor among `height` rows shown from
+    /// `scroll`
+    pub fn scroll_to_cursor(&self, scroll: usize, height: usize) -> usize {
+        scroll.min(self.cursor).max((self.cursor + 1).saturating_sub(height.max(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// {            0
+    ///   "a": [     1
+    ///     1,       2
+    ///     2        3
+    ///   ],         4
+    ///   "b": {     5
+    ///     "c": 3   6
+    ///   }          7
+    /// }            8
+    fn outline() -> ViewerOutline {
+        let paths = ["$", "$.a", "$.a[0]", "$.a[1]", "$.a", "$.b", "$.b.c", "$.b", "$"];
+        let paths = paths.iter().map(|p| p.to_string()).collect();
+        ViewerOutline::new(9, vec![(0, 8), (1, 4), (5, 7), (7, 20)], paths).unwrap()
+    }
+
+    #[test]
+    fn test_folding() {
+        let mut outline = outline();
+        assert_eq!(outline.fold_state(7), None);
+        outline.move_cursor(1);
+        outline.toggle();
+        assert_eq!(outline.rows(), [0, 1, 5, 6, 7, 8]);
+        assert_eq!((outline.fold_state(1), outline.folded_lines(1)), (Some(true), 3));
+
+        // Left on a line inside a block goes to the block, then folds it
+        outline.move_cursor(2);
+        assert_eq!(outline.path(), Some("$.b.c"));
+        outline.collapse();
+        assert_eq!(outline.line(), 5);
+        outline.collapse();
+        assert_eq!(outline.rows(), [0, 1, 5, 8]);
+
+        outline.set_all(true);
+        assert_eq!((outline.rows(), outline.cursor), (&[0][..], 0));
+        outline.expand();
+        assert_eq!(outline.rows(), [0, 1, 5, 8]);
+        outline.set_all(false);
+        assert_eq!((outline.rows().len(), outline.line()), (9, 0));
+
+        outline.move_cursor(100);
+        assert_eq!(outline.line(), 8);
+        assert_eq!(outline.scroll_to_cursor(0, 4), 5);
+        assert_eq!(outline.scroll_to_cursor(8, 4), 8);
+        assert!(ViewerOutline::new(3, Vec::new(), Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,105 @@
+//! Table navigation for plugin viewers that show columns
+//!
+//! A viewer plugin may send heading lines to keep above the rows and the
+//! position of each column. [`ViewerTable`] keeps the selected column, how
+//! far the table is scrolled sideways, and the sort order asked for.
+
+use crate::plugins::TableColumn;
+
+/// Columns, heading and horizontal position of a plugin viewer
+#[derive(Clone, Debug)]
+pub struct ViewerTable {
+    /// Lines shown above the rows
+    pub header: Vec<String>,
+    pub columns: Vec<TableColumn>,
+    /// Selected column
+    pub column: usize,
+    /// Characters scrolled off the left edge
+    pub hscroll: usize,
+    /// Column the rows are sorted by, and whether descending
+    pub sort: Option<(usize, bool)>,
+}
+
+impl ViewerTable {
+    /// Table of a plugin reply, or None when it sent neither heading nor
+    /// columns
+    pub fn new(header: Vec<String>, columns: Vec<TableColumn>, sort: Option<(usize, bool)>) -> Option<Self> {
+        if header.is_empty() && columns.is_empty() {
+            return None;
+        }
+        Some(Self { header, columns, column: 0, hscroll: 0, sort })
+    }
+
+    /// Select the column `delta` columns away and scroll it into a view
+    /// `width` characters wide
+    pub fn select(&mut self, delta: isize, width: usize) {
+        if self.columns.is_empty() {
+            return;
+        }
+        self.column = self.column.saturating_add_signed(delta).min(self.columns.len() - 1);
+        self.scroll_to_column(width);
+    }
+
+    /// Scroll sideways so the selected column shows, from its start
+    pub fn scroll_to_column(&mut self, width: usize) {
+        let Some(column) = self.columns.get(self.column) else {
+            return;
+        };
+        let end = column.start + column.width;
+        if self.column == 0 {
+            // Keep whatever the plugin put before the first column
+            self.hscroll = 0;
+        } else if column.start < self.hscroll {
+            self.hscroll = column.start;
+        } else if end > self.hscroll + width {
+            self.hscroll = column.start.min(end.saturating_sub(width));
+        }
+    }
+
+    /// Sort order after the next press of the sort key: ascending by the
+    /// selected column, then descending, then back to the file's order
+    pub fn next_sort(&self) -> Option<(usize, bool)> {
+        match self.sort {
+            Some((column, false)) if column == self.column => Some((column, true)),
+            Some((column, true)) if column == self.column => None,
+            _ => Some((self.column, false)),
+        }
+    }
+
+    /// Info line of the selected column
+    pub fn info(&self) -> Option<&str> {
+        self.columns.get(self.column).map(|column| column.info.as_str()).filter(|info| !info.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_and_sort() {
+        let column = |start, width| TableColumn { start, width, info: format!("at {}", start) };
+        let columns = vec![column(4, 10), column(17, 30), column(50, 5)];
+        let mut table = ViewerTable::new(vec!["heading".into()], columns, None).unwrap();
+
+        table.select(1, 40);
+        assert_eq!((table.column, table.hscroll), (1, 7));
+        table.select(1, 40);
+        assert_eq!((table.column, table.hscroll), (2, 15));
+        table.select(5, 40);
+        assert_eq!(table.column, 2);
+        table.select(-1, 40);
+        assert_eq!(table.hscroll, 15);
+        table.select(-1, 40);
+        assert_eq!((table.hscroll, table.info()), (0, Some("at 4")));
+
+        assert_eq!(table.next_sort(), Some((0, false)));
+        table.sort = Some((0, false));
+        assert_eq!(table.next_sort(), Some((0, true)));
+        table.sort = Some((0, true));
+        assert_eq!(table.next_sort(), None);
+        table.column = 2;
+        assert_eq!(table.next_sort(), Some((2, false)));
+        assert!(ViewerTable::new(Vec::new(), Vec::new(), None).is_none());
+    }
+}
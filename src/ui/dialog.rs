@@ -9,6 +9,12 @@ use ratatui::{
     widgets::Widget,
 };
 
+use crate::fs::compare::{SyncAction, SyncItem};
+use crate::fs::copy::CopyOptions;
+use crate::fs::find::{FindField, FindOptions};
+use crate::fs::pack::PackFormat;
+use crate::fs::trash::TrashedItem;
+use crate::state::jobs::Job;
 use crate::state::mode::FileOperation;
 use super::Theme;
 
@@ -20,6 +26,7 @@ pub struct ConfirmDialog<'a> {
     focus: usize,
     input_selected: bool,
     apply_all: bool,
+    options: CopyOptions,
     theme: &'a Theme,
 }
 
@@ -32,6 +39,7 @@ impl<'a> ConfirmDialog<'a> {
         focus: usize,
         input_selected: bool,
         apply_all: bool,
+        options: CopyOptions,
         theme: &'a Theme,
     ) -> Self {
         Self {
@@ -41,25 +49,32 @@ impl<'a> ConfirmDialog<'a> {
             focus,
             input_selected,
             apply_all,
+            options,
             theme,
         }
     }
 }
 
+/// Rows the copy options add to the copy/move dialog
+fn copy_option_rows(operation: &FileOperation) -> u16 {
+    if matches!(operation, FileOperation::Copy) { CopyOptions::LABELS.len() as u16 } else { 0 }
+}
+
 impl Widget for ConfirmDialog<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let is_delete = matches!(self.operation, FileOperation::Delete);
+        let is_delete = self.operation.is_delete();
         // Show checkbox when deleting a single directory
-        let show_checkbox = is_delete
+        let show_checkbox = matches!(self.operation, FileOperation::Delete)
             && self.sources.len() == 1
             && self.sources[0].is_dir();
 
         // Dialog dimensions (smaller for delete, taller with checkbox)
         let dialog_width = 60.min(area.width.saturating_sub(4));
+        let option_rows = copy_option_rows(self.operation);
         let dialog_height = if is_delete {
             if show_checkbox { 10 } else { 8 }
         } else {
-            10
+            10 + option_rows
         };
 
         if area.width < 20 || area.height < dialog_height {
@@ -81,7 +96,7 @@ impl Widget for ConfirmDialog<'_> {
         let (bg_color, border_color) = match self.operation {
             FileOperation::Copy => (self.theme.dialog_copy_bg, self.theme.dialog_copy_border),
             FileOperation::Move => (self.theme.dialog_move_bg, self.theme.dialog_move_border),
-            FileOperation::Delete => (self.theme.dialog_delete_bg, self.theme.dialog_delete_border),
+            FileOperation::Delete | FileOperation::Trash => (self.theme.dialog_delete_bg, self.theme.dialog_delete_border),
         };
 
         // Styles
@@ -132,6 +147,7 @@ impl Widget for ConfirmDialog<'_> {
             FileOperation::Copy => " Copy ",
             FileOperation::Move => " Move ",
             FileOperation::Delete => " Delete ",
+            FileOperation::Trash => " Move to Trash ",
         };
         let title_x = dialog_area.x + (dialog_area.width.saturating_sub(title.len() as u16)) / 2;
         buf.set_string(title_x, dialog_area.y, title, title_style);
@@ -143,7 +159,10 @@ impl Widget for ConfirmDialog<'_> {
         if is_delete {
             // Delete dialog - simpler layout
             // Warning message (line 2)
-            let warning = "Are you sure you want to delete:";
+            let warning = match self.operation {
+                FileOperation::Trash => "Move to the trash:",
+                _ => "Are you sure you want to delete:",
+            };
             buf.set_string(content_x, dialog_area.y + 2, warning, warning_style);
 
             // File info (line 4)
@@ -170,7 +189,10 @@ impl Widget for ConfirmDialog<'_> {
             // Buttons — when checkbox is shown: Delete=focus 2, Cancel=focus 3
             //           otherwise:              Delete=focus 1, Cancel=focus 2
             let button_y = dialog_area.y + 6 + button_offset;
-            let ok_text = "[ Delete ]";
+            let ok_text = match self.operation {
+                FileOperation::Trash => "[ Trash ]",
+                _ => "[ Delete ]",
+            };
             let cancel_text = "[ Cancel ]";
 
             let total_button_width = ok_text.len() + 4 + cancel_text.len();
@@ -193,7 +215,7 @@ impl Widget for ConfirmDialog<'_> {
             let source_label = match self.operation {
                 FileOperation::Copy => source_label,
                 FileOperation::Move => source_label.replace("Copy", "Move"),
-                FileOperation::Delete => unreachable!(),
+                FileOperation::Delete | FileOperation::Trash => unreachable!(),
             };
             let truncated_label: String = source_label.chars().take(content_width).collect();
             buf.set_string(content_x, dialog_area.y + 2, &truncated_label, label_style);
@@ -220,14 +242,24 @@ impl Widget for ConfirmDialog<'_> {
             };
             buf.set_string(content_x, input_y, &display_input, input_style);
 
-            // Buttons (line 7)
-            let button_y = dialog_area.y + 7;
+            // Copy options, one checkbox per line from line 6 (focus 1 onwards)
+            for i in 0..option_rows as usize {
+                let check_char = if self.options.get(i) { 'x' } else { ' ' };
+                let checkbox_text = format!("[{}] {}", check_char, CopyOptions::LABELS[i]);
+                let checkbox_style = if self.focus == i + 1 { button_style_focused } else { label_style };
+                let truncated: String = checkbox_text.chars().take(content_width).collect();
+                buf.set_string(content_x, dialog_area.y + 6 + i as u16, &truncated, checkbox_style);
+            }
+
+            // Buttons (line 7, below the options)
+            let button_y = dialog_area.y + 7 + option_rows;
             let ok_text = "[ OK ]";
             let cancel_text = "[ Cancel ]";
 
             // Button styles based on focus
-            let ok_style = if self.focus == 1 { button_style_focused } else { button_style_unfocused };
-            let cancel_style = if self.focus == 2 { button_style_focused } else { button_style_unfocused };
+            let ok_focus = option_rows as usize + 1;
+            let ok_style = if self.focus == ok_focus { button_style_focused } else { button_style_unfocused };
+            let cancel_style = if self.focus == ok_focus + 1 { button_style_focused } else { button_style_unfocused };
 
             // Center buttons
             let total_button_width = ok_text.len() + 4 + cancel_text.len();
@@ -236,10 +268,14 @@ impl Widget for ConfirmDialog<'_> {
             buf.set_string(button_start_x, button_y, ok_text, ok_style);
             buf.set_string(button_start_x + ok_text.len() as u16 + 4, button_y, cancel_text, cancel_style);
 
-            // Help text (line 8)
-            let help_text = "Tab=Switch  Enter=Select  Esc=Cancel";
+            // Help text (below the buttons)
+            let help_text = if option_rows > 0 {
+                "Tab=Switch  Space=Toggle  Enter=Select  Esc=Cancel"
+            } else {
+                "Tab=Switch  Enter=Select  Esc=Cancel"
+            };
             let help_x = dialog_area.x + (dialog_area.width.saturating_sub(help_text.len() as u16)) / 2;
-            buf.set_string(help_x, dialog_area.y + 8, help_text, help_style);
+            buf.set_string(help_x, button_y + 1, help_text, help_style);
         }
     }
 }
@@ -629,10 +665,121 @@ pub fn mkdir_cursor_position(area: Rect, name_input: &str, cursor_pos: usize) ->
     (cursor_x, input_y)
 }
 
+/// Pack dialog for creating an archive from the selection
+pub struct PackDialog<'a> {
+    item_count: usize,
+    name_input: &'a str,
+    format: PackFormat,
+    level: u32,
+    focus: usize,
+    input_selected: bool,
+    theme: &'a Theme,
+}
+
+impl<'a> PackDialog<'a> {
+    pub fn new(
+        item_count: usize,
+        name_input: &'a str,
+        format: PackFormat,
+        level: u32,
+        focus: usize,
+        input_selected: bool,
+        theme: &'a Theme,
+    ) -> Self {
+        Self {
+            item_count,
+            name_input,
+            format,
+            level,
+            focus,
+            input_selected,
+            theme,
+        }
+    }
+}
+
+impl Widget for PackDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use super::dialog_helpers::{DialogRenderer, DialogStyles};
+
+        let Some(dialog_area) = DialogRenderer::center_dialog(area, 56, 11, 30) else {
+            return;
+        };
+
+        let bg_color = self.theme.dialog_copy_bg;
+        let border_color = self.theme.dialog_copy_border;
+        let styles = DialogStyles::new(self.theme, bg_color, border_color);
+
+        DialogRenderer::fill_background(dialog_area, buf, styles.bg);
+        DialogRenderer::draw_border(dialog_area, buf, styles.border);
+        DialogRenderer::draw_title(dialog_area, buf, " Pack ", styles.title);
+
+        let content_x = dialog_area.x + 2;
+        let content_width = dialog_area.width.saturating_sub(4) as usize;
+
+        let label = format!("Pack {} item(s) into archive:", self.item_count);
+        buf.set_string(content_x, dialog_area.y + 2, &label, styles.label);
+
+        let input_style = if self.focus == 0 {
+            if self.input_selected { styles.input_selected } else { styles.input_focused }
+        } else {
+            styles.input_unfocused
+        };
+        DialogRenderer::draw_input_field(buf, content_x, dialog_area.y + 3, content_width, self.name_input, input_style);
+
+        // Choice rows, cycled with Left/Right when focused
+        let level_text = if self.format.has_level() {
+            self.level.to_string()
+        } else {
+            "none".to_string()
+        };
+        let rows = [
+            ("Format:", self.format.label().to_string(), 1),
+            ("Level: ", level_text, 2),
+        ];
+        for (i, (name, value, focus_idx)) in rows.iter().enumerate() {
+            let y = dialog_area.y + 5 + i as u16;
+            buf.set_string(content_x, y, name, styles.label);
+            let value_style = if self.focus == *focus_idx { styles.input_focused } else { styles.label };
+            buf.set_string(content_x + 9, y, format!("◄ {:<7} ►", value), value_style);
+        }
+
+        DialogRenderer::draw_buttons(
+            dialog_area, buf, 8,
+            &[("[ Pack ]", self.focus == 3), ("[ Cancel ]", self.focus == 4)],
+            styles.button_focused, styles.button_unfocused,
+        );
+
+        DialogRenderer::draw_help(dialog_area, buf, "Tab=Switch  ←/→=Change  Enter=Pack  Esc=Cancel", styles.help);
+    }
+}
+
+/// Calculate cursor position for the pack dialog name field
+pub fn pack_cursor_position(area: Rect, name_input: &str, cursor_pos: usize) -> (u16, u16) {
+    let dialog_width = 56.min(area.width.saturating_sub(4));
+    let dialog_height = 11;
+
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+
+    let content_x = x + 2;
+    let content_width = dialog_width.saturating_sub(4) as usize;
+    let input_y = y + 3;
+
+    let max_input_display = content_width.saturating_sub(1);
+    let cursor_x = if name_input.len() > max_input_display {
+        content_x + max_input_display as u16
+    } else {
+        content_x + cursor_pos.min(name_input.len()) as u16
+    };
+
+    (cursor_x, input_y)
+}
+
 /// Calculate cursor position for the dialog input field
-pub fn dialog_cursor_position(area: Rect, dest_input: &str, cursor_pos: usize) -> (u16, u16) {
+pub fn dialog_cursor_position(area: Rect, operation: &FileOperation, dest_input: &str, cursor_pos: usize) -> (u16, u16) {
     let dialog_width = 60.min(area.width.saturating_sub(4));
-    let dialog_height = 10;
+    let dialog_height = 10 + copy_option_rows(operation);
 
     let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
     let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
@@ -795,37 +942,16 @@ impl Widget for CommandHistoryDialog<'_> {
 
 /// Find files dialog (Alt+/)
 pub struct FindFilesDialog<'a> {
-    pattern_input: &'a str,
-    pattern_case_sensitive: bool,
-    content_input: &'a str,
-    content_case_sensitive: bool,
-    path_input: &'a str,
-    recursive: bool,
-    focus: usize,
+    options: &'a FindOptions,
+    focus: FindField,
     input_selected: bool,
     theme: &'a Theme,
 }
 
 impl<'a> FindFilesDialog<'a> {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        pattern_input: &'a str,
-        pattern_case_sensitive: bool,
-        content_input: &'a str,
-        content_case_sensitive: bool,
-        path_input: &'a str,
-        recursive: bool,
-        focus: usize,
-        input_selected: bool,
-        theme: &'a Theme,
-    ) -> Self {
+    pub fn new(options: &'a FindOptions, focus: FindField, input_selected: bool, theme: &'a Theme) -> Self {
         Self {
-            pattern_input,
-            pattern_case_sensitive,
-            content_input,
-            content_case_sensitive,
-            path_input,
-            recursive,
+            options,
             focus,
             input_selected,
             theme,
@@ -833,274 +959,486 @@ impl<'a> FindFilesDialog<'a> {
     }
 }
 
+const FIND_DIALOG_WIDTH: u16 = 64;
+const FIND_DIALOG_HEIGHT: u16 = 22;
+
+/// Dialog area of the find files dialog, None if the terminal is too small
+fn find_files_area(area: Rect) -> Option<Rect> {
+    use super::dialog_helpers::DialogRenderer;
+    DialogRenderer::center_dialog(area, FIND_DIALOG_WIDTH, FIND_DIALOG_HEIGHT, FIND_DIALOG_WIDTH + 4)
+}
+
+/// Position (x offset, y offset, width) of a find files text field inside the dialog
+fn find_text_field_rect(field: FindField) -> Option<(u16, u16, u16)> {
+    let full = FIND_DIALOG_WIDTH - 4;
+    Some(match field {
+        FindField::Pattern => (2, 3, full),
+        FindField::Content => (2, 7, full),
+        FindField::Path => (2, 11, full),
+        FindField::MinSize => (13, 13, 12),
+        FindField::MaxSize => (29, 13, 12),
+        FindField::NewerThan => (13, 14, 12),
+        FindField::OlderThan => (29, 14, 12),
+        FindField::Owner => (13, 15, 12),
+        FindField::Permissions => (33, 15, 8),
+        _ => return None,
+    })
+}
+
 impl Widget for FindFilesDialog<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        // Dialog dimensions (increased height for case sensitive checkboxes)
-        let dialog_width = 60.min(area.width.saturating_sub(4));
-        let dialog_height = 19;
+        use super::dialog_helpers::{DialogRenderer, DialogStyles};
 
-        if area.width < 30 || area.height < dialog_height {
+        let Some(dialog_area) = find_files_area(area) else {
             return;
-        }
-
-        // Center the dialog
-        let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
-        let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
-
-        let dialog_area = Rect {
-            x,
-            y,
-            width: dialog_width,
-            height: dialog_height,
         };
 
         // Use copy dialog colors (search is non-destructive)
-        let bg_color = self.theme.dialog_copy_bg;
-        let border_color = self.theme.dialog_copy_border;
-
-        // Styles
-        let border_style = Style::default().fg(border_color);
-        let title_style = Style::default().bg(bg_color).fg(self.theme.dialog_title).add_modifier(Modifier::BOLD);
-        let label_style = Style::default().bg(bg_color).fg(self.theme.dialog_text);
-        let input_style_focused = Style::default().bg(self.theme.dialog_input_focused_bg).fg(self.theme.dialog_input_focused_fg);
-        let input_style_selected = Style::default().bg(self.theme.dialog_input_selected_bg).fg(self.theme.dialog_input_selected_fg);
-        let input_style_unfocused = Style::default().bg(bg_color).fg(self.theme.dialog_input_unfocused_fg);
-        let button_style_focused = Style::default().fg(self.theme.dialog_button_focused_fg).bg(self.theme.dialog_button_focused_bg).add_modifier(Modifier::BOLD);
-        let button_style_unfocused = Style::default().fg(self.theme.dialog_button_unfocused).bg(bg_color);
-        let checkbox_style_focused = Style::default().bg(self.theme.dialog_input_focused_bg).fg(self.theme.dialog_input_focused_fg);
-        let checkbox_style_unfocused = Style::default().bg(bg_color).fg(self.theme.dialog_text);
-        let help_style = Style::default().bg(bg_color).fg(self.theme.dialog_help);
-
-        // Draw background
-        let bg_style = Style::default().bg(bg_color);
-        for row in dialog_area.y..dialog_area.y + dialog_area.height {
-            for col in dialog_area.x..dialog_area.x + dialog_area.width {
-                buf[(col, row)].set_char(' ').set_style(bg_style);
+        let styles = DialogStyles::new(self.theme, self.theme.dialog_copy_bg, self.theme.dialog_copy_border);
+        DialogRenderer::fill_background(dialog_area, buf, styles.bg);
+        DialogRenderer::draw_border(dialog_area, buf, styles.border);
+        DialogRenderer::draw_title(dialog_area, buf, " Find Files ", styles.title);
+
+        let (x, y) = (dialog_area.x, dialog_area.y);
+        let labels = [
+            (2, 2, "File name (* and ?, or a regex):"),
+            (2, 6, "Containing text (optional):"),
+            (2, 10, "Starting path:"),
+            (2, 13, "Size:"),
+            (26, 13, "to"),
+            (43, 13, "e.g. 10K, 2M"),
+            (2, 14, "Modified:"),
+            (26, 14, "to"),
+            (43, 14, "YYYY-MM-DD, 7d"),
+            (2, 15, "Owner:"),
+            (26, 15, "Perms:"),
+            (43, 15, "octal, e.g. 111"),
+            (2, 16, "Type:"),
+        ];
+        for (dx, dy, label) in labels {
+            buf.set_string(x + dx, y + dy, label, styles.label);
+        }
+
+        for field in FindField::ALL {
+            let focused = self.focus == field;
+            if let (Some((dx, dy, width)), Some(text)) = (find_text_field_rect(field), self.options.text(field)) {
+                let style = if !focused {
+                    styles.input_unfocused
+                } else if self.input_selected {
+                    styles.input_selected
+                } else {
+                    styles.input_focused
+                };
+                DialogRenderer::draw_input_field(buf, x + dx, y + dy, width as usize, text, style);
             }
         }
 
-        // Draw border
-        buf[(dialog_area.x, dialog_area.y)].set_char('┌').set_style(border_style);
-        buf[(dialog_area.x + dialog_area.width - 1, dialog_area.y)].set_char('┐').set_style(border_style);
-        for col in dialog_area.x + 1..dialog_area.x + dialog_area.width - 1 {
-            buf[(col, dialog_area.y)].set_char('─').set_style(border_style);
+        let checkboxes = [
+            (4, 4, "Case sensitive", FindField::PatternCase),
+            (25, 4, "Regex", FindField::PatternRegex),
+            (4, 8, "Case sensitive", FindField::ContentCase),
+            (25, 8, "Regex", FindField::ContentRegex),
+            (2, 17, "Recursive", FindField::Recursive),
+            (20, 17, "Skip .gitignore'd paths", FindField::SkipIgnored),
+        ];
+        for (dx, dy, label, field) in checkboxes {
+            let checked = self.options.flag(field).unwrap_or(false);
+            DialogRenderer::draw_checkbox(
+                buf, x + dx, y + dy, label, checked, self.focus == field,
+                styles.input_focused, styles.label,
+            );
         }
 
-        buf[(dialog_area.x, dialog_area.y + dialog_area.height - 1)].set_char('└').set_style(border_style);
-        buf[(dialog_area.x + dialog_area.width - 1, dialog_area.y + dialog_area.height - 1)].set_char('┘').set_style(border_style);
-        for col in dialog_area.x + 1..dialog_area.x + dialog_area.width - 1 {
-            buf[(col, dialog_area.y + dialog_area.height - 1)].set_char('─').set_style(border_style);
-        }
+        let type_style = if self.focus == FindField::FileType { styles.input_focused } else { styles.label };
+        buf.set_string(x + 13, y + 16, format!("◄ {:<11} ►", self.options.file_type.label()), type_style);
 
-        for row in dialog_area.y + 1..dialog_area.y + dialog_area.height - 1 {
-            buf[(dialog_area.x, row)].set_char('│').set_style(border_style);
-            buf[(dialog_area.x + dialog_area.width - 1, row)].set_char('│').set_style(border_style);
-        }
-
-        // Title
-        let title = " Find Files ";
-        let title_x = dialog_area.x + (dialog_area.width.saturating_sub(title.len() as u16)) / 2;
-        buf.set_string(title_x, dialog_area.y, title, title_style);
+        DialogRenderer::draw_buttons(
+            dialog_area, buf, 19,
+            &[("[ Search ]", self.focus == FindField::Search), ("[ Cancel ]", self.focus == FindField::Cancel)],
+            styles.button_focused, styles.button_unfocused,
+        );
 
-        // Content area
-        let content_x = dialog_area.x + 2;
-        let content_width = dialog_area.width.saturating_sub(4) as usize;
-        let max_display = content_width.saturating_sub(1);
+        DialogRenderer::draw_help(dialog_area, buf, "Tab=Switch  Space=Toggle  ←/→=Type  Enter=Search  Esc=Cancel", styles.help);
+    }
+}
 
-        // File pattern label (line 2)
-        let pattern_label = "File pattern (* and ? allowed):";
-        buf.set_string(content_x, dialog_area.y + 2, pattern_label, label_style);
+/// Calculate cursor position for the focused find files text field
+pub fn find_files_cursor_position(area: Rect, options: &FindOptions, focus: FindField, cursor_pos: usize) -> Option<(u16, u16)> {
+    let dialog_area = find_files_area(area)?;
+    let (dx, dy, width) = find_text_field_rect(focus)?;
+    let text = options.text(focus)?;
 
-        // Pattern input field (line 3) - focus 0
-        let pattern_y = dialog_area.y + 3;
-        let pattern_style = if self.focus == 0 {
-            if self.input_selected { input_style_selected } else { input_style_focused }
-        } else {
-            input_style_unfocused
-        };
-        for col in content_x..content_x + content_width as u16 {
-            buf[(col, pattern_y)].set_char(' ').set_style(pattern_style);
-        }
-        let display_pattern = if self.pattern_input.len() > max_display {
-            let skip = self.pattern_input.len() - max_display;
-            format!("…{}", &self.pattern_input[skip + 1..])
-        } else {
-            self.pattern_input.to_string()
-        };
-        buf.set_string(content_x, pattern_y, &display_pattern, pattern_style);
+    let max_input_display = width.saturating_sub(1) as usize;
+    let offset = if text.len() > max_input_display {
+        max_input_display
+    } else {
+        cursor_pos.min(text.len())
+    };
+    Some((dialog_area.x + dx + offset as u16, dialog_area.y + dy))
+}
 
-        // Pattern case sensitive checkbox (line 4) - focus 1
-        let pattern_case_y = dialog_area.y + 4;
-        let pattern_case_style = if self.focus == 1 { checkbox_style_focused } else { checkbox_style_unfocused };
-        let pattern_case_text = if self.pattern_case_sensitive {
-            "[x] Case sensitive"
-        } else {
-            "[ ] Case sensitive"
-        };
-        buf.set_string(content_x + 2, pattern_case_y, pattern_case_text, pattern_case_style);
+/// Compare directories dialog (Alt+C)
+pub struct CompareDirsDialog<'a> {
+    by_content: bool,
+    focus: usize,
+    theme: &'a Theme,
+}
 
-        // Content search label (line 6)
-        let content_label = "Containing text (optional):";
-        buf.set_string(content_x, dialog_area.y + 6, content_label, label_style);
+impl<'a> CompareDirsDialog<'a> {
+    pub fn new(by_content: bool, focus: usize, theme: &'a Theme) -> Self {
+        Self { by_content, focus, theme }
+    }
+}
 
-        // Content input field (line 7) - focus 2
-        let content_y = dialog_area.y + 7;
-        let content_style = if self.focus == 2 {
-            if self.input_selected { input_style_selected } else { input_style_focused }
-        } else {
-            input_style_unfocused
-        };
-        for col in content_x..content_x + content_width as u16 {
-            buf[(col, content_y)].set_char(' ').set_style(content_style);
-        }
-        let display_content = if self.content_input.len() > max_display {
-            let skip = self.content_input.len() - max_display;
-            format!("…{}", &self.content_input[skip + 1..])
-        } else {
-            self.content_input.to_string()
-        };
-        buf.set_string(content_x, content_y, &display_content, content_style);
+impl Widget for CompareDirsDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use super::dialog_helpers::{DialogRenderer, DialogStyles};
 
-        // Content case sensitive checkbox (line 8) - focus 3
-        let content_case_y = dialog_area.y + 8;
-        let content_case_style = if self.focus == 3 { checkbox_style_focused } else { checkbox_style_unfocused };
-        let content_case_text = if self.content_case_sensitive {
-            "[x] Case sensitive"
-        } else {
-            "[ ] Case sensitive"
+        let Some(dialog_area) = DialogRenderer::center_dialog(area, 68, 9, 62) else {
+            return;
         };
-        buf.set_string(content_x + 2, content_case_y, content_case_text, content_case_style);
-
-        // Starting path label (line 10)
-        let path_label = "Starting path:";
-        buf.set_string(content_x, dialog_area.y + 10, path_label, label_style);
 
-        // Path input field (line 11) - focus 4
-        let path_y = dialog_area.y + 11;
-        let path_style = if self.focus == 4 {
-            if self.input_selected { input_style_selected } else { input_style_focused }
-        } else {
-            input_style_unfocused
-        };
-        for col in content_x..content_x + content_width as u16 {
-            buf[(col, path_y)].set_char(' ').set_style(path_style);
-        }
-        let display_path = if self.path_input.len() > max_display {
-            let skip = self.path_input.len() - max_display;
-            format!("…{}", &self.path_input[skip + 1..])
-        } else {
-            self.path_input.to_string()
-        };
-        buf.set_string(content_x, path_y, &display_path, path_style);
+        let styles = DialogStyles::new(self.theme, self.theme.dialog_copy_bg, self.theme.dialog_copy_border);
+        DialogRenderer::fill_background(dialog_area, buf, styles.bg);
+        DialogRenderer::draw_border(dialog_area, buf, styles.border);
+        DialogRenderer::draw_title(dialog_area, buf, " Compare Directories ", styles.title);
 
-        // Recursive checkbox (line 13) - focus 5
-        let recursive_y = dialog_area.y + 13;
-        let recursive_style = if self.focus == 5 { checkbox_style_focused } else { checkbox_style_unfocused };
-        let recursive_text = if self.recursive {
-            "[x] Recursive"
-        } else {
-            "[ ] Recursive"
-        };
-        buf.set_string(content_x, recursive_y, recursive_text, recursive_style);
+        let (x, y) = (dialog_area.x, dialog_area.y);
+        buf.set_string(x + 2, y + 2, "Mark files that are new, newer or different", styles.label);
+        DialogRenderer::draw_checkbox(
+            buf, x + 2, y + 3, "Compare contents of same-sized files", self.by_content, self.focus == 0,
+            styles.input_focused, styles.label,
+        );
 
-        // Buttons (line 15) - focus 6 and 7
-        let button_y = dialog_area.y + 15;
-        let search_text = "[ Search ]";
-        let cancel_text = "[ Cancel ]";
+        DialogRenderer::draw_buttons(
+            dialog_area, buf, 5,
+            &[
+                ("[ Compare ]", self.focus == 1),
+                ("[ Synchronize... ]", self.focus == 2),
+                ("[ Tree Diff ]", self.focus == 3),
+                ("[ Cancel ]", self.focus == 4),
+            ],
+            styles.button_focused, styles.button_unfocused,
+        );
 
-        let search_style = if self.focus == 6 { button_style_focused } else { button_style_unfocused };
-        let cancel_style = if self.focus == 7 { button_style_focused } else { button_style_unfocused };
+        DialogRenderer::draw_help(dialog_area, buf, "Tab=Switch  Space=Toggle  Enter=Select  Esc=Cancel", styles.help);
+    }
+}
 
-        // Center buttons
-        let total_button_width = search_text.len() + 4 + cancel_text.len();
-        let button_start_x = dialog_area.x + (dialog_area.width.saturating_sub(total_button_width as u16)) / 2;
+/// Synchronize dialog: one row per differing name with its planned action
+pub struct SynchronizeDialog<'a> {
+    items: &'a [SyncItem],
+    cursor: usize,
+    scroll: usize,
+    theme: &'a Theme,
+}
 
-        buf.set_string(button_start_x, button_y, search_text, search_style);
-        buf.set_string(button_start_x + search_text.len() as u16 + 4, button_y, cancel_text, cancel_style);
+impl<'a> SynchronizeDialog<'a> {
+    pub fn new(items: &'a [SyncItem], cursor: usize, scroll: usize, theme: &'a Theme) -> Self {
+        Self { items, cursor, scroll, theme }
+    }
+}
 
-        // Help text (line 17)
-        let help_text = "Tab=Switch  Space=Toggle  Enter=Select  Esc=Cancel";
-        let help_x = dialog_area.x + (dialog_area.width.saturating_sub(help_text.len() as u16)) / 2;
-        buf.set_string(help_x, dialog_area.y + 17, help_text, help_style);
+/// Size and date columns for one side of a synchronize row
+fn sync_side_columns(entry: Option<&crate::fs::FileEntry>) -> (String, String) {
+    use super::panel::{format_date, format_size_short};
+    match entry {
+        Some(e) if e.is_dir => ("<DIR>".to_string(), format_date(e.modified)),
+        Some(e) => (format_size_short(e.size), format_date(e.modified)),
+        None => (String::new(), String::new()),
     }
 }
 
-/// Calculate cursor position for the find files dialog pattern input field
-pub fn find_files_pattern_cursor_position(area: Rect, pattern_input: &str, cursor_pos: usize) -> (u16, u16) {
-    let dialog_width = 60.min(area.width.saturating_sub(4));
-    let dialog_height = 19;
+impl Widget for SynchronizeDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use super::dialog_helpers::{DialogRenderer, DialogStyles};
 
-    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
-    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+        // Dialog is 6 less in each dimension than the terminal, like the command history
+        let width = area.width.saturating_sub(6);
+        let height = area.height.saturating_sub(6);
+        if width < 60 || height < 6 {
+            return;
+        }
+        let dialog_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
 
-    let content_x = x + 2;
-    let content_width = dialog_width.saturating_sub(4) as usize;
-    let input_y = y + 3;
+        let styles = DialogStyles::new(self.theme, self.theme.dialog_copy_bg, self.theme.dialog_copy_border);
+        let selected_style = Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor_fg);
+        let delete_style = Style::default().bg(self.theme.dialog_copy_bg).fg(self.theme.dialog_warning);
+        DialogRenderer::fill_background(dialog_area, buf, styles.bg);
+        DialogRenderer::draw_border(dialog_area, buf, styles.border);
+        DialogRenderer::draw_title(dialog_area, buf, " Synchronize ", styles.title);
+
+        // name │ size date │ action │ date size
+        let x = dialog_area.x + 2;
+        let inner = (width - 4) as usize;
+        let side_width = 7 + 1 + 12;
+        let name_width = inner.saturating_sub(2 * side_width + 6 + 4);
+        let action_x = x + (name_width + 1 + side_width + 1) as u16;
+        buf.set_string(x, dialog_area.y + 1, "Name", styles.title);
+        buf.set_string(x + name_width as u16 + 1, dialog_area.y + 1, "Left", styles.title);
+        buf.set_string(action_x + 7, dialog_area.y + 1, "Right", styles.title);
+
+        let rows = height.saturating_sub(4) as usize;
+        for (i, sync) in self.items.iter().enumerate().skip(self.scroll).take(rows) {
+            let row_y = dialog_area.y + 2 + (i - self.scroll) as u16;
+            let style = if i == self.cursor { selected_style } else { styles.label };
+            for col in x..x + inner as u16 {
+                buf[(col, row_y)].set_char(' ').set_style(style);
+            }
 
-    let max_input_display = content_width.saturating_sub(1);
-    let cursor_x = if pattern_input.len() > max_input_display {
-        content_x + max_input_display as u16
-    } else {
-        content_x + cursor_pos.min(pattern_input.len()) as u16
-    };
+            let mut name: String = sync.item.name.chars().take(name_width).collect();
+            if sync.item.name.chars().count() > name_width && name_width > 0 {
+                name.pop();
+                name.push('…');
+            }
+            let (left_size, left_date) = sync_side_columns(sync.item.left.as_ref());
+            let (right_size, right_date) = sync_side_columns(sync.item.right.as_ref());
+            buf.set_string(x, row_y, name, style);
+            buf.set_string(
+                x + name_width as u16 + 1, row_y,
+                format!("{:>7} {:12}", left_size, left_date), style,
+            );
+            let action_style = match sync.action {
+                SyncAction::DeleteLeft | SyncAction::DeleteRight if i != self.cursor => delete_style,
+                _ => style,
+            };
+            buf.set_string(action_x, row_y, sync.action.label(), action_style);
+            buf.set_string(action_x + 7, row_y, format!("{:12} {:>7}", right_date, right_size), style);
+        }
 
-    (cursor_x, input_y)
+        DialogRenderer::draw_help(
+            dialog_area, buf,
+            "←/→=Direction  Space=Cycle  Del=Delete  Enter=Run  Esc=Cancel",
+            styles.help,
+        );
+    }
 }
 
-/// Calculate cursor position for the find files dialog content input field
-pub fn find_files_content_cursor_position(area: Rect, content_input: &str, cursor_pos: usize) -> (u16, u16) {
-    let dialog_width = 60.min(area.width.saturating_sub(4));
-    let dialog_height = 19;
+/// Trash browser: one row per trashed item with where it came from
+pub struct TrashBrowserDialog<'a> {
+    items: &'a [TrashedItem],
+    cursor: usize,
+    scroll: usize,
+    theme: &'a Theme,
+}
 
-    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
-    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+impl<'a> TrashBrowserDialog<'a> {
+    pub fn new(items: &'a [TrashedItem], cursor: usize, scroll: usize, theme: &'a Theme) -> Self {
+        Self { items, cursor, scroll, theme }
+    }
+}
 
-    let content_x = x + 2;
-    let content_width = dialog_width.saturating_sub(4) as usize;
-    let input_y = y + 7;
+/// `text` cut to `width` characters, with an ellipsis when it doesn't fit
+fn fit_column(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
 
-    let max_input_display = content_width.saturating_sub(1);
-    let cursor_x = if content_input.len() > max_input_display {
-        content_x + max_input_display as u16
-    } else {
-        content_x + cursor_pos.min(content_input.len()) as u16
-    };
+impl Widget for TrashBrowserDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use super::dialog_helpers::{DialogRenderer, DialogStyles};
+        use super::panel::format_size_short;
 
-    (cursor_x, input_y)
+        // Dialog is 6 less in each dimension than the terminal, like the command history
+        let width = area.width.saturating_sub(6);
+        let height = area.height.saturating_sub(6);
+        if width < 50 || height < 6 {
+            return;
+        }
+        let dialog_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let styles = DialogStyles::new(self.theme, self.theme.dialog_delete_bg, self.theme.dialog_delete_border);
+        let selected_style = Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor_fg);
+        DialogRenderer::fill_background(dialog_area, buf, styles.bg);
+        DialogRenderer::draw_border(dialog_area, buf, styles.border);
+        DialogRenderer::draw_title(dialog_area, buf, &format!(" Trash ({}) ", self.items.len()), styles.title);
+
+        // name │ original folder │ size │ deleted
+        let x = dialog_area.x + 2;
+        let inner = (width - 4) as usize;
+        let date_width = 16;
+        let size_width = 7;
+        let name_width = (inner.saturating_sub(date_width + size_width + 3) * 2 / 5).max(10);
+        let folder_width = inner.saturating_sub(name_width + date_width + size_width + 3);
+        let folder_x = x + name_width as u16 + 1;
+        let size_x = folder_x + folder_width as u16 + 1;
+        let date_x = size_x + size_width as u16 + 1;
+        buf.set_string(x, dialog_area.y + 1, "Name", styles.title);
+        buf.set_string(folder_x, dialog_area.y + 1, "Original location", styles.title);
+        buf.set_string(date_x, dialog_area.y + 1, "Deleted", styles.title);
+
+        if self.items.is_empty() {
+            let message = "(Trash is empty)";
+            let message_x = dialog_area.x + (width.saturating_sub(message.len() as u16)) / 2;
+            buf.set_string(message_x, dialog_area.y + 3, message, styles.help);
+        }
+
+        let rows = height.saturating_sub(4) as usize;
+        for (i, item) in self.items.iter().enumerate().skip(self.scroll).take(rows) {
+            let row_y = dialog_area.y + 2 + (i - self.scroll) as u16;
+            let style = if i == self.cursor { selected_style } else { styles.label };
+            for col in x..x + inner as u16 {
+                buf[(col, row_y)].set_char(' ').set_style(style);
+            }
+
+            let mut name = item.original.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if item.is_dir {
+                name.push('/');
+            }
+            let folder = item.original.parent().map(|p| p.display().to_string()).unwrap_or_default();
+            let size = if item.is_dir { "<DIR>".to_string() } else { format_size_short(item.size) };
+            let deleted: String = item.deleted.replacen('T', " ", 1).chars().take(date_width).collect();
+            buf.set_string(x, row_y, fit_column(&name, name_width), style);
+            buf.set_string(folder_x, row_y, fit_column(&folder, folder_width), style);
+            buf.set_string(size_x, row_y, format!("{:>size_width$}", size), style);
+            buf.set_string(date_x, row_y, deleted, style);
+        }
+
+        DialogRenderer::draw_help(
+            dialog_area, buf,
+            "Enter=Restore  F8=Delete  E=Empty trash  Esc=Close",
+            styles.help,
+        );
+    }
 }
 
-/// Calculate cursor position for the find files dialog path input field
-pub fn find_files_path_cursor_position(area: Rect, path_input: &str, cursor_pos: usize) -> (u16, u16) {
-    let dialog_width = 60.min(area.width.saturating_sub(4));
-    let dialog_height = 19;
+/// Background jobs: progress, speed and time left of each, in queue order
+pub struct JobsDialog<'a> {
+    jobs: &'a [Job],
+    cursor: usize,
+    theme: &'a Theme,
+}
 
-    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
-    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+impl<'a> JobsDialog<'a> {
+    pub fn new(jobs: &'a [Job], cursor: usize, theme: &'a Theme) -> Self {
+        Self { jobs, cursor, theme }
+    }
+}
 
-    let content_x = x + 2;
-    let content_width = dialog_width.saturating_sub(4) as usize;
-    let input_y = y + 11;
+impl Widget for JobsDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use super::dialog_helpers::{DialogRenderer, DialogStyles};
+        use super::panel::format_size_short;
 
-    let max_input_display = content_width.saturating_sub(1);
-    let cursor_x = if path_input.len() > max_input_display {
-        content_x + max_input_display as u16
-    } else {
-        content_x + cursor_pos.min(path_input.len()) as u16
-    };
+        let width = area.width.saturating_sub(6);
+        let height = area.height.saturating_sub(6);
+        if width < 60 || height < 9 {
+            return;
+        }
+        let dialog_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
 
-    (cursor_x, input_y)
+        let styles = DialogStyles::new(self.theme, self.theme.dialog_copy_bg, self.theme.dialog_copy_border);
+        let selected_style = Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor_fg);
+        DialogRenderer::fill_background(dialog_area, buf, styles.bg);
+        DialogRenderer::draw_border(dialog_area, buf, styles.border);
+        DialogRenderer::draw_title(dialog_area, buf, &format!(" Jobs ({}) ", self.jobs.len()), styles.title);
+
+        // job │ state │ % │ done/total │ speed │ ETA
+        let x = dialog_area.x + 2;
+        let inner = (width - 4) as usize;
+        let (state_width, pct_width, size_width, speed_width, eta_width) = (10, 4, 13, 9, 7);
+        let job_width = inner.saturating_sub(state_width + pct_width + size_width + speed_width + eta_width + 5);
+        let state_x = x + job_width as u16 + 1;
+        let pct_x = state_x + state_width as u16 + 1;
+        let size_x = pct_x + pct_width as u16 + 1;
+        let speed_x = size_x + size_width as u16 + 1;
+        let eta_x = speed_x + speed_width as u16 + 1;
+        buf.set_string(x, dialog_area.y + 1, "Job", styles.title);
+        buf.set_string(state_x, dialog_area.y + 1, "State", styles.title);
+        buf.set_string(size_x, dialog_area.y + 1, format!("{:>size_width$}", "Done"), styles.title);
+        buf.set_string(speed_x, dialog_area.y + 1, format!("{:>speed_width$}", "Speed"), styles.title);
+        buf.set_string(eta_x, dialog_area.y + 1, format!("{:>eta_width$}", "ETA"), styles.title);
+
+        if self.jobs.is_empty() {
+            let message = "(No background jobs)";
+            let message_x = dialog_area.x + (width.saturating_sub(message.len() as u16)) / 2;
+            buf.set_string(message_x, dialog_area.y + 3, message, styles.help);
+        }
+
+        // Two rows at the bottom tell more about the highlighted job
+        let rows = height.saturating_sub(7) as usize;
+        let scroll = (self.cursor + 1).saturating_sub(rows);
+        for (i, job) in self.jobs.iter().enumerate().skip(scroll).take(rows) {
+            let row_y = dialog_area.y + 2 + (i - scroll) as u16;
+            let style = if i == self.cursor { selected_style } else { styles.label };
+            for col in x..x + inner as u16 {
+                buf[(col, row_y)].set_char(' ').set_style(style);
+            }
+
+            let progress = &job.progress;
+            let percent = (progress.bytes_done * 100).checked_div(progress.bytes_total).unwrap_or(0).min(100);
+            let size = format!("{}/{}", format_size_short(progress.bytes_done), format_size_short(progress.bytes_total));
+            let speed = if job.speed >= 1.0 { format!("{}/s", format_size_short(job.speed as u64)) } else { "-".to_string() };
+            let eta = match job.eta() {
+                Some(eta) if eta.as_secs() >= 3600 => {
+                    format!("{}:{:02}:{:02}", eta.as_secs() / 3600, eta.as_secs() / 60 % 60, eta.as_secs() % 60)
+                }
+                Some(eta) => format!("{}:{:02}", eta.as_secs() / 60, eta.as_secs() % 60),
+                None => "-".to_string(),
+            };
+            buf.set_string(x, row_y, fit_column(&job.description, job_width), style);
+            buf.set_string(state_x, row_y, job.state.label(), style);
+            buf.set_string(pct_x, row_y, format!("{:>3}%", percent), style);
+            buf.set_string(size_x, row_y, format!("{:>size_width$}", fit_column(&size, size_width)), style);
+            buf.set_string(speed_x, row_y, format!("{:>speed_width$}", speed), style);
+            buf.set_string(eta_x, row_y, format!("{:>eta_width$}", eta), style);
+        }
+
+        if let Some(job) = self.jobs.get(self.cursor) {
+            let detail_y = dialog_area.y + height - 4;
+            let file = format!(
+                "File {} of {}: {}",
+                (job.progress.files_done + 1).min(job.progress.files_total),
+                job.progress.files_total,
+                job.progress.current_file,
+            );
+            buf.set_string(x, detail_y, fit_column(&file, inner), styles.label);
+            let errors = match job.errors.as_slice() {
+                [] => String::new(),
+                [error] => format!("Skipped: {}", error),
+                [.., last] => format!("{} errors, last: {}", job.errors.len(), last),
+            };
+            buf.set_string(x, detail_y + 1, fit_column(&errors, inner), styles.title);
+        }
+
+        DialogRenderer::draw_help(
+            dialog_area, buf,
+            "Space=Pause/Resume  Del=Cancel  Shift+↑/↓=Reorder  Esc=Close",
+            styles.help,
+        );
+    }
 }
 
 /// Viewer search dialog widget
 pub struct ViewerSearchDialog<'a> {
     text_input: &'a str,
     case_sensitive: bool,
+    regex: bool,
+    multiline: bool,
     hex_input: &'a str,
     focus: usize,
     input_selected: bool,
     theme: &'a Theme,
-    match_count: usize,
-    current_match: Option<usize>,
+    error: Option<&'a str>,
 }
 
 impl<'a> ViewerSearchDialog<'a> {
@@ -1108,22 +1446,24 @@ impl<'a> ViewerSearchDialog<'a> {
     pub fn new(
         text_input: &'a str,
         case_sensitive: bool,
+        regex: bool,
+        multiline: bool,
         hex_input: &'a str,
         focus: usize,
         input_selected: bool,
         theme: &'a Theme,
-        match_count: usize,
-        current_match: Option<usize>,
+        error: Option<&'a str>,
     ) -> Self {
         Self {
             text_input,
             case_sensitive,
+            regex,
+            multiline,
             hex_input,
             focus,
             input_selected,
             theme,
-            match_count,
-            current_match,
+            error,
         }
     }
 }
@@ -1203,7 +1543,7 @@ impl Widget for ViewerSearchDialog<'_> {
         let max_display = content_width.saturating_sub(1);
 
         // Text search label (line 2)
-        let text_label = "Text (* wildcard):";
+        let text_label = if self.regex { "Regular expression:" } else { "Text (* wildcard):" };
         buf.set_string(content_x, dialog_area.y + 2, text_label, label_style);
 
         // Text input field (line 3) - focus 0
@@ -1224,23 +1564,27 @@ impl Widget for ViewerSearchDialog<'_> {
         };
         buf.set_string(content_x, text_y, &display_text, text_style);
 
-        // Case sensitive checkbox (line 4) - focus 1
-        let case_y = dialog_area.y + 4;
-        let case_style = if self.focus == 1 { checkbox_style_focused } else { checkbox_style_unfocused };
-        let case_text = if self.case_sensitive {
-            "[x] Case sensitive"
-        } else {
-            "[ ] Case sensitive"
-        };
-        buf.set_string(content_x + 2, case_y, case_text, case_style);
+        // Case sensitive, regex and multiline checkboxes (line 4) - focus 1-3
+        let options_y = dialog_area.y + 4;
+        let mut option_x = content_x + 2;
+        for (focus, checked, label) in [
+            (1, self.case_sensitive, "Case sensitive"),
+            (2, self.regex, "Regex"),
+            (3, self.multiline, "Multiline"),
+        ] {
+            let style = if self.focus == focus { checkbox_style_focused } else { checkbox_style_unfocused };
+            let text = format!("[{}] {}", if checked { 'x' } else { ' ' }, label);
+            buf.set_string(option_x, options_y, &text, style);
+            option_x += text.len() as u16 + 3;
+        }
 
         // Hex search label (line 6)
         let hex_label = "Hex (e.g., 4D 5A or 4D5A):";
         buf.set_string(content_x, dialog_area.y + 6, hex_label, label_style);
 
-        // Hex input field (line 7) - focus 2
+        // Hex input field (line 7) - focus 4
         let hex_y = dialog_area.y + 7;
-        let hex_style = if self.focus == 2 {
+        let hex_style = if self.focus == 4 {
             if self.input_selected { input_style_selected } else { input_style_focused }
         } else {
             input_style_unfocused
@@ -1256,34 +1600,25 @@ impl Widget for ViewerSearchDialog<'_> {
         };
         buf.set_string(content_x, hex_y, &display_hex, hex_style);
 
-        // Buttons (line 9) - focus 3 and 4
+        // Buttons (line 9) - focus 5, 6 and 7
         let button_y = dialog_area.y + 9;
-        let search_text = "[ Search ]";
-        let cancel_text = "[ Cancel ]";
-
-        let search_style = if self.focus == 3 { button_style_focused } else { button_style_unfocused };
-        let cancel_style = if self.focus == 4 { button_style_focused } else { button_style_unfocused };
+        let buttons = [(5, "[ Search ]"), (6, "[ List All ]"), (7, "[ Cancel ]")];
 
         // Center buttons
-        let total_button_width = search_text.len() + 4 + cancel_text.len();
-        let button_start_x = dialog_area.x + (dialog_area.width.saturating_sub(total_button_width as u16)) / 2;
-
-        buf.set_string(button_start_x, button_y, search_text, search_style);
-        buf.set_string(button_start_x + search_text.len() as u16 + 4, button_y, cancel_text, cancel_style);
+        let total_button_width: usize = buttons.iter().map(|(_, text)| text.len()).sum::<usize>() + 4 * (buttons.len() - 1);
+        let mut button_x = dialog_area.x + (dialog_area.width.saturating_sub(total_button_width as u16)) / 2;
+        for (focus, text) in buttons {
+            let style = if self.focus == focus { button_style_focused } else { button_style_unfocused };
+            buf.set_string(button_x, button_y, text, style);
+            button_x += text.len() as u16 + 4;
+        }
 
-        // Status line (line 11) - show match count if any
-        let status_text = if self.match_count > 0 {
-            if let Some(current) = self.current_match {
-                format!("Match {} of {}", current + 1, self.match_count)
-            } else {
-                format!("{} matches", self.match_count)
-            }
-        } else {
-            String::new()
-        };
-        if !status_text.is_empty() {
-            let status_x = dialog_area.x + (dialog_area.width.saturating_sub(status_text.len() as u16)) / 2;
-            buf.set_string(status_x, dialog_area.y + 11, &status_text, label_style);
+        // Status line (line 11) - why the search could not run
+        if let Some(error) = self.error {
+            let error: String = error.chars().take(content_width).collect();
+            let status_x = dialog_area.x + (dialog_area.width.saturating_sub(error.chars().count() as u16)) / 2;
+            let error_style = Style::default().bg(bg_color).fg(Color::Red);
+            buf.set_string(status_x, dialog_area.y + 11, &error, error_style);
         }
 
         // Help text (line 12)
@@ -2254,6 +2589,86 @@ impl Widget for SimpleConfirmDialog<'_> {
     }
 }
 
+/// Prompt shown when an SSH server's host key is not in known_hosts
+pub struct HostKeyConfirmDialog<'a> {
+    host: &'a str,
+    key_type: &'a str,
+    fingerprint: &'a str,
+    focus: usize,
+    theme: &'a Theme,
+}
+
+impl<'a> HostKeyConfirmDialog<'a> {
+    pub fn new(
+        host: &'a str,
+        key_type: &'a str,
+        fingerprint: &'a str,
+        focus: usize,
+        theme: &'a Theme,
+    ) -> Self {
+        Self {
+            host,
+            key_type,
+            fingerprint,
+            focus,
+            theme,
+        }
+    }
+}
+
+impl Widget for HostKeyConfirmDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use super::dialog_helpers::{DialogRenderer, DialogStyles};
+
+        let Some(dialog_area) = DialogRenderer::center_dialog(area, 68, 12, 40) else {
+            return;
+        };
+
+        // Connection-related dialogs use the move colors
+        let bg_color = self.theme.dialog_move_bg;
+        let border_color = self.theme.dialog_move_border;
+        let styles = DialogStyles::new(self.theme, bg_color, border_color);
+        let warning_style = Style::default().bg(bg_color).fg(self.theme.dialog_warning);
+
+        DialogRenderer::fill_background(dialog_area, buf, styles.bg);
+        DialogRenderer::draw_border(dialog_area, buf, styles.border);
+        DialogRenderer::draw_title(dialog_area, buf, " Unknown Host Key ", styles.title);
+
+        let content_x = dialog_area.x + 2;
+        let content_width = dialog_area.width.saturating_sub(4) as usize;
+        let fit = |text: String| -> String { text.chars().take(content_width).collect() };
+
+        buf.set_string(
+            content_x, dialog_area.y + 2,
+            fit(format!("Host '{}' is not in known_hosts.", self.host)),
+            warning_style,
+        );
+        buf.set_string(
+            content_x, dialog_area.y + 4,
+            fit(format!("{} key fingerprint:", self.key_type)),
+            styles.label,
+        );
+        buf.set_string(content_x, dialog_area.y + 5, fit(self.fingerprint.to_string()), styles.label);
+        buf.set_string(
+            content_x, dialog_area.y + 7,
+            fit("Only continue if this matches the server's real key.".to_string()),
+            styles.label,
+        );
+
+        DialogRenderer::draw_buttons(
+            dialog_area, buf, 9,
+            &[
+                ("[ Trust once ]", self.focus == 0),
+                ("[ Trust and save ]", self.focus == 1),
+                ("[ Cancel ]", self.focus == 2),
+            ],
+            styles.button_focused, styles.button_unfocused,
+        );
+
+        DialogRenderer::draw_help(dialog_area, buf, "O=Once  S=Save  Tab=Switch  Esc=Cancel", styles.help);
+    }
+}
+
 /// User menu dialog (F2)
 pub struct UserMenuDialog<'a> {
     rules: &'a [crate::config::UserMenuRule],
@@ -2818,13 +3233,15 @@ pub struct OverwriteConfirmDialog<'a> {
     filename: &'a str,
     current: usize,
     total: usize,
+    /// Size of the shorter copy there, if the file can be resumed
+    resumable: Option<u64>,
     focus: usize,
     theme: &'a Theme,
 }
 
 impl<'a> OverwriteConfirmDialog<'a> {
-    pub fn new(filename: &'a str, current: usize, total: usize, focus: usize, theme: &'a Theme) -> Self {
-        Self { filename, current, total, focus, theme }
+    pub fn new(filename: &'a str, current: usize, total: usize, resumable: Option<u64>, focus: usize, theme: &'a Theme) -> Self {
+        Self { filename, current, total, resumable, focus, theme }
     }
 }
 
@@ -2895,24 +3312,33 @@ impl Widget for OverwriteConfirmDialog<'_> {
         let name_x = dialog_area.x + (dialog_area.width.saturating_sub(display_name.len() as u16)) / 2;
         buf.set_string(name_x, dialog_area.y + 2, &display_name, dialog_bg);
 
-        // Conflict count
-        let count_str = format!("({} of {})", self.current + 1, self.total);
+        // Conflict count, and how much a resume would keep
+        let count_str = match self.resumable {
+            Some(copied) => format!("({} of {}, {} copied)", self.current + 1, self.total, super::panel::format_size_short(copied)),
+            None => format!("({} of {})", self.current + 1, self.total),
+        };
         let count_x = dialog_area.x + (dialog_area.width.saturating_sub(count_str.len() as u16)) / 2;
         buf.set_string(count_x, dialog_area.y + 3, &count_str, dialog_bg);
 
-        // Buttons: [Yes] [All] [Skip] [Skip All] [Cancel]
-        let buttons = ["[Yes]", "[All]", "[Skip]", "[Skip All]", "[Cancel]"];
+        // Buttons: [Yes] [All] [Resume] [Skip] [Skip All] [Cancel]
+        let buttons = ["[Yes]", "[All]", "[Resume]", "[Skip]", "[Skip All]", "[Cancel]"];
         let total_btn_width: usize = buttons.iter().map(|b| b.len()).sum::<usize>() + (buttons.len() - 1);
         let btn_x = dialog_area.x + (dialog_area.width.saturating_sub(total_btn_width as u16)) / 2;
         let mut cx = btn_x;
         for (i, btn) in buttons.iter().enumerate() {
-            let style = if i == self.focus { button_focused_style } else { button_style };
+            let style = if i == self.focus {
+                button_focused_style
+            } else if i == 2 && self.resumable.is_none() {
+                help_style
+            } else {
+                button_style
+            };
             buf.set_string(cx, dialog_area.y + 5, btn, style);
             cx += btn.len() as u16 + 1;
         }
 
         // Help text
-        let help = "Y/A/S/N/C, Tab, Enter, Esc";
+        let help = "Y/A/R/S/N/C, Tab, Enter, Esc";
         if dialog_width > help.len() as u16 + 4 {
             let help_x = dialog_area.x + (dialog_area.width.saturating_sub(help.len() as u16)) / 2;
             buf.set_string(help_x, dialog_area.y + dialog_area.height - 1, help, help_style);
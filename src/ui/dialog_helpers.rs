@@ -164,7 +164,7 @@ impl DialogRenderer {
     }
 
     /// Draw a checkbox with label.
-    #[allow(dead_code, clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_checkbox(
         buf: &mut Buffer,
         x: u16,
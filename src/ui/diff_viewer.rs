@@ -0,0 +1,229 @@
+//! Tree diff and line diff widgets
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+use super::Theme;
+use super::panel::{format_date, format_size_short};
+use crate::fs::FileEntry;
+use crate::fs::diff::{DiffLine, DiffStatus, FileDiff, TreeDiff};
+
+fn status_color(status: DiffStatus) -> Color {
+    match status {
+        DiffStatus::Added => Color::Green,
+        DiffStatus::Removed => Color::Red,
+        DiffStatus::Modified => Color::Yellow,
+    }
+}
+
+/// Fill a row with spaces and write `text` from its left edge, cut to fit
+fn draw_bar(buf: &mut Buffer, area: Rect, y: u16, text: &str, style: Style) {
+    for x in area.x..area.x + area.width {
+        buf[(x, y)].set_char(' ').set_style(style);
+    }
+    let text: String = text.chars().take(area.width as usize).collect();
+    buf.set_string(area.x, y, text, style);
+}
+
+/// Write `text` at the right edge of a footer row if it clears `left_len`
+fn draw_right(buf: &mut Buffer, area: Rect, y: u16, left_len: usize, text: &str, style: Style) {
+    let len = text.chars().count() as u16;
+    let x = (area.x + area.width).saturating_sub(len);
+    if x > area.x + left_len as u16 {
+        buf.set_string(x, y, text, style);
+    }
+}
+
+/// Full-screen list of the added, removed and modified entries of two trees
+pub struct DiffTreeViewer<'a> {
+    tree: &'a TreeDiff,
+    cursor: usize,
+    scroll: usize,
+    theme: &'a Theme,
+}
+
+impl<'a> DiffTreeViewer<'a> {
+    pub fn new(tree: &'a TreeDiff, cursor: usize, scroll: usize, theme: &'a Theme) -> Self {
+        Self { tree, cursor, scroll, theme }
+    }
+
+    /// Calculate the visible height (content area, excluding header and footer)
+    pub fn content_height(area: Rect) -> usize {
+        area.height.saturating_sub(2) as usize // -1 header, -1 footer
+    }
+}
+
+/// Size and date of one side of a tree diff row
+fn side_columns(entry: Option<&FileEntry>) -> String {
+    match entry {
+        Some(e) if e.is_dir => format!("{:>7} {:12}", "<DIR>", format_date(e.modified)),
+        Some(e) => format!("{:>7} {:12}", format_size_short(e.size), format_date(e.modified)),
+        None => " ".repeat(20),
+    }
+}
+
+impl Widget for DiffTreeViewer<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 || area.width < 20 {
+            return;
+        }
+
+        let header_style = Style::default().bg(self.theme.viewer_header_bg).fg(self.theme.viewer_header_fg);
+        let content_style = Style::default().bg(self.theme.viewer_content_bg).fg(self.theme.viewer_content_fg);
+        let footer_style = Style::default().bg(self.theme.viewer_footer_bg).fg(self.theme.viewer_footer_fg);
+        let cursor_style = Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor_fg);
+
+        let header = format!(" Tree Diff: {} → {} ", self.tree.left_root, self.tree.right_root);
+        draw_bar(buf, area, area.y, &header, header_style);
+
+        // marker │ indented name │ left size date │ right size date
+        let content_y = area.y + 1;
+        let content_height = Self::content_height(area);
+        let width = area.width as usize;
+        let sides_width = if width >= 80 { 2 * 20 + 2 } else { 0 };
+        let name_width = width.saturating_sub(sides_width + 3);
+
+        for row in 0..content_height {
+            let y = content_y + row as u16;
+            let index = self.scroll + row;
+            let style = if index == self.cursor { cursor_style } else { content_style };
+            draw_bar(buf, area, y, "", content_style);
+            let Some(node) = self.tree.nodes.get(index) else {
+                continue;
+            };
+            draw_bar(buf, area, y, "", style);
+
+            if let Some(status) = node.status {
+                let marker_style = if index == self.cursor { style } else { style.fg(status_color(status)) };
+                buf.set_string(area.x + 1, y, status.marker().to_string(), marker_style);
+            }
+
+            let suffix = if node.is_dir { "/" } else { "" };
+            let name = format!("{}{}{}", "  ".repeat(node.depth), node.name, suffix);
+            let mut shown: String = name.chars().take(name_width).collect();
+            if name.chars().count() > name_width && name_width > 0 {
+                shown.pop();
+                shown.push('…');
+            }
+            let name_style = match node.status {
+                None => style.add_modifier(Modifier::BOLD),
+                Some(status) if index != self.cursor => style.fg(status_color(status)),
+                Some(_) => style,
+            };
+            buf.set_string(area.x + 3, y, shown, name_style);
+
+            if sides_width > 0 && node.status.is_some() {
+                let sides = format!("{} {}", side_columns(node.left.as_ref()), side_columns(node.right.as_ref()));
+                buf.set_string(area.x + (width - sides_width) as u16 + 1, y, sides, style);
+            }
+        }
+
+        let footer_y = area.y + area.height - 1;
+        let summary = format!(
+            " {} added, {} removed, {} modified ",
+            self.tree.count(DiffStatus::Added),
+            self.tree.count(DiffStatus::Removed),
+            self.tree.count(DiffStatus::Modified),
+        );
+        draw_bar(buf, area, footer_y, &summary, footer_style);
+        draw_right(
+            buf, area, footer_y, summary.chars().count(),
+            " +:Right only  -:Left only  ~:Modified  Enter:Diff  Esc:Close ",
+            footer_style,
+        );
+    }
+}
+
+/// Full-screen unified line diff of one file
+pub struct DiffViewer<'a> {
+    diff: &'a FileDiff,
+    scroll: usize,
+    theme: &'a Theme,
+}
+
+impl<'a> DiffViewer<'a> {
+    pub fn new(diff: &'a FileDiff, scroll: usize, theme: &'a Theme) -> Self {
+        Self { diff, scroll, theme }
+    }
+
+    /// Calculate the visible height (content area, excluding header and footer)
+    pub fn content_height(area: Rect) -> usize {
+        area.height.saturating_sub(2) as usize // -1 header, -1 footer
+    }
+}
+
+impl Widget for DiffViewer<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 || area.width < 20 {
+            return;
+        }
+
+        let header_style = Style::default().bg(self.theme.viewer_header_bg).fg(self.theme.viewer_header_fg);
+        let content_style = Style::default().bg(self.theme.viewer_content_bg).fg(self.theme.viewer_content_fg);
+        let number_style = content_style.fg(self.theme.viewer_line_number);
+        let footer_style = Style::default().bg(self.theme.viewer_footer_bg).fg(self.theme.viewer_footer_fg);
+
+        let header = format!(" Diff: {}  (+{} -{}) ", self.diff.rel_path, self.diff.added, self.diff.removed);
+        draw_bar(buf, area, area.y, &header, header_style);
+
+        // Both line number columns are as wide as the largest number
+        let largest = self.diff.lines.iter()
+            .map(|line| match line {
+                DiffLine::Context { old, new, .. } => *old.max(new),
+                DiffLine::Removed { old, .. } => *old,
+                DiffLine::Added { new, .. } => *new,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        let digits = largest.max(1).to_string().len();
+        let number = |n: Option<usize>| n.map_or_else(|| " ".repeat(digits), |n| format!("{:>digits$}", n));
+
+        let content_y = area.y + 1;
+        let content_height = Self::content_height(area);
+        let text_x = area.x + (2 * digits + 3) as u16;
+        let text_width = (area.x + area.width).saturating_sub(text_x) as usize;
+
+        for row in 0..content_height {
+            let y = content_y + row as u16;
+            draw_bar(buf, area, y, "", content_style);
+            let Some(line) = self.diff.lines.get(self.scroll + row) else {
+                continue;
+            };
+            let (old, new, prefix, text, style) = match line {
+                DiffLine::Hunk(header) => {
+                    draw_bar(buf, area, y, header, content_style.fg(Color::Cyan));
+                    continue;
+                }
+                DiffLine::Note(note) => {
+                    draw_bar(buf, area, y, &format!(" {}", note), content_style.add_modifier(Modifier::BOLD));
+                    continue;
+                }
+                DiffLine::Context { old, new, text } => (Some(*old), Some(*new), ' ', text, content_style),
+                DiffLine::Removed { old, text } => (Some(*old), None, '-', text, content_style.fg(Color::Red)),
+                DiffLine::Added { new, text } => (None, Some(*new), '+', text, content_style.fg(Color::Green)),
+            };
+            buf.set_string(area.x, y, format!("{} {}", number(old), number(new)), number_style);
+            let shown: String = std::iter::once(prefix)
+                .chain(text.replace('\t', "    ").chars())
+                .take(text_width + 1)
+                .collect();
+            buf.set_string(text_x - 1, y, shown, style);
+        }
+
+        let footer_y = area.y + area.height - 1;
+        let total = self.diff.lines.len();
+        let visible_end = (self.scroll + content_height).min(total);
+        let position = format!(" Lines {}-{} of {} ", self.scroll.min(total.saturating_sub(1)) + 1, visible_end, total);
+        draw_bar(buf, area, footer_y, &position, footer_style);
+        draw_right(
+            buf, area, footer_y, position.chars().count(),
+            " n/N:Next/Prev change  Esc:Back to tree  F10:Close ",
+            footer_style,
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Text encodings for the file viewer
+//!
+//! [`detect`] looks for a byte-order mark first and otherwise guesses from
+//! the first [`SAMPLE_SIZE`] bytes. Text that is not UTF-8 is decoded into
+//! a `String` for display, while the hex view keeps showing the raw bytes.
+
+use encoding_rs::{Encoding, GBK, KOI8_R, SHIFT_JIS, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1251, WINDOWS_1252};
+
+/// Bytes looked at when guessing an encoding without a BOM
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Largest file decoded automatically on open, which blocks the UI while
+/// it runs. Bigger ones open in hex until an encoding is picked by hand.
+pub const AUTO_DECODE_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Encodings the viewer can decode text from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, read as its Windows-1252 superset
+    Latin1,
+    Windows1251,
+    Koi8R,
+    ShiftJis,
+    Gbk,
+}
+
+impl TextEncoding {
+    /// Cycle order of the viewer's encoding hotkey
+    pub const ALL: [TextEncoding; 8] = [
+        TextEncoding::Utf8,
+        TextEncoding::Utf16Le,
+        TextEncoding::Utf16Be,
+        TextEncoding::Latin1,
+        TextEncoding::Windows1251,
+        TextEncoding::Koi8R,
+        TextEncoding::ShiftJis,
+        TextEncoding::Gbk,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+            TextEncoding::Latin1 => "Latin-1",
+            TextEncoding::Windows1251 => "Windows-1251",
+            TextEncoding::Koi8R => "KOI8-R",
+            TextEncoding::ShiftJis => "Shift-JIS",
+            TextEncoding::Gbk => "GBK",
+        }
+    }
+
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            TextEncoding::Utf8 => UTF_8,
+            TextEncoding::Utf16Le => UTF_16LE,
+            TextEncoding::Utf16Be => UTF_16BE,
+            TextEncoding::Latin1 => WINDOWS_1252,
+            TextEncoding::Windows1251 => WINDOWS_1251,
+            TextEncoding::Koi8R => KOI8_R,
+            TextEncoding::ShiftJis => SHIFT_JIS,
+            TextEncoding::Gbk => GBK,
+        }
+    }
+
+    /// The encoding after this one in [`TextEncoding::ALL`], wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&e| e == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The encoding before this one in [`TextEncoding::ALL`], wrapping around
+    pub fn prev(self) -> Self {
+        let index = Self::ALL.iter().position(|&e| e == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Decode `bytes` to UTF-8, dropping this encoding's BOM and replacing
+    /// invalid sequences with U+FFFD
+    pub fn decode(self, bytes: &[u8]) -> String {
+        let (text, _) = self.encoding().decode_with_bom_removal(bytes);
+        text.into_owned()
+    }
+
+    /// Decode a piece of a file. Only a piece `at_start` of it can begin
+    /// with a BOM, which is dropped.
+    pub fn decode_part(self, bytes: &[u8], at_start: bool) -> String {
+        if at_start {
+            return self.decode(bytes);
+        }
+        self.encoding().decode_without_bom_handling(bytes).0.into_owned()
+    }
+
+    /// Length of `bytes` through their last line feed, or 0 without one.
+    /// No character spans a line feed (in Shift-JIS and GBK it is never a
+    /// trail byte), so text can be decoded piecewise by splitting there.
+    /// UTF-16 `bytes` have to start on a code unit.
+    pub fn line_end(self, bytes: &[u8]) -> usize {
+        let unit = match self {
+            TextEncoding::Utf16Le => [b'\n', 0],
+            TextEncoding::Utf16Be => [0, b'\n'],
+            _ => return bytes.iter().rposition(|&b| b == b'\n').map_or(0, |p| p + 1),
+        };
+        bytes.chunks_exact(2).rposition(|pair| pair == unit).map_or(0, |p| 2 * p + 2)
+    }
+}
+
+/// Why text that needs decoding was opened in hex: it is over
+/// [`AUTO_DECODE_LIMIT`]. None for anything else.
+pub fn undecoded_notice(bytes: &[u8]) -> Option<String> {
+    if bytes.len() <= AUTO_DECODE_LIMIT {
+        return None;
+    }
+    match detect(bytes)? {
+        TextEncoding::Utf8 => None,
+        encoding => Some(format!(
+            " {} text over {} MiB is not decoded, e:Encoding decodes it ",
+            encoding.name(),
+            AUTO_DECODE_LIMIT >> 20
+        )),
+    }
+}
+
+/// Guess the encoding of `bytes`, or None if they look binary
+pub fn detect(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Some(TextEncoding::Utf16Le);
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some(TextEncoding::Utf16Be);
+    }
+
+    // Cut a large file's sample after an ASCII byte so it doesn't end in
+    // the middle of a multi-byte character
+    let sample = if bytes.len() > SAMPLE_SIZE {
+        let sample = &bytes[..SAMPLE_SIZE];
+        &sample[..sample.iter().rposition(|b| b.is_ascii()).map_or(0, |p| p + 1)]
+    } else {
+        bytes
+    };
+
+    // UTF-16 text that is mostly ASCII has a zero in every other byte
+    let pairs = sample.len() / 2;
+    let zeros_at = |parity: usize| sample.iter().skip(parity).step_by(2).take(pairs).filter(|&&b| b == 0).count();
+    let (even_zeros, odd_zeros) = (zeros_at(0), zeros_at(1));
+    if pairs > 0 && odd_zeros * 10 >= pairs * 3 && even_zeros * 20 < pairs {
+        return Some(TextEncoding::Utf16Le);
+    }
+    if pairs > 0 && even_zeros * 10 >= pairs * 3 && odd_zeros * 20 < pairs {
+        return Some(TextEncoding::Utf16Be);
+    }
+
+    // Checked after UTF-16, whose ASCII text is also valid UTF-8
+    if std::str::from_utf8(bytes).is_ok() {
+        return Some(TextEncoding::Utf8);
+    }
+
+    // Any other zero, or more than a few control characters, means binary
+    let controls = sample
+        .iter()
+        .filter(|&&b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B)) || b == 0x7F)
+        .count();
+    if sample.contains(&0) || controls * 100 > sample.len() {
+        return None;
+    }
+
+    // Japanese text always has kana; other text read as Shift-JIS turns
+    // into half-width katakana and stray kanji instead
+    if let Some(text) = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(sample) {
+        let kana = text.chars().filter(|c| ('\u{3040}'..='\u{30FF}').contains(c)).count();
+        let half_width = text.chars().filter(|c| ('\u{FF61}'..='\u{FF9F}').contains(c)).count();
+        if kana > 0 && kana > half_width {
+            return Some(TextEncoding::ShiftJis);
+        }
+    }
+
+    // Western text has the odd accented letter among ASCII ones, while
+    // Cyrillic and Chinese text is mostly high bytes
+    let high = sample.iter().filter(|&&b| b >= 0x80).count();
+    let ascii_letters = sample.iter().filter(|b| b.is_ascii_alphabetic()).count();
+    if high * 10 < (high + ascii_letters) * 3 {
+        return Some(TextEncoding::Latin1);
+    }
+    if GBK.decode_without_bom_handling_and_without_replacement(sample).is_some() {
+        return Some(TextEncoding::Gbk);
+    }
+
+    // Lower case dominates running text: KOI8-R puts it at 0xC0-0xDF and
+    // Windows-1251 at 0xE0-0xFF
+    let koi8_lower = sample.iter().filter(|&&b| (0xC0..0xE0).contains(&b)).count();
+    let cp1251_lower = sample.iter().filter(|&&b| b >= 0xE0).count();
+    if koi8_lower > cp1251_lower {
+        Some(TextEncoding::Koi8R)
+    } else {
+        Some(TextEncoding::Windows1251)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+        encoding.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("plain ascii, ünïcödé".as_bytes()), Some(TextEncoding::Utf8));
+        assert_eq!(detect(b"\x00\x01\x02\x03binary\xFF\x00"), None);
+
+        let utf16: Vec<u8> = "hello\r\nworld".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        assert_eq!(detect(&utf16), Some(TextEncoding::Utf16Le));
+        let utf16: Vec<u8> = "hello\r\nworld".encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        assert_eq!(detect(&utf16), Some(TextEncoding::Utf16Be));
+        assert_eq!(detect(&[0xFF, 0xFE, b'h', 0]), Some(TextEncoding::Utf16Le));
+
+        let cases = [
+            (WINDOWS_1252, "Le café est très crème, où est le garçon?", TextEncoding::Latin1),
+            (WINDOWS_1251, "Привет, как дела? Это простой тест кодировки.", TextEncoding::Windows1251),
+            (KOI8_R, "Привет, как дела? Это простой тест кодировки.", TextEncoding::Koi8R),
+            (SHIFT_JIS, "こんにちは、世界。これはテストです。", TextEncoding::ShiftJis),
+            (GBK, "你好，世界。这是一个编码测试。", TextEncoding::Gbk),
+        ];
+        for (encoding, text, expected) in cases {
+            assert_eq!(detect(&encode(encoding, text)), Some(expected), "{}", expected.name());
+        }
+
+        // Only text that needs decoding gets a notice once it's too big
+        let latin1 = encode(WINDOWS_1252, "très ").repeat(AUTO_DECODE_LIMIT / 5 + 1);
+        assert!(undecoded_notice(&latin1).is_some_and(|notice| notice.contains("Latin-1")));
+        assert_eq!(undecoded_notice(&latin1[..AUTO_DECODE_LIMIT]), None);
+        assert_eq!(undecoded_notice(&vec![b'a'; AUTO_DECODE_LIMIT + 1]), None);
+    }
+
+    #[test]
+    fn test_decode_and_cycle() {
+        assert_eq!(TextEncoding::Windows1251.decode(&encode(WINDOWS_1251, "тест")), "тест");
+        assert_eq!(TextEncoding::Utf16Le.decode(&[0xFF, 0xFE, b'o', 0, b'k', 0]), "ok");
+        assert_eq!(TextEncoding::Utf8.decode(b"a\xFFb"), "a\u{FFFD}b");
+
+        // Split at the last line end, both halves decode to the whole
+        let text = "первая\nвторая\nтре";
+        for (encoding, raw) in [
+            (TextEncoding::Utf16Le, "\u{FEFF}".encode_utf16().chain(text.encode_utf16()).flat_map(u16::to_le_bytes).collect()),
+            (TextEncoding::Utf16Be, text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+            (TextEncoding::Koi8R, encode(KOI8_R, text)),
+            (TextEncoding::Gbk, encode(GBK, "你好\n世界\n编码")),
+        ] {
+            let split = encoding.line_end(&raw);
+            assert!(split > 0 && split < raw.len(), "{}", encoding.name());
+            let joined = encoding.decode_part(&raw[..split], true) + &encoding.decode_part(&raw[split..], false);
+            assert_eq!(joined, encoding.decode(&raw), "{}", encoding.name());
+        }
+        assert_eq!(TextEncoding::Utf16Le.line_end(&[0, b'\n', 0, b'x']), 0);
+
+        assert_eq!(TextEncoding::Utf8.next(), TextEncoding::Utf16Le);
+        assert_eq!(TextEncoding::Gbk.next(), TextEncoding::Utf8);
+        assert_eq!(TextEncoding::Utf8.prev(), TextEncoding::Gbk);
+    }
+}
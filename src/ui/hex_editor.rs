@@ -0,0 +1,170 @@
+//! Hex editor widget
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+use super::cp437::CP437_TABLE;
+use super::Theme;
+use crate::state::hex_editor::HexEditor;
+use crate::utils::calculate_hex_bytes_per_line;
+
+/// Columns taken by the offset at the start of each row
+const OFFSET_WIDTH: usize = 10;
+
+/// Bytes between the extra spaces of the hex column
+const GROUP_SIZE: usize = 8;
+
+/// Hex editor laid out like the viewer's hex dump
+pub struct HexEditorView<'a> {
+    editor: &'a HexEditor,
+    theme: &'a Theme,
+}
+
+impl<'a> HexEditorView<'a> {
+    pub fn new(editor: &'a HexEditor, theme: &'a Theme) -> Self {
+        Self { editor, theme }
+    }
+
+    /// Calculate the visible height (content area, excluding header and footer)
+    pub fn content_height(area: Rect) -> usize {
+        area.height.saturating_sub(2) as usize // -1 header, -1 footer
+    }
+
+    /// Screen position of the cursor in the go-to prompt, if it is open
+    pub fn prompt_cursor(editor: &HexEditor, area: Rect) -> Option<(u16, u16)> {
+        let (_, cursor) = editor.goto.as_ref()?;
+        let x = area.x + (GOTO_PROMPT.len() + cursor) as u16;
+        Some((x.min(area.x + area.width.saturating_sub(1)), area.y + area.height.saturating_sub(1)))
+    }
+}
+
+/// Label of the go-to-offset prompt in the footer
+const GOTO_PROMPT: &str = " Go to offset (decimal or 0x hex): ";
+
+/// Column of byte `index` of a row in the hex area
+fn hex_column(index: usize) -> usize {
+    OFFSET_WIDTH + index * 3 + index / GROUP_SIZE
+}
+
+impl Widget for HexEditorView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 || area.width < 20 {
+            return;
+        }
+        let editor = self.editor;
+        let theme = self.theme;
+
+        let header_style = Style::default().bg(theme.viewer_header_bg).fg(theme.viewer_header_fg);
+        let content_style = Style::default().bg(theme.viewer_content_bg).fg(theme.viewer_content_fg);
+        let offset_style = content_style.fg(theme.viewer_line_number);
+        let changed_style = content_style.fg(Color::Red).add_modifier(Modifier::BOLD);
+        let cursor_style = Style::default().bg(theme.cursor_bg).fg(theme.cursor_fg);
+        let footer_style = Style::default().bg(theme.viewer_footer_bg).fg(theme.viewer_footer_fg);
+
+        let fill = |buf: &mut Buffer, y: u16, style: Style| {
+            for x in area.x..area.x + area.width {
+                buf[(x, y)].set_char(' ').set_style(style);
+            }
+        };
+
+        // Header: path, typing mode and whether there are unsaved changes
+        let mode = if editor.insert { "INS" } else { "OVR" };
+        let column = if editor.ascii { "ASCII" } else { "HEX" };
+        let modified = if editor.buffer.is_modified() { " *" } else { "" };
+        let header = format!(" {}{} [EDIT, {}, {}] ", editor.path.to_string_lossy(), modified, column, mode);
+        fill(buf, area.y, header_style);
+        buf.set_string(area.x, area.y, &header, header_style);
+
+        // Rows of bytes
+        let width = area.width as usize;
+        let bytes_per_line = calculate_hex_bytes_per_line(width);
+        let height = Self::content_height(area);
+        let first = editor.scroll * bytes_per_line;
+        let bytes = editor.buffer.read(first, height * bytes_per_line);
+        let ascii_start = hex_column(bytes_per_line) + 1;
+        let put = |buf: &mut Buffer, col: usize, y: u16, c: char, style: Style| {
+            if col < width {
+                buf[(area.x + col as u16, y)].set_char(c).set_style(style);
+            }
+        };
+
+        for row in 0..height {
+            let y = area.y + 1 + row as u16;
+            fill(buf, y, content_style);
+            let row_start = first + row * bytes_per_line;
+            if row_start > editor.buffer.len() || (row_start == editor.buffer.len() && editor.cursor != row_start) {
+                continue;
+            }
+            for (i, c) in format!("{:08X}", row_start).chars().enumerate() {
+                put(buf, i, y, c, offset_style);
+            }
+            put(buf, ascii_start, y, '|', content_style);
+
+            for index in 0..bytes_per_line {
+                let offset = row_start + index;
+                let at_cursor = offset == editor.cursor;
+                let (hex_x, ascii_x) = (hex_column(index), ascii_start + 1 + index);
+                let Some(&(byte, changed)) = bytes.get(offset - first) else {
+                    // Appending happens at the cell after the last byte
+                    if at_cursor {
+                        let (x, c) = if editor.ascii { (ascii_x, ' ') } else { (hex_x, '_') };
+                        put(buf, x, y, c, cursor_style);
+                    }
+                    put(buf, ascii_x + usize::from(at_cursor && editor.ascii), y, '|', content_style);
+                    break;
+                };
+
+                let style = if changed { changed_style } else { content_style };
+                // The cursor sits on the nibble or character being typed;
+                // the same byte in the other column is underlined
+                let other = style.add_modifier(Modifier::UNDERLINED);
+                let hex = format!("{:02X}", byte);
+                for (n, c) in hex.chars().enumerate() {
+                    let on_nibble = at_cursor && !editor.ascii && (n == 1) == editor.low_nibble;
+                    let cell = match (on_nibble, at_cursor && editor.ascii) {
+                        (true, _) => cursor_style,
+                        (false, true) => other,
+                        _ => style,
+                    };
+                    put(buf, hex_x + n, y, c, cell);
+                }
+                let cell = match (at_cursor, editor.ascii) {
+                    (true, true) => cursor_style,
+                    (true, false) => other,
+                    _ => style,
+                };
+                put(buf, ascii_x, y, CP437_TABLE[byte as usize], cell);
+                if index + 1 == bytes_per_line {
+                    put(buf, ascii_x + 1, y, '|', content_style);
+                }
+            }
+        }
+
+        // Footer: go-to prompt, message, or position and help
+        let footer_y = area.y + area.height - 1;
+        fill(buf, footer_y, footer_style);
+        if let Some((input, _)) = &editor.goto {
+            buf.set_string(area.x, footer_y, format!("{}{}", GOTO_PROMPT, input), footer_style);
+            return;
+        }
+        let position = match editor.buffer.get(editor.cursor) {
+            Some(byte) => format!(
+                " Offset {} (0x{:X}) of {}  Byte 0x{:02X} = {} ",
+                editor.cursor, editor.cursor, editor.buffer.len(), byte, byte
+            ),
+            None => format!(" Offset {} (end) of {} ", editor.cursor, editor.buffer.len()),
+        };
+        let left = editor.message.as_deref().map_or(position, |message| format!(" {} ", message));
+        buf.set_string(area.x, footer_y, &left, footer_style);
+
+        let help = " Tab:HEX/ASCII  Ins:Insert  ^Z/^Y:Undo/Redo  ^G:Go to  F2:Save  Esc:Exit ";
+        let help_x = (area.x + area.width).saturating_sub(help.len() as u16);
+        if help_x > area.x + left.len() as u16 {
+            buf.set_string(help_x, footer_y, help, footer_style);
+        }
+    }
+}
@@ -1,15 +1,20 @@
 //! UI components
 
 pub mod dialog;
+pub mod encoding;
 mod dialog_helpers;
+pub mod diff_viewer;
 pub mod help;
+pub mod hex_editor;
 pub mod panel;
 pub mod plugin_viewer;
 pub mod shell;
 pub mod spinner;
 pub mod status;
+pub mod syntax;
 pub mod theme;
 pub mod viewer;
+pub mod viewer_matches;
 pub mod viewer_menu;
 pub mod cp437;
 pub mod viewer_utils;
@@ -19,16 +24,24 @@ pub use dialog::DeleteIterativeDialog;
 pub use dialog::SimpleConfirmDialog;
 pub use dialog::SourceSelector;
 pub use dialog::MkdirDialog;
+pub use dialog::PackDialog;
 pub use dialog::CommandHistoryDialog;
 pub use dialog::FindFilesDialog;
+pub use dialog::CompareDirsDialog;
+pub use dialog::SynchronizeDialog;
+pub use dialog::TrashBrowserDialog;
+pub use dialog::JobsDialog;
+pub use diff_viewer::{DiffTreeViewer, DiffViewer};
 pub use dialog::ViewerSearchDialog;
 pub use dialog::SelectFilesDialog;
 pub use dialog::ScpConnectDialog;
 pub use dialog::ScpPasswordPromptDialog;
+pub use dialog::HostKeyConfirmDialog;
 pub use dialog::ArchivePasswordPromptDialog;
 pub use dialog::UserMenuDialog;
 pub use dialog::UserMenuEditDialog;
 pub use help::HelpViewer;
+pub use hex_editor::HexEditorView;
 pub use panel::PanelWidget;
 pub use plugin_viewer::PluginViewer;
 pub use shell::ShellArea;
@@ -37,6 +50,7 @@ pub use status::StatusBar;
 pub use theme::Theme;
 pub use theme::ThemeConfig;
 pub use viewer::FileViewer;
+pub use viewer_matches::ViewerMatchList;
 pub use viewer_menu::ViewerPluginMenu;
 pub use dialog::OverlayDialog;
 pub use dialog::OverlaySelectorDialog;
@@ -15,6 +15,7 @@ use ratatui::{
 use crate::utils::get_drive_letter;
 use crate::state::panel::{Panel, ViewMode};
 use crate::fs::FileEntry;
+use super::spinner::Spinner;
 use super::Theme;
 
 /// Get free space for the filesystem containing the given path
@@ -79,6 +80,9 @@ const DRIVE_LINE_HEIGHT: u16 = 1;
 /// Height of the footer area (separator line + footer text line)
 const FOOTER_HEIGHT: u16 = 2;
 
+/// How long a listing runs before the panel shows a loading indicator
+const LOADING_INDICATOR_DELAY: std::time::Duration = std::time::Duration::from_millis(150);
+
 /// Widget for rendering a file panel
 pub struct PanelWidget<'a> {
     is_active: bool,
@@ -134,8 +138,9 @@ impl<'a> PanelWidget<'a> {
 
     /// Format the footer left side (free space + file counts or selection info)
     fn footer_left(panel: &Panel) -> String {
-        // Get free space for current mount
-        let free_space = get_free_space(&panel.path)
+        // Free space comes from the mount for local panels and from the
+        // provider (if it reports it) for remote ones
+        let free_space = if panel.is_local() { get_free_space(&panel.path) } else { panel.remote_free_space }
             .map(|s| format!("{} free", format_size(s)))
             .unwrap_or_default();
 
@@ -548,6 +553,25 @@ impl StatefulWidget for PanelWidget<'_> {
         // Render drive line at top
         Self::render_drive_line(state, self.theme, panel_bg, drive_area, buf);
 
+        // Fast listings finish before the indicator would only flicker
+        if let Some(elapsed) = state.loading_elapsed()
+            && elapsed >= LOADING_INDICATOR_DELAY {
+                let message = "Loading… (Esc to cancel)";
+                let width = (message.chars().count() as u16 + 2).min(drive_area.width);
+                let spinner_area = Rect {
+                    x: drive_area.x + drive_area.width.saturating_sub(width),
+                    y: drive_area.y,
+                    width,
+                    height: drive_area.height,
+                };
+                let style = Style::default().fg(self.theme.panel_header).bg(panel_bg);
+                let frame = (elapsed.as_millis() / 80) as usize;
+                Spinner::new(frame, message)
+                    .spinner_style(style)
+                    .message_style(style)
+                    .render(spinner_area, buf);
+            }
+
         // Render content based on effective view mode
         match effective_view_mode {
             ViewMode::Brief => Self::render_brief(state, self.is_active, self.theme, content_area, buf),
@@ -688,7 +712,7 @@ fn format_size(bytes: u64) -> String {
 }
 
 /// Format a file size for display (short version for columns)
-fn format_size_short(bytes: u64) -> String {
+pub(crate) fn format_size_short(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
@@ -708,7 +732,7 @@ fn format_size_short(bytes: u64) -> String {
 }
 
 /// Format a date for display
-fn format_date(time: Option<SystemTime>) -> String {
+pub(crate) fn format_date(time: Option<SystemTime>) -> String {
     let Some(time) = time else {
         return "------------".to_string();
     };
@@ -10,6 +10,8 @@ use ratatui::{
 };
 
 use super::Theme;
+use crate::state::viewer_outline::ViewerOutline;
+use crate::state::viewer_table::ViewerTable;
 
 /// Plugin viewer widget - displays content rendered by a plugin
 pub struct PluginViewer<'a> {
@@ -19,6 +21,8 @@ pub struct PluginViewer<'a> {
     scroll: usize,
     total_lines: usize,
     status_message: Option<&'a str>,
+    outline: Option<&'a ViewerOutline>,
+    table: Option<&'a ViewerTable>,
     theme: &'a Theme,
 }
 
@@ -39,10 +43,24 @@ impl<'a> PluginViewer<'a> {
             scroll,
             total_lines,
             status_message,
+            outline: None,
+            table: None,
             theme,
         }
     }
 
+    /// Show the lines as a foldable tree with a cursor
+    pub fn with_outline(mut self, outline: Option<&'a ViewerOutline>) -> Self {
+        self.outline = outline;
+        self
+    }
+
+    /// Keep the table heading above the rows and scroll sideways
+    pub fn with_table(mut self, table: Option<&'a ViewerTable>) -> Self {
+        self.table = table;
+        self
+    }
+
     /// Calculate the visible height (content area, excluding header and footer)
     pub fn content_height(area: Rect) -> usize {
         area.height.saturating_sub(2) as usize // -1 header, -1 footer
@@ -84,6 +102,15 @@ impl Widget for PluginViewer<'_> {
             }
         }
 
+        if let Some(outline) = self.outline {
+            self.render_outline(outline, area, buf);
+            return;
+        }
+        if let Some(table) = self.table {
+            self.render_table(table, area, buf);
+            return;
+        }
+
         // Render visible lines (slice from scroll offset)
         for (i, line) in self.lines.iter().skip(self.scroll).enumerate() {
             if i >= content_height {
@@ -136,3 +163,120 @@ impl Widget for PluginViewer<'_> {
         }
     }
 }
+
+impl PluginViewer<'_> {
+    /// Content and footer of a tree: fold markers in front of the lines,
+    /// the cursor line highlighted, and its path in the footer
+    fn render_outline(&self, outline: &ViewerOutline, area: Rect, buf: &mut Buffer) {
+        let content_style = Style::default().bg(self.theme.viewer_content_bg).fg(self.theme.viewer_content_fg);
+        let marker_style = content_style.fg(self.theme.viewer_line_number);
+        let cursor_style = Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor_fg);
+        let footer_style = Style::default().bg(self.theme.viewer_footer_bg).fg(self.theme.viewer_footer_fg);
+
+        let content_height = Self::content_height(area);
+        let width = area.width as usize;
+        let rows = outline.rows();
+        for (i, &line) in rows.iter().skip(self.scroll).take(content_height).enumerate() {
+            let y = area.y + 1 + i as u16;
+            let selected = self.scroll + i == outline.cursor;
+            let style = if selected { cursor_style } else { content_style };
+            if selected {
+                for x in area.x..area.x + area.width {
+                    buf[(x, y)].set_char(' ').set_style(style);
+                }
+            }
+
+            let marker = match outline.fold_state(line) {
+                Some(true) => "▸ ",
+                Some(false) => "▾ ",
+                None => "  ",
+            };
+            buf.set_string(area.x, y, marker, if selected { style } else { marker_style });
+            let text: String = self.lines.get(line).map_or("", String::as_str).chars().take(width.saturating_sub(2)).collect();
+            buf.set_string(area.x + 2, y, &text, style);
+            if outline.fold_state(line) == Some(true) {
+                let folded = format!(" … {} lines", outline.folded_lines(line));
+                let x = area.x + 2 + text.chars().count() as u16;
+                let room = (area.x + area.width).saturating_sub(x) as usize;
+                buf.set_stringn(x, y, &folded, room, if selected { style } else { marker_style });
+            }
+        }
+
+        let footer_y = area.y + area.height - 1;
+        for x in area.x..area.x + area.width {
+            buf[(x, footer_y)].set_char(' ').set_style(footer_style);
+        }
+        let position = format!(" {}/{} ", outline.cursor + 1, rows.len());
+        let left = match outline.path() {
+            Some(path) => format!("{}{} ", position, path),
+            None => position,
+        };
+        buf.set_stringn(area.x, footer_y, &left, width, footer_style);
+
+        let (right, right_style) = match self.status_message {
+            Some(msg) => (format!(" {} ", msg), footer_style.add_modifier(Modifier::BOLD)),
+            None => (" Enter:Fold  ←→:Close/Open  -/+:All  Esc:Exit ".to_string(), footer_style),
+        };
+        let right_x = (area.x + area.width).saturating_sub(right.chars().count() as u16);
+        if right_x > area.x + left.chars().count() as u16 {
+            buf.set_string(right_x, footer_y, &right, right_style);
+        }
+    }
+
+    /// Content and footer of a table: the heading stays on top, every
+    /// line is shifted by the horizontal scroll, and the selected column is
+    /// marked in the heading with its info in the footer
+    fn render_table(&self, table: &ViewerTable, area: Rect, buf: &mut Buffer) {
+        let content_style = Style::default().bg(self.theme.viewer_content_bg).fg(self.theme.viewer_content_fg);
+        let heading_style = content_style.add_modifier(Modifier::BOLD);
+        let selected_style = Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor_fg);
+        let footer_style = Style::default().bg(self.theme.viewer_footer_bg).fg(self.theme.viewer_footer_fg);
+
+        let width = area.width as usize;
+        let content_height = Self::content_height(area);
+        let shifted = |line: &str| -> String { line.chars().skip(table.hscroll).take(width).collect() };
+
+        let heading_rows = table.header.len().min(content_height);
+        for (i, line) in table.header.iter().take(heading_rows).enumerate() {
+            let y = area.y + 1 + i as u16;
+            buf.set_string(area.x, y, shifted(line), heading_style);
+        }
+        // Mark the selected column in the first heading line
+        if let Some(column) = table.columns.get(table.column)
+            && heading_rows > 0
+        {
+            let from = column.start.saturating_sub(table.hscroll).min(width);
+            let to = (column.start + column.width).saturating_sub(table.hscroll).min(width);
+            for x in from..to {
+                buf[(area.x + x as u16, area.y + 1)].set_style(selected_style);
+            }
+        }
+
+        let rows = content_height - heading_rows;
+        for (i, line) in self.lines.iter().skip(self.scroll).take(rows).enumerate() {
+            let y = area.y + 1 + (heading_rows + i) as u16;
+            buf.set_string(area.x, y, shifted(line), content_style);
+        }
+
+        let footer_y = area.y + area.height - 1;
+        for x in area.x..area.x + area.width {
+            buf[(x, footer_y)].set_char(' ').set_style(footer_style);
+        }
+        let visible_end = (self.scroll + rows).min(self.total_lines);
+        let position = format!(" Rows {}-{} of {} ", (self.scroll + 1).min(visible_end), visible_end, self.total_lines);
+        let left = match table.info() {
+            Some(info) => format!("{}│ {} ", position, info),
+            None => position,
+        };
+        buf.set_stringn(area.x, footer_y, &left, width, footer_style);
+
+        let (right, right_style) = match self.status_message {
+            Some(msg) => (format!(" {} ", msg), footer_style.add_modifier(Modifier::BOLD)),
+            None => (" ←→:Column  s:Sort  Esc:Exit ".to_string(), footer_style),
+        };
+        let right_x = (area.x + area.width).saturating_sub(right.chars().count() as u16);
+        if right_x > area.x + left.chars().count() as u16 {
+            buf.set_string(right_x, footer_y, &right, right_style);
+        }
+    }
+}
@@ -226,6 +226,8 @@ pub struct FileOpProgressDialog<'a> {
     bytes_total: u64,
     files_done: usize,
     files_total: usize,
+    /// The operation can be sent to the job queue
+    background: bool,
     theme: &'a Theme,
 }
 
@@ -248,9 +250,15 @@ impl<'a> FileOpProgressDialog<'a> {
             bytes_total,
             files_done,
             files_total,
+            background: false,
             theme,
         }
     }
+
+    pub fn with_background_hint(mut self, background: bool) -> Self {
+        self.background = background;
+        self
+    }
 }
 
 impl Widget for FileOpProgressDialog<'_> {
@@ -358,7 +366,7 @@ impl Widget for FileOpProgressDialog<'_> {
         buf.set_string(count_x, dialog_area.y + 6, &count_str, dialog_bg);
 
         // Help text
-        let help = "Esc = Cancel";
+        let help = if self.background { "Esc = Cancel  B = Background" } else { "Esc = Cancel" };
         if dialog_width > help.len() as u16 + 4 {
             let help_x = dialog_area.x + (dialog_area.width.saturating_sub(help.len() as u16)) / 2;
             buf.set_string(help_x, dialog_area.y + dialog_area.height - 1, help, help_style);
@@ -21,12 +21,14 @@ pub struct StatusBar<'a> {
     python_env: Option<&'a str>,
     quick_search: Option<&'a str>,
     plugin_status: Option<&'a [(String, String)]>,
+    /// Background jobs summary, shown before the plugin outputs
+    jobs: Option<&'a str>,
     theme: &'a Theme,
 }
 
 impl<'a> StatusBar<'a> {
     pub fn new(panel: &'a Panel, theme: &'a Theme) -> Self {
-        Self { panel, git_status: None, python_env: None, quick_search: None, plugin_status: None, theme }
+        Self { panel, git_status: None, python_env: None, quick_search: None, plugin_status: None, jobs: None, theme }
     }
 
     pub fn with_git(mut self, git_status: Option<&'a GitStatus>) -> Self {
@@ -49,6 +51,11 @@ impl<'a> StatusBar<'a> {
         self.plugin_status = status;
         self
     }
+
+    pub fn with_jobs(mut self, jobs: Option<&'a str>) -> Self {
+        self.jobs = jobs;
+        self
+    }
 }
 
 impl Widget for StatusBar<'_> {
@@ -124,6 +131,11 @@ impl Widget for StatusBar<'_> {
         // Calculate plugin width (will be rendered on the right)
         let mut plugin_total_width: u16 = 0;
         let mut plugin_strings: Vec<String> = Vec::new();
+        if let Some(jobs) = self.jobs {
+            let display = format!("│ {} ", jobs);
+            plugin_total_width += display.chars().count() as u16;
+            plugin_strings.push(display);
+        }
         if let Some(plugin_outputs) = self.plugin_status {
             for (_name, text) in plugin_outputs {
                 if !text.is_empty() {
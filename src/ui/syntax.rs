@@ -0,0 +1,668 @@
+//! Syntax highlighting for the built-in text viewer
+//!
+//! A small table of grammars, each described by its comment and string
+//! delimiters and its keyword lists. [`Grammar::highlight_line`] splits one
+//! line into tokens, carrying open block comments and multi-line strings to
+//! the next line in a [`LineState`]. The viewer only highlights the lines on
+//! screen, plus [`LOOKBACK_LINES`] above them to pick up that state.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Lines above the window that are scanned for an open comment or string
+pub const LOOKBACK_LINES: usize = 200;
+
+/// Kind of text a span of a line holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token {
+    Plain,
+    Keyword,
+    Type,
+    /// Built-in values such as true, false and null
+    Constant,
+    String,
+    Number,
+    Comment,
+}
+
+/// Construct left open at the end of a line
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineState {
+    #[default]
+    Clear,
+    BlockComment,
+    /// Index into the grammar's block strings
+    BlockString(usize),
+    /// Quoted string that continues on the next line
+    Quote(char),
+}
+
+/// How one language is highlighted
+#[derive(Debug)]
+pub struct Grammar {
+    /// Name shown in the viewer header and used in `viewer.syntax_map`
+    pub name: &'static str,
+    extensions: &'static [&'static str],
+    /// Whole file names, for files without an extension
+    file_names: &'static [&'static str],
+    /// Shebang interpreters, without version suffixes
+    interpreters: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Delimiters that open and close a string spanning lines, like """
+    block_strings: &'static [&'static str],
+    quotes: &'static [char],
+    /// Quoted strings may continue past the end of a line
+    multiline_quotes: bool,
+    /// Rust-style char literals: a quote that is not closed right away is
+    /// a lifetime, not a string
+    char_literals: bool,
+    /// Identifiers starting with an upper-case letter are types
+    capitalized_types: bool,
+    case_insensitive: bool,
+    keywords: &'static [&'static str],
+    types: &'static [&'static str],
+    constants: &'static [&'static str],
+}
+
+const BASE: Grammar = Grammar {
+    name: "",
+    extensions: &[],
+    file_names: &[],
+    interpreters: &[],
+    line_comments: &[],
+    block_comment: None,
+    block_strings: &[],
+    quotes: &['"', '\''],
+    multiline_quotes: false,
+    char_literals: false,
+    capitalized_types: false,
+    case_insensitive: false,
+    keywords: &[],
+    types: &[],
+    constants: &[],
+};
+
+const C_TYPES: &[&str] = &[
+    "bool", "char", "double", "float", "int", "long", "short", "signed", "unsigned", "void", "size_t", "ssize_t",
+    "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t", "uint64_t", "FILE",
+];
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "const", "continue", "default", "do", "else", "enum", "extern", "for", "goto", "if",
+    "inline", "register", "restrict", "return", "sizeof", "static", "struct", "switch", "typedef", "union",
+    "volatile", "while", "#include", "#define", "#ifdef", "#ifndef", "#if", "#else", "#elif", "#endif", "#pragma",
+    "#undef",
+];
+
+static GRAMMARS: &[Grammar] = &[
+    Grammar {
+        name: "Rust",
+        extensions: &["rs"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        multiline_quotes: true,
+        char_literals: true,
+        capitalized_types: true,
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn",
+            "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+            "static", "struct", "super", "trait", "type", "unsafe", "use", "where", "while", "yield",
+        ],
+        types: &[
+            "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+            "isize", "f32", "f64",
+        ],
+        constants: &["true", "false", "None", "Some", "Ok", "Err"],
+        ..BASE
+    },
+    Grammar {
+        name: "C",
+        extensions: &["c", "h"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        keywords: C_KEYWORDS,
+        types: C_TYPES,
+        constants: &["NULL", "true", "false"],
+        ..BASE
+    },
+    Grammar {
+        name: "C++",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        keywords: &[
+            "auto", "break", "case", "catch", "class", "const", "constexpr", "continue", "default", "delete", "do",
+            "else", "enum", "explicit", "extern", "for", "friend", "goto", "if", "inline", "namespace", "new",
+            "noexcept", "operator", "override", "private", "protected", "public", "return", "sizeof", "static",
+            "struct", "switch", "template", "this", "throw", "try", "typedef", "typename", "union", "using",
+            "virtual", "volatile", "while", "#include", "#define", "#ifdef", "#ifndef", "#if", "#else", "#elif",
+            "#endif", "#pragma",
+        ],
+        types: C_TYPES,
+        constants: &["nullptr", "NULL", "true", "false"],
+        ..BASE
+    },
+    Grammar {
+        name: "C#",
+        extensions: &["cs"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        capitalized_types: true,
+        keywords: &[
+            "abstract", "as", "async", "await", "base", "break", "case", "catch", "class", "const", "continue",
+            "default", "delegate", "do", "else", "enum", "event", "finally", "for", "foreach", "if", "in",
+            "interface", "internal", "is", "lock", "namespace", "new", "out", "override", "private", "protected",
+            "public", "readonly", "ref", "return", "sealed", "static", "struct", "switch", "this", "throw", "try",
+            "using", "var", "virtual", "void", "while",
+        ],
+        types: &["bool", "byte", "char", "decimal", "double", "float", "int", "long", "object", "short", "string"],
+        constants: &["true", "false", "null"],
+        ..BASE
+    },
+    Grammar {
+        name: "Go",
+        extensions: &["go"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        multiline_quotes: true,
+        keywords: &[
+            "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "for", "func",
+            "go", "goto", "if", "import", "interface", "map", "package", "range", "return", "select", "struct",
+            "switch", "type", "var",
+        ],
+        types: &[
+            "bool", "byte", "error", "float32", "float64", "int", "int8", "int16", "int32", "int64", "rune", "string",
+            "uint", "uint8", "uint16", "uint32", "uint64", "uintptr", "any",
+        ],
+        constants: &["true", "false", "nil", "iota"],
+        ..BASE
+    },
+    Grammar {
+        name: "Java",
+        extensions: &["java", "kt", "kts", "scala"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        capitalized_types: true,
+        keywords: &[
+            "abstract", "break", "case", "catch", "class", "continue", "default", "do", "else", "enum", "extends",
+            "final", "finally", "for", "fun", "if", "implements", "import", "instanceof", "interface", "new",
+            "package", "private", "protected", "public", "return", "static", "super", "switch", "synchronized",
+            "this", "throw", "throws", "try", "val", "var", "void", "when", "while",
+        ],
+        types: &["boolean", "byte", "char", "double", "float", "int", "long", "short"],
+        constants: &["true", "false", "null"],
+        ..BASE
+    },
+    Grammar {
+        name: "JavaScript",
+        extensions: &["js", "mjs", "cjs", "jsx"],
+        interpreters: &["node", "deno"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        multiline_quotes: true,
+        keywords: &[
+            "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete", "do",
+            "else", "export", "extends", "finally", "for", "from", "function", "if", "import", "in", "instanceof",
+            "let", "new", "of", "return", "static", "super", "switch", "this", "throw", "try", "typeof", "var",
+            "void", "while", "yield",
+        ],
+        constants: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+        ..BASE
+    },
+    Grammar {
+        name: "TypeScript",
+        extensions: &["ts", "tsx", "mts", "cts"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        multiline_quotes: true,
+        capitalized_types: true,
+        keywords: &[
+            "abstract", "as", "async", "await", "break", "case", "catch", "class", "const", "continue", "declare",
+            "default", "delete", "do", "else", "enum", "export", "extends", "finally", "for", "from", "function",
+            "if", "implements", "import", "in", "instanceof", "interface", "keyof", "let", "namespace", "new", "of",
+            "private", "protected", "public", "readonly", "return", "static", "super", "switch", "this", "throw",
+            "try", "type", "typeof", "var", "void", "while", "yield",
+        ],
+        types: &["any", "boolean", "never", "number", "object", "string", "symbol", "unknown"],
+        constants: &["true", "false", "null", "undefined"],
+        ..BASE
+    },
+    Grammar {
+        name: "Python",
+        extensions: &["py", "pyw", "pyi"],
+        interpreters: &["python"],
+        line_comments: &["#"],
+        block_strings: &["\"\"\"", "'''"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else",
+            "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not",
+            "or", "pass", "raise", "return", "try", "while", "with", "yield", "self",
+        ],
+        types: &["bool", "bytes", "dict", "float", "int", "list", "object", "set", "str", "tuple"],
+        constants: &["True", "False", "None"],
+        ..BASE
+    },
+    Grammar {
+        name: "Ruby",
+        extensions: &["rb", "rake", "gemspec"],
+        file_names: &["Gemfile", "Rakefile"],
+        interpreters: &["ruby"],
+        line_comments: &["#"],
+        capitalized_types: true,
+        keywords: &[
+            "alias", "and", "begin", "break", "case", "class", "def", "do", "else", "elsif", "end",
+            "ensure", "for", "if", "in", "module", "next", "not", "or", "redo", "require", "rescue", "retry",
+            "return", "self", "super", "then", "unless", "until", "when", "while", "yield",
+        ],
+        constants: &["true", "false", "nil"],
+        ..BASE
+    },
+    Grammar {
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh", "ksh", "fish"],
+        file_names: &[".bashrc", ".bash_profile", ".profile", ".zshrc"],
+        interpreters: &["sh", "bash", "zsh", "ksh", "dash", "fish"],
+        line_comments: &["#"],
+        multiline_quotes: true,
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "exit", "export", "fi", "for", "function", "if", "in",
+            "local", "readonly", "return", "select", "set", "shift", "source", "then", "unset", "until", "while",
+        ],
+        constants: &["true", "false"],
+        ..BASE
+    },
+    Grammar {
+        name: "Lua",
+        extensions: &["lua"],
+        interpreters: &["lua"],
+        line_comments: &["--"],
+        block_comment: Some(("--[[", "]]")),
+        keywords: &[
+            "and", "break", "do", "else", "elseif", "end", "for", "function", "goto", "if", "in", "local", "not",
+            "or", "repeat", "return", "then", "until", "while",
+        ],
+        constants: &["true", "false", "nil"],
+        ..BASE
+    },
+    Grammar {
+        name: "SQL",
+        extensions: &["sql"],
+        line_comments: &["--"],
+        block_comment: Some(("/*", "*/")),
+        case_insensitive: true,
+        keywords: &[
+            "add", "alter", "and", "as", "asc", "begin", "between", "by", "case", "commit", "create", "delete",
+            "desc", "distinct", "drop", "else", "end", "exists", "from", "group", "having", "in", "index", "inner",
+            "insert", "into", "is", "join", "key", "left", "like", "limit", "not", "on", "or", "order", "outer",
+            "primary", "references", "right", "rollback", "select", "set", "table", "then", "union", "update",
+            "values", "view", "when", "where", "with",
+        ],
+        types: &[
+            "bigint", "blob", "boolean", "char", "date", "decimal", "float", "int", "integer", "numeric", "real",
+            "smallint", "text", "timestamp", "varchar",
+        ],
+        constants: &["null", "true", "false"],
+        ..BASE
+    },
+    Grammar {
+        name: "TOML",
+        extensions: &["toml"],
+        file_names: &["Cargo.lock"],
+        line_comments: &["#"],
+        block_strings: &["\"\"\"", "'''"],
+        constants: &["true", "false"],
+        ..BASE
+    },
+    Grammar {
+        name: "YAML",
+        extensions: &["yaml", "yml"],
+        line_comments: &["#"],
+        constants: &["true", "false", "null", "yes", "no", "on", "off", "~"],
+        ..BASE
+    },
+    Grammar {
+        name: "JSON",
+        extensions: &["json", "jsonc", "json5", "geojson"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        constants: &["true", "false", "null"],
+        ..BASE
+    },
+    Grammar {
+        name: "XML",
+        extensions: &["xml", "html", "htm", "xhtml", "svg", "xsl", "xsd", "plist", "csproj", "vue"],
+        block_comment: Some(("<!--", "-->")),
+        multiline_quotes: true,
+        ..BASE
+    },
+    Grammar {
+        name: "Makefile",
+        extensions: &["mk", "mak"],
+        file_names: &["Makefile", "makefile", "GNUmakefile"],
+        interpreters: &["make"],
+        line_comments: &["#"],
+        keywords: &[
+            "define", "endef", "ifdef", "ifndef", "ifeq", "ifneq", "else", "endif", "include", "export", "override",
+        ],
+        ..BASE
+    },
+    Grammar {
+        name: "Dockerfile",
+        extensions: &["dockerfile"],
+        file_names: &["Dockerfile", "Containerfile"],
+        line_comments: &["#"],
+        keywords: &[
+            "FROM", "RUN", "CMD", "LABEL", "EXPOSE", "ENV", "ADD", "COPY", "ENTRYPOINT", "VOLUME", "USER", "WORKDIR",
+            "ARG", "ONBUILD", "STOPSIGNAL", "HEALTHCHECK", "SHELL", "AS",
+        ],
+        ..BASE
+    },
+];
+
+/// Look up a grammar by name, ignoring case
+pub fn grammar_by_name(name: &str) -> Option<&'static Grammar> {
+    GRAMMARS.iter().find(|g| g.name.eq_ignore_ascii_case(name))
+}
+
+/// Pick the grammar for a file. `overrides` maps extensions or whole file
+/// names to grammar names and is checked first; a name that is not a
+/// grammar (e.g. "plain") turns highlighting off for those files. Files
+/// without a known name or extension are matched by their shebang line.
+pub fn detect(path: &Path, first_line: &str, overrides: &HashMap<String, String>) -> Option<&'static Grammar> {
+    let file_name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+
+    let overridden = overrides.iter().find(|(key, _)| {
+        let key = key.trim_start_matches('.');
+        *key == file_name || extension.as_deref().is_some_and(|ext| key.eq_ignore_ascii_case(ext))
+    });
+    if let Some((_, name)) = overridden {
+        return grammar_by_name(name);
+    }
+
+    GRAMMARS
+        .iter()
+        .find(|g| g.file_names.contains(&file_name.as_ref()))
+        .or_else(|| extension.as_deref().and_then(|ext| GRAMMARS.iter().find(|g| g.extensions.contains(&ext))))
+        .or_else(|| {
+            let interpreter = shebang_interpreter(first_line)?;
+            GRAMMARS.iter().find(|g| g.interpreters.contains(&interpreter))
+        })
+}
+
+/// Interpreter named by a "#!" line, without its directory or version:
+/// "#!/usr/bin/env python3" gives "python"
+fn shebang_interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+    Some(program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.'))
+}
+
+impl Grammar {
+    /// Split `line` into token spans, given as (start byte, token) pairs in
+    /// order, each running to the next start. `state` carries what the
+    /// previous line left open and is updated for the next one.
+    pub fn highlight_line(&self, line: &str, state: &mut LineState) -> Vec<(usize, Token)> {
+        let mut spans = Spans::default();
+        let mut i = match self.close_open(line, 0, state) {
+            Some(end) => {
+                if end > 0 {
+                    spans.push(0, self.open_token(*state));
+                }
+                *state = LineState::Clear;
+                end
+            }
+            None => {
+                spans.push(0, self.open_token(*state));
+                return spans.0;
+            }
+        };
+
+        while i < line.len() {
+            let rest = &line[i..];
+            if self.line_comments.iter().any(|c| rest.starts_with(c)) {
+                spans.push(i, Token::Comment);
+                break;
+            }
+
+            let opened = if let Some((open, _)) = self.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+                Some((LineState::BlockComment, open.len()))
+            } else if let Some(k) = self.block_strings.iter().position(|s| rest.starts_with(s)) {
+                Some((LineState::BlockString(k), self.block_strings[k].len()))
+            } else {
+                let c = rest.chars().next().unwrap_or(' ');
+                if self.quotes.contains(&c) {
+                    Some((LineState::Quote(c), 1))
+                } else if self.char_literals && c == '\'' {
+                    // 'a' and '\n' are chars; 'a on its own is a lifetime or label
+                    let is_char = rest.starts_with("'\\") || rest.chars().nth(2) == Some('\'');
+                    is_char.then_some((LineState::Quote('\''), 1))
+                } else {
+                    None
+                }
+            };
+            if let Some((open, len)) = opened {
+                spans.push(i, self.open_token(open));
+                let mut inner = open;
+                match self.close_open(line, i + len, &mut inner) {
+                    Some(end) => i = end,
+                    None => {
+                        // Quotes only carry over where the language allows it
+                        let carries = !matches!(open, LineState::Quote(_)) || self.multiline_quotes;
+                        *state = if carries { open } else { LineState::Clear };
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            let c = rest.chars().next().unwrap_or(' ');
+            if c.is_ascii_digit() {
+                let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.')).unwrap_or(rest.len());
+                spans.push(i, Token::Number);
+                i += len;
+            } else if is_word_start(c) {
+                let len = rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+                let word = &rest[..len];
+                spans.push(i, self.classify(word));
+                i += len;
+            } else {
+                spans.push(i, Token::Plain);
+                i += c.len_utf8();
+            }
+        }
+        spans.0
+    }
+
+    /// Token for the construct an open state belongs to
+    fn open_token(&self, state: LineState) -> Token {
+        match state {
+            LineState::BlockComment => Token::Comment,
+            LineState::Clear => Token::Plain,
+            LineState::BlockString(_) | LineState::Quote(_) => Token::String,
+        }
+    }
+
+    /// Find where the construct in `state` closes, starting at byte `from`.
+    /// Returns the byte after the closing delimiter, `Some(from)` when
+    /// nothing is open, or None when it stays open past the line.
+    fn close_open(&self, line: &str, from: usize, state: &mut LineState) -> Option<usize> {
+        let rest = &line[from..];
+        match *state {
+            LineState::Clear => Some(from),
+            LineState::BlockComment => {
+                let (_, close) = self.block_comment?;
+                rest.find(close).map(|p| from + p + close.len())
+            }
+            LineState::BlockString(k) => {
+                let close = self.block_strings.get(k)?;
+                rest.find(close).map(|p| from + p + close.len())
+            }
+            LineState::Quote(q) => {
+                let mut escaped = false;
+                for (p, c) in rest.char_indices() {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == q {
+                        return Some(from + p + c.len_utf8());
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    fn classify(&self, word: &str) -> Token {
+        let find = |list: &[&str]| {
+            if self.case_insensitive {
+                list.iter().any(|k| k.eq_ignore_ascii_case(word))
+            } else {
+                list.contains(&word)
+            }
+        };
+        if find(self.keywords) {
+            Token::Keyword
+        } else if find(self.constants) {
+            Token::Constant
+        } else if find(self.types) || (self.capitalized_types && word.starts_with(|c: char| c.is_ascii_uppercase())) {
+            Token::Type
+        } else {
+            Token::Plain
+        }
+    }
+}
+
+fn is_word_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '#'
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Token spans that merge neighbours of the same kind
+#[derive(Default)]
+struct Spans(Vec<(usize, Token)>);
+
+impl Spans {
+    fn push(&mut self, start: usize, token: Token) {
+        if self.0.last().is_none_or(|&(_, last)| last != token) {
+            self.0.push((start, token));
+        }
+    }
+}
+
+/// Token at byte `offset` of a line highlighted into `spans`
+pub fn token_at(spans: &[(usize, Token)], offset: usize) -> Token {
+    let index = spans.partition_point(|&(start, _)| start <= offset);
+    index.checked_sub(1).map_or(Token::Plain, |i| spans[i].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The highlighted (non-plain) pieces of a line
+    fn marked(grammar: &Grammar, line: &str, state: &mut LineState) -> Vec<(String, Token)> {
+        let spans = grammar.highlight_line(line, state);
+        spans
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, token))| *token != Token::Plain)
+            .map(|(i, &(start, token))| {
+                let end = spans.get(i + 1).map_or(line.len(), |&(next, _)| next);
+                (line[start..end].to_string(), token)
+            })
+            .collect()
+    }
+
+    fn pieces(expected: &[(&str, Token)]) -> Vec<(String, Token)> {
+        expected.iter().map(|&(text, token)| (text.to_string(), token)).collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        let none = HashMap::new();
+        assert_eq!(detect(Path::new("src/main.rs"), "", &none).map(|g| g.name), Some("Rust"));
+        assert_eq!(detect(Path::new("Makefile"), "", &none).map(|g| g.name), Some("Makefile"));
+        assert_eq!(detect(Path::new("run"), "#!/usr/bin/env python3", &none).map(|g| g.name), Some("Python"));
+        assert_eq!(detect(Path::new("build"), "#!/bin/bash -e", &none).map(|g| g.name), Some("Shell"));
+        assert!(detect(Path::new("notes.txt"), "hello", &none).is_none());
+
+        let overrides = HashMap::from([("h".to_string(), "c++".to_string()), ("rs".to_string(), "plain".to_string())]);
+        assert!(detect(Path::new("main.rs"), "", &overrides).is_none());
+        assert_eq!(detect(Path::new("x.H"), "", &overrides).map(|g| g.name), Some("C++"));
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        let rust = grammar_by_name("rust").unwrap();
+        let mut state = LineState::Clear;
+        assert_eq!(
+            marked(rust, "let s: &'a str = \"x\\\"y\"; // done", &mut state),
+            pieces(&[
+                ("let", Token::Keyword),
+                ("str", Token::Type),
+                ("\"x\\\"y\"", Token::String),
+                ("// done", Token::Comment),
+            ])
+        );
+        assert_eq!(state, LineState::Clear);
+
+        assert_eq!(
+            marked(rust, "x = '\\n'; Vec::new(0x1F, true)", &mut state),
+            pieces(&[
+                ("'\\n'", Token::String),
+                ("Vec", Token::Type),
+                ("0x1F", Token::Number),
+                ("true", Token::Constant),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_state_carries_across_lines() {
+        let c = grammar_by_name("c").unwrap();
+        let mut state = LineState::Clear;
+        assert_eq!(marked(c, "int x; /* start", &mut state).last(), Some(&("/* start".to_string(), Token::Comment)));
+        assert_eq!(state, LineState::BlockComment);
+        assert_eq!(marked(c, "still", &mut state), pieces(&[("still", Token::Comment)]));
+        assert_eq!(
+            marked(c, "end */ return 1;", &mut state),
+            pieces(&[("end */", Token::Comment), ("return", Token::Keyword), ("1", Token::Number)])
+        );
+        assert_eq!(state, LineState::Clear);
+
+        // C strings end with the line; Python block strings don't
+        marked(c, "char *s = \"open", &mut state);
+        assert_eq!(state, LineState::Clear);
+        let python = grammar_by_name("python").unwrap();
+        marked(python, "doc = \"\"\"text", &mut state);
+        assert_eq!(state, LineState::BlockString(0));
+        assert_eq!(
+            marked(python, "more\"\"\" if True", &mut state),
+            pieces(&[("more\"\"\"", Token::String), ("if", Token::Keyword), ("True", Token::Constant)])
+        );
+        assert_eq!(state, LineState::Clear);
+    }
+
+    #[test]
+    fn test_token_at() {
+        let spans = [(0, Token::Keyword), (3, Token::Plain), (4, Token::String)];
+        assert_eq!(token_at(&spans, 2), Token::Keyword);
+        assert_eq!(token_at(&spans, 3), Token::Plain);
+        assert_eq!(token_at(&spans, 99), Token::String);
+        assert_eq!(token_at(&[], 5), Token::Plain);
+    }
+}
@@ -45,6 +45,14 @@ pub struct Theme {
     pub viewer_footer_bg: Color,
     pub viewer_footer_fg: Color,
 
+    // Viewer syntax highlighting
+    pub syntax_keyword: Color,
+    pub syntax_type: Color,
+    pub syntax_string: Color,
+    pub syntax_number: Color,
+    pub syntax_comment: Color,
+    pub syntax_constant: Color,
+
     // Help viewer
     pub help_header_bg: Color,
     pub help_header_fg: Color,
@@ -138,6 +146,14 @@ impl Theme {
             viewer_footer_bg: teal,
             viewer_footer_fg: Color::Black,
 
+            // Syntax highlighting
+            syntax_keyword: Color::Rgb(198, 120, 221),  // Soft purple
+            syntax_type: Color::Rgb(229, 192, 123),     // Sand
+            syntax_string: Color::Rgb(152, 195, 121),   // Soft green
+            syntax_number: Color::Rgb(209, 154, 102),   // Orange
+            syntax_comment: Color::Rgb(127, 132, 142),
+            syntax_constant: Color::Rgb(86, 182, 194),  // Cyan
+
             // Help
             help_header_bg: teal,
             help_header_fg: Color::Black,
@@ -217,6 +233,14 @@ impl Theme {
             viewer_footer_bg: Color::Cyan,
             viewer_footer_fg: Color::Black,
 
+            // Syntax highlighting
+            syntax_keyword: Color::White,
+            syntax_type: Color::LightGreen,
+            syntax_string: Color::Yellow,
+            syntax_number: Color::LightMagenta,
+            syntax_comment: Color::Gray,
+            syntax_constant: Color::LightRed,
+
             // Help
             help_header_bg: Color::Cyan,
             help_header_fg: Color::Black,
@@ -296,6 +320,14 @@ impl Theme {
             viewer_footer_bg: Color::Blue,
             viewer_footer_fg: Color::White,
 
+            // Syntax highlighting
+            syntax_keyword: Color::Rgb(166, 38, 164),
+            syntax_type: Color::Rgb(193, 132, 1),
+            syntax_string: Color::Rgb(80, 161, 79),
+            syntax_number: Color::Rgb(152, 104, 1),
+            syntax_comment: Color::Rgb(160, 161, 167),
+            syntax_constant: Color::Rgb(1, 132, 188),
+
             // Help
             help_header_bg: Color::Blue,
             help_header_fg: Color::White,
@@ -379,6 +411,12 @@ impl Theme {
                     "viewer_line_number" => self.viewer_line_number = color,
                     "viewer_footer_bg" => self.viewer_footer_bg = color,
                     "viewer_footer_fg" => self.viewer_footer_fg = color,
+                    "syntax_keyword" => self.syntax_keyword = color,
+                    "syntax_type" => self.syntax_type = color,
+                    "syntax_string" => self.syntax_string = color,
+                    "syntax_number" => self.syntax_number = color,
+                    "syntax_comment" => self.syntax_comment = color,
+                    "syntax_constant" => self.syntax_constant = color,
                     "help_header_bg" => self.help_header_bg = color,
                     "help_header_fg" => self.help_header_fg = color,
                     "help_content_bg" => self.help_content_bg = color,
@@ -9,7 +9,10 @@ use ratatui::{
     widgets::Widget,
 };
 
-use crate::ui::viewer_utils::{format_hex_dump_range, format_cp437_range, cp437_line_count};
+use unicode_width::UnicodeWidthStr;
+
+use crate::ui::syntax::{self, Grammar, LineState, Token};
+use crate::ui::viewer_utils::{TextLayout, format_hex_dump_range, format_cp437_range, cp437_line_count, expand_line, wrapped_rows};
 use crate::state::mode::{ViewContent, BinaryViewMode};
 use super::Theme;
 
@@ -24,11 +27,84 @@ pub struct FileViewer<'a> {
     search_matches: &'a [(usize, usize)],
     /// Current match index (for different highlight)
     current_match: Option<usize>,
+    /// Wrap, gutter and tab settings for text
+    layout: TextLayout,
+    /// Wrapped row of the top line shown first
+    wrap_row: usize,
+    /// Grammar used to highlight text, if any
+    syntax: Option<&'static Grammar>,
+    /// The file is followed as it grows
+    following: bool,
+    /// Shown in the footer instead of the position
+    notice: Option<&'a str>,
+}
+
+/// Styles used to draw text lines
+struct TextStyles<'a> {
+    content: Style,
+    line_number: Style,
+    highlight: Style,
+    current: Style,
+    theme: &'a Theme,
+}
+
+impl TextStyles<'_> {
+    /// Content style coloured for a syntax token
+    fn syntax(&self, token: Token) -> Style {
+        let theme = self.theme;
+        match token {
+            Token::Plain => self.content,
+            Token::Keyword => self.content.fg(theme.syntax_keyword),
+            Token::Type => self.content.fg(theme.syntax_type),
+            Token::Constant => self.content.fg(theme.syntax_constant),
+            Token::String => self.content.fg(theme.syntax_string),
+            Token::Number => self.content.fg(theme.syntax_number),
+            Token::Comment => self.content.fg(theme.syntax_comment),
+        }
+    }
 }
 
 impl<'a> FileViewer<'a> {
     pub fn new(content: &'a ViewContent, scroll: usize, path: &'a Path, theme: &'a Theme, binary_mode: BinaryViewMode) -> Self {
-        Self { content, scroll, path, theme, binary_mode, search_matches: &[], current_match: None }
+        Self {
+            content,
+            scroll,
+            path,
+            theme,
+            binary_mode,
+            search_matches: &[],
+            current_match: None,
+            layout: TextLayout::default(),
+            wrap_row: 0,
+            syntax: None,
+            following: false,
+            notice: None,
+        }
+    }
+
+    /// Set how text lines are laid out and the wrapped row to start from
+    pub fn with_layout(mut self, layout: TextLayout, wrap_row: usize) -> Self {
+        self.layout = layout;
+        self.wrap_row = wrap_row;
+        self
+    }
+
+    /// Highlight text lines with `grammar`
+    pub fn with_syntax(mut self, grammar: Option<&'static Grammar>) -> Self {
+        self.syntax = grammar;
+        self
+    }
+
+    /// Mark the file as followed in the header
+    pub fn with_follow(mut self, following: bool) -> Self {
+        self.following = following;
+        self
+    }
+
+    /// Show `notice` in the footer
+    pub fn with_notice(mut self, notice: Option<&'a str>) -> Self {
+        self.notice = notice;
+        self
     }
 
     /// Set search matches to highlight
@@ -38,24 +114,6 @@ impl<'a> FileViewer<'a> {
         self
     }
 
-    /// Calculate byte offset for a given text line number (O(1) via precomputed offsets)
-    fn byte_offset_for_text_line(&self, bytes: &[u8], line_num: usize) -> usize {
-        let offsets: Option<&[usize]> = match self.content {
-            ViewContent::Text(_, line_offsets) => Some(line_offsets),
-            ViewContent::MappedFile { line_offsets, .. } => Some(line_offsets),
-            _ => None,
-        };
-        if let Some(offsets) = offsets {
-            if line_num < offsets.len() {
-                offsets[line_num]
-            } else {
-                bytes.len()
-            }
-        } else {
-            bytes.len()
-        }
-    }
-
     /// Get the appropriate style for a byte offset based on search matches
     fn get_highlight_style(&self, byte_offset: usize, default: Style, highlight: Style, current: Style) -> Style {
         for (i, &(start, len)) in self.search_matches.iter().enumerate() {
@@ -144,7 +202,297 @@ impl<'a> FileViewer<'a> {
                     }
                 }
             }
+            ViewContent::Decoded { source, line_offsets, .. } => {
+                match binary_mode {
+                    BinaryViewMode::Cp437 => line_offsets.len(),
+                    BinaryViewMode::Hex => Self::line_count(source, term_width, binary_mode),
+                }
+            }
+        }
+    }
+
+    /// Move a wrapped-text position (line, row within the line) by `delta`
+    /// screen rows. Only the lines passed over are measured.
+    pub fn step_rows(content: &ViewContent, layout: TextLayout, term_width: usize, position: (usize, usize), delta: isize) -> (usize, usize) {
+        let total = content.text_lines(BinaryViewMode::Cp437).map_or(0, |(_, offsets)| offsets.len());
+        if total == 0 {
+            return (0, 0);
+        }
+        let (mut line, mut row) = position;
+        line = line.min(total - 1);
+        let mut rows = content.line_rows(line, layout, term_width);
+        row = row.min(rows - 1);
+        let mut left = delta.unsigned_abs();
+
+        if delta > 0 {
+            while left > 0 {
+                let below = rows - 1 - row;
+                if left <= below {
+                    row += left;
+                    break;
+                }
+                if line + 1 >= total {
+                    row = rows - 1;
+                    break;
+                }
+                left -= below + 1;
+                line += 1;
+                row = 0;
+                rows = content.line_rows(line, layout, term_width);
+            }
+        } else {
+            while left > 0 {
+                if left <= row {
+                    row -= left;
+                    break;
+                }
+                if line == 0 {
+                    row = 0;
+                    break;
+                }
+                left -= row + 1;
+                line -= 1;
+                row = content.line_rows(line, layout, term_width) - 1;
+            }
+        }
+        (line, row)
+    }
+
+    /// Last wrapped-text position that still fills `visible_height` rows,
+    /// found by measuring lines back from the end
+    pub fn max_position(content: &ViewContent, layout: TextLayout, term_width: usize, visible_height: usize) -> (usize, usize) {
+        let total = content.text_lines(BinaryViewMode::Cp437).map_or(0, |(_, offsets)| offsets.len());
+        let mut needed = visible_height.max(1);
+        for line in (0..total).rev() {
+            let rows = content.line_rows(line, layout, term_width);
+            if rows >= needed {
+                return (line, rows - needed);
+            }
+            needed -= rows;
+        }
+        (0, 0)
+    }
+
+    /// Position that shows the end of the content: the last wrapped-text
+    /// position, or the last full page of lines
+    pub fn end_position(content: &ViewContent, binary_mode: BinaryViewMode, layout: TextLayout, term_width: usize, visible_height: usize) -> (usize, usize) {
+        if layout.wrap && content.text_lines(binary_mode).is_some() {
+            Self::max_position(content, layout, term_width, visible_height)
+        } else {
+            (Self::line_count(content, term_width, binary_mode).saturating_sub(visible_height), 0)
+        }
+    }
+
+    /// Draw text lines from (scroll, wrap_row) into `area`, expanding tabs and
+    /// wrapping or cutting each line per the layout. Returns the 1-based
+    /// number of the last line drawn.
+    fn render_text(&self, buf: &mut Buffer, area: Rect, styles: &TextStyles) -> usize {
+        let Some((_, line_offsets)) = self.content.text_lines(self.binary_mode) else {
+            return 0;
+        };
+        let total = line_offsets.len();
+        let gutter = self.layout.gutter_width(total);
+        let text_width = self.layout.text_width(area.width as usize, total);
+        let height = area.height as usize;
+
+        let mut y = 0;
+        let mut line = self.scroll;
+        let mut skip = if self.layout.wrap { self.wrap_row } else { 0 };
+
+        // Only the window is highlighted; a few lines above it are scanned
+        // to find out whether it starts inside a comment or string
+        let mut state = LineState::Clear;
+        if let Some(grammar) = self.syntax {
+            for before in self.scroll.saturating_sub(syntax::LOOKBACK_LINES)..self.scroll {
+                if let Some(text) = self.content.line_text(before) {
+                    grammar.highlight_line(&text, &mut state);
+                }
+            }
+        }
+
+        while y < height && line < total {
+            let Some(text) = self.content.line_text(line) else {
+                break;
+            };
+            let cells = expand_line(&text, self.layout.tab_width, self.layout.wrap.then_some(text_width));
+            let spans = self.syntax.map(|g| g.highlight_line(&text, &mut state)).unwrap_or_default();
+            let rows = if self.layout.wrap { wrapped_rows(cells.len(), text_width) } else { 1 };
+            for row in skip.min(rows - 1)..rows {
+                if y >= height {
+                    break;
+                }
+                let row_y = area.y + y as u16;
+                if gutter > 0 && row == 0 {
+                    let number = format!("{:>width$} ", line + 1, width = gutter - 1);
+                    buf.set_string(area.x, row_y, &number, styles.line_number);
+                }
+                for (col, &(symbol, offset)) in cells.iter().skip(row * text_width).take(text_width).enumerate() {
+                    // A line cut at the edge can end in half a wide character,
+                    // which would spill over; wrapped lines move it down instead
+                    let symbol = if col + 1 == text_width && symbol.width() > 1 { " " } else { symbol };
+                    let base = styles.syntax(syntax::token_at(&spans, offset));
+                    let style = self.get_highlight_style(line_offsets[line] + offset, base, styles.highlight, styles.current);
+                    buf[(area.x + (gutter + col) as u16, row_y)].set_symbol(symbol).set_style(style);
+                }
+                y += 1;
+            }
+            skip = 0;
+            line += 1;
+        }
+        line
+    }
+
+    /// Draw a hex or CP437 dump of the raw bytes from line `scroll`.
+    /// Returns the total number of dump lines.
+    fn render_dump(&self, buf: &mut Buffer, area: Rect, content_start_y: u16, content_height: usize, styles: &TextStyles) -> usize {
+        let bytes = self.content.raw_bytes();
+        let content_width = area.width as usize;
+        let (content_style, highlight_style, current_highlight_style) = (styles.content, styles.highlight, styles.current);
+
+        // Calculate bytes per line for hex mode (same formula as format_hex_dump)
+        let bytes_per_line = if content_width > 20 {
+            let calc_bytes = ((content_width.saturating_sub(12)) * 8) / 33;
+            (calc_bytes / 8 * 8).clamp(8, 64)
+        } else {
+            8
+        };
+
+        // Only format the visible portion
+        let (lines, total_lines) = match self.binary_mode {
+            BinaryViewMode::Hex => {
+                let total = if bytes.is_empty() {
+                    0
+                } else {
+                    bytes.len().div_ceil(bytes_per_line)
+                };
+                let start_byte = self.scroll * bytes_per_line;
+                let end_byte = ((self.scroll + content_height) * bytes_per_line).min(bytes.len());
+                let visible_lines = if start_byte < bytes.len() {
+                    format_hex_dump_range(bytes, content_width, start_byte, end_byte)
+                } else {
+                    Vec::new()
+                };
+                (visible_lines, total)
+            }
+            BinaryViewMode::Cp437 => {
+                let total = cp437_line_count(bytes.len(), content_width);
+                (format_cp437_range(bytes, content_width, self.scroll, content_height), total)
+            }
+        };
+
+        for (i, line) in lines.iter().enumerate() {
+            let y = content_start_y + i as u16;
+            let display_line: String = line.chars().take(content_width).collect();
+
+            match self.binary_mode {
+                BinaryViewMode::Hex => {
+                    // Hex view - highlight both hex and ASCII portions
+                    let line_byte_start = (self.scroll + i) * bytes_per_line;
+
+                    // Calculate layout parameters
+                    // Format: "00000000  XX XX XX XX XX XX XX XX  XX XX XX XX XX XX XX XX  |AAAAAAAAAAAAAAAA|"
+                    let offset_width = 10; // "00000000  " (8 hex + 2 spaces)
+                    let group_size = 8;
+                    let num_groups = bytes_per_line / group_size;
+                    // Extra spaces between groups (one less than num_groups)
+                    let extra_spaces = num_groups.saturating_sub(1);
+                    let hex_section_width = bytes_per_line * 3 + extra_spaces; // "XX " per byte + group separators
+                    let separator_start = offset_width + hex_section_width;
+                    let ascii_start = separator_start + 2; // " |"
+
+                    // Render the line character by character
+                    for (col, ch) in display_line.chars().enumerate() {
+                        let x = area.x + col as u16;
+
+                        let style = if col < offset_width {
+                            // Offset area - no highlight
+                            content_style
+                        } else if col < separator_start {
+                            // Hex area - need to account for group separators
+                            let hex_col = col - offset_width;
+
+                            // Calculate which byte this column belongs to
+                            // Each group of 8 bytes takes 8*3 + 1 = 25 chars (except last group: 24 chars)
+                            let chars_per_group_with_sep = group_size * 3 + 1;
+                            let chars_per_group_no_sep = group_size * 3;
+
+                            let mut byte_idx = None;
+                            let mut col_remaining = hex_col;
+
+                            for group in 0..num_groups {
+                                let group_width = if group < num_groups - 1 {
+                                    chars_per_group_with_sep
+                                } else {
+                                    chars_per_group_no_sep
+                                };
+
+                                if col_remaining < group_width {
+                                    // We're in this group
+                                    let pos_in_group = col_remaining;
+                                    if pos_in_group < group_size * 3 {
+                                        // In hex bytes area (not in trailing space)
+                                        let byte_in_group = pos_in_group / 3;
+                                        byte_idx = Some(group * group_size + byte_in_group);
+                                    }
+                                    // else: in the group separator space, no byte
+                                    break;
+                                }
+                                col_remaining -= group_width;
+                            }
+
+                            if let Some(idx) = byte_idx {
+                                let byte_offset = line_byte_start + idx;
+                                if byte_offset < bytes.len() {
+                                    self.get_highlight_style(byte_offset, content_style, highlight_style, current_highlight_style)
+                                } else {
+                                    content_style
+                                }
+                            } else {
+                                content_style
+                            }
+                        } else if col < ascii_start {
+                            // Separator " |" - no highlight
+                            content_style
+                        } else {
+                            // ASCII area (including closing "|")
+                            let ascii_col = col - ascii_start;
+                            if ascii_col < bytes_per_line {
+                                let byte_offset = line_byte_start + ascii_col;
+                                if byte_offset < bytes.len() {
+                                    self.get_highlight_style(byte_offset, content_style, highlight_style, current_highlight_style)
+                                } else {
+                                    content_style
+                                }
+                            } else {
+                                // Closing "|" or beyond
+                                content_style
+                            }
+                        };
+
+                        buf[(x, y)].set_char(ch).set_style(style);
+                    }
+                }
+                BinaryViewMode::Cp437 => {
+                    // CP437 view - each char is one byte
+                    let line_byte_start = (self.scroll + i) * content_width;
+
+                    for (col, ch) in display_line.chars().enumerate() {
+                        let x = area.x + col as u16;
+                        let byte_offset = line_byte_start + col;
+
+                        let style = if byte_offset < bytes.len() {
+                            self.get_highlight_style(byte_offset, content_style, highlight_style, current_highlight_style)
+                        } else {
+                            content_style
+                        };
+
+                        buf[(x, y)].set_char(ch).set_style(style);
+                    }
+                }
+            }
         }
+
+        total_lines
     }
 }
 
@@ -164,14 +512,29 @@ impl Widget for FileViewer<'_> {
         let is_binary = match self.content {
             ViewContent::Binary(_) => true,
             ViewContent::MappedFile { is_text, .. } => !is_text,
-            ViewContent::Text(..) => false,
+            ViewContent::Text(..) | ViewContent::Decoded { .. } => false,
         };
-        let header = match (is_binary, self.binary_mode) {
+        let mut header = match (is_binary, self.binary_mode) {
             (true, BinaryViewMode::Hex) => format!(" {} [HEX] ", path_str),
             (true, BinaryViewMode::Cp437) => format!(" {} [CP437] ", path_str),
             (false, BinaryViewMode::Hex) => format!(" {} [HEX] ", path_str),
-            (false, BinaryViewMode::Cp437) => format!(" {} [TEXT] ", path_str),
+            (false, BinaryViewMode::Cp437) => {
+                let mut tags = vec!["TEXT"];
+                if let ViewContent::Decoded { encoding, .. } = self.content {
+                    tags.push(encoding.name());
+                }
+                if self.layout.wrap {
+                    tags.push("WRAP");
+                }
+                if let Some(grammar) = self.syntax {
+                    tags.push(grammar.name);
+                }
+                format!(" {} [{}] ", path_str, tags.join(", "))
+            }
         };
+        if self.following {
+            header.push_str("[FOLLOW] ");
+        }
         for x in area.x..area.x + area.width {
             buf[(x, area.y)].set_char(' ').set_style(header_style);
         }
@@ -185,7 +548,6 @@ impl Widget for FileViewer<'_> {
         // Content area
         let content_start_y = area.y + 1;
         let content_height = area.height.saturating_sub(2) as usize;
-        let content_width = area.width as usize;
 
         // Clear content area
         for y in content_start_y..content_start_y + content_height as u16 {
@@ -194,333 +556,26 @@ impl Widget for FileViewer<'_> {
             }
         }
 
-        // Get lines to render based on content type
-        let (lines, total_lines, show_line_numbers) = match self.content {
-            ViewContent::Text(text, line_offsets) => {
-                match self.binary_mode {
-                    BinaryViewMode::Cp437 => {
-                        // Normal text view — O(1) access via precomputed offsets
-                        let total = line_offsets.len();
-                        let bytes = text.as_bytes();
-                        let mut visible = Vec::with_capacity(content_height);
-                        for i in self.scroll..total.min(self.scroll + content_height) {
-                            let start = line_offsets[i];
-                            let end = if i + 1 < total {
-                                line_offsets[i + 1]
-                            } else {
-                                bytes.len()
-                            };
-                            // Trim trailing \n or \r\n
-                            let mut line_end = end;
-                            if line_end > start && bytes[line_end - 1] == b'\n' {
-                                line_end -= 1;
-                            }
-                            if line_end > start && bytes[line_end - 1] == b'\r' {
-                                line_end -= 1;
-                            }
-                            visible.push(String::from_utf8_lossy(&bytes[start..line_end]).into_owned());
-                        }
-                        (visible, total, true)
-                    }
-                    BinaryViewMode::Hex => {
-                        // Hex view of text content
-                        let bytes = text.as_bytes();
-                        let calc_bytes = if content_width > 20 {
-                            ((content_width.saturating_sub(12)) * 8) / 33
-                        } else {
-                            8
-                        };
-                        let bytes_per_line = (calc_bytes / 8 * 8).clamp(8, 64);
-                        let total = if bytes.is_empty() {
-                            0
-                        } else {
-                            bytes.len().div_ceil(bytes_per_line)
-                        };
-                        let start_byte = self.scroll * bytes_per_line;
-                        let end_byte = ((self.scroll + content_height) * bytes_per_line).min(bytes.len());
-                        let visible_lines = if start_byte < bytes.len() {
-                            format_hex_dump_range(bytes, content_width, start_byte, end_byte)
-                        } else {
-                            Vec::new()
-                        };
-                        (visible_lines, total, false)
-                    }
-                }
-            }
-            ViewContent::Binary(bytes) => {
-                match self.binary_mode {
-                    BinaryViewMode::Hex => {
-                        // Calculate bytes per line (same formula as format_hex_dump)
-                        let calc_bytes = if content_width > 20 {
-                            ((content_width.saturating_sub(12)) * 8) / 33
-                        } else {
-                            8
-                        };
-                        let bytes_per_line = (calc_bytes / 8 * 8).clamp(8, 64);
-
-                        // Calculate total lines
-                        let total = if bytes.is_empty() {
-                            0
-                        } else {
-                            bytes.len().div_ceil(bytes_per_line)
-                        };
-
-                        // Only format visible portion
-                        let start_byte = self.scroll * bytes_per_line;
-                        let end_byte = ((self.scroll + content_height) * bytes_per_line).min(bytes.len());
-
-                        let visible_lines = if start_byte < bytes.len() {
-                            format_hex_dump_range(bytes, content_width, start_byte, end_byte)
-                        } else {
-                            Vec::new()
-                        };
-
-                        (visible_lines, total, false)
-                    }
-                    BinaryViewMode::Cp437 => {
-                        let total = cp437_line_count(bytes.len(), content_width);
-                        let visible_lines = format_cp437_range(bytes, content_width, self.scroll, content_height);
-                        (visible_lines, total, false)
-                    }
-                }
-            }
-            ViewContent::MappedFile { mmap, is_text, line_offsets } => {
-                let bytes: &[u8] = mmap;
-                match (*is_text, self.binary_mode) {
-                    (true, BinaryViewMode::Cp437) => {
-                        // Text view using precomputed line offsets
-                        let total = line_offsets.len();
-                        let visible: Vec<String> = (self.scroll..self.scroll + content_height)
-                            .filter_map(|line_idx| {
-                                if line_idx >= line_offsets.len() {
-                                    return None;
-                                }
-                                let start = line_offsets[line_idx];
-                                let end = if line_idx + 1 < line_offsets.len() {
-                                    // End is start of next line minus the newline char
-                                    let next_start = line_offsets[line_idx + 1];
-                                    if next_start > 0 && bytes.get(next_start - 1) == Some(&b'\n') {
-                                        next_start - 1
-                                    } else {
-                                        next_start
-                                    }
-                                } else {
-                                    bytes.len()
-                                };
-                                // Convert bytes to string, handling any invalid UTF-8 gracefully
-                                Some(String::from_utf8_lossy(&bytes[start..end]).into_owned())
-                            })
-                            .collect();
-                        (visible, total, true)
-                    }
-                    (_, BinaryViewMode::Hex) => {
-                        // Hex view
-                        let calc_bytes = if content_width > 20 {
-                            ((content_width.saturating_sub(12)) * 8) / 33
-                        } else {
-                            8
-                        };
-                        let bytes_per_line = (calc_bytes / 8 * 8).clamp(8, 64);
-                        let total = if bytes.is_empty() {
-                            0
-                        } else {
-                            bytes.len().div_ceil(bytes_per_line)
-                        };
-                        let start_byte = self.scroll * bytes_per_line;
-                        let end_byte = ((self.scroll + content_height) * bytes_per_line).min(bytes.len());
-                        let visible_lines = if start_byte < bytes.len() {
-                            format_hex_dump_range(bytes, content_width, start_byte, end_byte)
-                        } else {
-                            Vec::new()
-                        };
-                        (visible_lines, total, false)
-                    }
-                    (false, BinaryViewMode::Cp437) => {
-                        // Binary CP437 view
-                        let total = cp437_line_count(bytes.len(), content_width);
-                        let visible_lines = format_cp437_range(bytes, content_width, self.scroll, content_height);
-                        (visible_lines, total, false)
-                    }
-                }
-            }
-        };
-
-        // Line number width (based on total lines) - only for text
-        let line_num_width = if show_line_numbers && total_lines > 0 {
-            ((total_lines as f64).log10().floor() as usize + 1).max(4)
-        } else {
-            0
-        };
-
         // Highlight style for search matches
         let highlight_style = Style::default().bg(Color::Rgb(180, 160, 60)).fg(Color::Black); // Darker for inactive
         let current_highlight_style = Style::default().bg(Color::Yellow).fg(Color::Black);
 
-        // Get the raw bytes for highlighting calculation
-        let bytes: &[u8] = match self.content {
-            ViewContent::Text(text, _) => text.as_bytes(),
-            ViewContent::Binary(data) => data,
-            ViewContent::MappedFile { mmap, .. } => mmap,
+        // Text is laid out line by line; anything else is a hex or CP437 dump
+        let styles = TextStyles {
+            content: content_style,
+            line_number: line_num_style,
+            highlight: highlight_style,
+            current: current_highlight_style,
+            theme: self.theme,
         };
-
-        // Calculate bytes per line for hex mode
-        let bytes_per_line = if content_width > 20 {
-            let calc_bytes = ((content_width.saturating_sub(12)) * 8) / 33;
-            (calc_bytes / 8 * 8).clamp(8, 64)
+        let (visible_end, total_lines) = if let Some((_, line_offsets)) = self.content.text_lines(self.binary_mode) {
+            let content_area = Rect::new(area.x, content_start_y, area.width, content_height as u16);
+            (self.render_text(buf, content_area, &styles), line_offsets.len())
         } else {
-            8
+            let total_lines = self.render_dump(buf, area, content_start_y, content_height, &styles);
+            ((self.scroll + content_height).min(total_lines), total_lines)
         };
 
-        // Render visible lines (already filtered to visible portion)
-        for (i, line) in lines.iter().enumerate() {
-            let y = content_start_y + i as u16;
-
-            if show_line_numbers {
-                // Text mode with line numbers
-                let line_num = self.scroll + i + 1;
-                // Line number
-                let num_str = format!("{:>width$} ", line_num, width = line_num_width);
-                buf.set_string(area.x, y, &num_str, line_num_style);
-
-                // Line content with highlighting
-                let text_start = area.x + line_num_width as u16 + 1;
-                let text_width = content_width.saturating_sub(line_num_width + 1);
-
-                // Calculate byte offset for this line
-                let line_byte_start = self.byte_offset_for_text_line(bytes, self.scroll + i);
-                let line_byte_end = self.byte_offset_for_text_line(bytes, self.scroll + i + 1);
-
-                // Render each character, applying highlight if needed
-                // Track actual byte offset (not character index) for proper UTF-8 handling
-                let mut byte_offset = line_byte_start;
-                for (col, ch) in line.chars().take(text_width).enumerate() {
-                    let x = text_start + col as u16;
-
-                    let style = if byte_offset < line_byte_end {
-                        self.get_highlight_style(byte_offset, content_style, highlight_style, current_highlight_style)
-                    } else {
-                        content_style
-                    };
-
-                    // Replace control characters (like TAB) with spaces for display
-                    // TAB and other control chars aren't rendered properly by terminals
-                    let display_char = if ch.is_control() { ' ' } else { ch };
-                    buf[(x, y)].set_char(display_char).set_style(style);
-                    byte_offset += ch.len_utf8(); // Advance by actual byte length
-                }
-            } else {
-                // Hex dump or CP437 view - no line numbers
-                let display_line: String = line.chars().take(content_width).collect();
-
-                match self.binary_mode {
-                    BinaryViewMode::Hex => {
-                        // Hex view - highlight both hex and ASCII portions
-                        let line_byte_start = (self.scroll + i) * bytes_per_line;
-
-                        // Calculate layout parameters
-                        // Format: "00000000  XX XX XX XX XX XX XX XX  XX XX XX XX XX XX XX XX  |AAAAAAAAAAAAAAAA|"
-                        let offset_width = 10; // "00000000  " (8 hex + 2 spaces)
-                        let group_size = 8;
-                        let num_groups = bytes_per_line / group_size;
-                        // Extra spaces between groups (one less than num_groups)
-                        let extra_spaces = num_groups.saturating_sub(1);
-                        let hex_section_width = bytes_per_line * 3 + extra_spaces; // "XX " per byte + group separators
-                        let separator_start = offset_width + hex_section_width;
-                        let ascii_start = separator_start + 2; // " |"
-
-                        // Render the line character by character
-                        for (col, ch) in display_line.chars().enumerate() {
-                            let x = area.x + col as u16;
-
-                            let style = if col < offset_width {
-                                // Offset area - no highlight
-                                content_style
-                            } else if col < separator_start {
-                                // Hex area - need to account for group separators
-                                let hex_col = col - offset_width;
-
-                                // Calculate which byte this column belongs to
-                                // Each group of 8 bytes takes 8*3 + 1 = 25 chars (except last group: 24 chars)
-                                let chars_per_group_with_sep = group_size * 3 + 1;
-                                let chars_per_group_no_sep = group_size * 3;
-
-                                let mut byte_idx = None;
-                                let mut col_remaining = hex_col;
-
-                                for group in 0..num_groups {
-                                    let group_width = if group < num_groups - 1 {
-                                        chars_per_group_with_sep
-                                    } else {
-                                        chars_per_group_no_sep
-                                    };
-
-                                    if col_remaining < group_width {
-                                        // We're in this group
-                                        let pos_in_group = col_remaining;
-                                        if pos_in_group < group_size * 3 {
-                                            // In hex bytes area (not in trailing space)
-                                            let byte_in_group = pos_in_group / 3;
-                                            byte_idx = Some(group * group_size + byte_in_group);
-                                        }
-                                        // else: in the group separator space, no byte
-                                        break;
-                                    }
-                                    col_remaining -= group_width;
-                                }
-
-                                if let Some(idx) = byte_idx {
-                                    let byte_offset = line_byte_start + idx;
-                                    if byte_offset < bytes.len() {
-                                        self.get_highlight_style(byte_offset, content_style, highlight_style, current_highlight_style)
-                                    } else {
-                                        content_style
-                                    }
-                                } else {
-                                    content_style
-                                }
-                            } else if col < ascii_start {
-                                // Separator " |" - no highlight
-                                content_style
-                            } else {
-                                // ASCII area (including closing "|")
-                                let ascii_col = col - ascii_start;
-                                if ascii_col < bytes_per_line {
-                                    let byte_offset = line_byte_start + ascii_col;
-                                    if byte_offset < bytes.len() {
-                                        self.get_highlight_style(byte_offset, content_style, highlight_style, current_highlight_style)
-                                    } else {
-                                        content_style
-                                    }
-                                } else {
-                                    // Closing "|" or beyond
-                                    content_style
-                                }
-                            };
-
-                            buf[(x, y)].set_char(ch).set_style(style);
-                        }
-                    }
-                    BinaryViewMode::Cp437 => {
-                        // CP437 view - each char is one byte
-                        let line_byte_start = (self.scroll + i) * content_width;
-
-                        for (col, ch) in display_line.chars().enumerate() {
-                            let x = area.x + col as u16;
-                            let byte_offset = line_byte_start + col;
-
-                            let style = if byte_offset < bytes.len() {
-                                self.get_highlight_style(byte_offset, content_style, highlight_style, current_highlight_style)
-                            } else {
-                                content_style
-                            };
-
-                            buf[(x, y)].set_char(ch).set_style(style);
-                        }
-                    }
-                }
-            }
-        }
-
         // Footer row
         let footer_y = area.y + area.height - 1;
         for x in area.x..area.x + area.width {
@@ -528,23 +583,19 @@ impl Widget for FileViewer<'_> {
         }
 
         // Footer content: line info and help
-        let visible_end = (self.scroll + content_height).min(total_lines);
         let percent = if total_lines > 0 {
             ((visible_end as f64 / total_lines as f64) * 100.0) as usize
         } else {
             100
         };
-        let position_info = format!(
-            " Lines {}-{} of {} ({}%) ",
-            self.scroll + 1,
-            visible_end,
-            total_lines,
-            percent
-        );
+        let position_info = match self.notice {
+            Some(notice) => notice.to_string(),
+            None => format!(" Lines {}-{} of {} ({}%) ", self.scroll + 1, visible_end, total_lines, percent),
+        };
         let help_text = if is_binary {
-            " TAB:Toggle HEX/CP437  ESC/q:Exit  Up/Down:Scroll "
+            " TAB:Toggle HEX/CP437  e:Encoding  F:Follow  ESC/q:Exit "
         } else {
-            " TAB:Toggle TEXT/HEX  ESC/q:Exit  Up/Down:Scroll "
+            " TAB:Toggle TEXT/HEX  e:Encoding  w:Wrap  #:Numbers  F:Follow  ESC/q:Exit "
         };
 
         buf.set_string(area.x, footer_y, &position_info, footer_style);
@@ -556,3 +607,40 @@ impl Widget for FileViewer<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer_utils::compute_line_offsets;
+
+    fn text(s: &str) -> ViewContent {
+        ViewContent::Text(s.to_string(), compute_line_offsets(s.as_bytes()))
+    }
+
+    #[test]
+    fn test_wrapped_scrolling() {
+        // 10 columns of text after a 5-column gutter: the middle line takes 3 rows
+        let content = text("short\n0123456789abcdefghijKLMNO\nend\n");
+        let layout = TextLayout { wrap: true, line_numbers: true, tab_width: 8 };
+        let width = 15;
+        assert_eq!(content.line_rows(1, layout, width), 3);
+
+        let step = |pos, delta| FileViewer::step_rows(&content, layout, width, pos, delta);
+        assert_eq!(step((0, 0), 1), (1, 0));
+        assert_eq!(step((0, 0), 3), (1, 2));
+        assert_eq!(step((1, 2), 10), (2, 0));
+        assert_eq!(step((2, 0), -2), (1, 1));
+        assert_eq!(step((1, 1), -5), (0, 0));
+
+        // Five rows in all: a 2-row screen ends on the last row of line 1 and line 2
+        assert_eq!(FileViewer::max_position(&content, layout, width, 2), (1, 2));
+        assert_eq!(FileViewer::max_position(&content, layout, width, 10), (0, 0));
+
+        // A match on "K" sits on the third row of line 1
+        let offset = "short\n0123456789abcdefghij".len();
+        assert_eq!(content.byte_offset_to_position(offset, BinaryViewMode::Cp437, width, layout), (1, 2));
+        let unwrapped = TextLayout { wrap: false, ..layout };
+        assert_eq!(content.byte_offset_to_position(offset, BinaryViewMode::Cp437, width, unwrapped), (1, 0));
+        assert_eq!(content.line_rows(1, unwrapped, width), 1);
+    }
+}
@@ -0,0 +1,144 @@
+//! List of viewer search matches
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+use super::Theme;
+use crate::fs::search::MAX_MATCHES;
+use crate::state::mode::{BinaryViewMode, ViewContent};
+
+/// Bytes shown before a match that isn't on a text line
+const RAW_CONTEXT: usize = 16;
+
+/// Pane listing every search match with its line number (or byte offset
+/// in hex view) and the text around it
+pub struct ViewerMatchList<'a> {
+    content: &'a ViewContent,
+    binary_mode: BinaryViewMode,
+    matches: &'a [(usize, usize)],
+    cursor: usize,
+    scroll: usize,
+    theme: &'a Theme,
+}
+
+impl<'a> ViewerMatchList<'a> {
+    pub fn new(
+        content: &'a ViewContent,
+        binary_mode: BinaryViewMode,
+        matches: &'a [(usize, usize)],
+        cursor: usize,
+        scroll: usize,
+        theme: &'a Theme,
+    ) -> Self {
+        Self { content, binary_mode, matches, cursor, scroll, theme }
+    }
+
+    /// Calculate the visible height (list rows, excluding title and footer)
+    pub fn content_height(area: Rect) -> usize {
+        area.height.saturating_sub(2) as usize // -1 title, -1 footer
+    }
+
+    /// Label and context cells of one match; each cell says whether it is
+    /// part of the match
+    fn row(&self, offset: usize, len: usize) -> (String, Vec<(char, bool)>) {
+        let in_match = |b: usize| b >= offset && b < offset + len;
+        if let Some((bytes, line_offsets)) = self.content.text_lines(self.binary_mode) {
+            let line = self.content.byte_offset_to_line(offset, self.binary_mode, 0);
+            let digits = line_offsets.len().to_string().len();
+            let start = line_offsets.get(line).copied().unwrap_or(0);
+            let end = line_offsets.get(line + 1).copied().unwrap_or(bytes.len());
+            // Walk the raw bytes so cells keep their offsets even past
+            // invalid UTF-8
+            let mut cells = Vec::new();
+            let mut pos = start;
+            for chunk in bytes[start..end].utf8_chunks() {
+                for (i, c) in chunk.valid().char_indices() {
+                    cells.push((if c.is_control() { ' ' } else { c }, in_match(pos + i)));
+                }
+                pos += chunk.valid().len();
+                if !chunk.invalid().is_empty() {
+                    cells.push(('\u{FFFD}', in_match(pos)));
+                    pos += chunk.invalid().len();
+                }
+            }
+            return (format!("{:>digits$}:", line + 1), cells);
+        }
+
+        let bytes = self.content.raw_bytes();
+        let from = offset.saturating_sub(RAW_CONTEXT);
+        let to = (offset + len + 2 * RAW_CONTEXT).min(bytes.len());
+        let cells = bytes[from.min(to)..to]
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| (if (0x20..0x7F).contains(&b) { b as char } else { '.' }, in_match(from + i)))
+            .collect();
+        (format!("{:08X}:", offset), cells)
+    }
+}
+
+impl Widget for ViewerMatchList<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 || area.width < 20 {
+            return;
+        }
+
+        let header_style = Style::default().bg(self.theme.viewer_header_bg).fg(self.theme.viewer_header_fg);
+        let content_style = Style::default().bg(self.theme.viewer_content_bg).fg(self.theme.viewer_content_fg);
+        let number_style = content_style.fg(self.theme.viewer_line_number);
+        let cursor_style = Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor_fg);
+        let match_style = Style::default().bg(Color::Rgb(180, 160, 60)).fg(Color::Black);
+        let footer_style = Style::default().bg(self.theme.viewer_footer_bg).fg(self.theme.viewer_footer_fg);
+
+        let fill = |buf: &mut Buffer, y: u16, style: Style| {
+            for x in area.x..area.x + area.width {
+                buf[(x, y)].set_char(' ').set_style(style);
+            }
+        };
+
+        let more = if self.matches.len() >= MAX_MATCHES { "+" } else { "" };
+        let title = format!(" {}{} matches ", self.matches.len(), more);
+        fill(buf, area.y, header_style);
+        buf.set_string(area.x, area.y, &title, header_style);
+
+        // The list may have been scrolled for a different pane height
+        let height = Self::content_height(area);
+        let scroll = self.scroll.min(self.cursor).max((self.cursor + 1).saturating_sub(height));
+        let width = area.width as usize;
+        for row in 0..height {
+            let y = area.y + 1 + row as u16;
+            let index = scroll + row;
+            let selected = index == self.cursor;
+            let style = if selected { cursor_style } else { content_style };
+            fill(buf, y, style);
+            let Some(&(offset, len)) = self.matches.get(index) else {
+                continue;
+            };
+
+            let (label, cells) = self.row(offset, len);
+            buf.set_string(area.x + 1, y, &label, if selected { style } else { number_style });
+
+            // Keep the match in view on long lines
+            let text_x = label.len() + 2;
+            let text_width = width.saturating_sub(text_x);
+            let first = cells.iter().position(|&(_, hit)| hit).unwrap_or(0);
+            let skip = first.saturating_sub(text_width / 3);
+            let mut x = area.x + text_x as u16;
+            if skip > 0 && text_width > 0 {
+                buf[(x, y)].set_char('…').set_style(style);
+                x += 1;
+            }
+            for &(c, hit) in cells.iter().skip(skip).take(text_width.saturating_sub((skip > 0) as usize)) {
+                buf[(x, y)].set_char(c).set_style(if hit { match_style } else { style });
+                x += 1;
+            }
+        }
+
+        let footer_y = area.y + area.height - 1;
+        fill(buf, footer_y, footer_style);
+        buf.set_string(area.x, footer_y, " ↑↓:Select  Enter:Go to match  Esc:Back ", footer_style);
+    }
+}
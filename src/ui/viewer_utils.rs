@@ -1,3 +1,5 @@
+use unicode_width::UnicodeWidthChar;
+
 use super::cp437::CP437_TABLE;
 
 /// Compute line byte offsets for a text file
@@ -14,6 +16,17 @@ pub fn compute_line_offsets(bytes: &[u8]) -> Vec<usize> {
     offsets
 }
 
+/// Bring `offsets`, computed by [`compute_line_offsets`] when `bytes` was
+/// only `old_len` long, up to date by scanning just the appended bytes
+pub fn extend_line_offsets(offsets: &mut Vec<usize>, bytes: &[u8], old_len: usize) {
+    // A newline that ended the old bytes starts a line once data follows it
+    for (i, &b) in bytes.iter().enumerate().skip(old_len.saturating_sub(1)) {
+        if b == b'\n' && i + 1 < bytes.len() {
+            offsets.push(i + 1);
+        }
+    }
+}
+
 /// Format a range of bytes as hex dump lines
 pub fn format_hex_dump_range(bytes: &[u8], term_width: usize, start_byte: usize, end_byte: usize) -> Vec<String> {
     // Calculate bytes per line based on terminal width
@@ -98,3 +111,171 @@ pub fn cp437_line_count(bytes_len: usize, term_width: usize) -> usize {
         bytes_len.div_ceil(term_width)
     }
 }
+
+/// How the built-in viewer lays out text lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextLayout {
+    /// Soft-wrap long lines instead of cutting them at the right edge
+    pub wrap: bool,
+    /// Show the line-number gutter
+    pub line_numbers: bool,
+    /// Columns between tab stops
+    pub tab_width: usize,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self { wrap: false, line_numbers: true, tab_width: 8 }
+    }
+}
+
+impl TextLayout {
+    /// Width of the line-number gutter, including its trailing space
+    pub fn gutter_width(&self, total_lines: usize) -> usize {
+        if self.line_numbers && total_lines > 0 {
+            total_lines.to_string().len().max(4) + 1
+        } else {
+            0
+        }
+    }
+
+    /// Columns left for text in a viewer `term_width` wide
+    pub fn text_width(&self, term_width: usize, total_lines: usize) -> usize {
+        term_width.saturating_sub(self.gutter_width(total_lines)).max(1)
+    }
+}
+
+/// Expand a text line into display cells, one per terminal column, each
+/// holding the symbol drawn there and the byte offset of its source
+/// character within the line. Tabs advance to the next tab stop and other
+/// control characters show as a space. Wide characters (CJK, most emoji)
+/// take a second cell holding a space; when wrapping at `wrap_width`, one
+/// that would start in the last column moves to the next row whole.
+/// Zero-width characters, like combining accents, join the symbol before them.
+pub fn expand_line(line: &str, tab_width: usize, wrap_width: Option<usize>) -> Vec<(&str, usize)> {
+    let tab_width = tab_width.max(1);
+    let mut cells = Vec::with_capacity(line.len());
+    // Cell of the last character drawn, which zero-width ones are added to
+    let mut base: Option<usize> = None;
+    for (offset, ch) in line.char_indices() {
+        let end = offset + ch.len_utf8();
+        if ch == '\t' {
+            let stop = (cells.len() / tab_width + 1) * tab_width;
+            cells.resize(stop, (" ", offset));
+            base = None;
+        } else if ch.is_control() {
+            cells.push((" ", offset));
+            base = None;
+        } else {
+            match ch.width().unwrap_or(1) {
+                0 => {
+                    if let Some(cell) = base.and_then(|i| cells.get_mut(i)) {
+                        cell.0 = &line[cell.1..end];
+                    }
+                }
+                1 => {
+                    base = Some(cells.len());
+                    cells.push((&line[offset..end], offset));
+                }
+                _ => {
+                    if ends_row(cells.len(), wrap_width) {
+                        cells.push((" ", offset));
+                    }
+                    base = Some(cells.len());
+                    cells.extend([(&line[offset..end], offset), (" ", offset)]);
+                }
+            }
+        }
+    }
+    cells
+}
+
+/// Whether column `col` is the last of a row when wrapping at `wrap_width`,
+/// leaving no room for a wide character
+fn ends_row(col: usize, wrap_width: Option<usize>) -> bool {
+    wrap_width.is_some_and(|width| width > 1 && col % width == width - 1)
+}
+
+/// Number of display columns `line` takes, the same count `expand_line`
+/// produces without building the cells
+pub fn display_width(line: &str, tab_width: usize, wrap_width: Option<usize>) -> usize {
+    let tab_width = tab_width.max(1);
+    line.chars().fold(0, |col, ch| match ch {
+        '\t' => (col / tab_width + 1) * tab_width,
+        _ if ch.is_control() => col + 1,
+        _ => match ch.width().unwrap_or(1) {
+            0 => col,
+            1 => col + 1,
+            _ => col + 2 + usize::from(ends_row(col, wrap_width)),
+        },
+    })
+}
+
+/// Screen rows a line of `columns` takes when wrapped at `width`; an empty
+/// line still takes one
+pub fn wrapped_rows(columns: usize, width: usize) -> usize {
+    columns.div_ceil(width.max(1)).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_line_tabs() {
+        let cells = expand_line("a\tbc\td", 4, None);
+        let text: String = cells.iter().map(|(c, _)| *c).collect();
+        assert_eq!(text, "a   bc  d");
+        // Padding cells point back at their tab
+        assert_eq!(cells[1].1, 1);
+        assert_eq!(cells[3].1, 1);
+        assert_eq!(cells[8].1, 5);
+        assert_eq!(display_width("a\tbc\td", 4, None), cells.len());
+        assert_eq!(display_width("\u{1}é\t", 8, None), expand_line("\u{1}é\t", 8, None).len());
+    }
+
+    #[test]
+    fn test_expand_line_wide() {
+        // CJK takes two columns, a combining accent none but stays on its letter
+        let cells = expand_line("a中e\u{301}\tb", 4, None);
+        let text: String = cells.iter().map(|(c, _)| *c).collect();
+        assert_eq!(text, "a中 e\u{301}    b");
+        assert_eq!(cells[2], (" ", 1));
+        assert_eq!(cells[3], ("e\u{301}", 4));
+        // The tab pads from after the accent
+        assert_eq!(cells[4].1, 7);
+        assert_eq!(display_width("a中e\u{301}\tb", 4, None), cells.len());
+        assert_eq!(display_width("日本語", 8, None), 6);
+
+        // Wrapped at 3 columns, the second character moves to the next row whole
+        let cells = expand_line("日本語", 8, Some(3));
+        let text: String = cells.iter().map(|(c, _)| *c).collect();
+        assert_eq!(text, "日  本  語 ");
+        assert_eq!(cells[2], (" ", 3));
+        assert_eq!(display_width("日本語", 8, Some(3)), cells.len());
+    }
+
+    #[test]
+    fn test_extend_line_offsets() {
+        let text = b"one\ntwo\n\nthree\nfour";
+        for old_len in 0..=text.len() {
+            let mut offsets = compute_line_offsets(&text[..old_len]);
+            extend_line_offsets(&mut offsets, text, old_len);
+            assert_eq!(offsets, compute_line_offsets(text), "grown from {} bytes", old_len);
+        }
+    }
+
+    #[test]
+    fn test_wrapped_rows_and_gutter() {
+        assert_eq!(wrapped_rows(0, 10), 1);
+        assert_eq!(wrapped_rows(10, 10), 1);
+        assert_eq!(wrapped_rows(11, 10), 2);
+
+        let layout = TextLayout::default();
+        assert_eq!(layout.gutter_width(12), 5);
+        assert_eq!(layout.gutter_width(123_456), 7);
+        assert_eq!(layout.text_width(80, 12), 75);
+        let bare = TextLayout { line_numbers: false, ..layout };
+        assert_eq!(bare.text_width(80, 12), 80);
+    }
+}